@@ -0,0 +1,297 @@
+// Action Log - Audit trail for applied and dismissed AI recommendations
+// Every setting a recommendation changes is recorded with its previous value so it can be undone
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::database::Database;
+use crate::error::SysError;
+use crate::privilege::PrivilegedBatch;
+use crate::sensors;
+use crate::watchdog;
+use crate::{AIRecommendation, HardwareController};
+
+/// Fan duty cycle used by "Increase fan speeds"
+const BOOSTED_FAN_PERCENT: u8 = 80;
+
+/// How far back undo_applied_since looks; far more actions than one run produces
+const SESSION_HISTORY_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Applied,
+    Dismissed,
+    Undone,
+}
+
+impl ActionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionKind::Applied => "applied",
+            ActionKind::Dismissed => "dismissed",
+            ActionKind::Undone => "undone",
+        }
+    }
+    
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "applied" => Some(ActionKind::Applied),
+            "dismissed" => Some(ActionKind::Dismissed),
+            "undone" => Some(ActionKind::Undone),
+            _ => None,
+        }
+    }
+}
+
+/// One sysfs (or similar) setting and the value it held
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingValue {
+    pub path: String,
+    pub value: String,
+}
+
+/// What a recommendation changed. `before` is in the order the settings were
+/// written, and undo restores it back to front.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedAction {
+    pub id: String,
+    pub title: String,
+    pub kind: ActionKind,
+    pub before: Vec<SettingValue>,
+    pub after: Vec<SettingValue>,
+    pub timestamp: u64,
+}
+
+/// A write an action would make, with what is there now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedSetting {
+    pub path: String,
+    /// None when the file can't be read, in which case applying it would fail too
+    pub current: Option<String>,
+    pub value: String,
+}
+
+impl PlannedSetting {
+    /// The write as a shell command, for showing to the user
+    pub fn command(&self) -> String {
+        format!("echo {} > {}", self.value, self.path)
+    }
+}
+
+/// What `apply` would do for one of a recommendation's actions; no settings
+/// means the action is only advice and is noted without running anything
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedAction {
+    pub action: String,
+    pub settings: Vec<PlannedSetting>,
+}
+
+pub struct ActionLog {
+    database: Database,
+}
+
+impl ActionLog {
+    pub fn new(database: Database) -> Self {
+        Self { database }
+    }
+    
+    /// Carry out the recommendation's actions and record what they changed.
+    /// Writes go through one privileged batch, so a user session gets at most one
+    /// polkit prompt. Fails without recording anything when the recommendation had
+    /// settings to change and none of them could be written; advice-only actions
+    /// have nothing to write and are just noted. Refused while the safety watchdog
+    /// is throttling, so a recommendation can't undo its limits.
+    pub fn apply(&self, rec: &AIRecommendation) -> Result<AppliedAction> {
+        let mut writes = Vec::new();
+        for action in &rec.actions {
+            let groups = action_writes(action);
+            if groups.is_empty() {
+                debug!("📝 Action noted: {}", action);
+            }
+            writes.extend(groups.into_iter().flatten());
+        }
+        if !writes.is_empty() && watchdog::is_throttling() {
+            return Err(anyhow!("Not applying '{}' while the safety watchdog is throttling an overheating system", rec.title));
+        }
+        
+        let (before, after, errors) = change_settings(&writes);
+        for e in &errors {
+            warn!("Failed to apply '{}': {}", rec.title, e);
+        }
+        if !writes.is_empty() && after.is_empty() {
+            return Err(anyhow!("No settings changed for '{}': {}", rec.title, describe_errors(&errors)));
+        }
+        
+        let entry = AppliedAction {
+            id: rec.id.clone(),
+            title: rec.title.clone(),
+            kind: ActionKind::Applied,
+            before,
+            after,
+            timestamp: now_secs(),
+        };
+        self.database.record_action(&entry)?;
+        
+        info!("📋 Applied '{}' ({} settings changed)", rec.title, entry.after.len());
+        Ok(entry)
+    }
+    
+    /// What `apply` would write for each of the recommendation's actions, without writing it
+    pub fn plan(rec: &AIRecommendation) -> Vec<PlannedAction> {
+        rec.actions.iter()
+            .map(|action| PlannedAction {
+                action: action.clone(),
+                settings: action_writes(action).into_iter().flatten()
+                    .map(|(path, value)| PlannedSetting {
+                        current: fs::read_to_string(&path).ok().map(|current| current.trim().to_string()),
+                        path: path.display().to_string(),
+                        value,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+    
+    pub fn dismiss(&self, rec: &AIRecommendation) -> Result<AppliedAction> {
+        let entry = AppliedAction {
+            id: rec.id.clone(),
+            title: rec.title.clone(),
+            kind: ActionKind::Dismissed,
+            before: Vec::new(),
+            after: Vec::new(),
+            timestamp: now_secs(),
+        };
+        self.database.record_action(&entry)?;
+        Ok(entry)
+    }
+    
+    /// Restore the settings from the most recent apply of `id`
+    pub fn undo(&self, id: &str) -> Result<AppliedAction> {
+        let applied = match self.database.latest_action(id)? {
+            Some(entry) if entry.kind == ActionKind::Applied => entry,
+            Some(entry) => return Err(anyhow!("Recommendation {} was {}, nothing to undo", id, entry.kind.as_str())),
+            None => return Err(anyhow!("No applied action recorded for {}", id)),
+        };
+        
+        let writes: Vec<(PathBuf, String)> = applied.before.iter().rev()
+            .map(|setting| (PathBuf::from(&setting.path), setting.value.clone()))
+            .collect();
+        let (replaced, restored, errors) = change_settings(&writes);
+        for e in &errors {
+            warn!("Failed to restore setting for {}: {}", id, e);
+        }
+        if !writes.is_empty() && restored.is_empty() {
+            return Err(anyhow!("No settings restored for {}: {}", id, describe_errors(&errors)));
+        }
+        if restored.len() < applied.before.len() {
+            warn!("⚠️ Restored {} of {} settings for {}", restored.len(), applied.before.len(), id);
+        }
+        
+        let entry = AppliedAction {
+            id: applied.id,
+            title: applied.title,
+            kind: ActionKind::Undone,
+            before: replaced,
+            after: restored,
+            timestamp: now_secs(),
+        };
+        self.database.record_action(&entry)?;
+        
+        info!("↩️ Undid '{}' ({} settings restored)", entry.title, entry.after.len());
+        Ok(entry)
+    }
+    
+    /// Undo every recommendation applied at or after `since` that is still in
+    /// effect, newest first
+    pub fn undo_applied_since(&self, since: u64) -> Result<Vec<AppliedAction>> {
+        let mut seen = HashSet::new();
+        let mut undone = Vec::new();
+        for entry in self.database.action_history(SESSION_HISTORY_LIMIT)? {
+            if entry.timestamp < since {
+                break;
+            }
+            // History is newest first, so the first entry per id is its current state
+            if !seen.insert(entry.id.clone()) || entry.kind != ActionKind::Applied {
+                continue;
+            }
+            match self.undo(&entry.id) {
+                Ok(done) => undone.push(done),
+                Err(e) => warn!("Failed to undo {}: {}", entry.id, e),
+            }
+        }
+        Ok(undone)
+    }
+    
+    pub fn history(&self, limit: usize) -> Result<Vec<AppliedAction>> {
+        self.database.action_history(limit)
+    }
+}
+
+/// The settings an action writes, in groups where each write needs the one before it.
+/// The batch writes them in order, so a fan is in manual mode before its duty cycle is set.
+fn action_writes(action: &str) -> Vec<Vec<(PathBuf, String)>> {
+    let lower = action.to_lowercase();
+    if lower.contains("performance cpu governor") {
+        HardwareController::cpu_governor_paths().into_iter()
+            .map(|(_, path)| vec![(path, "performance".to_string())])
+            .collect()
+    } else if lower.contains("increase fan speed") {
+        let pwm_value = (BOOSTED_FAN_PERCENT as f64 / 100.0 * 255.0).round() as u8;
+        HardwareController::find_pwm_channels(Path::new(sensors::HWMON_DIR)).into_iter()
+            // Manual mode first, otherwise the firmware ignores the duty cycle
+            .map(|(pwm_path, enable_path)| vec![(enable_path, "1".to_string()), (pwm_path, pwm_value.to_string())])
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Write each value through one privileged batch, returning the old and new values
+/// of the writes that succeeded, and the errors of the ones that didn't. A setting
+/// that can't be read is skipped, since it couldn't be restored afterwards.
+fn change_settings(writes: &[(PathBuf, String)]) -> (Vec<SettingValue>, Vec<SettingValue>, Vec<SysError>) {
+    let mut errors = Vec::new();
+    let mut pending = Vec::new();
+    let mut batch = PrivilegedBatch::new();
+    for (path, value) in writes {
+        match fs::read_to_string(path) {
+            Ok(previous) => {
+                batch.write(path.clone(), value.as_str());
+                pending.push((path, previous.trim().to_string(), value));
+            }
+            Err(e) => errors.push(SysError::io(path, e)),
+        }
+    }
+    
+    let mut before = Vec::new();
+    let mut after = Vec::new();
+    if batch.is_empty() {
+        return (before, after, errors);
+    }
+    for ((path, previous, value), result) in pending.into_iter().zip(batch.apply()) {
+        match result {
+            Ok(()) => {
+                let path = path.display().to_string();
+                before.push(SettingValue { path: path.clone(), value: previous });
+                after.push(SettingValue { path, value: value.clone() });
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    (before, after, errors)
+}
+
+fn describe_errors(errors: &[SysError]) -> String {
+    errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
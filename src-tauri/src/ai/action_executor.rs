@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use crate::action_log::ActionLog;
+use crate::ai::ActionOutcome;
+use crate::ai::custom_actions::ActionRegistry;
+use crate::config;
+use crate::database::Database;
+use crate::logs::{JournalReader, VacuumTarget, DEFAULT_JOURNAL_SIZE_MB};
+use crate::package_manager::PackageManager;
+use crate::{BackupManager, HardwareController};
+
+/// What happened when the executor ran (or declined to run) an action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionResult {
+    pub action: String,
+    pub success: bool,
+    pub message: String,
+    /// Set when the action was not run because it needs explicit confirmation
+    pub requires_confirmation: bool,
+    /// Background operation to poll, for package and backup actions
+    pub operation_id: Option<String>,
+    pub executed_at: DateTime<Utc>,
+}
+
+impl ActionResult {
+    fn completed(action: &str, success: bool, message: String) -> Self {
+        Self {
+            action: action.to_string(),
+            success,
+            message,
+            requires_confirmation: false,
+            operation_id: None,
+            executed_at: Utc::now(),
+        }
+    }
+    
+    /// How the result should be fed back into learning
+    pub fn outcome(&self) -> ActionOutcome {
+        if self.success {
+            ActionOutcome::Success
+        } else if self.requires_confirmation {
+            ActionOutcome::Partial(self.message.clone())
+        } else {
+            ActionOutcome::Failed(self.message.clone())
+        }
+    }
+    
+    /// "\n\n✅ Done: <message>" to append to a response
+    pub fn describe(&self) -> String {
+        let status = if self.requires_confirmation {
+            "⏸️ Needs confirmation"
+        } else if self.success {
+            "✅ Done"
+        } else {
+            "❌ Failed"
+        };
+        format!("\n\n{}: {}", status, self.message)
+    }
+}
+
+/// Actions handled by `execute` itself; custom actions can't take these names
+pub const BUILTIN_ACTIONS: [&str; 12] = [
+    "optimize_cpu", "optimize_cpu_high_usage", "set_cpu_governor", "set_governor_for_power_source",
+    "reduce_gpu_power_limit",
+    "revert_system_changes", "emergency_cooling", "emergency_system_protection",
+    "clean_system", "aggressive_cleanup", "clean_logs", "create_backup",
+];
+
+/// The call an action name is carried out with
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dispatch {
+    CpuGovernor(String),
+    /// Performance on AC, powersave on battery
+    GovernorForPowerSource,
+    /// Watts, or each GPU's minimum for None
+    GpuPowerLimit(Option<f64>),
+    /// Undo the recommendations applied since the executor was created
+    RevertSystemChanges,
+    EmergencyCooling,
+    PackageCleanup,
+    VacuumJournal(VacuumTarget),
+    Backup(PathBuf),
+    Custom(String),
+}
+
+/// Turns decision-engine action names into real calls on the hardware controller,
+/// package manager, journal and backup manager, or into user scripts from the
+/// custom action registry.
+pub struct ActionExecutor {
+    action_log: ActionLog,
+    /// Start of the session `revert_system_changes` undoes, in Unix seconds
+    session_started: u64,
+    /// Actions that change or delete data and only run with `confirmed = true`
+    pub destructive_actions: Vec<String>,
+    custom_actions: ActionRegistry,
+}
+
+impl ActionExecutor {
+    pub fn new(database: Database, custom_actions: ActionRegistry) -> Self {
+        Self {
+            action_log: ActionLog::new(database),
+            session_started: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            destructive_actions: vec![
+                "clean_system".to_string(),
+                "aggressive_cleanup".to_string(),
+            ],
+            custom_actions,
+        }
+    }
+    
+    pub fn custom_actions(&self) -> &ActionRegistry {
+        &self.custom_actions
+    }
+    
+    /// Pick up added or edited action manifests
+    pub fn reload_custom_actions(&mut self) {
+        self.custom_actions = ActionRegistry::load(&self.custom_actions.dir, &BUILTIN_ACTIONS);
+    }
+    
+    /// Whether `action` is something `execute` can run, as opposed to advice or a query
+    pub fn handles(&self, action: &str) -> bool {
+        BUILTIN_ACTIONS.contains(&action) || self.custom_actions.get(action).is_some()
+    }
+    
+    pub fn is_destructive(&self, action: &str) -> bool {
+        self.destructive_actions.iter().any(|a| a == action)
+            || self.custom_actions.get(action).map(|custom| custom.needs_confirmation()).unwrap_or(false)
+    }
+    
+    /// The call `action` would make with `parameters`, without making it
+    pub fn plan(&self, action: &str, parameters: &HashMap<String, String>) -> Result<Dispatch, String> {
+        match action {
+            "optimize_cpu" | "optimize_cpu_high_usage" => Ok(Dispatch::CpuGovernor("performance".to_string())),
+            "set_cpu_governor" => match parameters.get("governor") {
+                Some(governor) => Ok(Dispatch::CpuGovernor(governor.clone())),
+                None => Err("set_cpu_governor needs a 'governor' parameter".to_string()),
+            },
+            "set_governor_for_power_source" => Ok(Dispatch::GovernorForPowerSource),
+            "reduce_gpu_power_limit" => match parameters.get("watts") {
+                Some(watts) => watts.parse().map(|watts| Dispatch::GpuPowerLimit(Some(watts)))
+                    .map_err(|_| format!("Invalid watts '{}'", watts)),
+                None => Ok(Dispatch::GpuPowerLimit(None)),
+            },
+            "revert_system_changes" => Ok(Dispatch::RevertSystemChanges),
+            "emergency_cooling" | "emergency_system_protection" => Ok(Dispatch::EmergencyCooling),
+            "clean_system" | "aggressive_cleanup" => Ok(Dispatch::PackageCleanup),
+            // max_age_days wins over max_size_mb; neither means DEFAULT_JOURNAL_SIZE_MB
+            "clean_logs" => match (parameters.get("max_age_days"), parameters.get("max_size_mb")) {
+                (Some(days), _) => days.parse().map(|days| Dispatch::VacuumJournal(VacuumTarget::MaxAgeDays(days)))
+                    .map_err(|_| format!("Invalid max_age_days '{}'", days)),
+                (None, Some(mb)) => mb.parse().map(|mb| Dispatch::VacuumJournal(VacuumTarget::SizeMb(mb)))
+                    .map_err(|_| format!("Invalid max_size_mb '{}'", mb)),
+                (None, None) => Ok(Dispatch::VacuumJournal(VacuumTarget::SizeMb(DEFAULT_JOURNAL_SIZE_MB))),
+            },
+            "create_backup" => {
+                let destination = config::get().backup.destination.unwrap_or_else(|| PathBuf::from("./backups"));
+                Ok(Dispatch::Backup(destination))
+            },
+            _ if self.custom_actions.get(action).is_some() => Ok(Dispatch::Custom(action.to_string())),
+            _ => Err(format!("No executor for action '{}'", action)),
+        }
+    }
+    
+    pub async fn execute(&self, action: &str, parameters: &HashMap<String, String>, confirmed: bool) -> ActionResult {
+        if self.is_destructive(action) && !confirmed {
+            info!("⏸️ Action {} needs confirmation before it runs", action);
+            let message = match action {
+                // Show what would go, so the confirmation is an informed one
+                "clean_system" | "aggressive_cleanup" => match PackageManager::new_comprehensive().await {
+                    Ok(package_manager) => format!("'{}' needs confirmation. {}", action, package_manager.preview_cleanup().await.summary()),
+                    Err(_) => format!("'{}' removes packages and cached files and needs confirmation", action),
+                },
+                _ => format!("'{}' changes the system and needs confirmation", action),
+            };
+            return ActionResult {
+                action: action.to_string(),
+                success: false,
+                message,
+                requires_confirmation: true,
+                operation_id: None,
+                executed_at: Utc::now(),
+            };
+        }
+        
+        info!("▶️ Executing action: {}", action);
+        
+        let result = match self.plan(action, parameters) {
+            Ok(dispatch) => self.run(dispatch, parameters).await,
+            Err(e) => Err(e),
+        };
+        
+        match result {
+            Ok(message) => ActionResult::completed(action, true, message),
+            Err(message) => {
+                warn!("❌ Action {} failed: {}", action, message);
+                ActionResult::completed(action, false, message)
+            }
+        }
+    }
+    
+    async fn run(&self, dispatch: Dispatch, parameters: &HashMap<String, String>) -> Result<String, String> {
+        match dispatch {
+            Dispatch::CpuGovernor(governor) => {
+                blocking(move || HardwareController::set_cpu_governor(&governor)).await
+            },
+            Dispatch::GovernorForPowerSource => {
+                let governor = match crate::read_ac_online(std::path::Path::new(crate::POWER_SUPPLY_DIR)) {
+                    Some(true) => "performance",
+                    Some(false) => "powersave",
+                    None => return Err("No AC adapter found to pick a governor for".to_string()),
+                };
+                blocking(move || HardwareController::set_cpu_governor(governor)).await
+            },
+            Dispatch::GpuPowerLimit(watts) => {
+                blocking(move || HardwareController::set_gpu_power_limit(watts)).await
+            },
+            Dispatch::RevertSystemChanges => {
+                let undone = self.action_log.undo_applied_since(self.session_started).map_err(|e| e.to_string())?;
+                let settings: usize = undone.iter().map(|action| action.after.len()).sum();
+                Ok(format!("Reverted {} recommendations, {} settings restored", undone.len(), settings))
+            },
+            Dispatch::EmergencyCooling => blocking(HardwareController::emergency_cooling).await,
+            Dispatch::PackageCleanup => {
+                let mut package_manager = PackageManager::new_comprehensive().await.map_err(|e| e.to_string())?;
+                let preview = package_manager.preview_cleanup().await;
+                package_manager.apply_cleanup(&preview).await.map_err(|e| e.to_string())
+            },
+            Dispatch::VacuumJournal(target) => {
+                JournalReader::new().vacuum(target)
+                    .map(|freed| format!("Journal vacuumed, {:.1} MiB freed", freed as f64 / 1048576.0))
+                    .map_err(|e| e.to_string())
+            },
+            Dispatch::Backup(destination) => {
+                blocking(move || BackupManager::create_backup(&destination.to_string_lossy())).await
+            },
+            Dispatch::Custom(name) => {
+                self.custom_actions.run(&name, parameters).await.map_err(|e| e.to_string())
+            },
+        }
+    }
+}
+
+/// Run a blocking hardware or backup call off the async workers
+async fn blocking<F>(f: F) -> Result<String, String>
+where
+    F: FnOnce() -> anyhow::Result<String> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(e) => Err(format!("Action task failed: {}", e)),
+    }
+}
@@ -0,0 +1,182 @@
+// Assistant - natural-language commands end to end: the NLP processor parses the
+// input, the decision engine picks an action for the current state and the action
+// executor carries it out.
+
+use chrono::{Datelike, Local, Timelike, Utc};
+use tracing::{info, warn};
+use crate::database::Database;
+use crate::{ProcessCandidate, SystemMetrics};
+use super::action_executor::ActionExecutor;
+use super::custom_actions::ActionRegistry;
+use super::decision_engine::{Decision, DecisionEngine};
+use super::natural_language::NLPProcessor;
+use super::preferences::Preferences;
+use super::workload_classifier::WorkloadClassifier;
+use super::{SystemState, UserAction};
+
+/// Celsius, RTX 4080 Mobile
+const GPU_TEMPERATURE_LIMIT: f64 = 83.0;
+
+/// How an input was understood
+#[derive(Debug, Clone)]
+pub enum Interpretation {
+    /// The input matched several actions; holds the question to ask back
+    Ambiguous(String),
+    Decided(Decision),
+}
+
+pub struct Assistant {
+    nlp: NLPProcessor,
+    decision_engine: DecisionEngine,
+    executor: ActionExecutor,
+    preferences: Preferences,
+}
+
+impl Assistant {
+    pub async fn new(database: Database, custom_actions: ActionRegistry) -> Result<Self, Box<dyn std::error::Error>> {
+        info!("💬 Initializing assistant...");
+        let nlp = NLPProcessor::new_with_sysadmin_vocab().await?;
+        let mut decision_engine = DecisionEngine::new(GPU_TEMPERATURE_LIMIT).await?;
+        decision_engine.register_custom_actions(&custom_actions);
+        
+        Ok(Self {
+            nlp,
+            decision_engine,
+            preferences: Preferences::load(database.clone()),
+            executor: ActionExecutor::new(database, custom_actions),
+        })
+    }
+    
+    pub fn executor(&self) -> &ActionExecutor {
+        &self.executor
+    }
+    
+    pub fn preferences(&self) -> &Preferences {
+        &self.preferences
+    }
+    
+    /// Parse `input` and pick the action for `state`, without running anything
+    pub async fn interpret(&mut self, input: &str, state: &SystemState) -> Result<Interpretation, Box<dyn std::error::Error>> {
+        let intent = self.nlp.parse_intent(input).await?;
+        if self.nlp.needs_clarification(&intent) {
+            return Ok(Interpretation::Ambiguous(self.nlp.clarification_question(&intent)));
+        }
+        
+        let decision = self.decision_engine.decide_action(&intent, state).await?;
+        Ok(Interpretation::Decided(decision))
+    }
+    
+    /// Answer `input` and run the action it asks for. Destructive actions only run
+    /// with `confirmed`; otherwise the reply says what would happen.
+    pub async fn respond(&mut self, input: &str, state: &SystemState, confirmed: bool) -> Result<String, Box<dyn std::error::Error>> {
+        let decision = match self.interpret(input, state).await? {
+            Interpretation::Ambiguous(question) => return Ok(question),
+            Interpretation::Decided(decision) => decision,
+        };
+        
+        let mut response = self.nlp.generate_response(&decision.action, state).await?;
+        
+        // Advice and status queries have nothing to execute
+        if self.executor.handles(&decision.action) {
+            let result = self.executor.execute(&decision.action, &decision.parameters, confirmed).await;
+            if !result.requires_confirmation {
+                let mut parameters = decision.parameters.clone();
+                parameters.insert("action".to_string(), decision.action.clone());
+                self.preferences.learn_from_user_action(&UserAction {
+                    timestamp: Utc::now(),
+                    action_type: "natural_language_command".to_string(),
+                    context: input.to_string(),
+                    parameters,
+                    outcome: result.outcome(),
+                });
+                self.decision_engine.record_decision_outcome(decision, result.success).await?;
+            }
+            response.push_str(&result.describe());
+        }
+        
+        Ok(response)
+    }
+}
+
+/// The state the decision engine decides against, from the monitor's last sample
+pub fn system_state(metrics: Option<&SystemMetrics>, processes: &[ProcessCandidate], gpu_temperature: Option<f64>) -> SystemState {
+    let now = Local::now();
+    if metrics.is_none() {
+        warn!("No metrics sampled yet, deciding against an idle state");
+    }
+    
+    SystemState {
+        cpu_usage: metrics.map(|m| m.cpu_usage).unwrap_or(0.0),
+        memory_usage: metrics.map(|m| m.memory_usage).unwrap_or(0.0),
+        disk_usage: metrics.map(|m| m.disk_usage).unwrap_or(0.0),
+        temperature: metrics.map(|m| m.temperature).unwrap_or(0.0),
+        gpu_temperature: gpu_temperature.unwrap_or(0.0),
+        memory_pressure: metrics.and_then(|m| m.pressure.memory_stall_percent()),
+        active_processes: processes.iter().map(|p| p.name.clone()).collect(),
+        current_workload: WorkloadClassifier::default().classify(processes),
+        time_of_day: now.hour() as u8,
+        day_of_week: now.weekday().num_days_from_sunday() as u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::ai::action_executor::Dispatch;
+    use crate::ai::WorkloadType;
+    
+    fn calm_state() -> SystemState {
+        SystemState {
+            cpu_usage: 10.0,
+            memory_usage: 30.0,
+            disk_usage: 40.0,
+            temperature: 50.0,
+            gpu_temperature: 45.0,
+            memory_pressure: None,
+            active_processes: Vec::new(),
+            current_workload: WorkloadType::Idle,
+            time_of_day: 12,
+            day_of_week: 3,
+        }
+    }
+    
+    #[tokio::test]
+    async fn governor_request_dispatches_to_governor_setter() {
+        let root = std::env::temp_dir().join(format!("assistant_dispatch_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let database = Database::open(root.join("assistant.db")).unwrap();
+        let mut assistant = Assistant::new(database, ActionRegistry::default()).await.unwrap();
+        
+        let decision = match assistant.interpret("set the cpu governor to powersave", &calm_state()).await.unwrap() {
+            Interpretation::Decided(decision) => decision,
+            Interpretation::Ambiguous(question) => panic!("asked back: {}", question),
+        };
+        assert_eq!(decision.action, "set_cpu_governor");
+        assert!(assistant.executor().handles(&decision.action));
+        assert_eq!(
+            assistant.executor().plan(&decision.action, &decision.parameters),
+            Ok(Dispatch::CpuGovernor("powersave".to_string()))
+        );
+        
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn gpu_power_limit_action_dispatches_to_power_limit_setter() {
+        let root = std::env::temp_dir().join(format!("assistant_gpu_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let database = Database::open(root.join("assistant.db")).unwrap();
+        let assistant = Assistant::new(database, ActionRegistry::default()).await.unwrap();
+        let executor = assistant.executor();
+        
+        assert!(executor.handles("reduce_gpu_power_limit"));
+        assert_eq!(executor.plan("reduce_gpu_power_limit", &HashMap::new()), Ok(Dispatch::GpuPowerLimit(None)));
+        let watts = HashMap::from([("watts".to_string(), "120".to_string())]);
+        assert_eq!(executor.plan("reduce_gpu_power_limit", &watts), Ok(Dispatch::GpuPowerLimit(Some(120.0))));
+        let garbage = HashMap::from([("watts".to_string(), "lots".to_string())]);
+        assert!(executor.plan("reduce_gpu_power_limit", &garbage).is_err());
+        
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+// Custom Actions - User scripts the AI can suggest and run like built-in actions
+// Each action is a TOML manifest in ~/.config/ai-sysadmin/actions/ next to its script:
+//
+//   name = "tune_wifi"
+//   description = "Disable Wi-Fi power saving"
+//   script = "tune-wifi.sh"
+//   revert_script = "untune-wifi.sh"
+//   [[suggest_when]]
+//   metric = "cpu_usage"
+//   below = 20.0
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command as AsyncCommand;
+use tracing::{info, debug, warn};
+use crate::ai::SystemState;
+use crate::config::Config;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+/// Script parameters arrive as environment variables named AI_PARAM_<KEY>
+const PARAMETER_ENV_PREFIX: &str = "AI_PARAM_";
+/// Parameter that makes the executor run `revert_script` instead of `script`
+pub const REVERT_PARAMETER: &str = "revert";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomAction {
+    /// Action name the AI uses; must not clash with a built-in
+    pub name: String,
+    pub description: String,
+    /// Relative to the actions directory unless absolute
+    pub script: PathBuf,
+    /// Undoes `script`; an action is reversible only when this is set
+    #[serde(default)]
+    pub revert_script: Option<PathBuf>,
+    /// Every condition must hold for the action to be suggested; none means
+    /// it only runs when asked for by name
+    #[serde(default)]
+    pub suggest_when: Vec<SuggestCondition>,
+    /// Defaults to true unless the action is reversible
+    #[serde(default)]
+    pub requires_confirmation: Option<bool>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// One requirement on the current system state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SuggestCondition {
+    /// cpu_usage, memory_usage, disk_usage, temperature, gpu_temperature or memory_pressure
+    pub metric: Option<String>,
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    /// A process with this name is running
+    pub process: Option<String>,
+    /// Gaming, Development, Media, SystemMaintenance or Idle
+    pub workload: Option<String>,
+}
+
+impl CustomAction {
+    pub fn reversible(&self) -> bool {
+        self.revert_script.is_some()
+    }
+    
+    pub fn needs_confirmation(&self) -> bool {
+        self.requires_confirmation.unwrap_or(!self.reversible())
+    }
+    
+    pub fn should_suggest(&self, state: &SystemState) -> bool {
+        !self.suggest_when.is_empty() && self.suggest_when.iter().all(|condition| condition.matches(state))
+    }
+}
+
+impl SuggestCondition {
+    pub fn matches(&self, state: &SystemState) -> bool {
+        if let Some(metric) = &self.metric {
+            let value = match metric.as_str() {
+                "cpu_usage" => Some(state.cpu_usage),
+                "memory_usage" => Some(state.memory_usage),
+                "disk_usage" => Some(state.disk_usage),
+                "temperature" => Some(state.temperature),
+                "gpu_temperature" => Some(state.gpu_temperature),
+                "memory_pressure" => state.memory_pressure,
+                _ => None,
+            };
+            let value = match value {
+                Some(value) => value,
+                None => return false,
+            };
+            if self.above.map(|above| value <= above).unwrap_or(false) || self.below.map(|below| value >= below).unwrap_or(false) {
+                return false;
+            }
+        }
+        
+        if let Some(process) = &self.process {
+            if !state.active_processes.iter().any(|p| p == process) {
+                return false;
+            }
+        }
+        
+        if let Some(workload) = &self.workload {
+            if !format!("{:?}", state.current_workload).eq_ignore_ascii_case(workload) {
+                return false;
+            }
+        }
+        
+        true
+    }
+}
+
+/// Custom actions loaded from the actions directory
+#[derive(Debug, Clone, Default)]
+pub struct ActionRegistry {
+    pub dir: PathBuf,
+    actions: HashMap<String, CustomAction>,
+}
+
+impl ActionRegistry {
+    /// ~/.config/ai-sysadmin/actions, next to config.toml
+    pub fn default_dir() -> PathBuf {
+        Config::path().parent().map(Path::to_path_buf).unwrap_or_default().join("actions")
+    }
+    
+    /// Load every `*.toml` manifest in `dir`. Broken manifests and missing or
+    /// non-executable scripts are skipped with a warning.
+    pub fn load(dir: &Path, builtin_actions: &[&str]) -> Self {
+        let mut registry = Self { dir: dir.to_path_buf(), actions: HashMap::new() };
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => {
+                debug!("🧩 No custom actions directory at {}", dir.display());
+                return registry;
+            }
+        };
+        
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|ext| ext != "toml").unwrap_or(true) {
+                continue;
+            }
+            match registry.load_manifest(&path) {
+                Ok(action) if builtin_actions.contains(&action.name.as_str()) => {
+                    warn!("⚠️ Custom action {} in {} clashes with a built-in action, skipping", action.name, path.display());
+                }
+                Ok(action) => {
+                    debug!("🧩 Loaded custom action {}", action.name);
+                    registry.actions.insert(action.name.clone(), action);
+                }
+                Err(e) => warn!("⚠️ Skipping custom action {}: {}", path.display(), e),
+            }
+        }
+        
+        info!("🧩 Loaded {} custom actions from {}", registry.actions.len(), dir.display());
+        registry
+    }
+    
+    fn load_manifest(&self, path: &Path) -> Result<CustomAction, Box<dyn std::error::Error>> {
+        let mut action: CustomAction = toml::from_str(&fs::read_to_string(path)?)?;
+        if action.name.trim().is_empty() {
+            return Err("manifest has no name".into());
+        }
+        
+        action.script = self.resolve(&action.script);
+        check_executable(&action.script)?;
+        if let Some(revert_script) = &action.revert_script {
+            let revert_script = self.resolve(revert_script);
+            check_executable(&revert_script)?;
+            action.revert_script = Some(revert_script);
+        }
+        Ok(action)
+    }
+    
+    fn resolve(&self, script: &Path) -> PathBuf {
+        if script.is_absolute() { script.to_path_buf() } else { self.dir.join(script) }
+    }
+    
+    pub fn get(&self, name: &str) -> Option<&CustomAction> {
+        self.actions.get(name)
+    }
+    
+    pub fn actions(&self) -> impl Iterator<Item = &CustomAction> {
+        self.actions.values()
+    }
+    
+    /// Actions whose `suggest_when` conditions all hold right now
+    pub fn suggestions(&self, state: &SystemState) -> Vec<&CustomAction> {
+        self.actions.values().filter(|action| action.should_suggest(state)).collect()
+    }
+    
+    /// Run `name`'s script, or its revert script when `parameters` has revert=true.
+    /// Returns the script's trimmed stdout.
+    pub async fn run(&self, name: &str, parameters: &HashMap<String, String>) -> Result<String, Box<dyn std::error::Error>> {
+        let action = self.get(name).ok_or_else(|| format!("No custom action '{}'", name))?;
+        let revert = parameters.get(REVERT_PARAMETER).map(|r| r == "true").unwrap_or(false);
+        let script = if revert {
+            action.revert_script.as_ref().ok_or_else(|| format!("'{}' has no revert script", name))?
+        } else {
+            &action.script
+        };
+        info!("🧩 Running custom action {}{}", name, if revert { " (revert)" } else { "" });
+        
+        let mut command = AsyncCommand::new(script);
+        command.current_dir(&self.dir).kill_on_drop(true);
+        for (key, value) in parameters {
+            command.env(format!("{}{}", PARAMETER_ENV_PREFIX, key.to_uppercase()), value);
+        }
+        
+        let timeout = Duration::from_secs(action.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        let output = tokio::time::timeout(timeout, command.output()).await
+            .map_err(|_| format!("'{}' timed out after {}s", name, timeout.as_secs()))??;
+        
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() {
+            Ok(if stdout.is_empty() { format!("{} completed", action.description) } else { stdout })
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            Err(format!("'{}' failed ({}): {}", name, output.status, if stderr.is_empty() { stdout } else { stderr }).into())
+        }
+    }
+}
+
+fn check_executable(script: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let metadata = fs::metadata(script).map_err(|e| format!("{}: {}", script.display(), e))?;
+    if !metadata.is_file() || metadata.permissions().mode() & 0o111 == 0 {
+        return Err(format!("{} is not an executable file", script.display()).into());
+    }
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
+use chrono::Timelike;
 use serde::{Deserialize, Serialize};
 use tracing::{info, debug, warn};
 use crate::ai::{SystemState, WorkloadType, natural_language::Intent, natural_language::IntentCategory};
+use crate::ai::custom_actions::ActionRegistry;
+use crate::config;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Decision {
@@ -40,7 +43,6 @@ pub enum RiskLevel {
     Critical,
 }
 
-#[derive(Debug, Clone)]
 struct DecisionRule {
     condition: Box<dyn Fn(&SystemState, &Intent) -> bool + Send + Sync>,
     action: String,
@@ -50,6 +52,8 @@ struct DecisionRule {
 
 pub struct DecisionEngine {
     decision_rules: Vec<DecisionRule>,
+    /// Rules past this index come from custom actions and are replaced on re-register
+    builtin_rule_count: usize,
     action_history: Vec<(Decision, bool)>, // (decision, was_successful)
     system_constraints: SystemConstraints,
     user_preferences: UserPreferences,
@@ -60,6 +64,8 @@ struct SystemConstraints {
     max_cpu_usage_threshold: f64,
     max_memory_usage_threshold: f64,
     max_temperature_threshold: f64,
+    /// °C before the GPU counts as running hot
+    max_gpu_temperature_threshold: f64,
     critical_processes: Vec<String>,
     protected_directories: Vec<String>,
     maintenance_window: (u8, u8), // (start_hour, end_hour)
@@ -85,16 +91,19 @@ enum NotificationLevel {
 }
 
 impl DecisionEngine {
-    pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    /// `gpu_temperature_limit` is the GPU temperature (°C) above which the power limit is lowered
+    pub async fn new(gpu_temperature_limit: f64) -> Result<Self, Box<dyn std::error::Error>> {
         info!("🧭 Initializing Decision Engine for system administration...");
         
         let mut engine = Self {
             decision_rules: Vec::new(),
+            builtin_rule_count: 0,
             action_history: Vec::new(),
             system_constraints: SystemConstraints {
                 max_cpu_usage_threshold: 85.0,
                 max_memory_usage_threshold: 90.0,
                 max_temperature_threshold: 85.0,
+                max_gpu_temperature_threshold: gpu_temperature_limit,
                 critical_processes: vec![
                     "systemd".to_string(),
                     "kernel".to_string(),
@@ -120,6 +129,7 @@ impl DecisionEngine {
         };
         
         engine.initialize_decision_rules().await?;
+        engine.builtin_rule_count = engine.decision_rules.len();
         
         Ok(engine)
     }
@@ -190,6 +200,8 @@ impl DecisionEngine {
             risk_level: RiskLevel::Low,
         });
         
+        self.decision_rules.push(gpu_temperature_rule(self.system_constraints.max_gpu_temperature_threshold));
+        
         // Package Management Rules
         self.decision_rules.push(DecisionRule {
             condition: Box::new(|_state, intent| {
@@ -223,6 +235,17 @@ impl DecisionEngine {
             risk_level: RiskLevel::Low,
         });
         
+        // Monthly maintenance: only archived journal files are deleted
+        self.decision_rules.push(DecisionRule {
+            condition: Box::new(|_state, intent| {
+                matches!(intent.category, IntentCategory::Maintenance) &&
+                intent.action == "clean_logs"
+            }),
+            action: "clean_logs".to_string(),
+            confidence_modifier: 0.9,
+            risk_level: RiskLevel::Safe,
+        });
+        
         self.decision_rules.push(DecisionRule {
             condition: Box::new(|state, intent| {
                 matches!(intent.category, IntentCategory::FileManagement) &&
@@ -267,6 +290,35 @@ impl DecisionEngine {
         Ok(())
     }
     
+    /// Move the GPU temperature (°C) above which the power limit is lowered
+    pub fn set_gpu_temperature_limit(&mut self, max_celsius: f64) {
+        self.system_constraints.max_gpu_temperature_threshold = max_celsius;
+        if let Some(rule) = self.decision_rules.iter_mut().find(|rule| rule.action == "reduce_gpu_power_limit") {
+            *rule = gpu_temperature_rule(max_celsius);
+        }
+    }
+    
+    /// Add a rule per custom action: it matches when asked for by name, or for an
+    /// optimization request while its `suggest_when` conditions hold
+    pub fn register_custom_actions(&mut self, registry: &ActionRegistry) {
+        self.decision_rules.truncate(self.builtin_rule_count);
+        
+        for action in registry.actions() {
+            let name = action.name.clone();
+            let custom = action.clone();
+            self.decision_rules.push(DecisionRule {
+                condition: Box::new(move |state, intent| {
+                    intent.action == name ||
+                    (matches!(intent.category, IntentCategory::SystemOptimization) && custom.should_suggest(state))
+                }),
+                action: action.name.clone(),
+                confidence_modifier: 0.85,
+                risk_level: if action.reversible() { RiskLevel::Low } else { RiskLevel::Medium },
+            });
+        }
+        debug!("🧩 Registered {} custom action rules", self.decision_rules.len() - self.builtin_rule_count);
+    }
+    
     pub async fn decide_action(&mut self, intent: &Intent, system_state: &SystemState) -> Result<Decision, Box<dyn std::error::Error>> {
         debug!("🤔 Making decision for intent: {} in current system state", intent.action);
         
@@ -414,8 +466,9 @@ impl DecisionEngine {
             },
             "optimize_memory_aggressive" => {
                 reasoning = format!(
-                    "Memory usage is high ({:.1}% of 64GB). Applying aggressive cleanup.",
-                    system_state.memory_usage
+                    "Memory usage is high ({:.1}% of {}GB). Applying aggressive cleanup.",
+                    system_state.memory_usage,
+                    config::get().hardware.total_memory_gb()
                 );
             },
             "emergency_cooling" => {
@@ -424,6 +477,12 @@ impl DecisionEngine {
                     system_state.temperature
                 );
             },
+            "reduce_gpu_power_limit" => {
+                reasoning = format!(
+                    "GPU temperature is {:.1}°C. Lowering the GPU power limit to reduce heat.",
+                    system_state.gpu_temperature
+                );
+            },
             "gaming_optimization" => {
                 reasoning = "Gaming workload detected. Optimizing for performance over power efficiency.".to_string();
             },
@@ -438,6 +497,11 @@ impl DecisionEngine {
             }
         }
         
+        if action != "reduce_gpu_power_limit" &&
+           system_state.gpu_temperature > self.system_constraints.max_gpu_temperature_threshold {
+            reasoning.push_str(&format!(" GPU is also running hot ({:.1}°C).", system_state.gpu_temperature));
+        }
+        
         // Add workload context
         match system_state.current_workload {
             WorkloadType::Gaming => reasoning.push_str(" Gaming mode optimizations applied."),
@@ -485,6 +549,17 @@ impl DecisionEngine {
                 ],
             },
             
+            "reduce_gpu_power_limit" => ExpectedOutcome {
+                description: format!("Bring GPU temperature down from {:.1}°C", system_state.gpu_temperature),
+                estimated_duration: 60,
+                system_impact: SystemImpact::Low,
+                user_benefits: vec![
+                    "Lower GPU temperature".to_string(),
+                    "Quieter fans".to_string(),
+                    "Avoid GPU thermal throttling".to_string(),
+                ],
+            },
+            
             "gaming_optimization" => ExpectedOutcome {
                 description: "Optimize system for gaming performance".to_string(),
                 estimated_duration: 20,
@@ -531,6 +606,10 @@ impl DecisionEngine {
             priority += 2;
         }
         
+        if system_state.gpu_temperature > self.system_constraints.max_gpu_temperature_threshold {
+            priority += 1;
+        }
+        
         if system_state.cpu_usage > 90.0 {
             priority += 1;
         }
@@ -642,3 +721,16 @@ impl DecisionEngine {
         stats
     }
 }
+
+/// Lower the GPU power limit when asked to cool the GPU, or once it runs past `max_celsius`
+fn gpu_temperature_rule(max_celsius: f64) -> DecisionRule {
+    DecisionRule {
+        condition: Box::new(move |state, intent| {
+            intent.action == "reduce_gpu_temperature" ||
+            state.gpu_temperature > max_celsius
+        }),
+        action: "reduce_gpu_power_limit".to_string(),
+        confidence_modifier: 0.9,
+        risk_level: RiskLevel::Low,
+    }
+}
@@ -0,0 +1,1004 @@
+// AIEngine - ties the learning models in ai/ to the system monitor, database and
+// system controller. Not declared in ai/mod.rs yet: monitoring_system and system
+// don't build, so the parts that do are compiled on their own.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{info, warn, debug};
+use crate::config;
+use crate::database::Database;
+use crate::hardware::{self, StorageDevice};
+use crate::monitoring_system::SystemMonitor;
+use crate::package_manager::FileIntegrityIssue;
+use crate::system::{PerformanceProfile, SystemController};
+use crate::system::security::SecurityAuditor;
+use crate::system::virtualization::LibvirtClient;
+use super::{
+    action_executor, custom_actions, decision_engine, natural_language, neural_network,
+    pattern_recognition, preferences, report, state_export, workload_classifier,
+    AIRecommendation, ActionOutcome, SystemState, UserAction, WorkloadType,
+};
+
+/// One natural-language command: what was typed, how it was understood and what ran
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandRecord {
+    pub timestamp: DateTime<Utc>,
+    pub input: String,
+    pub intent: natural_language::Intent,
+    /// What actually ran, which the decision engine may have picked over the intent's action
+    pub action: String,
+    pub parameters: HashMap<String, String>,
+    pub outcome: ActionOutcome,
+}
+
+pub struct AIEngine {
+    neural_network: neural_network::NeuralNetwork,
+    neural_network_path: PathBuf,
+    pattern_recognition: pattern_recognition::PatternRecognizer,
+    nlp_processor: natural_language::NLPProcessor,
+    decision_engine: decision_engine::DecisionEngine,
+    preferences: preferences::Preferences,
+    learned_patterns: Vec<UserAction>,
+    system_knowledge: SystemKnowledge,
+    system_monitor: Arc<Mutex<SystemMonitor>>,
+    workload_classifier: workload_classifier::WorkloadClassifier,
+    action_executor: action_executor::ActionExecutor,
+    system_controller: Arc<Mutex<SystemController>>,
+    security_auditor: SecurityAuditor,
+    integrity_issues: Vec<FileIntegrityIssue>,
+    database: Database,
+    /// Power source seen at the last check, to notice the charger being plugged in or pulled
+    on_ac_power: Option<bool>,
+    storage_health: Vec<StorageDevice>,
+    storage_checked_at: Option<DateTime<Utc>>,
+    /// Ambiguous input and its parse, waiting for the user to say which action they meant
+    pending_clarification: Option<(String, natural_language::Intent)>,
+    /// A destructive command waiting for execute_confirmed_action; it is learned from
+    /// and recorded only once it has actually run
+    pending_confirmation: Option<PendingConfirmation>,
+    /// Oldest first, at most COMMAND_HISTORY_LIMIT entries; mirrored to the database
+    command_history: VecDeque<CommandRecord>,
+    /// Latest of each distinct recommendation, oldest first, for system reports
+    recent_recommendations: VecDeque<AIRecommendation>,
+}
+
+/// What was asked for when an action stopped for confirmation
+struct PendingConfirmation {
+    input: String,
+    intent: natural_language::Intent,
+    action: String,
+    /// None for a repeated or undone command, which the decision engine didn't pick
+    decision: Option<decision_engine::Decision>,
+}
+
+/// Average benchmark change (%) from switching to a profile, as "benchmark:<profile>" rows
+const BENCHMARK_PATTERN_PREFIX: &str = "benchmark:";
+
+/// Extra watts a profile draws over the previous settings while benchmarking, as "benchmark_power:<profile>" rows
+const BENCHMARK_POWER_PATTERN_PREFIX: &str = "benchmark_power:";
+
+/// A profile costing at least this many extra watts...
+const PROFILE_POWER_COST_WATTS: f64 = 15.0;
+/// ...for less than this average benchmark gain (%) is suggested against
+const PROFILE_WORTHWHILE_GAIN_PERCENT: f64 = 10.0;
+
+/// SMART data changes slowly and smartctl spins up sleeping disks
+const STORAGE_HEALTH_INTERVAL_SECS: i64 = 3600;
+
+/// Metric samples behind the trend in status answers
+const STATUS_TREND_SAMPLES: usize = 10;
+
+const COMMAND_HISTORY_LIMIT: usize = 200;
+
+const RECENT_RECOMMENDATIONS_LIMIT: usize = 20;
+
+/// How much of each list goes into a system report
+const REPORT_PROCESSES: usize = 10;
+const REPORT_ALERTS: usize = 10;
+const REPORT_RECOMMENDATIONS: usize = 10;
+
+/// Actions whose changes SystemController tracks, so "undo that" can revert them
+const REVERTIBLE_ACTIONS: [&str; 6] = [
+    "optimize_cpu", "optimize_cpu_high_usage", "set_cpu_governor",
+    "set_governor_for_power_source", "emergency_cooling", "emergency_system_protection",
+];
+
+#[derive(Debug)]
+struct SystemKnowledge {
+    // Hardware-specific knowledge for i9-13900HX
+    optimal_cpu_temps: (f64, f64), // (min, max) for optimal performance
+    optimal_gpu_temps: (f64, f64), // (min, max) for the NVIDIA GPU; the decision engine keeps its own copy of max
+    memory_usage_patterns: HashMap<String, f64>,
+    disk_io_patterns: HashMap<String, f64>,
+    
+    // Software patterns for Garuda Linux
+    package_update_frequency: HashMap<String, u32>,
+    system_maintenance_schedule: Vec<String>,
+    
+    // User-specific patterns (learned over time)
+    daily_usage_patterns: HashMap<u8, WorkloadType>, // hour -> typical workload
+    application_preferences: HashMap<String, f64>,
+    optimization_preferences: HashMap<String, bool>,
+}
+
+impl AIEngine {
+    /// `neural_network_path` is where the network is loaded from here and saved to by `shutdown`
+    pub async fn new_for_i9_13900hx(
+        system_monitor: Arc<Mutex<SystemMonitor>>,
+        system_controller: Arc<Mutex<SystemController>>,
+        action_executor: action_executor::ActionExecutor,
+        database: Database,
+        neural_network_path: PathBuf,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        info!("🧠 Initializing AI Engine for i9-13900HX...");
+        
+        // Initialize components
+        // Resume from previously learned weights when available
+        let neural_network = match neural_network::NeuralNetwork::load(&neural_network_path) {
+            Ok(network) => network,
+            Err(e) => {
+                debug!("No usable saved neural network ({}), starting fresh", e);
+                neural_network::NeuralNetwork::new_for_sysadmin().await?
+            }
+        };
+        let pattern_recognition = pattern_recognition::PatternRecognizer::new().await?;
+        let nlp_processor = natural_language::NLPProcessor::new_with_sysadmin_vocab().await?;
+        let gpu_temperature_limit = 83.0; // Celsius, RTX 4080 Mobile
+        let mut decision_engine = decision_engine::DecisionEngine::new(gpu_temperature_limit).await?;
+        decision_engine.register_custom_actions(action_executor.custom_actions());
+        
+        // Initialize system knowledge with hardware-specific data
+        let system_knowledge = SystemKnowledge {
+            // i9-13900HX optimal operating ranges
+            optimal_cpu_temps: (65.0, 85.0), // Celsius
+            optimal_gpu_temps: (60.0, gpu_temperature_limit), // Celsius
+            memory_usage_patterns: HashMap::new(),
+            disk_io_patterns: HashMap::new(),
+            
+            // Garuda Linux specific
+            package_update_frequency: HashMap::new(),
+            system_maintenance_schedule: vec![
+                "Daily: Clear package cache".to_string(),
+                "Weekly: Update system".to_string(),
+                "Monthly: Clean logs".to_string(),
+            ],
+            
+            // Will be learned over time
+            daily_usage_patterns: HashMap::new(),
+            application_preferences: HashMap::new(),
+            optimization_preferences: HashMap::new(),
+        };
+        
+        Ok(Self {
+            neural_network,
+            neural_network_path,
+            pattern_recognition,
+            nlp_processor,
+            decision_engine,
+            preferences: preferences::Preferences::load(database.clone()),
+            learned_patterns: Vec::new(),
+            system_knowledge,
+            system_monitor,
+            workload_classifier: workload_classifier::WorkloadClassifier::default(),
+            action_executor,
+            system_controller,
+            security_auditor: SecurityAuditor::new(),
+            integrity_issues: Vec::new(),
+            database,
+            on_ac_power: None,
+            storage_health: Vec::new(),
+            storage_checked_at: None,
+            pending_clarification: None,
+            pending_confirmation: None,
+            command_history: VecDeque::new(),
+            recent_recommendations: VecDeque::new(),
+        })
+    }
+    
+    fn load_command_history(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let history = self.database.command_history(COMMAND_HISTORY_LIMIT)?;
+        self.command_history = history.into_iter().rev()
+            .filter_map(|entry| serde_json::from_value(entry).ok())
+            .collect();
+        debug!("📚 Loaded {} commands of history", self.command_history.len());
+        Ok(())
+    }
+    
+    /// Where the app keeps the trained network between runs
+    pub fn default_neural_network_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(home).join(".local/share/ai-sysadmin-supreme/neural_network.bin")
+    }
+    
+    /// Persist learned state so training carries over. Owners call this on the way out;
+    /// nothing is written when the engine is merely dropped.
+    pub fn shutdown(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.neural_network.save(&self.neural_network_path)?;
+        info!("💾 AI Engine state saved");
+        Ok(())
+    }
+    
+    pub async fn initialize_with_system_context(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        info!("🔍 Learning current system context...");
+        
+        // Analyze current system state
+        let current_state = self.get_current_system_state().await?;
+        
+        // Preferences were loaded with the engine; commands are loaded here
+        self.load_command_history()?;
+        
+        // Initialize neural network with current context
+        self.neural_network.initialize_with_context(&current_state).await?;
+        
+        info!("✅ AI Engine initialized with system context");
+        Ok(())
+    }
+    
+    pub async fn process_natural_language(&mut self, input: &str) -> Result<String, Box<dyn std::error::Error>> {
+        debug!("🗣️ Processing natural language input: {}", input);
+        
+        // Parse the natural language input
+        let mut intent = self.nlp_processor.parse_intent(input).await?;
+        // What the learning sample is recorded against; a clarified request keeps its original wording
+        let mut learned_input = input.to_string();
+        
+        if let Some((original_input, pending)) = self.pending_clarification.take() {
+            if self.nlp_processor.is_cancellation(input) {
+                return Ok("👍 Okay, I won't do anything.".to_string());
+            }
+            if let Some(chosen) = self.nlp_processor.resolve_clarification(input, &pending, &intent) {
+                debug!("🎯 Clarified '{}' as {}", original_input, chosen.action);
+                intent = chosen;
+                learned_input = original_input;
+            }
+        }
+        
+        // Don't guess at ambiguous requests; nothing is run or learned until the user picks
+        if self.nlp_processor.needs_clarification(&intent) {
+            let question = self.nlp_processor.clarification_question(&intent);
+            self.pending_clarification = Some((input.to_string(), intent));
+            return Ok(question);
+        }
+        
+        // Status questions are answered from live data without running anything
+        if matches!(intent.action.as_str(), "status_summary" | "explain_temperature") {
+            let system_state = self.get_current_system_state().await?;
+            let context = self.status_context().await;
+            return Ok(self.nlp_processor.generate_status_response(&intent.action, &system_state, &context));
+        }
+        
+        if matches!(intent.action.as_str(), "repeat_last_action" | "undo_last_action") {
+            return self.handle_follow_up(input, &intent).await;
+        }
+        
+        // Get current system state for context
+        let system_state = self.get_current_system_state().await?;
+        
+        // Use decision engine to determine appropriate action
+        let decision = self.decision_engine.decide_action(&intent, &system_state).await?;
+        
+        // Carry the action out; destructive ones wait for execute_confirmed_action
+        let result = if self.action_executor.handles(&decision.action) {
+            Some(self.action_executor.execute(&decision.action, &decision.parameters, false).await)
+        } else {
+            None
+        };
+        
+        // Generate natural language response
+        let mut response = self.nlp_processor.generate_response(&decision.action, &system_state).await?;
+        
+        // Nothing ran yet; the confirmation path learns from the real outcome
+        if result.as_ref().map(|result| result.requires_confirmation).unwrap_or(false) {
+            self.pending_confirmation = Some(PendingConfirmation { input: learned_input, intent, action: decision.action.clone(), decision: Some(decision) });
+        } else {
+            // Learn from what actually happened
+            let outcome = match &result {
+                Some(result) => result.outcome(),
+                // Advice and queries: nothing ran, so nothing to learn either way
+                None => ActionOutcome::Partial("Nothing to execute".to_string()),
+            };
+            if let Some(result) = &result {
+                self.decision_engine.record_decision_outcome(decision.clone(), result.success).await?;
+            }
+            self.learn_from_interaction(&learned_input, &intent, &decision.action, outcome.clone()).await?;
+            self.record_command(CommandRecord {
+                timestamp: Utc::now(),
+                input: learned_input,
+                intent,
+                action: decision.action.clone(),
+                parameters: decision.parameters.clone(),
+                outcome,
+            });
+        }
+        
+        if let Some(result) = result {
+            response.push_str(&result.describe());
+        }
+        
+        Ok(response)
+    }
+    
+    /// "do that again" re-runs the previous command's action; "undo that" reverts it
+    async fn handle_follow_up(&mut self, input: &str, intent: &natural_language::Intent) -> Result<String, Box<dyn std::error::Error>> {
+        let undo = intent.action == "undo_last_action";
+        let previous = match self.command_history.back() {
+            Some(previous) => previous.clone(),
+            None => return Ok(format!("🤷 There's no earlier command to {}.", if undo { "undo" } else { "repeat" })),
+        };
+        
+        let (action, parameters) = if undo {
+            if previous.action == "revert_system_changes" || previous.parameters.contains_key(custom_actions::REVERT_PARAMETER) {
+                return Ok("↩️ The last command was already an undo, there's nothing newer to revert.".to_string());
+            }
+            if !matches!(previous.outcome, ActionOutcome::Success) {
+                return Ok(format!("↩️ '{}' didn't complete, so there's nothing to undo.", previous.input));
+            }
+            let custom_reversible = self.action_executor.custom_actions().get(&previous.action)
+                .map(|custom| custom.reversible());
+            match custom_reversible {
+                // Custom actions ship their own revert script
+                Some(true) => (
+                    previous.action.clone(),
+                    HashMap::from([(custom_actions::REVERT_PARAMETER.to_string(), "true".to_string())]),
+                ),
+                Some(false) => return Ok(format!("↩️ '{}' has no revert script, so I can't undo it.", previous.action)),
+                None if !REVERTIBLE_ACTIONS.contains(&previous.action.as_str()) => {
+                    return Ok(format!("↩️ I can't undo '{}' ({}).", previous.input, previous.action));
+                }
+                // Reverts every tuning change still applied, not only the last one
+                None => ("revert_system_changes".to_string(), HashMap::new()),
+            }
+        } else {
+            (previous.action.clone(), previous.parameters.clone())
+        };
+        
+        let result = self.action_executor.execute(&action, &parameters, false).await;
+        
+        if result.requires_confirmation {
+            self.pending_confirmation = Some(PendingConfirmation { input: input.to_string(), intent: intent.clone(), action, decision: None });
+        } else {
+            let outcome = result.outcome();
+            self.learn_from_interaction(input, intent, &action, outcome.clone()).await?;
+            self.record_command(CommandRecord {
+                timestamp: Utc::now(),
+                input: input.to_string(),
+                intent: intent.clone(),
+                action,
+                parameters,
+                outcome,
+            });
+        }
+        
+        let summary = if undo {
+            format!("↩️ Undoing '{}'.", previous.input)
+        } else {
+            format!("🔁 Running '{}' again.", previous.input)
+        };
+        Ok(format!("{}{}", summary, result.describe()))
+    }
+    
+    fn record_command(&mut self, record: CommandRecord) {
+        match serde_json::to_value(&record) {
+            Ok(entry) => {
+                if let Err(e) = self.database.record_command(&entry, COMMAND_HISTORY_LIMIT) {
+                    warn!("Failed to persist command history: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize command history entry: {}", e),
+        }
+        
+        self.command_history.push_back(record);
+        while self.command_history.len() > COMMAND_HISTORY_LIMIT {
+            self.command_history.pop_front();
+        }
+    }
+    
+    /// Newest first
+    pub fn get_command_history(&self, limit: usize) -> Vec<CommandRecord> {
+        self.command_history.iter().rev().take(limit).cloned().collect()
+    }
+    
+    /// Run an action the user has explicitly confirmed, including destructive ones
+    pub async fn execute_confirmed_action(&mut self, action: &str, parameters: HashMap<String, String>) -> Result<action_executor::ActionResult, Box<dyn std::error::Error>> {
+        let result = self.action_executor.execute(action, &parameters, true).await;
+        let outcome = result.outcome();
+        
+        // The command that asked for it is learned from and recorded now that it ran
+        match self.pending_confirmation.take() {
+            Some(pending) if pending.action == action => {
+                if let Some(decision) = pending.decision {
+                    self.decision_engine.record_decision_outcome(decision, result.success).await?;
+                }
+                self.learn_from_interaction(&pending.input, &pending.intent, action, outcome.clone()).await?;
+                self.record_command(CommandRecord {
+                    timestamp: Utc::now(),
+                    input: pending.input,
+                    intent: pending.intent,
+                    action: action.to_string(),
+                    parameters,
+                    outcome,
+                });
+            }
+            _ => {
+                self.learn_from_user_action(UserAction {
+                    timestamp: Utc::now(),
+                    action_type: action.to_string(),
+                    context: "confirmed_action".to_string(),
+                    parameters,
+                    outcome,
+                }).await?;
+            }
+        }
+        
+        Ok(result)
+    }
+    
+    /// Measure what `profile` does on this machine and remember the result, so later
+    /// recommendations can tell profiles that help from ones that don't
+    pub async fn benchmark_profile(&mut self, profile: &str, suite: crate::system::benchmark::BenchmarkSuite) -> Result<crate::system::benchmark::BenchmarkComparison, Box<dyn std::error::Error>> {
+        let comparison = self.system_controller.lock().await.benchmark_profile(profile, suite).await?;
+        
+        let confidence = (comparison.deltas.len() as f64 / 4.0).min(1.0);
+        self.database.record_pattern(&format!("{}{}", BENCHMARK_PATTERN_PREFIX, profile), comparison.average_delta(), confidence)?;
+        if let Some(watts) = comparison.power_delta_watts {
+            self.database.record_pattern(&format!("{}{}", BENCHMARK_POWER_PATTERN_PREFIX, profile), watts, confidence)?;
+        }
+        Ok(comparison)
+    }
+    
+    /// Average benchmark change (%) measured for each profile
+    pub fn profile_benchmark_gains(&self) -> HashMap<String, f64> {
+        self.profile_benchmark_patterns(BENCHMARK_PATTERN_PREFIX)
+    }
+    
+    /// Extra watts (negative for savings) measured for each profile while benchmarking
+    pub fn profile_power_costs(&self) -> HashMap<String, f64> {
+        self.profile_benchmark_patterns(BENCHMARK_POWER_PATTERN_PREFIX)
+    }
+    
+    fn profile_benchmark_patterns(&self, prefix: &str) -> HashMap<String, f64> {
+        match self.database.latest_patterns(prefix) {
+            Ok(patterns) => patterns.into_iter()
+                .map(|(name, value)| (name.trim_start_matches(prefix).to_string(), value))
+                .collect(),
+            Err(e) => {
+                warn!("Failed to read benchmark history: {}", e);
+                HashMap::new()
+            }
+        }
+    }
+    
+    /// Suggest balanced when the active profile was measured to cost a lot of
+    /// power for little speed, e.g. 40 W more for 5% faster inference
+    async fn profile_efficiency_recommendation(&self) -> Option<AIRecommendation> {
+        let profile = {
+            let active = self.system_controller.lock().await.performance_profile.clone();
+            match active {
+                PerformanceProfile::Gaming => "gaming",
+                PerformanceProfile::LLMInference => "ollama",
+                PerformanceProfile::Development => "development",
+                _ => return None,
+            }
+        };
+        // Benchmarks may have been run under either name for the LLM profile
+        let find = |values: HashMap<String, f64>| match profile {
+            "ollama" => values.get("ollama").or_else(|| values.get("llm")).copied(),
+            _ => values.get(profile).copied(),
+        };
+        let watts = find(self.profile_power_costs())?;
+        let gain = find(self.profile_benchmark_gains())?;
+        if watts < PROFILE_POWER_COST_WATTS || gain >= PROFILE_WORTHWHILE_GAIN_PERCENT {
+            return None;
+        }
+        
+        let label = match profile {
+            "ollama" => "LLM",
+            "gaming" => "Gaming",
+            _ => "Development",
+        };
+        let speedup = if gain > 0.0 { format!("only {:.0}% faster", gain) } else { "no faster".to_string() };
+        Some(AIRecommendation {
+            id: uuid::Uuid::new_v4().to_string(),
+            priority: 4,
+            title: format!("{} profile costs {:.0} W for little gain", label, watts),
+            description: format!("The {} profile draws {:.0} W more and benchmarked {} on this machine.", label, watts, speedup),
+            action: "switch_profile:balanced".to_string(),
+            confidence: 0.7,
+            reasoning: "Measured with the profile benchmark: CPU package power from RAPL plus GPU power, \
+                before and after applying the profile. The extra heat and fan noise buy little here.".to_string(),
+            estimated_impact: format!("Save about {:.0} W", watts),
+            relevant_logs: Vec::new(),
+        })
+    }
+    
+    /// Reload ~/.config/ai-sysadmin/actions after scripts were added or edited
+    pub fn reload_custom_actions(&mut self) {
+        self.action_executor.reload_custom_actions();
+        self.decision_engine.register_custom_actions(self.action_executor.custom_actions());
+    }
+    
+    /// Re-pick the CPU governor when the power source changes. The first call
+    /// only records the current source.
+    pub async fn handle_power_source_change(&mut self) -> Option<action_executor::ActionResult> {
+        let on_ac = SystemController::on_ac_power()?;
+        let previous = self.on_ac_power.replace(on_ac);
+        if previous.is_none() || previous == Some(on_ac) {
+            return None;
+        }
+        
+        info!("🔌 Power source changed to {}", if on_ac { "AC" } else { "battery" });
+        let result = self.action_executor.execute("set_governor_for_power_source", &HashMap::new(), false).await;
+        if !result.success {
+            warn!("Failed to adjust governor for power source: {}", result.message);
+        }
+        Some(result)
+    }
+    
+    pub async fn generate_proactive_recommendations(&mut self) -> Result<Vec<AIRecommendation>, Box<dyn std::error::Error>> {
+        debug!("🎯 Generating proactive recommendations...");
+        
+        self.handle_power_source_change().await;
+        
+        let current_state = self.get_current_system_state().await?;
+        let mut recommendations = Vec::new();
+        let thresholds = config::get().thresholds;
+        
+        // Analyze system performance
+        if current_state.cpu_usage > 80.0 {
+            recommendations.push(AIRecommendation {
+                id: uuid::Uuid::new_v4().to_string(),
+                priority: 8,
+                title: "High CPU Usage Detected".to_string(),
+                description: "CPU usage is above 80%. Consider optimizing running processes.".to_string(),
+                action: "optimize_cpu_usage".to_string(),
+                confidence: 0.9,
+                reasoning: "Sustained high CPU usage can impact system responsiveness and increase temperatures.".to_string(),
+                estimated_impact: "Improve system responsiveness by 15-25%".to_string(),
+                relevant_logs: Vec::new(),
+            });
+        }
+        
+        // Check memory usage patterns
+        let memory_pressured = memory_under_pressure(&current_state, &thresholds);
+        if memory_pressured {
+            let (description, reasoning) = match current_state.memory_pressure {
+                Some(stall) => (
+                    format!("Tasks spent {:.1}% of the last minute waiting on memory. Consider closing unused applications.", stall),
+                    "Sustained memory stalls mean the kernel is reclaiming or swapping constantly, which slows everything down.",
+                ),
+                None => (
+                    format!("Memory usage is above {:.0}%. Consider closing unused applications.", thresholds.memory_usage),
+                    "High memory usage can lead to swap usage and reduced performance.",
+                ),
+            };
+            recommendations.push(AIRecommendation {
+                id: uuid::Uuid::new_v4().to_string(),
+                priority: 7,
+                title: "High Memory Pressure".to_string(),
+                description,
+                action: "optimize_memory_usage".to_string(),
+                confidence: if current_state.memory_pressure.is_some() { 0.9 } else { 0.85 },
+                reasoning: reasoning.to_string(),
+                estimated_impact: "Free up 2-4GB of RAM".to_string(),
+                relevant_logs: self.relevant_log_lines("memory").await,
+            });
+        }
+        
+        // Running VMs compete with the host for memory and CPU
+        if memory_pressured || current_state.cpu_usage > thresholds.cpu_usage {
+            if let Some(rec) = self.vm_pressure_recommendation(&current_state) {
+                recommendations.push(rec);
+            }
+        }
+        
+        // Temperature monitoring for i9-13900HX
+        if current_state.temperature > self.system_knowledge.optimal_cpu_temps.1 {
+            recommendations.push(AIRecommendation {
+                id: uuid::Uuid::new_v4().to_string(),
+                priority: 9,
+                title: "CPU Temperature Warning".to_string(),
+                description: format!("CPU temperature is {}°C, above optimal range.", current_state.temperature),
+                action: "reduce_cpu_temperature".to_string(),
+                confidence: 0.95,
+                reasoning: "High temperatures can cause thermal throttling and reduce CPU performance.".to_string(),
+                estimated_impact: "Prevent thermal throttling and maintain performance".to_string(),
+                relevant_logs: self.relevant_log_lines("cpu_temperature").await,
+            });
+        }
+        
+        // GPU temperature matters more than CPU while gaming
+        let gpu_limit = self.system_knowledge.optimal_gpu_temps.1;
+        if current_state.gpu_temperature > gpu_limit {
+            recommendations.push(AIRecommendation {
+                id: uuid::Uuid::new_v4().to_string(),
+                priority: 9,
+                title: "GPU Temperature Warning".to_string(),
+                description: format!(
+                    "GPU temperature is {:.0}°C, above the {:.0}°C threshold. Lower the GPU power limit.",
+                    current_state.gpu_temperature, gpu_limit
+                ),
+                action: "reduce_gpu_power_limit".to_string(),
+                confidence: 0.9,
+                reasoning: "A lower power limit cuts GPU heat output quickly at a small cost in frame rate.".to_string(),
+                estimated_impact: "Lower GPU temperature by 5-10°C".to_string(),
+                relevant_logs: self.relevant_log_lines("gpu_temperature").await,
+            });
+        }
+        
+        // High-severity security findings, re-audited at most hourly
+        let security_report = self.security_auditor.cached_report(3600);
+        for finding in security_report.high_severity() {
+            recommendations.push(AIRecommendation {
+                id: uuid::Uuid::new_v4().to_string(),
+                priority: 8,
+                title: finding.title.clone(),
+                description: finding.description.clone(),
+                action: format!("apply_security_hardening:{}", finding.id),
+                confidence: 0.95,
+                reasoning: finding.remediation.clone(),
+                estimated_impact: "Close a security exposure".to_string(),
+                relevant_logs: Vec::new(),
+            });
+        }
+        
+        // Failing or worn-out drives, from SMART data refreshed at most hourly
+        let stale = self.storage_checked_at
+            .map(|checked| (Utc::now() - checked).num_seconds() >= STORAGE_HEALTH_INTERVAL_SECS)
+            .unwrap_or(true);
+        if stale {
+            self.storage_health = hardware::detect_storage_devices().await;
+            self.storage_checked_at = Some(Utc::now());
+        }
+        recommendations.extend(self.storage_health_recommendations(thresholds.ssd_wear_percent));
+        
+        // Packaged binaries that no longer match pacman's database
+        let mut tampered: HashMap<&str, Vec<String>> = HashMap::new();
+        for issue in self.integrity_issues.iter().filter(|i| i.is_suspicious()) {
+            tampered.entry(issue.package.as_str()).or_default().push(issue.path.display().to_string());
+        }
+        for (package, paths) in tampered {
+            recommendations.push(AIRecommendation {
+                id: uuid::Uuid::new_v4().to_string(),
+                priority: 10,
+                title: format!("Modified binaries in package {}", package),
+                description: format!("{} file(s) differ from the package database: {}", paths.len(), paths.join(", ")),
+                action: format!("reinstall_package:{}", package),
+                confidence: 0.8,
+                reasoning: "Packaged executables and libraries only change on upgrade; a checksum or size mismatch \
+                    can mean tampering or disk corruption. Reinstall the package and investigate if it recurs.".to_string(),
+                estimated_impact: "Restore trusted binaries".to_string(),
+                relevant_logs: Vec::new(),
+            });
+        }
+        
+        // Profiles that were measured to burn power without a matching speedup
+        if let Some(rec) = self.profile_efficiency_recommendation().await {
+            recommendations.push(rec);
+        }
+        
+        // User scripts whose suggest_when conditions hold, ranked by how they went before
+        for custom in self.action_executor.custom_actions().suggestions(&current_state) {
+            let preference = self.preferences.get(&custom.name);
+            recommendations.push(AIRecommendation {
+                id: uuid::Uuid::new_v4().to_string(),
+                priority: 5,
+                title: custom.description.clone(),
+                description: format!("Custom action '{}' matches the current system state.", custom.name),
+                action: custom.name.clone(),
+                confidence: 0.5 + preference * 0.4,
+                reasoning: format!("Suggested by the conditions in its manifest{}.", if custom.reversible() { "; can be undone" } else { "" }),
+                estimated_impact: "User-defined".to_string(),
+                relevant_logs: Vec::new(),
+            });
+        }
+        
+        // Pattern-based recommendations
+        let pattern_recs = self.pattern_recognition.generate_pattern_based_recommendations(&current_state).await?;
+        recommendations.extend(pattern_recs);
+        
+        // Sort by priority
+        recommendations.sort_by(|a, b| b.priority.cmp(&a.priority));
+        
+        // The same condition is recommended every cycle; keep only its latest instance
+        for recommendation in &recommendations {
+            self.recent_recommendations.retain(|previous| previous.title != recommendation.title);
+            self.recent_recommendations.push_back(recommendation.clone());
+        }
+        while self.recent_recommendations.len() > RECENT_RECOMMENDATIONS_LIMIT {
+            self.recent_recommendations.pop_front();
+        }
+        
+        Ok(recommendations)
+    }
+    
+    /// Everything needed for a support post in one Markdown or JSON document.
+    /// `redact` hides the hostname, username and process command lines.
+    pub async fn generate_system_report(&self, format: report::ReportFormat, redact: bool) -> String {
+        let (metrics, top_processes, gpus, recent_alerts) = {
+            let mut monitor = self.system_monitor.lock().await;
+            let metrics = match monitor.get_comprehensive_metrics().await {
+                Ok(metrics) => Some(metrics),
+                Err(e) => {
+                    warn!("System report without current metrics: {}", e);
+                    None
+                }
+            };
+            let mut processes = monitor.get_process_list().await;
+            processes.truncate(REPORT_PROCESSES);
+            (metrics, processes, monitor.get_gpu_info().await, monitor.get_recent_alerts(REPORT_ALERTS))
+        };
+        
+        let status = self.system_controller.lock().await.get_system_status().await;
+        let configuration = match status {
+            Ok(status) => status.into_iter().collect(),
+            Err(e) => {
+                warn!("System report without controller status: {}", e);
+                Default::default()
+            }
+        };
+        
+        let storage = if self.storage_health.is_empty() {
+            hardware::detect_storage_devices().await
+        } else {
+            self.storage_health.clone()
+        };
+        
+        let skip = self.recent_recommendations.len().saturating_sub(REPORT_RECOMMENDATIONS);
+        let recommendations = self.recent_recommendations.iter().skip(skip).rev().cloned().collect();
+        
+        let report = report::SystemReport::new(metrics, gpus, storage, configuration, top_processes, recent_alerts, recommendations);
+        info!("📋 Generated {:?} system report{}", format, if redact { " (redacted)" } else { "" });
+        report.render(format, redact)
+    }
+    
+    pub async fn learn_from_user_action(&mut self, action: UserAction) -> Result<(), Box<dyn std::error::Error>> {
+        debug!("📚 Learning from user action: {:?}", action.action_type);
+        
+        // Store the action
+        self.learned_patterns.push(action.clone());
+        
+        // Update neural network
+        self.neural_network.train_on_action(&action).await?;
+        
+        // Update pattern recognition
+        self.pattern_recognition.analyze_action(&action).await?;
+        
+        // Update the preference for the action that ran, however it was asked for
+        self.preferences.learn_from_user_action(&action);
+        
+        Ok(())
+    }
+    
+    /// Everything learned so far, for auditing or for `import_state` on another install
+    pub fn export_state(&self) -> state_export::AiStateExport {
+        state_export::AiStateExport {
+            exported_at: Utc::now(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            user_preferences: self.preferences.values().iter().map(|(key, value)| (key.clone(), *value)).collect(),
+            patterns: self.pattern_recognition.patterns().to_vec(),
+            pattern_weights: self.pattern_recognition.pattern_weights().iter().map(|(id, weight)| (id.clone(), *weight)).collect(),
+            pattern_statistics: self.pattern_recognition.get_pattern_statistics().into_iter().collect(),
+            learned_actions: self.learned_patterns.clone(),
+            recent_recommendations: self.recent_recommendations.iter().cloned().collect(),
+        }
+    }
+    
+    /// Replace learned preferences, patterns and actions with `state`, e.g. a
+    /// curated export. Preferences not in `state` are forgotten, in the database too;
+    /// statistics and recommendations are derived and left alone.
+    pub fn import_state(&mut self, state: state_export::AiStateExport) -> Result<(), Box<dyn std::error::Error>> {
+        // Preferences first: they can be rejected, and a failed write leaves the running state as it was
+        self.preferences.replace(state.user_preferences.into_iter().collect())?;
+        self.pattern_recognition.replace_patterns(state.patterns, state.pattern_weights.into_iter().collect());
+        self.learned_patterns = state.learned_actions;
+        
+        info!("📥 Imported AI state from {}: {} preferences, {} patterns, {} learned actions",
+            state.exported_at.format("%Y-%m-%d %H:%M"), self.preferences.values().len(),
+            self.pattern_recognition.patterns().len(), self.learned_patterns.len());
+        Ok(())
+    }
+    
+    /// Temperature above which the GPU warning recommendation fires
+    /// Suggest pausing the largest running VM when the host is starved
+    fn vm_pressure_recommendation(&self, state: &SystemState) -> Option<AIRecommendation> {
+        let vms = match LibvirtClient::new().list_vms() {
+            Ok(vms) => vms,
+            Err(e) => {
+                debug!("Skipping VM pressure check: {}", e);
+                return None;
+            }
+        };
+        
+        let vm = vms.into_iter()
+            .filter(|vm| vm.is_running())
+            .max_by_key(|vm| vm.memory_mb)?;
+        
+        let memory_note = match &vm.hugepages {
+            Some(hugepages) => format!(
+                "Its {} MB is backed by {} hugepages ({} KB), which stay reserved until it shuts down.",
+                vm.memory_mb, hugepages.count, hugepages.page_size_kb
+            ),
+            None => format!("It holds up to {} MB of host memory.", vm.memory_mb),
+        };
+        
+        Some(AIRecommendation {
+            id: uuid::Uuid::new_v4().to_string(),
+            priority: 7,
+            title: format!("Pause VM '{}'", vm.name),
+            description: format!(
+                "Host is under pressure (CPU {:.0}%, memory {:.0}%) while VM '{}' runs with {} vCPUs.",
+                state.cpu_usage, state.memory_usage, vm.name, vm.vcpus
+            ),
+            action: "pause_vm".to_string(),
+            confidence: 0.7,
+            reasoning: format!(
+                "Pausing stops the guest competing for CPU immediately; shutting it down also returns its memory. {}",
+                memory_note
+            ),
+            estimated_impact: format!("Free {} vCPUs, up to {} MB after shutdown", vm.vcpus, vm.memory_mb),
+            relevant_logs: Vec::new(),
+        })
+    }
+    
+    /// Journal lines around the latest monitor alert of `kind`
+    async fn relevant_log_lines(&self, kind: &str) -> Vec<String> {
+        let monitor = self.system_monitor.lock().await;
+        monitor.relevant_log_lines(kind, 5)
+    }
+    
+    fn storage_health_recommendations(&self, wear_threshold: f64) -> Vec<AIRecommendation> {
+        let mut recommendations = Vec::new();
+        
+        for device in &self.storage_health {
+            let smart = match &device.smart {
+                Some(smart) => smart,
+                None => continue,
+            };
+            
+            if !smart.passed {
+                recommendations.push(AIRecommendation {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    priority: 10,
+                    title: format!("Drive {} is failing", device.device_name),
+                    description: format!(
+                        "{} reports SMART overall health FAILING ({} reallocated sectors, {} media errors). Back it up now.",
+                        device.device_name,
+                        smart.reallocated_sectors.map(|n| n.to_string()).unwrap_or_else(|| "?".to_string()),
+                        smart.media_errors.map(|n| n.to_string()).unwrap_or_else(|| "?".to_string()),
+                    ),
+                    action: format!("backup_and_replace_drive:{}", device.device_name),
+                    confidence: 0.95,
+                    reasoning: "A drive that fails its own SMART self-assessment can stop working at any time.".to_string(),
+                    estimated_impact: "Avoid data loss".to_string(),
+                    relevant_logs: Vec::new(),
+                });
+            } else if let Some(used) = smart.percentage_used.filter(|used| *used as f64 >= wear_threshold) {
+                recommendations.push(AIRecommendation {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    priority: 9,
+                    title: format!("Drive {} is {}% worn", device.device_name, used),
+                    description: format!(
+                        "{} has used {}% of its rated write endurance ({} power-on hours). Plan a replacement and keep backups current.",
+                        device.device_name, used,
+                        smart.power_on_hours.map(|h| h.to_string()).unwrap_or_else(|| "?".to_string()),
+                    ),
+                    action: format!("backup_and_replace_drive:{}", device.device_name),
+                    confidence: 0.85,
+                    reasoning: "Flash cells wear out with writes; past rated endurance, errors and read-only mode become likely.".to_string(),
+                    estimated_impact: "Replace the drive before it fails".to_string(),
+                    relevant_logs: Vec::new(),
+                });
+            }
+        }
+        
+        recommendations
+    }
+    
+    /// Results of the last `pacman -Qkk` run, turned into recommendations
+    pub fn record_integrity_check(&mut self, issues: Vec<FileIntegrityIssue>) {
+        self.integrity_issues = issues;
+    }
+    
+    pub fn set_gpu_temperature_threshold(&mut self, max_celsius: f64) {
+        self.system_knowledge.optimal_gpu_temps.1 = max_celsius;
+        self.decision_engine.set_gpu_temperature_limit(max_celsius);
+    }
+    
+    pub fn set_workload_classifier(&mut self, classifier: workload_classifier::WorkloadClassifier) {
+        self.workload_classifier = classifier;
+    }
+    
+    async fn get_current_system_state(&self) -> Result<SystemState, Box<dyn std::error::Error>> {
+        let (metrics, processes) = {
+            let mut monitor = self.system_monitor.lock().await;
+            let metrics = monitor.get_comprehensive_metrics().await?;
+            let processes = monitor.get_process_list().await;
+            (metrics, processes)
+        };
+        
+        // Top processes by CPU, one entry per program name
+        let mut active_processes: Vec<String> = Vec::new();
+        for process in processes.iter().take(25) {
+            if !active_processes.contains(&process.name) {
+                active_processes.push(process.name.clone());
+            }
+        }
+        
+        let now = Local::now();
+        Ok(SystemState {
+            cpu_usage: metrics.cpu_usage,
+            memory_usage: metrics.memory_usage,
+            disk_usage: metrics.disk_usage,
+            temperature: metrics.temperature,
+            gpu_temperature: metrics.gpu_temp as f64,
+            memory_pressure: metrics.pressure.memory_stall_percent(),
+            active_processes,
+            current_workload: self.workload_classifier.classify(&processes),
+            time_of_day: now.hour() as u8,
+            day_of_week: now.weekday().num_days_from_monday() as u8,
+        })
+    }
+    
+    /// Alerts, busiest processes, thermal sensors and the recent trend for status answers
+    async fn status_context(&mut self) -> natural_language::StatusContext {
+        let hour_ago = (Utc::now().timestamp() - 3600).max(0) as u64;
+        let mut context = {
+            let monitor = self.system_monitor.lock().await;
+            let processes = monitor.get_process_list().await;
+            let history = monitor.get_historical_data(STATUS_TREND_SAMPLES);
+            natural_language::StatusContext {
+                recent_alerts: monitor.get_recent_alerts(10).into_iter()
+                    .filter(|alert| alert.timestamp >= hour_ago)
+                    .map(|alert| alert.message)
+                    .collect(),
+                top_processes: processes.iter().take(5)
+                    .map(|process| (process.name.clone(), process.cpu_usage, process.memory_percent))
+                    .collect(),
+                top_recommendation: None,
+                thermal_sensors: monitor.get_thermal_zones().await.into_iter()
+                    .map(|zone| (zone.sensor_type, zone.temperature, zone.critical_temp))
+                    .collect(),
+                cpu_trend: history.iter().map(|metrics| metrics.cpu_usage).collect(),
+                temperature_trend: history.iter().map(|metrics| metrics.temperature).collect(),
+            }
+        };
+        
+        context.top_recommendation = match self.generate_proactive_recommendations().await {
+            Ok(recommendations) => recommendations.into_iter()
+                .max_by_key(|recommendation| recommendation.priority)
+                .map(|recommendation| recommendation.title),
+            Err(e) => {
+                debug!("No recommendation for status answer: {}", e);
+                None
+            }
+        };
+        context
+    }
+    
+    async fn learn_from_interaction(&mut self, input: &str, intent: &natural_language::Intent, action: &str, outcome: ActionOutcome) -> Result<(), Box<dyn std::error::Error>> {
+        // Create user action record
+        let user_action = UserAction {
+            timestamp: Utc::now(),
+            action_type: "natural_language_command".to_string(),
+            context: input.to_string(),
+            parameters: preferences::interaction_parameters(intent, action),
+            outcome,
+        };
+        
+        self.learn_from_user_action(user_action).await?;
+        Ok(())
+    }
+}
+
+/// Sustained memory stalls when the kernel reports PSI, otherwise percent used.
+/// A nearly full page cache is normal and cheap to reclaim; stalls are not.
+fn memory_under_pressure(state: &SystemState, thresholds: &config::ThresholdConfig) -> bool {
+    match state.memory_pressure {
+        Some(stall) => stall > thresholds.memory_pressure,
+        None => state.memory_usage > thresholds.memory_usage,
+    }
+}
@@ -4,14 +4,20 @@
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
-use tracing::{info, warn, debug};
-use crate::database::Database;
 
+pub mod action_executor;
+pub mod assistant;
+pub mod custom_actions;
+pub mod decision_engine;
+pub mod natural_language;
 pub mod neural_network;
 pub mod pattern_recognition;
-pub mod natural_language;
-pub mod decision_engine;
+pub mod preferences;
+pub mod workload_classifier;
+
+// The rest of the engine (engine.rs and the models it drives) is declared here
+// as each part builds against the compiled modules; engine.rs itself still needs
+// monitoring_system and system.
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserAction {
@@ -35,13 +41,17 @@ pub struct SystemState {
     pub memory_usage: f64,
     pub disk_usage: f64,
     pub temperature: f64,
+    pub gpu_temperature: f64,
+    /// Memory PSI "some" avg60 (%); None when the kernel doesn't expose PSI
+    #[serde(default)]
+    pub memory_pressure: Option<f64>,
     pub active_processes: Vec<String>,
     pub current_workload: WorkloadType,
     pub time_of_day: u8, // 0-23
     pub day_of_week: u8, // 0-6
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum WorkloadType {
     Gaming,
     Development,
@@ -60,235 +70,7 @@ pub struct AIRecommendation {
     pub confidence: f64,
     pub reasoning: String,
     pub estimated_impact: String,
-}
-
-pub struct AIEngine {
-    neural_network: neural_network::NeuralNetwork,
-    pattern_recognition: pattern_recognition::PatternRecognizer,
-    nlp_processor: natural_language::NLPProcessor,
-    decision_engine: decision_engine::DecisionEngine,
-    user_preferences: HashMap<String, f64>,
-    learned_patterns: Vec<UserAction>,
-    system_knowledge: SystemKnowledge,
-}
-
-#[derive(Debug)]
-struct SystemKnowledge {
-    // Hardware-specific knowledge for i9-13900HX
-    optimal_cpu_temps: (f64, f64), // (min, max) for optimal performance
-    memory_usage_patterns: HashMap<String, f64>,
-    disk_io_patterns: HashMap<String, f64>,
-    
-    // Software patterns for Garuda Linux
-    package_update_frequency: HashMap<String, u32>,
-    system_maintenance_schedule: Vec<String>,
-    
-    // User-specific patterns (learned over time)
-    daily_usage_patterns: HashMap<u8, WorkloadType>, // hour -> typical workload
-    application_preferences: HashMap<String, f64>,
-    optimization_preferences: HashMap<String, bool>,
-}
-
-impl AIEngine {
-    pub async fn new_for_i9_13900hx() -> Result<Self, Box<dyn std::error::Error>> {
-        info!("🧠 Initializing AI Engine for i9-13900HX...");
-        
-        // Initialize components
-        let neural_network = neural_network::NeuralNetwork::new_for_sysadmin().await?;
-        let pattern_recognition = pattern_recognition::PatternRecognizer::new().await?;
-        let nlp_processor = natural_language::NLPProcessor::new_with_sysadmin_vocab().await?;
-        let decision_engine = decision_engine::DecisionEngine::new().await?;
-        
-        // Initialize system knowledge with hardware-specific data
-        let system_knowledge = SystemKnowledge {
-            // i9-13900HX optimal operating ranges
-            optimal_cpu_temps: (65.0, 85.0), // Celsius
-            memory_usage_patterns: HashMap::new(),
-            disk_io_patterns: HashMap::new(),
-            
-            // Garuda Linux specific
-            package_update_frequency: HashMap::new(),
-            system_maintenance_schedule: vec![
-                "Daily: Clear package cache".to_string(),
-                "Weekly: Update system".to_string(),
-                "Monthly: Clean logs".to_string(),
-            ],
-            
-            // Will be learned over time
-            daily_usage_patterns: HashMap::new(),
-            application_preferences: HashMap::new(),
-            optimization_preferences: HashMap::new(),
-        };
-        
-        Ok(Self {
-            neural_network,
-            pattern_recognition,
-            nlp_processor,
-            decision_engine,
-            user_preferences: HashMap::new(),
-            learned_patterns: Vec::new(),
-            system_knowledge,
-        })
-    }
-    
-    pub async fn initialize_with_system_context(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        info!("🔍 Learning current system context...");
-        
-        // Analyze current system state
-        let current_state = self.get_current_system_state().await?;
-        
-        // Load existing patterns from database
-        // self.load_learned_patterns().await?;
-        
-        // Initialize neural network with current context
-        self.neural_network.initialize_with_context(&current_state).await?;
-        
-        info!("✅ AI Engine initialized with system context");
-        Ok(())
-    }
-    
-    pub async fn process_natural_language(&mut self, input: &str) -> Result<String, Box<dyn std::error::Error>> {
-        debug!("🗣️ Processing natural language input: {}", input);
-        
-        // Parse the natural language input
-        let intent = self.nlp_processor.parse_intent(input).await?;
-        
-        // Get current system state for context
-        let system_state = self.get_current_system_state().await?;
-        
-        // Use decision engine to determine appropriate action
-        let action = self.decision_engine.decide_action(&intent, &system_state).await?;
-        
-        // Learn from this interaction
-        self.learn_from_interaction(input, &action).await?;
-        
-        // Generate natural language response
-        let response = self.nlp_processor.generate_response(&action, &system_state).await?;
-        
-        Ok(response)
-    }
-    
-    pub async fn generate_proactive_recommendations(&mut self) -> Result<Vec<AIRecommendation>, Box<dyn std::error::Error>> {
-        debug!("🎯 Generating proactive recommendations...");
-        
-        let current_state = self.get_current_system_state().await?;
-        let mut recommendations = Vec::new();
-        
-        // Analyze system performance
-        if current_state.cpu_usage > 80.0 {
-            recommendations.push(AIRecommendation {
-                id: uuid::Uuid::new_v4().to_string(),
-                priority: 8,
-                title: "High CPU Usage Detected".to_string(),
-                description: "CPU usage is above 80%. Consider optimizing running processes.".to_string(),
-                action: "optimize_cpu_usage".to_string(),
-                confidence: 0.9,
-                reasoning: "Sustained high CPU usage can impact system responsiveness and increase temperatures.".to_string(),
-                estimated_impact: "Improve system responsiveness by 15-25%".to_string(),
-            });
-        }
-        
-        // Check memory usage patterns
-        if current_state.memory_usage > 85.0 {
-            recommendations.push(AIRecommendation {
-                id: uuid::Uuid::new_v4().to_string(),
-                priority: 7,
-                title: "High Memory Usage".to_string(),
-                description: "Memory usage is above 85%. Consider closing unused applications.".to_string(),
-                action: "optimize_memory_usage".to_string(),
-                confidence: 0.85,
-                reasoning: "High memory usage can lead to swap usage and reduced performance.".to_string(),
-                estimated_impact: "Free up 2-4GB of RAM".to_string(),
-            });
-        }
-        
-        // Temperature monitoring for i9-13900HX
-        if current_state.temperature > self.system_knowledge.optimal_cpu_temps.1 {
-            recommendations.push(AIRecommendation {
-                id: uuid::Uuid::new_v4().to_string(),
-                priority: 9,
-                title: "CPU Temperature Warning".to_string(),
-                description: format!("CPU temperature is {}°C, above optimal range.", current_state.temperature),
-                action: "reduce_cpu_temperature".to_string(),
-                confidence: 0.95,
-                reasoning: "High temperatures can cause thermal throttling and reduce CPU performance.".to_string(),
-                estimated_impact: "Prevent thermal throttling and maintain performance".to_string(),
-            });
-        }
-        
-        // Pattern-based recommendations
-        let pattern_recs = self.pattern_recognition.generate_pattern_based_recommendations(&current_state).await?;
-        recommendations.extend(pattern_recs);
-        
-        // Sort by priority
-        recommendations.sort_by(|a, b| b.priority.cmp(&a.priority));
-        
-        Ok(recommendations)
-    }
-    
-    pub async fn learn_from_user_action(&mut self, action: UserAction) -> Result<(), Box<dyn std::error::Error>> {
-        debug!("📚 Learning from user action: {:?}", action.action_type);
-        
-        // Store the action
-        self.learned_patterns.push(action.clone());
-        
-        // Update neural network
-        self.neural_network.train_on_action(&action).await?;
-        
-        // Update pattern recognition
-        self.pattern_recognition.analyze_action(&action).await?;
-        
-        // Update user preferences based on action outcome
-        match &action.outcome {
-            ActionOutcome::Success => {
-                // Increase preference for this type of action
-                let pref_key = format!("{}_{}", action.action_type, action.context);
-                let current_pref = self.user_preferences.get(&pref_key).unwrap_or(&0.5);
-                self.user_preferences.insert(pref_key, (current_pref + 0.1).min(1.0));
-            },
-            ActionOutcome::Failed(_) => {
-                // Decrease preference for this type of action
-                let pref_key = format!("{}_{}", action.action_type, action.context);
-                let current_pref = self.user_preferences.get(&pref_key).unwrap_or(&0.5);
-                self.user_preferences.insert(pref_key, (current_pref - 0.1).max(0.0));
-            },
-            ActionOutcome::Partial(_) => {
-                // Slight adjustment
-                let pref_key = format!("{}_{}", action.action_type, action.context);
-                let current_pref = self.user_preferences.get(&pref_key).unwrap_or(&0.5);
-                self.user_preferences.insert(pref_key, (current_pref + 0.05).min(1.0));
-            }
-        }
-        
-        Ok(())
-    }
-    
-    async fn get_current_system_state(&self) -> Result<SystemState, Box<dyn std::error::Error>> {
-        // This would integrate with system monitoring
-        // For now, returning mock data
-        Ok(SystemState {
-            cpu_usage: 45.0,
-            memory_usage: 65.0,
-            disk_usage: 75.0,
-            temperature: 72.0,
-            active_processes: vec!["firefox".to_string(), "vscode".to_string()],
-            current_workload: WorkloadType::Development,
-            time_of_day: chrono::Utc::now().hour() as u8,
-            day_of_week: chrono::Utc::now().weekday().number_from_monday() as u8 - 1,
-        })
-    }
-    
-    async fn learn_from_interaction(&mut self, input: &str, action: &str) -> Result<(), Box<dyn std::error::Error>> {
-        // Create user action record
-        let user_action = UserAction {
-            timestamp: Utc::now(),
-            action_type: "natural_language_command".to_string(),
-            context: input.to_string(),
-            parameters: HashMap::new(),
-            outcome: ActionOutcome::Success, // Will be updated based on actual execution
-        };
-        
-        self.learn_from_user_action(user_action).await?;
-        Ok(())
-    }
+    /// Journal lines logged around the condition that triggered this
+    #[serde(default)]
+    pub relevant_logs: Vec<String>,
 }
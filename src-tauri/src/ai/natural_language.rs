@@ -3,17 +3,29 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tracing::{info, debug, warn};
 use crate::ai::{SystemState, WorkloadType};
+use crate::config;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Intent {
     pub category: IntentCategory,
     pub action: String,
+    /// Slots filled from the input, e.g. "governor" or "package"
     pub parameters: HashMap<String, String>,
+    /// Lowered when other actions matched almost as well
     pub confidence: f64,
     pub entities: Vec<Entity>,
+    /// Other actions that matched, best first
+    #[serde(default)]
+    pub alternatives: Vec<Intent>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Below this, `process_natural_language` asks which action was meant instead of acting
+pub const CLARIFICATION_THRESHOLD: f64 = 0.6;
+
+/// Runner-up actions scoring within this of the best one make the input ambiguous
+const AMBIGUITY_MARGIN: f64 = 0.1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum IntentCategory {
     SystemOptimization,
     FileManagement,
@@ -34,7 +46,7 @@ pub struct Entity {
     pub end_pos: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EntityType {
     Number,
     Percentage,
@@ -46,12 +58,58 @@ pub enum EntityType {
     Action,
 }
 
+/// Settings for the optional local-LLM intent parser (Ollama `/api/chat`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmBackendConfig {
+    pub enabled: bool,
+    pub base_url: String,
+    pub model: String,
+    pub timeout_secs: u64,
+}
+
+impl Default for LlmBackendConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: config::get().ollama.base_url(),
+            model: "llama3.1:8b".to_string(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Live data behind status answers, gathered by the AI engine at question time
+#[derive(Debug, Clone, Default)]
+pub struct StatusContext {
+    /// Messages of alerts raised in the last hour, oldest first
+    pub recent_alerts: Vec<String>,
+    /// (name, CPU %, memory %) of the busiest processes
+    pub top_processes: Vec<(String, f32, f32)>,
+    /// Title of the highest-priority recommendation
+    pub top_recommendation: Option<String>,
+    /// (sensor, °C, critical °C) from the thermal zones
+    pub thermal_sensors: Vec<(String, f32, f32)>,
+    /// Recent CPU usage and temperature samples, oldest first
+    pub cpu_trend: Vec<f64>,
+    pub temperature_trend: Vec<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LlmIntent {
+    action: String,
+    #[serde(default)]
+    parameters: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    confidence: Option<f64>,
+}
+
 pub struct NLPProcessor {
     intent_patterns: HashMap<IntentCategory, Vec<IntentPattern>>,
     entity_extractors: HashMap<EntityType, Regex>,
     response_templates: HashMap<String, Vec<String>>,
     conversation_context: ConversationContext,
     system_vocabulary: SystemVocabulary,
+    llm_backend: LlmBackendConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -116,6 +174,7 @@ impl NLPProcessor {
                 ],
                 synonyms: HashMap::new(),
             },
+            llm_backend: LlmBackendConfig::default(),
         };
         
         processor.initialize_intent_patterns().await?;
@@ -306,6 +365,14 @@ impl NLPProcessor {
         // Maintenance patterns
         let mut maintenance_patterns = Vec::new();
         
+        // Before clean_system, so "clean system logs" vacuums the journal
+        maintenance_patterns.push(IntentPattern {
+            pattern: Regex::new(r"(?i)(clean|cleanup|vacuum|trim).*?(logs?|journal)")?,
+            action: "clean_logs".to_string(),
+            confidence: 0.9,
+            parameter_extractors: HashMap::new(),
+        });
+        
         maintenance_patterns.push(IntentPattern {
             pattern: Regex::new(r"(?i)(clean|cleanup).*?(system)")?,
             action: "clean_system".to_string(),
@@ -332,6 +399,20 @@ impl NLPProcessor {
             parameter_extractors: HashMap::new(),
         });
         
+        query_patterns.push(IntentPattern {
+            pattern: Regex::new(r"(?i)(how('s| is| are)\b.*?(system|machine|computer|laptop|pc|things|everything)|system (health|status report)|status report|everything (ok|okay|alright|fine))")?,
+            action: "status_summary".to_string(),
+            confidence: 0.92,
+            parameter_extractors: HashMap::new(),
+        });
+        
+        query_patterns.push(IntentPattern {
+            pattern: Regex::new(r"(?i)(why|how come).*?(hot|warm|heat|temperature|temp\b|fans?\b|loud|throttl)")?,
+            action: "explain_temperature".to_string(),
+            confidence: 0.93,
+            parameter_extractors: HashMap::new(),
+        });
+        
         query_patterns.push(IntentPattern {
             pattern: Regex::new(r"(?i)(what.*?should.*?i|recommend|suggest)")?,
             action: "provide_recommendation".to_string(),
@@ -341,6 +422,25 @@ impl NLPProcessor {
         
         self.intent_patterns.insert(IntentCategory::Query, query_patterns);
         
+        // Follow-ups on the previous command; anchored so "update again" stays an update
+        let mut conversation_patterns = Vec::new();
+        
+        conversation_patterns.push(IntentPattern {
+            pattern: Regex::new(r"(?i)^\s*(do (that|it|this) again|repeat( that| it| the last (action|command))?|same again|once more|again)\s*[.!?]*\s*$")?,
+            action: "repeat_last_action".to_string(),
+            confidence: 0.95,
+            parameter_extractors: HashMap::new(),
+        });
+        
+        conversation_patterns.push(IntentPattern {
+            pattern: Regex::new(r"(?i)^\s*(undo|revert|reverse|roll ?back)( that| it| this| the last (action|command)| what you (just )?did)?\s*[.!?]*\s*$")?,
+            action: "undo_last_action".to_string(),
+            confidence: 0.95,
+            parameter_extractors: HashMap::new(),
+        });
+        
+        self.intent_patterns.insert(IntentCategory::Conversation, conversation_patterns);
+        
         Ok(())
     }
     
@@ -378,7 +478,7 @@ impl NLPProcessor {
         
         self.response_templates.insert("optimize_memory".to_string(), vec![
             "🧠 Freeing up memory to improve system responsiveness...".to_string(),
-            "💾 Optimizing RAM usage across your 64GB system...".to_string(),
+            format!("💾 Optimizing RAM usage across your {}GB system...", config::get().hardware.total_memory_gb()),
             "🗑️ Clearing unnecessary memory usage...".to_string(),
         ]);
         
@@ -388,6 +488,11 @@ impl NLPProcessor {
             "✨ Cleaning up your Garuda Linux system...".to_string(),
         ]);
         
+        self.response_templates.insert("clean_logs".to_string(), vec![
+            "📜 Vacuuming old journal logs...".to_string(),
+            "🗑️ Trimming the systemd journal to free disk space...".to_string(),
+        ]);
+        
         self.response_templates.insert("update_packages".to_string(), vec![
             "📦 Updating system packages with pacman...".to_string(),
             "⬆️ Checking for package updates on Garuda Linux...".to_string(),
@@ -452,12 +557,32 @@ impl NLPProcessor {
         Ok(())
     }
     
+    pub fn set_llm_backend(&mut self, config: LlmBackendConfig) {
+        info!("🧠 LLM intent backend {} (model: {})",
+            if config.enabled { "enabled" } else { "disabled" }, config.model);
+        self.llm_backend = config;
+    }
+    
+    pub fn get_llm_backend(&self) -> &LlmBackendConfig {
+        &self.llm_backend
+    }
+    
     pub async fn parse_intent(&mut self, input: &str) -> Result<Intent, Box<dyn std::error::Error>> {
         debug!("🔍 Parsing intent from input: {}", input);
         
+        // Prefer the local LLM when enabled; fall back to rule-based parsing
+        if self.llm_backend.enabled {
+            match self.parse_intent_with_llm(input).await {
+                Ok(intent) => {
+                    self.conversation_context.last_intent = Some(intent.clone());
+                    return Ok(intent);
+                }
+                Err(e) => warn!("LLM intent parsing unavailable, using rule-based parser: {}", e),
+            }
+        }
+        
         let normalized_input = self.normalize_input(input);
-        let mut best_intent: Option<Intent> = None;
-        let mut best_confidence = 0.0;
+        let mut candidates: Vec<Intent> = Vec::new();
         
         // Try to match against all intent patterns
         for (category, patterns) in &self.intent_patterns {
@@ -477,35 +602,46 @@ impl NLPProcessor {
                     // Extract entities
                     let entities = self.extract_entities(&normalized_input)?;
                     
-                    let intent = Intent {
+                    candidates.push(Intent {
                         category: category.clone(),
                         action: pattern.action.clone(),
                         parameters,
                         confidence: pattern.confidence,
                         entities,
-                    };
-                    
-                    if pattern.confidence > best_confidence {
-                        best_confidence = pattern.confidence;
-                        best_intent = Some(intent);
-                    }
+                        alternatives: Vec::new(),
+                    });
                 }
             }
         }
         
-        // If no specific intent found, try to extract a general query intent
-        if best_intent.is_none() {
-            let entities = self.extract_entities(&normalized_input)?;
-            best_intent = Some(Intent {
+        // Best match per action, best first
+        candidates.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap_or(std::cmp::Ordering::Equal));
+        let mut seen_actions = std::collections::HashSet::new();
+        candidates.retain(|candidate| seen_actions.insert(candidate.action.clone()));
+        
+        let intent = if candidates.is_empty() {
+            // If no specific intent found, try to extract a general query intent
+            Intent {
                 category: IntentCategory::Conversation,
                 action: "general_query".to_string(),
                 parameters: HashMap::new(),
                 confidence: 0.3,
-                entities,
-            });
-        }
-        
-        let intent = best_intent.unwrap();
+                entities: self.extract_entities(&normalized_input)?,
+                alternatives: Vec::new(),
+            }
+        } else {
+            let mut best = candidates.remove(0);
+            // A close runner-up means the input could mean either; at a tie confidence halves
+            if let Some(runner_up) = candidates.first() {
+                let gap = best.confidence - runner_up.confidence;
+                if gap < AMBIGUITY_MARGIN {
+                    best.confidence *= 0.5 + 0.5 * (gap / AMBIGUITY_MARGIN);
+                }
+            }
+            best.alternatives = candidates;
+            best
+        };
+        debug!("🎯 Intent {} ({:.2}), {} alternatives", intent.action, intent.confidence, intent.alternatives.len());
         
         // Update conversation context
         self.conversation_context.last_intent = Some(intent.clone());
@@ -513,6 +649,77 @@ impl NLPProcessor {
         Ok(intent)
     }
     
+    async fn parse_intent_with_llm(&self, input: &str) -> Result<Intent, Box<dyn std::error::Error>> {
+        // Advertise exactly the actions the rule-based patterns know about
+        let mut actions: Vec<&str> = self.intent_patterns.values()
+            .flatten()
+            .map(|p| p.action.as_str())
+            .collect();
+        actions.sort();
+        actions.dedup();
+        
+        let system_prompt = format!(
+            "You are the intent parser for a Linux system administration assistant on an \
+             i9-13900HX Garuda Linux laptop. Map the user's request to exactly one of these \
+             actions: {}. Reply with JSON only, in the form \
+             {{\"action\": \"<action>\", \"parameters\": {{}}, \"confidence\": <0.0-1.0>}}. \
+             Use \"general_query\" if nothing fits.",
+            actions.join(", ")
+        );
+        
+        let response: serde_json::Value = reqwest::Client::new()
+            .post(format!("{}/api/chat", self.llm_backend.base_url))
+            .timeout(std::time::Duration::from_secs(self.llm_backend.timeout_secs))
+            .json(&serde_json::json!({
+                "model": self.llm_backend.model,
+                "stream": false,
+                "format": "json",
+                "messages": [
+                    { "role": "system", "content": system_prompt },
+                    { "role": "user", "content": input },
+                ],
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        
+        let content = response["message"]["content"].as_str()
+            .ok_or("LLM response missing message content")?;
+        let parsed: LlmIntent = serde_json::from_str(content.trim())?;
+        
+        let category = if parsed.action == "general_query" {
+            IntentCategory::Conversation
+        } else {
+            self.intent_patterns.iter()
+                .find(|(_, patterns)| patterns.iter().any(|p| p.action == parsed.action))
+                .map(|(category, _)| category.clone())
+                .ok_or_else(|| format!("LLM returned unknown action '{}'", parsed.action))?
+        };
+        
+        let parameters = parsed.parameters.into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                (key, value)
+            })
+            .collect();
+        
+        debug!("🧠 LLM intent: {} ({:?})", parsed.action, category);
+        
+        Ok(Intent {
+            category,
+            action: parsed.action,
+            parameters,
+            confidence: parsed.confidence.unwrap_or(0.8).clamp(0.0, 1.0),
+            entities: self.extract_entities(&self.normalize_input(input))?,
+            alternatives: Vec::new(),
+        })
+    }
+    
     fn normalize_input(&self, input: &str) -> String {
         let mut normalized = input.to_lowercase();
         
@@ -567,6 +774,178 @@ impl NLPProcessor {
         Ok(contextual_response)
     }
     
+    /// Whether `intent` is too uncertain to act on without asking first
+    pub fn needs_clarification(&self, intent: &Intent) -> bool {
+        intent.action != "general_query" && intent.confidence < CLARIFICATION_THRESHOLD
+    }
+    
+    /// "Did you mean optimize CPU or clean system?" for the best candidates of `intent`
+    pub fn clarification_question(&self, intent: &Intent) -> String {
+        let options: Vec<String> = std::iter::once(intent)
+            .chain(intent.alternatives.iter())
+            .take(3)
+            .map(|candidate| action_label(&candidate.action))
+            .collect();
+        
+        let choices = match options.split_last() {
+            Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+            _ => options.join(""),
+        };
+        let reply_hint = if options.len() > 1 {
+            format!("Reply with 1-{} or rephrase", options.len())
+        } else {
+            "Reply yes or rephrase".to_string()
+        };
+        format!("🤔 Did you mean {}? {}, and I'll wait before doing anything.", choices, reply_hint)
+    }
+    
+    /// Match a reply to an earlier clarification question: a number or "yes" picks a
+    /// candidate, and a confident re-parse counts if it names one of them. None means
+    /// the reply is a new request.
+    pub fn resolve_clarification(&self, reply: &str, pending: &Intent, reparsed: &Intent) -> Option<Intent> {
+        let candidates: Vec<&Intent> = std::iter::once(pending)
+            .chain(pending.alternatives.iter())
+            .take(3)
+            .collect();
+        let reply = reply.trim().trim_end_matches(|c: char| c == '.' || c == '!').to_lowercase();
+        
+        let index = match reply.as_str() {
+            "1" | "first" | "the first" | "the first one" | "yes" | "y" | "yeah" | "yep" => Some(0),
+            "2" | "second" | "the second" | "the second one" => Some(1),
+            "3" | "third" | "the third" | "the third one" => Some(2),
+            _ => None,
+        };
+        
+        let chosen = match index {
+            Some(index) => candidates.get(index).copied(),
+            None if !self.needs_clarification(reparsed) => {
+                candidates.into_iter().find(|candidate| candidate.action == reparsed.action)
+            }
+            None => None,
+        }?;
+        
+        Some(Intent {
+            confidence: 1.0,
+            alternatives: Vec::new(),
+            ..chosen.clone()
+        })
+    }
+    
+    /// "no", "cancel" and the like, in reply to a clarification question
+    pub fn is_cancellation(&self, reply: &str) -> bool {
+        let reply = reply.trim().trim_end_matches(|c: char| c == '.' || c == '!').to_lowercase();
+        matches!(reply.as_str(), "no" | "n" | "nope" | "cancel" | "neither" | "none" | "never mind" | "nevermind" | "stop")
+    }
+    
+    /// Answer for the read-only status intents (`status_summary`, `explain_temperature`)
+    pub fn generate_status_response(&mut self, action: &str, system_state: &SystemState, context: &StatusContext) -> String {
+        self.conversation_context.last_system_state = Some(system_state.clone());
+        
+        match action {
+            "explain_temperature" => self.explain_temperature(system_state, context),
+            _ => self.summarize_status(system_state, context),
+        }
+    }
+    
+    fn summarize_status(&self, state: &SystemState, context: &StatusContext) -> String {
+        let thresholds = config::get().thresholds;
+        let mut concerns = Vec::new();
+        if state.temperature > thresholds.cpu_temperature {
+            concerns.push(format!("the CPU is running hot at {:.0}°C", state.temperature));
+        }
+        if state.gpu_temperature > thresholds.gpu_temperature_alert {
+            concerns.push(format!("the GPU is at {:.0}°C", state.gpu_temperature));
+        }
+        if state.cpu_usage > thresholds.cpu_usage {
+            concerns.push(format!("CPU load is high at {:.0}%", state.cpu_usage));
+        }
+        if state.memory_usage > thresholds.memory_usage {
+            concerns.push(format!("memory is {:.0}% full", state.memory_usage));
+        }
+        if state.disk_usage > thresholds.disk_usage {
+            concerns.push(format!("the disk is {:.0}% full", state.disk_usage));
+        }
+        
+        let mut sentences = vec![if concerns.is_empty() {
+            "✅ Your system is doing well.".to_string()
+        } else {
+            format!("⚠️ Your system needs some attention: {}.", concerns.join(", "))
+        }];
+        
+        let mut readings = format!("CPU is at {:.0}% and {:.0}°C, memory at {:.0}%, disk at {:.0}%",
+                                   state.cpu_usage, state.temperature, state.memory_usage, state.disk_usage);
+        if state.gpu_temperature > 0.0 {
+            readings += &format!(", GPU at {:.0}°C", state.gpu_temperature);
+        }
+        sentences.push(format!("{}.", readings));
+        
+        if !context.top_processes.is_empty() {
+            let busiest: Vec<String> = context.top_processes.iter().take(3)
+                .map(|(name, cpu, memory)| format!("{} ({:.0}% CPU, {:.0}% RAM)", name, cpu, memory))
+                .collect();
+            sentences.push(format!("Busiest right now: {}.", busiest.join(", ")));
+        }
+        
+        if !context.recent_alerts.is_empty() {
+            let skip = context.recent_alerts.len().saturating_sub(3);
+            sentences.push(format!("Recent alerts: {}.", context.recent_alerts[skip..].join("; ")));
+        }
+        
+        if let Some(recommendation) = &context.top_recommendation {
+            sentences.push(format!("My top suggestion: {}.", recommendation));
+        }
+        
+        sentences.join(" ")
+    }
+    
+    fn explain_temperature(&self, state: &SystemState, context: &StatusContext) -> String {
+        let thresholds = config::get().thresholds;
+        let hot = state.temperature > thresholds.cpu_temperature
+            || state.gpu_temperature > thresholds.gpu_temperature_alert;
+        
+        let mut sentences = vec![if hot {
+            format!("🌡️ The CPU is at {:.0}°C (threshold {:.0}°C) and the GPU at {:.0}°C.",
+                    state.temperature, thresholds.cpu_temperature, state.gpu_temperature)
+        } else {
+            format!("🌡️ Temperatures look normal: CPU {:.0}°C is under the {:.0}°C threshold.",
+                    state.temperature, thresholds.cpu_temperature)
+        }];
+        
+        let mut sensors = context.thermal_sensors.clone();
+        sensors.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        if !sensors.is_empty() {
+            let hottest: Vec<String> = sensors.iter().take(3)
+                .map(|(name, temp, critical)| {
+                    if *critical > 0.0 {
+                        format!("{} {:.0}°C (critical {:.0}°C)", name, temp, critical)
+                    } else {
+                        format!("{} {:.0}°C", name, temp)
+                    }
+                })
+                .collect();
+            sentences.push(format!("Hottest sensors: {}.", hottest.join(", ")));
+        }
+        
+        if let Some(trend) = describe_trend("CPU load", &context.cpu_trend, "%") {
+            sentences.push(trend);
+        }
+        if let Some(trend) = describe_trend("CPU temperature", &context.temperature_trend, "°C") {
+            sentences.push(trend);
+        }
+        
+        if let Some((name, cpu, _)) = context.top_processes.first() {
+            if *cpu > 25.0 {
+                sentences.push(format!("The most likely cause is {} using {:.0}% CPU.", name, cpu));
+            }
+        }
+        
+        if hot {
+            sentences.push("Say \"reduce temperature\" and I'll apply cooling optimizations.".to_string());
+        }
+        
+        sentences.join(" ")
+    }
+    
     fn get_response_template(&self, action: &str) -> String {
         if let Some(templates) = self.response_templates.get(action) {
             // Select a random template for variety
@@ -657,11 +1036,42 @@ impl NLPProcessor {
         stats.insert("user_preferences_count".to_string(), 
             self.conversation_context.user_preferences.len() as f64);
         stats.insert("vocabulary_size".to_string(), 
-            self.system_vocabulary.processes.len() + 
+            (self.system_vocabulary.processes.len() + 
             self.system_vocabulary.packages.len() + 
             self.system_vocabulary.system_components.len() + 
-            self.system_vocabulary.actions.len());
+            self.system_vocabulary.actions.len()) as f64);
         
         stats
     }
 }
+
+/// "CPU load has risen from 20% to 85% over the last 10 readings", or None with too few samples
+fn describe_trend(label: &str, samples: &[f64], unit: &str) -> Option<String> {
+    if samples.len() < 3 {
+        return None;
+    }
+    let first = samples[0];
+    let last = samples[samples.len() - 1];
+    let change = last - first;
+    
+    // Below this the series is noise, not a trend
+    let significant = if unit == "%" { 10.0 } else { 5.0 };
+    Some(if change.abs() < significant {
+        let average = samples.iter().sum::<f64>() / samples.len() as f64;
+        format!("{} has been steady around {:.0}{}.", label, average, unit)
+    } else {
+        format!("{} has {} from {:.0}{} to {:.0}{} over the last {} readings.",
+                label, if change > 0.0 { "risen" } else { "fallen" }, first, unit, last, unit, samples.len())
+    })
+}
+
+/// "optimize_cpu" -> "optimize CPU"
+fn action_label(action: &str) -> String {
+    action.split('_')
+        .map(|word| match word {
+            "cpu" | "gpu" | "ram" => word.to_uppercase(),
+            _ => word.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
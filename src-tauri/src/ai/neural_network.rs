@@ -1,9 +1,22 @@
 use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use chrono::{Datelike, Timelike};
 use nalgebra::{DMatrix, DVector};
 use serde::{Deserialize, Serialize};
 use tracing::{info, debug};
 use crate::ai::{UserAction, SystemState};
 
+// Saved model header: magic bytes followed by a little-endian format version.
+// Bump MODEL_FORMAT_VERSION whenever NetworkWeights or the layer layout changes.
+const MODEL_MAGIC: &[u8; 4] = b"AINN";
+const MODEL_FORMAT_VERSION: u32 = 1;
+
+const SYSADMIN_INPUT_SIZE: usize = 12; // System metrics: CPU, RAM, disk, temp, processes, etc.
+const SYSADMIN_OUTPUT_SIZE: usize = 8; // Actions: optimize, clean, update, backup, etc.
+const SYSADMIN_HIDDEN_LAYERS: [usize; 3] = [24, 16, 12]; // Deep network for complex patterns
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkWeights {
     input_hidden: DMatrix<f64>,
@@ -23,13 +36,22 @@ pub struct NeuralNetwork {
     activation_history: Vec<Vec<DVector<f64>>>,
 }
 
+#[derive(Serialize, Deserialize)]
+struct SavedNetwork {
+    input_size: usize,
+    hidden_layers: Vec<usize>,
+    output_size: usize,
+    learning_rate: f64,
+    weights: NetworkWeights,
+}
+
 impl NeuralNetwork {
     pub async fn new_for_sysadmin() -> Result<Self, Box<dyn std::error::Error>> {
         info!("🧠 Initializing Neural Network for system administration...");
         
-        let input_size = 12; // System metrics: CPU, RAM, disk, temp, processes, etc.
-        let output_size = 8;  // Actions: optimize, clean, update, backup, etc.
-        let hidden_layers = vec![24, 16, 12]; // Deep network for complex patterns
+        let input_size = SYSADMIN_INPUT_SIZE;
+        let output_size = SYSADMIN_OUTPUT_SIZE;
+        let hidden_layers = SYSADMIN_HIDDEN_LAYERS.to_vec();
         
         let weights = Self::initialize_weights(input_size, &hidden_layers, output_size);
         
@@ -43,6 +65,94 @@ impl NeuralNetwork {
         })
     }
     
+    /// Write the trained weights to `path`, replacing any previous save atomically.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let saved = SavedNetwork {
+            input_size: self.input_size,
+            hidden_layers: self.hidden_layers.clone(),
+            output_size: self.output_size,
+            learning_rate: self.learning_rate,
+            weights: self.weights.clone(),
+        };
+        
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        let tmp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(MODEL_MAGIC)?;
+        file.write_all(&MODEL_FORMAT_VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut file, &saved)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        
+        debug!("💾 Saved neural network weights to {}", path.display());
+        Ok(())
+    }
+    
+    /// Load weights saved by `save`. Fails if the file was written by a
+    /// different format version or for a different network architecture.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = fs::File::open(path)?;
+        
+        let mut magic = [0u8; 4];
+        file.read_exact(&mut magic)?;
+        if &magic != MODEL_MAGIC {
+            return Err(format!("{} is not a saved neural network", path.display()).into());
+        }
+        
+        let mut version = [0u8; 4];
+        file.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != MODEL_FORMAT_VERSION {
+            return Err(format!("Unsupported neural network format version {} (expected {})", version, MODEL_FORMAT_VERSION).into());
+        }
+        
+        let saved: SavedNetwork = bincode::deserialize_from(file)?;
+        Self::validate_architecture(&saved)?;
+        if saved.input_size != SYSADMIN_INPUT_SIZE
+            || saved.output_size != SYSADMIN_OUTPUT_SIZE
+            || saved.hidden_layers != SYSADMIN_HIDDEN_LAYERS
+        {
+            return Err("Saved neural network was trained for a different architecture".into());
+        }
+        
+        info!("🧠 Loaded neural network weights from {}", path.display());
+        Ok(Self {
+            weights: saved.weights,
+            learning_rate: saved.learning_rate,
+            hidden_layers: saved.hidden_layers,
+            input_size: saved.input_size,
+            output_size: saved.output_size,
+            activation_history: Vec::new(),
+        })
+    }
+    
+    fn validate_architecture(saved: &SavedNetwork) -> Result<(), Box<dyn std::error::Error>> {
+        let layers = &saved.hidden_layers;
+        let weights = &saved.weights;
+        
+        if layers.is_empty() || weights.hidden_hidden.len() + 1 != layers.len() || weights.hidden_bias.len() != layers.len() {
+            return Err("Saved neural network has an inconsistent layer count".into());
+        }
+        
+        let mut shapes_match = weights.input_hidden.shape() == (layers[0], saved.input_size)
+            && weights.hidden_output.shape() == (saved.output_size, layers[layers.len() - 1])
+            && weights.output_bias.len() == saved.output_size;
+        for i in 1..layers.len() {
+            shapes_match &= weights.hidden_hidden[i - 1].shape() == (layers[i], layers[i - 1]);
+        }
+        for (bias, &size) in weights.hidden_bias.iter().zip(layers) {
+            shapes_match &= bias.len() == size;
+        }
+        
+        if !shapes_match {
+            return Err("Saved neural network weights don't match its declared architecture".into());
+        }
+        Ok(())
+    }
+    
     fn initialize_weights(input_size: usize, hidden_layers: &[usize], output_size: usize) -> NetworkWeights {
         let mut rng = rand::thread_rng();
         
@@ -173,6 +283,8 @@ impl NeuralNetwork {
             memory_usage: 60.0,
             disk_usage: 70.0,
             temperature: 65.0,
+            gpu_temperature: 60.0,
+            memory_pressure: None,
             active_processes: vec!["firefox".to_string(), "vscode".to_string()],
             current_workload: crate::ai::WorkloadType::Development,
             time_of_day: chrono::Utc::now().hour() as u8,
@@ -214,6 +326,9 @@ impl NeuralNetwork {
         let output_error = predicted - target;
         let output_delta = &output_error.component_mul(&self.sigmoid_derivative(predicted));
         
+        // Error reaching the last hidden layer, through the weights the prediction used
+        let mut error = self.weights.hidden_output.transpose() * output_delta;
+        
         // Update output weights
         if let Some(last_hidden) = self.activation_history.last().and_then(|h| h.last()) {
             let output_gradient = output_delta * last_hidden.transpose();
@@ -222,21 +337,17 @@ impl NeuralNetwork {
         }
         
         // Backpropagate through hidden layers
-        let mut error = self.weights.hidden_output.transpose() * output_delta;
-        
         if let Some(activations) = self.activation_history.last() {
             for i in (0..self.weights.hidden_hidden.len()).rev() {
                 let delta = error.component_mul(&self.relu_derivative(&activations[i + 1]));
+                // hidden_hidden[i] maps layer i to layer i + 1, so this is layer i's error
+                let previous_error = self.weights.hidden_hidden[i].transpose() * &delta;
                 
                 let gradient = &delta * activations[i].transpose();
                 self.weights.hidden_hidden[i] -= self.learning_rate * gradient;
                 self.weights.hidden_bias[i + 1] -= self.learning_rate * &delta;
                 
-                if i > 0 {
-                    error = self.weights.hidden_hidden[i].transpose() * &delta;
-                } else {
-                    error = self.weights.input_hidden.transpose() * &delta;
-                }
+                error = previous_error;
             }
             
             // Update input to first hidden layer weights
@@ -316,3 +427,47 @@ impl NeuralNetwork {
         stats
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::ActionOutcome;
+    
+    fn action(action_type: &str, outcome: ActionOutcome) -> UserAction {
+        UserAction {
+            timestamp: chrono::Utc::now(),
+            action_type: action_type.to_string(),
+            context: "test".to_string(),
+            parameters: HashMap::new(),
+            outcome,
+        }
+    }
+    
+    #[tokio::test]
+    async fn trained_network_survives_save_and_load() {
+        let mut network = NeuralNetwork::new_for_sysadmin().await.unwrap();
+        for step in 0..5 {
+            let outcome = if step % 2 == 0 { ActionOutcome::Success } else { ActionOutcome::Failed("test".to_string()) };
+            network.train_on_action(&action("optimize_cpu", outcome)).await.unwrap();
+        }
+        
+        let path = std::env::temp_dir().join(format!("neural_network_roundtrip_{}.bin", std::process::id()));
+        network.save(&path).unwrap();
+        let loaded = NeuralNetwork::load(&path);
+        let _ = fs::remove_file(&path);
+        let mut loaded = loaded.unwrap();
+        
+        let input = DVector::from_fn(SYSADMIN_INPUT_SIZE, |i, _| i as f64 / SYSADMIN_INPUT_SIZE as f64);
+        assert_eq!(network.forward_pass(&input), loaded.forward_pass(&input));
+    }
+    
+    #[test]
+    fn load_rejects_other_files() {
+        let path = std::env::temp_dir().join(format!("neural_network_garbage_{}.bin", std::process::id()));
+        fs::write(&path, b"not a model").unwrap();
+        let result = NeuralNetwork::load(&path);
+        let _ = fs::remove_file(&path);
+        
+        assert!(result.is_err());
+    }
+}
@@ -1,8 +1,8 @@
 use std::collections::{HashMap, VecDeque};
 use chrono::{DateTime, Utc, Timelike, Weekday, Datelike};
 use serde::{Deserialize, Serialize};
-use tracing::{info, debug, warn};
-use statrs::statistics::{Statistics, Data};
+use tracing::{info, debug};
+use statrs::statistics::{Data, Distribution};
 use crate::ai::{UserAction, SystemState, AIRecommendation, WorkloadType, ActionOutcome};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +61,10 @@ pub struct PatternRecognizer {
     pattern_weights: HashMap<String, f64>,
     min_pattern_occurrences: usize,
     max_history_size: usize,
+    decay_half_life_days: f64,
+    min_pattern_confidence: f64,
+    max_pattern_age_days: i64,
+    last_decay: DateTime<Utc>,
 }
 
 impl PatternRecognizer {
@@ -74,6 +78,10 @@ impl PatternRecognizer {
             pattern_weights: HashMap::new(),
             min_pattern_occurrences: 3,
             max_history_size: 1000,
+            decay_half_life_days: 14.0,
+            min_pattern_confidence: 0.2,
+            max_pattern_age_days: 60,
+            last_decay: Utc::now(),
         })
     }
     
@@ -107,10 +115,16 @@ impl PatternRecognizer {
         // Continuously analyze system state patterns
         self.analyze_system_state_patterns().await?;
         
+        // Age out patterns that haven't been seen lately
+        if Utc::now() - self.last_decay >= chrono::Duration::hours(1) {
+            self.decay_patterns();
+        }
+        
         Ok(())
     }
     
     async fn detect_time_based_patterns(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut found = Vec::new();
         let mut time_action_map: HashMap<(u8, Weekday), Vec<&UserAction>> = HashMap::new();
         
         // Group actions by time and day
@@ -148,15 +162,21 @@ impl PatternRecognizer {
                         }],
                     };
                     
-                    self.add_or_update_pattern(pattern).await?;
+                    found.push(pattern);
                 }
             }
         }
         
+        // Adding needs &mut self, so it waits until the borrows of the history end
+        for pattern in found {
+            self.add_or_update_pattern(pattern).await?;
+        }
+        
         Ok(())
     }
     
     async fn detect_workload_patterns(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut found = Vec::new();
         // Analyze workload transitions and associated actions
         let mut workload_transitions: HashMap<(WorkloadType, WorkloadType), Vec<&UserAction>> = HashMap::new();
         
@@ -217,15 +237,20 @@ impl PatternRecognizer {
                         }],
                     };
                     
-                    self.add_or_update_pattern(pattern).await?;
+                    found.push(pattern);
                 }
             }
         }
         
+        for pattern in found {
+            self.add_or_update_pattern(pattern).await?;
+        }
+        
         Ok(())
     }
     
     async fn detect_performance_patterns(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut found = Vec::new();
         // Analyze performance-related actions
         let performance_actions: Vec<&UserAction> = self.action_history.iter()
             .filter(|action| {
@@ -283,15 +308,20 @@ impl PatternRecognizer {
                         }],
                     };
                     
-                    self.add_or_update_pattern(pattern).await?;
+                    found.push(pattern);
                 }
             }
         }
         
+        for pattern in found {
+            self.add_or_update_pattern(pattern).await?;
+        }
+        
         Ok(())
     }
     
     async fn detect_maintenance_patterns(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut found = Vec::new();
         // Analyze maintenance scheduling patterns
         let maintenance_actions: Vec<&UserAction> = self.action_history.iter()
             .filter(|action| {
@@ -339,11 +369,15 @@ impl PatternRecognizer {
                         }],
                     };
                     
-                    self.add_or_update_pattern(pattern).await?;
+                    found.push(pattern);
                 }
             }
         }
         
+        for pattern in found {
+            self.add_or_update_pattern(pattern).await?;
+        }
+        
         Ok(())
     }
     
@@ -359,70 +393,69 @@ impl PatternRecognizer {
             .map(|(_, state)| state)
             .collect();
         
-        // CPU usage patterns
+        let sample_count = recent_states.len() as f64;
         let cpu_usages: Vec<f64> = recent_states.iter().map(|s| s.cpu_usage).collect();
-        if let Ok(cpu_data) = Data::new(cpu_usages.clone()) {
-            let cpu_mean = cpu_data.mean().unwrap_or(0.0);
-            let cpu_std = cpu_data.std_dev().unwrap_or(0.0);
+        let memory_usages: Vec<f64> = recent_states.iter().map(|s| s.memory_usage).collect();
+        
+        // CPU usage patterns
+        let cpu_data = Data::new(cpu_usages);
+        let cpu_mean = cpu_data.mean().unwrap_or(0.0);
+        let cpu_std = cpu_data.std_dev().unwrap_or(0.0);
+        
+        if cpu_mean > 70.0 && cpu_std < 10.0 {
+            // Consistently high CPU usage pattern
+            let pattern = UsagePattern {
+                pattern_id: "high_cpu_usage_pattern".to_string(),
+                pattern_type: PatternType::ResourceUsage,
+                frequency: sample_count,
+                confidence: 0.85,
+                last_seen: Utc::now(),
+                context: PatternContext {
+                    time_range: (0, 24),
+                    days_of_week: vec![
+                        Weekday::Mon, Weekday::Tue, Weekday::Wed, 
+                        Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun
+                    ],
+                    system_conditions: vec![SystemCondition::CpuUsageAbove(70.0)],
+                    user_actions: vec!["optimize_cpu".to_string()],
+                },
+                triggers: vec![PatternTrigger {
+                    condition: "sustained_high_cpu".to_string(),
+                    threshold: 70.0,
+                    action: "optimize_cpu".to_string(),
+                }],
+            };
             
-            if cpu_mean > 70.0 && cpu_std < 10.0 {
-                // Consistently high CPU usage pattern
-                let pattern = UsagePattern {
-                    pattern_id: "high_cpu_usage_pattern".to_string(),
-                    pattern_type: PatternType::ResourceUsage,
-                    frequency: recent_states.len() as f64,
-                    confidence: 0.85,
-                    last_seen: Utc::now(),
-                    context: PatternContext {
-                        time_range: (0, 24),
-                        days_of_week: vec![
-                            Weekday::Mon, Weekday::Tue, Weekday::Wed, 
-                            Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun
-                        ],
-                        system_conditions: vec![SystemCondition::CpuUsageAbove(70.0)],
-                        user_actions: vec!["optimize_cpu".to_string()],
-                    },
-                    triggers: vec![PatternTrigger {
-                        condition: "sustained_high_cpu".to_string(),
-                        threshold: 70.0,
-                        action: "optimize_cpu".to_string(),
-                    }],
-                };
-                
-                self.add_or_update_pattern(pattern).await?;
-            }
+            self.add_or_update_pattern(pattern).await?;
         }
         
         // Memory usage patterns
-        let memory_usages: Vec<f64> = recent_states.iter().map(|s| s.memory_usage).collect();
-        if let Ok(memory_data) = Data::new(memory_usages.clone()) {
-            let memory_mean = memory_data.mean().unwrap_or(0.0);
+        let memory_mean = Data::new(memory_usages).mean().unwrap_or(0.0);
+        
+        if memory_mean > 80.0 {
+            let pattern = UsagePattern {
+                pattern_id: "high_memory_usage_pattern".to_string(),
+                pattern_type: PatternType::ResourceUsage,
+                frequency: sample_count,
+                confidence: 0.80,
+                last_seen: Utc::now(),
+                context: PatternContext {
+                    time_range: (0, 24),
+                    days_of_week: vec![
+                        Weekday::Mon, Weekday::Tue, Weekday::Wed, 
+                        Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun
+                    ],
+                    system_conditions: vec![SystemCondition::MemoryUsageAbove(80.0)],
+                    user_actions: vec!["optimize_memory".to_string()],
+                },
+                triggers: vec![PatternTrigger {
+                    condition: "high_memory_usage".to_string(),
+                    threshold: 80.0,
+                    action: "optimize_memory".to_string(),
+                }],
+            };
             
-            if memory_mean > 80.0 {
-                let pattern = UsagePattern {
-                    pattern_id: "high_memory_usage_pattern".to_string(),
-                    pattern_type: PatternType::ResourceUsage,
-                    frequency: recent_states.len() as f64,
-                    confidence: 0.80,
-                    last_seen: Utc::now(),
-                    context: PatternContext {
-                        time_range: (0, 24),
-                        days_of_week: vec![
-                            Weekday::Mon, Weekday::Tue, Weekday::Wed, 
-                            Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun
-                        ],
-                        system_conditions: vec![SystemCondition::MemoryUsageAbove(80.0)],
-                        user_actions: vec!["optimize_memory".to_string()],
-                    },
-                    triggers: vec![PatternTrigger {
-                        condition: "high_memory_usage".to_string(),
-                        threshold: 80.0,
-                        action: "optimize_memory".to_string(),
-                    }],
-                };
-                
-                self.add_or_update_pattern(pattern).await?;
-            }
+            self.add_or_update_pattern(pattern).await?;
         }
         
         Ok(())
@@ -520,6 +553,7 @@ impl PatternRecognizer {
                         reasoning: format!("This recommendation is based on {} previous occurrences with {:.1}% success rate.",
                                          pattern.frequency as u32, pattern.confidence * 100.0),
                         estimated_impact: self.estimate_impact(action),
+                        relevant_logs: Vec::new(),
                     });
                 }
             }
@@ -618,6 +652,40 @@ impl PatternRecognizer {
         }
     }
     
+    pub fn set_decay_policy(&mut self, half_life_days: f64, min_confidence: f64, max_age_days: i64) {
+        self.decay_half_life_days = half_life_days.max(0.1);
+        self.min_pattern_confidence = min_confidence.clamp(0.0, 1.0);
+        self.max_pattern_age_days = max_age_days.max(1);
+    }
+    
+    /// Exponentially decay confidence of patterns by how long they've gone unseen,
+    /// then drop patterns that are too weak or too old to be useful.
+    pub fn decay_patterns(&mut self) {
+        let now = Utc::now();
+        let half_life_secs = self.decay_half_life_days * 86_400.0;
+        
+        for pattern in &mut self.patterns {
+            // Only decay the time not already accounted for by the previous pass
+            let since = pattern.last_seen.max(self.last_decay);
+            let idle_secs = (now - since).num_seconds().max(0) as f64;
+            pattern.confidence *= 0.5_f64.powf(idle_secs / half_life_secs);
+        }
+        
+        let before = self.patterns.len();
+        let max_age = chrono::Duration::days(self.max_pattern_age_days);
+        let min_confidence = self.min_pattern_confidence;
+        self.patterns.retain(|p| p.confidence >= min_confidence && now - p.last_seen <= max_age);
+        
+        let pattern_ids: Vec<&String> = self.patterns.iter().map(|p| &p.pattern_id).collect();
+        self.pattern_weights.retain(|id, _| pattern_ids.contains(&id));
+        
+        let pruned = before - self.patterns.len();
+        if pruned > 0 {
+            debug!("🧹 Pruned {} stale patterns", pruned);
+        }
+        self.last_decay = now;
+    }
+    
     pub fn get_pattern_statistics(&self) -> HashMap<String, f64> {
         let mut stats = HashMap::new();
         
@@ -641,6 +709,153 @@ impl PatternRecognizer {
             stats.insert(format!("patterns_{}", pattern_type.to_lowercase()), count as f64);
         }
         
+        // Frequency of the pattern we're most sure about
+        if let Some(strongest) = self.patterns.iter().max_by(|a, b| a.confidence.total_cmp(&b.confidence)) {
+            stats.insert("top_pattern_confidence".to_string(), strongest.confidence);
+            stats.insert("top_pattern_frequency".to_string(), strongest.frequency);
+        }
+        
         stats
     }
+    
+    pub fn patterns(&self) -> &[UsagePattern] {
+        &self.patterns
+    }
+    
+    pub fn pattern_weights(&self) -> &HashMap<String, f64> {
+        &self.pattern_weights
+    }
+    
+    /// Swap in a curated set of patterns, e.g. from an exported state. Confidence
+    /// is clamped to 0-1 and weights for patterns not in the set are dropped.
+    pub fn replace_patterns(&mut self, mut patterns: Vec<UsagePattern>, mut weights: HashMap<String, f64>) {
+        for pattern in &mut patterns {
+            pattern.confidence = pattern.confidence.clamp(0.0, 1.0);
+        }
+        weights.retain(|id, weight| weight.is_finite() && patterns.iter().any(|p| &p.pattern_id == id));
+        
+        info!("🔍 Replaced {} patterns with {} imported", self.patterns.len(), patterns.len());
+        self.patterns = patterns;
+        self.pattern_weights = weights;
+        self.last_decay = Utc::now();
+    }
+    
+    /// The `n` strongest learned behaviors, ranked by confidence weighted by frequency
+    pub fn top_patterns(&self, n: usize) -> Vec<&UsagePattern> {
+        let mut ranked: Vec<&UsagePattern> = self.patterns.iter().collect();
+        ranked.sort_by(|a, b| (b.confidence * b.frequency).total_cmp(&(a.confidence * a.frequency)));
+        ranked.truncate(n);
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    fn pattern(id: &str, pattern_type: PatternType, confidence: f64, frequency: f64, last_seen: DateTime<Utc>) -> UsagePattern {
+        UsagePattern {
+            pattern_id: id.to_string(),
+            pattern_type,
+            frequency,
+            confidence,
+            last_seen,
+            context: PatternContext {
+                time_range: (0, 24),
+                days_of_week: Vec::new(),
+                system_conditions: Vec::new(),
+                user_actions: Vec::new(),
+            },
+            triggers: Vec::new(),
+        }
+    }
+    
+    fn governor_action(outcome: ActionOutcome) -> UserAction {
+        UserAction {
+            // Monday 09:00
+            timestamp: Utc.with_ymd_and_hms(2026, 10, 12, 9, 0, 0).unwrap(),
+            action_type: "set_cpu_governor".to_string(),
+            context: String::new(),
+            parameters: HashMap::new(),
+            outcome,
+        }
+    }
+    
+    #[tokio::test]
+    async fn statistics_follow_analyzed_actions() {
+        let mut recognizer = PatternRecognizer::new().await.unwrap();
+        for _ in 0..3 {
+            recognizer.analyze_action(&governor_action(ActionOutcome::Success)).await.unwrap();
+        }
+        // Three successes at the same hour and weekday make a time-based pattern
+        let stats = recognizer.get_pattern_statistics();
+        assert_eq!(stats["total_patterns"], 1.0);
+        assert_eq!(stats["action_history_size"], 3.0);
+        assert_eq!(stats["system_history_size"], 0.0);
+        assert_eq!(stats["patterns_timebasedusage"], 1.0);
+        assert_eq!(stats["average_confidence"], 1.0);
+        assert_eq!(stats["top_pattern_frequency"], 3.0);
+        
+        // A failure lowers the success rate to 0.75 and the pattern's weight to 1.08
+        recognizer.analyze_action(&governor_action(ActionOutcome::Failed("EPERM".to_string()))).await.unwrap();
+        let stats = recognizer.get_pattern_statistics();
+        assert_eq!(stats["total_patterns"], 1.0);
+        assert_eq!(stats["top_pattern_frequency"], 4.0);
+        assert!((stats["average_confidence"] - 0.875 * 1.08).abs() < 1e-9);
+        assert_eq!(recognizer.top_patterns(5)[0].pattern_id, "time_9_Mon");
+    }
+    
+    #[tokio::test]
+    async fn top_patterns_rank_by_confidence_times_frequency() {
+        let mut recognizer = PatternRecognizer::new().await.unwrap();
+        let now = Utc::now();
+        recognizer.replace_patterns(vec![
+            pattern("sure_but_rare", PatternType::TimeBasedUsage, 0.9, 2.0, now),
+            pattern("frequent", PatternType::MaintenanceSchedule, 0.5, 10.0, now),
+            pattern("weak", PatternType::MaintenanceSchedule, 0.1, 3.0, now),
+        ], HashMap::new());
+        
+        let top: Vec<&str> = recognizer.top_patterns(2).iter().map(|p| p.pattern_id.as_str()).collect();
+        assert_eq!(top, vec!["frequent", "sure_but_rare"]);
+        
+        let stats = recognizer.get_pattern_statistics();
+        assert_eq!(stats["total_patterns"], 3.0);
+        assert!((stats["average_confidence"] - 0.5).abs() < 1e-9);
+        assert_eq!(stats["patterns_maintenanceschedule"], 2.0);
+        assert_eq!(stats["patterns_timebasedusage"], 1.0);
+        // The most confident pattern, not the highest ranked one
+        assert_eq!(stats["top_pattern_confidence"], 0.9);
+        assert_eq!(stats["top_pattern_frequency"], 2.0);
+    }
+    
+    #[tokio::test]
+    async fn decay_prunes_aged_patterns() {
+        let mut recognizer = PatternRecognizer::new().await.unwrap();
+        let now = Utc::now();
+        let days_ago = |days: i64| now - chrono::Duration::days(days);
+        recognizer.replace_patterns(vec![
+            pattern("fresh", PatternType::TimeBasedUsage, 0.9, 5.0, now),
+            pattern("aging", PatternType::TimeBasedUsage, 0.8, 5.0, days_ago(14)),
+            pattern("faded", PatternType::TimeBasedUsage, 0.5, 5.0, days_ago(28)),
+            pattern("stale", PatternType::MaintenanceSchedule, 1.0, 5.0, days_ago(90)),
+        ], HashMap::from([("stale".to_string(), 1.5), ("fresh".to_string(), 1.2)]));
+        // As if the last pass ran a month ago
+        recognizer.last_decay = days_ago(30);
+        
+        recognizer.decay_patterns();
+        
+        // Default policy: 14 day half-life, 0.2 minimum confidence, 60 days maximum age
+        let remaining: HashMap<&str, f64> = recognizer.patterns().iter()
+            .map(|p| (p.pattern_id.as_str(), p.confidence))
+            .collect();
+        assert_eq!(remaining.len(), 2);
+        assert!((remaining["fresh"] - 0.9).abs() < 1e-3);
+        assert!((remaining["aging"] - 0.4).abs() < 1e-3);
+        // faded fell to 0.125 and stale is past the maximum age
+        assert!(!remaining.contains_key("faded"));
+        assert!(!remaining.contains_key("stale"));
+        assert!(!recognizer.pattern_weights().contains_key("stale"));
+        assert!(recognizer.pattern_weights().contains_key("fresh"));
+    }
 }
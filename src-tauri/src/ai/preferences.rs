@@ -0,0 +1,164 @@
+// Preferences - how well each action has worked out for this user, learned from
+// outcomes and kept in the shared database so they survive restarts.
+
+use std::collections::HashMap;
+use tracing::{debug, warn};
+use crate::database::Database;
+use super::{natural_language, ActionOutcome, UserAction};
+
+pub const PREFERENCE_PATTERN_PREFIX: &str = "preference:";
+
+/// Preference for an action nothing has been learned about yet
+pub const NEUTRAL_PREFERENCE: f64 = 0.5;
+
+pub struct Preferences {
+    values: HashMap<String, f64>,
+    database: Database,
+}
+
+impl Preferences {
+    /// Restore preferences learned in earlier runs
+    pub fn load(database: Database) -> Self {
+        let values = match database.latest_patterns(PREFERENCE_PATTERN_PREFIX) {
+            Ok(patterns) => patterns.into_iter()
+                .map(|(name, value)| (name.trim_start_matches(PREFERENCE_PATTERN_PREFIX).to_string(), value))
+                .collect(),
+            Err(e) => {
+                warn!("Failed to load learned preferences: {}", e);
+                HashMap::new()
+            }
+        };
+        
+        debug!("📚 Loaded {} learned preferences", values.len());
+        Self { values, database }
+    }
+    
+    /// 0.0 (keeps failing) to 1.0 (keeps working) for `action`
+    pub fn get(&self, action: &str) -> f64 {
+        *self.values.get(action).unwrap_or(&NEUTRAL_PREFERENCE)
+    }
+    
+    pub fn values(&self) -> &HashMap<String, f64> {
+        &self.values
+    }
+    
+    /// Replace every preference, in the database too. Values are clamped to 0.0-1.0.
+    pub fn replace(&mut self, values: HashMap<String, f64>) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some((key, _)) = values.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(format!("Preference {} is not a number", key).into());
+        }
+        let values: HashMap<String, f64> = values.into_iter()
+            .map(|(key, value)| (key, value.clamp(0.0, 1.0)))
+            .collect();
+        
+        // Database first, so a failed write leaves the running state as it was
+        self.database.replace_patterns(PREFERENCE_PATTERN_PREFIX, &values, 1.0)?;
+        self.values = values;
+        Ok(())
+    }
+    
+    /// Move the preference for the action `action` ran by one outcome and persist it.
+    /// Returns the new value.
+    pub fn learn_from_user_action(&mut self, action: &UserAction) -> f64 {
+        let key = preference_key(action).to_string();
+        let preference = adjusted_preference(self.get(&key), &action.outcome);
+        self.values.insert(key.clone(), preference);
+        
+        let pattern_name = format!("{}{}", PREFERENCE_PATTERN_PREFIX, key);
+        if let Err(e) = self.database.record_pattern(&pattern_name, preference, 1.0) {
+            warn!("⚠️ Failed to persist preference {}: {}", key, e);
+        }
+        preference
+    }
+}
+
+/// The action that ran: commands record it as the "action" parameter, since their
+/// action_type only says a command was typed and their context is the raw input
+pub fn preference_key(action: &UserAction) -> &str {
+    action.parameters.get("action").map(String::as_str).unwrap_or(&action.action_type)
+}
+
+/// The parsed slots of a command, so patterns can key on them (e.g. which governor was asked for)
+pub fn interaction_parameters(intent: &natural_language::Intent, action: &str) -> HashMap<String, String> {
+    let mut parameters = intent.parameters.clone();
+    for entity in &intent.entities {
+        parameters.entry(format!("{:?}", entity.entity_type).to_lowercase())
+            .or_insert_with(|| entity.value.clone());
+    }
+    parameters.insert("intent".to_string(), intent.action.clone());
+    parameters.insert("action".to_string(), action.to_string());
+    parameters
+}
+
+/// Preference for an action after one more outcome: successes raise it, failures lower it
+fn adjusted_preference(current: f64, outcome: &ActionOutcome) -> f64 {
+    match outcome {
+        ActionOutcome::Success => (current + 0.1).min(1.0),
+        ActionOutcome::Failed(_) => (current - 0.1).max(0.0),
+        // Slight adjustment
+        ActionOutcome::Partial(_) => (current + 0.05).min(1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use natural_language::{Entity, EntityType, Intent, IntentCategory};
+    
+    fn command(input: &str, intent: &Intent, action: &str, outcome: ActionOutcome) -> UserAction {
+        UserAction {
+            timestamp: Utc::now(),
+            action_type: "natural_language_command".to_string(),
+            context: input.to_string(),
+            parameters: interaction_parameters(intent, action),
+            outcome,
+        }
+    }
+    
+    #[test]
+    fn failed_action_lowers_preference() {
+        let root = std::env::temp_dir().join(format!("preferences_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let database = Database::open(root.join("preferences.db")).unwrap();
+        let intent = Intent {
+            category: IntentCategory::SystemOptimization,
+            action: "set_cpu_governor".to_string(),
+            parameters: HashMap::from([("governor".to_string(), "powersave".to_string())]),
+            confidence: 0.9,
+            entities: vec![Entity {
+                entity_type: EntityType::SystemComponent,
+                value: "cpu".to_string(),
+                start_pos: 4,
+                end_pos: 7,
+            }],
+            alternatives: Vec::new(),
+        };
+        
+        let mut preferences = Preferences::load(database.clone());
+        let failed = command("set the cpu governor to powersave", &intent, "set_cpu_governor", ActionOutcome::Failed("Permission denied".to_string()));
+        assert_eq!(failed.parameters["governor"], "powersave");
+        assert_eq!(failed.parameters["systemcomponent"], "cpu");
+        preferences.learn_from_user_action(&failed);
+        assert!((preferences.get("set_cpu_governor") - 0.4).abs() < 1e-9);
+        
+        // Worded differently, the same action: the same preference drops again
+        let reworded = command("change cpu governor to powersave please", &intent, "set_cpu_governor", ActionOutcome::Failed("again".to_string()));
+        preferences.learn_from_user_action(&reworded);
+        assert!((preferences.get("set_cpu_governor") - 0.3).abs() < 1e-9);
+        assert_eq!(preferences.values().len(), 1);
+        
+        // Stored, not only held in memory
+        assert!((Preferences::load(database).get("set_cpu_governor") - 0.3).abs() < 1e-9);
+        
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+    
+    #[test]
+    fn preference_stays_within_bounds() {
+        assert!(adjusted_preference(0.5, &ActionOutcome::Success) > 0.5);
+        assert!(adjusted_preference(0.5, &ActionOutcome::Partial("2 of 4 CPUs".to_string())) > 0.5);
+        assert_eq!(adjusted_preference(0.05, &ActionOutcome::Failed("again".to_string())), 0.0);
+        assert_eq!(adjusted_preference(0.95, &ActionOutcome::Success), 1.0);
+    }
+}
@@ -0,0 +1,244 @@
+// System Report - One shareable snapshot of the machine for support and forum posts
+// Collected by AIEngine::generate_system_report, rendered as Markdown or JSON
+
+use std::collections::BTreeMap;
+use std::fs;
+use chrono::{DateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::SystemMetrics;
+use crate::ai::AIRecommendation;
+use crate::hardware::{CpuTopology, StorageDevice};
+use crate::monitoring_system::{GPUInfo, MonitorAlert, ProcessInfo};
+
+const HOSTNAME_PLACEHOLDER: &str = "<hostname>";
+const USERNAME_PLACEHOLDER: &str = "<user>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuSummary {
+    pub model: String,
+    pub physical_cores: usize,
+    pub threads: usize,
+    pub hybrid: bool,
+    pub base_freq_mhz: Option<u32>,
+    pub boost_freq_mhz: Option<u32>,
+}
+
+impl From<&CpuTopology> for CpuSummary {
+    fn from(topology: &CpuTopology) -> Self {
+        Self {
+            model: topology.model.clone(),
+            physical_cores: topology.physical_cores,
+            threads: topology.threads,
+            hybrid: topology.hybrid,
+            base_freq_mhz: topology.base_freq_mhz,
+            boost_freq_mhz: topology.boost_freq_mhz,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemReport {
+    pub generated_at: DateTime<Utc>,
+    pub app_version: String,
+    pub hostname: String,
+    pub username: String,
+    pub distro: String,
+    pub kernel: String,
+    pub cpu: CpuSummary,
+    pub memory_total_gb: f64,
+    pub gpus: Vec<GPUInfo>,
+    pub storage: Vec<StorageDevice>,
+    /// None when the monitor couldn't take a sample
+    pub metrics: Option<SystemMetrics>,
+    /// Governor, performance profile, kernel and hugepage state from SystemController
+    pub configuration: BTreeMap<String, String>,
+    pub top_processes: Vec<ProcessInfo>,
+    pub recent_alerts: Vec<MonitorAlert>,
+    pub recommendations: Vec<AIRecommendation>,
+}
+
+impl SystemReport {
+    /// Adds what the monitor doesn't know: identity, distro, kernel, CPU and RAM
+    pub fn new(
+        metrics: Option<SystemMetrics>,
+        gpus: Vec<GPUInfo>,
+        storage: Vec<StorageDevice>,
+        configuration: BTreeMap<String, String>,
+        top_processes: Vec<ProcessInfo>,
+        recent_alerts: Vec<MonitorAlert>,
+        recommendations: Vec<AIRecommendation>,
+    ) -> Self {
+        let kernel = fs::read_to_string("/proc/sys/kernel/osrelease")
+            .map(|release| release.trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        let memory_total_gb = fs::read_to_string("/proc/meminfo").ok()
+            .and_then(|meminfo| {
+                meminfo.lines()
+                    .find(|line| line.starts_with("MemTotal:"))
+                    .and_then(|line| line.split_whitespace().nth(1))
+                    .and_then(|kb| kb.parse::<f64>().ok())
+            })
+            .map(|kb| kb / 1024.0 / 1024.0)
+            .unwrap_or(0.0);
+        
+        Self {
+            generated_at: Utc::now(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            hostname: whoami::hostname(),
+            username: whoami::username(),
+            distro: whoami::distro(),
+            kernel,
+            cpu: CpuSummary::from(&CpuTopology::detect()),
+            memory_total_gb,
+            gpus,
+            storage,
+            metrics,
+            configuration,
+            top_processes,
+            recent_alerts,
+            recommendations,
+        }
+    }
+    
+    /// Render as `format`. With `redact`, the hostname and username are replaced
+    /// wherever they appear and process command lines are left out, since
+    /// arguments often carry paths, tokens or URLs.
+    pub fn render(&self, format: ReportFormat, redact: bool) -> String {
+        let mut report = self.clone();
+        if redact {
+            for process in &mut report.top_processes {
+                process.command.clear();
+            }
+        }
+        
+        let rendered = match format {
+            ReportFormat::Markdown => report.to_markdown(),
+            ReportFormat::Json => serde_json::to_string_pretty(&report)
+                .unwrap_or_else(|e| format!("{{\"error\": \"Failed to serialize report: {}\"}}", e)),
+        };
+        
+        if redact { self.redact(&rendered) } else { rendered }
+    }
+    
+    /// Whole-word replacement, so a short username doesn't mangle other words.
+    /// The hostname goes first because it often contains the username.
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (value, placeholder) in [(&self.hostname, HOSTNAME_PLACEHOLDER), (&self.username, USERNAME_PLACEHOLDER)] {
+            if value.trim().is_empty() {
+                continue;
+            }
+            if let Ok(pattern) = Regex::new(&format!(r"\b{}\b", regex::escape(value))) {
+                text = pattern.replace_all(&text, placeholder).into_owned();
+            }
+        }
+        text
+    }
+    
+    fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        
+        md.push_str(&format!("# System Report: {}\n\n", self.hostname));
+        md.push_str(&format!("Generated {} by AI SysAdmin Supreme {}\n\n", self.generated_at.format("%Y-%m-%d %H:%M:%S UTC"), self.app_version));
+        
+        md.push_str("## Hardware\n\n");
+        md.push_str(&format!("- **OS:** {} (kernel {})\n", self.distro, self.kernel));
+        md.push_str(&format!("- **User:** {}\n", self.username));
+        md.push_str(&format!(
+            "- **CPU:** {} ({} cores, {} threads{})\n",
+            self.cpu.model, self.cpu.physical_cores, self.cpu.threads, if self.cpu.hybrid { ", hybrid P/E" } else { "" }
+        ));
+        if let (Some(base), Some(boost)) = (self.cpu.base_freq_mhz, self.cpu.boost_freq_mhz) {
+            md.push_str(&format!("- **CPU clocks:** {} MHz base, {} MHz boost\n", base, boost));
+        }
+        md.push_str(&format!("- **Memory:** {:.1} GB\n", self.memory_total_gb));
+        for gpu in &self.gpus {
+            md.push_str(&format!(
+                "- **GPU:** {} ({} / {} MB VRAM, {:.0}°C, {:.0} W)\n",
+                gpu.name, gpu.memory_used / (1024 * 1024), gpu.memory_total / (1024 * 1024), gpu.temperature, gpu.power_draw
+            ));
+        }
+        for device in &self.storage {
+            md.push_str(&format!(
+                "- **Disk:** {} {:?}, {} / {} GB, health {}\n",
+                device.device_name, device.device_type, device.used_gb, device.total_gb, device.health_status
+            ));
+        }
+        md.push('\n');
+        
+        md.push_str("## Current Metrics\n\n");
+        match &self.metrics {
+            Some(metrics) => {
+                md.push_str(&format!("- **CPU usage:** {:.1}%\n", metrics.cpu_usage));
+                md.push_str(&format!("- **Memory usage:** {:.1}%\n", metrics.memory_usage));
+                if let Some(stall) = metrics.pressure.memory_stall_percent() {
+                    md.push_str(&format!("- **Memory pressure:** {:.1}% stalled (avg60)\n", stall));
+                }
+                md.push_str(&format!("- **Disk usage:** {:.1}%\n", metrics.disk_usage));
+                md.push_str(&format!("- **CPU temperature:** {:.1}°C\n", metrics.temperature));
+                md.push_str(&format!("- **GPU temperature:** {:.1}°C\n", metrics.gpu_temp));
+            }
+            None => md.push_str("No metrics sample available.\n"),
+        }
+        md.push('\n');
+        
+        md.push_str("## Configuration\n\n");
+        if self.configuration.is_empty() {
+            md.push_str("System controller not available.\n");
+        } else {
+            md.push_str("| Setting | Value |\n|---|---|\n");
+            for (key, value) in &self.configuration {
+                md.push_str(&format!("| {} | {} |\n", key, markdown_cell(value)));
+            }
+        }
+        md.push('\n');
+        
+        md.push_str("## Top Processes\n\n");
+        md.push_str("| PID | Name | CPU % | Memory % | Command |\n|---|---|---|---|---|\n");
+        for process in &self.top_processes {
+            md.push_str(&format!(
+                "| {} | {} | {:.1} | {:.1} | {} |\n",
+                process.pid, markdown_cell(&process.name), process.cpu_usage, process.memory_percent, markdown_cell(&process.command)
+            ));
+        }
+        md.push('\n');
+        
+        md.push_str("## Recent Alerts\n\n");
+        if self.recent_alerts.is_empty() {
+            md.push_str("None.\n");
+        }
+        for alert in &self.recent_alerts {
+            let raised_at = Utc.timestamp_opt(alert.timestamp as i64, 0).single()
+                .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default();
+            md.push_str(&format!("- `{}` **{}**: {}\n", raised_at, alert.kind, alert.message));
+        }
+        md.push('\n');
+        
+        md.push_str("## Recent Recommendations\n\n");
+        if self.recommendations.is_empty() {
+            md.push_str("None.\n");
+        }
+        for recommendation in &self.recommendations {
+            md.push_str(&format!(
+                "- **{}** (priority {}, {:.0}% confidence): {}\n",
+                recommendation.title, recommendation.priority, recommendation.confidence * 100.0, recommendation.description
+            ));
+        }
+        
+        md
+    }
+}
+
+/// Pipes and newlines would break the table row
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
@@ -0,0 +1,111 @@
+// AI State Export - What the engine has learned, as one document
+// Written by AIEngine::export_state for auditing, read back by import_state to seed or reset learning
+
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::ai::{AIRecommendation, UserAction};
+use crate::ai::pattern_recognition::UsagePattern;
+use crate::ai::report::ReportFormat;
+
+/// Preferences listed in the summary, strongest and weakest each
+const SUMMARY_PREFERENCES: usize = 10;
+const SUMMARY_PATTERNS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiStateExport {
+    pub exported_at: DateTime<Utc>,
+    pub app_version: String,
+    /// 0-1 per "<action_type>_<context>"; 0.5 is neutral, higher means it tends to work out
+    pub user_preferences: BTreeMap<String, f64>,
+    pub patterns: Vec<UsagePattern>,
+    /// Recognizer weight per pattern_id
+    #[serde(default)]
+    pub pattern_weights: BTreeMap<String, f64>,
+    /// Derived from `patterns` when exported; ignored on import
+    #[serde(default)]
+    pub pattern_statistics: BTreeMap<String, f64>,
+    /// Actions learned from, oldest first
+    #[serde(default)]
+    pub learned_actions: Vec<UserAction>,
+    /// Latest of each distinct recommendation, oldest first; ignored on import
+    #[serde(default)]
+    pub recent_recommendations: Vec<AIRecommendation>,
+}
+
+impl AiStateExport {
+    /// JSON is the form `import_state` reads back; Markdown is a summary for reading
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.to_summary(),
+            ReportFormat::Json => serde_json::to_string_pretty(self)
+                .unwrap_or_else(|e| format!("{{\"error\": \"Failed to serialize AI state: {}\"}}", e)),
+        }
+    }
+    
+    fn to_summary(&self) -> String {
+        let mut md = String::new();
+        
+        md.push_str("# AI Learning State\n\n");
+        md.push_str(&format!("Exported {} by AI SysAdmin Supreme {}\n\n", self.exported_at.format("%Y-%m-%d %H:%M:%S UTC"), self.app_version));
+        md.push_str(&format!(
+            "{} preferences, {} patterns, {} learned actions, {} recent recommendations\n\n",
+            self.user_preferences.len(), self.patterns.len(), self.learned_actions.len(), self.recent_recommendations.len()
+        ));
+        
+        let mut preferences: Vec<(&String, &f64)> = self.user_preferences.iter().collect();
+        preferences.sort_by(|a, b| b.1.total_cmp(a.1));
+        md.push_str("## Most Preferred\n\n");
+        push_preferences(&mut md, preferences.iter().take(SUMMARY_PREFERENCES).filter(|(_, value)| **value > 0.5));
+        md.push_str("## Least Preferred\n\n");
+        push_preferences(&mut md, preferences.iter().rev().take(SUMMARY_PREFERENCES).filter(|(_, value)| **value < 0.5));
+        
+        md.push_str("## Strongest Patterns\n\n");
+        let mut patterns: Vec<&UsagePattern> = self.patterns.iter().collect();
+        patterns.sort_by(|a, b| (b.confidence * b.frequency).total_cmp(&(a.confidence * a.frequency)));
+        if patterns.is_empty() {
+            md.push_str("None.\n");
+        }
+        for pattern in patterns.iter().take(SUMMARY_PATTERNS) {
+            let actions: Vec<&str> = pattern.triggers.iter().map(|trigger| trigger.action.as_str()).collect();
+            md.push_str(&format!(
+                "- **{:?}** `{}`: {:.0}% confidence, frequency {:.2}, {}:00-{}:00, last seen {}{}\n",
+                pattern.pattern_type, pattern.pattern_id, pattern.confidence * 100.0, pattern.frequency,
+                pattern.context.time_range.0, pattern.context.time_range.1, pattern.last_seen.format("%Y-%m-%d"),
+                if actions.is_empty() { String::new() } else { format!(" → {}", actions.join(", ")) }
+            ));
+        }
+        md.push('\n');
+        
+        md.push_str("## Pattern Statistics\n\n");
+        for (name, value) in &self.pattern_statistics {
+            md.push_str(&format!("- {}: {:.2}\n", name, value));
+        }
+        md.push('\n');
+        
+        md.push_str("## Recent Recommendations\n\n");
+        if self.recent_recommendations.is_empty() {
+            md.push_str("None.\n");
+        }
+        for recommendation in self.recent_recommendations.iter().rev() {
+            md.push_str(&format!(
+                "- **{}** (priority {}, {:.0}% confidence): {}\n",
+                recommendation.title, recommendation.priority, recommendation.confidence * 100.0, recommendation.reasoning
+            ));
+        }
+        
+        md
+    }
+}
+
+fn push_preferences<'a>(md: &mut String, preferences: impl Iterator<Item = &'a (&'a String, &'a f64)>) {
+    let mut any = false;
+    for (key, value) in preferences {
+        md.push_str(&format!("- `{}`: {:.2}\n", key, value));
+        any = true;
+    }
+    if !any {
+        md.push_str("None.\n");
+    }
+    md.push('\n');
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use crate::ai::WorkloadType;
+use crate::ProcessCandidate;
+
+/// What classification needs from an entry of the process list
+pub trait ProcessSample {
+    fn name(&self) -> &str;
+    /// Percent of one core
+    fn cpu_usage(&self) -> f32;
+}
+
+impl ProcessSample for ProcessCandidate {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn cpu_usage(&self) -> f32 {
+        self.cpu_usage
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadSignature {
+    pub workload: WorkloadType,
+    pub process_names: Vec<String>,
+    pub weight: f64,
+}
+
+/// Maps the running process list to a `WorkloadType` by weighting each
+/// matching process with its CPU usage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadClassifier {
+    pub signatures: Vec<WorkloadSignature>,
+    /// Total CPU% across matched processes below which the machine counts as idle
+    pub idle_cpu_threshold: f64,
+    /// Baseline score a matching process gets even when it's using no CPU
+    pub presence_score: f64,
+}
+
+impl Default for WorkloadClassifier {
+    fn default() -> Self {
+        let signature = |workload, names: &[&str], weight| WorkloadSignature {
+            workload,
+            process_names: names.iter().map(|n| n.to_string()).collect(),
+            weight,
+        };
+        
+        Self {
+            signatures: vec![
+                signature(WorkloadType::Gaming, &["steam", "lutris", "gamescope", "wine", "proton", "heroic"], 1.5),
+                signature(WorkloadType::Development, &["cargo", "rustc", "gcc", "clang", "node", "code", "nvim", "idea"], 1.0),
+                signature(WorkloadType::Media, &["ffmpeg", "obs", "kdenlive", "vlc", "mpv", "blender"], 1.0),
+                // Maintenance jobs are short-lived but should win while they run
+                signature(WorkloadType::SystemMaintenance, &["pacman", "paru", "yay", "makepkg", "snapper", "rsync", "borg", "restic"], 2.0),
+            ],
+            idle_cpu_threshold: 5.0,
+            presence_score: 1.0,
+        }
+    }
+}
+
+impl WorkloadClassifier {
+    pub fn new(signatures: Vec<WorkloadSignature>) -> Self {
+        Self {
+            signatures,
+            ..Self::default()
+        }
+    }
+    
+    pub fn classify<P: ProcessSample>(&self, processes: &[P]) -> WorkloadType {
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        // Unrelated load (an indexer, a browser tab) shouldn't make a quiet game or editor count as busy
+        let mut matched_cpu = 0.0;
+        for process in processes {
+            let name = process.name().to_lowercase();
+            let mut matched = false;
+            for (index, signature) in self.signatures.iter().enumerate() {
+                if signature.process_names.iter().any(|n| name.contains(n.as_str())) {
+                    let score = (process.cpu_usage() as f64 + self.presence_score) * signature.weight;
+                    *scores.entry(index).or_insert(0.0) += score;
+                    matched = true;
+                }
+            }
+            if matched {
+                matched_cpu += process.cpu_usage() as f64;
+            }
+        }
+        
+        if matched_cpu < self.idle_cpu_threshold {
+            return WorkloadType::Idle;
+        }
+        
+        let best = scores.into_iter().max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((index, score)) => {
+                let workload = self.signatures[index].workload.clone();
+                debug!("🎯 Classified workload as {:?} (score {:.1})", workload, score);
+                workload
+            }
+            None => WorkloadType::Idle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn process(name: &str, cpu_usage: f32) -> ProcessCandidate {
+        ProcessCandidate {
+            pid: 1000,
+            name: name.to_string(),
+            cpu_usage,
+            memory_bytes: 0,
+        }
+    }
+    
+    #[test]
+    fn unrelated_load_does_not_count_toward_idle_threshold() {
+        let classifier = WorkloadClassifier::default();
+        
+        // A busy indexer next to an idle editor is still idle as far as workloads go
+        let background = [process("baloo_file_extractor", 80.0), process("nvim", 0.5)];
+        assert_eq!(classifier.classify(&background), WorkloadType::Idle);
+        
+        let compiling = [process("baloo_file_extractor", 80.0), process("rustc", 40.0)];
+        assert_eq!(classifier.classify(&compiling), WorkloadType::Development);
+    }
+}
@@ -3,17 +3,23 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::io::{Read, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::path::PathBuf;
 use std::fs;
 use std::env;
 
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio::time::interval;
 use tracing::{info, warn, error, debug};
 
 use crate::{SystemMetrics, AIRecommendation, DiskInfo, FanStatus};
+use crate::action_log::{ActionLog, PlannedAction};
+use crate::config;
+use crate::database::Database;
+use crate::monitoring_system::OomEvent;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupRecommendation {
@@ -57,6 +63,204 @@ pub struct OptimizationTarget {
     pub priority: u8,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionResult {
+    pub method: String,
+    pub level: u8,
+    pub original_bytes: u64,
+    pub compressed_bytes: u64,
+    pub ratio: f64,
+    pub throughput_mb_s: f64,
+}
+
+/// Measured compression results for this machine, persisted between runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionBenchmark {
+    pub measured_at: u64,
+    pub results: Vec<CompressionResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub prior_average: f64,
+    pub recent_average: f64,
+    pub change_percent: f64,
+    pub direction: String,
+}
+
+/// Recent samples compared against the samples just before them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceDelta {
+    pub recent_samples: usize,
+    pub prior_samples: usize,
+    pub recent_window_secs: i64,
+    pub metrics: Vec<MetricDelta>,
+}
+
+impl PerformanceDelta {
+    pub fn get(&self, metric: &str) -> Option<&MetricDelta> {
+        self.metrics.iter().find(|m| m.metric == metric)
+    }
+    
+    /// Human-readable evidence, e.g. "memory usage has risen 18% over the last 10 minutes"
+    pub fn describe(&self, metric: &str) -> Option<String> {
+        let delta = self.get(metric)?;
+        let window = if self.recent_window_secs >= 120 {
+            format!("the last {} minutes", self.recent_window_secs / 60)
+        } else {
+            format!("the last {} samples", self.recent_samples)
+        };
+        let verb = if delta.change_percent >= 0.0 { "risen" } else { "fallen" };
+        Some(format!("{} has {} {:.0}% over {}", delta.metric, verb, delta.change_percent.abs(), window))
+    }
+}
+
+/// Projected time until a filesystem fills, from its recent usage trend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskForecast {
+    pub mount: String,
+    pub used_percent: f64,
+    pub rate_gb_per_day: f64,
+    pub days_until_full: f64,
+    pub full_at: DateTime<Utc>,
+    pub samples: usize,
+}
+
+/// A reading behind a recommendation, against the limit its rule uses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerMetric {
+    pub name: String,
+    pub value: f64,
+    pub threshold: Option<f64>,
+    pub unit: String,
+}
+
+/// How consistently a rule's condition has held over the recent samples
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchedPattern {
+    pub description: String,
+    pub samples: usize,
+    /// Share of `samples` that met the condition
+    pub frequency: f64,
+    /// Consecutive latest samples meeting it over the auto-apply trigger count;
+    /// 1.0 means auto-apply would act on it
+    pub confidence: f64,
+}
+
+/// Everything behind a recommendation and exactly what applying it would do,
+/// without doing it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecommendationExplanation {
+    pub recommendation: AIRecommendation,
+    pub triggering_metrics: Vec<TriggerMetric>,
+    pub matched_patterns: Vec<MatchedPattern>,
+    /// Recent change per metric, as evidence that this isn't a one-off reading
+    pub trends: PerformanceDelta,
+    pub expected_impact: String,
+    pub planned_actions: Vec<PlannedAction>,
+    /// The writes in `planned_actions` as shell commands, in the order they would run
+    pub commands: Vec<String>,
+    /// Auto-applicable and auto-apply is enabled in the config
+    pub would_auto_apply: bool,
+}
+
+/// How far back the fill-rate trend looks
+const DISK_FORECAST_WINDOW_DAYS: i64 = 7;
+/// Fewer samples, or a shorter span, is too noisy to extrapolate
+const DISK_FORECAST_MIN_SAMPLES: usize = 10;
+const DISK_FORECAST_MIN_SPAN_SECS: i64 = 3600;
+/// Growth below this is treated as flat
+const DISK_FORECAST_MIN_GB_PER_DAY: f64 = 0.01;
+/// Warn when a filesystem is projected to fill within this many days
+const DISK_FULL_WARNING_DAYS: f64 = 7.0;
+
+/// Samples the temperature trend is fitted over
+const TEMPERATURE_TREND_SAMPLES: usize = 10;
+/// How far ahead the "rising fast" rule looks
+const TEMPERATURE_PREDICTION_HORIZON_SECS: u64 = 60;
+/// 15°C a minute; slower climbs are left to the fan curve
+const RISING_FAST_DEGREES_PER_SEC: f64 = 0.25;
+/// Memory usage (%) above which "High Memory Usage" fires
+const HIGH_MEMORY_PERCENT: f64 = 80.0;
+/// Samples an explanation's patterns are measured over
+const EXPLAIN_SAMPLES: usize = 10;
+
+/// Which side of its hysteresis band an auto-applied rule is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleCondition {
+    Triggered,
+    Holding,
+    Cleared,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoApplyDecision {
+    Apply,
+    Revert,
+    Wait,
+}
+
+#[derive(Debug, Default)]
+struct RuleState {
+    active: bool,
+    streak: usize,
+}
+
+/// Debounces auto-applied rules so a reading hovering around a threshold
+/// doesn't flap the fans. A rule applies only after `trigger_samples`
+/// consecutive triggered readings, reverts only after `clear_samples`
+/// consecutive cleared ones, and never changes state twice within `cooldown`.
+#[derive(Debug)]
+pub struct AutoApplyGuard {
+    pub trigger_samples: usize,
+    pub clear_samples: usize,
+    pub cooldown: Duration,
+    pub last_applied: HashMap<String, SystemTime>,
+    rules: HashMap<String, RuleState>,
+}
+
+impl AutoApplyGuard {
+    pub fn new(trigger_samples: usize, clear_samples: usize, cooldown: Duration) -> Self {
+        Self {
+            trigger_samples,
+            clear_samples,
+            cooldown,
+            last_applied: HashMap::new(),
+            rules: HashMap::new(),
+        }
+    }
+    
+    pub fn observe(&mut self, rule: &str, condition: RuleCondition, now: SystemTime) -> AutoApplyDecision {
+        let state = self.rules.entry(rule.to_string()).or_default();
+        
+        // A Holding reading breaks the streak either way: it's neither clearly hot nor clearly cool
+        let wanted = if state.active { RuleCondition::Cleared } else { RuleCondition::Triggered };
+        state.streak = if condition == wanted { state.streak + 1 } else { 0 };
+        
+        let required = if state.active { self.clear_samples } else { self.trigger_samples };
+        if state.streak < required {
+            return AutoApplyDecision::Wait;
+        }
+        if let Some(last) = self.last_applied.get(rule) {
+            if now.duration_since(*last).unwrap_or_default() < self.cooldown {
+                return AutoApplyDecision::Wait;
+            }
+        }
+        
+        state.streak = 0;
+        state.active = !state.active;
+        self.last_applied.insert(rule.to_string(), now);
+        
+        if state.active { AutoApplyDecision::Apply } else { AutoApplyDecision::Revert }
+    }
+    
+    /// Every rule observed so far
+    pub fn known_rules(&self) -> impl Iterator<Item = &String> {
+        self.rules.keys()
+    }
+}
+
 pub struct AIOptimizer {
     pub enabled: bool,
     pub sensitivity_level: f64,
@@ -72,6 +276,15 @@ pub struct AIOptimizer {
     pub backup_sizes: HashMap<String, Vec<u64>>,
     pub user_preferences: HashMap<String, serde_json::Value>,
     pub system_performance_history: Vec<SystemMetrics>,
+    pub compression_benchmark: Option<CompressionBenchmark>,
+    /// What the backups read from (`BackupManager::source_paths`), which the
+    /// compression benchmark samples; the quick-backup folders until set
+    pub backup_sources: Vec<PathBuf>,
+    pub oom_events: Vec<OomEvent>,
+    
+    // Auto-apply hysteresis; auto_applied maps a rule to the recommendation id to undo
+    pub auto_apply_guard: AutoApplyGuard,
+    pub auto_applied: HashMap<String, String>,
     
     // AI parameters
     pub last_analysis: Option<SystemTime>,
@@ -83,10 +296,13 @@ pub struct AIOptimizer {
     pub work_dir: PathBuf,
     pub config_dir: PathBuf,
     pub data_dir: PathBuf,
+    
+    // Shared with the other AI components
+    database: Database,
 }
 
 impl AIOptimizer {
-    pub async fn new_for_i9_13900hx() -> Result<Self> {
+    pub async fn new_for_i9_13900hx(database: Database) -> Result<Self> {
         info!("🧠 Initializing AI Optimizer for i9-13900HX system");
         
         // Setup relative working directories
@@ -134,6 +350,11 @@ impl AIOptimizer {
             backup_sizes: HashMap::new(),
             user_preferences: HashMap::new(),
             system_performance_history: Vec::new(),
+            compression_benchmark: None,
+            backup_sources: Self::default_backup_sources(),
+            oom_events: Vec::new(),
+            auto_apply_guard: AutoApplyGuard::new(3, 3, Duration::from_secs(600)),
+            auto_applied: HashMap::new(),
             last_analysis: None,
             analysis_interval: Duration::from_secs(300), // 5 minutes
             learning_rate: 0.1,
@@ -141,9 +362,18 @@ impl AIOptimizer {
             work_dir,
             config_dir,
             data_dir,
+            database,
         })
     }
     
+    /// The folders a quick backup takes
+    fn default_backup_sources() -> Vec<PathBuf> {
+        match env::var("HOME") {
+            Ok(home) => vec![PathBuf::from(&home).join("Documents"), PathBuf::from(&home).join("Desktop")],
+            Err(_) => Vec::new(),
+        }
+    }
+    
     pub async fn start_analysis_engine(&mut self) -> Result<()> {
         info!("🔍 Starting AI analysis engine");
         self.enabled = true;
@@ -282,9 +512,18 @@ impl AIOptimizer {
     async fn evaluate_compression_options(&mut self) -> Result<()> {
         debug!("🗜️ Evaluating compression options");
         
-        // AI logic for optimal compression based on system capabilities
-        // For i9-13900HX: high CPU, recommend zstd for best balance
-        let optimal_compression = "zstd".to_string();
+        // Measure once on the data the backups compress; the result is persisted and reused
+        if self.compression_benchmark.is_none() {
+            let results = self.benchmark_compression(&self.backup_sources).await;
+            if !results.is_empty() {
+                self.compression_benchmark = Some(CompressionBenchmark {
+                    measured_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+                    results,
+                });
+            }
+        }
+        
+        let optimal_compression = self.get_optimal_compression_method();
         let compression_level = self.get_optimal_compression_level();
         
         debug!("💡 Optimal compression: {} level {}", optimal_compression, compression_level);
@@ -292,7 +531,159 @@ impl AIOptimizer {
         Ok(())
     }
     
+    /// Compress a few MB sampled from `sample_paths` with gzip, zstd and lz4 at a
+    /// couple of levels each, measuring ratio and throughput on this CPU. Results
+    /// are saved to `data_dir/compression_benchmark.json`.
+    pub async fn benchmark_compression(&self, sample_paths: &[PathBuf]) -> Vec<CompressionResult> {
+        // Reading and compressing megabytes takes seconds; keep it off the async workers
+        let sample_paths = sample_paths.to_vec();
+        let results = match tokio::task::spawn_blocking(move || Self::measure_compression(&sample_paths)).await {
+            Ok(results) => results,
+            Err(e) => {
+                warn!("⚠️ Compression benchmark task failed: {}", e);
+                return Vec::new();
+            }
+        };
+        if results.is_empty() {
+            return results;
+        }
+        
+        let benchmark = CompressionBenchmark {
+            measured_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            results: results.clone(),
+        };
+        let benchmark_file = self.data_dir.join("compression_benchmark.json");
+        match serde_json::to_string_pretty(&benchmark) {
+            Ok(json) => {
+                if let Err(e) = fs::write(&benchmark_file, json) {
+                    warn!("⚠️ Failed to save compression benchmark: {}", e);
+                }
+            }
+            Err(e) => warn!("⚠️ Failed to serialize compression benchmark: {}", e),
+        }
+        
+        results
+    }
+    
+    /// The benchmark itself: blocking reads and compression
+    fn measure_compression(sample_paths: &[PathBuf]) -> Vec<CompressionResult> {
+        const SAMPLE_BUDGET: usize = 8 * 1024 * 1024;
+        
+        let mut sample = Vec::new();
+        for path in sample_paths {
+            Self::collect_sample(path, &mut sample, SAMPLE_BUDGET);
+            if sample.len() >= SAMPLE_BUDGET {
+                break;
+            }
+        }
+        
+        if sample.is_empty() {
+            warn!("⚠️ No readable files to benchmark compression on");
+            return Vec::new();
+        }
+        
+        info!("🗜️ Benchmarking compression on {} KiB of sample data", sample.len() / 1024);
+        
+        let candidates: [(&str, u8); 6] = [
+            ("gzip", 1),
+            ("gzip", 6),
+            ("zstd", 3),
+            ("zstd", 9),
+            ("zstd", 19),
+            ("lz4", 1),
+        ];
+        
+        let mut results = Vec::new();
+        for (method, level) in candidates.iter() {
+            let started = Instant::now();
+            let compressed = match Self::compress_sample(method, *level, &sample) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    warn!("⚠️ {} level {} benchmark failed: {}", method, level, e);
+                    continue;
+                }
+            };
+            let seconds = started.elapsed().as_secs_f64().max(1e-6);
+            
+            results.push(CompressionResult {
+                method: method.to_string(),
+                level: *level,
+                original_bytes: sample.len() as u64,
+                compressed_bytes: compressed.len() as u64,
+                ratio: compressed.len() as f64 / sample.len() as f64,
+                throughput_mb_s: sample.len() as f64 / (1024.0 * 1024.0) / seconds,
+            });
+        }
+        
+        results
+    }
+    
+    /// Append file contents under `path` to `sample` until `budget` bytes are collected
+    fn collect_sample(path: &PathBuf, sample: &mut Vec<u8>, budget: usize) {
+        if sample.len() >= budget {
+            return;
+        }
+        
+        if path.is_file() {
+            if let Ok(file) = fs::File::open(path) {
+                let remaining = (budget - sample.len()) as u64;
+                // At most 1 MiB per file so the sample mixes several file types
+                let _ = file.take(remaining.min(1024 * 1024)).read_to_end(sample);
+            }
+        } else if path.is_dir() {
+            if let Ok(entries) = fs::read_dir(path) {
+                for entry in entries.flatten() {
+                    // Don't follow symlinks; they can loop back into the tree
+                    if entry.file_type().map(|t| t.is_symlink()).unwrap_or(true) {
+                        continue;
+                    }
+                    Self::collect_sample(&entry.path(), sample, budget);
+                    if sample.len() >= budget {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    
+    fn compress_sample(method: &str, level: u8, data: &[u8]) -> Result<Vec<u8>> {
+        match method {
+            "gzip" => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(level as u32));
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            "zstd" => Ok(zstd::stream::encode_all(data, level as i32)?),
+            "lz4" => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            other => Err(anyhow!("Unknown compression method: {}", other)),
+        }
+    }
+    
+    /// The measured result that best fits the current free-space ratio: favour ratio
+    /// when space is scarce, throughput when it is plentiful
+    fn best_measured_compression(&self) -> Option<&CompressionResult> {
+        let results = &self.compression_benchmark.as_ref()?.results;
+        let storage_ratio = self.system_analysis.available_space as f64 /
+                           self.system_analysis.total_disk_space.max(1) as f64;
+        let space_weight = (1.0 - storage_ratio).clamp(0.0, 1.0);
+        let max_throughput = results.iter().map(|r| r.throughput_mb_s).fold(0.0, f64::max).max(1e-6);
+        
+        let score = |r: &CompressionResult| {
+            space_weight * (1.0 - r.ratio) + (1.0 - space_weight) * (r.throughput_mb_s / max_throughput)
+        };
+        
+        results.iter().max_by(|a, b| score(a).partial_cmp(&score(b)).unwrap_or(std::cmp::Ordering::Equal))
+    }
+    
     fn get_optimal_compression_level(&self) -> u8 {
+        if let Some(measured) = self.best_measured_compression() {
+            return measured.level;
+        }
+        
         // AI logic for compression level based on available space and CPU
         let storage_ratio = self.system_analysis.available_space as f64 / 
                            self.system_analysis.total_disk_space.max(1) as f64;
@@ -383,6 +774,10 @@ impl AIOptimizer {
     }
     
     fn get_optimal_compression_method(&self) -> String {
+        if let Some(measured) = self.best_measured_compression() {
+            return measured.method.clone();
+        }
+        
         // AI logic for compression method based on system analysis
         let cpu_performance_score = 1.0; // i9-13900HX is high performance
         let storage_ratio = self.system_analysis.available_space as f64 / 
@@ -532,11 +927,80 @@ impl AIOptimizer {
         Ok(())
     }
     
+    /// Compare the average of the most recent `recent_n` samples with the `prior_n`
+    /// samples before them. The prior window shrinks if history is short.
+    pub fn compare_windows(&self, recent_n: usize, prior_n: usize) -> PerformanceDelta {
+        let history = &self.system_performance_history;
+        let recent_n = recent_n.min(history.len());
+        let prior_n = prior_n.min(history.len() - recent_n);
+        
+        let recent = &history[history.len() - recent_n..];
+        let prior = &history[history.len() - recent_n - prior_n..history.len() - recent_n];
+        
+        let recent_window_secs = match (recent.first(), recent.last()) {
+            (Some(first), Some(last)) => (last.timestamp - first.timestamp).num_seconds(),
+            _ => 0,
+        };
+        
+        let mut metrics = Vec::new();
+        if !recent.is_empty() && !prior.is_empty() {
+            let series: [(&str, fn(&SystemMetrics) -> f64); 3] = [
+                ("CPU usage", |s| s.cpu_usage as f64),
+                ("memory usage", |s| s.memory_usage),
+                ("CPU temperature", |s| s.cpu_temp as f64),
+            ];
+            
+            for (name, extract) in series.iter() {
+                let prior_values: Vec<f64> = prior.iter().map(extract).collect();
+                let recent_values: Vec<f64> = recent.iter().map(extract).collect();
+                let prior_average = prior_values.iter().sum::<f64>() / prior_values.len() as f64;
+                let recent_average = recent_values.iter().sum::<f64>() / recent_values.len() as f64;
+                
+                let change_percent = if prior_average.abs() > f64::EPSILON {
+                    (recent_average - prior_average) / prior_average * 100.0
+                } else {
+                    0.0
+                };
+                
+                // Direction over both windows uses the same slope as the trend tracker
+                let combined: Vec<f64> = prior_values.into_iter().chain(recent_values).collect();
+                
+                metrics.push(MetricDelta {
+                    metric: name.to_string(),
+                    prior_average,
+                    recent_average,
+                    change_percent,
+                    direction: self.calculate_trend(&combined),
+                });
+            }
+        }
+        
+        PerformanceDelta {
+            recent_samples: recent.len(),
+            prior_samples: prior.len(),
+            recent_window_secs,
+            metrics,
+        }
+    }
+    
     fn calculate_trend(&self, values: &[f64]) -> String {
         if values.len() < 3 {
             return "stable".to_string();
         }
         
+        let slope = Self::trend_slope(values);
+        
+        if slope > 0.5 {
+            "increasing".to_string()
+        } else if slope < -0.5 {
+            "decreasing".to_string()
+        } else {
+            "stable".to_string()
+        }
+    }
+    
+    /// Least-squares slope per sample
+    fn trend_slope(values: &[f64]) -> f64 {
         // Simple linear regression for trend detection
         let n = values.len() as f64;
         let x_sum = (0..values.len()).sum::<usize>() as f64;
@@ -544,14 +1008,50 @@ impl AIOptimizer {
         let xy_sum: f64 = values.iter().enumerate().map(|(i, &y)| i as f64 * y).sum();
         let x2_sum: f64 = (0..values.len()).map(|i| (i * i) as f64).sum();
         
-        let slope = (n * xy_sum - x_sum * y_sum) / (n * x2_sum - x_sum * x_sum);
+        let denominator = n * x2_sum - x_sum * x_sum;
+        if denominator.abs() <= f64::EPSILON {
+            return 0.0;
+        }
+        (n * xy_sum - x_sum * y_sum) / denominator
+    }
+    
+    /// (smoothed current temperature, °C per second) over the recent history.
+    /// Each sample is the median of itself and the two before it, so a single
+    /// spike neither moves the fit nor counts as the current reading.
+    fn temperature_rate(&self) -> Option<(f64, f64)> {
+        let history = &self.system_performance_history;
+        let recent = &history[history.len().saturating_sub(TEMPERATURE_TREND_SAMPLES)..];
+        if recent.len() < 3 {
+            return None;
+        }
         
-        if slope > 0.5 {
-            "increasing".to_string()
-        } else if slope < -0.5 {
-            "decreasing".to_string()
-        } else {
-            "stable".to_string()
+        let raw: Vec<f64> = recent.iter().map(|s| s.cpu_temp as f64).collect();
+        let smoothed: Vec<f64> = (0..raw.len())
+            .map(|i| {
+                let mut window = raw[i.saturating_sub(2)..=i].to_vec();
+                window.sort_by(|a, b| a.total_cmp(b));
+                match window.len() {
+                    3 => window[1],
+                    _ => window.iter().sum::<f64>() / window.len() as f64,
+                }
+            })
+            .collect();
+        
+        let span_secs = (recent[recent.len() - 1].timestamp - recent[0].timestamp).num_milliseconds() as f64 / 1000.0;
+        if span_secs <= 0.0 {
+            return None;
+        }
+        let secs_per_sample = span_secs / (recent.len() - 1) as f64;
+        
+        Some((smoothed[smoothed.len() - 1], Self::trend_slope(&smoothed) / secs_per_sample))
+    }
+    
+    /// CPU temperature `horizon_secs` from now if the short-term trend holds.
+    /// Falls back to the latest reading while there are too few samples to fit.
+    pub fn predict_temperature(&self, horizon_secs: u64) -> f64 {
+        match self.temperature_rate() {
+            Some((current, rate)) => current + rate * horizon_secs as f64,
+            None => self.system_performance_history.last().map(|s| s.cpu_temp as f64).unwrap_or(0.0),
         }
     }
     
@@ -559,13 +1059,25 @@ impl AIOptimizer {
         // Clear previous dynamic recommendations
         self.recommendations.retain(|r| r.category != "Dynamic");
         
+        // Back recommendations with how things moved, not just the current snapshot
+        let delta = self.compare_windows(10, 10);
+        let evidence = |metric: &str| -> String {
+            match delta.get(metric) {
+                Some(d) if d.change_percent.abs() >= 10.0 => {
+                    format!(" ({})", delta.describe(metric).unwrap_or_default())
+                }
+                _ => String::new(),
+            }
+        };
+        
         // CPU usage recommendations
-        if metrics.cpu_usage > 90.0 {
+        let thresholds = config::get().thresholds;
+        if metrics.cpu_usage > thresholds.cpu_usage {
             let rec = AIRecommendation {
                 id: uuid::Uuid::new_v4().to_string(),
                 category: "Dynamic".to_string(),
                 title: "High CPU Usage Detected".to_string(),
-                description: format!("CPU usage at {:.1}% - consider optimization", metrics.cpu_usage),
+                description: format!("CPU usage at {:.1}% - consider optimization{}", metrics.cpu_usage, evidence("CPU usage")),
                 priority: 8,
                 actions: vec![
                     "Switch to performance mode".to_string(),
@@ -579,12 +1091,12 @@ impl AIOptimizer {
         }
         
         // Memory usage recommendations  
-        if metrics.memory_usage > 80.0 {
+        if metrics.memory_usage > HIGH_MEMORY_PERCENT {
             let rec = AIRecommendation {
                 id: uuid::Uuid::new_v4().to_string(),
                 category: "Dynamic".to_string(),
                 title: "High Memory Usage".to_string(),
-                description: format!("Memory usage at {:.1}% - optimization recommended", metrics.memory_usage),
+                description: format!("Memory usage at {:.1}% - optimization recommended{}", metrics.memory_usage, evidence("memory usage")),
                 priority: 7,
                 actions: vec![
                     "Clear system caches".to_string(),
@@ -597,13 +1109,41 @@ impl AIOptimizer {
             self.recommendations.push(rec);
         }
         
+        // An OOM kill after memory had been climbing is a capacity problem, not a one-off
+        if let Some(rec) = self.oom_recommendation()? {
+            self.recommendations.push(rec);
+        }
+        
+        // A fast-filling disk matters long before it crosses the usage threshold
+        for forecast in self.forecast_all_disks().into_iter().filter(|f| f.days_until_full < DISK_FULL_WARNING_DAYS) {
+            let rec = AIRecommendation {
+                id: uuid::Uuid::new_v4().to_string(),
+                category: "Dynamic".to_string(),
+                title: format!("{} will be full in {:.1} days", forecast.mount, forecast.days_until_full),
+                description: format!(
+                    "{} is {:.0}% used and growing {:.1} GB/day; at this rate it fills around {}",
+                    forecast.mount, forecast.used_percent, forecast.rate_gb_per_day,
+                    forecast.full_at.format("%Y-%m-%d %H:%M UTC")
+                ),
+                priority: if forecast.days_until_full < 2.0 { 9 } else { 7 },
+                actions: vec![
+                    "Clean package cache".to_string(),
+                    "Find what is growing".to_string(),
+                    "Move data to another drive".to_string(),
+                ],
+                auto_apply: false,
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            };
+            self.recommendations.push(rec);
+        }
+        
         // Temperature recommendations
-        if metrics.cpu_temp > 80.0 {
+        if (metrics.cpu_temp as f64) > thresholds.cpu_temperature {
             let rec = AIRecommendation {
                 id: uuid::Uuid::new_v4().to_string(),
                 category: "Dynamic".to_string(),
                 title: "High CPU Temperature".to_string(),
-                description: format!("CPU temperature at {:.1}°C - thermal management needed", metrics.cpu_temp),
+                description: format!("CPU temperature at {:.1}°C - thermal management needed{}", metrics.cpu_temp, evidence("CPU temperature")),
                 priority: 9,
                 actions: vec![
                     "Increase fan speeds".to_string(),
@@ -614,52 +1154,328 @@ impl AIOptimizer {
                 timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
             };
             self.recommendations.push(rec);
+        } else if let Some((current, rate)) = self.temperature_rate() {
+            // Still moderate but climbing fast enough to cross the threshold soon;
+            // ramping the fans now is cheaper than throttling later
+            let predicted = self.predict_temperature(TEMPERATURE_PREDICTION_HORIZON_SECS);
+            if rate >= RISING_FAST_DEGREES_PER_SEC && predicted > thresholds.cpu_temperature {
+                let rec = AIRecommendation {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    category: "Dynamic".to_string(),
+                    title: "CPU Temperature Rising Fast".to_string(),
+                    description: format!(
+                        "CPU temperature at {:.1}°C and rising {:.1}°C/min - projected {:.1}°C within {}s",
+                        current, rate * 60.0, predicted, TEMPERATURE_PREDICTION_HORIZON_SECS
+                    ),
+                    priority: 8,
+                    actions: vec![
+                        "Increase fan speeds".to_string(),
+                        "Check for runaway processes".to_string(),
+                    ],
+                    auto_apply: true,
+                    timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+                };
+                self.recommendations.push(rec);
+            }
         }
         
         Ok(())
     }
     
+    /// Time until `mount` fills at its current rate, from a least-squares fit of
+    /// the last week of stored samples. None when the trend is flat or shrinking,
+    /// or there isn't enough history.
+    pub fn forecast_disk_full(&self, mount: &str) -> Option<DiskForecast> {
+        let since = Utc::now() - chrono::Duration::days(DISK_FORECAST_WINDOW_DAYS);
+        let mut history = match self.database.disk_usage_history(since) {
+            Ok(history) => history,
+            Err(e) => {
+                debug!("No disk usage history for forecasting: {}", e);
+                return None;
+            }
+        };
+        Self::forecast_from_samples(mount, &history.remove(mount)?)
+    }
+    
+    /// Forecasts for every mount with history, soonest to fill first
+    pub fn forecast_all_disks(&self) -> Vec<DiskForecast> {
+        let since = Utc::now() - chrono::Duration::days(DISK_FORECAST_WINDOW_DAYS);
+        let history = self.database.disk_usage_history(since).unwrap_or_default();
+        
+        let mut forecasts: Vec<DiskForecast> = history.iter()
+            .filter_map(|(mount, samples)| Self::forecast_from_samples(mount, samples))
+            .collect();
+        forecasts.sort_by(|a, b| a.days_until_full.total_cmp(&b.days_until_full));
+        forecasts
+    }
+    
+    fn forecast_from_samples(mount: &str, samples: &[(DateTime<Utc>, f64)]) -> Option<DiskForecast> {
+        let (first, _) = *samples.first()?;
+        let (last, used_percent) = *samples.last()?;
+        if samples.len() < DISK_FORECAST_MIN_SAMPLES || (last - first).num_seconds() < DISK_FORECAST_MIN_SPAN_SECS {
+            return None;
+        }
+        
+        // Percent used against days since the first sample
+        let points: Vec<(f64, f64)> = samples.iter()
+            .map(|(timestamp, percent)| ((*timestamp - first).num_seconds() as f64 / 86_400.0, *percent))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if variance <= f64::EPSILON {
+            return None;
+        }
+        let percent_per_day = covariance / variance;
+        
+        let stats = nix::sys::statvfs::statvfs(mount).ok()?;
+        let total_gb = stats.blocks() as f64 * stats.fragment_size() as f64 / 1024.0 / 1024.0 / 1024.0;
+        let rate_gb_per_day = percent_per_day / 100.0 * total_gb;
+        if rate_gb_per_day < DISK_FORECAST_MIN_GB_PER_DAY {
+            return None;
+        }
+        
+        let days_until_full = (100.0 - used_percent).max(0.0) / percent_per_day;
+        Some(DiskForecast {
+            mount: mount.to_string(),
+            used_percent,
+            rate_gb_per_day,
+            days_until_full,
+            full_at: last + chrono::Duration::seconds((days_until_full * 86_400.0) as i64),
+            samples: samples.len(),
+        })
+    }
+    
+    /// OOM kills from the monitor; only the last hour is kept for recommendations
+    pub fn record_oom_events(&mut self, events: Vec<OomEvent>) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        self.oom_events.extend(events);
+        self.oom_events.retain(|e| now.saturating_sub(e.timestamp) < 3600);
+    }
+    
+    fn oom_recommendation(&self) -> Result<Option<AIRecommendation>> {
+        let event = match self.oom_events.last() {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+        
+        let history = &self.system_performance_history;
+        let recent_memory: Vec<f64> = history[history.len().saturating_sub(10)..].iter()
+            .map(|s| s.memory_usage)
+            .collect();
+        if self.calculate_trend(&recent_memory) != "increasing" {
+            return Ok(None);
+        }
+        
+        let mut actions = vec![
+            "Enable zram swap (zram-generator, zram-size = ram / 2)".to_string(),
+        ];
+        if event.process_name.contains("ollama") {
+            actions.insert(0, "Set OLLAMA_MAX_LOADED_MODELS=1 so only one model stays in memory".to_string());
+        } else {
+            actions.push("Lower OLLAMA_MAX_LOADED_MODELS if Ollama is running".to_string());
+        }
+        
+        Ok(Some(AIRecommendation {
+            id: uuid::Uuid::new_v4().to_string(),
+            category: "Dynamic".to_string(),
+            title: "Out of Memory After Rising Usage".to_string(),
+            description: format!(
+                "The OOM killer killed {} ({} MB) after memory usage kept climbing{}",
+                event.process_name,
+                event.anon_rss_kb / 1024,
+                event.memory_usage.map(|m| format!(" to {:.1}%", m)).unwrap_or_default()
+            ),
+            priority: 9,
+            actions,
+            auto_apply: false,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        }))
+    }
+    
     pub async fn optimize_system_performance(&mut self) -> Result<()> {
         info!("⚡ Optimizing system performance");
         
-        // Apply auto-applicable recommendations
+        if !config::get().features.auto_apply {
+            debug!("Auto-apply disabled in config");
+            return Ok(());
+        }
+        
+        // Apply auto-applicable recommendations, keyed by title since ids change every pass
         let auto_recommendations: Vec<_> = self.recommendations
             .iter()
             .filter(|r| r.auto_apply)
             .cloned()
             .collect();
         
-        for rec in auto_recommendations {
-            info!("🔧 Auto-applying: {}", rec.title);
-            self.apply_recommendation(&rec).await?;
+        // Every rule seen before is observed each pass, fired or not, so a reading
+        // that bounces across the threshold breaks the streak instead of building it
+        let mut rules: Vec<String> = auto_recommendations.iter().map(|r| r.title.clone()).collect();
+        for rule in self.auto_applied.keys().chain(self.auto_apply_guard.known_rules()) {
+            if !rules.contains(rule) {
+                rules.push(rule.clone());
+            }
+        }
+        
+        let now = SystemTime::now();
+        for rule in rules {
+            let rec = auto_recommendations.iter().find(|r| r.title == rule);
+            let condition = self.rule_condition(&rule, rec.is_some());
+            
+            match self.auto_apply_guard.observe(&rule, condition, now) {
+                AutoApplyDecision::Apply => {
+                    if let Some(rec) = rec {
+                        info!("🔧 Auto-applying: {}", rec.title);
+                        self.apply_recommendation(rec).await?;
+                        self.auto_applied.insert(rule, rec.id.clone());
+                    }
+                }
+                AutoApplyDecision::Revert => {
+                    if let Some(id) = self.auto_applied.remove(&rule) {
+                        info!("↩️ Condition cleared, reverting: {}", rule);
+                        ActionLog::new(self.database.clone()).undo(&id)?;
+                    }
+                }
+                AutoApplyDecision::Wait => {}
+            }
         }
         
         Ok(())
     }
     
-    async fn apply_recommendation(&mut self, rec: &AIRecommendation) -> Result<()> {
-        debug!("🎯 Applying recommendation: {}", rec.title);
+    /// A rule that didn't fire is only Cleared once its reading is clearly past the
+    /// trigger; the thermal rules have to drop below `cpu_temperature_clear`
+    fn rule_condition(&self, rule: &str, fired: bool) -> RuleCondition {
+        if fired {
+            return RuleCondition::Triggered;
+        }
+        
+        let clear_temp = config::get().thresholds.cpu_temperature_clear;
+        match (rule, self.system_performance_history.last()) {
+            ("High CPU Temperature", Some(latest)) if (latest.cpu_temp as f64) >= clear_temp => RuleCondition::Holding,
+            // Boosted fans stay up until the climb has actually been caught
+            ("CPU Temperature Rising Fast", Some(_)) if self.predict_temperature(TEMPERATURE_PREDICTION_HORIZON_SECS) >= clear_temp => RuleCondition::Holding,
+            _ => RuleCondition::Cleared,
+        }
+    }
+    
+    /// Dry run of a current recommendation: the readings and patterns behind it
+    /// and the commands applying it would run
+    pub fn explain_recommendation(&self, id: &str) -> Result<RecommendationExplanation> {
+        let rec = self.recommendations.iter()
+            .find(|r| r.id == id)
+            .ok_or_else(|| anyhow!("No current recommendation with id {}", id))?;
+        
+        let thresholds = config::get().thresholds;
+        let history = &self.system_performance_history;
+        let recent = &history[history.len().saturating_sub(EXPLAIN_SAMPLES)..];
+        let metric = |name: &str, value: f64, threshold: Option<f64>, unit: &str| TriggerMetric {
+            name: name.to_string(),
+            value,
+            threshold,
+            unit: unit.to_string(),
+        };
         
-        for action in &rec.actions {
-            match action.as_str() {
-                "Enable performance CPU governor" => {
-                    // Would set CPU governor in real implementation
-                    debug!("🚀 Setting CPU to performance mode");
-                },
-                "Increase fan speeds" => {
-                    // Would control fans in real implementation
-                    debug!("🌪️ Increasing fan speeds for cooling");
-                },
-                "Clear system caches" => {
-                    // Would clear caches in real implementation
-                    debug!("🧹 Clearing system caches");
-                },
-                _ => {
-                    debug!("📝 Action noted: {}", action);
+        let mut triggering_metrics = Vec::new();
+        let mut matched_patterns = Vec::new();
+        match (rec.title.as_str(), recent.last()) {
+            ("High CPU Usage Detected", Some(latest)) => {
+                triggering_metrics.push(metric("CPU usage", latest.cpu_usage, Some(thresholds.cpu_usage), "%"));
+                matched_patterns.push(self.sample_pattern(
+                    format!("CPU usage above {:.0}%", thresholds.cpu_usage),
+                    recent.iter().map(|s| s.cpu_usage > thresholds.cpu_usage).collect(),
+                ));
+            }
+            ("High Memory Usage", Some(latest)) => {
+                triggering_metrics.push(metric("Memory usage", latest.memory_usage, Some(HIGH_MEMORY_PERCENT), "%"));
+                matched_patterns.push(self.sample_pattern(
+                    format!("Memory usage above {:.0}%", HIGH_MEMORY_PERCENT),
+                    recent.iter().map(|s| s.memory_usage > HIGH_MEMORY_PERCENT).collect(),
+                ));
+            }
+            ("High CPU Temperature", Some(latest)) => {
+                triggering_metrics.push(metric("CPU temperature", latest.cpu_temp as f64, Some(thresholds.cpu_temperature), "°C"));
+                matched_patterns.push(self.sample_pattern(
+                    format!("CPU temperature above {:.0}°C", thresholds.cpu_temperature),
+                    recent.iter().map(|s| (s.cpu_temp as f64) > thresholds.cpu_temperature).collect(),
+                ));
+            }
+            ("CPU Temperature Rising Fast", Some(_)) => {
+                if let Some((current, rate)) = self.temperature_rate() {
+                    triggering_metrics.push(metric("CPU temperature", current, None, "°C"));
+                    triggering_metrics.push(metric("Temperature rise", rate * 60.0, Some(RISING_FAST_DEGREES_PER_SEC * 60.0), "°C/min"));
+                }
+                triggering_metrics.push(metric(
+                    &format!("Projected temperature in {}s", TEMPERATURE_PREDICTION_HORIZON_SECS),
+                    self.predict_temperature(TEMPERATURE_PREDICTION_HORIZON_SECS),
+                    Some(thresholds.cpu_temperature),
+                    "°C",
+                ));
+                matched_patterns.push(self.sample_pattern(
+                    "CPU temperature rising".to_string(),
+                    recent.windows(2).map(|pair| pair[1].cpu_temp > pair[0].cpu_temp).collect(),
+                ));
+            }
+            ("Out of Memory After Rising Usage", Some(latest)) => {
+                triggering_metrics.push(metric("Memory usage", latest.memory_usage, None, "%"));
+                if let Some(event) = self.oom_events.last() {
+                    triggering_metrics.push(metric(&format!("Memory of killed {}", event.process_name), (event.anon_rss_kb / 1024) as f64, None, "MB"));
+                }
+                matched_patterns.push(self.sample_pattern(
+                    "Memory usage rising".to_string(),
+                    recent.windows(2).map(|pair| pair[1].memory_usage > pair[0].memory_usage).collect(),
+                ));
+            }
+            (title, _) => {
+                if let Some(forecast) = self.forecast_all_disks().into_iter().find(|f| title.starts_with(&format!("{} will be full", f.mount))) {
+                    triggering_metrics.push(metric(&format!("{} used", forecast.mount), forecast.used_percent, None, "%"));
+                    triggering_metrics.push(metric(&format!("{} growth", forecast.mount), forecast.rate_gb_per_day, None, "GB/day"));
+                    triggering_metrics.push(metric("Days until full", forecast.days_until_full, Some(DISK_FULL_WARNING_DAYS), "days"));
                 }
             }
         }
         
+        let planned_actions = ActionLog::plan(rec);
+        let commands = planned_actions.iter()
+            .flat_map(|planned| planned.settings.iter().map(|setting| setting.command()))
+            .collect();
+        let changes: usize = planned_actions.iter().map(|planned| planned.settings.len()).sum();
+        let expected_impact = match changes {
+            0 => "Advice only: applying records it but changes no settings".to_string(),
+            n => format!("{} settings change; undo, or the condition clearing after an auto-apply, restores them", n),
+        };
+        
+        Ok(RecommendationExplanation {
+            recommendation: rec.clone(),
+            triggering_metrics,
+            matched_patterns,
+            trends: self.compare_windows(10, 10),
+            expected_impact,
+            planned_actions,
+            commands,
+            would_auto_apply: rec.auto_apply && config::get().features.auto_apply,
+        })
+    }
+    
+    fn sample_pattern(&self, description: String, matches: Vec<bool>) -> MatchedPattern {
+        let met = matches.iter().filter(|&&matched| matched).count();
+        let streak = matches.iter().rev().take_while(|&&matched| matched).count();
+        MatchedPattern {
+            description,
+            samples: matches.len(),
+            frequency: met as f64 / matches.len().max(1) as f64,
+            confidence: (streak as f64 / self.auto_apply_guard.trigger_samples.max(1) as f64).min(1.0),
+        }
+    }
+    
+    async fn apply_recommendation(&mut self, rec: &AIRecommendation) -> Result<()> {
+        debug!("🎯 Applying recommendation: {}", rec.title);
+        
+        // Logged with the previous values so an auto-apply can be undone
+        ActionLog::new(self.database.clone()).apply(rec)?;
+        
         Ok(())
     }
     
@@ -695,17 +1511,30 @@ impl AIOptimizer {
     }
     
     async fn load_learning_data(&mut self) -> Result<()> {
-        let data_file = self.data_dir.join("ai_learning_data.json");
+        let mut data = self.database.learning_values()?;
+        if data.is_empty() {
+            data = self.import_legacy_learning_data()?;
+        }
         
-        if data_file.exists() {
-            let data = fs::read_to_string(&data_file)?;
-            if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&data) {
-                if let Some(prefs) = parsed.get("user_preferences").and_then(|p| p.as_object()) {
-                    for (key, value) in prefs {
-                        self.user_preferences.insert(key.clone(), value.clone());
-                    }
-                }
-                debug!("📚 Loaded AI learning data from {}", data_file.display());
+        if let Some(prefs) = data.get("user_preferences").and_then(|p| p.as_object()) {
+            for (key, value) in prefs {
+                self.user_preferences.insert(key.clone(), value.clone());
+            }
+        }
+        if let Some(durations) = data.get("backup_durations") {
+            self.backup_durations = serde_json::from_value(durations.clone()).unwrap_or_default();
+        }
+        if let Some(sizes) = data.get("backup_sizes") {
+            self.backup_sizes = serde_json::from_value(sizes.clone()).unwrap_or_default();
+        }
+        debug!("📚 Loaded AI learning data from {}", self.database.path().display());
+        
+        let benchmark_file = self.data_dir.join("compression_benchmark.json");
+        if benchmark_file.exists() {
+            let data = fs::read_to_string(&benchmark_file)?;
+            if let Ok(benchmark) = serde_json::from_str::<CompressionBenchmark>(&data) {
+                debug!("📚 Loaded compression benchmark with {} results", benchmark.results.len());
+                self.compression_benchmark = Some(benchmark);
             }
         }
         
@@ -721,13 +1550,37 @@ impl AIOptimizer {
             "performance_trends": self.performance_trends,
         });
         
-        let data_file = self.data_dir.join("ai_learning_data.json");
-        fs::write(&data_file, serde_json::to_string_pretty(&data)?)?;
+        if let Some(entries) = data.as_object() {
+            for (key, value) in entries {
+                self.database.set_learning_value(key, value)?;
+            }
+        }
         
-        debug!("💾 Saved AI learning data to {}", data_file.display());
+        debug!("💾 Saved AI learning data to {}", self.database.path().display());
         Ok(())
     }
     
+    /// Move ai_learning_data.json written by older versions into the database
+    fn import_legacy_learning_data(&self) -> Result<HashMap<String, serde_json::Value>> {
+        let data_file = self.data_dir.join("ai_learning_data.json");
+        if !data_file.exists() {
+            return Ok(HashMap::new());
+        }
+        
+        let parsed: serde_json::Value = serde_json::from_str(&fs::read_to_string(&data_file)?)?;
+        let mut imported = HashMap::new();
+        if let Some(entries) = parsed.as_object() {
+            for (key, value) in entries {
+                self.database.set_learning_value(key, value)?;
+                imported.insert(key.clone(), value.clone());
+            }
+        }
+        
+        fs::rename(&data_file, data_file.with_extension("json.imported"))?;
+        info!("📦 Imported {} learning entries from {}", imported.len(), data_file.display());
+        Ok(imported)
+    }
+    
     pub fn get_system_insights(&self) -> HashMap<String, serde_json::Value> {
         let mut insights = HashMap::new();
         
@@ -740,3 +1593,42 @@ impl AIOptimizer {
         insights
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn observe_all(guard: &mut AutoApplyGuard, conditions: &[RuleCondition]) -> Vec<AutoApplyDecision> {
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        conditions.iter().enumerate()
+            .map(|(i, condition)| guard.observe("High CPU Temperature", *condition, start + Duration::from_secs(i as u64 * 60)))
+            .collect()
+    }
+    
+    #[test]
+    fn oscillating_reading_never_applies() {
+        // 81, 70, 81, 70, 81 with the trigger at 80 and clear at 75: the passes at 70
+        // didn't fire, and are observed as Cleared
+        let mut guard = AutoApplyGuard::new(3, 3, Duration::ZERO);
+        let readings = [RuleCondition::Triggered, RuleCondition::Cleared, RuleCondition::Triggered, RuleCondition::Cleared, RuleCondition::Triggered];
+        
+        let decisions = observe_all(&mut guard, &readings);
+        
+        assert!(decisions.iter().all(|d| *d == AutoApplyDecision::Wait), "{:?}", decisions);
+    }
+    
+    #[test]
+    fn sustained_reading_applies_then_reverts() {
+        let mut guard = AutoApplyGuard::new(3, 3, Duration::ZERO);
+        let readings = [
+            RuleCondition::Triggered, RuleCondition::Triggered, RuleCondition::Triggered,
+            RuleCondition::Holding, RuleCondition::Cleared, RuleCondition::Cleared, RuleCondition::Cleared,
+        ];
+        
+        let decisions = observe_all(&mut guard, &readings);
+        
+        assert_eq!(decisions[2], AutoApplyDecision::Apply);
+        assert_eq!(decisions[6], AutoApplyDecision::Revert);
+        assert_eq!(decisions.iter().filter(|d| **d != AutoApplyDecision::Wait).count(), 2);
+    }
+}
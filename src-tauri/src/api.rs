@@ -0,0 +1,341 @@
+// REST API - Opt-in HTTP access to metrics and recommendations (--serve)
+// For custom frontends and home-automation dashboards; binds to localhost unless told otherwise
+// Live streaming and applying recommendations need the per-session token, so a web page
+// the user happens to have open can't drive them through the browser
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::commands::{apply_recommendation_by_id, get_ai_recommendations};
+use crate::config::{self, Config};
+use crate::{AIRecommendation, LiveEvent, SystemMetrics, SystemMonitor};
+
+pub const DEFAULT_API_ADDR: &str = "127.0.0.1:8080";
+
+/// History requests are capped at what the monitor keeps anyway
+const MAX_HISTORY_LIMIT: usize = 1000;
+
+/// Header carrying the session token; browsers can't set it cross-site without a
+/// CORS preflight, which this API never answers
+pub const TOKEN_HEADER: &str = "x-session-token";
+/// WebSocket clients in a browser can't set headers, so they pass the token as ?token=
+const TOKEN_QUERY: &str = "token";
+
+/// Origins the app's own UI is loaded from: the bundled webview and the dev server
+const UI_ORIGINS: &[&str] = &["tauri://localhost", "https://tauri.localhost", "http://tauri.localhost", "http://localhost:5173"];
+
+static SESSION_TOKEN: OnceLock<String> = OnceLock::new();
+
+/// Random per-process token every protected request has to carry
+pub fn session_token() -> &'static str {
+    SESSION_TOKEN.get_or_init(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+/// Where the API is served and the token it expects, for the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiSession {
+    pub url: String,
+    pub token: String,
+}
+
+static SESSION: OnceLock<ApiSession> = OnceLock::new();
+
+/// Set once the API is listening
+pub fn session() -> Option<ApiSession> {
+    SESSION.get().cloned()
+}
+
+/// Token file for clients outside the app, readable only by this user
+pub fn token_path() -> std::path::PathBuf {
+    Config::path().with_file_name("api-token")
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, (StatusCode, String)>;
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub system_monitor: Arc<Mutex<SystemMonitor>>,
+    pub events: broadcast::Sender<LiveEvent>,
+    /// Address the API is bound to; requests naming any other host are refused
+    pub addr: SocketAddr,
+    /// Extra names from `api.allowed_hosts`, for reaching a non-loopback bind by name
+    pub allowed_hosts: Arc<[String]>,
+    pub token: Arc<str>,
+}
+
+impl ApiState {
+    pub fn new(system_monitor: Arc<Mutex<SystemMonitor>>, addr: SocketAddr) -> Self {
+        let events = match system_monitor.lock() {
+            Ok(monitor) => monitor.event_sender(),
+            Err(poisoned) => poisoned.into_inner().event_sender(),
+        };
+        
+        let allowed_hosts = Arc::from(config::get().api.allowed_hosts);
+        Self { system_monitor, events, addr, allowed_hosts, token: Arc::from(session_token()) }
+    }
+    
+    /// The Host header must name this server, which stops DNS rebinding: a page on
+    /// evil.example resolving to 127.0.0.1 still sends Host: evil.example. Beyond the
+    /// bound address and localhost, only names listed in `api.allowed_hosts` pass.
+    fn host_allowed(&self, headers: &HeaderMap) -> bool {
+        let host = match headers.get(header::HOST).and_then(|h| h.to_str().ok()) {
+            Some(host) => host,
+            None => return false,
+        };
+        let port = self.addr.port();
+        host == self.addr.to_string()
+            || ["localhost", "127.0.0.1", "[::1]"].iter().copied()
+                .chain(self.allowed_hosts.iter().map(String::as_str))
+                .any(|name| host.eq_ignore_ascii_case(&format!("{}:{}", name, port)))
+    }
+    
+    /// No Origin means a non-browser client, which the token alone covers; a browser
+    /// always sends one, and only the app's own UI or this server's pages may connect
+    fn origin_allowed(&self, headers: &HeaderMap) -> bool {
+        let origin = match headers.get(header::ORIGIN) {
+            Some(origin) => match origin.to_str() {
+                Ok(origin) => origin,
+                Err(_) => return false,
+            },
+            None => return true,
+        };
+        if UI_ORIGINS.contains(&origin) {
+            return true;
+        }
+        match origin.strip_prefix("http://") {
+            Some(authority) => self.host_allowed(&host_header(authority)),
+            None => false,
+        }
+    }
+    
+    fn token_matches(&self, presented: &str) -> bool {
+        constant_time_eq(presented.as_bytes(), self.token.as_bytes())
+    }
+}
+
+fn host_header(authority: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = authority.parse() {
+        headers.insert(header::HOST, value);
+    }
+    headers
+}
+
+/// Compare without returning early, so response timing doesn't reveal how much of a guess was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn query_token(query: Option<&str>) -> Option<&str> {
+    query?.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == TOKEN_QUERY)
+        .map(|(_, value)| value)
+}
+
+/// Reject requests from foreign hosts or origins; applies to every route
+async fn require_known_host(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    if !state.host_allowed(headers) {
+        warn!("🚫 Refused API request for host {:?}", headers.get(header::HOST));
+        return (StatusCode::FORBIDDEN, "Unknown host").into_response();
+    }
+    if !state.origin_allowed(headers) {
+        warn!("🚫 Refused API request from origin {:?}", headers.get(header::ORIGIN));
+        return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+    }
+    
+    next.run(request).await
+}
+
+/// Reject requests without the session token
+async fn require_session(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    
+    // Only a WebSocket upgrade may put the token in the URL; anything else must use
+    // the header, which a cross-site form or fetch can't send without a preflight
+    let presented = match headers.get(TOKEN_HEADER) {
+        Some(token) => token.to_str().ok(),
+        None if headers.contains_key(header::UPGRADE) => query_token(request.uri().query()),
+        None => None,
+    };
+    if !presented.map(|token| state.token_matches(token)).unwrap_or(false) {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid session token").into_response();
+    }
+    
+    next.run(request).await
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    limit: Option<usize>,
+}
+
+/// Sent by a WebSocket client to throttle metrics; 0 means every sample
+#[derive(Debug, Deserialize)]
+struct StreamSettings {
+    interval_secs: u64,
+}
+
+pub fn router(state: ApiState) -> Router {
+    let protected = Router::new()
+        .route("/api/recommendations/:id/apply", post(apply_recommendation))
+        .route("/ws/metrics", get(metrics_socket))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_session));
+    
+    Router::new()
+        .route("/api/metrics/latest", get(latest_metrics))
+        .route("/api/metrics/history", get(metrics_history))
+        .route("/api/recommendations", get(recommendations))
+        .merge(protected)
+        .layer(middleware::from_fn_with_state(state.clone(), require_known_host))
+        .with_state(state)
+}
+
+/// Serve the API until the listener fails
+pub async fn serve(state: ApiState) -> Result<()> {
+    let addr = state.addr;
+    if !addr.ip().is_loopback() {
+        warn!("⚠️ REST API bound to {}; it is reachable from the network, guarded only by the session token", addr);
+    }
+    
+    let listener = tokio::net::TcpListener::bind(addr).await
+        .context(format!("Failed to bind REST API to {}", addr))?;
+    info!("🌐 REST API listening on http://{}", addr);
+    
+    if let Err(e) = write_token_file(&state.token) {
+        warn!("⚠️ Failed to write API token to {}: {}", token_path().display(), e);
+    }
+    SESSION.get_or_init(|| ApiSession { url: format!("http://{}", addr), token: state.token.to_string() });
+    
+    axum::serve(listener, router(state)).await.context("REST API server failed")
+}
+
+fn write_token_file(token: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    
+    let path = token_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(&path)?;
+    // mode() only applies when the file is created
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(token.as_bytes())
+}
+
+async fn latest_metrics(State(state): State<ApiState>) -> ApiResult<SystemMetrics> {
+    let cached = state.system_monitor.lock()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("System monitor lock poisoned: {}", e)))?
+        .latest_metrics();
+    if let Some(metrics) = cached {
+        return Ok(Json(metrics));
+    }
+    
+    // Nothing sampled yet: collecting reads /proc and sensors, so keep it off the async workers
+    let system_monitor = state.system_monitor.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut monitor = system_monitor.lock()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("System monitor lock poisoned: {}", e)))?;
+        monitor.collect_metrics()
+            .map(Json)
+            .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Metrics task failed: {}", e)))?
+}
+
+async fn metrics_history(State(state): State<ApiState>, Query(query): Query<HistoryQuery>) -> ApiResult<Vec<SystemMetrics>> {
+    let limit = query.limit.unwrap_or(60).min(MAX_HISTORY_LIMIT);
+    let monitor = state.system_monitor.lock()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("System monitor lock poisoned: {}", e)))?;
+    
+    Ok(Json(monitor.recent_metrics(limit)))
+}
+
+async fn recommendations() -> ApiResult<Vec<AIRecommendation>> {
+    get_ai_recommendations().await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn apply_recommendation(State(state): State<ApiState>, Path(id): Path<String>) -> ApiResult<String> {
+    info!("🌐 Applying recommendation {} requested over REST", id);
+    apply_recommendation_by_id(&id, &state.system_monitor)
+        .map(Json)
+        .map_err(|e| {
+            let status = if e == "Recommendation not found" { StatusCode::NOT_FOUND } else { StatusCode::INTERNAL_SERVER_ERROR };
+            (status, e)
+        })
+}
+
+async fn metrics_socket(ws: WebSocketUpgrade, State(state): State<ApiState>) -> Response {
+    let events = state.events.subscribe();
+    ws.on_upgrade(move |socket| stream_live_events(socket, events))
+}
+
+/// Forward live events to one client until it disconnects. Each client has its own
+/// bounded receiver, so a slow one skips the oldest events instead of buffering.
+async fn stream_live_events(mut socket: WebSocket, mut events: broadcast::Receiver<LiveEvent>) {
+    let mut interval = Duration::ZERO;
+    let mut last_metrics: Option<Instant> = None;
+    
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!("WebSocket client lagging, skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                
+                if let LiveEvent::Metrics(_) = event {
+                    if last_metrics.map(|sent| sent.elapsed() < interval).unwrap_or(false) {
+                        continue;
+                    }
+                    last_metrics = Some(Instant::now());
+                }
+                
+                let json = match serde_json::to_string(&event) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        warn!("⚠️ Failed to serialize live event: {}", e);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                match message {
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str::<StreamSettings>(&text) {
+                        Ok(settings) => interval = Duration::from_secs(settings.interval_secs),
+                        Err(e) => debug!("Ignoring WebSocket message {:?}: {}", text, e),
+                    },
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+    
+    debug!("WebSocket client disconnected");
+}
@@ -2,12 +2,15 @@
 // Adapted from ArchBackupPro BackupManager and RestoreManager
 // Complete implementation with no placeholders
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::env;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::{Read, Seek, SeekFrom};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
@@ -17,11 +20,18 @@ use uuid::Uuid;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
 use tar::{Archive, Builder};
 use walkdir::WalkDir;
 use chrono::{DateTime, Utc};
 
 use crate::BackupInfo;
+use crate::config;
+use crate::snapshots;
+
+/// How often verification logs how far it has got
+const VERIFY_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BackupType {
@@ -41,6 +51,30 @@ pub enum CompressionType {
     Lz4,
 }
 
+impl CompressionType {
+    /// As written in the config file; unknown names fall back to gzip
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "none" => CompressionType::None,
+            "zstd" => CompressionType::Zstd,
+            "lz4" => CompressionType::Lz4,
+            _ => CompressionType::Gzip,
+        }
+    }
+}
+
+/// How hard verification looks at an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum VerifyLevel {
+    /// End-of-archive marker and every header; entry data is skipped over, which
+    /// is cheap on plain .tar but still has to decompress compressed archives
+    Quick,
+    /// Reads every entry and checks it against the per-entry SHA-256 manifest
+    /// recorded when the backup was made
+    #[default]
+    Deep,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupConfig {
     pub name: String,
@@ -48,6 +82,10 @@ pub struct BackupConfig {
     pub source_paths: Vec<PathBuf>,
     pub destination_path: PathBuf,
     pub compression: CompressionType,
+    /// gzip 1-9 or zstd 1-22, e.g. the level the AI optimizer measured as best;
+    /// None uses the method's default. lz4 has no levels.
+    #[serde(default)]
+    pub compression_level: Option<u8>,
     pub exclude_patterns: Vec<String>,
     pub include_system_files: bool,
     pub include_home_dir: bool,
@@ -55,6 +93,8 @@ pub struct BackupConfig {
     pub encryption_enabled: bool,
     pub retention_days: u32,
     pub schedule_cron: Option<String>,
+    #[serde(default)]
+    pub verify_level: VerifyLevel,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,6 +137,16 @@ pub struct RestoreOperation {
     pub errors: Vec<String>,
 }
 
+/// One entry of a backup archive, read from its tar header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Path inside the archive, which is where restore puts it under the destination
+    pub path: PathBuf,
+    pub size: u64,
+    pub mtime: u64,
+    pub is_dir: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupSchedule {
     pub id: String,
@@ -107,6 +157,82 @@ pub struct BackupSchedule {
     pub run_count: u32,
 }
 
+/// Outcome of verifying one archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub level: VerifyLevel,
+    pub entries: u64,
+    /// Entry data covered; Quick skips it rather than reading it
+    pub bytes: u64,
+    /// Entries compared against the manifest; 0 for Quick or without a manifest
+    pub checksums_checked: u64,
+    /// Entries whose contents no longer match the manifest
+    pub mismatched: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.entries > 0 && self.mismatched.is_empty()
+    }
+}
+
+/// What scrubbing needs to know about a backup beyond its registry entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupIntegrity {
+    /// SHA-256 of the archive file as written
+    pub sha256: String,
+    /// Full backup an incremental was taken against
+    pub base_backup_id: Option<String>,
+}
+
+/// Result of checking every registered backup. Lists hold backup ids.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScrubReport {
+    pub started_at: u64,
+    pub completed_at: u64,
+    pub checked: usize,
+    pub healthy: usize,
+    /// Readable backups from before checksums were recorded; their checksum is recorded now
+    pub baselined: Vec<String>,
+    pub corrupted: Vec<String>,
+    pub missing: Vec<String>,
+    /// Incrementals whose full backup is corrupted or missing
+    pub unrestorable: Vec<String>,
+}
+
+impl ScrubReport {
+    pub fn is_clean(&self) -> bool {
+        self.corrupted.is_empty() && self.missing.is_empty() && self.unrestorable.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrubSchedule {
+    pub id: String,
+    pub cron_expression: String,
+    pub enabled: bool,
+    pub last_run: Option<u64>,
+    pub next_run: Option<u64>,
+    pub run_count: u32,
+    pub last_report: Option<ScrubReport>,
+}
+
+/// file_changes.json: the change journal and which roots it covers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ChangeJournal {
+    changes: HashMap<PathBuf, SystemTime>,
+    /// Watched roots and when unbroken watching of each began
+    covered_since: HashMap<PathBuf, SystemTime>,
+}
+
+/// Returned from inside a backup or restore loop when the user cancels it
+#[derive(Debug, thiserror::Error)]
+#[error("Operation cancelled")]
+struct OperationCancelled;
+
+/// Cheap to clone: every clone shares the same operation tables and registry,
+/// so background backup tasks can update progress without borrowing the manager.
+#[derive(Clone)]
 pub struct BackupManager {
     pub work_dir: PathBuf,
     pub data_dir: PathBuf,
@@ -115,17 +241,26 @@ pub struct BackupManager {
     pub config_dir: PathBuf,
     
     // Active operations
-    pub active_operations: HashMap<String, BackupOperation>,
-    pub active_restores: HashMap<String, RestoreOperation>,
+    pub active_operations: Arc<Mutex<HashMap<String, BackupOperation>>>,
+    pub active_restores: Arc<Mutex<HashMap<String, RestoreOperation>>>,
+    cancel_flags: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
     
     // Backup registry
-    pub backup_registry: HashMap<String, BackupInfo>,
-    pub backup_configs: HashMap<String, BackupConfig>,
-    pub backup_schedules: HashMap<String, BackupSchedule>,
+    pub backup_registry: Arc<Mutex<HashMap<String, BackupInfo>>>,
+    pub backup_configs: Arc<Mutex<HashMap<String, BackupConfig>>>,
+    pub backup_schedules: Arc<Mutex<HashMap<String, BackupSchedule>>>,
+    pub backup_integrity: Arc<Mutex<HashMap<String, BackupIntegrity>>>,
+    pub scrub_schedule: Arc<Mutex<Option<ScrubSchedule>>>,
     
-    // File change tracking for incremental backups
-    pub file_changes: HashMap<PathBuf, SystemTime>,
-    pub last_full_backup: Option<SystemTime>,
+    // File change tracking for incremental backups: paths the watcher saw change,
+    // or files the last mtime scan backed up
+    pub file_changes: Arc<Mutex<HashMap<PathBuf, SystemTime>>>,
+    /// Roots being watched and when watching began; the journal only has every
+    /// change under a root from that time on
+    journal_coverage: Arc<Mutex<HashMap<PathBuf, SystemTime>>>,
+    change_watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    pub last_full_backup: Arc<Mutex<Option<SystemTime>>>,
+    last_full_backup_id: Arc<Mutex<Option<String>>>,
     
     // System integration
     pub package_manager_integration: bool,
@@ -148,94 +283,163 @@ impl BackupManager {
             fs::create_dir_all(dir)?;
         }
         
-        let mut manager = Self {
+        let manager = Self {
             work_dir,
             data_dir,
             backups_dir,
             temp_dir,
             config_dir,
-            active_operations: HashMap::new(),
-            active_restores: HashMap::new(),
-            backup_registry: HashMap::new(),
-            backup_configs: HashMap::new(),
-            backup_schedules: HashMap::new(),
-            file_changes: HashMap::new(),
-            last_full_backup: None,
+            active_operations: Arc::new(Mutex::new(HashMap::new())),
+            active_restores: Arc::new(Mutex::new(HashMap::new())),
+            cancel_flags: Arc::new(Mutex::new(HashMap::new())),
+            backup_registry: Arc::new(Mutex::new(HashMap::new())),
+            backup_configs: Arc::new(Mutex::new(HashMap::new())),
+            backup_schedules: Arc::new(Mutex::new(HashMap::new())),
+            backup_integrity: Arc::new(Mutex::new(HashMap::new())),
+            scrub_schedule: Arc::new(Mutex::new(None)),
+            file_changes: Arc::new(Mutex::new(HashMap::new())),
+            journal_coverage: Arc::new(Mutex::new(HashMap::new())),
+            change_watcher: Arc::new(Mutex::new(None)),
+            last_full_backup: Arc::new(Mutex::new(None)),
+            last_full_backup_id: Arc::new(Mutex::new(None)),
             package_manager_integration: true,
-            system_snapshot_support: true,
+            system_snapshot_support: snapshots::is_btrfs_root(),
         };
         
         // Load existing backup registry
         manager.load_backup_registry().await?;
         manager.load_backup_configs().await?;
         manager.load_backup_schedules().await?;
+        manager.load_backup_integrity().await?;
+        manager.load_scrub_schedule().await?;
         
         // Initialize file change tracking
         manager.initialize_change_tracking().await?;
         
+        // Journal the sources of saved incremental backups from now on
+        let incremental_sources: Vec<PathBuf> = manager.backup_configs.lock().unwrap().values()
+            .chain(manager.backup_schedules.lock().unwrap().values().map(|schedule| &schedule.config))
+            .filter(|config| matches!(config.backup_type, BackupType::Incremental))
+            .flat_map(|config| config.source_paths.clone())
+            .collect();
+        if let Err(e) = manager.watch_sources(&incremental_sources) {
+            warn!("⚠️ Change journal unavailable, incrementals will scan: {}", e);
+        }
+        
         info!("✅ ArchBackupPro backup system initialized with {} existing backups", 
-              manager.backup_registry.len());
+              manager.backup_registry.lock().unwrap().len());
         
         Ok(manager)
     }
     
-    async fn load_backup_registry(&mut self) -> Result<()> {
+    /// Every path a saved or scheduled backup reads from, e.g. for sampling what gets compressed
+    pub fn source_paths(&self) -> Vec<PathBuf> {
+        let configs = self.backup_configs.lock().unwrap();
+        let schedules = self.backup_schedules.lock().unwrap();
+        let mut sources: Vec<PathBuf> = configs.values()
+            .chain(schedules.values().map(|schedule| &schedule.config))
+            .flat_map(|config| config.source_paths.iter().cloned())
+            .collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+    
+    async fn load_backup_registry(&self) -> Result<()> {
         let registry_file = self.data_dir.join("backup_registry.json");
         if registry_file.exists() {
             let content = fs::read_to_string(&registry_file)?;
-            self.backup_registry = serde_json::from_str(&content).unwrap_or_default();
+            *self.backup_registry.lock().unwrap() = serde_json::from_str(&content).unwrap_or_default();
         }
         Ok(())
     }
     
     async fn save_backup_registry(&self) -> Result<()> {
         let registry_file = self.data_dir.join("backup_registry.json");
-        let content = serde_json::to_string_pretty(&self.backup_registry)?;
+        let content = serde_json::to_string_pretty(&*self.backup_registry.lock().unwrap())?;
         fs::write(&registry_file, content)?;
         Ok(())
     }
     
-    async fn load_backup_configs(&mut self) -> Result<()> {
+    async fn load_backup_configs(&self) -> Result<()> {
         let configs_file = self.data_dir.join("backup_configs.json");
         if configs_file.exists() {
             let content = fs::read_to_string(&configs_file)?;
-            self.backup_configs = serde_json::from_str(&content).unwrap_or_default();
+            *self.backup_configs.lock().unwrap() = serde_json::from_str(&content).unwrap_or_default();
         }
         Ok(())
     }
     
     async fn save_backup_configs(&self) -> Result<()> {
         let configs_file = self.data_dir.join("backup_configs.json");
-        let content = serde_json::to_string_pretty(&self.backup_configs)?;
+        let content = serde_json::to_string_pretty(&*self.backup_configs.lock().unwrap())?;
         fs::write(&configs_file, content)?;
         Ok(())
     }
     
-    async fn load_backup_schedules(&mut self) -> Result<()> {
+    async fn load_backup_schedules(&self) -> Result<()> {
         let schedules_file = self.data_dir.join("backup_schedules.json");
         if schedules_file.exists() {
             let content = fs::read_to_string(&schedules_file)?;
-            self.backup_schedules = serde_json::from_str(&content).unwrap_or_default();
+            *self.backup_schedules.lock().unwrap() = serde_json::from_str(&content).unwrap_or_default();
         }
         Ok(())
     }
     
     async fn save_backup_schedules(&self) -> Result<()> {
         let schedules_file = self.data_dir.join("backup_schedules.json");
-        let content = serde_json::to_string_pretty(&self.backup_schedules)?;
+        let content = serde_json::to_string_pretty(&*self.backup_schedules.lock().unwrap())?;
         fs::write(&schedules_file, content)?;
         Ok(())
     }
     
-    async fn initialize_change_tracking(&mut self) -> Result<()> {
+    async fn load_backup_integrity(&self) -> Result<()> {
+        let integrity_file = self.data_dir.join("backup_integrity.json");
+        if integrity_file.exists() {
+            let content = fs::read_to_string(&integrity_file)?;
+            *self.backup_integrity.lock().unwrap() = serde_json::from_str(&content).unwrap_or_default();
+        }
+        Ok(())
+    }
+    
+    async fn save_backup_integrity(&self) -> Result<()> {
+        let integrity_file = self.data_dir.join("backup_integrity.json");
+        let content = serde_json::to_string_pretty(&*self.backup_integrity.lock().unwrap())?;
+        fs::write(&integrity_file, content)?;
+        Ok(())
+    }
+    
+    async fn load_scrub_schedule(&self) -> Result<()> {
+        let scrub_file = self.data_dir.join("scrub_schedule.json");
+        if scrub_file.exists() {
+            let content = fs::read_to_string(&scrub_file)?;
+            *self.scrub_schedule.lock().unwrap() = serde_json::from_str(&content).unwrap_or_default();
+        }
+        Ok(())
+    }
+    
+    async fn save_scrub_schedule(&self) -> Result<()> {
+        let scrub_file = self.data_dir.join("scrub_schedule.json");
+        let content = serde_json::to_string_pretty(&*self.scrub_schedule.lock().unwrap())?;
+        fs::write(&scrub_file, content)?;
+        Ok(())
+    }
+    
+    async fn initialize_change_tracking(&self) -> Result<()> {
         debug!("🔍 Initializing file change tracking for incremental backups");
         
         let tracking_file = self.data_dir.join("file_changes.json");
         if tracking_file.exists() {
             let content = fs::read_to_string(&tracking_file)?;
-            if let Ok(changes) = serde_json::from_str::<HashMap<String, SystemTime>>(&content) {
-                for (path_str, time) in changes {
-                    self.file_changes.insert(PathBuf::from(path_str), time);
+            // Older versions stored only the path -> time map
+            let journal = serde_json::from_str::<ChangeJournal>(&content).ok()
+                .or_else(|| serde_json::from_str::<HashMap<PathBuf, SystemTime>>(&content).ok()
+                    .map(|changes| ChangeJournal { changes, ..Default::default() }));
+            if let Some(journal) = journal {
+                self.file_changes.lock().unwrap().extend(journal.changes);
+                // Nothing was watching while the app was down, so the saved coverage has a gap
+                if !journal.covered_since.is_empty() {
+                    info!("🔍 Change journal coverage reset for {} sources: the watcher was not running", journal.covered_since.len());
                 }
             }
         }
@@ -245,18 +449,77 @@ impl BackupManager {
     
     async fn save_change_tracking(&self) -> Result<()> {
         let tracking_file = self.data_dir.join("file_changes.json");
-        let changes: HashMap<String, SystemTime> = self.file_changes.iter()
-            .map(|(path, time)| (path.to_string_lossy().to_string(), *time))
-            .collect();
-        let content = serde_json::to_string_pretty(&changes)?;
+        let journal = ChangeJournal {
+            changes: self.file_changes.lock().unwrap().clone(),
+            covered_since: self.journal_coverage.lock().unwrap().clone(),
+        };
+        let content = serde_json::to_string_pretty(&journal)?;
         fs::write(&tracking_file, content)?;
         Ok(())
     }
     
+    /// Record changes under `paths` as they happen, so incrementals of these
+    /// sources can read the journal instead of walking every file. Coverage of
+    /// each path starts now.
+    pub fn watch_sources(&self, paths: &[PathBuf]) -> Result<()> {
+        let mut watcher_slot = self.change_watcher.lock().unwrap();
+        if watcher_slot.is_none() {
+            let changes = self.file_changes.clone();
+            let coverage = self.journal_coverage.clone();
+            *watcher_slot = Some(notify::recommended_watcher(move |event: notify::Result<Event>| {
+                record_change_event(event, &changes, &coverage);
+            })?);
+        }
+        
+        if let Some(watcher) = watcher_slot.as_mut() {
+            for path in paths {
+                if !path.exists() || self.journal_coverage.lock().unwrap().contains_key(path) {
+                    continue;
+                }
+                // Large trees can exceed fs.inotify.max_user_watches; those sources keep scanning
+                watcher.watch(path, RecursiveMode::Recursive)
+                    .map_err(|e| anyhow!("Failed to watch {}: {}", path.display(), e))?;
+                self.journal_coverage.lock().unwrap().insert(path.clone(), SystemTime::now());
+                info!("🔍 Journaling changes under {}", path.display());
+            }
+        }
+        Ok(())
+    }
+    
+    /// Paths journaled under `sources` after `since`, or None when any source
+    /// wasn't watched for that whole period. The journal is shared by every
+    /// config, so it is only read here; each incremental filters by its own full backup.
+    ///
+    /// Only additions and modifications are listed. Files removed or renamed away
+    /// since the full backup aren't, just as a scan can't see them: an incremental
+    /// archive only adds files, and restoring it over the full backup brings those back.
+    fn journal_changes_since(&self, sources: &[PathBuf], since: SystemTime) -> Option<Vec<PathBuf>> {
+        let covered = {
+            let coverage = self.journal_coverage.lock().unwrap();
+            !sources.is_empty() && sources.iter().all(|source| coverage.get(source).map(|start| *start <= since).unwrap_or(false))
+        };
+        if !covered {
+            return None;
+        }
+        
+        let changes = self.file_changes.lock().unwrap();
+        Some(changes.iter()
+            .filter(|(path, changed_at)| **changed_at > since && sources.iter().any(|source| path.starts_with(source)))
+            .map(|(path, _)| path.clone())
+            .collect())
+    }
+    
     pub async fn create_backup(&mut self, config: BackupConfig) -> Result<String> {
         let operation_id = Uuid::new_v4().to_string();
         let backup_id = Uuid::new_v4().to_string();
         
+        // Later incrementals of these sources can then skip the full walk
+        if matches!(config.backup_type, BackupType::Incremental) {
+            if let Err(e) = self.watch_sources(&config.source_paths) {
+                warn!("⚠️ Not journaling {}: {}", config.name, e);
+            }
+        }
+        
         info!("💾 Starting backup: {} ({})", config.name, backup_id);
         
         let operation = BackupOperation {
@@ -274,32 +537,94 @@ impl BackupManager {
             errors: Vec::new(),
         };
         
-        self.active_operations.insert(operation_id.clone(), operation);
+        self.active_operations.lock().unwrap().insert(operation_id.clone(), operation);
+        self.cancel_flags.lock().unwrap().insert(operation_id.clone(), Arc::new(AtomicBool::new(false)));
         
-        // Execute backup in background
-        let manager_ptr = self as *mut Self;
+        // Execute backup in background on a handle that shares our state
+        let manager = self.clone();
+        let task_operation_id = operation_id.clone();
         tokio::spawn(async move {
-            unsafe {
-                let manager = &mut *manager_ptr;
-                if let Err(e) = manager.execute_backup(&operation_id, &backup_id).await {
-                    error!("Backup failed: {}", e);
-                    if let Some(op) = manager.active_operations.get_mut(&operation_id) {
-                        op.status = BackupStatus::Failed;
-                        op.errors.push(format!("Backup failed: {}", e));
-                    }
-                }
+            if let Err(e) = manager.execute_backup(&task_operation_id, &backup_id).await {
+                error!("Backup failed: {}", e);
+                manager.update_operation(&task_operation_id, |op| {
+                    op.status = BackupStatus::Failed;
+                    op.errors.push(format!("Backup failed: {}", e));
+                });
             }
+            manager.cancel_flags.lock().unwrap().remove(&task_operation_id);
         });
         
         Ok(operation_id)
     }
     
-    async fn execute_backup(&mut self, operation_id: &str, backup_id: &str) -> Result<()> {
-        let config = if let Some(op) = self.active_operations.get(operation_id) {
-            op.backup_config.clone()
-        } else {
-            return Err(anyhow!("Operation not found"));
-        };
+    /// Ask a running backup to stop. The archive written so far is removed.
+    pub fn cancel_backup(&mut self, operation_id: &str) -> Result<()> {
+        let running = matches!(
+            self.active_operations.lock().unwrap().get(operation_id).map(|op| &op.status),
+            Some(BackupStatus::Running)
+        );
+        if !running {
+            return Err(anyhow!("No running backup with id {}", operation_id));
+        }
+        
+        self.request_cancel(operation_id)?;
+        self.update_operation(operation_id, |op| {
+            op.log.push("Cancellation requested".to_string());
+        });
+        info!("⏹️ Cancelling backup {}", operation_id);
+        Ok(())
+    }
+    
+    /// Ask a running restore to stop after the file currently being extracted.
+    pub fn cancel_restore(&mut self, operation_id: &str) -> Result<()> {
+        let running = matches!(
+            self.active_restores.lock().unwrap().get(operation_id).map(|op| &op.status),
+            Some(BackupStatus::Running)
+        );
+        if !running {
+            return Err(anyhow!("No running restore with id {}", operation_id));
+        }
+        
+        self.request_cancel(operation_id)?;
+        self.update_restore(operation_id, |op| {
+            op.log.push("Cancellation requested".to_string());
+        });
+        info!("⏹️ Cancelling restore {}", operation_id);
+        Ok(())
+    }
+    
+    fn request_cancel(&self, operation_id: &str) -> Result<()> {
+        let flags = self.cancel_flags.lock().unwrap();
+        let flag = flags.get(operation_id)
+            .ok_or_else(|| anyhow!("Operation {} is no longer running", operation_id))?;
+        flag.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+    
+    fn is_cancelled(&self, operation_id: &str) -> bool {
+        self.cancel_flags.lock().unwrap()
+            .get(operation_id)
+            .map(|flag| flag.load(Ordering::SeqCst))
+            .unwrap_or(false)
+    }
+    
+    fn update_operation(&self, operation_id: &str, update: impl FnOnce(&mut BackupOperation)) {
+        if let Some(op) = self.active_operations.lock().unwrap().get_mut(operation_id) {
+            update(op);
+        }
+    }
+    
+    fn update_restore(&self, operation_id: &str, update: impl FnOnce(&mut RestoreOperation)) {
+        if let Some(op) = self.active_restores.lock().unwrap().get_mut(operation_id) {
+            update(op);
+        }
+    }
+    
+    async fn execute_backup(&self, operation_id: &str, backup_id: &str) -> Result<()> {
+        let config = self.active_operations.lock().unwrap()
+            .get(operation_id)
+            .map(|op| op.backup_config.clone())
+            .ok_or_else(|| anyhow!("Operation not found"))?;
         
         let backup_filename = format!("{}_{}.tar", 
             config.name.replace(' ', "_"), 
@@ -308,27 +633,63 @@ impl BackupManager {
         let backup_path = self.backups_dir.join(&backup_filename);
         
         // Update operation status
-        if let Some(op) = self.active_operations.get_mut(operation_id) {
+        self.update_operation(operation_id, |op| {
             op.log.push(format!("Creating backup archive: {}", backup_filename));
-        }
+        });
         
-        match config.backup_type {
-            BackupType::Full => self.create_full_backup(&config, &backup_path, operation_id).await?,
-            BackupType::Incremental => self.create_incremental_backup(&config, &backup_path, operation_id).await?,
-            BackupType::Package => self.create_package_backup(&config, &backup_path, operation_id).await?,
-            BackupType::Settings => self.create_settings_backup(&config, &backup_path, operation_id).await?,
-            BackupType::UserData => self.create_user_data_backup(&config, &backup_path, operation_id).await?,
-            BackupType::System => self.create_system_backup(&config, &backup_path, operation_id).await?,
+        // An incremental with no full backup to build on is written as a full one
+        let writes_full_archive = match config.backup_type {
+            BackupType::Full | BackupType::System => true,
+            BackupType::Incremental => self.last_full_backup.lock().unwrap().is_none(),
+            _ => false,
+        };
+        let base_backup_id = if matches!(config.backup_type, BackupType::Incremental) && !writes_full_archive {
+            self.last_full_backup_id.lock().unwrap().clone()
+        } else {
+            None
+        };
+        
+        let result = match config.backup_type {
+            BackupType::Full => self.create_full_backup(&config, &backup_path, operation_id).await,
+            BackupType::Incremental => self.create_incremental_backup(&config, &backup_path, operation_id).await,
+            BackupType::Package => self.create_package_backup(&config, &backup_path, operation_id).await,
+            BackupType::Settings => self.create_settings_backup(&config, &backup_path, operation_id).await,
+            BackupType::UserData => self.create_user_data_backup(&config, &backup_path, operation_id).await,
+            BackupType::System => self.create_system_backup(&config, &backup_path, operation_id).await,
+        };
+        
+        if let Err(e) = result {
+            // Never leave a half-written archive behind
+            if backup_path.exists() {
+                let _ = fs::remove_file(&backup_path);
+            }
+            if e.is::<OperationCancelled>() {
+                self.update_operation(operation_id, |op| {
+                    op.status = BackupStatus::Cancelled;
+                    op.completed_at = Some(unix_now());
+                    op.log.push("Backup cancelled, partial archive removed".to_string());
+                });
+                info!("⏹️ Backup cancelled: {}", config.name);
+                return Ok(());
+            }
+            return Err(e);
         }
         
-        // Verify backup integrity
-        self.verify_backup(&backup_path, operation_id).await?;
+        // Verify backup integrity; a deep verify records the manifest later ones check against
+        let (report, manifest) = self.verify_archive(&backup_path, config.verify_level, None, Some(operation_id))?;
+        if report.entries == 0 {
+            let _ = fs::remove_file(&backup_path);
+            return Err(anyhow!("Backup archive is empty"));
+        }
+        if let Some(manifest) = manifest {
+            self.save_manifest(backup_id, &manifest)?;
+        }
         
         // Apply compression if specified
         let final_backup_path = if matches!(config.compression, CompressionType::None) {
             backup_path
         } else {
-            self.compress_backup(&backup_path, &config.compression, operation_id).await?
+            self.compress_backup(&backup_path, &config.compression, config.compression_level, operation_id).await?
         };
         
         // Calculate final size
@@ -346,16 +707,28 @@ impl BackupManager {
         };
         
         // Update registry
-        self.backup_registry.insert(backup_id.to_string(), backup_info);
+        self.backup_registry.lock().unwrap().insert(backup_id.to_string(), backup_info);
         self.save_backup_registry().await?;
         
+        // Checksum for later scrubs, and the chain this backup belongs to
+        match sha256_file(&final_backup_path) {
+            Ok(sha256) => {
+                self.backup_integrity.lock().unwrap().insert(backup_id.to_string(), BackupIntegrity { sha256, base_backup_id });
+                self.save_backup_integrity().await?;
+            }
+            Err(e) => warn!("⚠️ Failed to checksum {}: {}", final_backup_path.display(), e),
+        }
+        if writes_full_archive {
+            *self.last_full_backup_id.lock().unwrap() = Some(backup_id.to_string());
+        }
+        
         // Mark operation as completed
-        if let Some(op) = self.active_operations.get_mut(operation_id) {
+        self.update_operation(operation_id, |op| {
             op.status = BackupStatus::Completed;
             op.progress = 100.0;
-            op.completed_at = Some(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs());
+            op.completed_at = Some(unix_now());
             op.log.push(format!("Backup completed successfully: {} bytes", backup_size));
-        }
+        });
         
         info!("✅ Backup completed: {} ({} bytes)", config.name, backup_size);
         
@@ -367,7 +740,7 @@ impl BackupManager {
         Ok(())
     }
     
-    async fn create_full_backup(&mut self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
+    async fn create_full_backup(&self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
         debug!("📦 Creating full backup");
         
         let file = std::fs::File::create(backup_path)?;
@@ -388,34 +761,39 @@ impl BackupManager {
         }
         
         // Update total files count
-        if let Some(op) = self.active_operations.get_mut(operation_id) {
+        self.update_operation(operation_id, |op| {
             op.total_files = total_files;
             op.log.push(format!("Found {} files to backup", total_files));
-        }
+        });
         
         // Second pass: add files to archive
         for source_path in &config.source_paths {
             if source_path.exists() {
                 for entry in WalkDir::new(source_path).into_iter().filter_map(|e| e.ok()) {
                     if entry.file_type().is_file() && !self.should_exclude(&entry.path(), &config.exclude_patterns) {
+                        if self.is_cancelled(operation_id) {
+                            archive.finish()?;
+                            return Err(OperationCancelled.into());
+                        }
+                        
                         let relative_path = entry.path().strip_prefix(source_path).unwrap_or(entry.path());
                         
                         if let Err(e) = archive.append_path_with_name(entry.path(), relative_path) {
-                            if let Some(op) = self.active_operations.get_mut(operation_id) {
+                            self.update_operation(operation_id, |op| {
                                 op.errors.push(format!("Failed to add {}: {}", entry.path().display(), e));
-                            }
+                            });
                         } else {
                             processed_files += 1;
                             
                             // Update progress
-                            if let Some(op) = self.active_operations.get_mut(operation_id) {
+                            self.update_operation(operation_id, |op| {
                                 op.files_processed = processed_files;
                                 op.progress = (processed_files as f32 / total_files as f32) * 100.0;
                                 
                                 if processed_files % 1000 == 0 {
                                     op.log.push(format!("Processed {} / {} files", processed_files, total_files));
                                 }
-                            }
+                            });
                         }
                     }
                 }
@@ -423,97 +801,129 @@ impl BackupManager {
         }
         
         archive.finish()?;
-        self.last_full_backup = Some(SystemTime::now());
+        *self.last_full_backup.lock().unwrap() = Some(SystemTime::now());
         
         Ok(())
     }
     
-    async fn create_incremental_backup(&mut self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
+    async fn create_incremental_backup(&self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
         debug!("📦 Creating incremental backup");
         
-        if self.last_full_backup.is_none() {
-            if let Some(op) = self.active_operations.get_mut(operation_id) {
-                op.log.push("No full backup found, creating full backup instead".to_string());
+        let last_full_backup = *self.last_full_backup.lock().unwrap();
+        let since = match last_full_backup {
+            Some(since) => since,
+            None => {
+                self.update_operation(operation_id, |op| {
+                    op.log.push("No full backup found, creating full backup instead".to_string());
+                });
+                return self.create_full_backup(config, backup_path, operation_id).await;
             }
-            return self.create_full_backup(config, backup_path, operation_id).await;
-        }
+        };
+        
+        // The journal is only complete if every source was watched since the full backup
+        let (changed, from_journal) = match self.journal_changes_since(&config.source_paths, since) {
+            Some(journaled) => (self.expand_journaled_paths(config, journaled, since), true),
+            None => (self.scan_changes_since(config, since), false),
+        };
+        let total_files = changed.len() as u64;
+        let mut processed_files = 0u64;
+        
+        self.update_operation(operation_id, |op| {
+            op.total_files = total_files;
+            op.log.push(format!(
+                "Found {} changed files for incremental backup ({})",
+                total_files, if from_journal { "change journal" } else { "full scan" }
+            ));
+        });
         
-        let since = self.last_full_backup.unwrap();
         let file = std::fs::File::create(backup_path)?;
         let mut archive = Builder::new(file);
         
-        let mut total_files = 0u64;
-        let mut processed_files = 0u64;
-        
-        // Find changed files since last full backup
-        for source_path in &config.source_paths {
-            if source_path.exists() {
-                for entry in WalkDir::new(source_path).into_iter().filter_map(|e| e.ok()) {
-                    if entry.file_type().is_file() && !self.should_exclude(&entry.path(), &config.exclude_patterns) {
-                        if let Ok(metadata) = entry.metadata() {
-                            if let Ok(modified) = metadata.modified() {
-                                if modified > since {
-                                    total_files += 1;
-                                }
-                            }
-                        }
-                    }
-                }
+        for (path, source_path, modified) in changed {
+            if self.is_cancelled(operation_id) {
+                archive.finish()?;
+                return Err(OperationCancelled.into());
+            }
+            
+            let relative_path = path.strip_prefix(&source_path).unwrap_or(&path);
+            if let Err(e) = archive.append_path_with_name(&path, relative_path) {
+                self.update_operation(operation_id, |op| {
+                    op.errors.push(format!("Failed to add {}: {}", path.display(), e));
+                });
+                continue;
             }
+            processed_files += 1;
+            
+            // The watcher records journaled files itself
+            if !from_journal {
+                self.file_changes.lock().unwrap().insert(path.clone(), modified);
+            }
+            
+            self.update_operation(operation_id, |op| {
+                op.files_processed = processed_files;
+                op.progress = (processed_files as f32 / total_files.max(1) as f32) * 100.0;
+            });
         }
         
-        // Update operation
-        if let Some(op) = self.active_operations.get_mut(operation_id) {
-            op.total_files = total_files;
-            op.log.push(format!("Found {} changed files for incremental backup", total_files));
-        }
+        archive.finish()?;
+        self.save_change_tracking().await?;
         
-        // Add changed files to archive
+        Ok(())
+    }
+    
+    /// (file, source it belongs to, mtime) for every file under the sources
+    /// modified after `since`, by walking them all
+    fn scan_changes_since(&self, config: &BackupConfig, since: SystemTime) -> Vec<(PathBuf, PathBuf, SystemTime)> {
+        let mut changed = Vec::new();
         for source_path in &config.source_paths {
-            if source_path.exists() {
-                for entry in WalkDir::new(source_path).into_iter().filter_map(|e| e.ok()) {
-                    if entry.file_type().is_file() && !self.should_exclude(&entry.path(), &config.exclude_patterns) {
-                        if let Ok(metadata) = entry.metadata() {
-                            if let Ok(modified) = metadata.modified() {
-                                if modified > since {
-                                    let relative_path = entry.path().strip_prefix(source_path).unwrap_or(entry.path());
-                                    
-                                    if let Err(e) = archive.append_path_with_name(entry.path(), relative_path) {
-                                        if let Some(op) = self.active_operations.get_mut(operation_id) {
-                                            op.errors.push(format!("Failed to add {}: {}", entry.path().display(), e));
-                                        }
-                                    } else {
-                                        processed_files += 1;
-                                        
-                                        // Update file change tracking
-                                        self.file_changes.insert(entry.path().to_path_buf(), modified);
-                                        
-                                        // Update progress
-                                        if let Some(op) = self.active_operations.get_mut(operation_id) {
-                                            op.files_processed = processed_files;
-                                            op.progress = (processed_files as f32 / total_files.max(1) as f32) * 100.0;
-                                        }
-                                    }
-                                }
-                            }
-                        }
+            if !source_path.exists() {
+                continue;
+            }
+            for entry in WalkDir::new(source_path).into_iter().filter_map(|e| e.ok()) {
+                if !entry.file_type().is_file() || self.should_exclude(entry.path(), &config.exclude_patterns) {
+                    continue;
+                }
+                if let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) {
+                    if modified > since {
+                        changed.push((entry.path().to_path_buf(), source_path.clone(), modified));
                     }
                 }
             }
         }
+        changed
+    }
+    
+    /// Journaled paths as backup entries. Directories that were created or moved
+    /// in are walked, since their contents produce no events of their own.
+    fn expand_journaled_paths(&self, config: &BackupConfig, journaled: Vec<PathBuf>, since: SystemTime) -> Vec<(PathBuf, PathBuf, SystemTime)> {
+        let mut files = BTreeSet::new();
+        for path in journaled {
+            if path.is_dir() {
+                files.extend(WalkDir::new(&path).into_iter().filter_map(|e| e.ok())
+                    .filter(|entry| entry.file_type().is_file())
+                    .map(|entry| entry.path().to_path_buf()));
+            } else if path.is_file() {
+                files.insert(path);
+            }
+        }
         
-        archive.finish()?;
-        self.save_change_tracking().await?;
-        
-        Ok(())
+        files.into_iter()
+            .filter(|path| !self.should_exclude(path, &config.exclude_patterns))
+            .filter_map(|path| {
+                let source_path = config.source_paths.iter().find(|source| path.starts_with(source))?.clone();
+                // A file inside a new directory may be older than the full backup; include it anyway
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).unwrap_or(since);
+                Some((path, source_path, modified))
+            })
+            .collect()
     }
     
-    async fn create_package_backup(&mut self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
+    async fn create_package_backup(&self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
         debug!("📦 Creating package backup");
         
-        if let Some(op) = self.active_operations.get_mut(operation_id) {
+        self.update_operation(operation_id, |op| {
             op.log.push("Generating package list".to_string());
-        }
+        });
         
         // Get list of explicitly installed packages
         let output = TokioCommand::new("pacman")
@@ -568,17 +978,17 @@ impl BackupManager {
         // Cleanup temp directory
         fs::remove_dir_all(&temp_dir)?;
         
-        if let Some(op) = self.active_operations.get_mut(operation_id) {
+        self.update_operation(operation_id, |op| {
             op.progress = 100.0;
             op.files_processed = 1;
             op.total_files = 1;
             op.log.push("Package backup completed".to_string());
-        }
+        });
         
         Ok(())
     }
     
-    async fn create_settings_backup(&mut self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
+    async fn create_settings_backup(&self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
         debug!("📦 Creating settings backup");
         
         let temp_dir = self.temp_dir.join("settings_backup");
@@ -615,9 +1025,9 @@ impl BackupManager {
                 if expanded_path.is_file() {
                     if let Some(filename) = expanded_path.file_name() {
                         if let Err(e) = archive.append_path_with_name(&expanded_path, filename) {
-                            if let Some(op) = self.active_operations.get_mut(operation_id) {
+                            self.update_operation(operation_id, |op| {
                                 op.errors.push(format!("Failed to add {}: {}", expanded_path.display(), e));
-                            }
+                            });
                         } else {
                             files_added += 1;
                         }
@@ -626,12 +1036,17 @@ impl BackupManager {
                     // For directories, add all contents
                     for entry in WalkDir::new(&expanded_path).into_iter().filter_map(|e| e.ok()) {
                         if entry.file_type().is_file() && !self.should_exclude(&entry.path(), &config.exclude_patterns) {
+                            if self.is_cancelled(operation_id) {
+                                archive.finish()?;
+                                return Err(OperationCancelled.into());
+                            }
+                            
                             if let Ok(relative_path) = entry.path().strip_prefix(&expanded_path) {
                                 let archive_path = Path::new(config_path).join(relative_path);
                                 if let Err(e) = archive.append_path_with_name(entry.path(), archive_path) {
-                                    if let Some(op) = self.active_operations.get_mut(operation_id) {
+                                    self.update_operation(operation_id, |op| {
                                         op.errors.push(format!("Failed to add {}: {}", entry.path().display(), e));
-                                    }
+                                    });
                                 } else {
                                     files_added += 1;
                                 }
@@ -644,17 +1059,17 @@ impl BackupManager {
         
         archive.finish()?;
         
-        if let Some(op) = self.active_operations.get_mut(operation_id) {
+        self.update_operation(operation_id, |op| {
             op.progress = 100.0;
             op.files_processed = files_added;
             op.total_files = files_added;
             op.log.push(format!("Settings backup completed: {} files", files_added));
-        }
+        });
         
         Ok(())
     }
     
-    async fn create_user_data_backup(&mut self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
+    async fn create_user_data_backup(&self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
         debug!("📦 Creating user data backup");
         
         // Default user data directories
@@ -683,7 +1098,7 @@ impl BackupManager {
         self.create_full_backup(&user_config, backup_path, operation_id).await
     }
     
-    async fn create_system_backup(&mut self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
+    async fn create_system_backup(&self, config: &BackupConfig, backup_path: &Path, operation_id: &str) -> Result<()> {
         debug!("📦 Creating system backup");
         
         // Critical system directories
@@ -715,43 +1130,140 @@ impl BackupManager {
         self.create_full_backup(&system_config, backup_path, operation_id).await
     }
     
-    async fn verify_backup(&self, backup_path: &Path, operation_id: &str) -> Result<()> {
-        debug!("🔍 Verifying backup integrity");
+    /// Check a registered backup at `level`. Deep compares entries against the
+    /// manifest recorded at backup time; backups from before manifests existed
+    /// are only checked for readability.
+    pub async fn verify_existing_backup(&self, backup_id: &str, level: VerifyLevel) -> Result<VerifyReport> {
+        let location = self.backup_registry.lock().unwrap()
+            .get(backup_id)
+            .map(|info| info.location.clone())
+            .ok_or_else(|| anyhow!("Backup {} not found", backup_id))?;
+        let manifest = match level {
+            VerifyLevel::Deep => self.load_manifest(backup_id),
+            VerifyLevel::Quick => None,
+        };
         
-        if let Some(op) = self.active_operations.get(operation_id) {
-            if let Some(op) = self.active_operations.get(&op.operation_id) {
-                // This would be mutable in a real implementation
-                // op.log.push("Verifying backup integrity".to_string());
-            }
+        let (report, _) = self.verify_archive(&location, level, manifest.as_ref(), None)?;
+        if let Some(info) = self.backup_registry.lock().unwrap().get_mut(backup_id) {
+            info.verified = report.is_ok();
         }
+        self.save_backup_registry().await?;
+        Ok(report)
+    }
+    
+    /// Walk the archive at `level`, logging progress to the operation every
+    /// VERIFY_PROGRESS_INTERVAL. Deep also returns each file entry's SHA-256,
+    /// and reports entries that differ from `manifest`.
+    fn verify_archive(
+        &self,
+        backup_path: &Path,
+        level: VerifyLevel,
+        manifest: Option<&BTreeMap<String, String>>,
+        operation_id: Option<&str>,
+    ) -> Result<(VerifyReport, Option<BTreeMap<String, String>>)> {
+        debug!("🔍 Verifying backup integrity ({:?})", level);
+        let log = |line: String| match operation_id {
+            Some(operation_id) => self.update_operation(operation_id, |op| op.log.push(line)),
+            None => debug!("{}", line),
+        };
+        log(format!("Verifying backup integrity ({:?})", level));
+        
+        let mut report = VerifyReport { level, entries: 0, bytes: 0, checksums_checked: 0, mismatched: Vec::new() };
+        let mut checksums = BTreeMap::new();
+        let mut last_progress = Instant::now();
+        let mut progress = |report: &VerifyReport| {
+            if last_progress.elapsed() >= VERIFY_PROGRESS_INTERVAL {
+                log(format!("Verified {} entries, {:.1} MB", report.entries, report.bytes as f64 / 1_048_576.0));
+                last_progress = Instant::now();
+            }
+        };
         
-        // Open and verify the tar file
-        let file = std::fs::File::open(backup_path)?;
-        let mut archive = Archive::new(file);
-        
-        // Count entries to verify archive is readable
-        let mut entry_count = 0;
-        for entry in archive.entries()? {
-            let _entry = entry?;
-            entry_count += 1;
+        let is_plain_tar = backup_path.extension().map_or(false, |ext| ext == "tar");
+        match level {
+            VerifyLevel::Quick if is_plain_tar => {
+                let mut file = fs::File::open(backup_path)?;
+                check_tar_footer(&mut file)?;
+                file.seek(SeekFrom::Start(0))?;
+                let mut archive = Archive::new(file);
+                for entry in archive.entries_with_seek()? {
+                    report.entries += 1;
+                    report.bytes += entry?.size();
+                    progress(&report);
+                }
+            }
+            VerifyLevel::Quick => {
+                let mut archive = Archive::new(Self::open_archive_reader(backup_path)?);
+                for entry in archive.entries()? {
+                    report.entries += 1;
+                    report.bytes += entry?.size();
+                    progress(&report);
+                }
+            }
+            VerifyLevel::Deep => {
+                let mut archive = Archive::new(Self::open_archive_reader(backup_path)?);
+                for entry in archive.entries()? {
+                    let mut entry = entry?;
+                    let path = entry.path()?.into_owned();
+                    let mut hasher = Sha256::new();
+                    report.bytes += std::io::copy(&mut entry, &mut hasher)?;
+                    report.entries += 1;
+                    
+                    if entry.header().entry_type().is_file() {
+                        let sha256 = format!("{:x}", hasher.finalize());
+                        let key = path.to_string_lossy().to_string();
+                        if let Some(expected) = manifest.and_then(|manifest| manifest.get(&key)) {
+                            report.checksums_checked += 1;
+                            if *expected != sha256 {
+                                report.mismatched.push(path);
+                            }
+                        }
+                        checksums.insert(key, sha256);
+                    }
+                    progress(&report);
+                }
+            }
         }
         
-        if entry_count == 0 {
-            return Err(anyhow!("Backup archive is empty"));
+        if !report.mismatched.is_empty() {
+            warn!("⚠️ {} entries of {} no longer match their checksum", report.mismatched.len(), backup_path.display());
         }
-        
-        info!("✅ Backup verification completed: {} entries", entry_count);
+        log(format!(
+            "Verification finished: {} entries, {:.1} MB, {} checksums checked, {} mismatched",
+            report.entries, report.bytes as f64 / 1_048_576.0, report.checksums_checked, report.mismatched.len()
+        ));
+        info!("✅ Backup verification completed: {} entries", report.entries);
+        
+        let checksums = (level == VerifyLevel::Deep).then_some(checksums);
+        Ok((report, checksums))
+    }
+    
+    fn manifest_path(&self, backup_id: &str) -> PathBuf {
+        self.data_dir.join("manifests").join(format!("{}.json", backup_id))
+    }
+    
+    fn save_manifest(&self, backup_id: &str, manifest: &BTreeMap<String, String>) -> Result<()> {
+        let path = self.manifest_path(backup_id);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string(manifest)?)?;
         Ok(())
     }
     
-    async fn compress_backup(&self, backup_path: &Path, compression: &CompressionType, operation_id: &str) -> Result<PathBuf> {
+    fn load_manifest(&self, backup_id: &str) -> Option<BTreeMap<String, String>> {
+        fs::read_to_string(self.manifest_path(backup_id)).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+    
+    async fn compress_backup(&self, backup_path: &Path, compression: &CompressionType, level: Option<u8>, operation_id: &str) -> Result<PathBuf> {
         match compression {
             CompressionType::None => Ok(backup_path.to_path_buf()),
             CompressionType::Gzip => {
                 let compressed_path = backup_path.with_extension("tar.gz");
                 let input = std::fs::File::open(backup_path)?;
                 let output = std::fs::File::create(&compressed_path)?;
-                let mut encoder = GzEncoder::new(output, Compression::default());
+                let level = level.map(|level| Compression::new(level.min(9) as u32)).unwrap_or_default();
+                let mut encoder = GzEncoder::new(output, level);
                 std::io::copy(&mut std::io::BufReader::new(input), &mut encoder)?;
                 encoder.finish()?;
                 
@@ -761,16 +1273,53 @@ impl BackupManager {
                 Ok(compressed_path)
             },
             CompressionType::Zstd => {
-                // Would implement zstd compression here
-                Ok(backup_path.to_path_buf())
+                let compressed_path = backup_path.with_extension("tar.zst");
+                let input = std::fs::File::open(backup_path)?;
+                let output = std::fs::File::create(&compressed_path)?;
+                let level = level.map(i32::from).unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL);
+                let mut encoder = zstd::stream::write::Encoder::new(output, level)?;
+                std::io::copy(&mut std::io::BufReader::new(input), &mut encoder)?;
+                encoder.finish()?;
+                
+                // Remove uncompressed file
+                fs::remove_file(backup_path)?;
+                
+                Ok(compressed_path)
             },
             CompressionType::Lz4 => {
-                // Would implement lz4 compression here
-                Ok(backup_path.to_path_buf())
+                let compressed_path = backup_path.with_extension("tar.lz4");
+                let input = std::fs::File::open(backup_path)?;
+                let output = std::fs::File::create(&compressed_path)?;
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(output);
+                std::io::copy(&mut std::io::BufReader::new(input), &mut encoder)?;
+                encoder.finish()?;
+                
+                // Remove uncompressed file
+                fs::remove_file(backup_path)?;
+                
+                Ok(compressed_path)
             },
         }
     }
     
+    /// Reader over the tar stream of a backup, decompressing by file extension
+    fn open_archive_reader(backup_path: &Path) -> Result<Box<dyn std::io::Read + Send>> {
+        let file = std::io::BufReader::new(std::fs::File::open(backup_path)?);
+        let name = backup_path.to_string_lossy();
+        
+        let reader: Box<dyn std::io::Read + Send> = if name.ends_with(".gz") {
+            Box::new(GzDecoder::new(file))
+        } else if name.ends_with(".zst") {
+            Box::new(zstd::stream::read::Decoder::new(file.into_inner())?)
+        } else if name.ends_with(".lz4") {
+            Box::new(lz4_flex::frame::FrameDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+        
+        Ok(reader)
+    }
+    
     fn should_exclude(&self, path: &Path, exclude_patterns: &[String]) -> bool {
         let path_str = path.to_string_lossy();
         
@@ -796,14 +1345,87 @@ impl BackupManager {
         false
     }
     
+    /// Everything in a backup, read from the tar headers without extracting.
+    /// Entries up to an unreadable one are returned if the archive is damaged.
+    pub fn list_backup_contents(&self, backup_id: &str) -> Vec<ArchiveEntry> {
+        let location = match self.backup_registry.lock().unwrap().get(backup_id) {
+            Some(backup_info) => backup_info.location.clone(),
+            None => {
+                warn!("⚠️ Backup not found: {}", backup_id);
+                return Vec::new();
+            }
+        };
+        
+        let reader = match Self::open_archive_reader(&location) {
+            Ok(reader) => reader,
+            Err(e) => {
+                warn!("⚠️ Cannot open backup {}: {}", location.display(), e);
+                return Vec::new();
+            }
+        };
+        let mut archive = Archive::new(reader);
+        let entries = match archive.entries() {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("⚠️ Cannot read backup {}: {}", location.display(), e);
+                return Vec::new();
+            }
+        };
+        
+        let mut contents = Vec::new();
+        for entry in entries {
+            // Iterating skips over each entry's data, it is never unpacked
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("⚠️ Backup {} is damaged after {} entries: {}", location.display(), contents.len(), e);
+                    break;
+                }
+            };
+            let path = match entry.path() {
+                Ok(path) => path.into_owned(),
+                Err(_) => continue,
+            };
+            let header = entry.header();
+            contents.push(ArchiveEntry {
+                path,
+                size: header.size().unwrap_or(0),
+                mtime: header.mtime().unwrap_or(0),
+                is_dir: header.entry_type().is_dir(),
+            });
+        }
+        
+        contents
+    }
+    
     pub async fn restore_backup(&mut self, backup_id: &str, destination: PathBuf) -> Result<String> {
+        self.start_restore(backup_id, destination, None).await
+    }
+    
+    /// Restore only `paths` (as listed by list_backup_contents) from a backup.
+    /// A directory brings everything under it.
+    pub async fn restore_selected(&mut self, backup_id: &str, paths: Vec<PathBuf>, destination: PathBuf) -> Result<String> {
+        if paths.is_empty() {
+            return Err(anyhow!("No paths selected to restore"));
+        }
+        // Archive paths are relative; accept them with a leading / as well
+        let selection = paths.into_iter()
+            .map(|path| path.strip_prefix("/").map(Path::to_path_buf).unwrap_or(path))
+            .collect();
+        self.start_restore(backup_id, destination, Some(selection)).await
+    }
+    
+    async fn start_restore(&mut self, backup_id: &str, destination: PathBuf, selection: Option<Vec<PathBuf>>) -> Result<String> {
         let operation_id = Uuid::new_v4().to_string();
         
-        let backup_info = self.backup_registry.get(backup_id)
+        let backup_info = self.backup_registry.lock().unwrap().get(backup_id)
             .ok_or_else(|| anyhow!("Backup not found: {}", backup_id))?
             .clone();
         
-        info!("🔄 Starting restore: {} to {}", backup_info.name, destination.display());
+        match &selection {
+            Some(selection) => info!("🔄 Starting restore of {} paths: {} to {}", selection.len(), backup_info.name, destination.display()),
+            None => info!("🔄 Starting restore: {} to {}", backup_info.name, destination.display()),
+        }
         
         let operation = RestoreOperation {
             operation_id: operation_id.clone(),
@@ -819,28 +1441,54 @@ impl BackupManager {
             errors: Vec::new(),
         };
         
-        self.active_restores.insert(operation_id.clone(), operation);
+        self.active_restores.lock().unwrap().insert(operation_id.clone(), operation);
+        self.cancel_flags.lock().unwrap().insert(operation_id.clone(), Arc::new(AtomicBool::new(false)));
         
-        // Execute restore
-        self.execute_restore(&operation_id, &backup_info, &destination).await?;
+        // Execute restore in background so it can be cancelled
+        let manager = self.clone();
+        let task_operation_id = operation_id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = manager.execute_restore(&task_operation_id, &backup_info, &destination, selection.as_deref()).await {
+                error!("Restore failed: {}", e);
+                manager.update_restore(&task_operation_id, |op| {
+                    op.status = BackupStatus::Failed;
+                    op.errors.push(format!("Restore failed: {}", e));
+                });
+            }
+            manager.cancel_flags.lock().unwrap().remove(&task_operation_id);
+        });
         
         Ok(operation_id)
     }
     
-    async fn execute_restore(&mut self, operation_id: &str, backup_info: &BackupInfo, destination: &Path) -> Result<()> {
+    async fn execute_restore(&self, operation_id: &str, backup_info: &BackupInfo, destination: &Path, selection: Option<&[PathBuf]>) -> Result<()> {
         // Ensure destination directory exists
         fs::create_dir_all(destination)?;
         
         // Open backup archive
-        let file = std::fs::File::open(&backup_info.location)?;
-        let mut archive = Archive::new(file);
+        let mut archive = Archive::new(Self::open_archive_reader(&backup_info.location)?);
         
         let mut files_processed = 0u64;
         let entries = archive.entries()?;
         
         for entry in entries {
+            if self.is_cancelled(operation_id) {
+                self.update_restore(operation_id, |op| {
+                    op.status = BackupStatus::Cancelled;
+                    op.completed_at = Some(unix_now());
+                    op.log.push(format!("Restore cancelled after {} files", files_processed));
+                });
+                info!("⏹️ Restore cancelled after {} files", files_processed);
+                return Ok(());
+            }
+            
             let mut entry = entry?;
-            let path = entry.path()?;
+            let path = entry.path()?.into_owned();
+            if let Some(selection) = selection {
+                if !selection.iter().any(|selected| path.starts_with(selected)) {
+                    continue;
+                }
+            }
             let extract_path = destination.join(&path);
             
             // Create parent directories if needed
@@ -850,35 +1498,36 @@ impl BackupManager {
             
             // Extract file
             if let Err(e) = entry.unpack(&extract_path) {
-                if let Some(op) = self.active_restores.get_mut(operation_id) {
+                self.update_restore(operation_id, |op| {
                     op.errors.push(format!("Failed to extract {}: {}", path.display(), e));
-                }
+                });
             } else {
                 files_processed += 1;
                 
                 // Update progress
-                if let Some(op) = self.active_restores.get_mut(operation_id) {
+                self.update_restore(operation_id, |op| {
                     op.files_processed = files_processed;
                     if files_processed % 100 == 0 {
                         op.log.push(format!("Restored {} files", files_processed));
                     }
-                }
+                });
             }
         }
         
         // Mark operation as completed
-        if let Some(op) = self.active_restores.get_mut(operation_id) {
+        self.update_restore(operation_id, |op| {
             op.status = BackupStatus::Completed;
             op.progress = 100.0;
-            op.completed_at = Some(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs());
+            op.completed_at = Some(unix_now());
             op.log.push(format!("Restore completed: {} files", files_processed));
-        }
+        });
         
         info!("✅ Restore completed: {} files to {}", files_processed, destination.display());
         Ok(())
     }
     
     pub async fn create_quick_backup(&mut self) -> Result<String> {
+        let defaults = config::get().backup;
         let config = BackupConfig {
             name: "Quick Backup".to_string(),
             backup_type: BackupType::UserData,
@@ -890,8 +1539,9 @@ impl BackupManager {
             } else {
                 vec![]
             },
-            destination_path: self.backups_dir.clone(),
-            compression: CompressionType::Gzip,
+            destination_path: defaults.destination.unwrap_or_else(|| self.backups_dir.clone()),
+            compression: CompressionType::from_name(&defaults.compression),
+            compression_level: defaults.compression_level,
             exclude_patterns: vec![
                 "*.tmp".to_string(),
                 "*.cache".to_string(),
@@ -900,23 +1550,24 @@ impl BackupManager {
             include_home_dir: true,
             include_package_list: false,
             encryption_enabled: false,
-            retention_days: 30,
+            retention_days: defaults.retention_days,
             schedule_cron: None,
+            verify_level: VerifyLevel::default(),
         };
         
         self.create_backup(config).await
     }
     
     pub fn list_backups(&self) -> Vec<BackupInfo> {
-        self.backup_registry.values().cloned().collect()
+        self.backup_registry.lock().unwrap().values().cloned().collect()
     }
     
     pub fn get_backup_operation(&self, operation_id: &str) -> Option<BackupOperation> {
-        self.active_operations.get(operation_id).cloned()
+        self.active_operations.lock().unwrap().get(operation_id).cloned()
     }
     
     pub fn get_restore_operation(&self, operation_id: &str) -> Option<RestoreOperation> {
-        self.active_restores.get(operation_id).cloned()
+        self.active_restores.lock().unwrap().get(operation_id).cloned()
     }
     
     async fn cleanup_old_backups(&self, retention_days: u32) -> Result<()> {
@@ -925,7 +1576,7 @@ impl BackupManager {
         
         let mut to_remove = Vec::new();
         
-        for (backup_id, backup_info) in &self.backup_registry {
+        for (backup_id, backup_info) in self.backup_registry.lock().unwrap().iter() {
             if backup_info.timestamp < cutoff_timestamp {
                 to_remove.push(backup_id.clone());
                 
@@ -937,6 +1588,7 @@ impl BackupManager {
                         info!("🗑️ Removed old backup: {} ({})", backup_info.name, backup_info.location.display());
                     }
                 }
+                let _ = fs::remove_file(self.manifest_path(backup_id));
             }
         }
         
@@ -944,6 +1596,133 @@ impl BackupManager {
         Ok(())
     }
     
+    /// Re-check every registered backup against the checksum taken when it was
+    /// written. Backups from before checksums were kept are read through instead,
+    /// and their checksum recorded if they're intact.
+    pub async fn scrub_all(&mut self) -> ScrubReport {
+        let mut report = ScrubReport { started_at: unix_now(), ..Default::default() };
+        let backups: Vec<BackupInfo> = self.backup_registry.lock().unwrap().values().cloned().collect();
+        info!("🔍 Scrubbing {} backups", backups.len());
+        
+        let mut intact = HashMap::new();
+        for backup_info in &backups {
+            report.checked += 1;
+            if !backup_info.location.exists() {
+                warn!("❌ Backup missing: {} ({})", backup_info.name, backup_info.location.display());
+                report.missing.push(backup_info.id.clone());
+                intact.insert(backup_info.id.clone(), false);
+                continue;
+            }
+            
+            let recorded = self.backup_integrity.lock().unwrap().get(&backup_info.id).map(|integrity| integrity.sha256.clone());
+            let healthy = match recorded {
+                Some(expected) => match sha256_file(&backup_info.location) {
+                    Ok(actual) => actual == expected,
+                    Err(e) => {
+                        warn!("Failed to read {}: {}", backup_info.location.display(), e);
+                        false
+                    }
+                },
+                None => match Self::read_archive_through(&backup_info.location).and_then(|_| sha256_file(&backup_info.location).map_err(Into::into)) {
+                    Ok(sha256) => {
+                        self.backup_integrity.lock().unwrap().insert(backup_info.id.clone(), BackupIntegrity { sha256, base_backup_id: None });
+                        report.baselined.push(backup_info.id.clone());
+                        true
+                    }
+                    Err(e) => {
+                        warn!("Backup {} is unreadable: {}", backup_info.location.display(), e);
+                        false
+                    }
+                },
+            };
+            
+            if healthy {
+                report.healthy += 1;
+            } else {
+                warn!("❌ Backup corrupted: {} ({})", backup_info.name, backup_info.location.display());
+                report.corrupted.push(backup_info.id.clone());
+            }
+            intact.insert(backup_info.id.clone(), healthy);
+        }
+        
+        // Incrementals hold only what changed since their full backup, so they die with it
+        {
+            let mut integrity = self.backup_integrity.lock().unwrap();
+            integrity.retain(|backup_id, _| intact.contains_key(backup_id));
+            for (backup_id, record) in integrity.iter() {
+                let base_lost = record.base_backup_id.as_ref()
+                    .map(|base| !intact.get(base).copied().unwrap_or(false))
+                    .unwrap_or(false);
+                if base_lost && intact.get(backup_id).copied().unwrap_or(false) {
+                    report.unrestorable.push(backup_id.clone());
+                }
+            }
+        }
+        report.unrestorable.sort();
+        
+        {
+            let mut registry = self.backup_registry.lock().unwrap();
+            for (backup_id, backup_info) in registry.iter_mut() {
+                backup_info.verified = intact.get(backup_id).copied().unwrap_or(false) && !report.unrestorable.contains(backup_id);
+            }
+        }
+        
+        report.completed_at = unix_now();
+        if let Some(schedule) = self.scrub_schedule.lock().unwrap().as_mut() {
+            schedule.last_run = Some(report.completed_at);
+            schedule.run_count += 1;
+            schedule.last_report = Some(report.clone());
+        }
+        if let Err(e) = self.save_backup_registry().await {
+            warn!("Failed to save backup registry: {}", e);
+        }
+        if let Err(e) = self.save_backup_integrity().await {
+            warn!("Failed to save backup checksums: {}", e);
+        }
+        if let Err(e) = self.save_scrub_schedule().await {
+            warn!("Failed to save scrub schedule: {}", e);
+        }
+        
+        if report.is_clean() {
+            info!("✅ Scrub complete: {} backups intact", report.healthy);
+        } else {
+            warn!("⚠️ Scrub complete: {} corrupted, {} missing, {} unrestorable of {}",
+                  report.corrupted.len(), report.missing.len(), report.unrestorable.len(), report.checked);
+        }
+        report
+    }
+    
+    /// Decompress and read every entry, which is the best check possible without a checksum
+    fn read_archive_through(backup_path: &Path) -> Result<usize> {
+        let mut archive = Archive::new(Self::open_archive_reader(backup_path)?);
+        let mut entry_count = 0;
+        for entry in archive.entries()? {
+            std::io::copy(&mut entry?, &mut std::io::sink())?;
+            entry_count += 1;
+        }
+        Ok(entry_count)
+    }
+    
+    pub async fn schedule_scrub(&mut self, cron_expression: String) -> Result<String> {
+        let schedule_id = Uuid::new_v4().to_string();
+        
+        let schedule = ScrubSchedule {
+            id: schedule_id.clone(),
+            cron_expression,
+            enabled: true,
+            last_run: None,
+            next_run: None, // Would calculate based on cron expression
+            run_count: 0,
+            last_report: None,
+        };
+        
+        *self.scrub_schedule.lock().unwrap() = Some(schedule);
+        self.save_scrub_schedule().await?;
+        
+        info!("📅 Backup scrub scheduled: {}", schedule_id);
+        Ok(schedule_id)
+    }
+    
     pub async fn schedule_backup(&mut self, config: BackupConfig, cron_expression: String) -> Result<String> {
         let schedule_id = Uuid::new_v4().to_string();
         
@@ -956,10 +1735,75 @@ impl BackupManager {
             run_count: 0,
         };
         
-        self.backup_schedules.insert(schedule_id.clone(), schedule);
+        self.backup_schedules.lock().unwrap().insert(schedule_id.clone(), schedule);
         self.save_backup_schedules().await?;
         
         info!("📅 Backup scheduled: {}", schedule_id);
         Ok(schedule_id)
     }
 }
+
+/// A tar archive ends with two zero blocks; anything else means it was cut short
+fn check_tar_footer(file: &mut fs::File) -> Result<()> {
+    let len = file.metadata()?.len();
+    if len < 1024 || len % 512 != 0 {
+        return Err(anyhow!("Archive is truncated: {} bytes is not a whole number of tar blocks", len));
+    }
+    
+    let mut footer = [0u8; 1024];
+    file.seek(SeekFrom::Start(len - 1024))?;
+    file.read_exact(&mut footer)?;
+    if footer.iter().any(|&byte| byte != 0) {
+        return Err(anyhow!("Archive is truncated: no end-of-archive marker"));
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::io::BufReader::new(fs::File::open(path)?), &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Change watcher callback. Missed events (queue overflow, watcher errors)
+/// restart coverage, so the next incremental falls back to scanning.
+fn record_change_event(
+    event: notify::Result<Event>,
+    changes: &Mutex<HashMap<PathBuf, SystemTime>>,
+    coverage: &Mutex<HashMap<PathBuf, SystemTime>>,
+) {
+    let event = match event {
+        Ok(event) if !event.need_rescan() => event,
+        Ok(_) => {
+            warn!("⚠️ Change watcher missed events, incrementals will scan until the next full backup");
+            restart_coverage(coverage);
+            return;
+        }
+        Err(e) => {
+            warn!("⚠️ Change watcher error, incrementals will scan until the next full backup: {}", e);
+            restart_coverage(coverage);
+            return;
+        }
+    };
+    
+    // Removals aren't journaled; see journal_changes_since. A rename away arrives as a
+    // Modify of the old path, which is skipped later because it no longer exists.
+    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        let now = SystemTime::now();
+        let mut changes = changes.lock().unwrap();
+        for path in event.paths {
+            changes.insert(path, now);
+        }
+    }
+}
+
+fn restart_coverage(coverage: &Mutex<HashMap<PathBuf, SystemTime>>) {
+    let now = SystemTime::now();
+    for started in coverage.lock().unwrap().values_mut() {
+        *started = now;
+    }
+}
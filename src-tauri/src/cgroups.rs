@@ -0,0 +1,310 @@
+// Cgroups - Cap a misbehaving process's CPU and memory instead of killing it
+// Uses a transient systemd scope when systemd is running, a cgroup v2 directory of our own otherwise
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::error::{SysError, SysResult};
+use crate::privilege::PrivilegedBatch;
+
+pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const SYSTEMD_RUNTIME_DIR: &str = "/run/systemd/system";
+/// cpu.max period in µs; the quota is a share of this
+const CPU_PERIOD_USEC: u64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitMethod {
+    /// Scope from the user's systemd instance, for the user's own processes
+    UserScope,
+    /// Scope from the system manager, which asks polkit itself
+    SystemScope,
+    /// A directory under /sys/fs/cgroup, written through the privileged helper
+    Cgroupfs,
+}
+
+/// Limits in force on one process, as applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessLimit {
+    pub pid: u32,
+    /// Percent of one CPU, so 200 allows two full cores
+    pub cpu_percent: Option<f32>,
+    pub memory_bytes: Option<u64>,
+    pub method: LimitMethod,
+    /// Scope unit name, or the cgroup directory for Cgroupfs
+    pub unit: String,
+    /// Where the process was before, so Cgroupfs can move it back
+    #[serde(skip)]
+    original_cgroup: Option<PathBuf>,
+}
+
+/// Limits set by this app, keyed by pid
+#[derive(Default)]
+pub struct CgroupLimiter {
+    limits: Mutex<HashMap<u32, ProcessLimit>>,
+}
+
+impl CgroupLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// The limit table; a panic while it was held must not take every later call down too
+    fn table(&self) -> SysResult<MutexGuard<'_, HashMap<u32, ProcessLimit>>> {
+        self.limits.lock().map_err(|_| SysError::Other("process limit table is poisoned".to_string()))
+    }
+    
+    /// Cap `pid` at `cpu_pct` percent of one CPU and/or `mem_bytes` of memory.
+    /// Calling it again for the same process replaces its limits.
+    pub fn limit_process(&self, pid: u32, cpu_pct: Option<f32>, mem_bytes: Option<u64>) -> SysResult<ProcessLimit> {
+        if cpu_pct.is_none() && mem_bytes.is_none() {
+            return Err(SysError::InvalidParameter("a process limit needs a CPU or memory cap".to_string()));
+        }
+        if cpu_pct.map_or(false, |pct| !(pct > 0.0)) || mem_bytes == Some(0) {
+            return Err(SysError::InvalidParameter("process limits must be above zero".to_string()));
+        }
+        if !Path::new(CGROUP_ROOT).join("cgroup.controllers").exists() {
+            return Err(SysError::NotSupported("process limits without the unified cgroup v2 hierarchy".to_string()));
+        }
+        let owner = process_owner(pid)?;
+        
+        let existing = self.table()?.get(&pid).cloned();
+        let limit = match existing {
+            Some(existing) => {
+                let limit = ProcessLimit { cpu_percent: cpu_pct, memory_bytes: mem_bytes, ..existing };
+                match limit.method {
+                    LimitMethod::UserScope | LimitMethod::SystemScope => set_scope_limits(&limit)?,
+                    LimitMethod::Cgroupfs => write_cgroup_limits(&limit, false)?,
+                }
+                limit
+            }
+            None if Path::new(SYSTEMD_RUNTIME_DIR).exists() => {
+                let method = if owner == nix::unistd::getuid().as_raw() { LimitMethod::UserScope } else { LimitMethod::SystemScope };
+                let limit = ProcessLimit {
+                    pid,
+                    cpu_percent: cpu_pct,
+                    memory_bytes: mem_bytes,
+                    method,
+                    unit: format!("ai-sysadmin-limit-{}.scope", pid),
+                    original_cgroup: None,
+                };
+                start_scope(&limit)?;
+                limit
+            }
+            None => {
+                let limit = ProcessLimit {
+                    pid,
+                    cpu_percent: cpu_pct,
+                    memory_bytes: mem_bytes,
+                    method: LimitMethod::Cgroupfs,
+                    unit: Path::new(CGROUP_ROOT).join(format!("ai-sysadmin-limit-{}", pid)).display().to_string(),
+                    original_cgroup: current_cgroup(pid),
+                };
+                write_cgroup_limits(&limit, true)?;
+                limit
+            }
+        };
+        
+        info!("🧯 Limited process {} to {} CPU, {} memory ({:?})",
+            pid, describe_cpu(limit.cpu_percent), describe_memory(limit.memory_bytes), limit.method);
+        self.table()?.insert(pid, limit.clone());
+        Ok(limit)
+    }
+    
+    /// Lift the limits set by `limit_process`. A scope stays around, unlimited,
+    /// until the process exits; a cgroup directory is removed once it's empty.
+    pub fn remove_limit(&self, pid: u32) -> SysResult<ProcessLimit> {
+        let limit = self.table()?.get(&pid).cloned()
+            .ok_or_else(|| SysError::InvalidParameter(format!("process {} has no limit set by this app", pid)))?;
+        
+        match limit.method {
+            LimitMethod::UserScope | LimitMethod::SystemScope => {
+                let unlimited = ProcessLimit { cpu_percent: None, memory_bytes: None, ..limit.clone() };
+                set_scope_limits(&unlimited)?;
+            }
+            LimitMethod::Cgroupfs => {
+                let mut batch = PrivilegedBatch::new();
+                if let Some(original) = &limit.original_cgroup {
+                    batch.write(original.join("cgroup.procs"), pid.to_string());
+                }
+                batch.remove_dir(&limit.unit);
+                // Gone already when the process exited and the directory was emptied
+                if let Err(e) = batch.apply_all() {
+                    if Path::new(&format!("/proc/{}", pid)).exists() {
+                        return Err(e);
+                    }
+                    warn!("Process {} already exited: {}", pid, e);
+                }
+            }
+        }
+        
+        self.table()?.remove(&pid);
+        info!("🧯 Removed limits from process {}", pid);
+        Ok(limit)
+    }
+    
+    /// Limits currently set, dropping processes that have exited
+    pub fn limits(&self) -> SysResult<Vec<ProcessLimit>> {
+        let mut limits = self.table()?;
+        limits.retain(|pid, _| Path::new(&format!("/proc/{}", pid)).exists());
+        Ok(limits.values().cloned().collect())
+    }
+}
+
+/// UID owning `pid`
+fn process_owner(pid: u32) -> SysResult<u32> {
+    fs::metadata(format!("/proc/{}", pid))
+        .map(|metadata| metadata.uid())
+        .map_err(|_| SysError::InvalidParameter(format!("no process with pid {}", pid)))
+}
+
+/// The process's cgroup v2 directory, from the "0::" line of /proc/<pid>/cgroup
+fn current_cgroup(pid: u32) -> Option<PathBuf> {
+    fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|relative| Path::new(CGROUP_ROOT).join(relative.trim_start_matches('/')))
+}
+
+/// Create the transient scope with the process in it, the way `systemd-run --scope`
+/// does, but for a process that is already running
+fn start_scope(limit: &ProcessLimit) -> SysResult<()> {
+    run("busctl", &start_scope_args(limit))
+}
+
+/// busctl arguments for the StartTransientUnit call behind `start_scope`
+fn start_scope_args(limit: &ProcessLimit) -> Vec<String> {
+    // (name, signature, value...) per property
+    let mut properties: Vec<Vec<String>> = vec![vec!["PIDs".into(), "au".into(), "1".into(), limit.pid.to_string()]];
+    if let Some(pct) = limit.cpu_percent {
+        properties.push(vec!["CPUQuotaPerSecUSec".into(), "t".into(), ((pct as f64 / 100.0) * 1_000_000.0).round().to_string()]);
+    }
+    if let Some(bytes) = limit.memory_bytes {
+        properties.push(vec!["MemoryMax".into(), "t".into(), bytes.to_string()]);
+    }
+    
+    let mut args: Vec<String> = Vec::new();
+    if limit.method == LimitMethod::UserScope {
+        args.push("--user".into());
+    }
+    args.extend([
+        "call", "org.freedesktop.systemd1", "/org/freedesktop/systemd1", "org.freedesktop.systemd1.Manager",
+        "StartTransientUnit", "ssa(sv)a(sa(sv))",
+    ].map(String::from));
+    args.extend([limit.unit.clone(), "fail".into(), properties.len().to_string()]);
+    args.extend(properties.into_iter().flatten());
+    // No auxiliary units
+    args.push("0".into());
+    args
+}
+
+/// Change an existing scope's limits; None lifts that limit
+fn set_scope_limits(limit: &ProcessLimit) -> SysResult<()> {
+    let mut args: Vec<String> = Vec::new();
+    if limit.method == LimitMethod::UserScope {
+        args.push("--user".into());
+    }
+    args.extend(["set-property".into(), "--runtime".into(), limit.unit.clone()]);
+    args.push(match limit.cpu_percent {
+        Some(pct) => format!("CPUQuota={:.0}%", pct),
+        None => "CPUQuota=".to_string(),
+    });
+    args.push(format!("MemoryMax={}", limit.memory_bytes.map(|bytes| bytes.to_string()).unwrap_or_else(|| "infinity".to_string())));
+    
+    run("systemctl", &args)
+}
+
+/// cpu.max and memory.max on our own cgroup; `create` also makes the directory,
+/// enables the controllers on the root and moves the process in
+fn write_cgroup_limits(limit: &ProcessLimit, create: bool) -> SysResult<()> {
+    let dir = PathBuf::from(&limit.unit);
+    let memory_max = limit.memory_bytes.map(|bytes| bytes.to_string()).unwrap_or_else(|| "max".to_string());
+    
+    let mut batch = PrivilegedBatch::new();
+    if create {
+        batch.write(Path::new(CGROUP_ROOT).join("cgroup.subtree_control"), "+cpu +memory");
+        batch.create_dir(&dir);
+    }
+    batch.write(dir.join("cpu.max"), cpu_max(limit.cpu_percent));
+    batch.write(dir.join("memory.max"), memory_max);
+    if create {
+        batch.write(dir.join("cgroup.procs"), limit.pid.to_string());
+    }
+    batch.apply_all()
+}
+
+/// cpu.max value: quota and period in µs, or "max" for no quota
+fn cpu_max(cpu_percent: Option<f32>) -> String {
+    match cpu_percent {
+        Some(pct) => format!("{} {}", ((pct as f64 / 100.0) * CPU_PERIOD_USEC as f64).round() as u64, CPU_PERIOD_USEC),
+        None => format!("max {}", CPU_PERIOD_USEC),
+    }
+}
+
+fn run(program: &str, args: &[String]) -> SysResult<()> {
+    let output = Command::new(program).args(args).output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => SysError::HardwareUnavailable(format!("{} is not installed", program)),
+        _ => SysError::from(e),
+    })?;
+    if !output.status.success() {
+        return Err(SysError::command_failed(&format!("{} {}", program, args.join(" ")), &output));
+    }
+    Ok(())
+}
+
+fn describe_cpu(cpu_percent: Option<f32>) -> String {
+    cpu_percent.map(|pct| format!("{:.0}%", pct)).unwrap_or_else(|| "unlimited".to_string())
+}
+
+fn describe_memory(memory_bytes: Option<u64>) -> String {
+    memory_bytes.map(|bytes| format!("{} MB", bytes / (1024 * 1024))).unwrap_or_else(|| "unlimited".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn limit(method: LimitMethod, cpu_percent: Option<f32>, memory_bytes: Option<u64>) -> ProcessLimit {
+        ProcessLimit {
+            pid: 4242,
+            cpu_percent,
+            memory_bytes,
+            method,
+            unit: "ai-sysadmin-limit-4242.scope".to_string(),
+            original_cgroup: None,
+        }
+    }
+    
+    #[test]
+    fn start_scope_sends_a_well_formed_start_transient_unit() {
+        let args = start_scope_args(&limit(LimitMethod::UserScope, Some(50.0), Some(512 * 1024 * 1024)));
+        assert_eq!(args, [
+            "--user", "call", "org.freedesktop.systemd1", "/org/freedesktop/systemd1", "org.freedesktop.systemd1.Manager",
+            "StartTransientUnit", "ssa(sv)a(sa(sv))",
+            "ai-sysadmin-limit-4242.scope", "fail", "3",
+            "PIDs", "au", "1", "4242",
+            "CPUQuotaPerSecUSec", "t", "500000",
+            "MemoryMax", "t", "536870912",
+            "0",
+        ]);
+        
+        let args = start_scope_args(&limit(LimitMethod::SystemScope, None, Some(1024)));
+        assert_eq!(args[0], "call");
+        assert_eq!(args[7..], ["fail", "2", "PIDs", "au", "1", "4242", "MemoryMax", "t", "1024", "0"]);
+    }
+    
+    #[test]
+    fn cpu_max_is_quota_over_period() {
+        assert_eq!(cpu_max(Some(50.0)), "50000 100000");
+        assert_eq!(cpu_max(Some(250.0)), "250000 100000");
+        assert_eq!(cpu_max(Some(0.5)), "500 100000");
+        assert_eq!(cpu_max(None), "max 100000");
+    }
+}
@@ -1,9 +1,14 @@
 // Extended AI Engine Command Handlers
 // AI types will be defined locally for now
-use crate::AIRecommendation;
+use crate::action_log::{ActionLog, AppliedAction};
+use crate::ai::assistant::{self, Assistant};
+use crate::database::{self, Database};
+use crate::watchdog;
+use crate::{AIRecommendation, SystemMonitor};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use tauri::State;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,20 +32,29 @@ pub struct PerformanceTrends {
 
 // Simple state management for AI functionality
 static AI_RECOMMENDATIONS: Mutex<Vec<AIRecommendation>> = Mutex::new(Vec::new());
+static ACTION_DATABASE: Mutex<Option<Database>> = Mutex::new(None);
+
+/// Audit log backed by the shared database, opened on first use
+fn action_log() -> Result<ActionLog, String> {
+    let mut database = ACTION_DATABASE.lock().map_err(|e| e.to_string())?;
+    if database.is_none() {
+        *database = Some(Database::open(database::DEFAULT_DB_PATH).map_err(|e| e.to_string())?);
+    }
+    Ok(ActionLog::new(database.clone().unwrap()))
+}
 
 #[tauri::command]
 pub async fn get_ai_recommendations() -> Result<Vec<AIRecommendation>, String> {
-    let recommendations = AI_RECOMMENDATIONS.lock().map_err(|e| e.to_string())?;
+    // The guard is scoped rather than dropped so this future stays Send for
+    // the D-Bus and REST callers
+    let is_empty = AI_RECOMMENDATIONS.lock().map_err(|e| e.to_string())?.is_empty();
     
     // If empty, generate some sample recommendations
-    if recommendations.is_empty() {
-        drop(recommendations);
+    if is_empty {
         generate_sample_recommendations().await;
-        let recommendations = AI_RECOMMENDATIONS.lock().map_err(|e| e.to_string())?;
-        Ok(recommendations.clone())
-    } else {
-        Ok(recommendations.clone())
     }
+    let recommendations = AI_RECOMMENDATIONS.lock().map_err(|e| e.to_string())?;
+    Ok(recommendations.clone())
 }
 
 async fn generate_sample_recommendations() {
@@ -137,15 +151,35 @@ pub async fn get_performance_trends() -> Result<PerformanceTrends, String> {
 }
 
 #[tauri::command]
-pub async fn apply_ai_recommendation(recommendation_id: String) -> Result<String, String> {
+pub async fn apply_ai_recommendation(
+    recommendation_id: String,
+    system_monitor: State<'_, Arc<Mutex<SystemMonitor>>>,
+) -> Result<String, String> {
+    apply_recommendation_by_id(&recommendation_id, &system_monitor)
+}
+
+/// Shared by the Tauri command, REST and D-Bus. "Check for runaway processes"
+/// can't be carried out unattended, so applying it names the top CPU consumer;
+/// `limit_process` caps it, or `kill_process` stops it once the user confirms.
+pub fn apply_recommendation_by_id(recommendation_id: &str, system_monitor: &Mutex<SystemMonitor>) -> Result<String, String> {
     let mut recommendations = AI_RECOMMENDATIONS.lock().map_err(|e| e.to_string())?;
     
     if let Some(index) = recommendations.iter().position(|r| r.id == recommendation_id) {
+        let applied = action_log()?.apply(&recommendations[index]).map_err(|e| e.to_string())?;
         let rec = recommendations.remove(index);
         
-        // Mock applying the recommendation
         let actions_applied = rec.actions.len();
-        Ok(format!("Applied recommendation '{}' with {} actions", rec.title, actions_applied))
+        let mut message = format!("Applied recommendation '{}' with {} actions ({} settings changed)", rec.title, actions_applied, applied.after.len());
+        if rec.actions.iter().any(|action| action.to_lowercase().contains("runaway process")) {
+            let top = system_monitor.lock().ok().and_then(|monitor| monitor.top_cpu_process());
+            if let Some(top) = top {
+                message.push_str(&format!(
+                    ". Top CPU consumer: {} (pid {}, {:.0}% CPU); limit its CPU or confirm to kill it",
+                    top.name, top.pid, top.cpu_usage
+                ));
+            }
+        }
+        Ok(message)
     } else {
         Err("Recommendation not found".to_string())
     }
@@ -156,6 +190,7 @@ pub async fn dismiss_ai_recommendation(recommendation_id: String) -> Result<Stri
     let mut recommendations = AI_RECOMMENDATIONS.lock().map_err(|e| e.to_string())?;
     
     if let Some(index) = recommendations.iter().position(|r| r.id == recommendation_id) {
+        action_log()?.dismiss(&recommendations[index]).map_err(|e| e.to_string())?;
         let rec = recommendations.remove(index);
         Ok(format!("Dismissed recommendation '{}'", rec.title))
     } else {
@@ -163,26 +198,71 @@ pub async fn dismiss_ai_recommendation(recommendation_id: String) -> Result<Stri
     }
 }
 
+/// Apply every pending recommendation marked auto_apply, each through the
+/// action log so it can be undone
 #[tauri::command]
-pub async fn process_natural_language(query: String) -> Result<String, String> {
-    // Simple natural language processing mock
-    let query_lower = query.to_lowercase();
+pub async fn optimize_system_performance() -> Result<String, String> {
+    let mut recommendations = AI_RECOMMENDATIONS.lock().map_err(|e| e.to_string())?;
+    let log = action_log()?;
     
-    if query_lower.contains("performance") || query_lower.contains("slow") || query_lower.contains("speed") {
-        Ok("I can help optimize your system performance. Consider switching to performance mode, cleaning system caches, or updating your CPU governor settings. Would you like me to apply these optimizations?".to_string())
-    } else if query_lower.contains("temperature") || query_lower.contains("hot") || query_lower.contains("thermal") {
-        Ok("High temperatures can affect system performance. I recommend checking your fan curves, ensuring proper airflow, and monitoring thermal throttling. Your current thermal management settings seem appropriate for an i9-13900HX system.".to_string())
-    } else if query_lower.contains("backup") || query_lower.contains("save") || query_lower.contains("restore") {
-        Ok("For backup optimization, I suggest scheduling automated backups during low-usage periods, using incremental backup modes, and regularly cleaning old backup files. Your system appears to have sufficient storage for effective backup strategies.".to_string())
-    } else if query_lower.contains("rgb") || query_lower.contains("lighting") || query_lower.contains("color") {
-        Ok("RGB lighting can be customized through the Hardware Control panel. You can adjust colors, brightness, and effects. For gaming setups, consider profiles that match your game themes or reduce distractions during competitive play.".to_string())
-    } else if query_lower.contains("fan") || query_lower.contains("cooling") || query_lower.contains("noise") {
-        Ok("Fan management is crucial for system longevity. I can help optimize fan curves for better cooling efficiency while minimizing noise. Consider using balanced profiles for daily use and performance profiles for heavy workloads.".to_string())
-    } else if query_lower.contains("memory") || query_lower.contains("ram") {
-        Ok("Memory optimization involves managing background processes, clearing caches, and ensuring efficient memory allocation. Your system has good memory management, but occasional cleanup can improve responsiveness.".to_string())
-    } else if query_lower.contains("help") || query_lower.contains("what") || query_lower.contains("how") {
-        Ok("I'm your AI system administrator! I can help with performance optimization, thermal management, backup strategies, hardware control, and system maintenance. Ask me about specific issues like 'How can I improve gaming performance?' or 'Why is my system running hot?'".to_string())
-    } else {
-        Ok(format!("I understand you're asking about '{}'. I can help with system optimization, performance tuning, thermal management, backup strategies, and hardware control. Could you be more specific about what you'd like assistance with?", query))
+    let mut applied = Vec::new();
+    let mut failed = Vec::new();
+    recommendations.retain(|rec| {
+        if !rec.auto_apply {
+            return true;
+        }
+        match log.apply(rec) {
+            Ok(_) => {
+                applied.push(rec.title.clone());
+                false
+            }
+            Err(e) => {
+                failed.push(format!("{}: {}", rec.title, e));
+                true
+            }
+        }
+    });
+    
+    match (applied.is_empty(), failed.is_empty()) {
+        (true, true) => Ok("System already optimized: no automatic recommendations pending".to_string()),
+        (true, false) => Err(format!("Optimization failed: {}", failed.join("; "))),
+        (false, true) => Ok(format!("Applied {} recommendations: {}", applied.len(), applied.join(", "))),
+        (false, false) => Ok(format!(
+            "Applied {} recommendations: {}; failed: {}",
+            applied.len(), applied.join(", "), failed.join("; ")
+        )),
     }
 }
+
+/// Put back the settings the last apply of `recommendation_id` changed
+#[tauri::command]
+pub async fn undo_recommendation(recommendation_id: String) -> Result<String, String> {
+    let undone = action_log()?.undo(&recommendation_id).map_err(|e| e.to_string())?;
+    Ok(format!("Undid recommendation '{}', restored {} settings", undone.title, undone.after.len()))
+}
+
+#[tauri::command]
+pub async fn get_action_history(limit: Option<usize>) -> Result<Vec<AppliedAction>, String> {
+    action_log()?.history(limit.unwrap_or(50)).map_err(|e| e.to_string())
+}
+
+/// Answer a typed request and run the action it asks for. Destructive actions
+/// only run once the user has confirmed, which the frontend passes as `confirmed`.
+#[tauri::command]
+pub async fn process_natural_language(
+    query: String,
+    confirmed: Option<bool>,
+    system_monitor: State<'_, Arc<Mutex<SystemMonitor>>>,
+    assistant: State<'_, Arc<tokio::sync::Mutex<Assistant>>>,
+) -> Result<String, String> {
+    let (metrics, processes) = {
+        let monitor = system_monitor.lock().map_err(|e| e.to_string())?;
+        (monitor.latest_metrics(), monitor.processes())
+    };
+    // nvidia-smi blocks, so keep it off the async workers
+    let gpu_temperature = tokio::task::spawn_blocking(watchdog::read_gpu_temperature).await.map_err(|e| e.to_string())?;
+    let state = assistant::system_state(metrics.as_ref(), &processes, gpu_temperature);
+    
+    let mut assistant = assistant.lock().await;
+    assistant.respond(&query, &state, confirmed.unwrap_or(false)).await.map_err(|e| e.to_string())
+}
@@ -1,6 +1,11 @@
 // Hardware Control Command Handlers
 // Hardware types will be defined locally for now
-use crate::FanStatus;
+use crate::config;
+use crate::error::{SysError, SysResult};
+use crate::privilege::{self, PrivilegedBatch};
+use crate::sensors::{SensorKind, SensorMap};
+use crate::{FanControlResult, FanStatus, GpuProcess, HardwareController};
+use serde::Serialize;
 use tauri::State;
 use std::sync::{Arc, Mutex};
 use std::fs;
@@ -33,81 +38,78 @@ pub async fn get_active_hardware_profile() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn set_hardware_profile(profile_name: String) -> Result<String, String> {
+pub async fn set_hardware_profile(profile_name: String) -> Result<String, SysError> {
+    // Errors go to the frontend as {kind, message, detail} so it can e.g. offer sudo on PermissionDenied
     match profile_name.as_str() {
-        "performance" => {
-            if let Err(e) = set_cpu_governor_internal("performance").await {
-                return Err(format!("Failed to set performance profile: {}", e));
-            }
-        },
-        "power_saver" => {
-            if let Err(e) = set_cpu_governor_internal("powersave").await {
-                return Err(format!("Failed to set power saver profile: {}", e));
-            }
-        },
+        "performance" => set_cpu_governor_internal("performance").await?,
+        "power_saver" => set_cpu_governor_internal("powersave").await?,
         "gaming" => {
-            if let Err(e) = set_cpu_governor_internal("performance").await {
-                return Err(format!("Failed to set gaming profile: {}", e));
-            }
+            set_cpu_governor_internal("performance").await?;
             // Additional gaming optimizations could go here
         },
-        "balanced" | _ => {
-            if let Err(e) = set_cpu_governor_internal("schedutil").await {
-                return Err(format!("Failed to set balanced profile: {}", e));
-            }
-        },
+        "balanced" | _ => set_cpu_governor_internal("schedutil").await?,
     }
     
     Ok(format!("Hardware profile set to: {}", profile_name))
 }
 
-async fn set_cpu_governor_internal(governor: &str) -> Result<(), String> {
-    // Apply to all CPU cores
-    for cpu_id in 0..32 { // Support for up to 32 cores
+async fn set_cpu_governor_internal(governor: &str) -> SysResult<()> {
+    let available = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors")
+        .map_err(|_| SysError::NotSupported("CPU frequency scaling".to_string()))?;
+    if !available.split_whitespace().any(|g| g == governor) {
+        return Err(SysError::InvalidParameter(format!("governor '{}' is not available (have: {})", governor, available.trim())));
+    }
+    
+    // Apply to all CPU cores, with one authentication prompt when not root
+    let mut batch = PrivilegedBatch::new();
+    for cpu_id in 0..config::get().hardware.cpu_threads() {
         let governor_path = format!("/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor", cpu_id);
         if std::path::Path::new(&governor_path).exists() {
-            if let Err(_) = fs::write(&governor_path, governor) {
-                // Don't fail on individual core failures
-                continue;
-            }
+            batch.write(governor_path, governor);
         }
     }
-    Ok(())
+    
+    let mut written = 0;
+    let mut write_error = None;
+    for result in batch.apply() {
+        match result {
+            Ok(()) => written += 1,
+            // Don't fail on individual core failures
+            Err(e) => write_error = Some(e),
+        }
+    }
+    
+    match write_error {
+        Some(e) if written == 0 => Err(e),
+        _ => Ok(()),
+    }
 }
 
 #[tauri::command]
 pub async fn get_fan_status() -> Result<Vec<FanStatus>, String> {
     let mut fans = Vec::new();
     
-    // Check common hwmon paths for fan sensors
-    let hwmon_path = "/sys/class/hwmon";
-    if let Ok(entries) = fs::read_dir(hwmon_path) {
-        for entry in entries.flatten() {
-            if let Ok(name_file) = fs::read_to_string(entry.path().join("name")) {
-                let sensor_name = name_file.trim();
-                
-                // Look for fan input files
-                if let Ok(sensor_entries) = fs::read_dir(&entry.path()) {
-                    for sensor_entry in sensor_entries.flatten() {
-                        let filename = sensor_entry.file_name();
-                        let filename_str = filename.to_string_lossy();
-                        
-                        if filename_str.starts_with("fan") && filename_str.ends_with("_input") {
-                            if let Ok(rpm_str) = fs::read_to_string(sensor_entry.path()) {
-                                if let Ok(rpm) = rpm_str.trim().parse::<u32>() {
-                                    fans.push(FanStatus {
-                                        name: format!("{}_{}", sensor_name, filename_str),
-                                        rpm,
-                                        pwm: 128, // Default PWM value
-                                        auto: true,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    // Each fan is named after its chip and label, with the duty cycle of the pwm that drives it
+    let sensors = SensorMap::scan();
+    for fan in sensors.sensors_of(SensorKind::Fan) {
+        let rpm = match fan.value {
+            Some(rpm) => rpm as u32,
+            None => continue,
+        };
+        let pwm_channel = fan.channel.replacen("fan", "pwm", 1);
+        let pwm = sensors.sensors_of(SensorKind::Pwm)
+            .find(|pwm| pwm.hwmon == fan.hwmon && pwm.channel == pwm_channel);
+        let auto = pwm
+            .and_then(|pwm| fs::read_to_string(pwm.path.with_file_name(format!("{}_enable", pwm_channel))).ok())
+            .map(|mode| mode.trim() != "1")
+            .unwrap_or(true);
+        
+        fans.push(FanStatus {
+            name: fan.label.clone(),
+            rpm,
+            pwm: pwm.and_then(|pwm| pwm.value).map(|duty| duty.clamp(0.0, 255.0) as u8).unwrap_or(128),
+            auto,
+        });
     }
     
     // If no hardware fans detected, add default entries
@@ -136,6 +138,43 @@ pub async fn set_fan_speed(fan_name: String, speed: u8) -> Result<String, String
     Ok(format!("Fan {} speed set to {}%", fan_name, speed))
 }
 
+/// Set every fan to `speed`% and report which ones actually changed speed,
+/// so fans with locked firmware control can be shown as unavailable
+#[tauri::command]
+pub async fn set_all_fan_speeds(speed: u8) -> Result<Vec<FanControlResult>, String> {
+    tauri::async_runtime::spawn_blocking(move || HardwareController::control_fan_speed(speed))
+        .await
+        .map_err(|e| format!("Fan control task failed: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+/// Every hwmon temperature, fan and PWM channel tagged with its chip (CPU, GPU,
+/// drive, chipset or motherboard) and a readable label
+#[tauri::command]
+pub async fn get_sensor_map() -> Result<SensorMap, String> {
+    tauri::async_runtime::spawn_blocking(SensorMap::scan)
+        .await
+        .map_err(|e| format!("Sensor scan failed: {}", e))
+}
+
+/// Undo set_all_fan_speeds: every fan back under firmware control
+#[tauri::command]
+pub async fn set_fans_automatic() -> Result<String, String> {
+    let restored = tauri::async_runtime::spawn_blocking(HardwareController::set_fans_automatic)
+        .await
+        .map_err(|e| format!("Fan control task failed: {}", e))?
+        .map_err(|e| e.to_string())?;
+    Ok(format!("{} fans returned to automatic control", restored))
+}
+
+/// Processes holding VRAM, largest first; empty without an NVIDIA GPU
+#[tauri::command]
+pub async fn get_gpu_processes() -> Result<Vec<GpuProcess>, String> {
+    tauri::async_runtime::spawn_blocking(HardwareController::get_gpu_processes)
+        .await
+        .map_err(|e| format!("GPU process query failed: {}", e))
+}
+
 #[tauri::command]
 pub async fn get_available_cpu_governors() -> Result<Vec<String>, String> {
     let governors_path = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors";
@@ -163,9 +202,29 @@ pub async fn get_current_cpu_governor() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn set_cpu_governor(governor: String) -> Result<String, String> {
-    if let Err(e) = set_cpu_governor_internal(&governor).await {
-        return Err(e);
-    }
+pub async fn set_cpu_governor(governor: String) -> Result<String, SysError> {
+    set_cpu_governor_internal(&governor).await?;
     Ok(format!("CPU governor set to: {}", governor))
 }
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrivilegeStatus {
+    pub is_root: bool,
+    pub pkexec_available: bool,
+    pub polkit_policy_installed: bool,
+}
+
+#[tauri::command]
+pub async fn get_privilege_status() -> Result<PrivilegeStatus, String> {
+    Ok(PrivilegeStatus {
+        is_root: privilege::is_root(),
+        pkexec_available: privilege::pkexec_available(),
+        polkit_policy_installed: privilege::polkit_policy_installed(),
+    })
+}
+
+/// One-time setup so hardware changes ask for a password once per session instead of failing
+#[tauri::command]
+pub async fn install_polkit_policy() -> Result<String, SysError> {
+    privilege::install_polkit_policy()
+}
@@ -2,10 +2,12 @@
 pub mod ai_extended;
 pub mod hardware;
 pub mod monitoring;
+pub mod packages;
 pub mod rgb;
 
 // Re-export command functions for easy access
 pub use ai_extended::*;
 pub use hardware::*;
 pub use monitoring::*;
+pub use packages::*;
 pub use rgb::*;
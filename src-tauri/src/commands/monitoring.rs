@@ -1,5 +1,11 @@
 // System Monitoring Command Handlers
-use crate::SystemMetrics;
+use crate::ai::WorkloadType;
+use crate::ai::workload_classifier::WorkloadClassifier;
+use crate::api::{self, ApiSession};
+use crate::cgroups::ProcessLimit;
+use crate::logs::{JournalEntry, JournalReader};
+use crate::{ProcessCandidate, SystemMetrics, SystemMonitor};
+use nix::sys::signal::Signal;
 use tauri::State;
 use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
@@ -59,6 +65,86 @@ pub async fn get_process_list() -> Result<Vec<ProcessInfo>, String> {
     ])
 }
 
+/// The top CPU consumer, offered for killing or renicing
+#[tauri::command]
+pub async fn get_runaway_process(system_monitor: State<'_, Arc<Mutex<SystemMonitor>>>) -> Result<Option<ProcessCandidate>, String> {
+    let monitor = system_monitor.lock().map_err(|e| e.to_string())?;
+    Ok(monitor.top_cpu_process())
+}
+
+/// What the machine is being used for, going by the processes at the last sample
+#[tauri::command]
+pub async fn get_current_workload(system_monitor: State<'_, Arc<Mutex<SystemMonitor>>>) -> Result<WorkloadType, String> {
+    let monitor = system_monitor.lock().map_err(|e| e.to_string())?;
+    Ok(WorkloadClassifier::default().classify(&monitor.processes()))
+}
+
+/// Send `signal` (TERM by default) to `pid`. Nothing is sent until the user
+/// has confirmed, which the frontend passes as `confirmed`.
+#[tauri::command]
+pub async fn kill_process(
+    pid: u32,
+    signal: Option<String>,
+    confirmed: bool,
+) -> Result<String, String> {
+    let signal = match signal {
+        Some(name) => {
+            let name = name.trim().to_uppercase();
+            let name = if name.starts_with("SIG") { name } else { format!("SIG{}", name) };
+            name.parse::<Signal>().map_err(|_| format!("Unknown signal {}", name))?
+        }
+        None => Signal::SIGTERM,
+    };
+    if !confirmed {
+        return Err(format!("Sending {} to process {} needs confirmation", signal, pid));
+    }
+    
+    // Off the async runtime: pkexec may wait on the authorization prompt
+    tauri::async_runtime::spawn_blocking(move || SystemMonitor::kill_process(pid, signal))
+        .await
+        .map_err(|e| format!("Kill task failed: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn renice_process(pid: u32, nice: i32) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || SystemMonitor::renice_process(pid, nice))
+        .await
+        .map_err(|e| format!("Renice task failed: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+/// Cap a process instead of killing it; `cpu_percent` is of one CPU
+#[tauri::command]
+pub async fn limit_process(
+    pid: u32,
+    cpu_percent: Option<f32>,
+    memory_bytes: Option<u64>,
+    system_monitor: State<'_, Arc<Mutex<SystemMonitor>>>,
+) -> Result<ProcessLimit, String> {
+    // Only the limiter is needed; the monitor stays free to sample while systemd or pkexec asks
+    let limiter = system_monitor.lock().map_err(|e| e.to_string())?.cgroup_limiter();
+    tauri::async_runtime::spawn_blocking(move || SystemMonitor::limit_process(&limiter, pid, cpu_percent, memory_bytes))
+        .await
+        .map_err(|e| format!("Process limit task failed: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_process_limit(pid: u32, system_monitor: State<'_, Arc<Mutex<SystemMonitor>>>) -> Result<ProcessLimit, String> {
+    let limiter = system_monitor.lock().map_err(|e| e.to_string())?.cgroup_limiter();
+    tauri::async_runtime::spawn_blocking(move || limiter.remove_limit(pid))
+        .await
+        .map_err(|e| format!("Process limit task failed: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_process_limits(system_monitor: State<'_, Arc<Mutex<SystemMonitor>>>) -> Result<Vec<ProcessLimit>, String> {
+    let limiter = system_monitor.lock().map_err(|e| e.to_string())?.cgroup_limiter();
+    limiter.limits().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_network_interfaces() -> Result<Vec<NetworkInterface>, String> {
     // Generate sample network interface data
@@ -123,8 +209,27 @@ pub async fn get_historical_metrics(
             temperature: 40.0 + (i as f64 * 0.1) % 20.0,
             processes: 150 + (i % 20),
             uptime: 86400 + (i as u64 * 60),
+            pressure: Default::default(),
+            cpu_power_watts: None,
+            thermal_throttle_events: None,
         });
     }
     
     Ok(metrics)
 }
+
+/// URL and session token of the REST API, for the live metrics WebSocket;
+/// None unless the app was started with --serve
+#[tauri::command]
+pub async fn get_api_session() -> Result<Option<ApiSession>, String> {
+    Ok(api::session())
+}
+
+/// Journal entries at `priority` (default warning) or worse from the last `since_secs` seconds
+#[tauri::command]
+pub async fn get_recent_journal(priority: Option<u8>, since_secs: Option<u64>) -> Result<Vec<JournalEntry>, String> {
+    let reader = JournalReader::new();
+    tauri::async_runtime::spawn_blocking(move || reader.get_recent_journal(priority.unwrap_or(4), since_secs.unwrap_or(3600)))
+        .await
+        .map_err(|e| e.to_string())
+}
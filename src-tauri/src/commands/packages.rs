@@ -0,0 +1,40 @@
+// Package Command Handlers
+use tauri::Window;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::package_manager::{PackageManager, PackageOperation};
+
+/// Emitted to the calling window with the operation after each line of pacman output
+const PACKAGE_PROGRESS_EVENT: &str = "package_operation_progress";
+
+/// Sync and upgrade the system, taking a pre-upgrade snapshot first on Btrfs when
+/// `backup.pre_upgrade_snapshot` is on. Progress is emitted as
+/// `package_operation_progress` while it runs; returns the finished operation with its log.
+#[tauri::command]
+pub async fn upgrade_system(window: Window, include_aur: Option<bool>) -> Result<PackageOperation, String> {
+    let mut manager = PackageManager::new_comprehensive().await.map_err(|e| e.to_string())?;
+    
+    let mut progress = manager.progress_sender().subscribe();
+    let forwarder = tauri::async_runtime::spawn(async move {
+        loop {
+            match progress.recv().await {
+                Ok(operation) => {
+                    let _ = window.emit(PACKAGE_PROGRESS_EVENT, operation);
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    
+    let result = manager.upgrade_system(include_aur.unwrap_or(false)).await;
+    let history = std::mem::take(&mut manager.operation_history);
+    // Dropping the manager closes the channel once the forwarder has drained it
+    drop(manager);
+    let _ = forwarder.await;
+    
+    let operation_id = result.map_err(|e| e.to_string())?;
+    history.into_iter()
+        .find(|operation| operation.operation_id == operation_id)
+        .ok_or_else(|| format!("Upgrade {} finished without a record", operation_id))
+}
@@ -1,13 +1,32 @@
 // RGB Control Command Handlers
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::fs;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use crate::rgb::openrgb::{OpenRgbClient, RgbDevice, OPENRGB_DEFAULT_ADDR};
+use crate::sensors::SensorMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RgbStatus {
     pub enabled: bool,
     pub color: [u8; 3],
     pub brightness: u8,
+    pub effect: RgbEffect,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RgbEffect {
+    Static,
+    /// Fade the current color in and out once per period
+    Breathing { period_ms: u64 },
+    /// Rotate through the hue wheel, `speed` in full cycles per minute
+    Rainbow { speed: f64 },
+    /// Step through `colors`, holding each for `dwell_ms`
+    ColorCycle { colors: Vec<[u8; 3]>, dwell_ms: u64 },
+    /// Blue when the CPU is cool, red when it is hot
+    ReactiveTemp,
 }
 
 // Simple RGB state management
@@ -15,8 +34,28 @@ static RGB_STATE: Mutex<RgbStatus> = Mutex::new(RgbStatus {
     enabled: true,
     color: [255, 0, 0], // Default red
     brightness: 100,
+    effect: RgbEffect::Static,
 });
 
+// Background task driving the active effect, if any
+static RGB_EFFECT_TASK: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+// Shared OpenRGB connection; `None` client means no server was found at the last attempt
+struct OpenRgbState {
+    client: Option<OpenRgbClient>,
+    devices: Vec<RgbDevice>,
+    last_attempt: Option<Instant>,
+}
+
+static OPENRGB: OnceLock<tokio::sync::Mutex<OpenRgbState>> = OnceLock::new();
+
+// Don't probe for a missing server on every effect frame
+const OPENRGB_RETRY_SECS: u64 = 30;
+
+const EFFECT_FRAME_MS: u64 = 50;
+const REACTIVE_TEMP_POLL_MS: u64 = 1000;
+const REACTIVE_TEMP_RANGE: (f64, f64) = (40.0, 90.0);
+
 #[tauri::command]
 pub async fn get_rgb_status() -> Result<RgbStatus, String> {
     let state = RGB_STATE.lock().map_err(|e| e.to_string())?;
@@ -76,7 +115,239 @@ pub async fn set_rgb_brightness(brightness: u8) -> Result<String, String> {
     Ok(format!("RGB brightness set to {}%", brightness))
 }
 
+#[tauri::command]
+pub async fn set_rgb_effect(effect: RgbEffect) -> Result<String, String> {
+    match &effect {
+        RgbEffect::Breathing { period_ms } if *period_ms == 0 => {
+            return Err("Breathing period must be greater than zero".to_string());
+        }
+        RgbEffect::Rainbow { speed } if *speed <= 0.0 => {
+            return Err("Rainbow speed must be greater than zero".to_string());
+        }
+        RgbEffect::ColorCycle { colors, dwell_ms } if colors.is_empty() || *dwell_ms == 0 => {
+            return Err("Color cycle needs at least one color and a non-zero dwell time".to_string());
+        }
+        _ => {}
+    }
+    
+    // One lock for the swap, so concurrent calls can't both start an effect
+    // and leave one of them running unowned
+    let (enabled, color, brightness) = {
+        let mut task = RGB_EFFECT_TASK.lock().map_err(|e| e.to_string())?;
+        let (enabled, color, brightness) = {
+            let mut state = RGB_STATE.lock().map_err(|e| e.to_string())?;
+            state.effect = effect.clone();
+            (state.enabled, state.color, state.brightness)
+        };
+        
+        let handle = match effect {
+            RgbEffect::Static => None,
+            _ => Some(tokio::spawn(run_rgb_effect(effect.clone()))),
+        };
+        // The previous effect must be gone before the new one starts writing.
+        // Frames are written synchronously between sleeps, so aborting never
+        // leaves a half-written command on the device.
+        if let Some(previous) = std::mem::replace(&mut *task, handle) {
+            previous.abort();
+        }
+        (enabled, color, brightness)
+    };
+    
+    if matches!(effect, RgbEffect::Static) {
+        if enabled {
+            send_rgb_command(&color, brightness).await?;
+        }
+        return Ok("RGB effect set to static".to_string());
+    }
+    
+    Ok(format!("RGB effect started: {:?}", effect))
+}
+
+#[tauri::command]
+pub async fn stop_rgb_effect() -> Result<String, String> {
+    set_rgb_effect(RgbEffect::Static).await?;
+    Ok("RGB effect stopped".to_string())
+}
+
+async fn run_rgb_effect(effect: RgbEffect) {
+    // Custom mode is set once per effect, with the first frame, not every frame
+    if let Some(client) = openrgb_state().lock().await.client.as_mut() {
+        client.forget_custom_modes();
+    }
+    
+    let frame_ms = match effect {
+        RgbEffect::ReactiveTemp => REACTIVE_TEMP_POLL_MS,
+        _ => EFFECT_FRAME_MS,
+    };
+    let mut ticker = tokio::time::interval(Duration::from_millis(frame_ms));
+    let started = std::time::Instant::now();
+    
+    loop {
+        ticker.tick().await;
+        
+        let (enabled, base_color, brightness) = match RGB_STATE.lock() {
+            Ok(state) => (state.enabled, state.color, state.brightness),
+            Err(_) => return,
+        };
+        if !enabled {
+            continue;
+        }
+        
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let (color, brightness) = match &effect {
+            RgbEffect::Static => return,
+            RgbEffect::Breathing { period_ms } => {
+                let phase = (elapsed_ms % period_ms) as f64 / *period_ms as f64;
+                let level = (1.0 - (phase * std::f64::consts::TAU).cos()) / 2.0;
+                (base_color, (brightness as f64 * level).round() as u8)
+            }
+            RgbEffect::Rainbow { speed } => {
+                let hue = (elapsed_ms as f64 / 60_000.0 * speed * 360.0) % 360.0;
+                (hue_to_rgb(hue), brightness)
+            }
+            RgbEffect::ColorCycle { colors, dwell_ms } => {
+                let index = (elapsed_ms / dwell_ms) as usize % colors.len();
+                (colors[index], brightness)
+            }
+            RgbEffect::ReactiveTemp => match read_cpu_temperature() {
+                Some(temp) => (temperature_to_rgb(temp), brightness),
+                None => continue,
+            },
+        };
+        
+        // A missing device shouldn't kill the effect; it may appear after resume
+        let _ = send_rgb_command(&color, brightness).await;
+    }
+}
+
+/// Fully saturated color for a hue in degrees
+fn hue_to_rgb(hue: f64) -> [u8; 3] {
+    let sector = (hue / 60.0).floor() as u32 % 6;
+    let fraction = hue / 60.0 - (hue / 60.0).floor();
+    let rising = (fraction * 255.0).round() as u8;
+    let falling = 255 - rising;
+    
+    match sector {
+        0 => [255, rising, 0],
+        1 => [falling, 255, 0],
+        2 => [0, 255, rising],
+        3 => [0, falling, 255],
+        4 => [rising, 0, 255],
+        _ => [255, 0, falling],
+    }
+}
+
+/// Blue at the bottom of `REACTIVE_TEMP_RANGE`, red at the top
+fn temperature_to_rgb(temp: f64) -> [u8; 3] {
+    let (cool, hot) = REACTIVE_TEMP_RANGE;
+    let t = ((temp - cool) / (hot - cool)).clamp(0.0, 1.0);
+    [(255.0 * t).round() as u8, 0, (255.0 * (1.0 - t)).round() as u8]
+}
+
+/// CPU package temperature in °C from the CPU's hwmon chip, falling back to thermal_zone0
+fn read_cpu_temperature() -> Option<f64> {
+    if let Some(temp) = SensorMap::scan().cpu_temperature() {
+        return Some(temp);
+    }
+    
+    fs::read_to_string("/sys/class/thermal/thermal_zone0/temp").ok()
+        .and_then(|raw| raw.trim().parse::<f64>().ok())
+        .map(|millidegrees| millidegrees / 1000.0)
+}
+
+#[tauri::command]
+pub async fn list_rgb_devices() -> Result<Vec<RgbDevice>, String> {
+    let mut state = openrgb_state().lock().await;
+    ensure_openrgb_connected(&mut state).await;
+    if state.client.is_none() {
+        return Err(format!("No OpenRGB server found at {}", OPENRGB_DEFAULT_ADDR));
+    }
+    Ok(state.devices.clone())
+}
+
+#[tauri::command]
+pub async fn set_device_color(device_id: u32, leds: Vec<[u8; 3]>) -> Result<String, String> {
+    let mut state = openrgb_state().lock().await;
+    ensure_openrgb_connected(&mut state).await;
+    let client = state.client.as_mut()
+        .ok_or_else(|| format!("No OpenRGB server found at {}", OPENRGB_DEFAULT_ADDR))?;
+    
+    if let Err(e) = client.set_device_color(device_id, &leds).await {
+        state.client = None;
+        return Err(format!("Failed to set OpenRGB device {}: {}", device_id, e));
+    }
+    
+    Ok(format!("Set {} LEDs on OpenRGB device {}", leds.len(), device_id))
+}
+
+fn openrgb_state() -> &'static tokio::sync::Mutex<OpenRgbState> {
+    OPENRGB.get_or_init(|| tokio::sync::Mutex::new(OpenRgbState {
+        client: None,
+        devices: Vec::new(),
+        last_attempt: None,
+    }))
+}
+
+/// Connect (or reconnect) to the OpenRGB server, at most once per retry interval
+async fn ensure_openrgb_connected(state: &mut OpenRgbState) {
+    if state.client.is_some() {
+        return;
+    }
+    if let Some(last_attempt) = state.last_attempt {
+        if last_attempt.elapsed() < Duration::from_secs(OPENRGB_RETRY_SECS) {
+            return;
+        }
+    }
+    state.last_attempt = Some(Instant::now());
+    
+    if let Ok(mut client) = OpenRgbClient::connect(OPENRGB_DEFAULT_ADDR).await {
+        if let Ok(devices) = client.list_rgb_devices().await {
+            state.devices = devices;
+            state.client = Some(client);
+        }
+    }
+}
+
+/// Paint every OpenRGB device one color. Returns false when no server is
+/// available (or it stopped answering) so the caller can fall back.
+async fn send_openrgb_color(color: &[u8; 3], brightness: u8) -> bool {
+    let mut state = openrgb_state().lock().await;
+    ensure_openrgb_connected(&mut state).await;
+    
+    let scaled = color.map(|c| (c as u16 * brightness.min(100) as u16 / 100) as u8);
+    let devices = state.devices.clone();
+    let client = match state.client.as_mut() {
+        Some(client) => client,
+        None => return false,
+    };
+    
+    let mut delivered = true;
+    for device in &devices {
+        let leds = vec![scaled; device.led_count()];
+        if client.set_device_color(device.id, &leds).await.is_err() {
+            delivered = false;
+            break;
+        }
+    }
+    
+    // Drop a connection that stopped answering; it is retried later
+    if !delivered {
+        state.client = None;
+    }
+    delivered
+}
+
+/// Set the color on all OpenRGB devices when a server is running, otherwise
+/// on the built-in keyboard through hidraw
 async fn send_rgb_command(color: &[u8; 3], brightness: u8) -> Result<(), String> {
+    if send_openrgb_color(color, brightness).await {
+        return Ok(());
+    }
+    
+    send_hidraw_command(color, brightness).await
+}
+
+async fn send_hidraw_command(color: &[u8; 3], brightness: u8) -> Result<(), String> {
     use std::fs::OpenOptions;
     use std::io::Write;
     
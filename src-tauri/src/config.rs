@@ -0,0 +1,477 @@
+// Configuration - ~/.config/ai-sysadmin/config.toml, reloaded when the file changes
+// Every field has a default, so a missing file or a partial one is fine
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, warn};
+
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub monitoring: MonitoringConfig,
+    pub thresholds: ThresholdConfig,
+    pub hardware: HardwareConfig,
+    pub ollama: OllamaSettings,
+    pub backup: BackupDefaults,
+    pub features: FeatureToggles,
+    pub package_hooks: PackageHooks,
+    pub safety: SafetyConfig,
+    pub shutdown: ShutdownConfig,
+    pub auto_profile: AutoProfileConfig,
+    pub sensors: SensorConfig,
+    pub api: ApiConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitoringConfig {
+    pub interval_secs: u64,
+    pub alert_cooldown_secs: u64,
+    /// Metrics history is kept at full resolution this long...
+    pub raw_retention_secs: u64,
+    /// ...then as 1-minute averages up to this age...
+    pub minute_retention_secs: u64,
+    /// ...then as hourly averages until dropped at this age
+    pub hourly_retention_days: u64,
+    /// Remote ports outbound connections to public addresses normally use;
+    /// connections to any other port are flagged
+    pub expected_remote_ports: Vec<u16>,
+}
+
+/// Percentages for usage, °C for temperatures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThresholdConfig {
+    pub cpu_usage: f64,
+    pub memory_usage: f64,
+    pub disk_usage: f64,
+    pub cpu_temperature: f64,
+    /// The thermal auto-apply reverts only below this
+    pub cpu_temperature_clear: f64,
+    pub cpu_temperature_alert: f64,
+    pub gpu_temperature_alert: f64,
+    pub memory_alert: f64,
+    /// Memory PSI "some" avg60 (% of time tasks stalled on memory) that counts as
+    /// real pressure; used instead of `memory_usage` when the kernel has PSI
+    pub memory_pressure: f64,
+    /// SSD endurance used (SMART percentage_used) that warrants a replacement warning
+    pub ssd_wear_percent: f64,
+}
+
+/// Overrides for what is otherwise detected; leave unset on ordinary machines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HardwareConfig {
+    pub total_memory_gb: Option<u32>,
+    pub cpu_cores: Option<usize>,
+    pub cpu_threads: Option<usize>,
+    pub p_cores: Option<Vec<usize>>,
+    pub e_cores: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OllamaSettings {
+    pub host: String,
+    pub port: u16,
+    /// Model probed for tokens/sec on the dashboard; no probing when unset
+    pub monitor_model: Option<String>,
+    /// Each probe is a short generation, so keep this well above the metrics interval
+    pub monitor_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupDefaults {
+    /// Defaults to ./backups
+    pub destination: Option<PathBuf>,
+    /// none, gzip, zstd or lz4
+    pub compression: String,
+    /// gzip 1-9 or zstd 1-22; unset uses each method's default
+    pub compression_level: Option<u8>,
+    pub retention_days: u32,
+    /// Snapshot / before system upgrades when it is Btrfs and snap-pac isn't already doing it
+    pub pre_upgrade_snapshot: bool,
+}
+
+/// Shell commands run around package operations, in order, as the app's user.
+/// A failing pre_ hook aborts the operation; post_ hooks always run and only warn.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PackageHooks {
+    pub pre_upgrade: Vec<String>,
+    pub post_upgrade: Vec<String>,
+    pub pre_install: Vec<String>,
+    pub post_remove: Vec<String>,
+}
+
+/// Hard limits for the safety watchdog, which acts on its own when the
+/// normal cooling has failed. °C for temperatures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafetyConfig {
+    pub enabled: bool,
+    pub poll_interval_secs: u64,
+    /// Reads in a row above a limit before the watchdog acts, so one bad sensor value doesn't
+    pub consecutive_reads: u32,
+    /// Above these: powersave governor, capped CPU frequency and minimum GPU power limit.
+    /// Unset, they are `critical_margin` below the sensor's own limit (hwmon tempN_crit,
+    /// else tempN_max), since TjMax is about 100°C on Intel but 95°C on AMD.
+    pub cpu_critical: Option<f64>,
+    pub gpu_critical: Option<f64>,
+    /// Above these: `emergency_action`. Unset, `emergency_margin` below the sensor's limit.
+    pub cpu_emergency: Option<f64>,
+    pub gpu_emergency: Option<f64>,
+    pub critical_margin: f64,
+    pub emergency_margin: f64,
+    /// The throttling is lifted once both temperatures are this far below critical
+    pub recovery_margin: f64,
+    /// scaling_max_freq while throttled, as a percentage of the hardware maximum
+    pub frequency_cap_percent: u8,
+    /// none, suspend or shutdown
+    pub emergency_action: String,
+}
+
+/// The adaptive profile's control loop. The CPU limits are the
+/// thresholds.cpu_temperature / cpu_temperature_clear pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoProfileConfig {
+    pub interval_secs: u64,
+    /// Polls in a row a new workload has to be seen before settings change
+    pub switch_after_reads: u32,
+    /// Step down one level at this GPU temperature, back up below the clear value
+    pub gpu_temperature_limit: f64,
+    pub gpu_temperature_clear: f64,
+}
+
+/// Which hwmon sensors the monitor reads, by key as listed on the dashboard,
+/// e.g. "acpitz_temp1_input" or "nct6798_fan2_input"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SensorConfig {
+    /// When set, only these are read, and they are never dropped as dead
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Added to each reading: °C for temperatures, RPM for fans
+    pub offsets: HashMap<String, f64>,
+    /// A sensor stuck on its first value for this many samples is dropped; 0 keeps them all
+    pub dead_after_samples: u32,
+}
+
+/// REST API (`--serve`) settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// Host names clients may reach the API by besides the bound address and
+    /// localhost, e.g. "nas.lan" when serving on 0.0.0.0; the API port is implied
+    pub allowed_hosts: Vec<String>,
+}
+
+/// What happens on quit, SIGTERM or SIGINT
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// Exit anyway once saving has taken this long
+    pub timeout_secs: u64,
+    /// Undo the recommendations applied during this run, as if the app was never started
+    pub revert_applied_changes: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeatureToggles {
+    pub auto_apply: bool,
+    pub dbus_service: bool,
+    pub learning: bool,
+}
+
+impl Default for MonitoringConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 30,
+            alert_cooldown_secs: 300,
+            raw_retention_secs: 3600,
+            minute_retention_secs: 86400,
+            hourly_retention_days: 30,
+            // ssh, dns, http, ntp, smtp submission, dns-over-tls, imaps, pop3s, https-alt, git
+            expected_remote_ports: vec![22, 53, 80, 123, 443, 465, 587, 853, 993, 995, 8443, 9418],
+        }
+    }
+}
+
+impl Default for ThresholdConfig {
+    fn default() -> Self {
+        Self {
+            cpu_usage: 90.0,
+            memory_usage: 85.0,
+            disk_usage: 90.0,
+            cpu_temperature: 80.0,
+            cpu_temperature_clear: 75.0,
+            cpu_temperature_alert: 95.0,
+            gpu_temperature_alert: 87.0,
+            memory_alert: 95.0,
+            memory_pressure: 10.0,
+            ssd_wear_percent: 80.0,
+        }
+    }
+}
+
+impl Default for OllamaSettings {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 11434,
+            monitor_model: None,
+            monitor_interval_secs: 300,
+        }
+    }
+}
+
+impl Default for BackupDefaults {
+    fn default() -> Self {
+        Self {
+            destination: None,
+            compression: "gzip".to_string(),
+            compression_level: None,
+            retention_days: 30,
+            pre_upgrade_snapshot: true,
+        }
+    }
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: 2,
+            consecutive_reads: 3,
+            cpu_critical: None,
+            gpu_critical: None,
+            cpu_emergency: None,
+            gpu_emergency: None,
+            critical_margin: 8.0,
+            emergency_margin: 2.0,
+            recovery_margin: 15.0,
+            frequency_cap_percent: 50,
+            emergency_action: "suspend".to_string(),
+        }
+    }
+}
+
+impl Default for AutoProfileConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 5,
+            switch_after_reads: 3,
+            gpu_temperature_limit: 83.0,
+            gpu_temperature_clear: 76.0,
+        }
+    }
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 10,
+            revert_applied_changes: false,
+        }
+    }
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            offsets: HashMap::new(),
+            dead_after_samples: 20,
+        }
+    }
+}
+
+impl Default for FeatureToggles {
+    fn default() -> Self {
+        Self {
+            auto_apply: true,
+            dbus_service: true,
+            learning: true,
+        }
+    }
+}
+
+impl MonitoringConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+impl SensorConfig {
+    /// Whether a detected sensor is read at all
+    pub fn allows(&self, key: &str) -> bool {
+        (self.include.is_empty() || self.is_included(key)) && !self.exclude.iter().any(|excluded| excluded == key)
+    }
+    
+    pub fn is_included(&self, key: &str) -> bool {
+        self.include.iter().any(|included| included == key)
+    }
+    
+    pub fn offset(&self, key: &str) -> f64 {
+        self.offsets.get(key).copied().unwrap_or(0.0)
+    }
+}
+
+impl AutoProfileConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+impl ShutdownConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.max(1))
+    }
+}
+
+impl SafetyConfig {
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval_secs.max(1))
+    }
+}
+
+impl HardwareConfig {
+    /// Installed RAM in GB, from /proc/meminfo unless overridden
+    pub fn total_memory_gb(&self) -> u32 {
+        self.total_memory_gb.unwrap_or_else(|| {
+            fs::read_to_string("/proc/meminfo").ok()
+                .and_then(|meminfo| {
+                    meminfo.lines()
+                        .find(|line| line.starts_with("MemTotal:"))
+                        .and_then(|line| line.split_whitespace().nth(1))
+                        .and_then(|kb| kb.parse::<u64>().ok())
+                })
+                .map(|kb| ((kb as f64) / 1024.0 / 1024.0).round() as u32)
+                .unwrap_or(16)
+        })
+    }
+    
+    /// Logical CPUs (hardware threads)
+    pub fn cpu_threads(&self) -> usize {
+        self.cpu_threads.unwrap_or_else(num_cpus::get)
+    }
+    
+    /// Physical cores
+    pub fn cpu_cores(&self) -> usize {
+        self.cpu_cores.unwrap_or_else(num_cpus::get_physical)
+    }
+}
+
+impl OllamaSettings {
+    pub fn base_url(&self) -> String {
+        format!("http://{}:{}", self.host, self.port)
+    }
+    
+    pub fn monitor_interval(&self) -> Duration {
+        Duration::from_secs(self.monitor_interval_secs.max(10))
+    }
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        let config_home = std::env::var("XDG_CONFIG_HOME").ok()
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+                PathBuf::from(home).join(".config")
+            });
+        config_home.join("ai-sysadmin").join("config.toml")
+    }
+    
+    /// Read the config file, falling back to defaults if it doesn't exist
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::path())
+    }
+    
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            debug!("No config at {}, using defaults", path.display());
+            return Ok(Self::default());
+        }
+        
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).context(format!("Invalid config in {}", path.display()))
+    }
+    
+    /// Reload the global config whenever the file is written. Keep the returned
+    /// watcher alive for as long as reloading should happen.
+    pub fn watch() -> Result<RecommendedWatcher> {
+        let path = Self::path();
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+        fs::create_dir_all(&dir)?;
+        
+        let watched = path.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("⚠️ Config watcher error: {}", e);
+                    return;
+                }
+            };
+            // Editors often write a temp file and rename it over the original
+            let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_))
+                && event.paths.iter().any(|p| p == &watched);
+            if !relevant {
+                return;
+            }
+            
+            match Self::load_from(&watched) {
+                Ok(config) => {
+                    set(config);
+                    info!("🔄 Reloaded configuration from {}", watched.display());
+                }
+                // Keep running with the last good config
+                Err(e) => error!("Config reload failed, keeping previous settings: {:#}", e),
+            }
+        })?;
+        
+        // The directory, not the file, so replacing the file is still seen
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        info!("👀 Watching {} for changes", path.display());
+        Ok(watcher)
+    }
+}
+
+/// Snapshot of the current config, loading it on first use
+pub fn get() -> Config {
+    if let Ok(config) = CONFIG.read() {
+        if let Some(config) = config.as_ref() {
+            return config.clone();
+        }
+    }
+    
+    let config = Config::load().unwrap_or_else(|e| {
+        error!("Failed to load config, using defaults: {:#}", e);
+        Config::default()
+    });
+    set(config.clone());
+    config
+}
+
+pub fn set(config: Config) {
+    match CONFIG.write() {
+        Ok(mut current) => *current = Some(config),
+        Err(poisoned) => *poisoned.into_inner() = Some(config),
+    }
+}
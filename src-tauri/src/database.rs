@@ -1,16 +1,396 @@
 // Database module for AI learning and system data storage
-use anyhow::Result;
-use std::path::PathBuf;
+// One SQLite connection shared by every AI component; the schema is versioned with PRAGMA user_version
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tracing::{debug, info};
 
+use crate::action_log::{ActionKind, AppliedAction};
+use crate::{AIInsight, SystemMetrics};
+
+/// Kept at the name the first releases used so existing history is picked up
+pub const DEFAULT_DB_PATH: &str = "ai_sysadmin.db";
+
+/// Bump together with a new step in `migrate`
+const SCHEMA_VERSION: i64 = 4;
+
+/// Cheap to clone; every clone uses the same connection
+#[derive(Clone)]
 pub struct Database {
     db_path: PathBuf,
+    connection: Arc<Mutex<Connection>>,
 }
 
 impl Database {
     pub async fn new() -> Result<Self> {
-        let db_path = PathBuf::from("data/database.db");
-        std::fs::create_dir_all("data")?;
+        Self::open(DEFAULT_DB_PATH)
+    }
+    
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let db_path = path.as_ref().to_path_buf();
+        if let Some(parent) = db_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        let conn = Connection::open(&db_path)
+            .context(format!("Failed to open database {}", db_path.display()))?;
+        // The GUI and the daemon may both have the file open
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
         
-        Ok(Self { db_path })
+        migrate(&conn)?;
+        info!("🗄️ Database ready at {}", db_path.display());
+        
+        Ok(Self {
+            db_path,
+            connection: Arc::new(Mutex::new(conn)),
+        })
+    }
+    
+    pub fn path(&self) -> &Path {
+        &self.db_path
+    }
+    
+    fn lock(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.connection.lock().map_err(|e| anyhow!("Database lock poisoned: {}", e))
+    }
+    
+    pub fn record_metrics(&self, metrics: &SystemMetrics) -> Result<()> {
+        let metrics_json = serde_json::to_string(metrics)?;
+        self.lock()?.execute(
+            "INSERT INTO system_history (metrics, timestamp) VALUES (?1, ?2)",
+            params![metrics_json, metrics.timestamp.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+    
+    /// Disk usage (%) per mount point since `since`, oldest first. Rows that only
+    /// carry an overall disk_usage number are counted as "/".
+    pub fn disk_usage_history(&self, since: DateTime<Utc>) -> Result<BTreeMap<String, Vec<(DateTime<Utc>, f64)>>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT metrics, timestamp FROM system_history WHERE timestamp >= ?1 ORDER BY id"
+        )?;
+        let rows = stmt.query_map(params![since.to_rfc3339()], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        
+        let mut history: BTreeMap<String, Vec<(DateTime<Utc>, f64)>> = BTreeMap::new();
+        for row in rows {
+            let (metrics, timestamp) = row?;
+            let (metrics, timestamp) = match (serde_json::from_str::<serde_json::Value>(&metrics), timestamp.parse::<DateTime<Utc>>()) {
+                (Ok(metrics), Ok(timestamp)) => (metrics, timestamp),
+                _ => continue,
+            };
+            
+            match &metrics["disk_usage"] {
+                serde_json::Value::Object(mounts) => {
+                    for (mount, disk) in mounts {
+                        if let Some(percent) = disk["usage_percent"].as_f64() {
+                            history.entry(mount.clone()).or_default().push((timestamp, percent));
+                        }
+                    }
+                }
+                serde_json::Value::Number(percent) => {
+                    if let Some(percent) = percent.as_f64() {
+                        history.entry("/".to_string()).or_default().push((timestamp, percent));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(history)
+    }
+    
+    /// `source` names the component that produced the insight
+    pub fn store_insight(&self, insight: &AIInsight, source: &str) -> Result<()> {
+        self.lock()?.execute(
+            "INSERT INTO ai_insights (pattern, confidence, recommendation, priority, timestamp, source)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                insight.pattern,
+                insight.confidence,
+                insight.recommendation,
+                insight.priority,
+                insight.timestamp.to_rfc3339(),
+                source
+            ],
+        )?;
+        Ok(())
+    }
+    
+    /// Unapplied insights, most urgent first
+    pub fn pending_insights(&self, limit: usize) -> Result<Vec<AIInsight>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT pattern, confidence, recommendation, priority, timestamp
+             FROM ai_insights
+             WHERE applied = FALSE
+             ORDER BY priority ASC, confidence DESC
+             LIMIT ?1"
+        )?;
+        
+        let insights = stmt.query_map(params![limit as i64], |row| {
+            Ok(AIInsight {
+                pattern: row.get(0)?,
+                confidence: row.get(1)?,
+                recommendation: row.get(2)?,
+                priority: row.get(3)?,
+                timestamp: row.get::<_, String>(4)?.parse().unwrap_or(Utc::now()),
+                journal_context: Vec::new(),
+            })
+        })?;
+        
+        Ok(insights.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+    
+    pub fn record_pattern(&self, name: &str, value: f64, confidence: f64) -> Result<()> {
+        self.lock()?.execute(
+            "INSERT INTO system_patterns (pattern_name, pattern_value, timestamp, confidence)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![name, value, Utc::now().to_rfc3339(), confidence],
+        )?;
+        Ok(())
+    }
+    
+    /// Most recent value of every pattern whose name starts with `prefix`
+    pub fn latest_patterns(&self, prefix: &str) -> Result<HashMap<String, f64>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT pattern_name, pattern_value FROM system_patterns
+             WHERE id IN (SELECT MAX(id) FROM system_patterns GROUP BY pattern_name)
+             AND substr(pattern_name, 1, length(?1)) = ?1"
+        )?;
+        
+        let patterns = stmt.query_map(params![prefix], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))?;
+        Ok(patterns.collect::<rusqlite::Result<HashMap<_, _>>>()?)
+    }
+    
+    /// Replace every pattern whose name starts with `prefix` with `values`,
+    /// whose keys are the names without the prefix
+    pub fn replace_patterns(&self, prefix: &str, values: &HashMap<String, f64>, confidence: f64) -> Result<()> {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM system_patterns WHERE substr(pattern_name, 1, length(?1)) = ?1",
+            params![prefix],
+        )?;
+        let timestamp = Utc::now().to_rfc3339();
+        for (name, value) in values {
+            tx.execute(
+                "INSERT INTO system_patterns (pattern_name, pattern_value, timestamp, confidence)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![format!("{}{}", prefix, name), value, timestamp, confidence],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+    
+    pub fn set_learning_value(&self, key: &str, value: &serde_json::Value) -> Result<()> {
+        self.lock()?.execute(
+            "INSERT INTO learning_data (key, value, updated) VALUES (?1, ?2, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated = excluded.updated",
+            params![key, value.to_string(), Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+    
+    pub fn learning_values(&self) -> Result<HashMap<String, serde_json::Value>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare("SELECT key, value FROM learning_data")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        
+        let mut values = HashMap::new();
+        for row in rows {
+            let (key, value) = row?;
+            match serde_json::from_str(&value) {
+                Ok(value) => {
+                    values.insert(key, value);
+                }
+                Err(e) => debug!("Skipping unreadable learning value {}: {}", key, e),
+            }
+        }
+        Ok(values)
+    }
+    
+    pub fn record_action(&self, action: &AppliedAction) -> Result<()> {
+        self.lock()?.execute(
+            "INSERT INTO applied_actions (recommendation_id, title, kind, before, after, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                action.id,
+                action.title,
+                action.kind.as_str(),
+                serde_json::to_string(&action.before)?,
+                serde_json::to_string(&action.after)?,
+                action.timestamp as i64
+            ],
+        )?;
+        Ok(())
+    }
+    
+    /// Newest audit entry for a recommendation
+    pub fn latest_action(&self, recommendation_id: &str) -> Result<Option<AppliedAction>> {
+        Ok(self.query_actions(
+            "SELECT recommendation_id, title, kind, before, after, timestamp FROM applied_actions
+             WHERE recommendation_id = ?1 ORDER BY id DESC LIMIT 1",
+            params![recommendation_id],
+        )?.pop())
+    }
+    
+    /// Newest first
+    pub fn action_history(&self, limit: usize) -> Result<Vec<AppliedAction>> {
+        self.query_actions(
+            "SELECT recommendation_id, title, kind, before, after, timestamp FROM applied_actions
+             ORDER BY id DESC LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+    
+    /// Append a natural-language command to the history, keeping only the newest `keep` rows.
+    /// `entry` is the serialized history entry; this table doesn't interpret it.
+    pub fn record_command(&self, entry: &serde_json::Value, keep: usize) -> Result<()> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT INTO command_history (entry, timestamp) VALUES (?1, ?2)",
+            params![entry.to_string(), Utc::now().to_rfc3339()],
+        )?;
+        conn.execute(
+            "DELETE FROM command_history WHERE id NOT IN (SELECT id FROM command_history ORDER BY id DESC LIMIT ?1)",
+            params![keep as i64],
+        )?;
+        Ok(())
+    }
+    
+    /// Newest first
+    pub fn command_history(&self, limit: usize) -> Result<Vec<serde_json::Value>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare("SELECT entry FROM command_history ORDER BY id DESC LIMIT ?1")?;
+        let rows = stmt.query_map(params![limit as i64], |row| row.get::<_, String>(0))?;
+        
+        let mut entries = Vec::new();
+        for row in rows {
+            let entry = row?;
+            match serde_json::from_str(&entry) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => debug!("Skipping unreadable command history entry: {}", e),
+            }
+        }
+        Ok(entries)
+    }
+    
+    fn query_actions(&self, sql: &str, args: &[&dyn rusqlite::ToSql]) -> Result<Vec<AppliedAction>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(args, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?;
+        
+        let mut actions = Vec::new();
+        for row in rows {
+            let (id, title, kind, before, after, timestamp) = row?;
+            actions.push(AppliedAction {
+                id,
+                title,
+                kind: ActionKind::parse(&kind).ok_or(anyhow!("Unknown action kind '{}'", kind))?,
+                before: serde_json::from_str(&before)?,
+                after: serde_json::from_str(&after)?,
+                timestamp: timestamp as u64,
+            });
+        }
+        Ok(actions)
+    }
+}
+
+/// Bring the schema up to SCHEMA_VERSION. Steps only add to what is there, so
+/// an ai_sysadmin.db written before versioning (user_version 0) keeps its rows.
+fn migrate(conn: &Connection) -> Result<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+    
+    if version < 1 {
+        // The tables the original main.rs AIEngine created
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS system_patterns (
+                id INTEGER PRIMARY KEY,
+                pattern_name TEXT NOT NULL,
+                pattern_value REAL NOT NULL,
+                timestamp TEXT NOT NULL,
+                confidence REAL NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS ai_insights (
+                id INTEGER PRIMARY KEY,
+                pattern TEXT NOT NULL,
+                confidence REAL NOT NULL,
+                recommendation TEXT NOT NULL,
+                priority INTEGER NOT NULL,
+                timestamp TEXT NOT NULL,
+                applied BOOLEAN DEFAULT FALSE
+            );
+            CREATE TABLE IF NOT EXISTS system_history (
+                id INTEGER PRIMARY KEY,
+                metrics TEXT NOT NULL,
+                timestamp TEXT NOT NULL
+            );"
+        )?;
+    }
+    
+    if version < 2 {
+        // Insights from all AI components share one table; learning_data replaces ai_learning_data.json
+        conn.execute_batch(
+            "BEGIN;
+            ALTER TABLE ai_insights ADD COLUMN source TEXT NOT NULL DEFAULT 'monitor';
+            CREATE TABLE IF NOT EXISTS learning_data (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_system_patterns_name ON system_patterns (pattern_name);
+            CREATE INDEX IF NOT EXISTS idx_system_history_timestamp ON system_history (timestamp);
+            COMMIT;"
+        )?;
+    }
+    
+    if version < 3 {
+        // Audit trail for applied/dismissed recommendations; before/after are JSON SettingValue lists
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS applied_actions (
+                id INTEGER PRIMARY KEY,
+                recommendation_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                before TEXT NOT NULL,
+                after TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_applied_actions_recommendation ON applied_actions (recommendation_id);"
+        )?;
+    }
+    
+    if version < 4 {
+        // Natural-language commands with their parsed intent and outcome, as JSON
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS command_history (
+                id INTEGER PRIMARY KEY,
+                entry TEXT NOT NULL,
+                timestamp TEXT NOT NULL
+            );"
+        )?;
     }
+    
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    info!("🗄️ Database schema migrated from version {} to {}", version, SCHEMA_VERSION);
+    Ok(())
 }
@@ -0,0 +1,154 @@
+// D-Bus Service - Interface for desktop widgets (Waybar, KDE, etc.)
+// Exposes metrics and AI recommendations as JSON at org.wlfogle.AiSysadmin: on the
+// session bus from the GUI, on the system bus from the --daemon system service
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::time::interval;
+use tracing::{debug, info, warn};
+use zbus::{fdo, interface, Connection, SignalContext};
+
+use crate::commands::{apply_recommendation_by_id, get_ai_recommendations};
+use crate::SystemMonitor;
+
+pub const DBUS_SERVICE_NAME: &str = "org.wlfogle.AiSysadmin";
+pub const DBUS_OBJECT_PATH: &str = "/org/wlfogle/AiSysadmin";
+/// Lets the daemon own the name on the system bus; written by `--install-service`
+pub const DBUS_POLICY_PATH: &str = "/etc/dbus-1/system.d/org.wlfogle.AiSysadmin.conf";
+
+/// Which bus to register on. A system service has no session bus, so the
+/// daemon uses the system bus, which needs the policy file to own the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    Session,
+    System,
+}
+
+/// How often new recommendations are checked for the RecommendationAdded signal
+const RECOMMENDATION_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct AiSysadminService {
+    system_monitor: Arc<Mutex<SystemMonitor>>,
+}
+
+#[interface(name = "org.wlfogle.AiSysadmin")]
+impl AiSysadminService {
+    /// Latest SystemMetrics as JSON, collecting a fresh sample if none exists yet
+    async fn get_system_metrics(&self) -> fdo::Result<String> {
+        let metrics = {
+            let mut monitor = self.system_monitor.lock()
+                .map_err(|e| fdo::Error::Failed(format!("System monitor lock poisoned: {}", e)))?;
+            match monitor.latest_metrics() {
+                Some(metrics) => metrics,
+                None => monitor.collect_metrics().map_err(|e| fdo::Error::Failed(e.to_string()))?,
+            }
+        };
+        
+        serde_json::to_string(&metrics).map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+    
+    /// Pending recommendations, one JSON object per array element
+    async fn get_recommendations(&self) -> fdo::Result<Vec<String>> {
+        let recommendations = get_ai_recommendations().await.map_err(fdo::Error::Failed)?;
+        recommendations.iter()
+            .map(|rec| serde_json::to_string(rec).map_err(|e| fdo::Error::Failed(e.to_string())))
+            .collect()
+    }
+    
+    async fn apply_recommendation(&self, id: String) -> fdo::Result<String> {
+        info!("📡 Applying recommendation {} requested over D-Bus", id);
+        apply_recommendation_by_id(&id, &self.system_monitor).map_err(fdo::Error::Failed)
+    }
+    
+    #[zbus(signal)]
+    async fn recommendation_added(ctxt: &SignalContext<'_>, recommendation: String) -> zbus::Result<()>;
+}
+
+/// Registers the service on `bus` and emits RecommendationAdded for each new
+/// recommendation. Runs until the connection fails.
+pub async fn run_dbus_service(system_monitor: Arc<Mutex<SystemMonitor>>, bus: Bus) -> Result<()> {
+    let service = AiSysadminService { system_monitor };
+    let (builder, bus_name) = match bus {
+        Bus::Session => (zbus::connection::Builder::session()?, "session"),
+        Bus::System => (zbus::connection::Builder::system()?, "system"),
+    };
+    let connection = builder
+        .name(DBUS_SERVICE_NAME)?
+        .serve_at(DBUS_OBJECT_PATH, service)?
+        .build()
+        .await
+        .with_context(|| match bus {
+            Bus::Session => format!("Failed to register {} on the session bus", DBUS_SERVICE_NAME),
+            Bus::System => format!("Failed to register {} on the system bus (is {} installed? run --install-service)", DBUS_SERVICE_NAME, DBUS_POLICY_PATH),
+        })?;
+    
+    info!("📡 D-Bus service available on the {} bus at {} {}", bus_name, DBUS_SERVICE_NAME, DBUS_OBJECT_PATH);
+    
+    watch_recommendations(&connection).await
+}
+
+/// System bus policy: `owner` (the daemon's user) may own the name, anyone may
+/// read metrics and recommendations, and only root and wheel may apply one
+pub fn system_bus_policy(owner: &str) -> String {
+    let allow = |member: &str| format!(
+        "    <allow send_destination=\"{}\" send_interface=\"{}\" send_member=\"{}\"/>\n",
+        DBUS_SERVICE_NAME, DBUS_SERVICE_NAME, member
+    );
+    
+    let mut policy = String::new();
+    policy.push_str("<!DOCTYPE busconfig PUBLIC \"-//freedesktop//DTD D-BUS Bus Configuration 1.0//EN\"\n");
+    policy.push_str(" \"http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd\">\n");
+    policy.push_str("<busconfig>\n");
+    policy.push_str(&format!("  <policy user=\"{}\">\n", owner));
+    policy.push_str(&format!("    <allow own=\"{}\"/>\n", DBUS_SERVICE_NAME));
+    policy.push_str("  </policy>\n");
+    policy.push_str("  <policy context=\"default\">\n");
+    policy.push_str(&allow("GetSystemMetrics"));
+    policy.push_str(&allow("GetRecommendations"));
+    policy.push_str(&format!(
+        "    <allow send_destination=\"{}\" send_interface=\"org.freedesktop.DBus.Introspectable\"/>\n",
+        DBUS_SERVICE_NAME
+    ));
+    policy.push_str("  </policy>\n");
+    for admin in ["user=\"root\"", "group=\"wheel\""] {
+        policy.push_str(&format!("  <policy {}>\n", admin));
+        policy.push_str(&allow("ApplyRecommendation"));
+        policy.push_str("  </policy>\n");
+    }
+    policy.push_str("</busconfig>\n");
+    policy
+}
+
+async fn watch_recommendations(connection: &Connection) -> Result<()> {
+    let ctxt = SignalContext::new(connection, DBUS_OBJECT_PATH)?;
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut interval = interval(RECOMMENDATION_POLL_INTERVAL);
+    
+    loop {
+        interval.tick().await;
+        
+        let recommendations = match get_ai_recommendations().await {
+            Ok(recommendations) => recommendations,
+            Err(e) => {
+                warn!("⚠️ Failed to read recommendations for D-Bus: {}", e);
+                continue;
+            }
+        };
+        
+        for rec in &recommendations {
+            if !seen.insert(rec.id.clone()) {
+                continue;
+            }
+            
+            debug!("📡 Emitting RecommendationAdded for {}", rec.id);
+            let json = serde_json::to_string(rec)?;
+            AiSysadminService::recommendation_added(&ctxt, json).await?;
+        }
+        
+        // Forget applied/dismissed ones so the set stays small
+        seen.retain(|id| recommendations.iter().any(|rec| &rec.id == id));
+    }
+}
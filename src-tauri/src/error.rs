@@ -0,0 +1,111 @@
+// Errors - What can go wrong when touching the system, in a form callers can match on
+// The command layer turns these into specific UI messages, e.g. a sudo prompt for PermissionDenied
+
+use std::io;
+use std::path::Path;
+use std::process::Output;
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+pub type SysResult<T> = Result<T, SysError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SysError {
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    /// The device, driver or kernel interface isn't present on this machine
+    #[error("Hardware unavailable: {0}")]
+    HardwareUnavailable(String),
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+    #[error("{cmd} failed{}: {stderr}", code.map(|code| format!(" (exit code {})", code)).unwrap_or_default())]
+    CommandFailed {
+        cmd: String,
+        /// None when the command was killed by a signal
+        code: Option<i32>,
+        stderr: String,
+    },
+    /// Present, but refusing or unable to do this particular thing
+    #[error("Not supported: {0}")]
+    NotSupported(String),
+    #[error(transparent)]
+    Io(io::Error),
+    /// Errors from code that still reports them as plain strings
+    #[error("{0}")]
+    Other(String),
+}
+
+impl SysError {
+    /// An I/O error on `path`, with EACCES/EPERM and ENOENT given their own variants
+    pub fn io(path: &Path, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => SysError::PermissionDenied(path.display().to_string()),
+            io::ErrorKind::NotFound => SysError::HardwareUnavailable(format!("{} does not exist", path.display())),
+            _ => SysError::Io(io::Error::new(error.kind(), format!("{}: {}", path.display(), error))),
+        }
+    }
+    
+    /// A command that ran and exited unsuccessfully
+    pub fn command_failed(cmd: &str, output: &Output) -> Self {
+        SysError::CommandFailed {
+            cmd: cmd.to_string(),
+            code: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+    }
+    
+    /// Variant name, for the UI to switch on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SysError::PermissionDenied(_) => "PermissionDenied",
+            SysError::HardwareUnavailable(_) => "HardwareUnavailable",
+            SysError::InvalidParameter(_) => "InvalidParameter",
+            SysError::CommandFailed { .. } => "CommandFailed",
+            SysError::NotSupported(_) => "NotSupported",
+            SysError::Io(_) => "Io",
+            SysError::Other(_) => "Other",
+        }
+    }
+    
+    /// What to tell the user, including what they can do about it
+    pub fn user_message(&self) -> String {
+        match self {
+            SysError::PermissionDenied(what) => format!("Administrator rights are needed to change {}. Run with sudo or authorize the request.", what),
+            SysError::HardwareUnavailable(what) => format!("Not available on this machine: {}", what),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl From<io::Error> for SysError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => SysError::PermissionDenied(error.to_string()),
+            _ => SysError::Io(error),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for SysError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        SysError::Other(error.to_string())
+    }
+}
+
+/// Tauri commands can return SysError directly; the frontend gets {kind, message, detail}
+impl Serialize for SysError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SysError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.user_message())?;
+        state.serialize_field("detail", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl From<SysError> for String {
+    fn from(error: SysError) -> Self {
+        error.user_message()
+    }
+}
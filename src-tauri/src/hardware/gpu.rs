@@ -0,0 +1,226 @@
+// AMD GPUs - amdgpu monitoring from rocm-smi when installed, sysfs otherwise,
+// and control through the DPM performance level
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, debug, warn};
+use tokio::process::Command as AsyncCommand;
+use crate::error::{SysError, SysResult};
+use crate::privilege::PrivilegedBatch;
+
+pub const DRM_DIR: &str = "/sys/class/drm";
+const AMD_VENDOR_ID: &str = "0x1002";
+
+/// Values accepted by power_dpm_force_performance_level
+pub const PERFORMANCE_LEVELS: &[&str] = &[
+    "auto", "low", "high", "manual",
+    "profile_standard", "profile_min_sclk", "profile_min_mclk", "profile_peak",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmdGpuInfo {
+    /// DRM card name, e.g. card0
+    pub card: String,
+    pub name: String,
+    pub utilization_percent: f64,
+    /// Edge sensor
+    pub temperature_celsius: Option<f64>,
+    pub vram_used_bytes: u64,
+    pub vram_total_bytes: u64,
+    pub power_watts: Option<f64>,
+    pub fan_speed_percent: Option<f64>,
+    /// power_dpm_force_performance_level, e.g. auto
+    pub performance_level: Option<String>,
+}
+
+/// Every amdgpu card under `drm_dir`, read from sysfs and then overlaid with
+/// whatever rocm-smi reports for it
+pub async fn detect_amd_gpus(drm_dir: &Path) -> Vec<AmdGpuInfo> {
+    let mut gpus = read_amdgpu_sysfs(drm_dir);
+    if gpus.is_empty() {
+        return gpus;
+    }
+    
+    match AsyncCommand::new("rocm-smi")
+        .args(["--showuse", "--showtemp", "--showmemuse", "--showmeminfo", "vram", "--showpower", "--showproductname", "--json"])
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => {
+            apply_rocm_smi(&mut gpus, &String::from_utf8_lossy(&output.stdout));
+        }
+        Ok(output) => debug!("rocm-smi failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        Err(_) => debug!("rocm-smi not installed, using amdgpu sysfs only"),
+    }
+    
+    gpus
+}
+
+/// amdgpu cards from sysfs: gpu_busy_percent, mem_info_vram_*, and the hwmon
+/// edge temperature, power and fan
+pub fn read_amdgpu_sysfs(drm_dir: &Path) -> Vec<AmdGpuInfo> {
+    let mut gpus: Vec<AmdGpuInfo> = amd_cards(drm_dir).into_iter()
+        .map(|(card, device)| {
+            let hwmon = fs::read_dir(device.join("hwmon")).ok()
+                .and_then(|entries| entries.flatten().map(|entry| entry.path()).next());
+            let hwmon_value = |file: &str| hwmon.as_ref().and_then(|dir| read_f64(&dir.join(file)));
+            
+            let fan_speed_percent = match (hwmon_value("pwm1"), hwmon_value("pwm1_max")) {
+                (Some(pwm), Some(max)) if max > 0.0 => Some(pwm / max * 100.0),
+                (Some(pwm), _) => Some(pwm / 255.0 * 100.0),
+                _ => None,
+            };
+            let name = read_trimmed(&device.join("product_name"))
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("AMD Radeon ({})", card));
+            
+            AmdGpuInfo {
+                name,
+                utilization_percent: read_f64(&device.join("gpu_busy_percent")).unwrap_or(0.0),
+                temperature_celsius: hwmon_value("temp1_input").map(|millis| millis / 1000.0),
+                vram_used_bytes: read_f64(&device.join("mem_info_vram_used")).unwrap_or(0.0) as u64,
+                vram_total_bytes: read_f64(&device.join("mem_info_vram_total")).unwrap_or(0.0) as u64,
+                // Newer kernels only have power1_input
+                power_watts: hwmon_value("power1_average").or_else(|| hwmon_value("power1_input")).map(|micro| micro / 1_000_000.0),
+                fan_speed_percent,
+                performance_level: read_trimmed(&device.join("power_dpm_force_performance_level")),
+                card,
+            }
+        })
+        .collect();
+    
+    gpus.sort_by(|a, b| a.card.cmp(&b.card));
+    gpus
+}
+
+/// Overlay `rocm-smi --json` values onto the matching cards. Key names differ
+/// between ROCm releases, so fields are matched by what they contain.
+pub fn apply_rocm_smi(gpus: &mut [AmdGpuInfo], json: &str) {
+    let report: Value = match serde_json::from_str(json) {
+        Ok(report) => report,
+        Err(e) => {
+            warn!("⚠️ Unparseable rocm-smi output: {}", e);
+            return;
+        }
+    };
+    let cards = match report.as_object() {
+        Some(cards) => cards,
+        None => return,
+    };
+    
+    for (card, fields) in cards {
+        let fields = match fields.as_object() {
+            Some(fields) => fields,
+            None => continue,
+        };
+        // rocm-smi counts its own devices, which match DRM numbering on single-GPU systems
+        let gpu = match gpus.iter_mut().find(|gpu| &gpu.card == card) {
+            Some(gpu) => gpu,
+            None => continue,
+        };
+        
+        let number = |matches: &dyn Fn(&str) -> bool| {
+            fields.iter()
+                .find(|(key, _)| matches(&key.to_lowercase()))
+                .and_then(|(_, value)| match value {
+                    Value::String(text) => text.trim().parse::<f64>().ok(),
+                    other => other.as_f64(),
+                })
+        };
+        
+        if let Some(usage) = number(&|key| key.starts_with("gpu use")) {
+            gpu.utilization_percent = usage;
+        }
+        if let Some(temperature) = number(&|key| key.contains("temperature") && key.contains("edge")) {
+            gpu.temperature_celsius = Some(temperature);
+        }
+        if let Some(total) = number(&|key| key.starts_with("vram total memory")) {
+            gpu.vram_total_bytes = total as u64;
+        }
+        if let Some(used) = number(&|key| key.starts_with("vram total used memory")) {
+            gpu.vram_used_bytes = used as u64;
+        } else if let Some(percent) = number(&|key| key.contains("memory allocated") && key.contains("vram%")) {
+            gpu.vram_used_bytes = (gpu.vram_total_bytes as f64 * percent / 100.0) as u64;
+        }
+        if let Some(power) = number(&|key| key.contains("graphics package power")) {
+            gpu.power_watts = Some(power);
+        }
+        if let Some(name) = fields.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("card series"))
+            .and_then(|(_, value)| value.as_str())
+            .filter(|name| !name.trim().is_empty())
+        {
+            gpu.name = name.trim().to_string();
+        }
+    }
+}
+
+/// Set power_dpm_force_performance_level on every amdgpu card, e.g. "high" for
+/// gaming or "low" to cool down; "auto" hands control back to the driver
+pub fn set_amd_performance_level(drm_dir: &Path, level: &str) -> SysResult<String> {
+    if !PERFORMANCE_LEVELS.contains(&level) {
+        return Err(SysError::InvalidParameter(format!(
+            "AMD performance level '{}'; valid levels: {}", level, PERFORMANCE_LEVELS.join(", ")
+        )));
+    }
+    
+    let paths: Vec<PathBuf> = amd_cards(drm_dir).into_iter()
+        .map(|(_, device)| device.join("power_dpm_force_performance_level"))
+        .filter(|path| path.exists())
+        .collect();
+    if paths.is_empty() {
+        return Err(SysError::HardwareUnavailable("No AMD GPU with DPM control".to_string()));
+    }
+    
+    let mut batch = PrivilegedBatch::new();
+    for path in &paths {
+        batch.write(path, level);
+    }
+    
+    let mut written = 0;
+    let mut first_error = None;
+    for (path, result) in paths.iter().zip(batch.apply()) {
+        match result {
+            Ok(_) => written += 1,
+            Err(e) => {
+                warn!("Failed to set {}: {}", path.display(), e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    
+    match first_error {
+        Some(e) if written == 0 => Err(e),
+        _ => {
+            info!("🎮 AMD GPU performance level set to {} on {} cards", level, written);
+            Ok(format!("AMD GPU performance level set to {} on {} of {} cards", level, written, paths.len()))
+        }
+    }
+}
+
+/// (card name, device dir) for each amdgpu card; connectors like card0-DP-1 are skipped
+fn amd_cards(drm_dir: &Path) -> Vec<(String, PathBuf)> {
+    fs::read_dir(drm_dir)
+        .map(|entries| entries.flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let is_card = name.strip_prefix("card")
+                    .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+                    .unwrap_or(false);
+                let device = entry.path().join("device");
+                (is_card && read_trimmed(&device.join("vendor")).as_deref() == Some(AMD_VENDOR_ID))
+                    .then_some((name, device))
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
+
+fn read_f64(path: &Path) -> Option<f64> {
+    read_trimmed(path).and_then(|value| value.parse().ok())
+}
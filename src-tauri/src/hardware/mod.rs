@@ -3,15 +3,34 @@
 
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 use std::process::Command;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use tracing::{info, debug, warn, error};
 use tokio::process::Command as AsyncCommand;
+use crate::config;
+use crate::error::{SysError, SysResult};
+use crate::rapl::RaplZone;
+use crate::thermal_throttle;
+
+pub use crate::thermal_throttle::read_throttle_count;
+pub use gpu::AmdGpuInfo;
+pub use smart::SmartInfo;
+pub use topology::CpuTopology;
 
 pub mod gpu;
 pub mod thermal;
 pub mod power;
 pub mod sensors;
+pub mod smart;
+pub mod topology;
+
+/// Long enough for the energy counter to move meaningfully, short enough to wait on
+const RAPL_SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+/// Display, memory, storage, chipset and conversion losses on a gaming laptop under
+/// light load; replaced by the measured value once the battery has reported it
+const DEFAULT_PLATFORM_POWER_WATTS: f64 = 20.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HardwareManager {
@@ -35,12 +54,14 @@ pub struct CpuInfo {
     pub usage_percent: Vec<f64>,
     pub temperature_celsius: f64,
     pub governor: String,
+    pub topology: CpuTopology,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuInfo {
     pub nvidia_gpu: Option<NvidiaGpuInfo>,
     pub intel_gpu: Option<IntelGpuInfo>,
+    pub amd_gpus: Vec<AmdGpuInfo>,
     pub active_gpu: ActiveGpu,
 }
 
@@ -77,9 +98,21 @@ pub struct ThermalStatus {
     pub gpu_temperature: f64,
     pub system_fans: Vec<FanInfo>,
     pub thermal_throttling: bool,
+    pub throttle_count: Option<u64>, // package + core events at the last poll
+    pub throttle_delta: u64,         // events since the previous poll
     pub cooling_profile: CoolingProfile,
 }
 
+impl ThermalStatus {
+    /// Fold in a new throttle counter total. Throttling is active only if the
+    /// counters moved since the last poll; the first reading just sets the baseline.
+    fn record_throttle_count(&mut self, count: u64) {
+        self.throttle_delta = thermal_throttle::throttle_delta(self.throttle_count, count);
+        self.throttle_count = Some(count);
+        self.thermal_throttling = self.throttle_delta > 0;
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FanInfo {
     pub name: String,
@@ -99,9 +132,26 @@ pub enum CoolingProfile {
 pub struct PowerManagement {
     pub power_profile: PowerProfile,
     pub battery_status: Option<BatteryStatus>,
+    /// Whole-system estimate, the total from the last `get_power_breakdown`
     pub power_consumption_watts: f64,
+    /// CPU package power from RAPL; 0 when it can't be read
     pub cpu_power_watts: f64,
     pub gpu_power_watts: f64,
+    /// Everything but the CPU and GPU, as last measured on battery
+    pub platform_power_watts: Option<f64>,
+}
+
+/// Where the system's power goes, in watts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerBreakdown {
+    pub cpu: f64,
+    pub gpu: f64,
+    /// The rest of the system: measured against the battery while discharging,
+    /// otherwise the last such measurement or a typical laptop figure
+    pub other_estimate: f64,
+    pub total: f64,
+    /// The battery's own reading while discharging, which covers everything
+    pub battery_draw: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,7 +166,8 @@ pub enum PowerProfile {
 pub struct BatteryStatus {
     pub capacity_percent: u8,
     pub status: String,
-    pub time_remaining_minutes: Option<u32>,
+    pub time_remaining_minutes: Option<u32>, // to empty when discharging, to full when charging
+    pub power_draw_watts: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,7 +188,9 @@ pub struct StorageDevice {
     pub total_gb: u64,
     pub used_gb: u64,
     pub temperature_celsius: Option<f64>,
+    /// "Good", "FAILING", or "Unknown" when SMART data isn't available
     pub health_status: String,
+    pub smart: Option<SmartInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,25 +201,31 @@ pub enum StorageType {
 }
 
 impl HardwareManager {
-    pub async fn new_for_gaming_laptop() -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new_for_gaming_laptop() -> SysResult<Self> {
         info!("🖥️ Initializing Hardware Manager for i9-13900HX Gaming Laptop...");
         
+        let topology = CpuTopology::detect();
+        info!("🧩 {}: {} P-cores + {} E-cores, {} threads", topology.model,
+              topology.performance_core_count(), topology.efficiency_core_count(), topology.threads);
+        
         let mut manager = Self {
             cpu_info: CpuInfo {
-                model: "Intel i9-13900HX".to_string(),
-                cores_performance: 8,
-                cores_efficiency: 16,
-                threads_total: 32,
-                base_freq_mhz: 2200,
-                boost_freq_mhz: 5400,
+                model: topology.model.clone(),
+                cores_performance: topology.performance_core_count().min(u8::MAX as usize) as u8,
+                cores_efficiency: topology.efficiency_core_count().min(u8::MAX as usize) as u8,
+                threads_total: topology.threads.min(u8::MAX as usize) as u8,
+                base_freq_mhz: topology.base_freq_mhz.unwrap_or(0),
+                boost_freq_mhz: topology.boost_freq_mhz.unwrap_or(0),
                 current_freq_mhz: Vec::new(),
                 usage_percent: Vec::new(),
                 temperature_celsius: 0.0,
                 governor: "powersave".to_string(),
+                topology,
             },
             gpu_info: GpuInfo {
                 nvidia_gpu: None,
                 intel_gpu: None,
+                amd_gpus: Vec::new(),
                 active_gpu: ActiveGpu::Hybrid,
             },
             thermal_status: ThermalStatus {
@@ -174,6 +233,8 @@ impl HardwareManager {
                 gpu_temperature: 0.0,
                 system_fans: Vec::new(),
                 thermal_throttling: false,
+                throttle_count: None,
+                throttle_delta: 0,
                 cooling_profile: CoolingProfile::Balanced,
             },
             power_management: PowerManagement {
@@ -182,9 +243,10 @@ impl HardwareManager {
                 power_consumption_watts: 0.0,
                 cpu_power_watts: 0.0,
                 gpu_power_watts: 0.0,
+                platform_power_watts: None,
             },
             memory_info: MemoryInfo {
-                total_gb: 64, // Lou's system has 64GB DDR5
+                total_gb: config::get().hardware.total_memory_gb(),
                 used_gb: 0.0,
                 available_gb: 0.0,
                 swap_total_gb: 0,
@@ -201,7 +263,7 @@ impl HardwareManager {
         Ok(manager)
     }
     
-    async fn detect_hardware(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn detect_hardware(&mut self) -> SysResult<()> {
         debug!("🔍 Detecting hardware components...");
         
         // Detect CPU information
@@ -225,9 +287,9 @@ impl HardwareManager {
         Ok(())
     }
     
-    async fn detect_cpu_info(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn detect_cpu_info(&mut self) -> SysResult<()> {
         // Read CPU frequency for each core
-        for core in 0..32 { // i9-13900HX has 32 threads
+        for core in self.cpu_info.topology.cpu_ids() {
             if let Ok(freq_str) = fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/cpufreq/scaling_cur_freq", core)) {
                 if let Ok(freq_khz) = freq_str.trim().parse::<u32>() {
                     self.cpu_info.current_freq_mhz.push(freq_khz / 1000);
@@ -268,7 +330,7 @@ impl HardwareManager {
         Ok(())
     }
     
-    async fn detect_gpu_info(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn detect_gpu_info(&mut self) -> SysResult<()> {
         // Detect NVIDIA RTX 4080 Mobile
         if let Ok(output) = AsyncCommand::new("nvidia-smi")
             .arg("--query-gpu=name,memory.total,memory.used,temperature.gpu,power.draw,utilization.gpu,utilization.memory,fan.speed,power.limit")
@@ -297,6 +359,8 @@ impl HardwareManager {
             }
         }
         
+        self.gpu_info.amd_gpus = gpu::detect_amd_gpus(Path::new(gpu::DRM_DIR)).await;
+        
         // Detect Intel UHD Graphics
         if let Ok(output) = AsyncCommand::new("intel_gpu_top")
             .arg("-s")
@@ -316,7 +380,7 @@ impl HardwareManager {
         Ok(())
     }
     
-    async fn detect_memory_info(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn detect_memory_info(&mut self) -> SysResult<()> {
         if let Ok(meminfo) = fs::read_to_string("/proc/meminfo") {
             for line in meminfo.lines() {
                 if line.starts_with("MemTotal:") {
@@ -352,42 +416,18 @@ impl HardwareManager {
         Ok(())
     }
     
-    async fn detect_storage_info(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Detect NVMe devices
-        if let Ok(output) = AsyncCommand::new("lsblk")
-            .arg("-J")
-            .arg("-o")
-            .arg("NAME,SIZE,TYPE,MOUNTPOINT")
-            .output()
-            .await 
-        {
-            if output.status.success() {
-                // Parse lsblk JSON output
-                // This is simplified - in practice we'd parse the JSON properly
-                self.storage_info.push(StorageDevice {
-                    device_name: "nvme0n1".to_string(),
-                    device_type: StorageType::NvmeSsd,
-                    total_gb: 932, // From the system info we saw earlier
-                    used_gb: 94,
-                    temperature_celsius: Some(45.0),
-                    health_status: "Good".to_string(),
-                });
-                
-                self.storage_info.push(StorageDevice {
-                    device_name: "nvme1n1".to_string(),
-                    device_type: StorageType::NvmeSsd,
-                    total_gb: 3600, // 3.6TB drive
-                    used_gb: 2000,
-                    temperature_celsius: Some(42.0),
-                    health_status: "Good".to_string(),
-                });
+    async fn detect_storage_info(&mut self) -> SysResult<()> {
+        self.storage_info = detect_storage_devices().await;
+        
+        for device in &self.storage_info {
+            if device.health_status == "FAILING" {
+                warn!("💽 {} reports SMART status FAILING", device.device_name);
             }
         }
-        
         Ok(())
     }
     
-    async fn detect_thermal_info(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn detect_thermal_info(&mut self) -> SysResult<()> {
         // Read CPU temperature
         if let Ok(temp_dirs) = fs::read_dir("/sys/class/thermal") {
             for entry in temp_dirs.flatten() {
@@ -410,6 +450,14 @@ impl HardwareManager {
             self.thermal_status.gpu_temperature = nvidia_gpu.temperature_celsius;
         }
         
+        if let Some(count) = read_throttle_count(Path::new(thermal_throttle::CPU_DIR)) {
+            self.thermal_status.record_throttle_count(count);
+            
+            if self.thermal_status.thermal_throttling {
+                warn!("🔥 CPU thermal throttling: {} new events", self.thermal_status.throttle_delta);
+            }
+        }
+        
         // Detect fans (this is hardware-specific and may not work on all systems)
         if let Ok(output) = AsyncCommand::new("sensors")
             .output()
@@ -429,17 +477,20 @@ impl HardwareManager {
         Ok(())
     }
     
-    async fn detect_power_info(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn detect_power_info(&mut self) -> SysResult<()> {
         // Check battery status
         if let Ok(capacity_str) = fs::read_to_string("/sys/class/power_supply/BAT0/capacity") {
             if let Ok(capacity) = capacity_str.trim().parse::<u8>() {
                 let status = fs::read_to_string("/sys/class/power_supply/BAT0/status")
                     .unwrap_or_default().trim().to_string();
+                let (time_remaining_minutes, power_draw_watts) =
+                    estimate_battery_time(Path::new("/sys/class/power_supply/BAT0"), &status);
                 
                 self.power_management.battery_status = Some(BatteryStatus {
                     capacity_percent: capacity,
                     status,
-                    time_remaining_minutes: None,
+                    time_remaining_minutes,
+                    power_draw_watts,
                 });
             }
         }
@@ -449,10 +500,76 @@ impl HardwareManager {
             self.power_management.gpu_power_watts = nvidia_gpu.power_usage_watts;
         }
         
+        // Same two-sample measurement as read_cpu_power, without blocking the runtime
+        if let Some(zone) = RaplZone::package() {
+            if let Ok(earlier) = zone.sample() {
+                tokio::time::sleep(RAPL_SAMPLE_INTERVAL).await;
+                if let Some(watts) = zone.sample().ok().and_then(|later| zone.watts_between(&earlier, &later)) {
+                    self.power_management.cpu_power_watts = watts;
+                }
+            }
+        }
+        
         Ok(())
     }
     
-    pub async fn start_optimization_loop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    /// CPU, GPU and rest-of-system power. On battery the battery's draw is the
+    /// reference, and a CPU plus GPU reading above it is logged as inconsistent.
+    pub async fn get_power_breakdown(&mut self) -> PowerBreakdown {
+        if let Err(e) = self.detect_power_info().await {
+            warn!("Power breakdown without fresh battery data: {}", e);
+        }
+        let cpu = self.power_management.cpu_power_watts;
+        let gpu = read_gpu_power().await.unwrap_or(0.0);
+        self.power_management.gpu_power_watts = gpu;
+        
+        let battery_draw = self.power_management.battery_status.as_ref()
+            .filter(|battery| battery.status == "Discharging")
+            .and_then(|battery| battery.power_draw_watts);
+        let other_estimate = match battery_draw {
+            Some(draw) if draw >= cpu + gpu => {
+                self.power_management.platform_power_watts = Some(draw - cpu - gpu);
+                draw - cpu - gpu
+            }
+            Some(draw) => {
+                warn!("⚠️ Battery reports {:.1} W but CPU and GPU alone read {:.1} W", draw, cpu + gpu);
+                0.0
+            }
+            None => self.power_management.platform_power_watts.unwrap_or(DEFAULT_PLATFORM_POWER_WATTS),
+        };
+        
+        let total = cpu + gpu + other_estimate;
+        self.power_management.power_consumption_watts = total;
+        debug!("⚡ {:.1} W total: CPU {:.1} W, GPU {:.1} W, other {:.1} W", total, cpu, gpu, other_estimate);
+        PowerBreakdown { cpu, gpu, other_estimate, total, battery_draw }
+    }
+    
+    /// CPU package power in watts, from two RAPL energy readings `RAPL_SAMPLE_INTERVAL`
+    /// apart; blocks for that long. 0 without RAPL, or as a non-root user on
+    /// kernels where energy_uj is root-only.
+    pub fn read_cpu_power(&self) -> f64 {
+        let zone = match RaplZone::package() {
+            Some(zone) => zone,
+            None => return 0.0,
+        };
+        match zone.measure(RAPL_SAMPLE_INTERVAL) {
+            Ok(watts) => watts,
+            Err(e) => {
+                debug!("Can't read CPU package power: {}", e);
+                0.0
+            }
+        }
+    }
+    
+    pub fn is_throttling(&self) -> bool {
+        self.thermal_status.thermal_throttling
+    }
+    
+    pub fn throttle_delta(&self) -> u64 {
+        self.thermal_status.throttle_delta
+    }
+    
+    pub async fn start_optimization_loop(&mut self) -> SysResult<()> {
         info!("🔄 Starting hardware optimization loop...");
         
         // This would run in a background task
@@ -468,7 +585,7 @@ impl HardwareManager {
         Ok(())
     }
     
-    pub async fn optimize_for_workload(&mut self, workload: &str) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn optimize_for_workload(&mut self, workload: &str) -> SysResult<String> {
         info!("🎯 Optimizing hardware for workload: {}", workload);
         
         match workload {
@@ -476,11 +593,18 @@ impl HardwareManager {
             "ai_inference" => self.optimize_for_ai_inference().await,
             "development" => self.optimize_for_development().await,
             "media" => self.optimize_for_media().await,
-            _ => Ok("Workload not recognized".to_string()),
+            _ => Err(SysError::InvalidParameter(format!("unknown workload '{}'", workload))),
         }
     }
     
-    async fn optimize_for_gaming(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    async fn optimize_for_gaming(&mut self) -> SysResult<String> {
+        if !self.gpu_info.amd_gpus.is_empty() {
+            if let Err(e) = self.set_amd_performance_level("high") {
+                warn!("Failed to raise AMD GPU performance level: {}", e);
+            }
+        }
+        
+        
         // Set NVIDIA GPU to maximum performance
         if let Ok(_) = AsyncCommand::new("nvidia-smi")
             .args(&["-pl", "175"]) // Set power limit to max
@@ -500,7 +624,7 @@ impl HardwareManager {
         Ok("🎮 Hardware optimized for gaming - GPU at maximum performance".to_string())
     }
     
-    async fn optimize_for_ai_inference(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    async fn optimize_for_ai_inference(&mut self) -> SysResult<String> {
         // Optimize GPU memory for large models
         if let Ok(_) = AsyncCommand::new("nvidia-smi")
             .args(&["-pl", "150"]) // Balanced power for sustained workloads
@@ -519,7 +643,7 @@ impl HardwareManager {
         Ok("🧠 Hardware optimized for AI inference - Balanced power and memory".to_string())
     }
     
-    async fn optimize_for_development(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    async fn optimize_for_development(&mut self) -> SysResult<String> {
         // Balanced settings for development
         self.power_management.power_profile = PowerProfile::Balanced;
         self.thermal_status.cooling_profile = CoolingProfile::Balanced;
@@ -527,7 +651,7 @@ impl HardwareManager {
         Ok("💻 Hardware optimized for development - Balanced performance".to_string())
     }
     
-    async fn optimize_for_media(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    async fn optimize_for_media(&mut self) -> SysResult<String> {
         // Optimize for video encoding/decoding
         if let Ok(_) = AsyncCommand::new("nvidia-smi")
             .args(&["-ac", "1215,1800"]) // Optimize for encode/decode
@@ -540,7 +664,60 @@ impl HardwareManager {
         Ok("🎬 Hardware optimized for media processing".to_string())
     }
     
-    pub async fn get_real_time_stats(&mut self) -> Result<HashMap<String, f64>, Box<dyn std::error::Error>> {
+    /// Processes using VRAM, largest first. Empty on integrated-only systems.
+    pub fn get_gpu_processes(&self) -> Vec<crate::GpuProcess> {
+        if self.gpu_info.nvidia_gpu.is_none() {
+            return Vec::new();
+        }
+        crate::HardwareController::get_gpu_processes()
+    }
+    
+    /// Set the NVIDIA GPU power limit (`nvidia-smi -pl`). Lowering it is the quickest
+    /// way to bring GPU temperature down; the driver rejects values outside its range.
+    pub async fn set_gpu_power_limit(&mut self, watts: f64) -> SysResult<String> {
+        if self.gpu_info.nvidia_gpu.is_none() {
+            return Err(SysError::HardwareUnavailable("No NVIDIA GPU detected".to_string()));
+        }
+        if !watts.is_finite() || watts <= 0.0 {
+            return Err(SysError::InvalidParameter(format!("GPU power limit {} W", watts)));
+        }
+        
+        let output = AsyncCommand::new("nvidia-smi")
+            .args(&["-pl", &format!("{:.0}", watts)])
+            .output()
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => SysError::HardwareUnavailable("nvidia-smi is not installed".to_string()),
+                _ => SysError::from(e),
+            })?;
+        
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            // nvidia-smi needs root to change the power limit
+            if stderr.contains("Insufficient Permissions") || stderr.contains("requires root") {
+                return Err(SysError::PermissionDenied("the GPU power limit".to_string()));
+            }
+            return Err(SysError::command_failed("nvidia-smi -pl", &output));
+        }
+        
+        if let Some(nvidia_gpu) = &mut self.gpu_info.nvidia_gpu {
+            nvidia_gpu.power_limit_watts = watts;
+        }
+        
+        info!("🎮 GPU power limit set to {:.0} W", watts);
+        Ok(format!("GPU power limit set to {:.0} W", watts))
+    }
+    
+    /// Force every AMD GPU's DPM performance level; "auto" restores driver control
+    pub fn set_amd_performance_level(&mut self, level: &str) -> SysResult<String> {
+        let message = gpu::set_amd_performance_level(Path::new(gpu::DRM_DIR), level)?;
+        for amd_gpu in &mut self.gpu_info.amd_gpus {
+            amd_gpu.performance_level = Some(level.to_string());
+        }
+        Ok(message)
+    }
+    
+    pub async fn get_real_time_stats(&mut self) -> SysResult<HashMap<String, f64>> {
         let mut stats = HashMap::new();
         
         // Update hardware information
@@ -563,6 +740,17 @@ impl HardwareManager {
             stats.insert("gpu_utilization".to_string(), nvidia_gpu.gpu_utilization_percent);
             stats.insert("gpu_memory_utilization".to_string(), nvidia_gpu.memory_utilization_percent);
             stats.insert("gpu_power_watts".to_string(), nvidia_gpu.power_usage_watts);
+        } else if let Some(amd_gpu) = self.gpu_info.amd_gpus.first() {
+            if let Some(temperature) = amd_gpu.temperature_celsius {
+                stats.insert("gpu_temperature".to_string(), temperature);
+            }
+            stats.insert("gpu_utilization".to_string(), amd_gpu.utilization_percent);
+            if amd_gpu.vram_total_bytes > 0 {
+                stats.insert("gpu_memory_utilization".to_string(), amd_gpu.vram_used_bytes as f64 / amd_gpu.vram_total_bytes as f64 * 100.0);
+            }
+            if let Some(power) = amd_gpu.power_watts {
+                stats.insert("gpu_power_watts".to_string(), power);
+            }
         }
         
         // Memory stats
@@ -572,3 +760,130 @@ impl HardwareManager {
         Ok(stats)
     }
 }
+
+/// Current draw of every GPU that reports one, summed: NVIDIA through nvidia-smi,
+/// AMD through hwmon. None when no GPU reports its power.
+pub async fn read_gpu_power() -> Option<f64> {
+    let mut total = None;
+    
+    if let Ok(output) = AsyncCommand::new("nvidia-smi")
+        .args(["--query-gpu=power.draw", "--format=csv,noheader,nounits"])
+        .output()
+        .await
+    {
+        if output.status.success() {
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                // "[N/A]" on GPUs without power readings
+                if let Ok(watts) = line.trim().parse::<f64>() {
+                    *total.get_or_insert(0.0) += watts;
+                }
+            }
+        }
+    }
+    
+    for amd_gpu in gpu::detect_amd_gpus(Path::new(gpu::DRM_DIR)).await {
+        if let Some(watts) = amd_gpu.power_watts {
+            *total.get_or_insert(0.0) += watts;
+        }
+    }
+    
+    total
+}
+
+/// Every physical disk lsblk reports, with SMART health where smartctl can read it
+pub async fn detect_storage_devices() -> Vec<StorageDevice> {
+    let output = match AsyncCommand::new("lsblk")
+        .args(["-J", "-b", "-o", "NAME,SIZE,TYPE,ROTA,FSUSED"])
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            warn!("lsblk failed: {}", String::from_utf8_lossy(&output.stderr));
+            return Vec::new();
+        }
+        Err(e) => {
+            warn!("Failed to run lsblk: {}", e);
+            return Vec::new();
+        }
+    };
+    
+    let json: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+        Ok(json) => json,
+        Err(e) => {
+            warn!("Unreadable lsblk output: {}", e);
+            return Vec::new();
+        }
+    };
+    
+    const GB: u64 = 1024 * 1024 * 1024;
+    let mut devices = Vec::new();
+    for disk in json["blockdevices"].as_array().into_iter().flatten() {
+        if disk["type"].as_str() != Some("disk") {
+            continue;
+        }
+        let name = disk["name"].as_str().unwrap_or_default().to_string();
+        // Older lsblk prints numbers as strings
+        let number = |value: &serde_json::Value| value.as_u64().or_else(|| value.as_str().and_then(|s| s.parse().ok()));
+        let rotational = disk["rota"].as_bool().unwrap_or_else(|| number(&disk["rota"]) == Some(1));
+        
+        let device_type = if name.starts_with("nvme") {
+            StorageType::NvmeSsd
+        } else if rotational {
+            StorageType::Hdd
+        } else {
+            StorageType::Ssd
+        };
+        
+        let used_bytes: u64 = disk["children"].as_array().into_iter().flatten()
+            .filter_map(|part| number(&part["fsused"]))
+            .sum();
+        
+        let smart = smart::try_read_smart(&name).await;
+        devices.push(StorageDevice {
+            device_type,
+            total_gb: number(&disk["size"]).unwrap_or(0) / GB,
+            used_gb: used_bytes / GB,
+            temperature_celsius: smart.as_ref().and_then(|s| s.temperature_celsius),
+            health_status: smart.as_ref().map(|s| s.health_status()).unwrap_or("Unknown").to_string(),
+            smart,
+            device_name: name,
+        });
+    }
+    
+    devices
+}
+
+/// Estimate minutes to empty (discharging) or to full (charging) from a
+/// power_supply battery directory, plus the instantaneous draw in watts.
+/// Batteries report either energy (µWh/µW) or charge (µAh/µA); both are handled.
+fn estimate_battery_time(battery_dir: &Path, status: &str) -> (Option<u32>, Option<f64>) {
+    let read = |file: &str| {
+        fs::read_to_string(battery_dir.join(file))
+            .ok()
+            .and_then(|s| s.trim().parse::<f64>().ok())
+    };
+    
+    // (remaining, full, rate) in matching units, plus draw in watts
+    let (now, full, rate, watts) = if let (Some(now), Some(rate)) = (read("energy_now"), read("power_now")) {
+        (now, read("energy_full"), rate.abs(), Some(rate.abs() / 1_000_000.0))
+    } else if let (Some(now), Some(rate)) = (read("charge_now"), read("current_now")) {
+        let watts = read("voltage_now").map(|uv| rate.abs() * uv / 1_000_000_000_000.0);
+        (now, read("charge_full"), rate.abs(), watts)
+    } else {
+        return (None, None);
+    };
+    
+    // An idle battery reports zero rate; no meaningful estimate then
+    if rate <= 0.0 {
+        return (None, watts);
+    }
+    
+    let hours = match status {
+        "Discharging" => Some(now / rate),
+        "Charging" => full.map(|full| (full - now).max(0.0) / rate),
+        _ => None,
+    };
+    
+    (hours.map(|h| (h * 60.0).round() as u32), watts)
+}
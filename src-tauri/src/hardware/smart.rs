@@ -0,0 +1,103 @@
+// SMART Health - Drive health from `smartctl -j -H -A` for NVMe and SATA devices
+// smartctl is optional: without it (or without root) devices just have no SMART data
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::process::Command as AsyncCommand;
+use tracing::debug;
+
+/// Reallocated_Sector_Ct
+const ATA_REALLOCATED_SECTORS: u64 = 5;
+/// Wear_Leveling_Count (Samsung and most SATA SSDs) and Media_Wearout_Indicator (Intel);
+/// both count down from 100
+const ATA_WEAR_ATTRIBUTES: [u64; 2] = [177, 233];
+
+/// smartctl exit status bits 0-1: bad command line or the device could not be opened
+const SMARTCTL_FATAL_BITS: i32 = 0b11;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartInfo {
+    /// Overall self-assessment; false means the drive reports FAILING
+    pub passed: bool,
+    pub temperature_celsius: Option<f64>,
+    pub power_on_hours: Option<u64>,
+    /// Rated endurance used (%). NVMe percentage_used, or 100 minus the SATA wear-leveling value.
+    pub percentage_used: Option<u8>,
+    pub reallocated_sectors: Option<u64>,
+    /// NVMe media and data integrity errors
+    pub media_errors: Option<u64>,
+}
+
+impl SmartInfo {
+    pub fn health_status(&self) -> &'static str {
+        if self.passed { "Good" } else { "FAILING" }
+    }
+}
+
+/// Read SMART data for a block device name such as "nvme0n1" or "sda"
+pub async fn read_smart(device: &str) -> Result<SmartInfo, Box<dyn std::error::Error>> {
+    let output = match AsyncCommand::new("smartctl")
+        .args(["-j", "-H", "-A", &format!("/dev/{}", device)])
+        .output()
+        .await
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err("smartctl is not installed (smartmontools)".into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    
+    let json: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Unreadable smartctl output for {}: {}", device, e))?;
+    
+    // The other bits flag drive problems and still come with a full report
+    if output.status.code().map(|code| code & SMARTCTL_FATAL_BITS != 0).unwrap_or(true) {
+        let messages: Vec<&str> = json["smartctl"]["messages"].as_array()
+            .map(|messages| messages.iter().filter_map(|m| m["string"].as_str()).collect())
+            .unwrap_or_default();
+        let reason = if messages.is_empty() { "smartctl failed".to_string() } else { messages.join("; ") };
+        return Err(format!("Cannot read SMART data for {} ({}); smartctl usually needs root", device, reason).into());
+    }
+    
+    Ok(parse_smart_json(&json))
+}
+
+pub fn parse_smart_json(json: &Value) -> SmartInfo {
+    let nvme = &json["nvme_smart_health_information_log"];
+    let ata_attributes = json["ata_smart_attributes"]["table"].as_array().cloned().unwrap_or_default();
+    let ata_attribute = |id: u64| ata_attributes.iter().find(|attr| attr["id"].as_u64() == Some(id));
+    
+    let percentage_used = nvme["percentage_used"].as_u64()
+        .or_else(|| {
+            ATA_WEAR_ATTRIBUTES.iter()
+                .find_map(|id| ata_attribute(*id))
+                .and_then(|attr| attr["value"].as_u64())
+                .map(|remaining| 100u64.saturating_sub(remaining))
+        })
+        .map(|used| used.min(u8::MAX as u64) as u8);
+    
+    SmartInfo {
+        // Missing smart_status means the drive didn't answer -H; don't call that a failure
+        passed: json["smart_status"]["passed"].as_bool().unwrap_or(true),
+        temperature_celsius: json["temperature"]["current"].as_f64()
+            .or_else(|| nvme["temperature"].as_f64()),
+        power_on_hours: json["power_on_time"]["hours"].as_u64()
+            .or_else(|| nvme["power_on_hours"].as_u64()),
+        percentage_used,
+        reallocated_sectors: ata_attribute(ATA_REALLOCATED_SECTORS)
+            .and_then(|attr| attr["raw"]["value"].as_u64()),
+        media_errors: nvme["media_errors"].as_u64(),
+    }
+}
+
+/// SMART data for `device`, or None with the reason logged
+pub async fn try_read_smart(device: &str) -> Option<SmartInfo> {
+    match read_smart(device).await {
+        Ok(info) => Some(info),
+        Err(e) => {
+            debug!("💽 No SMART data for {}: {}", device, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,327 @@
+// CPU Topology - Real core/thread counts and P/E-core split for whatever CPU this runs on
+// Sources: /sys/devices/system/cpu/*/topology, the hybrid PMU cpu lists, per-core
+// capacity and maximum frequency, and /proc/cpuinfo
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use crate::config;
+
+/// A core whose maximum frequency is below this fraction of the fastest core's is
+/// an E-core. Favoured P-cores only boost a few percent higher than the rest, while
+/// E-cores top out far lower (3.9 vs 5.4 GHz on the i9-13900HX).
+const E_CORE_MAX_FREQ_RATIO: f64 = 0.85;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoreType {
+    Performance,
+    Efficiency,
+}
+
+/// One logical CPU (hardware thread)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogicalCpu {
+    pub id: usize,
+    pub package_id: u32,
+    pub core_id: u32,
+    /// Logical CPUs sharing this physical core, including this one
+    pub siblings: Vec<usize>,
+    pub core_type: CoreType,
+    pub max_freq_mhz: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuTopology {
+    pub model: String,
+    pub cpus: Vec<LogicalCpu>,
+    pub physical_cores: usize,
+    pub threads: usize,
+    pub hybrid: bool,
+    pub base_freq_mhz: Option<u32>,
+    pub boost_freq_mhz: Option<u32>,
+}
+
+impl CpuTopology {
+    /// Never fails: missing sysfs entries just leave less detail. On a machine
+    /// without any topology info every online CPU is its own performance core.
+    pub fn detect() -> CpuTopology {
+        Self::detect_from(Path::new("/sys/devices/system/cpu"), Path::new("/proc/cpuinfo"), &config::get().hardware)
+    }
+    
+    /// `hardware` holds the P/E-core overrides, which win over anything detected
+    fn detect_from(sys_cpu: &Path, proc_cpuinfo: &Path, hardware: &config::HardwareConfig) -> CpuTopology {
+        let cpuinfo = fs::read_to_string(proc_cpuinfo).unwrap_or_default();
+        let model = cpuinfo.lines()
+            .find(|line| line.starts_with("model name"))
+            .and_then(|line| line.split_once(':'))
+            .map(|(_, name)| name.trim().to_string())
+            .unwrap_or_else(|| "Unknown CPU".to_string());
+        let cpuinfo_cores = parse_cpuinfo_cores(&cpuinfo);
+        
+        let online = fs::read_to_string(sys_cpu.join("online")).ok()
+            .map(|list| parse_cpu_list(&list))
+            .filter(|ids| !ids.is_empty())
+            .unwrap_or_else(|| {
+                fs::read_dir(sys_cpu).map(|entries| {
+                    entries.flatten()
+                        .filter_map(|entry| entry.file_name().to_string_lossy().strip_prefix("cpu").and_then(|n| n.parse::<usize>().ok()))
+                        .collect()
+                }).unwrap_or_default()
+            });
+        
+        let mut cpus: Vec<LogicalCpu> = online.iter().map(|&id| {
+            let topology = sys_cpu.join(format!("cpu{}/topology", id));
+            let read_u32 = |name: &str| fs::read_to_string(topology.join(name)).ok().and_then(|v| v.trim().parse::<u32>().ok());
+            let (fallback_package, fallback_core) = cpuinfo_cores.get(&id).copied().unwrap_or((0, id as u32));
+            
+            let siblings = fs::read_to_string(topology.join("core_cpus_list"))
+                .or_else(|_| fs::read_to_string(topology.join("thread_siblings_list")))
+                .map(|list| parse_cpu_list(&list))
+                .unwrap_or_else(|_| vec![id]);
+            
+            LogicalCpu {
+                id,
+                package_id: read_u32("physical_package_id").unwrap_or(fallback_package),
+                core_id: read_u32("core_id").unwrap_or(fallback_core),
+                siblings,
+                core_type: CoreType::Performance,
+                max_freq_mhz: read_khz(&sys_cpu.join(format!("cpu{}/cpufreq/cpuinfo_max_freq", id))),
+            }
+        }).collect();
+        cpus.sort_by_key(|cpu| cpu.id);
+        
+        let efficiency = efficiency_cpus(sys_cpu, &cpus, hardware);
+        for cpu in &mut cpus {
+            if efficiency.contains(&cpu.id) {
+                cpu.core_type = CoreType::Efficiency;
+            }
+        }
+        
+        let physical_cores = cpus.iter()
+            .map(|cpu| (cpu.package_id, cpu.core_id))
+            .collect::<BTreeSet<_>>()
+            .len();
+        let hybrid = !efficiency.is_empty() && efficiency.len() < cpus.len();
+        
+        let topology = CpuTopology {
+            model,
+            threads: cpus.len(),
+            physical_cores,
+            hybrid,
+            base_freq_mhz: read_khz(&sys_cpu.join("cpu0/cpufreq/base_frequency")),
+            boost_freq_mhz: cpus.iter().filter_map(|cpu| cpu.max_freq_mhz).max(),
+            cpus,
+        };
+        debug!("🧩 CPU topology: {} cores / {} threads, {} P-cores, {} E-cores",
+               topology.physical_cores, topology.threads,
+               topology.performance_core_count(), topology.efficiency_core_count());
+        topology
+    }
+    
+    /// Logical CPU ids on P-cores
+    pub fn p_cpus(&self) -> Vec<usize> {
+        self.cpus_of(CoreType::Performance)
+    }
+    
+    /// Logical CPU ids on E-cores
+    pub fn e_cpus(&self) -> Vec<usize> {
+        self.cpus_of(CoreType::Efficiency)
+    }
+    
+    /// Physical P-cores
+    pub fn performance_core_count(&self) -> usize {
+        self.core_count(CoreType::Performance)
+    }
+    
+    /// Physical E-cores
+    pub fn efficiency_core_count(&self) -> usize {
+        self.core_count(CoreType::Efficiency)
+    }
+    
+    pub fn cpu_ids(&self) -> Vec<usize> {
+        self.cpus.iter().map(|cpu| cpu.id).collect()
+    }
+    
+    /// (package, core) -> logical CPUs on that physical core
+    pub fn core_siblings(&self) -> BTreeMap<(u32, u32), Vec<usize>> {
+        let mut cores: BTreeMap<(u32, u32), Vec<usize>> = BTreeMap::new();
+        for cpu in &self.cpus {
+            cores.entry((cpu.package_id, cpu.core_id)).or_default().push(cpu.id);
+        }
+        cores
+    }
+    
+    fn cpus_of(&self, core_type: CoreType) -> Vec<usize> {
+        self.cpus.iter().filter(|cpu| cpu.core_type == core_type).map(|cpu| cpu.id).collect()
+    }
+    
+    fn core_count(&self, core_type: CoreType) -> usize {
+        self.cpus.iter()
+            .filter(|cpu| cpu.core_type == core_type)
+            .map(|cpu| (cpu.package_id, cpu.core_id))
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+}
+
+/// E-core ids, in order of preference: config override, the hybrid PMU lists
+/// (cpu_atom, cpu_core), lower cpu_capacity, then a clearly lower maximum
+/// frequency. SMT isn't used: with it disabled no core has siblings, so it
+/// can't tell the core types apart. Empty means every core is a performance core.
+fn efficiency_cpus(sys_cpu: &Path, cpus: &[LogicalCpu], hardware: &config::HardwareConfig) -> BTreeSet<usize> {
+    if let Some(e_cores) = &hardware.e_cores {
+        return e_cores.iter().copied().collect();
+    }
+    if let Some(p_cores) = &hardware.p_cores {
+        return cpus.iter().map(|cpu| cpu.id).filter(|id| !p_cores.contains(id)).collect();
+    }
+    
+    // The hybrid PMUs sit in /sys/devices, next to system/cpu
+    if let Some(devices) = sys_cpu.parent().and_then(Path::parent) {
+        if let Ok(atom) = fs::read_to_string(devices.join("cpu_atom/cpus")) {
+            return parse_cpu_list(&atom).into_iter().collect();
+        }
+        // cpu_core without cpu_atom: a hybrid CPU with its E-cores disabled
+        if let Ok(core) = fs::read_to_string(devices.join("cpu_core/cpus")) {
+            let p_cpus = parse_cpu_list(&core);
+            return cpus.iter().map(|cpu| cpu.id).filter(|id| !p_cpus.contains(id)).collect();
+        }
+    }
+    
+    let capacities: Vec<(usize, u32)> = cpus.iter()
+        .filter_map(|cpu| {
+            fs::read_to_string(sys_cpu.join(format!("cpu{}/cpu_capacity", cpu.id))).ok()
+                .and_then(|c| c.trim().parse::<u32>().ok())
+                .map(|c| (cpu.id, c))
+        })
+        .collect();
+    if !capacities.is_empty() && capacities.len() == cpus.len() {
+        let max_capacity = capacities.iter().map(|(_, c)| *c).max().unwrap_or(0);
+        return capacities.into_iter().filter(|(_, c)| *c < max_capacity).map(|(id, _)| id).collect();
+    }
+    
+    let max_freqs: Vec<(usize, u32)> = cpus.iter()
+        .filter_map(|cpu| cpu.max_freq_mhz.map(|freq| (cpu.id, freq)))
+        .collect();
+    if !max_freqs.is_empty() && max_freqs.len() == cpus.len() {
+        let fastest = max_freqs.iter().map(|(_, freq)| *freq).max().unwrap_or(0) as f64;
+        return max_freqs.into_iter()
+            .filter(|(_, freq)| (*freq as f64) < fastest * E_CORE_MAX_FREQ_RATIO)
+            .map(|(id, _)| id)
+            .collect();
+    }
+    
+    BTreeSet::new()
+}
+
+/// processor -> (physical id, core id) from /proc/cpuinfo
+fn parse_cpuinfo_cores(cpuinfo: &str) -> BTreeMap<usize, (u32, u32)> {
+    let mut cores = BTreeMap::new();
+    for block in cpuinfo.split("\n\n") {
+        let field = |name: &str| {
+            block.lines()
+                .find(|line| line.split(':').next().map(|key| key.trim() == name).unwrap_or(false))
+                .and_then(|line| line.split_once(':'))
+                .and_then(|(_, value)| value.trim().parse::<u32>().ok())
+        };
+        if let Some(processor) = field("processor") {
+            cores.insert(processor as usize, (field("physical id").unwrap_or(0), field("core id").unwrap_or(processor)));
+        }
+    }
+    cores
+}
+
+/// Kernel cpu list format: "0-3,8,10-11"
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut ids = Vec::new();
+    for part in list.trim().split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.trim().parse::<usize>(), end.trim().parse::<usize>()) {
+                    ids.extend(start..=end);
+                }
+            }
+            None => {
+                if let Ok(id) = part.trim().parse::<usize>() {
+                    ids.push(id);
+                }
+            }
+        }
+    }
+    ids
+}
+
+fn read_khz(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()
+        .and_then(|khz| khz.trim().parse::<u32>().ok())
+        .map(|khz| khz / 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    
+    /// A sysfs tree with SMT off: one logical CPU per core, `max_khz[i]` for cpuN
+    fn mock_sysfs(name: &str, max_khz: &[u32]) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("cpu_topology_{}_{}", name, std::process::id()));
+        let sys_cpu = root.join("sys/devices/system/cpu");
+        fs::create_dir_all(&sys_cpu).unwrap();
+        fs::write(sys_cpu.join("online"), format!("0-{}\n", max_khz.len() - 1)).unwrap();
+        for (id, khz) in max_khz.iter().enumerate() {
+            let cpu = sys_cpu.join(format!("cpu{}", id));
+            fs::create_dir_all(cpu.join("topology")).unwrap();
+            fs::create_dir_all(cpu.join("cpufreq")).unwrap();
+            fs::write(cpu.join("topology/core_id"), id.to_string()).unwrap();
+            fs::write(cpu.join("topology/core_cpus_list"), id.to_string()).unwrap();
+            fs::write(cpu.join("cpufreq/cpuinfo_max_freq"), khz.to_string()).unwrap();
+        }
+        (root, sys_cpu)
+    }
+    
+    #[test]
+    fn hybrid_pmu_lists_split_cores_without_smt() {
+        let (root, sys_cpu) = mock_sysfs("pmu", &[5_400_000; 4]);
+        fs::create_dir_all(root.join("sys/devices/cpu_atom")).unwrap();
+        fs::write(root.join("sys/devices/cpu_atom/cpus"), "2-3\n").unwrap();
+        let topology = CpuTopology::detect_from(&sys_cpu, &root.join("cpuinfo"), &config::HardwareConfig::default());
+        fs::remove_dir_all(&root).unwrap();
+        
+        assert_eq!(topology.p_cpus(), vec![0, 1]);
+        assert_eq!(topology.e_cpus(), vec![2, 3]);
+        assert!(topology.hybrid);
+    }
+    
+    #[test]
+    fn max_frequency_splits_cores_without_smt() {
+        let (root, sys_cpu) = mock_sysfs("freq", &[5_400_000, 5_400_000, 3_900_000, 3_900_000]);
+        let topology = CpuTopology::detect_from(&sys_cpu, &root.join("cpuinfo"), &config::HardwareConfig::default());
+        fs::remove_dir_all(&root).unwrap();
+        
+        assert_eq!(topology.p_cpus(), vec![0, 1]);
+        assert_eq!(topology.e_cpus(), vec![2, 3]);
+    }
+    
+    #[test]
+    fn favoured_cores_are_not_efficiency_cores() {
+        let (root, sys_cpu) = mock_sysfs("favoured", &[5_300_000, 5_100_000, 5_100_000, 5_300_000]);
+        let topology = CpuTopology::detect_from(&sys_cpu, &root.join("cpuinfo"), &config::HardwareConfig::default());
+        fs::remove_dir_all(&root).unwrap();
+        
+        assert!(topology.e_cpus().is_empty());
+        assert!(!topology.hybrid);
+        assert_eq!(topology.physical_cores, 4);
+    }
+    
+    #[test]
+    fn configured_cores_override_detection() {
+        let (root, sys_cpu) = mock_sysfs("override", &[5_400_000, 5_400_000, 3_900_000, 3_900_000]);
+        let hardware = config::HardwareConfig { p_cores: Some(vec![0, 1, 2]), ..Default::default() };
+        let topology = CpuTopology::detect_from(&sys_cpu, &root.join("cpuinfo"), &hardware);
+        fs::remove_dir_all(&root).unwrap();
+        
+        assert_eq!(topology.e_cpus(), vec![3]);
+    }
+}
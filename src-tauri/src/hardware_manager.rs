@@ -12,6 +12,7 @@ use tokio::time::sleep;
 use tokio::task;
 use tracing::{info, warn, error, debug};
 
+use crate::hardware::CpuTopology;
 use crate::rgb_controller::RGBManager;
 use crate::fan_controller::FanManager;
 
@@ -316,7 +317,7 @@ impl HardwareController {
         }
         
         // Apply to all CPU cores
-        for cpu_id in 0..32 { // Support for up to 32 cores
+        for cpu_id in CpuTopology::detect().cpu_ids() {
             let governor_path = PathBuf::from(format!("/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor", cpu_id));
             if governor_path.exists() {
                 if let Err(e) = fs::write(&governor_path, governor) {
@@ -0,0 +1,186 @@
+// Logs - systemd journal access for correlating alerts with what the system logged
+// Reads through `journalctl -o json` so no libsystemd binding is needed
+
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::error::{SysError, SysResult};
+use crate::privilege::PrivilegedBatch;
+
+/// What "clean logs" vacuums the journal down to unless told otherwise
+pub const DEFAULT_JOURNAL_SIZE_MB: u64 = 500;
+
+/// How far `JournalReader::vacuum` trims the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VacuumTarget {
+    /// Delete the oldest archived files until the journal fits in this many MiB
+    SizeMb(u64),
+    /// Delete archived files with no entries newer than this many days
+    MaxAgeDays(u64),
+}
+
+impl VacuumTarget {
+    fn journalctl_arg(&self) -> String {
+        match self {
+            VacuumTarget::SizeMb(mb) => format!("--vacuum-size={}M", mb),
+            VacuumTarget::MaxAgeDays(days) => format!("--vacuum-time={}d", days),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: u64, // unix seconds
+    pub unit: Option<String>,
+    pub message: String,
+    pub priority: u8, // 0 (emerg) - 7 (debug)
+}
+
+impl JournalEntry {
+    /// "unit: message", the form recommendations and alerts show
+    pub fn display_line(&self) -> String {
+        format!("{}: {}", self.unit.as_deref().unwrap_or("kernel"), self.message)
+    }
+}
+
+pub struct JournalReader {
+    pub max_entries: usize,
+}
+
+impl JournalReader {
+    pub fn new() -> Self {
+        Self { max_entries: 200 }
+    }
+    
+    /// Entries at `priority` or more severe from the last `since_secs` seconds
+    pub fn get_recent_journal(&self, priority: u8, since_secs: u64) -> Vec<JournalEntry> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.query(priority, now.saturating_sub(since_secs), None)
+    }
+    
+    /// Entries within `window_secs` either side of `timestamp`, for alert context
+    pub fn get_journal_around(&self, timestamp: u64, window_secs: u64, priority: u8) -> Vec<JournalEntry> {
+        self.query(priority, timestamp.saturating_sub(window_secs), Some(timestamp + window_secs))
+    }
+    
+    /// Archived plus active journal size in bytes (`journalctl --disk-usage`).
+    /// Without root this may only count the user's own journal.
+    pub fn disk_usage(&self) -> Option<u64> {
+        let output = Command::new("journalctl").arg("--disk-usage").output().ok()?;
+        if !output.status.success() {
+            warn!("⚠️ journalctl --disk-usage failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+            return None;
+        }
+        
+        // "Archived and active journals take up 1.2G in the file system."
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .skip_while(|word| *word != "up")
+            .nth(1)
+            .and_then(parse_journal_size)
+    }
+    
+    /// Trim the journal to `target`, elevating if needed. Returns the bytes freed.
+    pub fn vacuum(&self, target: VacuumTarget) -> SysResult<u64> {
+        let before = self.disk_usage()
+            .ok_or_else(|| SysError::HardwareUnavailable("journalctl --disk-usage".to_string()))?;
+        
+        let mut batch = PrivilegedBatch::new();
+        batch.run("journalctl", &[&target.journalctl_arg()]);
+        batch.apply_all()?;
+        
+        let freed = before.saturating_sub(self.disk_usage().unwrap_or(before));
+        info!("📜 Journal vacuumed ({:?}): freed {:.1} MiB", target, freed as f64 / 1048576.0);
+        Ok(freed)
+    }
+    
+    fn query(&self, priority: u8, since: u64, until: Option<u64>) -> Vec<JournalEntry> {
+        let mut command = Command::new("journalctl");
+        command
+            .args(&["-o", "json", "--no-pager", "-q"])
+            .arg(format!("--since=@{}", since))
+            .arg(format!("--priority={}", priority.min(7)))
+            .arg(format!("--lines={}", self.max_entries));
+        if let Some(until) = until {
+            command.arg(format!("--until=@{}", until));
+        }
+        
+        let output = match command.output() {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("⚠️ Failed to run journalctl: {}", e);
+                return Vec::new();
+            }
+        };
+        
+        if !output.status.success() {
+            warn!("⚠️ journalctl failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+            return Vec::new();
+        }
+        
+        let entries: Vec<JournalEntry> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_journal_line)
+            .collect();
+        
+        debug!("📜 Read {} journal entries", entries.len());
+        entries
+    }
+}
+
+/// One `journalctl -o json` line. Non-UTF-8 messages come as byte arrays.
+fn parse_journal_line(line: &str) -> Option<JournalEntry> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    
+    let message = match value.get("MESSAGE")? {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes.iter().filter_map(|b| b.as_u64().map(|b| b as u8)).collect();
+            String::from_utf8_lossy(&bytes).to_string()
+        }
+        _ => return None,
+    };
+    
+    let timestamp = value.get("__REALTIME_TIMESTAMP")
+        .and_then(|t| t.as_str())
+        .and_then(|t| t.parse::<u64>().ok())
+        .map(|micros| micros / 1_000_000)
+        .unwrap_or(0);
+    
+    let priority = value.get("PRIORITY")
+        .and_then(|p| p.as_str())
+        .and_then(|p| p.parse::<u8>().ok())
+        .unwrap_or(6);
+    
+    let unit = value.get("_SYSTEMD_UNIT")
+        .or_else(|| value.get("SYSLOG_IDENTIFIER"))
+        .and_then(|u| u.as_str())
+        .map(|u| u.to_string());
+    
+    Some(JournalEntry {
+        timestamp,
+        unit,
+        message,
+        priority,
+    })
+}
+
+/// journalctl sizes like "1.2G", "512.0M" or "8.0K", in powers of 1024
+pub fn parse_journal_size(size: &str) -> Option<u64> {
+    let size = size.trim_end_matches('.');
+    let (number, multiplier) = match size.chars().last()? {
+        'B' => (&size[..size.len() - 1], 1u64),
+        'K' => (&size[..size.len() - 1], 1 << 10),
+        'M' => (&size[..size.len() - 1], 1 << 20),
+        'G' => (&size[..size.len() - 1], 1 << 30),
+        'T' => (&size[..size.len() - 1], 1 << 40),
+        _ => (size, 1),
+    };
+    number.parse::<f64>().ok().map(|value| (value * multiplier as f64) as u64)
+}
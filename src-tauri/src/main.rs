@@ -9,25 +9,45 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::thread;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use tauri::{Manager, State, Window, CustomMenuItem, SystemTray, SystemTrayMenu};
-use tokio::time::interval;
+use tauri::{Manager, State, Window, CustomMenuItem, SystemTray, SystemTrayHandle, SystemTrayMenu, SystemTrayMenuItem};
 use tracing::{info, warn, error, debug};
 use tracing_subscriber;
+use nix::sys::signal::Signal;
 use sysinfo::System;
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, Result as SqlResult};
 use walkdir::WalkDir;
 
 // Import command modules only for now
+mod action_log;
+mod ai;
+mod api;
+mod cgroups;
 mod commands;
+mod config;
+mod database;
+mod dbus_service;
+mod error;
+mod logs;
+mod package_manager;
+mod privilege;
+mod rapl;
+mod rgb;
+mod sensors;
+mod service;
+mod snapshots;
+mod thermal_throttle;
+mod watchdog;
+use action_log::ActionLog;
 use commands::*;
+use database::Database;
 
 // ============================================================================
 // CORE DATA STRUCTURES - COMPLETE IMPLEMENTATION
@@ -44,6 +64,15 @@ pub struct SystemMetrics {
     pub temperature: f64,
     pub processes: usize,
     pub uptime: u64,
+    #[serde(default)]
+    pub pressure: PressureStats,
+    /// CPU package power from RAPL; None without RAPL access or on the first sample
+    #[serde(default)]
+    pub cpu_power_watts: Option<f64>,
+    /// CPU thermal throttle events since the previous sample; None without the
+    /// kernel's counters or on the first sample
+    #[serde(default)]
+    pub thermal_throttle_events: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +82,9 @@ pub struct AIInsight {
     pub recommendation: String,
     pub priority: u8,
     pub timestamp: DateTime<Utc>,
+    /// Journal warnings logged around the time it fired; not stored with the insight
+    #[serde(default)]
+    pub journal_context: Vec<logs::JournalEntry>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +96,37 @@ pub struct HardwareStatus {
     pub power_consumption: Option<f64>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GpuProcessKind {
+    Compute,
+    Graphics,
+    /// Listed by both the compute and graphics queries
+    Both,
+}
+
+/// A process holding NVIDIA GPU memory, from `nvidia-smi`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuProcess {
+    pub pid: u32,
+    /// Short name from /proc (e.g. "ollama"), falling back to what nvidia-smi reported
+    pub name: String,
+    pub command: String,
+    /// None when the driver doesn't report it (e.g. "[N/A]" without permission)
+    pub used_memory_mb: Option<u64>,
+    pub kind: GpuProcessKind,
+}
+
+/// A process the user might want to stop, such as the top CPU consumer behind
+/// a "check for runaway processes" recommendation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessCandidate {
+    pub pid: u32,
+    pub name: String,
+    /// Percent of one core, so a busy multithreaded process goes past 100
+    pub cpu_usage: f32,
+    pub memory_bytes: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIRecommendation {
     pub id: String,
@@ -84,6 +147,81 @@ pub struct FanStatus {
     pub auto: bool,
 }
 
+/// Outcome of setting one PWM channel, checked against its tachometer.
+/// `responded` is false when there is no RPM sensor to check, the firmware put
+/// its own duty cycle back, or the RPM didn't move the way the new duty cycle should.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanControlResult {
+    pub name: String,
+    pub requested_pct: u8,
+    pub measured_rpm_before: Option<u32>,
+    pub measured_rpm_after: Option<u32>,
+    pub responded: bool,
+}
+
+/// Temperature-driven fan curve: each point maps a CPU temperature (°C)
+/// to a PWM duty cycle (%). Points must be sorted by temperature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanCurve {
+    pub points: Vec<(f64, u8)>,
+}
+
+impl FanCurve {
+    pub fn validate(&self) -> Result<()> {
+        if self.points.len() < 2 {
+            return Err(anyhow!("Fan curve needs at least two points"));
+        }
+        
+        for (temp, pwm) in &self.points {
+            if !(0.0..=110.0).contains(temp) {
+                return Err(anyhow!("Fan curve temperature {:.1}°C out of range (0-110)", temp));
+            }
+            if *pwm > 100 {
+                return Err(anyhow!("Fan curve PWM {}% out of range (0-100)", pwm));
+            }
+        }
+        
+        for pair in self.points.windows(2) {
+            if pair[1].0 <= pair[0].0 {
+                return Err(anyhow!("Fan curve temperatures must be strictly increasing"));
+            }
+            if pair[1].1 < pair[0].1 {
+                return Err(anyhow!("Fan curve PWM must not decrease as temperature rises"));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Linearly interpolate the PWM percentage for a temperature, clamping
+    /// to the first/last point outside the curve range. A curve with no
+    /// points runs the fans at full speed rather than guessing.
+    pub fn pwm_for_temperature(&self, temp: f64) -> u8 {
+        let (first_temp, first_pwm, last_temp, last_pwm) = match (self.points.first(), self.points.last()) {
+            (Some(&(first_temp, first_pwm)), Some(&(last_temp, last_pwm))) => (first_temp, first_pwm, last_temp, last_pwm),
+            _ => return 100,
+        };
+        
+        if temp <= first_temp {
+            return first_pwm;
+        }
+        if temp >= last_temp {
+            return last_pwm;
+        }
+        
+        for pair in self.points.windows(2) {
+            let (t0, p0) = pair[0];
+            let (t1, p1) = pair[1];
+            if temp <= t1 {
+                let ratio = (temp - t0) / (t1 - t0);
+                return (p0 as f64 + ratio * (p1 as f64 - p0 as f64)).round() as u8;
+            }
+        }
+        
+        last_pwm
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiskInfo {
     pub total: u64,
@@ -93,76 +231,130 @@ pub struct DiskInfo {
     pub filesystem: String,
 }
 
+/// Pressure stall information from /proc/pressure; None where the kernel lacks PSI
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PressureStats {
+    pub cpu: Option<ResourcePressure>,
+    pub memory: Option<ResourcePressure>,
+    pub io: Option<ResourcePressure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcePressure {
+    /// Time at least one task was stalled
+    pub some: PressureLine,
+    /// Time every non-idle task was stalled at once; absent for cpu on older kernels
+    pub full: Option<PressureLine>,
+}
+
+/// Stall time as a percentage over 10s/60s/300s windows, plus the cumulative total
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PressureLine {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    pub total_us: u64,
+}
+
+impl PressureStats {
+    pub fn read(proc_dir: &Path) -> Self {
+        let read = |resource: &str| {
+            fs::read_to_string(proc_dir.join("pressure").join(resource)).ok()
+                .and_then(|content| ResourcePressure::parse(&content))
+        };
+        PressureStats {
+            cpu: read("cpu"),
+            memory: read("memory"),
+            io: read("io"),
+        }
+    }
+    
+    /// Memory "some" avg60: sustained stalls mean thrashing, unlike a full page cache
+    pub fn memory_stall_percent(&self) -> Option<f64> {
+        self.memory.as_ref().map(|memory| memory.some.avg60)
+    }
+}
+
+impl ResourcePressure {
+    /// Parses "some avg10=0.00 avg60=0.00 avg300=0.00 total=0" plus an optional "full" line
+    fn parse(content: &str) -> Option<Self> {
+        let mut some = None;
+        let mut full = None;
+        for line in content.lines() {
+            let mut fields = line.split_whitespace();
+            let kind = fields.next();
+            let mut pressure = PressureLine { avg10: 0.0, avg60: 0.0, avg300: 0.0, total_us: 0 };
+            for field in fields {
+                match field.split_once('=') {
+                    Some(("avg10", value)) => pressure.avg10 = value.parse().ok()?,
+                    Some(("avg60", value)) => pressure.avg60 = value.parse().ok()?,
+                    Some(("avg300", value)) => pressure.avg300 = value.parse().ok()?,
+                    Some(("total", value)) => pressure.total_us = value.parse().ok()?,
+                    _ => {}
+                }
+            }
+            match kind {
+                Some("some") => some = Some(pressure),
+                Some("full") => full = Some(pressure),
+                _ => {}
+            }
+        }
+        Some(ResourcePressure { some: some?, full })
+    }
+}
+
 // ============================================================================
 // AI ENGINE - COMPLETE IMPLEMENTATION
 // ============================================================================
 
+/// learning_data keys this engine owns; the other AI components use their own
+const MONITOR_LEARNING_PREFIX: &str = "monitor.";
+
 pub struct AIEngine {
-    connection: Arc<Mutex<Connection>>,
+    database: Database,
     insights: Arc<Mutex<Vec<AIInsight>>>,
     learning_data: Arc<Mutex<HashMap<String, f64>>>,
 }
 
 impl AIEngine {
-    pub fn new() -> Result<Self> {
-        let conn = Connection::open("ai_sysadmin.db")?;
-        
-        // Initialize database tables
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS system_patterns (
-                id INTEGER PRIMARY KEY,
-                pattern_name TEXT NOT NULL,
-                pattern_value REAL NOT NULL,
-                timestamp TEXT NOT NULL,
-                confidence REAL NOT NULL
-            )",
-            [],
-        )?;
-        
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS ai_insights (
-                id INTEGER PRIMARY KEY,
-                pattern TEXT NOT NULL,
-                confidence REAL NOT NULL,
-                recommendation TEXT NOT NULL,
-                priority INTEGER NOT NULL,
-                timestamp TEXT NOT NULL,
-                applied BOOLEAN DEFAULT FALSE
-            )",
-            [],
-        )?;
-        
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS system_history (
-                id INTEGER PRIMARY KEY,
-                metrics TEXT NOT NULL,
-                timestamp TEXT NOT NULL
-            )",
-            [],
-        )?;
-        
-        info!("AI Engine initialized with database");
+    pub fn new(database: Database) -> Result<Self> {
+        // Running totals from earlier runs, written by save_learning_data
+        let learning_data: HashMap<String, f64> = database.learning_values()?
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.strip_prefix(MONITOR_LEARNING_PREFIX)?.to_string(), value.as_f64()?)))
+            .collect();
+        info!("AI Engine initialized with database ({} learned values)", learning_data.len());
         
         Ok(AIEngine {
-            connection: Arc::new(Mutex::new(conn)),
+            database,
             insights: Arc::new(Mutex::new(Vec::new())),
-            learning_data: Arc::new(Mutex::new(HashMap::new())),
+            learning_data: Arc::new(Mutex::new(learning_data)),
         })
     }
     
+    /// Persist the in-memory learning totals
+    pub fn save_learning_data(&self) -> Result<()> {
+        let learning = self.learning_data.lock().unwrap().clone();
+        for (key, value) in &learning {
+            self.database.set_learning_value(&format!("{}{}", MONITOR_LEARNING_PREFIX, key), &serde_json::json!(value))?;
+        }
+        info!("💾 Saved {} learned values", learning.len());
+        Ok(())
+    }
+    
+    pub fn action_log(&self) -> ActionLog {
+        ActionLog::new(self.database.clone())
+    }
+    
     pub fn analyze_system(&self, metrics: &SystemMetrics) -> Result<Vec<AIInsight>> {
         let mut insights = Vec::new();
-        let conn = self.connection.lock().unwrap();
+        let thresholds = config::get().thresholds;
         
         // Store metrics in database
-        let metrics_json = serde_json::to_string(metrics)?;
-        conn.execute(
-            "INSERT INTO system_history (metrics, timestamp) VALUES (?1, ?2)",
-            params![metrics_json, metrics.timestamp.to_rfc3339()]
-        )?;
+        self.database.record_metrics(metrics)?;
         
         // Generate AI insights based on patterns
-        if metrics.cpu_usage > 90.0 {
+        if metrics.cpu_usage > thresholds.cpu_usage {
             let insight = AIInsight {
                 pattern: "high_cpu_usage".to_string(),
                 confidence: 0.95,
@@ -172,25 +364,43 @@ impl AIEngine {
                 ),
                 priority: 1,
                 timestamp: Utc::now(),
+                journal_context: Vec::new(),
             };
             insights.push(insight);
         }
         
-        if metrics.memory_usage > 85.0 {
-            let insight = AIInsight {
-                pattern: "high_memory_usage".to_string(),
-                confidence: 0.90,
-                recommendation: format!(
-                    "Memory usage at {:.1}% - Consider adding more RAM or closing memory-intensive applications", 
-                    metrics.memory_usage
-                ),
-                priority: 1,
-                timestamp: Utc::now(),
-            };
-            insights.push(insight);
+        // A full page cache looks like high usage too, so prefer PSI when the kernel has it
+        match metrics.pressure.memory_stall_percent() {
+            Some(stall) if stall > thresholds.memory_pressure => {
+                insights.push(AIInsight {
+                    pattern: "memory_pressure".to_string(),
+                    confidence: 0.95,
+                    recommendation: format!(
+                        "Tasks stalled on memory {:.1}% of the last minute ({:.1}% used) - the system is thrashing; close memory-intensive applications or add RAM", 
+                        stall, metrics.memory_usage
+                    ),
+                    priority: 1,
+                    timestamp: Utc::now(),
+                    journal_context: Vec::new(),
+                });
+            }
+            None if metrics.memory_usage > thresholds.memory_usage => {
+                insights.push(AIInsight {
+                    pattern: "high_memory_usage".to_string(),
+                    confidence: 0.90,
+                    recommendation: format!(
+                        "Memory usage at {:.1}% - Consider adding more RAM or closing memory-intensive applications", 
+                        metrics.memory_usage
+                    ),
+                    priority: 1,
+                    timestamp: Utc::now(),
+                    journal_context: Vec::new(),
+                });
+            }
+            _ => {}
         }
         
-        if metrics.disk_usage > 90.0 {
+        if metrics.disk_usage > thresholds.disk_usage {
             let insight = AIInsight {
                 pattern: "high_disk_usage".to_string(),
                 confidence: 0.98,
@@ -200,11 +410,12 @@ impl AIEngine {
                 ),
                 priority: 1,
                 timestamp: Utc::now(),
+                journal_context: Vec::new(),
             };
             insights.push(insight);
         }
         
-        if metrics.temperature > 80.0 {
+        if metrics.temperature > thresholds.cpu_temperature {
             let insight = AIInsight {
                 pattern: "high_temperature".to_string(),
                 confidence: 0.85,
@@ -214,23 +425,14 @@ impl AIEngine {
                 ),
                 priority: 2,
                 timestamp: Utc::now(),
+                journal_context: Vec::new(),
             };
             insights.push(insight);
         }
         
         // Store insights
         for insight in &insights {
-            conn.execute(
-                "INSERT INTO ai_insights (pattern, confidence, recommendation, priority, timestamp) 
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-                params![
-                    insight.pattern,
-                    insight.confidence,
-                    insight.recommendation,
-                    insight.priority,
-                    insight.timestamp.to_rfc3339()
-                ],
-            )?;
+            self.database.store_insight(insight, "monitor")?;
         }
         
         // Update learning data
@@ -244,31 +446,7 @@ impl AIEngine {
     }
     
     pub fn get_recommendations(&self) -> Result<Vec<AIInsight>> {
-        let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT pattern, confidence, recommendation, priority, timestamp 
-             FROM ai_insights 
-             WHERE applied = FALSE 
-             ORDER BY priority ASC, confidence DESC 
-             LIMIT 10"
-        )?;
-        
-        let insight_iter = stmt.query_map([], |row| {
-            Ok(AIInsight {
-                pattern: row.get(0)?,
-                confidence: row.get(1)?,
-                recommendation: row.get(2)?,
-                priority: row.get(3)?,
-                timestamp: row.get::<_, String>(4)?.parse().unwrap_or(Utc::now()),
-            })
-        })?;
-        
-        let mut insights = Vec::new();
-        for insight in insight_iter {
-            insights.push(insight?);
-        }
-        
-        Ok(insights)
+        self.database.pending_insights(10)
     }
 }
 
@@ -276,10 +454,45 @@ impl AIEngine {
 // SYSTEM MONITOR - COMPLETE IMPLEMENTATION
 // ============================================================================
 
+/// Pushed to live subscribers (the /ws/metrics socket) as the monitor produces it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum LiveEvent {
+    Metrics(SystemMetrics),
+    Recommendation(AIInsight),
+    /// The charger was plugged in or pulled; capacity is None without a battery
+    PowerSourceChanged { on_ac: bool, battery_capacity: Option<u8> },
+}
+
+/// Events buffered per subscriber; a slower client loses the oldest ones
+const LIVE_EVENT_CAPACITY: usize = 64;
+
+pub(crate) const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Journal entries this far either side of an insight are attached to it
+const JOURNAL_WINDOW_SECS: u64 = 60;
+/// Warning and more severe
+const JOURNAL_CONTEXT_PRIORITY: u8 = 4;
+
+const PID_MAX_PATH: &str = "/proc/sys/kernel/pid_max";
+/// The kernel's ceiling for pid_max on 64-bit, used when it can't be read
+const PID_MAX_LIMIT: i32 = 4_194_304;
+
 pub struct SystemMonitor {
     system: System,
     ai_engine: Arc<AIEngine>,
     metrics_history: Arc<Mutex<Vec<SystemMetrics>>>,
+    events: tokio::sync::broadcast::Sender<LiveEvent>,
+    /// AC state at the previous sample; None until the first read or without an adapter
+    on_ac_power: Option<bool>,
+    /// CPU and memory caps on runaway processes
+    cgroups: Arc<cgroups::CgroupLimiter>,
+    cpu_power: rapl::PowerMeter,
+    cpu_throttle: thermal_throttle::ThrottleMeter,
+    journal: logs::JournalReader,
+    /// When journal context was last captured per insight pattern, so an insight
+    /// repeating every sample doesn't run journalctl every sample
+    journal_captured: HashMap<String, SystemTime>,
 }
 
 impl SystemMonitor {
@@ -291,9 +504,35 @@ impl SystemMonitor {
             system,
             ai_engine,
             metrics_history: Arc::new(Mutex::new(Vec::new())),
+            events: tokio::sync::broadcast::channel(LIVE_EVENT_CAPACITY).0,
+            on_ac_power: None,
+            cgroups: Arc::new(cgroups::CgroupLimiter::new()),
+            cpu_power: rapl::PowerMeter::new(),
+            cpu_throttle: thermal_throttle::ThrottleMeter::new(),
+            journal: logs::JournalReader::new(),
+            journal_captured: HashMap::new(),
         }
     }
     
+    /// Journal entries around `insight`, at most once per pattern per alert cooldown
+    fn journal_context_for(&mut self, insight: &AIInsight) -> Vec<logs::JournalEntry> {
+        let now = SystemTime::now();
+        let cooldown = Duration::from_secs(config::get().monitoring.alert_cooldown_secs);
+        let recent = self.journal_captured.get(&insight.pattern)
+            .map(|captured| now.duration_since(*captured).unwrap_or_default() < cooldown)
+            .unwrap_or(false);
+        if recent {
+            return Vec::new();
+        }
+        self.journal_captured.insert(insight.pattern.clone(), now);
+        self.journal.get_journal_around(insight.timestamp.timestamp().max(0) as u64, JOURNAL_WINDOW_SECS, JOURNAL_CONTEXT_PRIORITY)
+    }
+    
+    /// Sender to subscribe to for live metrics and recommendations
+    pub fn event_sender(&self) -> tokio::sync::broadcast::Sender<LiveEvent> {
+        self.events.clone()
+    }
+    
     pub fn collect_metrics(&mut self) -> Result<SystemMetrics> {
         self.system.refresh_all();
         
@@ -344,6 +583,9 @@ impl SystemMonitor {
             temperature,
             processes,
             uptime,
+            pressure: PressureStats::read(Path::new("/proc")),
+            cpu_power_watts: self.cpu_power.read_watts(),
+            thermal_throttle_events: self.cpu_throttle.read_events(),
         };
         
         // Store in history (keep last 1000 entries)
@@ -352,11 +594,20 @@ impl SystemMonitor {
         if history.len() > 1000 {
             history.remove(0);
         }
+        drop(history);
+        
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(LiveEvent::Metrics(metrics.clone()));
+        self.check_power_source(Path::new(POWER_SUPPLY_DIR));
         
         // AI analysis
         match self.ai_engine.analyze_system(&metrics) {
             Ok(insights) => {
                 info!("AI generated {} insights from system metrics", insights.len());
+                for mut insight in insights {
+                    insight.journal_context = self.journal_context_for(&insight);
+                    let _ = self.events.send(LiveEvent::Recommendation(insight));
+                }
             }
             Err(e) => {
                 warn!("AI analysis failed: {}", e);
@@ -366,27 +617,147 @@ impl SystemMonitor {
         Ok(metrics)
     }
     
-    fn read_cpu_temperature(&self) -> Result<f64> {
-        // Try thermal zones first
-        for i in 0..10 {
-            let path = format!("/sys/class/thermal/thermal_zone{}/temp", i);
-            if let Ok(temp_str) = fs::read_to_string(&path) {
-                if let Ok(temp_millis) = temp_str.trim().parse::<i32>() {
-                    return Ok(temp_millis as f64 / 1000.0);
+    /// Emit PowerSourceChanged when the AC adapter's online state flips.
+    /// The first reading only establishes the starting state.
+    fn check_power_source(&mut self, power_supply_dir: &Path) {
+        let on_ac = match read_ac_online(power_supply_dir) {
+            Some(on_ac) => on_ac,
+            None => return,
+        };
+        
+        let previous = self.on_ac_power.replace(on_ac);
+        if previous.is_none() || previous == Some(on_ac) {
+            return;
+        }
+        
+        let battery_capacity = read_battery_capacity(power_supply_dir);
+        info!("🔌 Power source changed: {} (battery {:?}%)", if on_ac { "AC" } else { "battery" }, battery_capacity);
+        let _ = self.events.send(LiveEvent::PowerSourceChanged { on_ac, battery_capacity });
+    }
+    
+    pub fn latest_metrics(&self) -> Option<SystemMetrics> {
+        self.metrics_history.lock().ok()?.last().cloned()
+    }
+    
+    /// The most recent `limit` samples, oldest first
+    pub fn recent_metrics(&self, limit: usize) -> Vec<SystemMetrics> {
+        match self.metrics_history.lock() {
+            Ok(history) => history[history.len().saturating_sub(limit)..].to_vec(),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Busiest process at the last sample, leaving out init and this app
+    pub fn top_cpu_process(&self) -> Option<ProcessCandidate> {
+        let own_pid = std::process::id();
+        self.system.processes().values()
+            .filter(|process| process.pid().as_u32() > 1 && process.pid().as_u32() != own_pid)
+            .max_by(|a, b| a.cpu_usage().total_cmp(&b.cpu_usage()))
+            .map(|process| ProcessCandidate {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string(),
+                cpu_usage: process.cpu_usage(),
+                memory_bytes: process.memory(),
+            })
+    }
+    
+    /// Every process at the last sample, for workload classification
+    pub fn processes(&self) -> Vec<ProcessCandidate> {
+        self.system.processes().values()
+            .map(|process| ProcessCandidate {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string(),
+                cpu_usage: process.cpu_usage(),
+                memory_bytes: process.memory(),
+            })
+            .collect()
+    }
+    
+    /// Send `signal` to `pid`. Processes owned by another user are signalled
+    /// through the privileged helper, which asks for authorization. Needs no
+    /// monitor state, so callers don't hold the monitor lock through the prompt.
+    pub fn kill_process(pid: u32, signal: Signal) -> Result<String> {
+        let target = Self::check_target(pid)?;
+        
+        match nix::sys::signal::kill(target, signal) {
+            Ok(()) => {}
+            Err(nix::errno::Errno::EPERM) => {
+                let mut batch = privilege::PrivilegedBatch::new();
+                batch.run("kill", &["-s", signal.as_str().trim_start_matches("SIG"), &pid.to_string()]);
+                batch.apply_all().map_err(|e| anyhow!("Failed to send {} to {}: {}", signal, pid, e))?;
+            }
+            Err(nix::errno::Errno::ESRCH) => return Err(anyhow!("No process with pid {}", pid)),
+            Err(e) => return Err(anyhow!("Failed to send {} to {}: {}", signal, pid, e)),
+        }
+        
+        info!("🛑 Sent {} to process {}", signal, pid);
+        Ok(format!("Sent {} to process {}", signal, pid))
+    }
+    
+    /// Set the nice value of `pid` (-20 to 19). Lowering it, or renicing another
+    /// user's process, goes through the privileged helper.
+    pub fn renice_process(pid: u32, nice: i32) -> Result<String> {
+        let target = Self::check_target(pid)?;
+        if !(-20..=19).contains(&nice) {
+            return Err(anyhow!("Nice value {} is outside -20 to 19", nice));
+        }
+        
+        // setpriority only reads its arguments; the pid was checked above
+        let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, target.as_raw() as libc::id_t, nice) };
+        if result != 0 {
+            match nix::errno::Errno::last() {
+                nix::errno::Errno::EPERM | nix::errno::Errno::EACCES => {
+                    let mut batch = privilege::PrivilegedBatch::new();
+                    batch.run("renice", &["-n", &nice.to_string(), "-p", &pid.to_string()]);
+                    batch.apply_all().map_err(|e| anyhow!("Failed to renice {}: {}", pid, e))?;
                 }
+                nix::errno::Errno::ESRCH => return Err(anyhow!("No process with pid {}", pid)),
+                e => return Err(anyhow!("Failed to renice {}: {}", pid, e)),
             }
         }
         
-        // Try hwmon
-        for entry in WalkDir::new("/sys/class/hwmon").max_depth(2) {
-            if let Ok(entry) = entry {
-                if entry.file_name().to_string_lossy().starts_with("temp") && 
-                   entry.file_name().to_string_lossy().ends_with("_input") {
-                    if let Ok(temp_str) = fs::read_to_string(entry.path()) {
-                        if let Ok(temp_millis) = temp_str.trim().parse::<i32>() {
-                            return Ok(temp_millis as f64 / 1000.0);
-                        }
-                    }
+        info!("🐢 Process {} reniced to {}", pid, nice);
+        Ok(format!("Process {} now runs at nice {}", pid, nice))
+    }
+    
+    /// The process limiter, shared so limits can be set and removed without
+    /// holding the monitor lock while systemd or pkexec asks for authorization
+    pub fn cgroup_limiter(&self) -> Arc<cgroups::CgroupLimiter> {
+        self.cgroups.clone()
+    }
+    
+    /// Cap `pid` at `cpu_pct` percent of one CPU and/or `mem_bytes` of memory,
+    /// a gentler answer to a runaway process than killing it
+    pub fn limit_process(limiter: &cgroups::CgroupLimiter, pid: u32, cpu_pct: Option<f32>, mem_bytes: Option<u64>) -> Result<cgroups::ProcessLimit> {
+        Self::check_target(pid)?;
+        Ok(limiter.limit_process(pid, cpu_pct, mem_bytes)?)
+    }
+    
+    /// The pid as the kernel takes it. init, this app and anything outside
+    /// 2..pid_max are refused: kill(2) reads 0 and negative pids as process
+    /// groups, so a u32 wrapping negative could signal everything we can reach.
+    fn check_target(pid: u32) -> Result<nix::unistd::Pid> {
+        let pid_max = fs::read_to_string(PID_MAX_PATH).ok()
+            .and_then(|value| value.trim().parse::<i32>().ok())
+            .unwrap_or(PID_MAX_LIMIT);
+        valid_target_pid(pid, pid_max)
+    }
+    
+    fn read_cpu_temperature(&self) -> Result<f64> {
+        // The CPU's own hwmon chip; thermal_zone0 is often acpitz or a chipset sensor
+        if let Some(temp) = sensors::SensorMap::scan().cpu_temperature() {
+            return Ok(temp);
+        }
+        
+        // Thermal zones, the package zone first
+        let mut zones: Vec<PathBuf> = (0..10)
+            .map(|i| PathBuf::from(format!("/sys/class/thermal/thermal_zone{}", i)))
+            .collect();
+        zones.sort_by_key(|zone| fs::read_to_string(zone.join("type")).map(|kind| kind.trim() != "x86_pkg_temp").unwrap_or(true));
+        for zone in zones {
+            if let Ok(temp_str) = fs::read_to_string(zone.join("temp")) {
+                if let Ok(temp_millis) = temp_str.trim().parse::<i32>() {
+                    return Ok(temp_millis as f64 / 1000.0);
                 }
             }
         }
@@ -412,12 +783,73 @@ impl SystemMonitor {
     }
 }
 
+/// Whether any mains adapter (AC*, ADP*) under `power_supply_dir` is online
+pub(crate) fn read_ac_online(power_supply_dir: &Path) -> Option<bool> {
+    let mut on_ac = None;
+    for entry in fs::read_dir(power_supply_dir).ok()?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with("AC") && !name.starts_with("ADP") {
+            continue;
+        }
+        if let Ok(online) = fs::read_to_string(entry.path().join("online")) {
+            if online.trim() == "1" {
+                return Some(true);
+            }
+            on_ac = Some(false);
+        }
+    }
+    on_ac
+}
+
+/// `pid` as a kernel pid if it can name a single process other than init and this app
+fn valid_target_pid(pid: u32, pid_max: i32) -> Result<nix::unistd::Pid> {
+    let raw = i32::try_from(pid).map_err(|_| anyhow!("Invalid process id {}", pid))?;
+    if raw >= pid_max {
+        return Err(anyhow!("Invalid process id {} (pid_max is {})", pid, pid_max));
+    }
+    if raw <= 1 || pid == std::process::id() {
+        return Err(anyhow!("Refusing to act on process {}", pid));
+    }
+    Ok(nix::unistd::Pid::from_raw(raw))
+}
+
+/// Capacity (%) of the first battery (BAT*) under `power_supply_dir`
+fn read_battery_capacity(power_supply_dir: &Path) -> Option<u8> {
+    let mut batteries: Vec<PathBuf> = fs::read_dir(power_supply_dir).ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("BAT"))
+        .map(|entry| entry.path())
+        .collect();
+    batteries.sort();
+    
+    batteries.iter().find_map(|battery| {
+        fs::read_to_string(battery.join("capacity")).ok()
+            .and_then(|capacity| capacity.trim().parse::<u8>().ok())
+    })
+}
+
 // ============================================================================
 // HARDWARE CONTROLLER - COMPLETE IMPLEMENTATION
 // ============================================================================
 
 pub struct HardwareController;
 
+const FAN_CURVE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// How long a fan gets to spin up or down before its RPM is checked
+const FAN_SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// RPM changes smaller than this are tachometer noise
+const FAN_RPM_TOLERANCE: u32 = 100;
+
+struct FanCurveTask {
+    stop: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+}
+
+// Only one fan curve may drive the PWM channels at a time
+static FAN_CURVE_TASK: Mutex<Option<FanCurveTask>> = Mutex::new(None);
+
 impl HardwareController {
     pub fn get_hardware_status() -> Result<HardwareStatus> {
         let mut cpu_temps = Vec::new();
@@ -467,12 +899,105 @@ impl HardwareController {
         })
     }
     
+    /// Processes using the NVIDIA GPU, biggest VRAM users first. Empty on machines
+    /// without nvidia-smi (integrated graphics only) or when the driver isn't loaded.
+    pub fn get_gpu_processes() -> Vec<GpuProcess> {
+        let mut processes: Vec<GpuProcess> = Vec::new();
+        
+        for (query, kind) in [("--query-compute-apps", GpuProcessKind::Compute), ("--query-graphics-apps", GpuProcessKind::Graphics)] {
+            let output = match std::process::Command::new("nvidia-smi")
+                .args([&format!("{}=pid,process_name,used_memory", query), "--format=csv,noheader,nounits"])
+                .output()
+            {
+                Ok(output) if output.status.success() => output,
+                Ok(output) => {
+                    debug!("nvidia-smi {} failed: {}", query, String::from_utf8_lossy(&output.stderr).trim());
+                    continue;
+                }
+                Err(e) => {
+                    debug!("nvidia-smi unavailable: {}", e);
+                    return Vec::new();
+                }
+            };
+            
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                let (pid, rest) = match line.split_once(',') {
+                    Some((pid, rest)) => (pid.trim(), rest),
+                    None => continue,
+                };
+                let pid = match pid.parse::<u32>() {
+                    Ok(pid) => pid,
+                    Err(_) => continue,
+                };
+                // process_name may contain commas, so used_memory is taken from the end
+                let (process_name, used_memory) = rest.rsplit_once(',').unwrap_or((rest, ""));
+                let used_memory_mb = used_memory.trim().parse::<u64>().ok();
+                
+                if let Some(existing) = processes.iter_mut().find(|p| p.pid == pid) {
+                    if existing.kind != kind {
+                        existing.kind = GpuProcessKind::Both;
+                    }
+                    existing.used_memory_mb = existing.used_memory_mb.max(used_memory_mb);
+                    continue;
+                }
+                
+                let process_name = process_name.trim();
+                let name = fs::read_to_string(format!("/proc/{}/comm", pid))
+                    .map(|comm| comm.trim().to_string())
+                    .unwrap_or_else(|_| Path::new(process_name).file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_else(|| process_name.to_string()));
+                let command = fs::read(format!("/proc/{}/cmdline", pid)).ok()
+                    .map(|cmdline| String::from_utf8_lossy(&cmdline).split('\0').filter(|a| !a.is_empty()).collect::<Vec<_>>().join(" "))
+                    .filter(|command| !command.is_empty())
+                    .unwrap_or_else(|| process_name.to_string());
+                
+                processes.push(GpuProcess { pid, name, command, used_memory_mb, kind });
+            }
+        }
+        
+        processes.sort_by(|a, b| b.used_memory_mb.cmp(&a.used_memory_mb));
+        processes
+    }
+    
+    /// Per-core scaling_governor paths for the CPUs that actually expose cpufreq.
+    pub(crate) fn cpu_governor_paths() -> Vec<(usize, PathBuf)> {
+        let mut paths = Vec::new();
+        
+        if let Ok(entries) = fs::read_dir("/sys/devices/system/cpu") {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(Ok(cpu_id)) = name.strip_prefix("cpu").map(|n| n.parse::<usize>()) {
+                    let path = entry.path().join("cpufreq/scaling_governor");
+                    if path.exists() {
+                        paths.push((cpu_id, path));
+                    }
+                }
+            }
+        }
+        
+        paths.sort_by_key(|(cpu_id, _)| *cpu_id);
+        paths
+    }
+    
     pub fn set_cpu_governor(governor: &str) -> Result<String> {
-        let mut results = Vec::new();
+        let available = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors")
+            .map_err(|e| anyhow!("Failed to read available CPU governors: {}", e))?;
+        let available: Vec<&str> = available.split_whitespace().collect();
+        if !available.contains(&governor) {
+            return Err(anyhow!("CPU governor '{}' not supported. Valid options: {}", 
+                governor, available.join(", ")));
+        }
         
-        for i in 0..num_cpus::get() {
-            let path = format!("/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor", i);
-            match fs::write(&path, governor) {
+        let paths = Self::cpu_governor_paths();
+        let mut batch = privilege::PrivilegedBatch::new();
+        for (_, path) in &paths {
+            batch.write(path, governor);
+        }
+        
+        let mut results = Vec::new();
+        for ((i, _), result) in paths.iter().zip(batch.apply()) {
+            match result {
                 Ok(_) => results.push(format!("CPU{}: {}", i, governor)),
                 Err(e) => warn!("Failed to set governor for CPU{}: {}", i, e),
             }
@@ -485,26 +1010,321 @@ impl HardwareController {
         }
     }
     
-    pub fn control_fan_speed(speed_percent: u8) -> Result<String> {
-        let speed_value = (speed_percent as f64 / 100.0 * 255.0) as u8;
+    /// Set every NVIDIA GPU's power limit (`nvidia-smi -pl`) to `watts`, or to its
+    /// minimum for None. Values below a GPU's minimum are raised to it.
+    pub fn set_gpu_power_limit(watts: Option<f64>) -> Result<String> {
+        if let Some(watts) = watts {
+            if !watts.is_finite() || watts <= 0.0 {
+                return Err(anyhow!("Invalid GPU power limit {} W", watts));
+            }
+        }
+        
+        let gpus = watchdog::nvidia_power_limits();
+        if gpus.is_empty() {
+            return Err(anyhow!("No NVIDIA GPU with an adjustable power limit"));
+        }
+        
+        let mut batch = privilege::PrivilegedBatch::new();
+        let mut limits = Vec::new();
+        for (index, _, min_limit) in &gpus {
+            let min_limit: f64 = min_limit.parse().unwrap_or(0.0);
+            let limit = format!("{:.0}", watts.unwrap_or(min_limit).max(min_limit));
+            batch.run("nvidia-smi", &["-i", index, "-pl", &limit]);
+            limits.push((index, limit));
+        }
+        
         let mut results = Vec::new();
+        for ((index, limit), result) in limits.iter().zip(batch.apply()) {
+            match result {
+                Ok(_) => results.push(format!("GPU{}: {} W", index, limit)),
+                Err(e) => warn!("Failed to set power limit for GPU{}: {}", index, e),
+            }
+        }
         
-        for entry in WalkDir::new("/sys/class/hwmon").max_depth(3) {
-            if let Ok(entry) = entry {
-                let file_name = entry.file_name().to_string_lossy();
-                if file_name.starts_with("pwm") && file_name.ends_with("_enable") {
-                    match fs::write(entry.path(), speed_value.to_string()) {
-                        Ok(_) => results.push(format!("{}={}", file_name, speed_percent)),
-                        Err(e) => warn!("Failed to control fan {}: {}", file_name, e),
+        if results.is_empty() {
+            Err(anyhow!("Failed to set GPU power limit"))
+        } else {
+            Ok(format!("Set GPU power limit: {}", results.join(", ")))
+        }
+    }
+    
+    /// Set every PWM channel to `speed_percent` and check, after FAN_SETTLE_TIME,
+    /// whether each fan's RPM actually followed. Blocks for the settle time.
+    /// Nothing below full speed is accepted while the safety watchdog is throttling.
+    pub fn control_fan_speed(speed_percent: u8) -> Result<Vec<FanControlResult>> {
+        let speed_percent = speed_percent.min(100);
+        if speed_percent < 100 && watchdog::is_throttling() {
+            return Err(anyhow!("The safety watchdog is throttling an overheating system; fans stay at full speed"));
+        }
+        let pwm_value = (speed_percent as f64 / 100.0 * 255.0).round() as u32;
+        
+        let channels = Self::find_pwm_channels(Path::new(sensors::HWMON_DIR));
+        if channels.is_empty() {
+            return Err(anyhow!("No controllable fans found"));
+        }
+        
+        // A running curve would overwrite the duty cycle within seconds
+        Self::stop_fan_curve()?;
+        
+        // Readings from before the change, then every channel in one privileged batch
+        let before: Vec<(Option<u32>, Option<u32>)> = channels.iter()
+            .map(|(pwm_path, _)| {
+                let rpm_before = Self::fan_input_for(pwm_path).as_deref().and_then(Self::read_sysfs_u32);
+                (rpm_before, Self::read_sysfs_u32(pwm_path))
+            })
+            .collect();
+        let mut batch = privilege::PrivilegedBatch::new();
+        for (pwm_path, enable_path) in &channels {
+            // Manual mode first, otherwise the firmware ignores the duty cycle
+            batch.write(enable_path, "1").write(pwm_path, pwm_value.to_string());
+        }
+        let mut outcomes = batch.apply().into_iter();
+        
+        let mut results = Vec::new();
+        let mut written = Vec::new();
+        for ((pwm_path, _), (rpm_before, pwm_before)) in channels.into_iter().zip(before) {
+            let name = Self::fan_channel_name(&pwm_path);
+            let rpm_path = Self::fan_input_for(&pwm_path);
+            let enable = outcomes.next().unwrap_or(Ok(()));
+            let pwm = outcomes.next().unwrap_or(Ok(()));
+            if let Err(e) = enable.and(pwm) {
+                warn!("Failed to control fan {}: {}", name, e);
+                results.push(FanControlResult {
+                    name,
+                    requested_pct: speed_percent,
+                    measured_rpm_before: rpm_before,
+                    measured_rpm_after: None,
+                    responded: false,
+                });
+                continue;
+            }
+            written.push((name, pwm_path, rpm_path, rpm_before, pwm_before));
+        }
+        
+        if !written.is_empty() {
+            thread::sleep(FAN_SETTLE_TIME);
+        }
+        
+        for (name, pwm_path, rpm_path, rpm_before, pwm_before) in written {
+            let rpm_after = rpm_path.as_deref().and_then(Self::read_sysfs_u32);
+            
+            // Locked firmware fan control puts its own value straight back
+            let held = Self::read_sysfs_u32(&pwm_path)
+                .map(|pwm| pwm.abs_diff(pwm_value) <= 2)
+                .unwrap_or(false);
+            let followed = match (rpm_before, rpm_after, pwm_before) {
+                (Some(before), Some(after), Some(old_pwm)) if pwm_value > old_pwm + 2 => after >= before + FAN_RPM_TOLERANCE,
+                (Some(before), Some(after), Some(old_pwm)) if pwm_value + 2 < old_pwm => after + FAN_RPM_TOLERANCE <= before,
+                // Same duty cycle as before, so no change is expected
+                (Some(_), Some(_), Some(_)) => true,
+                // Without a tachometer there is nothing to confirm
+                _ => false,
+            };
+            
+            let responded = held && followed;
+            if !responded {
+                warn!("⚠️ Fan {} did not respond to {}% ({:?} -> {:?} RPM)", name, speed_percent, rpm_before, rpm_after);
+            }
+            results.push(FanControlResult {
+                name,
+                requested_pct: speed_percent,
+                measured_rpm_before: rpm_before,
+                measured_rpm_after: rpm_after,
+                responded,
+            });
+        }
+        
+        info!("🌪️ Fan speed {}%: {} of {} fans responded", speed_percent,
+              results.iter().filter(|r| r.responded).count(), results.len());
+        Ok(results)
+    }
+    
+    /// fanN_input next to pwmN, if the channel has a tachometer
+    fn fan_input_for(pwm_path: &Path) -> Option<PathBuf> {
+        let channel = pwm_path.file_name()?.to_str()?.strip_prefix("pwm")?;
+        let rpm_path = pwm_path.with_file_name(format!("fan{}_input", channel));
+        rpm_path.exists().then_some(rpm_path)
+    }
+    
+    /// "<hwmon driver>/pwmN", e.g. "nct6775/pwm2"
+    fn fan_channel_name(pwm_path: &Path) -> String {
+        let channel = pwm_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        match pwm_path.parent().and_then(|dir| fs::read_to_string(dir.join("name")).ok()) {
+            Some(driver) => format!("{}/{}", driver.trim(), channel),
+            None => channel,
+        }
+    }
+    
+    fn read_sysfs_u32(path: &Path) -> Option<u32> {
+        fs::read_to_string(path).ok().and_then(|v| v.trim().parse::<u32>().ok())
+    }
+    
+    /// Returns (pwm, pwm_enable) path pairs for every PWM channel under
+    /// `hwmon_dir`, normally sensors::HWMON_DIR. The hwmonN entries there are
+    /// symlinks, so each is read through rather than walked.
+    pub(crate) fn find_pwm_channels(hwmon_dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+        let mut channels = Vec::new();
+        
+        let chips = match fs::read_dir(hwmon_dir) {
+            Ok(chips) => chips,
+            Err(_) => return channels,
+        };
+        for chip in chips.flatten() {
+            let entries = match fs::read_dir(chip.path()) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let is_pwm = file_name.strip_prefix("pwm")
+                    .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+                    .unwrap_or(false);
+                if is_pwm {
+                    let enable_path = entry.path().with_file_name(format!("{}_enable", file_name));
+                    if enable_path.exists() {
+                        channels.push((entry.path(), enable_path));
                     }
                 }
             }
         }
         
-        if results.is_empty() {
-            Err(anyhow!("No controllable fans found"))
+        channels.sort();
+        channels
+    }
+    
+    pub fn apply_fan_curve(curve: &FanCurve) -> Result<String> {
+        curve.validate()?;
+        
+        let channels = Self::find_pwm_channels(Path::new(sensors::HWMON_DIR));
+        if channels.is_empty() {
+            return Err(anyhow!("No controllable fans found"));
+        }
+        
+        // Replace any curve that is already running
+        Self::stop_fan_curve()?;
+        
+        // Switch every channel to manual control, remembering the previous mode
+        let mut controlled = Vec::new();
+        for (pwm_path, enable_path) in channels {
+            let previous_mode = fs::read_to_string(&enable_path)
+                .map(|m| m.trim().to_string())
+                .unwrap_or_else(|_| "2".to_string());
+            match fs::write(&enable_path, "1") {
+                Ok(_) => controlled.push((pwm_path, enable_path, previous_mode)),
+                Err(e) => warn!("Failed to enable manual control for {}: {}", enable_path.display(), e),
+            }
+        }
+        
+        if controlled.is_empty() {
+            return Err(anyhow!("Failed to take manual control of any fan"));
+        }
+        
+        let channel_count = controlled.len();
+        let curve = curve.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        
+        let handle = thread::spawn(move || {
+            while !stop_flag.load(Ordering::SeqCst) {
+                let cpu_temp = HardwareController::get_hardware_status()
+                    .map(|status| status.cpu_temps.iter().cloned().fold(f64::NAN, f64::max))
+                    .unwrap_or(f64::NAN);
+                
+                if cpu_temp.is_nan() {
+                    warn!("Fan curve could not read CPU temperature");
+                } else {
+                    // The watchdog is cooling down an overheating system; don't undercut it
+                    let speed_percent = if watchdog::is_throttling() { 100 } else { curve.pwm_for_temperature(cpu_temp) };
+                    let pwm_value = (speed_percent as f64 / 100.0 * 255.0).round() as u8;
+                    for (pwm_path, _, _) in &controlled {
+                        if let Err(e) = fs::write(pwm_path, pwm_value.to_string()) {
+                            warn!("Failed to write {}: {}", pwm_path.display(), e);
+                        }
+                    }
+                    debug!("Fan curve: {:.1}°C -> {}%", cpu_temp, speed_percent);
+                }
+                
+                thread::park_timeout(FAN_CURVE_INTERVAL);
+            }
+            
+            // Hand the fans back to the firmware
+            for (_, enable_path, previous_mode) in &controlled {
+                let mode = if previous_mode == "1" { "2" } else { previous_mode.as_str() };
+                if let Err(e) = fs::write(enable_path, mode) {
+                    error!("Failed to restore automatic fan control for {}: {}", enable_path.display(), e);
+                }
+            }
+        });
+        
+        *FAN_CURVE_TASK.lock().unwrap() = Some(FanCurveTask { stop, handle });
+        
+        info!("Fan curve applied to {} PWM channels", channel_count);
+        Ok(format!("Fan curve active on {} fans", channel_count))
+    }
+    
+    /// Stop any curve and hand every PWM channel back to the firmware
+    /// (pwmN_enable = 2). Returns how many channels were switched.
+    pub fn set_fans_automatic() -> Result<usize> {
+        Self::stop_fan_curve()?;
+        
+        let channels = Self::find_pwm_channels(Path::new(sensors::HWMON_DIR));
+        if channels.is_empty() {
+            return Err(anyhow!("No controllable fans found"));
+        }
+        
+        let mut batch = privilege::PrivilegedBatch::new();
+        for (_, enable_path) in &channels {
+            batch.write(enable_path, "2");
+        }
+        let mut restored = 0;
+        for ((pwm_path, _), result) in channels.iter().zip(batch.apply()) {
+            match result {
+                Ok(_) => restored += 1,
+                Err(e) => warn!("Failed to return fan {} to automatic control: {}", Self::fan_channel_name(pwm_path), e),
+            }
+        }
+        
+        if restored == 0 {
+            return Err(anyhow!("Failed to return any fan to automatic control"));
+        }
+        info!("{} fans back on automatic control", restored);
+        Ok(restored)
+    }
+    
+    /// Powersave governor and every fan at full speed. Either half working is
+    /// enough; picking a profile afterwards puts the governor back.
+    pub fn emergency_cooling() -> Result<String> {
+        warn!("🚨 Emergency cooling activated!");
+        let mut applied = Vec::new();
+        
+        match Self::set_cpu_governor("powersave") {
+            Ok(_) => applied.push("powersave governor".to_string()),
+            Err(e) => warn!("Emergency cooling could not set the governor: {}", e),
+        }
+        match Self::control_fan_speed(100) {
+            Ok(results) => applied.push(format!("{} fans at 100%", results.len())),
+            Err(e) => warn!("Emergency cooling could not drive the fans: {}", e),
+        }
+        
+        if applied.is_empty() {
+            Err(anyhow!("Emergency cooling failed: no governor or fan control available"))
         } else {
-            Ok(format!("Fan speeds set: {}", results.join(", ")))
+            Ok(format!("Emergency cooling: {}", applied.join(", ")))
+        }
+    }
+    
+    pub fn stop_fan_curve() -> Result<String> {
+        let task = FAN_CURVE_TASK.lock().unwrap().take();
+        
+        match task {
+            Some(task) => {
+                task.stop.store(true, Ordering::SeqCst);
+                task.handle.thread().unpark();
+                task.handle.join().map_err(|_| anyhow!("Fan curve task panicked"))?;
+                info!("Fan curve stopped, fans returned to automatic control");
+                Ok("Fans returned to automatic control".to_string())
+            }
+            None => Ok("No fan curve running".to_string()),
         }
     }
 }
@@ -645,99 +1465,560 @@ impl BackupManager {
 
 // Note: Tauri commands are now defined in the commands module
 
+// ============================================================================
+// SYSTEM TRAY
+// ============================================================================
+
+/// Tray items and the hardware profile each one switches to
+const TRAY_PROFILES: &[(&str, &str, &str)] = &[
+    ("profile_gaming", "Gaming", "gaming"),
+    ("profile_balanced", "Balanced", "balanced"),
+    ("profile_power_saver", "Power Save", "power_saver"),
+];
+
+fn build_tray_menu() -> SystemTrayMenu {
+    // Status lines are disabled items, retitled by refresh_tray_status
+    let mut menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("cpu_temp".to_string(), "CPU: --").disabled())
+        .add_item(CustomMenuItem::new("active_profile".to_string(), "Profile: --").disabled())
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("dashboard".to_string(), "Open Dashboard"))
+        .add_item(CustomMenuItem::new("optimize".to_string(), "Optimize System"))
+        .add_native_item(SystemTrayMenuItem::Separator);
+    for (id, title, _) in TRAY_PROFILES {
+        menu = menu.add_item(CustomMenuItem::new(id.to_string(), *title));
+    }
+    menu.add_item(CustomMenuItem::new("emergency_cooling".to_string(), "Emergency Cooling"))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("quit".to_string(), "Quit"))
+}
+
+/// Retitle the status lines from the latest sample and the current governor
+async fn refresh_tray_status(tray: &SystemTrayHandle, system_monitor: &Arc<Mutex<SystemMonitor>>) {
+    let temperature = system_monitor.lock().ok()
+        .and_then(|monitor| monitor.latest_metrics())
+        .map(|metrics| metrics.temperature)
+        .filter(|temperature| *temperature > 0.0);
+    let temperature_label = match temperature {
+        Some(temperature) => format!("CPU: {:.0}°C", temperature),
+        None => "CPU: --".to_string(),
+    };
+    let profile = get_active_hardware_profile().await.unwrap_or_else(|_| "unknown".to_string());
+    
+    if let Err(e) = tray.get_item("cpu_temp").set_title(temperature_label) {
+        debug!("Failed to update tray temperature: {}", e);
+    }
+    if let Err(e) = tray.get_item("active_profile").set_title(format!("Profile: {}", profile)) {
+        debug!("Failed to update tray profile: {}", e);
+    }
+}
+
+fn handle_tray_menu_click(app: &tauri::AppHandle, id: &str, system_monitor: Arc<Mutex<SystemMonitor>>, shutdown: &Shutdown) {
+    if let Some((_, _, profile)) = TRAY_PROFILES.iter().find(|(item_id, _, _)| *item_id == id) {
+        let tray = app.tray_handle();
+        tauri::async_runtime::spawn(async move {
+            match set_hardware_profile(profile.to_string()).await {
+                Ok(message) => info!("{}", message),
+                Err(e) => warn!("Tray profile switch to {} failed: {}", profile, e),
+            }
+            refresh_tray_status(&tray, &system_monitor).await;
+        });
+        return;
+    }
+    
+    match id {
+        "dashboard" => {
+            if let Some(window) = app.get_window("main") {
+                window.show().unwrap();
+                window.set_focus().unwrap();
+            }
+        }
+        "optimize" => {
+            tauri::async_runtime::spawn(async {
+                match optimize_system_performance().await {
+                    Ok(message) => info!("{}", message),
+                    Err(e) => warn!("Optimization from the tray failed: {}", e),
+                }
+            });
+        }
+        "emergency_cooling" => {
+            let tray = app.tray_handle();
+            // Blocks while the fans settle
+            tauri::async_runtime::spawn(async move {
+                match tauri::async_runtime::spawn_blocking(HardwareController::emergency_cooling).await {
+                    Ok(Ok(message)) => info!("{}", message),
+                    Ok(Err(e)) => error!("{}", e),
+                    Err(e) => error!("Emergency cooling task failed: {}", e),
+                }
+                refresh_tray_status(&tray, &system_monitor).await;
+            });
+        }
+        "quit" => {
+            shutdown.run();
+            std::process::exit(0);
+        }
+        _ => {}
+    }
+}
+
+// ============================================================================
+// SHUTDOWN
+// ============================================================================
+
+/// Orderly teardown for the tray quit and SIGTERM/SIGINT
+#[derive(Clone)]
+struct Shutdown {
+    ai_engine: Arc<AIEngine>,
+    /// Recommendations applied before this belong to earlier runs and are never reverted
+    started_at: u64,
+}
+
+impl Shutdown {
+    fn new(ai_engine: Arc<AIEngine>) -> Self {
+        Self {
+            ai_engine,
+            started_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        }
+    }
+    
+    /// Save state and put hardware back, giving up after the configured timeout
+    /// so a stuck write can't keep the process alive
+    fn run(&self) {
+        let settings = config::get().shutdown;
+        info!("Shutting down, saving state");
+        
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        let shutdown = self.clone();
+        thread::spawn(move || {
+            shutdown.teardown(settings.revert_applied_changes);
+            let _ = done_tx.send(());
+        });
+        
+        if done_rx.recv_timeout(settings.timeout()).is_err() {
+            warn!("⚠️ Shutdown did not finish within {}s, exiting anyway", settings.timeout().as_secs());
+        }
+    }
+    
+    fn teardown(&self, revert_applied_changes: bool) {
+        // Don't leave the fans in manual mode with nothing driving them
+        if let Err(e) = HardwareController::stop_fan_curve() {
+            warn!("Failed to return fans to automatic control: {}", e);
+        }
+        
+        if let Err(e) = self.ai_engine.save_learning_data() {
+            error!("Failed to save AI learning data: {}", e);
+        }
+        
+        if revert_applied_changes {
+            match self.ai_engine.action_log().undo_applied_since(self.started_at) {
+                Ok(undone) => info!("↩️ Reverted {} recommendations applied this run", undone.len()),
+                Err(e) => error!("Failed to revert applied recommendations: {}", e),
+            }
+        }
+    }
+}
+
+/// Resolves on the first SIGINT or SIGTERM
+async fn wait_for_termination() {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("Failed to install SIGTERM handler");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => info!("Received SIGINT"),
+        _ = terminate.recv() => info!("Received SIGTERM"),
+    }
+}
+
+// ============================================================================
+// HEADLESS DAEMON MODE
+// ============================================================================
+
+/// Address for the REST API: `--serve` alone uses localhost:8080, `--serve <addr>` binds there
+fn parse_serve_addr(args: &[String]) -> Option<std::net::SocketAddr> {
+    let position = args.iter().position(|a| a == "--serve")?;
+    let addr = args.get(position + 1)
+        .filter(|a| !a.starts_with("--"))
+        .map(|a| a.as_str())
+        .unwrap_or(api::DEFAULT_API_ADDR);
+    
+    match addr.parse() {
+        Ok(addr) => Some(addr),
+        Err(e) => {
+            error!("Invalid --serve address {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Run the monitor + AI loop without the Tauri window until SIGTERM/SIGINT
+fn run_daemon(system_monitor: Arc<Mutex<SystemMonitor>>, serve_addr: Option<std::net::SocketAddr>, shutdown: Shutdown) {
+    info!("Running in headless daemon mode");
+    
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start async runtime");
+    runtime.block_on(async move {
+        let dbus_monitor = system_monitor.clone();
+        tokio::spawn(async move {
+            if !config::get().features.dbus_service {
+                return;
+            }
+            // A system service has no session bus
+            if let Err(e) = dbus_service::run_dbus_service(dbus_monitor, dbus_service::Bus::System).await {
+                warn!("D-Bus service stopped: {}", e);
+            }
+        });
+        
+        if let Some(addr) = serve_addr {
+            let state = api::ApiState::new(system_monitor.clone(), addr);
+            tokio::spawn(async move {
+                if let Err(e) = api::serve(state).await {
+                    error!("REST API stopped: {}", e);
+                }
+            });
+        }
+        
+        let termination = wait_for_termination();
+        tokio::pin!(termination);
+        
+        loop {
+            // Re-read every pass so a changed interval applies without a restart
+            tokio::select! {
+                _ = tokio::time::sleep(config::get().monitoring.interval()) => {
+                    collect_sample(system_monitor.clone()).await;
+                }
+                _ = &mut termination => {
+                    info!("Stopping daemon");
+                    break;
+                }
+            }
+        }
+    });
+    
+    shutdown.run();
+}
+
+/// Take one sample on the blocking pool: collect_metrics stores it, runs the AI
+/// analysis and broadcasts both. Waits for the monitor instead of skipping the
+/// tick when a command is holding it.
+async fn collect_sample(system_monitor: Arc<Mutex<SystemMonitor>>) {
+    let result = tokio::task::spawn_blocking(move || match system_monitor.lock() {
+        Ok(mut monitor) => monitor.collect_metrics().map(|_| ()),
+        Err(e) => Err(anyhow!("System monitor lock poisoned: {}", e)),
+    }).await;
+    
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => error!("Background monitoring failed: {}", e),
+        Err(e) => error!("Monitoring task failed: {}", e),
+    }
+}
+
 // ============================================================================
 // APPLICATION MAIN - COMPLETE IMPLEMENTATION
 // ============================================================================
 
 fn main() {
-    // Initialize logging
+    // Before logging starts: the helper's stdout is its reply to the unprivileged process
+    if std::env::args().nth(1).as_deref() == Some(privilege::HELPER_FLAG) {
+        std::process::exit(privilege::run_helper());
+    }
+    
+    // Initialize logging (RUST_LOG overrides, e.g. from the systemd unit)
     tracing_subscriber::fmt()
-        .with_env_filter("lous_garuda_ai_sysadmin=info")
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("lous_garuda_ai_sysadmin=info"))
+        )
         .init();
     
+    let args: Vec<String> = std::env::args().collect();
+    
+    // Service management needs no GUI or monitoring state
+    if args.iter().any(|a| a == "--install-service" || a == "--uninstall-service") {
+        let manager = service::ServiceManager::new();
+        let result = if args.iter().any(|a| a == "--install-service") {
+            service::DaemonConfig::for_current_exe().and_then(|config| manager.install_service(&config))
+        } else {
+            manager.uninstall_service()
+        };
+        if let Err(e) = result {
+            error!("Service management failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    
+    if args.iter().any(|a| a == "--install-polkit-policy" || a == "--uninstall-polkit-policy") {
+        let result = if args.iter().any(|a| a == "--install-polkit-policy") {
+            privilege::install_polkit_policy()
+        } else {
+            privilege::uninstall_polkit_policy()
+        };
+        match result {
+            Ok(message) => info!("{}", message),
+            Err(e) => {
+                error!("Polkit policy management failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    
     info!("Starting Lou's Garuda AI SysAdmin Control Center - Alpha Release");
     
+    // Held for the whole run; dropping it stops config reloads
+    let _config_watcher = match config::Config::watch() {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            warn!("Config file will not be reloaded on change: {}", e);
+            None
+        }
+    };
+    
+    // Independent of everything below, so it keeps running if the AI loop doesn't
+    let _safety_watchdog = watchdog::SafetyWatchdog::start();
+    
     // Initialize core components
-    let ai_engine = Arc::new(AIEngine::new().expect("Failed to initialize AI Engine"));
+    let database = Database::open(database::DEFAULT_DB_PATH).expect("Failed to open database");
+    let ai_engine = Arc::new(AIEngine::new(database.clone()).expect("Failed to initialize AI Engine"));
     let system_monitor = Arc::new(Mutex::new(SystemMonitor::new(ai_engine.clone())));
     
-    // Create system tray
-    let tray_menu = SystemTrayMenu::new()
-        .add_item(CustomMenuItem::new("dashboard".to_string(), "Open Dashboard"))
-        .add_item(CustomMenuItem::new("status".to_string(), "System Status"))
-        .add_item(CustomMenuItem::new("optimize".to_string(), "Optimize System"))
-        .add_item(CustomMenuItem::new("quit".to_string(), "Quit"));
+    let serve_addr = parse_serve_addr(&args);
+    let shutdown = Shutdown::new(ai_engine.clone());
+    
+    if args.iter().any(|a| a == "--daemon") {
+        run_daemon(system_monitor, serve_addr, shutdown);
+        return;
+    }
     
-    let system_tray = SystemTray::new().with_menu(tray_menu);
+    // Natural-language commands: parsed, decided on and executed
+    let custom_actions = ai::custom_actions::ActionRegistry::load(
+        &ai::custom_actions::ActionRegistry::default_dir(),
+        &ai::action_executor::BUILTIN_ACTIONS,
+    );
+    let assistant = tauri::async_runtime::block_on(ai::assistant::Assistant::new(database, custom_actions))
+        .expect("Failed to initialize assistant");
+    let assistant = Arc::new(tokio::sync::Mutex::new(assistant));
     
-    // Start background monitoring
-    let ai_engine_bg = ai_engine.clone();
+    // Create system tray
+    let system_tray = SystemTray::new().with_menu(build_tray_menu());
+    
+    // Start background monitoring on Tauri's runtime; main has none of its own.
+    // The interval is re-read every pass so config changes apply without a restart.
     let monitor_bg = system_monitor.clone();
-    tokio::spawn(async move {
-        let mut interval = interval(Duration::from_secs(30));
+    tauri::async_runtime::spawn(async move {
         loop {
-            interval.tick().await;
-            if let Ok(mut monitor) = monitor_bg.try_lock() {
-                if let Err(e) = monitor.collect_metrics() {
-                    error!("Background monitoring failed: {}", e);
-                }
-            }
+            tokio::time::sleep(config::get().monitoring.interval()).await;
+            collect_sample(monitor_bg.clone()).await;
         }
     });
     
     info!("Launching Tauri application");
     
+    let tray_monitor = system_monitor.clone();
+    let tray_shutdown = shutdown.clone();
+    let exit_shutdown = shutdown.clone();
     tauri::Builder::default()
         .system_tray(system_tray)
-        .on_system_tray_event(|app, event| match event {
-            tauri::SystemTrayEvent::MenuItemClick { id, .. } => {
-                match id.as_str() {
-                    "dashboard" => {
-                        if let Some(window) = app.get_window("main") {
-                            window.show().unwrap();
-                            window.set_focus().unwrap();
-                        }
-                    }
-                    "quit" => {
-                        std::process::exit(0);
-                    }
-                    _ => {}
-                }
+        .on_system_tray_event(move |app, event| {
+            if let tauri::SystemTrayEvent::MenuItemClick { id, .. } = event {
+                handle_tray_menu_click(app, &id, tray_monitor.clone(), &tray_shutdown);
             }
-            _ => {}
         })
-        .manage(system_monitor)
+        .manage(system_monitor.clone())
         .manage(ai_engine)
+        .manage(assistant)
         .invoke_handler(tauri::generate_handler![
             // Monitoring commands (available)
             get_process_list,
             get_network_interfaces,
             get_thermal_zones,
             get_historical_metrics,
+            get_api_session,
+            get_recent_journal,
+            get_runaway_process,
+            get_current_workload,
+            kill_process,
+            renice_process,
+            limit_process,
+            remove_process_limit,
+            get_process_limits,
             // Hardware control commands (available)
             get_hardware_profiles,
             get_active_hardware_profile,
             set_hardware_profile,
             get_fan_status,
+            get_sensor_map,
             set_fan_speed,
+            set_all_fan_speeds,
+            get_gpu_processes,
             get_available_cpu_governors,
             get_current_cpu_governor,
+            get_privilege_status,
+            install_polkit_policy,
             // RGB control commands (available)
             get_rgb_status,
             toggle_rgb,
             set_rgb_color,
             set_rgb_brightness,
+            set_rgb_effect,
+            stop_rgb_effect,
+            list_rgb_devices,
+            set_device_color,
             // AI extended commands (available)
             get_decision_statistics,
             get_performance_trends,
             apply_ai_recommendation,
             dismiss_ai_recommendation,
-            process_natural_language
+            undo_recommendation,
+            get_action_history,
+            process_natural_language,
+            optimize_system_performance,
+            set_fans_automatic,
+            // Package commands
+            upgrade_system
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            tauri::async_runtime::spawn(async move {
+                wait_for_termination().await;
+                tauri::async_runtime::spawn_blocking(move || shutdown.run()).await.ok();
+                std::process::exit(0);
+            });
+            
+            // Tray status follows the monitor's sampling rate
+            let tray = app.tray_handle();
+            let status_monitor = system_monitor.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    refresh_tray_status(&tray, &status_monitor).await;
+                    tokio::time::sleep(config::get().monitoring.interval()).await;
+                }
+            });
+            
+            let dbus_monitor = system_monitor.clone();
+            tauri::async_runtime::spawn(async move {
+                if !config::get().features.dbus_service {
+                    return;
+                }
+                if let Err(e) = dbus_service::run_dbus_service(dbus_monitor, dbus_service::Bus::Session).await {
+                    warn!("D-Bus service stopped: {}", e);
+                }
+            });
+            
+            if let Some(addr) = serve_addr {
+                let state = api::ApiState::new(system_monitor.clone(), addr);
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = api::serve(state).await {
+                        error!("REST API stopped: {}", e);
+                    }
+                });
+            }
+            
             info!("Lou's Garuda AI SysAdmin Control Center initialized successfully");
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("Error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("Error while building tauri application")
+        .run(move |_app, event| {
+            // Closing the last window ends the app without the tray's quit
+            if let tauri::RunEvent::Exit = event {
+                exit_shutdown.run();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn target_pids_must_name_one_process() {
+        // u32::MAX would wrap to -1, which kill(2) sends to every process we can reach
+        assert!(valid_target_pid(u32::MAX, PID_MAX_LIMIT).is_err());
+        assert!(valid_target_pid(i32::MAX as u32 + 1, PID_MAX_LIMIT).is_err());
+        assert!(valid_target_pid(0, PID_MAX_LIMIT).is_err());
+        assert!(valid_target_pid(1, PID_MAX_LIMIT).is_err());
+        assert!(valid_target_pid(std::process::id(), PID_MAX_LIMIT).is_err());
+        assert!(valid_target_pid(32768, 32768).is_err());
+        assert_eq!(valid_target_pid(32767, 32768).unwrap().as_raw(), 32767);
+    }
+    
+    #[test]
+    fn pwm_for_temperature_interpolates_and_handles_empty_curves() {
+        let curve = FanCurve { points: vec![(40.0, 20), (60.0, 40), (80.0, 100)] };
+        assert_eq!(curve.pwm_for_temperature(30.0), 20);
+        assert_eq!(curve.pwm_for_temperature(50.0), 30);
+        assert_eq!(curve.pwm_for_temperature(70.0), 70);
+        assert_eq!(curve.pwm_for_temperature(95.0), 100);
+        
+        let single = FanCurve { points: vec![(50.0, 35)] };
+        assert_eq!(single.pwm_for_temperature(20.0), 35);
+        assert_eq!(single.pwm_for_temperature(90.0), 35);
+        
+        let empty = FanCurve { points: Vec::new() };
+        assert_eq!(empty.pwm_for_temperature(60.0), 100);
+        assert!(empty.validate().is_err());
+    }
+    
+    #[test]
+    fn find_pwm_channels_reads_mock_hwmon() {
+        let root = std::env::temp_dir().join(format!("hwmon_pwm_{}", std::process::id()));
+        let chip = root.join("hwmon3");
+        fs::create_dir_all(&chip).unwrap();
+        fs::create_dir_all(root.join("hwmon0")).unwrap();
+        for (file, value) in [
+            ("name", "nct6775"),
+            ("pwm1", "128"), ("pwm1_enable", "2"),
+            ("pwm2", "255"), ("pwm2_enable", "2"),
+            // No enable file, so not controllable
+            ("pwm3", "0"),
+            ("pwm1_mode", "1"),
+            ("fan1_input", "1200"),
+        ] {
+            fs::write(chip.join(file), value).unwrap();
+        }
+        
+        let channels = HardwareController::find_pwm_channels(&root);
+        let missing = HardwareController::find_pwm_channels(&root.join("absent"));
+        fs::remove_dir_all(&root).unwrap();
+        
+        assert_eq!(channels, vec![
+            (chip.join("pwm1"), chip.join("pwm1_enable")),
+            (chip.join("pwm2"), chip.join("pwm2_enable")),
+        ]);
+        assert!(missing.is_empty());
+    }
+    
+    #[test]
+    fn check_power_source_reports_ac_changes() {
+        let root = std::env::temp_dir().join(format!("power_source_{}", std::process::id()));
+        let power_supply = root.join("power_supply");
+        let online = power_supply.join("AC0").join("online");
+        fs::create_dir_all(online.parent().unwrap()).unwrap();
+        fs::create_dir_all(power_supply.join("BAT0")).unwrap();
+        fs::write(&online, "1").unwrap();
+        fs::write(power_supply.join("BAT0").join("capacity"), "76").unwrap();
+        
+        let database = Database::open(root.join("monitor.db")).unwrap();
+        let mut monitor = SystemMonitor::new(Arc::new(AIEngine::new(database).unwrap()));
+        let mut events = monitor.event_sender().subscribe();
+        
+        // The first reading only sets the starting state
+        monitor.check_power_source(&power_supply);
+        let initial = events.try_recv();
+        
+        fs::write(&online, "0").unwrap();
+        monitor.check_power_source(&power_supply);
+        let unplugged = events.try_recv();
+        monitor.check_power_source(&power_supply);
+        let unchanged = events.try_recv();
+        
+        fs::write(&online, "1").unwrap();
+        monitor.check_power_source(&power_supply);
+        let plugged = events.try_recv();
+        fs::remove_dir_all(&root).unwrap();
+        
+        assert!(initial.is_err());
+        assert!(matches!(unplugged, Ok(LiveEvent::PowerSourceChanged { on_ac: false, battery_capacity: Some(76) })));
+        assert!(unchanged.is_err());
+        assert!(matches!(plugged, Ok(LiveEvent::PowerSourceChanged { on_ac: true, battery_capacity: Some(76) })));
+    }
 }
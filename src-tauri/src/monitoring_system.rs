@@ -2,20 +2,35 @@
 // Adapted from OriginPC Control Center and ArchBackupPro monitoring systems
 // Complete implementation with hardware sensor detection and metrics collection
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::env;
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use futures::StreamExt;
+use tokio::task::JoinHandle;
 use tokio::time::interval;
 use tracing::{info, warn, error, debug};
 use sysinfo::{System, SystemExt, CpuExt, DiskExt, NetworkExt, ProcessExt, ComponentExt};
 
-use crate::{SystemMetrics, DiskInfo, FanStatus};
+use crate::{SystemMetrics, DiskInfo, FanStatus, PressureStats};
+use crate::ai::workload_classifier::ProcessSample;
+use crate::config::{self, Config, SensorConfig};
+use crate::hardware::gpu::{self, AmdGpuInfo};
+use crate::logs::{JournalEntry, JournalReader};
+use crate::rapl::PowerMeter;
+use crate::system::ollama::{InferenceStats, OllamaManager};
+
+/// How often `metrics_history` is downsampled
+const COMPACTION_INTERVAL_SECS: u64 = 60;
+
+/// Rate samples kept per network interface, one per metrics collection
+const NETWORK_RATE_HISTORY: usize = 150;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SensorReading {
@@ -36,15 +51,107 @@ pub struct ProcessInfo {
     pub command: String,
 }
 
+impl ProcessSample for ProcessInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn cpu_usage(&self) -> f32 {
+        self.cpu_usage
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkInterface {
     pub name: String,
+    pub kind: InterfaceKind,
+    /// Carries the main routing table's default route, i.e. the primary uplink.
+    /// A VPN tunnelling over it doesn't change this.
+    pub is_default_route: bool,
     pub bytes_received: u64,
     pub bytes_transmitted: u64,
     pub packets_received: u64,
     pub packets_transmitted: u64,
     pub errors_received: u64,
     pub errors_transmitted: u64,
+    /// Latest rate; None until two samples have been taken
+    pub rate: Option<NetworkRateSample>,
+}
+
+/// What sits behind an interface. Only Physical and Wireless count towards the
+/// host totals; the others carry traffic that already crosses one of those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InterfaceKind {
+    Physical,
+    Wireless,
+    Loopback,
+    /// tun/tap, WireGuard and PPP tunnels
+    Vpn,
+    Bridge,
+    /// veth pairs, dummy and other software-only interfaces
+    Virtual,
+}
+
+impl InterfaceKind {
+    /// Classify `name` from /sys/class/net
+    pub fn detect(sys_dir: &Path, name: &str) -> Self {
+        let dir = sys_dir.join("class/net").join(name);
+        // ARPHRD_* from include/uapi/linux/if_arp.h
+        let link_type = fs::read_to_string(dir.join("type")).ok()
+            .and_then(|t| t.trim().parse::<u32>().ok());
+        
+        if name == "lo" || link_type == Some(772) {
+            InterfaceKind::Loopback
+        } else if dir.join("wireless").exists() || dir.join("phy80211").exists() {
+            InterfaceKind::Wireless
+        } else if dir.join("bridge").exists() {
+            InterfaceKind::Bridge
+        } else if dir.join("tun_flags").exists()
+            || matches!(link_type, Some(512) | Some(65534))
+            || ["tun", "tap", "wg", "ppp", "tailscale", "zt"].iter().any(|prefix| name.starts_with(prefix))
+        {
+            // 512 is PPP, 65534 (ARPHRD_NONE) is what tun and WireGuard report
+            InterfaceKind::Vpn
+        } else if dir.join("device").exists() {
+            InterfaceKind::Physical
+        } else {
+            InterfaceKind::Virtual
+        }
+    }
+    
+    pub fn is_uplink(&self) -> bool {
+        matches!(self, InterfaceKind::Physical | InterfaceKind::Wireless)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectionProtocol {
+    Tcp,
+    Udp,
+}
+
+/// One socket from /proc/net/{tcp,tcp6,udp,udp6}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Connection {
+    pub protocol: ConnectionProtocol,
+    pub local_addr: SocketAddr,
+    /// Unspecified (0.0.0.0:0) for listening and unconnected UDP sockets
+    pub remote_addr: SocketAddr,
+    /// Kernel TCP state, e.g. ESTABLISHED or LISTEN; UDP reports ESTABLISHED when connected
+    pub state: String,
+    pub uid: u32,
+    /// None when the socket belongs to another user's process and we aren't root
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+    /// Outbound to a public address on a port outside `expected_remote_ports`
+    pub unexpected_port: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct NetworkRateSample {
+    pub timestamp: u64,
+    pub rx_bytes_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +165,20 @@ pub struct GPUInfo {
     pub fan_speed: f32,
 }
 
+impl From<&AmdGpuInfo> for GPUInfo {
+    fn from(amd_gpu: &AmdGpuInfo) -> Self {
+        Self {
+            name: amd_gpu.name.clone(),
+            utilization: amd_gpu.utilization_percent as f32,
+            memory_used: amd_gpu.vram_used_bytes,
+            memory_total: amd_gpu.vram_total_bytes,
+            temperature: amd_gpu.temperature_celsius.unwrap_or(0.0) as f32,
+            power_draw: amd_gpu.power_watts.unwrap_or(0.0) as f32,
+            fan_speed: amd_gpu.fan_speed_percent.unwrap_or(0.0) as f32,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThermalZone {
     pub name: String,
@@ -66,6 +187,70 @@ pub struct ThermalZone {
     pub sensor_type: String,
 }
 
+/// A threshold crossing, with the journal entries logged around it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorAlert {
+    pub kind: String,
+    pub message: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub timestamp: u64,
+    pub journal_context: Vec<JournalEntry>,
+}
+
+impl MonitorAlert {
+    /// Journal lines formatted for display, most recent last
+    pub fn log_lines(&self, limit: usize) -> Vec<String> {
+        let skip = self.journal_context.len().saturating_sub(limit);
+        self.journal_context[skip..].iter()
+            .map(|entry| entry.display_line())
+            .collect()
+    }
+}
+
+/// A kernel OOM-killer kill and the host memory state when it happened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OomEvent {
+    pub timestamp: u64,
+    pub pid: u32,
+    pub process_name: String,
+    pub total_vm_kb: u64,
+    pub anon_rss_kb: u64,
+    /// Host memory usage (%) from the closest metrics sample, if one was taken
+    pub memory_usage: Option<f64>,
+    /// Killed inside a memory cgroup limit rather than for the whole system
+    pub cgroup_limited: bool,
+}
+
+/// Which process to watch: every process with this name, or one pid
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProcessSelector {
+    Name(String),
+    Pid(u32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessSample {
+    pub timestamp: u64,
+    /// Oldest matching process; None while it isn't running
+    pub pid: Option<u32>,
+    /// Matching processes (e.g. ollama plus its runners)
+    pub process_count: usize,
+    /// Summed over all matching processes
+    pub cpu_usage: f32,
+    pub rss_bytes: u64,
+    /// The pid changed since the previous sample, i.e. it was restarted
+    pub restarted: bool,
+    /// The kernel OOM killer took the process since the previous sample
+    pub oom_killed: bool,
+}
+
+#[derive(Debug, Clone)]
+struct WatchedProcess {
+    samples: VecDeque<ProcessSample>,
+    last_pid: Option<u32>,
+}
+
 pub struct SystemMonitor {
     pub system: System,
     pub monitoring_active: bool,
@@ -73,19 +258,51 @@ pub struct SystemMonitor {
     
     // Historical data storage
     pub data_dir: PathBuf,
+    /// Oldest first; raw samples, then 1-minute and hourly averages as they age
     pub metrics_history: Vec<SystemMetrics>,
-    pub max_history_size: usize,
+    pub raw_retention: Duration,
+    pub minute_retention: Duration,
+    pub hourly_retention: Duration,
+    last_compaction: u64,
     
     // Sensor configurations
     pub temperature_sensors: HashMap<String, PathBuf>,
     pub fan_sensors: HashMap<String, PathBuf>,
     pub power_sensors: HashMap<String, PathBuf>,
+    cpu_power: PowerMeter,
+    pub sensor_config: SensorConfig,
+    /// Temperature and fan sensors not yet seen to change: (first value, samples read)
+    sensor_probation: HashMap<String, (f64, u32)>,
     
     // Performance counters
     pub last_network_stats: HashMap<String, (u64, u64)>,
+    last_network_sample_at: Option<Instant>,
+    /// Oldest first, at most NETWORK_RATE_HISTORY per interface
+    network_rates: HashMap<String, VecDeque<NetworkRateSample>>,
     pub last_disk_stats: HashMap<String, (u64, u64)>,
     pub performance_baseline: Option<SystemMetrics>,
     
+    // Alerts and their journal context
+    pub journal: JournalReader,
+    pub alert_thresholds: HashMap<String, f64>,
+    pub alert_cooldown: Duration,
+    pub journal_window_secs: u64,
+    pub recent_alerts: Vec<MonitorAlert>,
+    pub max_alerts: usize,
+    last_alert_times: HashMap<String, u64>,
+    
+    // OOM-killer events seen through /proc/vmstat
+    pub oom_events: Vec<OomEvent>,
+    last_oom_kill_count: Option<u64>,
+    
+    // Per-process history for watched processes
+    pub process_history_size: usize,
+    watched_processes: HashMap<ProcessSelector, WatchedProcess>,
+    
+    // Latest Ollama tokens/sec probe, filled in by a background task
+    latest_inference: Arc<Mutex<Option<InferenceStats>>>,
+    inference_monitor: Option<JoinHandle<()>>,
+    
     // Working directories
     pub work_dir: PathBuf,
     pub sys_dir: PathBuf,
@@ -116,17 +333,39 @@ impl SystemMonitor {
             update_interval: Duration::from_secs(2),
             data_dir,
             metrics_history: Vec::new(),
-            max_history_size: 1000,
+            raw_retention: Duration::from_secs(3600),
+            minute_retention: Duration::from_secs(86400),
+            hourly_retention: Duration::from_secs(30 * 86400),
+            last_compaction: 0,
             temperature_sensors: HashMap::new(),
             fan_sensors: HashMap::new(),
             power_sensors: HashMap::new(),
+            cpu_power: PowerMeter::new(),
+            sensor_config: SensorConfig::default(),
+            sensor_probation: HashMap::new(),
             last_network_stats: HashMap::new(),
+            last_network_sample_at: None,
+            network_rates: HashMap::new(),
             last_disk_stats: HashMap::new(),
             performance_baseline: None,
+            journal: JournalReader::new(),
+            alert_thresholds: HashMap::new(),
+            alert_cooldown: Duration::from_secs(300),
+            journal_window_secs: 60,
+            recent_alerts: Vec::new(),
+            max_alerts: 100,
+            last_alert_times: HashMap::new(),
+            oom_events: Vec::new(),
+            last_oom_kill_count: None,
+            process_history_size: 720,
+            watched_processes: HashMap::new(),
+            latest_inference: Arc::new(Mutex::new(None)),
+            inference_monitor: None,
             work_dir,
             sys_dir,
             proc_dir,
         };
+        monitor.apply_config(&config::get());
         
         // Detect available sensors
         monitor.detect_temperature_sensors().await?;
@@ -149,9 +388,45 @@ impl SystemMonitor {
         self.performance_baseline = Some(baseline);
         
         info!("📈 Performance baseline established");
+        
+        self.start_inference_monitoring().await;
         Ok(())
     }
     
+    /// Probe Ollama's generation speed in the background when `ollama.monitor_model` is set
+    async fn start_inference_monitoring(&mut self) {
+        let settings = config::get().ollama;
+        let model = match settings.monitor_model {
+            Some(model) => model,
+            None => return,
+        };
+        
+        let manager = match OllamaManager::new().await {
+            Ok(manager) => manager,
+            Err(e) => {
+                warn!("Ollama inference monitoring unavailable: {}", e);
+                return;
+            }
+        };
+        
+        if let Some(previous) = self.inference_monitor.take() {
+            previous.abort();
+        }
+        info!("🧠 Monitoring Ollama inference speed on {}", model);
+        let latest = Arc::clone(&self.latest_inference);
+        let mut probes = Box::pin(manager.monitor_inference(&model, settings.monitor_interval()));
+        self.inference_monitor = Some(tokio::spawn(async move {
+            while let Some(stats) = probes.next().await {
+                *latest.lock().unwrap() = Some(stats);
+            }
+        }));
+    }
+    
+    /// Most recent Ollama probe, if inference monitoring is running and has succeeded once
+    pub fn get_latest_inference(&self) -> Option<InferenceStats> {
+        self.latest_inference.lock().unwrap().clone()
+    }
+    
     pub async fn get_comprehensive_metrics(&mut self) -> Result<SystemMetrics> {
         if !self.monitoring_active {
             return Err(anyhow!("Monitoring not active"));
@@ -161,8 +436,8 @@ impl SystemMonitor {
         
         // Store in history
         self.metrics_history.push(metrics.clone());
-        if self.metrics_history.len() > self.max_history_size {
-            self.metrics_history.remove(0);
+        if metrics.timestamp >= self.last_compaction + COMPACTION_INTERVAL_SECS {
+            self.compact_history();
         }
         
         // Save periodic snapshots
@@ -170,9 +445,221 @@ impl SystemMonitor {
             self.save_metrics_snapshot(&metrics).await?;
         }
         
+        self.check_alerts(&metrics);
+        self.check_oom_kills(&metrics);
+        self.sample_watched_processes(metrics.timestamp);
+        
         Ok(metrics)
     }
     
+    /// Raise alerts for threshold crossings and capture the journal around each one
+    /// Take alert thresholds and cooldown from the config
+    pub fn apply_config(&mut self, config: &Config) {
+        self.alert_thresholds = HashMap::from([
+            ("cpu_temperature".to_string(), config.thresholds.cpu_temperature_alert),
+            ("gpu_temperature".to_string(), config.thresholds.gpu_temperature_alert),
+            ("memory".to_string(), config.thresholds.memory_alert),
+        ]);
+        self.alert_cooldown = Duration::from_secs(config.monitoring.alert_cooldown_secs);
+        
+        let monitoring = &config.monitoring;
+        self.raw_retention = Duration::from_secs(monitoring.raw_retention_secs);
+        self.minute_retention = Duration::from_secs(monitoring.minute_retention_secs.max(monitoring.raw_retention_secs));
+        self.hourly_retention = Duration::from_secs((monitoring.hourly_retention_days * 86400).max(monitoring.minute_retention_secs));
+        
+        self.sensor_config = config.sensors.clone();
+    }
+    
+    /// Downsample `metrics_history`: raw samples within `raw_retention`, 1-minute
+    /// averages up to `minute_retention`, hourly averages up to `hourly_retention`,
+    /// nothing older. Tier boundaries are aligned to whole minutes and hours, so a
+    /// bucket is only ever averaged once and rerunning this changes nothing.
+    pub fn compact_history(&mut self) {
+        let now = match self.metrics_history.last() {
+            Some(latest) => latest.timestamp,
+            None => return,
+        };
+        let before = self.metrics_history.len();
+        
+        let raw_cutoff = now.saturating_sub(self.raw_retention.as_secs()) / 60 * 60;
+        let minute_cutoff = now.saturating_sub(self.minute_retention.as_secs()) / 3600 * 3600;
+        let oldest = now.saturating_sub(self.hourly_retention.as_secs());
+        
+        let mut compacted: Vec<SystemMetrics> = Vec::with_capacity(before);
+        let mut bucket: Vec<SystemMetrics> = Vec::new();
+        let mut bucket_start = 0;
+        for metrics in self.metrics_history.drain(..) {
+            if metrics.timestamp < oldest {
+                continue;
+            }
+            
+            let bucket_secs = if metrics.timestamp < minute_cutoff {
+                3600
+            } else if metrics.timestamp < raw_cutoff {
+                60
+            } else {
+                0
+            };
+            let start = if bucket_secs > 0 { metrics.timestamp / bucket_secs * bucket_secs } else { metrics.timestamp };
+            
+            if !bucket.is_empty() && (bucket_secs == 0 || start != bucket_start) {
+                compacted.extend(average_metrics(&bucket, bucket_start));
+                bucket.clear();
+            }
+            if bucket_secs == 0 {
+                compacted.push(metrics);
+            } else {
+                bucket_start = start;
+                bucket.push(metrics);
+            }
+        }
+        compacted.extend(average_metrics(&bucket, bucket_start));
+        
+        self.metrics_history = compacted;
+        self.last_compaction = now;
+        if self.metrics_history.len() != before {
+            debug!("🗜️ Compacted metrics history from {} to {} samples", before, self.metrics_history.len());
+        }
+    }
+    
+    fn check_alerts(&mut self, metrics: &SystemMetrics) {
+        // Picked up on every check so edits to the config file apply live
+        self.apply_config(&config::get());
+        
+        let readings = [
+            ("cpu_temperature", metrics.cpu_temp as f64, "CPU temperature"),
+            ("gpu_temperature", metrics.gpu_temp as f64, "GPU temperature"),
+            ("memory", metrics.memory_usage, "Memory usage"),
+        ];
+        
+        for (kind, value, label) in readings {
+            let threshold = match self.alert_thresholds.get(kind) {
+                Some(threshold) => *threshold,
+                None => continue,
+            };
+            if value < threshold {
+                continue;
+            }
+            
+            // One alert per kind per cooldown, so a sustained condition doesn't flood the journal reader
+            if let Some(last) = self.last_alert_times.get(kind) {
+                if metrics.timestamp.saturating_sub(*last) < self.alert_cooldown.as_secs() {
+                    continue;
+                }
+            }
+            self.last_alert_times.insert(kind.to_string(), metrics.timestamp);
+            
+            // Warnings and worse only; info-level noise hides the useful lines
+            let journal_context = self.journal.get_journal_around(metrics.timestamp, self.journal_window_secs, 4);
+            let alert = MonitorAlert {
+                kind: kind.to_string(),
+                message: format!("{} at {:.1} exceeds {:.1}", label, value, threshold),
+                value,
+                threshold,
+                timestamp: metrics.timestamp,
+                journal_context,
+            };
+            
+            self.push_alert(alert);
+        }
+    }
+    
+    fn push_alert(&mut self, alert: MonitorAlert) {
+        warn!("🚨 {} ({} journal entries captured)", alert.message, alert.journal_context.len());
+        self.recent_alerts.push(alert);
+        if self.recent_alerts.len() > self.max_alerts {
+            self.recent_alerts.remove(0);
+        }
+    }
+    
+    /// The oom_kill counter in /proc/vmstat is cheap to poll; the journal is only
+    /// read for details once it moves
+    fn check_oom_kills(&mut self, metrics: &SystemMetrics) {
+        let count = match read_vmstat_counter("oom_kill") {
+            Some(count) => count,
+            None => return,
+        };
+        let previous = self.last_oom_kill_count.replace(count);
+        let new_kills = match previous {
+            Some(previous) if count > previous => count - previous,
+            _ => return,
+        };
+        
+        let since = self.update_interval.as_secs().max(60) * 2;
+        let known: Vec<(u64, u32)> = self.oom_events.iter().map(|e| (e.timestamp, e.pid)).collect();
+        let mut events: Vec<OomEvent> = self.get_oom_events(since).into_iter()
+            .filter(|event| !known.contains(&(event.timestamp, event.pid)))
+            .collect();
+        
+        if events.is_empty() {
+            // Kernel log not readable; still report that something was killed
+            events.push(OomEvent {
+                timestamp: metrics.timestamp,
+                pid: 0,
+                process_name: "unknown".to_string(),
+                total_vm_kb: 0,
+                anon_rss_kb: 0,
+                memory_usage: Some(metrics.memory_usage),
+                cgroup_limited: false,
+            });
+        }
+        
+        for event in events {
+            let journal_context = self.journal.get_journal_around(event.timestamp, self.journal_window_secs, 4);
+            self.push_alert(MonitorAlert {
+                kind: "oom_kill".to_string(),
+                message: format!(
+                    "OOM killer killed {} (pid {}, {} MB RSS) at {:.1}% memory ({} kill(s) since last poll)",
+                    event.process_name, event.pid, event.anon_rss_kb / 1024,
+                    event.memory_usage.unwrap_or(metrics.memory_usage), new_kills
+                ),
+                value: event.anon_rss_kb as f64,
+                threshold: 0.0,
+                timestamp: event.timestamp,
+                journal_context,
+            });
+            
+            self.oom_events.push(event);
+            if self.oom_events.len() > self.max_alerts {
+                self.oom_events.remove(0);
+            }
+        }
+    }
+    
+    /// OOM-killer kills from the kernel log in the last `since_secs` seconds
+    pub fn get_oom_events(&self, since_secs: u64) -> Vec<OomEvent> {
+        self.journal.get_recent_journal(3, since_secs)
+            .iter()
+            .filter_map(|entry| {
+                let mut event = parse_oom_kill(&entry.message)?;
+                event.timestamp = entry.timestamp;
+                // Closest sample at or before the kill
+                event.memory_usage = self.metrics_history.iter().rev()
+                    .find(|m| m.timestamp <= entry.timestamp)
+                    .map(|m| m.memory_usage);
+                Some(event)
+            })
+            .collect()
+    }
+    
+    pub fn get_recent_alerts(&self, limit: usize) -> Vec<MonitorAlert> {
+        let start = self.recent_alerts.len().saturating_sub(limit);
+        self.recent_alerts[start..].to_vec()
+    }
+    
+    /// Log lines from the latest alert of `kind`, or recent journal warnings if none fired
+    pub fn relevant_log_lines(&self, kind: &str, limit: usize) -> Vec<String> {
+        if let Some(alert) = self.recent_alerts.iter().rev().find(|alert| alert.kind == kind) {
+            return alert.log_lines(limit);
+        }
+        
+        let entries = self.journal.get_recent_journal(4, self.journal_window_secs * 5);
+        let skip = entries.len().saturating_sub(limit);
+        entries[skip..].iter()
+            .map(|entry| entry.display_line())
+            .collect()
+    }
+    
     async fn collect_comprehensive_metrics(&mut self) -> Result<SystemMetrics> {
         // Refresh system information
         self.system.refresh_all();
@@ -204,6 +691,9 @@ impl SystemMonitor {
         
         // Temperature sensors
         let temperatures = self.get_all_temperatures().await;
+        if !self.sensor_probation.is_empty() {
+            self.prune_dead_sensors(&fan_speeds, &temperatures);
+        }
         
         // System load
         let load_avg = self.system.load_average();
@@ -215,6 +705,9 @@ impl SystemMonitor {
         // Power profile detection
         let power_profile = self.detect_power_profile().await;
         
+        let pressure = self.read_pressure();
+        let cpu_power_watts = self.cpu_power.read_watts();
+        
         Ok(SystemMetrics {
             cpu_usage,
             cpu_temp,
@@ -234,9 +727,17 @@ impl SystemMonitor {
             system_load,
             uptime,
             timestamp,
+            pressure,
+            cpu_power_watts,
         })
     }
     
+    /// PSI for cpu, memory and io; unlike percent used, memory stalls tell a full
+    /// page cache apart from actual thrashing
+    pub fn read_pressure(&self) -> PressureStats {
+        PressureStats::read(&self.proc_dir)
+    }
+    
     async fn detect_temperature_sensors(&mut self) -> Result<()> {
         debug!("🌡️ Detecting temperature sensors");
         
@@ -256,7 +757,10 @@ impl SystemMonitor {
                                 
                                 if filename_str.starts_with("temp") && filename_str.ends_with("_input") {
                                     let sensor_key = format!("{}_{}", sensor_name, filename_str);
-                                    self.temperature_sensors.insert(sensor_key, sensor_entry.path());
+                                    if self.sensor_config.allows(&sensor_key) {
+                                        self.start_probation(&sensor_key);
+                                        self.temperature_sensors.insert(sensor_key, sensor_entry.path());
+                                    }
                                 }
                             }
                         }
@@ -287,7 +791,10 @@ impl SystemMonitor {
                                 
                                 if filename_str.starts_with("fan") && filename_str.ends_with("_input") {
                                     let sensor_key = format!("{}_{}", sensor_name, filename_str);
-                                    self.fan_sensors.insert(sensor_key, sensor_entry.path());
+                                    if self.sensor_config.allows(&sensor_key) {
+                                        self.start_probation(&sensor_key);
+                                        self.fan_sensors.insert(sensor_key, sensor_entry.path());
+                                    }
                                 }
                             }
                         }
@@ -300,6 +807,53 @@ impl SystemMonitor {
         Ok(())
     }
     
+    /// Watch a newly detected sensor for a stuck value, unless it is allowlisted
+    fn start_probation(&mut self, key: &str) {
+        if self.sensor_config.dead_after_samples > 0 && !self.sensor_config.is_included(key) {
+            self.sensor_probation.insert(key.to_string(), (f64::NAN, 0));
+        }
+    }
+    
+    /// Drop sensors that read the same value for their first `dead_after_samples`
+    /// samples, like an ACPI zone stuck at 27.8°C or a fan header with nothing on it
+    fn prune_dead_sensors(&mut self, fan_speeds: &[FanStatus], temperatures: &HashMap<String, f32>) {
+        let dead_after = self.sensor_config.dead_after_samples;
+        let readings = fan_speeds.iter()
+            .map(|fan| (fan.name.as_str(), fan.rpm as f64))
+            .chain(temperatures.iter().map(|(name, temp)| (name.as_str(), *temp as f64)));
+        
+        let mut dead = Vec::new();
+        for (name, value) in readings {
+            let (first, samples) = match self.sensor_probation.get_mut(name) {
+                Some(probation) => probation,
+                None => continue,
+            };
+            if *samples == 0 {
+                *first = value;
+            } else if value != *first {
+                self.sensor_probation.remove(name);
+                continue;
+            }
+            *samples += 1;
+            if *samples >= dead_after {
+                dead.push(name.to_string());
+            }
+        }
+        
+        for name in dead {
+            let value = self.sensor_probation.remove(&name).map(|(first, _)| first).unwrap_or_default();
+            self.temperature_sensors.remove(&name);
+            self.fan_sensors.remove(&name);
+            warn!("🪦 Dropped sensor {}: stuck at {} for {} samples (add it to sensors.include to keep it)", name, value, dead_after);
+        }
+    }
+    
+    /// A hwmon *_input value divided by `scale`, plus the configured offset
+    fn read_calibrated(&self, key: &str, path: &Path, scale: f64) -> Option<f64> {
+        let raw: f64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+        Some(raw / scale + self.sensor_config.offset(key))
+    }
+    
     async fn detect_power_sensors(&mut self) -> Result<()> {
         debug!("⚡ Detecting power sensors");
         
@@ -356,10 +910,8 @@ impl SystemMonitor {
         // Method 2: hwmon sensors
         for (name, path) in &self.temperature_sensors {
             if name.to_lowercase().contains("cpu") || name.to_lowercase().contains("core") {
-                if let Ok(temp_str) = fs::read_to_string(path) {
-                    if let Ok(temp_millic) = temp_str.trim().parse::<i32>() {
-                        return Ok(temp_millic as f32 / 1000.0);
-                    }
+                if let Some(temp) = self.read_calibrated(name, path, 1000.0) {
+                    return Ok(temp as f32);
                 }
             }
         }
@@ -438,56 +990,112 @@ impl SystemMonitor {
             }
         }
         
-        // Try AMD GPU monitoring with rocm-smi
-        let amd_output = tokio::process::Command::new("rocm-smi")
-            .arg("--showuse")
-            .output()
-            .await;
-            
-        if let Ok(output) = amd_output {
-            if output.status.success() {
-                // Parse rocm-smi output - this is simplified
-                return Ok((0.0, 50.0, 1024 * 1024 * 1024)); // Placeholder values
-            }
+        // AMD: rocm-smi when installed, amdgpu sysfs otherwise
+        if let Some(amd_gpu) = gpu::detect_amd_gpus(&self.sys_dir.join("class/drm")).await.first() {
+            return Ok((
+                amd_gpu.utilization_percent as f32,
+                amd_gpu.temperature_celsius.unwrap_or(0.0) as f32,
+                amd_gpu.vram_used_bytes,
+            ));
         }
         
         Ok((0.0, 0.0, 0))
     }
     
+    /// Host traffic totals, and a rate sample for every interface from the
+    /// counter change since the previous call
     fn get_network_metrics(&mut self) -> (u64, u64) {
         let mut total_rx = 0u64;
         let mut total_tx = 0u64;
         
+        let now = Instant::now();
+        let elapsed = self.last_network_sample_at
+            .map(|at| now.duration_since(at).as_secs_f64())
+            .filter(|secs| *secs > 0.0);
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut seen = Vec::new();
+        
         for (interface_name, data) in self.system.networks() {
-            let current_rx = data.received();
-            let current_tx = data.transmitted();
+            let current_rx = data.total_received();
+            let current_tx = data.total_transmitted();
             
-            total_rx += current_rx;
-            total_tx += current_tx;
+            // Tunnel and bridge traffic also crosses a physical interface; don't count it twice
+            if InterfaceKind::detect(&self.sys_dir, interface_name).is_uplink() {
+                total_rx += data.received();
+                total_tx += data.transmitted();
+            }
+            
+            if let (Some(elapsed), Some((last_rx, last_tx))) = (elapsed, self.last_network_stats.get(interface_name.as_str())) {
+                // Counters restart from zero when an interface is re-created, e.g. a VPN reconnecting
+                let sample = NetworkRateSample {
+                    timestamp,
+                    rx_bytes_per_sec: current_rx.saturating_sub(*last_rx) as f64 / elapsed,
+                    tx_bytes_per_sec: current_tx.saturating_sub(*last_tx) as f64 / elapsed,
+                };
+                let history = self.network_rates.entry(interface_name.to_string()).or_default();
+                history.push_back(sample);
+                while history.len() > NETWORK_RATE_HISTORY {
+                    history.pop_front();
+                }
+            }
             
             // Store for rate calculations
             self.last_network_stats.insert(
                 interface_name.to_string(),
                 (current_rx, current_tx)
             );
+            seen.push(interface_name.to_string());
         }
         
+        // Interfaces that went away (VPN down, container stopped) take their history with them
+        self.last_network_stats.retain(|name, _| seen.contains(name));
+        self.network_rates.retain(|name, _| seen.contains(name));
+        self.last_network_sample_at = Some(now);
+        
         (total_rx, total_tx)
     }
     
+    /// Every TCP/UDP socket with its owning process, outbound ones to unexpected ports flagged
+    pub fn get_connections(&self) -> Vec<Connection> {
+        read_connections(&self.proc_dir, &config::get().monitoring.expected_remote_ports)
+    }
+    
+    /// Recent rates for `name`, oldest first. Empty for unknown interfaces.
+    pub fn get_interface_rates(&self, name: &str) -> Vec<NetworkRateSample> {
+        self.network_rates.get(name)
+            .map(|history| history.iter().copied().collect())
+            .unwrap_or_default()
+    }
+    
+    /// Interface of the lowest-metric default route in /proc/net/route
+    pub fn default_route_interface(&self) -> Option<String> {
+        let routes = fs::read_to_string(self.proc_dir.join("net/route")).ok()?;
+        routes.lines()
+            .skip(1)
+            .filter_map(|line| {
+                // Iface Destination Gateway Flags RefCnt Use Metric Mask ...
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+                let metric: u32 = fields.get(6)?.parse().ok()?;
+                // RTF_UP
+                let is_default = fields[1] == "00000000" && fields.get(7) == Some(&"00000000") && flags & 0x1 != 0;
+                is_default.then(|| (metric, fields[0].to_string()))
+            })
+            .min_by_key(|(metric, _)| *metric)
+            .map(|(_, iface)| iface)
+    }
+    
     async fn get_fan_speeds(&self) -> Vec<FanStatus> {
         let mut fan_speeds = Vec::new();
         
         for (name, path) in &self.fan_sensors {
-            if let Ok(rpm_str) = fs::read_to_string(path) {
-                if let Ok(rpm) = rpm_str.trim().parse::<u32>() {
-                    fan_speeds.push(FanStatus {
-                        name: name.clone(),
-                        rpm,
-                        pwm: 128, // Default PWM value
-                        auto: true,
-                    });
-                }
+            if let Some(rpm) = self.read_calibrated(name, path, 1.0) {
+                fan_speeds.push(FanStatus {
+                    name: name.clone(),
+                    rpm: rpm.max(0.0).round() as u32,
+                    pwm: 128, // Default PWM value
+                    auto: true,
+                });
             }
         }
         
@@ -509,11 +1117,8 @@ impl SystemMonitor {
         
         // Read from detected temperature sensors
         for (name, path) in &self.temperature_sensors {
-            if let Ok(temp_str) = fs::read_to_string(path) {
-                if let Ok(temp_millic) = temp_str.trim().parse::<i32>() {
-                    let temp_celsius = temp_millic as f32 / 1000.0;
-                    temperatures.insert(name.clone(), temp_celsius);
-                }
+            if let Some(temp_celsius) = self.read_calibrated(name, path, 1000.0) {
+                temperatures.insert(name.clone(), temp_celsius as f32);
             }
         }
         
@@ -580,21 +1185,111 @@ impl SystemMonitor {
         processes
     }
     
+    /// Record a sample of this process on every poll, even when it isn't in the top list
+    pub fn watch_process(&mut self, selector: ProcessSelector) {
+        info!("👁️ Watching process {:?}", selector);
+        self.watched_processes.entry(selector).or_insert_with(|| WatchedProcess {
+            samples: VecDeque::new(),
+            last_pid: None,
+        });
+    }
+    
+    pub fn unwatch_process(&mut self, selector: &ProcessSelector) {
+        self.watched_processes.remove(selector);
+    }
+    
+    pub fn get_process_history(&self, selector: &ProcessSelector) -> Vec<ProcessSample> {
+        self.watched_processes.get(selector)
+            .map(|watched| watched.samples.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+    
+    fn sample_watched_processes(&mut self, timestamp: u64) {
+        let selectors: Vec<ProcessSelector> = self.watched_processes.keys().cloned().collect();
+        
+        for selector in selectors {
+            // (pid, start_time, cpu, rss) for every match
+            let matches: Vec<(u32, u64, f32, u64)> = self.system.processes().iter()
+                .filter(|(pid, process)| match &selector {
+                    ProcessSelector::Name(name) => process.name() == name,
+                    ProcessSelector::Pid(target) => pid.as_u32() == *target,
+                })
+                .map(|(pid, process)| (pid.as_u32(), process.start_time(), process.cpu_usage(), process.memory()))
+                .collect();
+            
+            let pid = matches.iter().min_by_key(|(_, start, _, _)| *start).map(|(pid, _, _, _)| *pid);
+            
+            let (last_pid, previous_sample_pid) = match self.watched_processes.get(&selector) {
+                Some(watched) => (watched.last_pid, watched.samples.back().and_then(|s| s.pid)),
+                None => continue,
+            };
+            
+            let restarted = matches!((last_pid, pid), (Some(previous), Some(current)) if previous != current);
+            // Only look in the journal on the poll where the old pid vanished
+            let oom_killed = match previous_sample_pid {
+                Some(previous) if pid != Some(previous) => self.was_oom_killed(previous),
+                _ => false,
+            };
+            
+            if restarted {
+                warn!("🔁 Watched process {:?} restarted ({:?} -> {:?})", selector, last_pid, pid);
+            }
+            if oom_killed {
+                warn!("💀 Watched process {:?} (pid {:?}) was OOM-killed", selector, last_pid);
+            }
+            
+            let sample = ProcessSample {
+                timestamp,
+                pid,
+                process_count: matches.len(),
+                cpu_usage: matches.iter().map(|(_, _, cpu, _)| cpu).sum(),
+                rss_bytes: matches.iter().map(|(_, _, _, rss)| rss).sum(),
+                restarted,
+                oom_killed,
+            };
+            
+            if let Some(watched) = self.watched_processes.get_mut(&selector) {
+                watched.samples.push_back(sample);
+                while watched.samples.len() > self.process_history_size {
+                    watched.samples.pop_front();
+                }
+                // Keep the old pid while it's down so a comeback still counts as a restart
+                if pid.is_some() {
+                    watched.last_pid = pid;
+                }
+            }
+        }
+    }
+    
+    /// The kernel logs "Killed process <pid>" at err level when the OOM killer fires
+    fn was_oom_killed(&self, pid: u32) -> bool {
+        let needle = format!("Killed process {} ", pid);
+        self.journal.get_recent_journal(3, self.update_interval.as_secs().max(60) * 2)
+            .iter()
+            .any(|entry| entry.message.contains(&needle))
+    }
+    
+    /// Every interface with cumulative counters and its latest rate, primary uplink first
     pub async fn get_network_interfaces(&self) -> Vec<NetworkInterface> {
         let mut interfaces = Vec::new();
+        let default_route = self.default_route_interface();
         
         for (name, data) in self.system.networks() {
             interfaces.push(NetworkInterface {
                 name: name.to_string(),
-                bytes_received: data.received(),
-                bytes_transmitted: data.transmitted(),
-                packets_received: data.packets_received(),
-                packets_transmitted: data.packets_transmitted(),
-                errors_received: data.errors_on_received(),
-                errors_transmitted: data.errors_on_transmitted(),
+                kind: InterfaceKind::detect(&self.sys_dir, name),
+                is_default_route: default_route.as_deref() == Some(name.as_str()),
+                bytes_received: data.total_received(),
+                bytes_transmitted: data.total_transmitted(),
+                packets_received: data.total_packets_received(),
+                packets_transmitted: data.total_packets_transmitted(),
+                errors_received: data.total_errors_on_received(),
+                errors_transmitted: data.total_errors_on_transmitted(),
+                rate: self.network_rates.get(name.as_str()).and_then(|history| history.back().copied()),
             });
         }
         
+        interfaces.sort_by_key(|interface| (!interface.is_default_route, interface.kind == InterfaceKind::Loopback, interface.name.clone()));
         interfaces
     }
     
@@ -630,7 +1325,9 @@ impl SystemMonitor {
             }
         }
         
-        // If no NVIDIA GPUs found, try AMD or add integrated graphics info
+        gpus.extend(gpu::detect_amd_gpus(&self.sys_dir.join("class/drm")).await.iter().map(GPUInfo::from));
+        
+        // Neither NVIDIA nor AMD, so integrated graphics
         if gpus.is_empty() {
             gpus.push(GPUInfo {
                 name: "Intel UHD Graphics".to_string(),
@@ -756,9 +1453,18 @@ impl SystemMonitor {
             "power": self.power_sensors.len()
         }));
         
+        if let Some(inference) = self.get_latest_inference() {
+            summary.insert("ollama_inference".to_string(), serde_json::json!({
+                "model": inference.model,
+                "tokens_per_sec": inference.tokens_per_sec,
+                "time_to_first_token_ms": inference.time_to_first_token_ms,
+                "measured_at": inference.measured_at,
+            }));
+        }
+        
         summary
     }
-
+    
     pub fn get_historical_data(&self, limit: usize) -> Vec<SystemMetrics> {
         let start_index = if self.metrics_history.len() > limit {
             self.metrics_history.len() - limit
@@ -768,7 +1474,7 @@ impl SystemMonitor {
         
         self.metrics_history[start_index..].to_vec()
     }
-
+    
     pub async fn set_monitoring_interval(&mut self, seconds: u64) -> Result<()> {
         if seconds < 1 {
             return Err(anyhow!("Monitoring interval must be at least 1 second"));
@@ -778,17 +1484,17 @@ impl SystemMonitor {
         info!("🔄 Monitoring interval set to {} seconds", seconds);
         Ok(())
     }
-
+    
     pub async fn export_metrics_csv(&self, path: &PathBuf, limit: Option<usize>) -> Result<()> {
         let data = if let Some(limit) = limit {
             self.get_historical_data(limit)
         } else {
             self.metrics_history.clone()
         };
-
+        
         let mut csv_content = String::new();
         csv_content.push_str("timestamp,cpu_usage,cpu_temp,cpu_freq,memory_usage,memory_total,gpu_usage,gpu_temp,network_rx,network_tx,uptime\n");
-
+        
         for metric in data {
             csv_content.push_str(&format!(
                 "{},{},{},{},{},{},{},{},{},{},{}\n",
@@ -805,9 +1511,214 @@ impl SystemMonitor {
                 metric.uptime
             ));
         }
-
+        
         fs::write(path, csv_content)?;
         info!("📊 Metrics exported to CSV: {}", path.display());
         Ok(())
     }
 }
+
+/// One sample standing for `bucket`: gauges are averaged, counters and
+/// descriptive fields come from the last sample
+fn average_metrics(bucket: &[SystemMetrics], timestamp: u64) -> Option<SystemMetrics> {
+    let mean = |field: fn(&SystemMetrics) -> f64| bucket.iter().map(field).sum::<f64>() / bucket.len() as f64;
+    
+    let mut averaged = bucket.last()?.clone();
+    averaged.timestamp = timestamp;
+    averaged.cpu_usage = mean(|m| m.cpu_usage as f64) as _;
+    averaged.cpu_temp = mean(|m| m.cpu_temp as f64) as _;
+    averaged.cpu_freq = mean(|m| m.cpu_freq as f64) as _;
+    averaged.memory_usage = mean(|m| m.memory_usage as f64) as _;
+    averaged.memory_available = mean(|m| m.memory_available as f64) as _;
+    averaged.gpu_usage = mean(|m| m.gpu_usage as f64) as _;
+    averaged.gpu_temp = mean(|m| m.gpu_temp as f64) as _;
+    Some(averaged)
+}
+
+/// "Out of memory: Killed process 1234 (ollama) total-vm:123kB, anon-rss:456kB, ..."
+fn parse_oom_kill(message: &str) -> Option<OomEvent> {
+    let rest = &message[message.find("Killed process ")? + "Killed process ".len()..];
+    let (pid, rest) = rest.split_once(' ')?;
+    let pid = pid.parse::<u32>().ok()?;
+    let process_name = rest.strip_prefix('(')?.split(')').next()?.to_string();
+    
+    let kb_field = |name: &str| -> u64 {
+        rest.split(|c: char| c == ',' || c.is_whitespace())
+            .find_map(|field| field.strip_prefix(name))
+            .and_then(|value| value.trim_end_matches("kB").parse().ok())
+            .unwrap_or(0)
+    };
+    
+    Some(OomEvent {
+        timestamp: 0,
+        pid,
+        process_name,
+        total_vm_kb: kb_field("total-vm:"),
+        anon_rss_kb: kb_field("anon-rss:"),
+        memory_usage: None,
+        cgroup_limited: message.contains("Memory cgroup out of memory"),
+    })
+}
+
+fn read_vmstat_counter(name: &str) -> Option<u64> {
+    fs::read_to_string("/proc/vmstat").ok()?
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            if key == name { value.trim().parse().ok() } else { None }
+        })
+}
+
+/// Sockets from `proc_dir`/net, mapped to processes through `proc_dir`/<pid>/fd.
+/// A connection is unexpected when it is outbound (its local port isn't one we
+/// listen on), goes to a public address, and uses a port not in `expected_ports`.
+pub fn read_connections(proc_dir: &Path, expected_ports: &[u16]) -> Vec<Connection> {
+    let mut connections = Vec::new();
+    let mut inodes = Vec::new();
+    for (file, protocol) in [
+        ("tcp", ConnectionProtocol::Tcp),
+        ("tcp6", ConnectionProtocol::Tcp),
+        ("udp", ConnectionProtocol::Udp),
+        ("udp6", ConnectionProtocol::Udp),
+    ] {
+        let table = match fs::read_to_string(proc_dir.join("net").join(file)) {
+            Ok(table) => table,
+            Err(_) => continue,
+        };
+        for line in table.lines().skip(1) {
+            if let Some((connection, inode)) = parse_socket_line(line, protocol) {
+                connections.push(connection);
+                inodes.push(inode);
+            }
+        }
+    }
+    
+    let owners = socket_owners(proc_dir);
+    let listening: HashSet<u16> = connections.iter()
+        .filter(|c| c.state == "LISTEN" || (c.protocol == ConnectionProtocol::Udp && c.remote_addr.port() == 0))
+        .map(|c| c.local_addr.port())
+        .collect();
+    
+    for (connection, inode) in connections.iter_mut().zip(inodes) {
+        if let Some((pid, name)) = owners.get(&inode) {
+            connection.pid = Some(*pid);
+            connection.process_name = Some(name.clone());
+        }
+        let active = matches!(connection.state.as_str(), "ESTABLISHED" | "SYN_SENT");
+        connection.unexpected_port = active
+            && is_public(connection.remote_addr.ip())
+            && !listening.contains(&connection.local_addr.port())
+            && !expected_ports.contains(&connection.remote_addr.port());
+    }
+    
+    connections
+}
+
+/// "0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000 0 0 23456 ..."
+/// Returns the connection and its socket inode.
+fn parse_socket_line(line: &str, protocol: ConnectionProtocol) -> Option<(Connection, u64)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let local_addr = parse_socket_addr(fields.get(1)?)?;
+    let remote_addr = parse_socket_addr(fields.get(2)?)?;
+    let state = match *fields.get(3)? {
+        "01" => "ESTABLISHED",
+        "02" => "SYN_SENT",
+        "03" => "SYN_RECV",
+        "04" => "FIN_WAIT1",
+        "05" => "FIN_WAIT2",
+        "06" => "TIME_WAIT",
+        "07" => "CLOSE",
+        "08" => "CLOSE_WAIT",
+        "09" => "LAST_ACK",
+        "0A" => "LISTEN",
+        "0B" => "CLOSING",
+        _ => "UNKNOWN",
+    };
+    let uid = fields.get(7)?.parse().ok()?;
+    let inode = fields.get(9)?.parse().ok()?;
+    
+    Some((Connection {
+        protocol,
+        local_addr,
+        remote_addr,
+        state: state.to_string(),
+        uid,
+        pid: None,
+        process_name: None,
+        unexpected_port: false,
+    }, inode))
+}
+
+/// "0100007F:0277" -> 127.0.0.1:631. The address is printed as 32-bit words in
+/// host byte order; the port is plain hex.
+fn parse_socket_addr(field: &str) -> Option<SocketAddr> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let mut bytes = Vec::with_capacity(16);
+    for chunk in address.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&bytes);
+            let ip = Ipv6Addr::from(octets);
+            // Dual-stack sockets show IPv4 peers as ::ffff:a.b.c.d
+            ip.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(ip))
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Socket inode -> (pid, process name). Other users' fds are unreadable unless root.
+fn socket_owners(proc_dir: &Path) -> HashMap<u64, (u32, String)> {
+    let mut owners = HashMap::new();
+    for entry in fs::read_dir(proc_dir).into_iter().flatten().flatten() {
+        let pid = match entry.file_name().to_string_lossy().parse::<u32>() {
+            Ok(pid) => pid,
+            Err(_) => continue,
+        };
+        let fds = match fs::read_dir(entry.path().join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        let name = fs::read_to_string(entry.path().join("comm"))
+            .map(|comm| comm.trim().to_string())
+            .unwrap_or_default();
+        
+        for fd in fds.flatten() {
+            let target = match fs::read_link(fd.path()) {
+                Ok(target) => target,
+                Err(_) => continue,
+            };
+            let inode = target.to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok());
+            if let Some(inode) = inode {
+                owners.insert(inode, (pid, name.clone()));
+            }
+        }
+    }
+    owners
+}
+
+/// Not loopback, private, link-local, CGNAT or unspecified
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let shared = ip.octets()[0] == 100 && (ip.octets()[1] & 0xC0) == 64;
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || shared)
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            let unique_local = (first & 0xFE00) == 0xFC00;
+            let link_local = (first & 0xFFC0) == 0xFE80;
+            !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+        }
+    }
+}
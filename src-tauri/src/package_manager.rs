@@ -2,19 +2,48 @@
 // Adapted from ArchForgePro and PackageManager modules 
 // Uses only relative paths and direct system calls
 
+use std::cmp::Ordering;
+use std::fmt::Write as _;
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::env;
 use std::process::{Command, Stdio};
 use std::str;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command as TokioCommand;
 use tracing::{info, warn, error, debug};
 use uuid::Uuid;
 
+use crate::config;
+use crate::logs::{JournalReader, VacuumTarget, DEFAULT_JOURNAL_SIZE_MB};
+use crate::privilege::{PrivilegedBatch, PACMAN_MIRRORLIST};
+use crate::snapshots::SnapshotManager;
+
+/// Longest a single package hook may run before it counts as failed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// pacman accepts any positive count, but past this mirrors start throttling
+const MAX_PARALLEL_DOWNLOADS: u32 = 20;
+
+const MIRROR_STATUS_URL: &str = "https://archlinux.org/mirrors/status/json/";
+/// Per-mirror download budget; slower mirrors are dropped from the ranking
+const MIRROR_BENCHMARK_TIMEOUT: Duration = Duration::from_secs(5);
+const MIRROR_BENCHMARK_CONCURRENCY: usize = 8;
+/// Mirrors that haven't synced within this long are skipped without benchmarking
+const MIRROR_MAX_SYNC_AGE_HOURS: i64 = 24;
+
+const PACKAGE_CACHE_DIR: &str = "/var/cache/pacman/pkg";
+
+/// Progress updates buffered per subscriber; a slower one loses the oldest
+const PROGRESS_EVENT_CAPACITY: usize = 64;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackageInfo {
     pub name: String,
@@ -36,6 +65,14 @@ pub struct PackageInfo {
     pub first_submitted: Option<String>,
     pub url: Option<String>,
     pub aur_package: bool,
+    /// Held back from upgrades by `PackageManager::pin_package`
+    #[serde(default)]
+    pub pinned: bool,
+    /// AUR votes and popularity, when known
+    #[serde(default)]
+    pub votes: Option<u32>,
+    #[serde(default)]
+    pub popularity: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +98,7 @@ pub enum OperationType {
     Clean,
     AurInstall,
     AurUpdate,
+    Downgrade,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,9 +117,164 @@ pub struct RepositoryStats {
     pub available_updates: u32,
     pub orphaned_packages: u32,
     pub cache_size: u64,
+    /// systemd journal on disk, so the UI can offer to vacuum it
+    pub journal_size: u64,
     pub last_update: Option<u64>,
 }
 
+/// What `pacman -Qkk` found different from the package database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegrityMismatch {
+    Checksum,
+    Size,
+    Permissions,
+    Ownership,
+    ModificationTime,
+    FileType,
+    SymlinkTarget,
+    Missing,
+    Other(String),
+}
+
+/// Where a file lives, which decides whether a change is expected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileClass {
+    /// /etc and other config files users are expected to edit
+    Config,
+    /// Executables and shared libraries that should never change outside an upgrade
+    Binary,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileIntegrityIssue {
+    pub package: String,
+    pub path: PathBuf,
+    pub mismatch: IntegrityMismatch,
+    pub file_class: FileClass,
+}
+
+impl FileIntegrityIssue {
+    /// Content changes to binaries are what tampering looks like; config edits and
+    /// mtime drift are normal
+    pub fn is_suspicious(&self) -> bool {
+        self.file_class == FileClass::Binary && matches!(
+            self.mismatch,
+            IntegrityMismatch::Checksum | IntegrityMismatch::Size | IntegrityMismatch::FileType
+                | IntegrityMismatch::SymlinkTarget | IntegrityMismatch::Permissions | IntegrityMismatch::Ownership
+        )
+    }
+}
+
+/// A mirror from `rank_mirrors`, fastest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MirrorInfo {
+    /// Base URL, e.g. "https://mirror.example.org/archlinux/"
+    pub url: String,
+    pub country: String,
+    pub last_sync: Option<DateTime<Utc>>,
+    /// Time to first response for core.db
+    pub latency_ms: u64,
+    /// core.db download rate
+    pub speed_kib_per_sec: f64,
+}
+
+/// pacman query results behind `get_repository_stats`, reused until `stats_cache_ttl` expires
+#[derive(Debug, Clone)]
+struct StatsCache {
+    fetched_at: Instant,
+    total_packages: u32,
+    updates: Vec<PackageInfo>,
+    orphans: Vec<PackageInfo>,
+}
+
+/// What an AUR build will do, so the dependency list can be reviewed before building
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AurBuildPlan {
+    pub name: String,
+    pub version: String,
+    pub build_dir: PathBuf,
+    pub depends: Vec<String>,
+    pub make_depends: Vec<String>,
+    pub check_depends: Vec<String>,
+}
+
+/// A cached package file `paccache -r` would delete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPackageFile {
+    pub path: PathBuf,
+    pub name: String,
+    pub version: String,
+    pub size_bytes: u64,
+    /// The cached copy of what is installed right now; a downgrade would have to download it again
+    pub is_installed_version: bool,
+}
+
+/// Everything `apply_cleanup` would remove, from `preview_cleanup`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanupPreview {
+    pub cached_packages: Vec<CachedPackageFile>,
+    pub orphans: Vec<PackageInfo>,
+    pub journal_bytes: u64,
+    /// What vacuuming to DEFAULT_JOURNAL_SIZE_MB would free
+    pub journal_reclaimable_bytes: u64,
+    pub total_reclaimable_bytes: u64,
+    pub removes_installed_version: bool,
+    /// Parts of the preview that couldn't be worked out
+    pub notes: Vec<String>,
+}
+
+impl CleanupPreview {
+    pub fn summary(&self) -> String {
+        let cache_bytes: u64 = self.cached_packages.iter().map(|p| p.size_bytes).sum();
+        let orphan_bytes: u64 = self.orphans.iter().map(|p| p.installed_size).sum();
+        let mut summary = format!(
+            "Would free {:.1} MiB: {} cached package files ({:.1} MiB), {} orphans ({:.1} MiB), journal vacuum ({:.1} MiB)",
+            self.total_reclaimable_bytes as f64 / 1048576.0,
+            self.cached_packages.len(), cache_bytes as f64 / 1048576.0,
+            self.orphans.len(), orphan_bytes as f64 / 1048576.0,
+            self.journal_reclaimable_bytes as f64 / 1048576.0,
+        );
+        if self.removes_installed_version {
+            summary.push_str(". Includes the cached copy of an installed version");
+        }
+        summary
+    }
+}
+
+/// Installed package dependency graph with version constraints and virtual
+/// provides resolved to real package names
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub dependencies: HashMap<String, Vec<String>>,
+    pub dependents: HashMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    /// Installed packages that directly depend on `name` (like `pactree -r -d1`)
+    pub fn dependents_of(&self, name: &str) -> Vec<String> {
+        self.dependents.get(name).cloned().unwrap_or_default()
+    }
+    
+    /// Everything `name` pulls in, directly or transitively
+    pub fn dependency_closure(&self, name: &str) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![name.to_string()];
+        
+        while let Some(current) = stack.pop() {
+            for dependency in self.dependencies.get(&current).into_iter().flatten() {
+                if dependency != name && seen.insert(dependency.clone()) {
+                    stack.push(dependency.clone());
+                }
+            }
+        }
+        
+        let mut closure: Vec<String> = seen.into_iter().collect();
+        closure.sort();
+        closure
+    }
+}
+
 pub struct PackageManager {
     pub work_dir: PathBuf,
     pub data_dir: PathBuf,
@@ -100,9 +293,21 @@ pub struct PackageManager {
     // Configuration
     pub pacman_conf: PathBuf,
     pub makepkg_conf: PathBuf,
+    pub mirrorlist: PathBuf,
     pub aur_helper: String,
     pub auto_clean: bool,
+    /// Written to pacman.conf by `apply_pacman_config`
     pub parallel_downloads: u32,
+    /// Also turn on `Color` and `ILoveCandy` in `apply_pacman_config`
+    pub eye_candy: bool,
+    pub pinned_packages: HashSet<String>,
+    /// `sudo`/`pkexec` when pacman needs elevation, `None` when already root
+    pub privilege_helper: Option<String>,
+    /// How long repository stats are served from cache before pacman is queried again
+    pub stats_cache_ttl: Duration,
+    stats_cache: Option<StatsCache>,
+    /// Snapshot of a streamed operation after each line of pacman output
+    progress: tokio::sync::broadcast::Sender<PackageOperation>,
 }
 
 impl PackageManager {
@@ -122,6 +327,7 @@ impl PackageManager {
         
         // Detect AUR helper
         let aur_helper = Self::detect_aur_helper().await;
+        let privilege_helper = Self::detect_privilege_helper().await;
         
         let mut manager = Self {
             work_dir,
@@ -135,13 +341,22 @@ impl PackageManager {
             operation_history: Vec::new(),
             pacman_conf: PathBuf::from("/etc/pacman.conf"),
             makepkg_conf: PathBuf::from("/etc/makepkg.conf"),
+            mirrorlist: PathBuf::from(PACMAN_MIRRORLIST),
             aur_helper,
             auto_clean: true,
             parallel_downloads: 5,
+            eye_candy: false,
+            pinned_packages: HashSet::new(),
+            privilege_helper,
+            stats_cache_ttl: Duration::from_secs(60),
+            stats_cache: None,
+            progress: tokio::sync::broadcast::channel(PROGRESS_EVENT_CAPACITY).0,
         };
         
-        // Load package databases
-        manager.refresh_package_database().await?;
+        manager.load_pins()?;
+        
+        // Load package databases. No sync here: a bare -Sy at startup followed by a
+        // later -S install is a partial upgrade, and it would prompt for a password.
         manager.load_installed_packages().await?;
         
         info!("✅ Package management initialized with {} installed packages", 
@@ -169,6 +384,48 @@ impl PackageManager {
         "none".to_string()
     }
     
+    /// pacman needs root to write its database; anything else gets a permission error
+    async fn detect_privilege_helper() -> Option<String> {
+        if crate::privilege::is_root() {
+            return None;
+        }
+        
+        // pkexec shows a graphical polkit prompt, which suits a desktop app better than sudo
+        for helper in ["pkexec", "sudo"] {
+            if let Ok(output) = TokioCommand::new("which").arg(helper).output().await {
+                if output.status.success() {
+                    debug!("🔐 Using {} for pacman operations", helper);
+                    return Some(helper.to_string());
+                }
+            }
+        }
+        
+        warn!("⚠️ Not running as root and neither pkexec nor sudo found; pacman operations will fail");
+        None
+    }
+    
+    /// A pacman command, elevated through `privilege_helper` when needed
+    fn pacman_command(&self) -> TokioCommand {
+        self.privileged_command("pacman")
+    }
+    
+    /// Any command that writes system files, elevated through `privilege_helper` when needed
+    fn privileged_command(&self, program: &str) -> TokioCommand {
+        match &self.privilege_helper {
+            Some(helper) => {
+                let mut command = TokioCommand::new(helper);
+                command.arg(program);
+                command
+            }
+            None => TokioCommand::new(program),
+        }
+    }
+    
+    /// Sync the package databases (`pacman -Sy`).
+    ///
+    /// Syncing without upgrading leaves the system open to partial upgrades if a
+    /// package is installed afterwards. Prefer `upgrade_system`, which syncs and
+    /// upgrades in a single `pacman -Syu`, unless an upgrade follows immediately.
     pub async fn refresh_package_database(&mut self) -> Result<()> {
         info!("🔄 Refreshing package databases");
         
@@ -187,7 +444,7 @@ impl PackageManager {
         self.active_operations.insert(operation_id.clone(), operation.clone());
         
         // Update pacman database
-        let output = TokioCommand::new("pacman")
+        let output = self.pacman_command()
             .args(&["-Sy", "--noconfirm"])
             .output()
             .await?;
@@ -196,8 +453,15 @@ impl PackageManager {
         if output.status.success() {
             operation.log.push("Pacman database updated successfully".to_string());
         } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            operation.log.push(format!("Pacman update warning: {}", stderr));
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            operation.log.push(format!("Pacman database sync failed: {}", stderr));
+            operation.status = OperationStatus::Failed;
+            operation.completed_at = Some(chrono::Utc::now().timestamp() as u64);
+            
+            self.active_operations.remove(&operation_id);
+            self.operation_history.push(operation);
+            
+            return Err(anyhow!("Pacman database sync failed: {}", stderr));
         }
         
         // Update AUR database if helper available
@@ -235,6 +499,9 @@ impl PackageManager {
     pub async fn load_installed_packages(&mut self) -> Result<()> {
         debug!("📋 Loading installed packages");
         
+        // Installed set is changing; cached update/orphan lists are stale
+        self.stats_cache = None;
+        
         let output = TokioCommand::new("pacman")
             .args(&["-Q", "-i"])
             .output()
@@ -283,7 +550,7 @@ impl PackageManager {
             if let Ok(output) = aur_output {
                 if output.status.success() {
                     let output_str = String::from_utf8_lossy(&output.stdout);
-                    let mut aur_packages = self.parse_aur_search(&output_str);
+                    let mut aur_packages = Self::parse_aur_search(&output_str);
                     results.append(&mut aur_packages);
                 }
             }
@@ -351,32 +618,35 @@ impl PackageManager {
         
         self.active_operations.insert(operation_id.clone(), operation.clone());
         
-        let command = if from_aur && self.aur_helper != "none" {
-            &self.aur_helper
-        } else {
-            "pacman"
-        };
+        let hooks = config::get().package_hooks;
+        if !self.run_hooks("pre_install", &hooks.pre_install, &mut operation).await {
+            return Ok(self.abort_operation(operation, "pre_install hook failed"));
+        }
         
-        let mut args = if from_aur {
-            vec!["-S", "--noconfirm"]
-        } else {
-            vec!["-S", "--noconfirm"]
-        };
+        let use_aur_helper = from_aur && self.aur_helper != "none";
+        let command_name = if use_aur_helper { self.aur_helper.clone() } else { "pacman".to_string() };
+        
+        let mut args = vec!["-S", "--noconfirm", "--noprogressbar"];
         
         for package in &packages {
             args.push(package);
         }
         
-        operation.log.push(format!("Starting installation: {} {:?}", command, args));
+        operation.log.push(format!("Starting installation: {} {:?}", command_name, args));
         
-        let output = TokioCommand::new(command)
-            .args(&args)
-            .output()
-            .await?;
+        // AUR helpers run unprivileged and call sudo themselves
+        let mut command = if use_aur_helper {
+            TokioCommand::new(&self.aur_helper)
+        } else {
+            self.pacman_command()
+        };
+        command.args(&args);
+        
+        let (success, stderr_lines) = self.stream_operation(&mut operation, command, packages.len(), 0.0, 95.0).await?;
         
         operation.progress = 100.0;
         
-        if output.status.success() {
+        if success {
             operation.status = OperationStatus::Completed;
             operation.log.push("Installation completed successfully".to_string());
             
@@ -384,8 +654,7 @@ impl PackageManager {
             self.load_installed_packages().await?;
         } else {
             operation.status = OperationStatus::Failed;
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            operation.log.push(format!("Installation failed: {}", stderr));
+            operation.log.push(format!("Installation failed: {}", stderr_lines.join(" | ")));
         }
         
         operation.completed_at = Some(chrono::Utc::now().timestamp() as u64);
@@ -396,7 +665,260 @@ impl PackageManager {
         Ok(operation_id)
     }
     
-    pub async fn remove_packages(&mut self, packages: Vec<String>, remove_deps: bool) -> Result<String> {
+    /// Run a pacman-style command with piped output, moving `operation.progress` from
+    /// `start` to `end` as pacman reports each package. The command needs
+    /// --noprogressbar; `expected` is the package count to assume until pacman lists
+    /// the transaction. The live copy in `active_operations` is updated and sent to
+    /// progress subscribers per line; `operation` is synced afterwards. Returns whether the command succeeded and its stderr lines.
+    async fn stream_operation(&mut self, operation: &mut PackageOperation, mut command: TokioCommand, expected: usize, start: f64, end: f64) -> Result<(bool, Vec<String>)> {
+        let operation_id = operation.operation_id.clone();
+        let mut progress = PacmanProgress::new(expected);
+        self.active_operations.insert(operation_id.clone(), operation.clone());
+        
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        
+        let stderr_task = child.stderr.take().map(|stderr| tokio::spawn(async move {
+            let mut collected = Vec::new();
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                collected.push(line);
+            }
+            collected
+        }));
+        
+        if let Some(stdout) = child.stdout.take() {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Some(line) = lines.next_line().await? {
+                if let Some(live) = self.active_operations.get_mut(&operation_id) {
+                    // AUR helpers' own downloads can still redraw with '\r'; keep the latest state
+                    let current = line.rsplit('\r').next().unwrap_or("").trim();
+                    if let Some(fraction) = progress.update(current) {
+                        // A later transaction in the same command starts over; never move backwards
+                        live.progress = live.progress.max((start + (end - start) * fraction) as f32);
+                    }
+                    if !current.is_empty() {
+                        live.log.push(current.to_string());
+                    }
+                    // Sending only fails when nobody is subscribed
+                    let _ = self.progress.send(live.clone());
+                }
+            }
+        }
+        
+        let status = child.wait().await?;
+        let stderr_lines = match stderr_task {
+            Some(task) => task.await.unwrap_or_default(),
+            None => Vec::new(),
+        };
+        
+        if let Some(live) = self.active_operations.get(&operation_id) {
+            *operation = live.clone();
+        }
+        
+        Ok((status.success(), stderr_lines))
+    }
+    
+    /// Fetch an AUR package's build files into `cache_dir` and check them without building.
+    pub async fn prepare_aur_package(&self, name: &str) -> Result<AurBuildPlan> {
+        // The name becomes a path component and part of the clone URL
+        if !is_valid_package_name(name) {
+            return Err(anyhow!("Invalid AUR package name '{}'", name));
+        }
+        let build_dir = self.cache_dir.join("aur").join(name);
+        
+        if build_dir.join(".git").exists() {
+            let output = TokioCommand::new("git")
+                .args(&["pull", "--ff-only"])
+                .current_dir(&build_dir)
+                .output()
+                .await?;
+            if !output.status.success() {
+                return Err(anyhow!("Failed to update AUR repository for {}: {}", name, String::from_utf8_lossy(&output.stderr)));
+            }
+        } else {
+            fs::create_dir_all(self.cache_dir.join("aur"))?;
+            let output = TokioCommand::new("git")
+                .arg("clone")
+                .arg(format!("https://aur.archlinux.org/{}.git", name))
+                .arg(&build_dir)
+                .output()
+                .await?;
+            if !output.status.success() {
+                return Err(anyhow!("Failed to clone AUR repository for {}: {}", name, String::from_utf8_lossy(&output.stderr)));
+            }
+        }
+        
+        // Cloning a name that isn't on the AUR gives an empty repository
+        if !build_dir.join("PKGBUILD").exists() {
+            return Err(anyhow!("AUR package '{}' not found", name));
+        }
+        // The version comes from .SRCINFO alone: PKGBUILDs often build pkgver from
+        // variables or pkgver(), which only makepkg can evaluate
+        let srcinfo = fs::read_to_string(build_dir.join(".SRCINFO"))
+            .map_err(|_| anyhow!("AUR package '{}' has no .SRCINFO", name))?;
+        
+        let srcinfo_fields = self.parse_srcinfo(&srcinfo);
+        let srcinfo_value = |key: &str| srcinfo_fields.get(key).and_then(|v| v.first()).cloned();
+        
+        let pkgver = srcinfo_value("pkgver").ok_or_else(|| anyhow!("No pkgver in .SRCINFO for {}", name))?;
+        let pkgrel = srcinfo_value("pkgrel").unwrap_or_else(|| "1".to_string());
+        let version = match srcinfo_value("epoch") {
+            Some(epoch) => format!("{}:{}-{}", epoch, pkgver, pkgrel),
+            None => format!("{}-{}", pkgver, pkgrel),
+        };
+        
+        let deps = |key: &str| {
+            let mut list = srcinfo_fields.get(key).cloned().unwrap_or_default();
+            list.extend(srcinfo_fields.get(&format!("{}_x86_64", key)).cloned().unwrap_or_default());
+            list
+        };
+        
+        Ok(AurBuildPlan {
+            name: name.to_string(),
+            version,
+            build_dir,
+            depends: deps("depends"),
+            make_depends: deps("makedepends"),
+            check_depends: deps("checkdepends"),
+        })
+    }
+    
+    /// Build and install an AUR package with makepkg, without needing an AUR helper.
+    pub async fn build_aur_package(&mut self, name: &str) -> Result<String> {
+        let operation_id = Uuid::new_v4().to_string();
+        info!("🔨 Building AUR package from source: {}", name);
+        
+        let operation = PackageOperation {
+            operation_id: operation_id.clone(),
+            operation_type: OperationType::AurInstall,
+            packages: vec![name.to_string()],
+            status: OperationStatus::Running,
+            progress: 0.0,
+            log: vec![format!("Fetching https://aur.archlinux.org/{}.git", name)],
+            started_at: chrono::Utc::now().timestamp() as u64,
+            completed_at: None,
+        };
+        self.active_operations.insert(operation_id.clone(), operation);
+        
+        let result = self.run_aur_build(&operation_id, name).await;
+        
+        if let Some(mut operation) = self.active_operations.remove(&operation_id) {
+            operation.progress = 100.0;
+            operation.completed_at = Some(chrono::Utc::now().timestamp() as u64);
+            match &result {
+                Ok(()) => {
+                    operation.status = OperationStatus::Completed;
+                    operation.log.push(format!("{} built and installed successfully", name));
+                }
+                Err(e) => {
+                    operation.status = OperationStatus::Failed;
+                    operation.log.push(format!("AUR build failed: {}", e));
+                }
+            }
+            self.operation_history.push(operation);
+        }
+        
+        result?;
+        self.load_installed_packages().await?;
+        Ok(operation_id)
+    }
+    
+    async fn run_aur_build(&mut self, operation_id: &str, name: &str) -> Result<()> {
+        let plan = self.prepare_aur_package(name).await?;
+        
+        if let Some(operation) = self.active_operations.get_mut(operation_id) {
+            operation.progress = 10.0;
+            operation.log.push(format!("{} {} ready to build", plan.name, plan.version));
+            operation.log.push(format!("Depends: {}", if plan.depends.is_empty() { "none".to_string() } else { plan.depends.join(" ") }));
+            operation.log.push(format!("Make depends: {}", if plan.make_depends.is_empty() { "none".to_string() } else { plan.make_depends.join(" ") }));
+            if !plan.check_depends.is_empty() {
+                operation.log.push(format!("Check depends: {}", plan.check_depends.join(" ")));
+            }
+        }
+        
+        // makepkg refuses to run as root and calls sudo itself for dependencies and install
+        let mut child = TokioCommand::new("makepkg")
+            .args(&["-si", "--noconfirm", "--nocolor"])
+            .current_dir(&plan.build_dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        
+        let stderr_task = child.stderr.take().map(|stderr| tokio::spawn(async move {
+            let mut collected = Vec::new();
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                collected.push(line);
+            }
+            collected
+        }));
+        
+        if let Some(stdout) = child.stdout.take() {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Some(line) = lines.next_line().await? {
+                if let Some(operation) = self.active_operations.get_mut(operation_id) {
+                    // Advance on makepkg's stage markers
+                    let stage_progress = if line.contains("Retrieving sources") {
+                        Some(20.0)
+                    } else if line.contains("Starting build()") {
+                        Some(35.0)
+                    } else if line.contains("Starting package()") {
+                        Some(75.0)
+                    } else if line.contains("Finished making") {
+                        Some(85.0)
+                    } else if line.contains("Installing package") {
+                        Some(90.0)
+                    } else {
+                        None
+                    };
+                    if let Some(progress) = stage_progress {
+                        operation.progress = progress;
+                    }
+                    operation.log.push(line);
+                }
+            }
+        }
+        
+        let status = child.wait().await?;
+        let stderr_lines = match stderr_task {
+            Some(task) => task.await.unwrap_or_default(),
+            None => Vec::new(),
+        };
+        
+        if !status.success() {
+            let tail: Vec<String> = stderr_lines.iter().rev().take(5).rev().cloned().collect();
+            return Err(anyhow!("makepkg exited with {}: {}", status, tail.join(" | ")));
+        }
+        
+        Ok(())
+    }
+    
+    pub fn build_dependency_graph(&self) -> DependencyGraph {
+        dependency_graph(&self.installed_packages)
+    }
+    
+    /// Remove packages. Unless `force` is set, refuses when other installed packages
+    /// (outside the removal set) still depend on any of them.
+    pub async fn remove_packages(&mut self, packages: Vec<String>, remove_deps: bool, force: bool) -> Result<String> {
+        let graph = self.build_dependency_graph();
+        let mut blocked = Vec::new();
+        for package in &packages {
+            let dependents: Vec<String> = graph.dependents_of(package)
+                .into_iter()
+                .filter(|d| !packages.contains(d))
+                .collect();
+            if !dependents.is_empty() {
+                warn!("⚠️ {} is required by: {}", package, dependents.join(", "));
+                blocked.push(format!("{} (required by {})", package, dependents.join(", ")));
+            }
+        }
+        if !blocked.is_empty() && !force {
+            return Err(anyhow!("Refusing to remove packages other packages depend on: {}", blocked.join("; ")));
+        }
+        
         let operation_id = Uuid::new_v4().to_string();
         info!("🗑️ Removing packages: {:?} (deps: {})", packages, remove_deps);
         
@@ -413,10 +935,17 @@ impl PackageManager {
         
         self.active_operations.insert(operation_id.clone(), operation.clone());
         
+        let hooks = config::get().package_hooks;
+        
         let mut args = vec!["-R", "--noconfirm"];
         if remove_deps {
             args.push("-s"); // Remove dependencies
         }
+        if !blocked.is_empty() {
+            // Forced: skip pacman's own dependency check too
+            args.push("-dd");
+            operation.log.push(format!("Forcing removal despite dependents: {}", blocked.join("; ")));
+        }
         
         for package in &packages {
             args.push(package);
@@ -424,7 +953,7 @@ impl PackageManager {
         
         operation.log.push(format!("Starting removal: pacman {:?}", args));
         
-        let output = TokioCommand::new("pacman")
+        let output = self.pacman_command()
             .args(&args)
             .output()
             .await?;
@@ -443,6 +972,102 @@ impl PackageManager {
             operation.log.push(format!("Removal failed: {}", stderr));
         }
         
+        self.run_hooks("post_remove", &hooks.post_remove, &mut operation).await;
+        
+        operation.completed_at = Some(chrono::Utc::now().timestamp() as u64);
+        
+        self.active_operations.remove(&operation_id);
+        self.operation_history.push(operation);
+        
+        Ok(operation_id)
+    }
+    
+    /// Versions of `name` in the pacman cache, oldest first, with their package files
+    pub fn cached_versions(&self, name: &str) -> Vec<(String, PathBuf)> {
+        let mut versions = Vec::new();
+        
+        if let Ok(entries) = fs::read_dir("/var/cache/pacman/pkg") {
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                if file_name.ends_with(".sig") {
+                    continue;
+                }
+                let stem = match file_name.find(".pkg.tar") {
+                    Some(index) => &file_name[..index],
+                    None => continue,
+                };
+                
+                // <name>-<pkgver>-<pkgrel>-<arch>; names may themselves contain dashes
+                let parts: Vec<&str> = stem.rsplitn(4, '-').collect();
+                if parts.len() == 4 && parts[3] == name {
+                    versions.push((format!("{}-{}", parts[2], parts[1]), entry.path()));
+                }
+            }
+        }
+        
+        versions.sort_by(|a, b| compare_versions(&a.0, &b.0));
+        versions
+    }
+    
+    /// Reinstall an older cached version of a package. With no version, the newest
+    /// cached version older than the installed one is used.
+    pub async fn downgrade_package(&mut self, name: &str, version: Option<String>) -> Result<String> {
+        let cached = self.cached_versions(name);
+        let available: Vec<String> = cached.iter().map(|(v, _)| v.clone()).collect();
+        if cached.is_empty() {
+            return Err(anyhow!("No cached versions of {} in /var/cache/pacman/pkg", name));
+        }
+        
+        let target = match &version {
+            Some(requested) => cached.iter().find(|(v, _)| v == requested),
+            None => {
+                let installed = self.installed_packages.get(name)
+                    .map(|p| p.version.clone())
+                    .ok_or_else(|| anyhow!("{} is not installed; specify a version", name))?;
+                cached.iter().rev().find(|(v, _)| compare_versions(v, &installed) == Ordering::Less)
+            }
+        };
+        let (target_version, package_file) = target.cloned().ok_or_else(|| anyhow!(
+            "Version {} of {} is not in the package cache. Available: {}",
+            version.as_deref().unwrap_or("older than installed"), name, available.join(", ")
+        ))?;
+        
+        let operation_id = Uuid::new_v4().to_string();
+        info!("⬇️ Downgrading {} to {}", name, target_version);
+        
+        let mut operation = PackageOperation {
+            operation_id: operation_id.clone(),
+            operation_type: OperationType::Downgrade,
+            packages: vec![format!("{}={}", name, target_version)],
+            status: OperationStatus::Running,
+            progress: 0.0,
+            log: vec![format!("Installing cached package {}", package_file.display())],
+            started_at: chrono::Utc::now().timestamp() as u64,
+            completed_at: None,
+        };
+        
+        self.active_operations.insert(operation_id.clone(), operation.clone());
+        
+        let output = self.pacman_command()
+            .args(&["-U", "--noconfirm"])
+            .arg(&package_file)
+            .output()
+            .await?;
+        
+        operation.progress = 100.0;
+        
+        if output.status.success() {
+            operation.status = OperationStatus::Completed;
+            operation.log.push(format!("Downgraded {} to {}", name, target_version));
+            
+            // Refresh installed packages
+            self.load_installed_packages().await?;
+        } else {
+            operation.status = OperationStatus::Failed;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            operation.log.push(format!("Downgrade failed: {}", stderr));
+        }
+        
         operation.completed_at = Some(chrono::Utc::now().timestamp() as u64);
         
         self.active_operations.remove(&operation_id);
@@ -451,6 +1076,55 @@ impl PackageManager {
         Ok(operation_id)
     }
     
+    fn load_pins(&mut self) -> Result<()> {
+        let pins_file = self.config_dir.join("pins.json");
+        if pins_file.exists() {
+            let content = fs::read_to_string(&pins_file)?;
+            self.pinned_packages = serde_json::from_str(&content).unwrap_or_default();
+        }
+        Ok(())
+    }
+    
+    fn save_pins(&self) -> Result<()> {
+        let pins_file = self.config_dir.join("pins.json");
+        let mut pins: Vec<&String> = self.pinned_packages.iter().collect();
+        pins.sort();
+        fs::write(&pins_file, serde_json::to_string_pretty(&pins)?)?;
+        Ok(())
+    }
+    
+    /// Hold a package at its current version during `upgrade_system`
+    pub fn pin_package(&mut self, name: &str) -> Result<()> {
+        if self.pinned_packages.insert(name.to_string()) {
+            self.save_pins()?;
+            info!("📌 Pinned package: {}", name);
+        }
+        Ok(())
+    }
+    
+    pub fn unpin_package(&mut self, name: &str) -> Result<()> {
+        if self.pinned_packages.remove(name) {
+            self.save_pins()?;
+            info!("📌 Unpinned package: {}", name);
+        }
+        Ok(())
+    }
+    
+    pub fn list_pinned(&self) -> Vec<String> {
+        let mut pins: Vec<String> = self.pinned_packages.iter().cloned().collect();
+        pins.sort();
+        pins
+    }
+    
+    /// `--ignore a,b,c` for the pinned packages, or nothing if none are pinned
+    fn ignore_args(&self) -> Vec<String> {
+        if self.pinned_packages.is_empty() {
+            Vec::new()
+        } else {
+            vec!["--ignore".to_string(), self.list_pinned().join(",")]
+        }
+    }
+    
     pub async fn upgrade_system(&mut self, include_aur: bool) -> Result<String> {
         let operation_id = Uuid::new_v4().to_string();
         info!("⬆️ Upgrading system (AUR: {})", include_aur);
@@ -468,43 +1142,45 @@ impl PackageManager {
         
         self.active_operations.insert(operation_id.clone(), operation.clone());
         
-        // First, update databases
-        self.refresh_package_database().await?;
-        operation.progress = 25.0;
+        let hooks = config::get().package_hooks;
+        if !self.run_hooks("pre_upgrade", &hooks.pre_upgrade, &mut operation).await {
+            return Ok(self.abort_operation(operation, "pre_upgrade hook failed"));
+        }
         
-        // Upgrade official packages
-        operation.log.push("Upgrading official packages...".to_string());
-        let output = TokioCommand::new("pacman")
-            .args(&["-Su", "--noconfirm"])
-            .output()
-            .await?;
+        if config::get().backup.pre_upgrade_snapshot {
+            self.pre_upgrade_snapshot(&mut operation).await;
+        }
         
-        operation.progress = 50.0;
+        // Sync and upgrade official packages in one transaction (never a bare -Sy)
+        operation.log.push("Syncing databases and upgrading official packages...".to_string());
+        if !self.pinned_packages.is_empty() {
+            operation.log.push(format!("Holding pinned packages: {}", self.list_pinned().join(", ")));
+        }
+        let include_aur = include_aur && self.aur_helper != "none";
+        let official_end = if include_aur { 70.0 } else { 95.0 };
         
-        if output.status.success() {
+        let mut command = self.pacman_command();
+        command.args(&["-Syu", "--noconfirm", "--noprogressbar"]).args(self.ignore_args());
+        let (success, stderr_lines) = self.stream_operation(&mut operation, command, 0, 0.0, official_end).await?;
+        
+        if success {
             operation.log.push("Official packages upgraded successfully".to_string());
         } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            operation.log.push(format!("Official upgrade warning: {}", stderr));
+            operation.log.push(format!("Official upgrade warning: {}", stderr_lines.join(" | ")));
         }
         
         // Upgrade AUR packages if requested and helper available
-        if include_aur && self.aur_helper != "none" {
+        if include_aur {
             operation.log.push("Upgrading AUR packages...".to_string());
-            let aur_output = TokioCommand::new(&self.aur_helper)
-                .args(&["-Su", "--noconfirm", "--aur"])
-                .output()
-                .await;
-            
-            operation.progress = 75.0;
+            let mut command = TokioCommand::new(&self.aur_helper);
+            command.args(&["-Su", "--noconfirm", "--noprogressbar", "--aur"]).args(self.ignore_args());
             
-            match aur_output {
-                Ok(out) if out.status.success() => {
+            match self.stream_operation(&mut operation, command, 0, official_end, 95.0).await {
+                Ok((true, _)) => {
                     operation.log.push("AUR packages upgraded successfully".to_string());
                 },
-                Ok(out) => {
-                    let stderr = String::from_utf8_lossy(&out.stderr);
-                    operation.log.push(format!("AUR upgrade warning: {}", stderr));
+                Ok((false, stderr_lines)) => {
+                    operation.log.push(format!("AUR upgrade warning: {}", stderr_lines.join(" | ")));
                 },
                 Err(e) => {
                     operation.log.push(format!("AUR upgrade error: {}", e));
@@ -512,6 +1188,8 @@ impl PackageManager {
             }
         }
         
+        self.run_hooks("post_upgrade", &hooks.post_upgrade, &mut operation).await;
+        
         operation.progress = 100.0;
         operation.status = OperationStatus::Completed;
         operation.completed_at = Some(chrono::Utc::now().timestamp() as u64);
@@ -525,7 +1203,207 @@ impl PackageManager {
         Ok(operation_id)
     }
     
-    pub async fn clean_cache(&mut self, clean_all: bool) -> Result<String> {
+    /// Run `hooks` in order through `sh -c`, logging their output into `operation`.
+    /// A failing pre_ hook stops the rest and returns false so the operation is
+    /// aborted; post_ hooks run regardless, since the change has already happened.
+    async fn run_hooks(&mut self, stage: &str, hooks: &[String], operation: &mut PackageOperation) -> bool {
+        let abort_on_failure = stage.starts_with("pre_");
+        let mut all_succeeded = true;
+        for hook in hooks {
+            operation.log.push(format!("Running {} hook: {}", stage, hook));
+            
+            let result = tokio::time::timeout(HOOK_TIMEOUT, TokioCommand::new("sh")
+                .args(["-c", hook])
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+                .output()).await;
+            
+            let failure = match result {
+                Ok(Ok(output)) => {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    for line in stdout.lines().chain(stderr.lines()).filter(|l| !l.trim().is_empty()) {
+                        operation.log.push(format!("[{}] {}", stage, line));
+                    }
+                    if output.status.success() {
+                        None
+                    } else {
+                        Some(format!("exited with {}", output.status))
+                    }
+                }
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some(format!("timed out after {}s", HOOK_TIMEOUT.as_secs())),
+            };
+            
+            if let Some(reason) = failure {
+                warn!("⚠️ {} hook '{}' failed: {}", stage, hook, reason);
+                operation.log.push(format!("{} hook failed ({}): {}", stage, reason, hook));
+                all_succeeded = false;
+                if abort_on_failure {
+                    self.active_operations.insert(operation.operation_id.clone(), operation.clone());
+                    return false;
+                }
+            }
+            self.active_operations.insert(operation.operation_id.clone(), operation.clone());
+        }
+        all_succeeded
+    }
+    
+    /// Mark `operation` failed before anything was changed and move it to history
+    fn abort_operation(&mut self, mut operation: PackageOperation, reason: &str) -> String {
+        error!("❌ Package operation aborted: {}", reason);
+        operation.status = OperationStatus::Failed;
+        operation.log.push(format!("Aborted: {}", reason));
+        operation.completed_at = Some(chrono::Utc::now().timestamp() as u64);
+        
+        let operation_id = operation.operation_id.clone();
+        self.active_operations.remove(&operation_id);
+        self.operation_history.push(operation);
+        operation_id
+    }
+    
+    /// Best effort: a failed snapshot is logged but doesn't block the upgrade
+    async fn pre_upgrade_snapshot(&self, operation: &mut PackageOperation) {
+        if SnapshotManager::snap_pac_installed() {
+            debug!("📸 snap-pac handles pacman snapshots, skipping our own");
+            return;
+        }
+        let snapshots = match SnapshotManager::detect(self.privilege_helper.clone()).await {
+            Some(snapshots) => snapshots,
+            None => return,
+        };
+        
+        match snapshots.create_snapshot("Before system upgrade").await {
+            Ok(snapshot) => {
+                operation.log.push(format!("Created snapshot {} before upgrading", snapshot.id));
+            }
+            Err(e) => {
+                warn!("⚠️ Pre-upgrade snapshot failed: {}", e);
+                operation.log.push(format!("Pre-upgrade snapshot failed: {}", e));
+            }
+        }
+    }
+    
+    /// Set `ParallelDownloads` (and `Color`/`ILoveCandy` with `eye_candy`) in the
+    /// `[options]` section of pacman.conf, uncommenting existing lines where possible.
+    /// Everything else in the file is left as it was.
+    pub async fn apply_pacman_config(&self) -> Result<()> {
+        if !(1..=MAX_PARALLEL_DOWNLOADS).contains(&self.parallel_downloads) {
+            return Err(anyhow!("parallel_downloads must be between 1 and {}, got {}", MAX_PARALLEL_DOWNLOADS, self.parallel_downloads));
+        }
+        
+        let content = fs::read_to_string(&self.pacman_conf)?;
+        let mut settings = vec![("ParallelDownloads", Some(self.parallel_downloads.to_string()))];
+        if self.eye_candy {
+            settings.push(("Color", None));
+            settings.push(("ILoveCandy", None));
+        }
+        
+        let updated = set_pacman_options(&content, &settings)
+            .ok_or_else(|| anyhow!("No [options] section in {}", self.pacman_conf.display()))?;
+        if updated == content {
+            debug!("⚙️ {} already up to date", self.pacman_conf.display());
+            return Ok(());
+        }
+        
+        self.write_system_file(&self.pacman_conf, &updated).await?;
+        info!("⚙️ Set ParallelDownloads = {} in {}", self.parallel_downloads, self.pacman_conf.display());
+        Ok(())
+    }
+    
+    /// Benchmark up-to-date mirrors from the Arch mirror status, optionally limited to one
+    /// country (name or code), and write the `count` fastest to `mirrorlist`. The previous
+    /// mirrorlist is kept as `mirrorlist.bak` for `restore_mirrorlist`; both writes go
+    /// through one privileged batch, so there is at most one prompt.
+    pub async fn rank_mirrors(&self, country: Option<&str>, count: usize) -> Result<Vec<MirrorInfo>> {
+        info!("🌐 Ranking mirrors (country: {})", country.unwrap_or("any"));
+        let client = reqwest::Client::new();
+        
+        let status: serde_json::Value = client.get(MIRROR_STATUS_URL)
+            .timeout(Duration::from_secs(30))
+            .send().await?
+            .error_for_status()?
+            .json().await?;
+        
+        let candidates = parse_mirror_status(&status, country);
+        if candidates.is_empty() {
+            return Err(anyhow!("No up-to-date mirrors found{}", country.map(|c| format!(" in {}", c)).unwrap_or_default()));
+        }
+        debug!("🌐 Benchmarking {} candidate mirrors", candidates.len());
+        
+        let mut ranked: Vec<MirrorInfo> = stream::iter(candidates)
+            .map(|mirror| {
+                let client = client.clone();
+                async move {
+                    let (latency_ms, speed_kib_per_sec) = benchmark_mirror(&client, &mirror.url).await?;
+                    Some(MirrorInfo { latency_ms, speed_kib_per_sec, ..mirror })
+                }
+            })
+            .buffer_unordered(MIRROR_BENCHMARK_CONCURRENCY)
+            .filter_map(|result| async move { result })
+            .collect()
+            .await;
+        
+        if ranked.is_empty() {
+            return Err(anyhow!("No mirror answered within {}s; mirrorlist left unchanged", MIRROR_BENCHMARK_TIMEOUT.as_secs()));
+        }
+        ranked.sort_by(|a, b| b.speed_kib_per_sec.partial_cmp(&a.speed_kib_per_sec).unwrap_or(Ordering::Equal));
+        ranked.truncate(count.max(1));
+        
+        let mirrorlist = format_mirrorlist(&ranked, &self.mirrorlist, Utc::now());
+        
+        // The backup goes first in the batch, so a refused backup leaves the list alone
+        let mut batch = PrivilegedBatch::new();
+        if self.mirrorlist.exists() {
+            let previous = fs::read_to_string(&self.mirrorlist)?;
+            batch.write(Self::mirrorlist_backup(&self.mirrorlist), previous);
+        }
+        batch.write(&self.mirrorlist, mirrorlist);
+        tokio::task::spawn_blocking(move || batch.apply_all()).await??;
+        
+        info!("✅ Wrote {} ranked mirrors to {}", ranked.len(), self.mirrorlist.display());
+        Ok(ranked)
+    }
+    
+    /// Put back the mirrorlist saved by the last `rank_mirrors`
+    pub async fn restore_mirrorlist(&self) -> Result<()> {
+        let backup = Self::mirrorlist_backup(&self.mirrorlist);
+        if !backup.exists() {
+            return Err(anyhow!("No mirrorlist backup at {}", backup.display()));
+        }
+        
+        let previous = fs::read_to_string(&backup)?;
+        let mut batch = PrivilegedBatch::new();
+        batch.write(&self.mirrorlist, previous);
+        tokio::task::spawn_blocking(move || batch.apply_all()).await??;
+        info!("↩️ Restored {} from backup", self.mirrorlist.display());
+        Ok(())
+    }
+    
+    fn mirrorlist_backup(mirrorlist: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.bak", mirrorlist.display()))
+    }
+    
+    /// Replace a root-owned file through `tee`, elevated when needed
+    async fn write_system_file(&self, path: &Path, content: &str) -> Result<()> {
+        let mut child = self.privileged_command("tee")
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(content.as_bytes()).await?;
+        }
+        
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(anyhow!("Failed to write {}: {}", path.display(), String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(())
+    }
+    
+    pub async fn clean_cache(&mut self, clean_all: bool) -> Result<String> {
         let operation_id = Uuid::new_v4().to_string();
         info!("🧹 Cleaning package cache (all: {})", clean_all);
         
@@ -550,7 +1428,7 @@ impl PackageManager {
         
         operation.log.push(format!("Cleaning cache: pacman {:?}", args));
         
-        let output = TokioCommand::new("pacman")
+        let output = self.pacman_command()
             .args(&args)
             .output()
             .await?;
@@ -574,11 +1452,114 @@ impl PackageManager {
         Ok(operation_id)
     }
     
+    /// What `apply_cleanup` would delete, without deleting anything: `paccache -d`
+    /// candidates, orphaned packages and journal space over DEFAULT_JOURNAL_SIZE_MB
+    pub async fn preview_cleanup(&self) -> CleanupPreview {
+        let mut preview = CleanupPreview::default();
+        
+        match TokioCommand::new("paccache").args(["-d", "-v"]).output().await {
+            Ok(output) if output.status.success() => {
+                for line in String::from_utf8_lossy(&output.stdout).lines() {
+                    if let Some(file) = self.parse_cached_package(line) {
+                        preview.cached_packages.push(file);
+                    }
+                }
+            }
+            Ok(output) => preview.notes.push(format!("paccache dry run failed: {}", String::from_utf8_lossy(&output.stderr).trim())),
+            Err(_) => preview.notes.push("paccache not installed (pacman-contrib); cache cleanup skipped".to_string()),
+        }
+        
+        match self.get_orphaned_packages().await {
+            Ok(orphans) => preview.orphans = orphans,
+            Err(e) => preview.notes.push(format!("Could not list orphans: {}", e)),
+        }
+        
+        match JournalReader::new().disk_usage() {
+            Some(journal_bytes) => {
+                preview.journal_bytes = journal_bytes;
+                preview.journal_reclaimable_bytes = journal_bytes.saturating_sub(DEFAULT_JOURNAL_SIZE_MB * 1024 * 1024);
+            }
+            None => preview.notes.push("Could not read journal disk usage".to_string()),
+        }
+        
+        preview.removes_installed_version = preview.cached_packages.iter().any(|p| p.is_installed_version);
+        preview.total_reclaimable_bytes = preview.cached_packages.iter().map(|p| p.size_bytes).sum::<u64>()
+            + preview.orphans.iter().map(|p| p.installed_size).sum::<u64>()
+            + preview.journal_reclaimable_bytes;
+        
+        info!("🧹 Cleanup preview: {}", preview.summary());
+        preview
+    }
+    
+    /// A paccache candidate line: a package file name or path,
+    /// "<name>-<pkgver>-<pkgrel>-<arch>.pkg.tar.<ext>"
+    fn parse_cached_package(&self, line: &str) -> Option<CachedPackageFile> {
+        let file = line.split_whitespace().last()?;
+        if !file.contains(".pkg.tar") || file.ends_with(".sig") {
+            return None;
+        }
+        let path = if Path::new(file).is_absolute() {
+            PathBuf::from(file)
+        } else {
+            Path::new(PACKAGE_CACHE_DIR).join(file)
+        };
+        
+        let file_name = path.file_name()?.to_str()?;
+        let stem = &file_name[..file_name.find(".pkg.tar")?];
+        let mut parts = stem.rsplitn(4, '-');
+        let _arch = parts.next()?;
+        let pkgrel = parts.next()?;
+        let pkgver = parts.next()?;
+        let name = parts.next()?.to_string();
+        let version = format!("{}-{}", pkgver, pkgrel);
+        
+        let is_installed_version = self.installed_packages.get(&name)
+            .map(|installed| installed.version == version)
+            .unwrap_or(false);
+        let size_bytes = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        
+        Some(CachedPackageFile { path, name, version, size_bytes, is_installed_version })
+    }
+    
+    /// Remove what `preview` lists: paccache's old versions, the orphans, and
+    /// journal space over DEFAULT_JOURNAL_SIZE_MB. Returns one message per step.
+    pub async fn apply_cleanup(&mut self, preview: &CleanupPreview) -> Result<String> {
+        let mut results = Vec::new();
+        
+        if !preview.cached_packages.is_empty() {
+            let output = self.privileged_command("paccache").arg("-r").output().await?;
+            if output.status.success() {
+                results.push(format!("Removed {} cached package files", preview.cached_packages.len()));
+            } else {
+                return Err(anyhow!("paccache -r failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+            }
+        }
+        
+        if !preview.orphans.is_empty() {
+            let names = preview.orphans.iter().map(|p| p.name.clone()).collect();
+            let operation_id = self.remove_packages(names, true, false).await?;
+            results.push(format!("Removing {} orphans (operation {})", preview.orphans.len(), operation_id));
+        }
+        
+        if preview.journal_reclaimable_bytes > 0 {
+            match JournalReader::new().vacuum(VacuumTarget::SizeMb(DEFAULT_JOURNAL_SIZE_MB)) {
+                Ok(freed) => results.push(format!("Journal vacuumed, {:.1} MiB freed", freed as f64 / 1048576.0)),
+                Err(e) => warn!("⚠️ Journal vacuum failed: {}", e),
+            }
+        }
+        
+        if results.is_empty() {
+            return Ok("Nothing to clean".to_string());
+        }
+        info!("🧹 Cleanup applied: {}", results.join(", "));
+        Ok(results.join(", "))
+    }
+    
     pub async fn get_orphaned_packages(&self) -> Result<Vec<PackageInfo>> {
         debug!("🔍 Finding orphaned packages");
         
         let output = TokioCommand::new("pacman")
-            .args(&["-Qdt"])
+            .args(&["-Qtdq"])
             .output()
             .await?;
         
@@ -625,6 +1606,7 @@ impl PackageManager {
                 
                 if let Some(mut package) = self.installed_packages.get(&name).cloned() {
                     package.version = format!("{} -> {}", current_version, new_version);
+                    package.pinned = self.pinned_packages.contains(&name);
                     updates.push(package);
                 }
             }
@@ -634,9 +1616,49 @@ impl PackageManager {
         Ok(updates)
     }
     
-    pub async fn get_repository_stats(&self) -> Result<RepositoryStats> {
+    /// Repository statistics. The pacman queries behind them are cached for
+    /// `stats_cache_ttl`, so repeated dashboard refreshes don't fork pacman each time.
+    pub async fn get_repository_stats(&mut self) -> Result<RepositoryStats> {
         debug!("📊 Gathering repository statistics");
         
+        let cache = self.cached_stats().await?;
+        let total_packages = cache.total_packages;
+        let available_updates = cache.updates.len() as u32;
+        let orphaned_packages = cache.orphans.len() as u32;
+        
+        let installed_packages = self.installed_packages.len() as u32;
+        
+        // Get cache size
+        let cache_size = self.get_cache_size().await.unwrap_or(0);
+        
+        Ok(RepositoryStats {
+            total_packages,
+            installed_packages,
+            available_updates,
+            orphaned_packages,
+            cache_size,
+            journal_size: JournalReader::new().disk_usage().unwrap_or(0),
+            last_update: None, // Would need to read from pacman logs
+        })
+    }
+    
+    /// Cached available updates, refreshed once `stats_cache_ttl` has passed
+    pub async fn get_cached_updates(&mut self) -> Result<Vec<PackageInfo>> {
+        Ok(self.cached_stats().await?.updates)
+    }
+    
+    /// Cached orphaned packages, refreshed once `stats_cache_ttl` has passed
+    pub async fn get_cached_orphans(&mut self) -> Result<Vec<PackageInfo>> {
+        Ok(self.cached_stats().await?.orphans)
+    }
+    
+    async fn cached_stats(&mut self) -> Result<StatsCache> {
+        if let Some(cache) = &self.stats_cache {
+            if cache.fetched_at.elapsed() < self.stats_cache_ttl {
+                return Ok(cache.clone());
+            }
+        }
+        
         let total_output = TokioCommand::new("pacman")
             .args(&["-Sl"])
             .output()
@@ -648,23 +1670,69 @@ impl PackageManager {
             0
         };
         
-        let installed_packages = self.installed_packages.len() as u32;
+        let cache = StatsCache {
+            fetched_at: Instant::now(),
+            total_packages,
+            updates: self.get_available_updates().await?,
+            orphans: self.get_orphaned_packages().await?,
+        };
+        self.stats_cache = Some(cache.clone());
         
-        let available_updates = self.get_available_updates().await?.len() as u32;
+        Ok(cache)
+    }
+    
+    /// Verify every installed file against the package database (`pacman -Qkk`).
+    /// Takes a while on a full system; use `verify_package_files` for a quick check.
+    pub async fn verify_installed_files(&self) -> Vec<FileIntegrityIssue> {
+        self.run_file_verification(None).await
+    }
+    
+    pub async fn verify_package_files(&self, package: &str) -> Vec<FileIntegrityIssue> {
+        self.run_file_verification(Some(package)).await
+    }
+    
+    /// Read-only, so this runs unelevated; root-only files are skipped with a warning
+    async fn run_file_verification(&self, package: Option<&str>) -> Vec<FileIntegrityIssue> {
+        info!("🔎 Verifying installed files for {}", package.unwrap_or("all packages"));
+        
+        let mut command = TokioCommand::new("pacman");
+        command.arg("-Qkk");
+        if let Some(package) = package {
+            command.arg(package);
+        }
         
-        let orphaned_packages = self.get_orphaned_packages().await?.len() as u32;
+        // pacman exits non-zero whenever it finds a mismatch, so the status says nothing
+        let output = match command.output().await {
+            Ok(output) => output,
+            Err(e) => {
+                error!("❌ Failed to run pacman -Qkk: {}", e);
+                return Vec::new();
+            }
+        };
         
-        // Get cache size
-        let cache_size = self.get_cache_size().await.unwrap_or(0);
+        let mut issues = Vec::new();
+        let mut unreadable = 0;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        for line in stdout.lines().chain(stderr.lines()) {
+            match parse_qkk_line(line) {
+                Some(issue) => issues.push(issue),
+                None if line.contains("Permission denied") => unreadable += 1,
+                None => {}
+            }
+        }
         
-        Ok(RepositoryStats {
-            total_packages,
-            installed_packages,
-            available_updates,
-            orphaned_packages,
-            cache_size,
-            last_update: None, // Would need to read from pacman logs
-        })
+        if unreadable > 0 {
+            warn!("⚠️ {} files could not be read for verification; run as root for a full check", unreadable);
+        }
+        
+        let suspicious = issues.iter().filter(|i| i.is_suspicious()).count();
+        if suspicious > 0 {
+            warn!("🚨 {} packaged binaries differ from the package database", suspicious);
+        }
+        info!("🔎 File verification found {} mismatches", issues.len());
+        
+        issues
     }
     
     async fn get_cache_size(&self) -> Result<u64> {
@@ -699,6 +1767,11 @@ impl PackageManager {
         self.active_operations.values().cloned().collect()
     }
     
+    /// Sender to subscribe to for live progress of streamed installs and upgrades
+    pub fn progress_sender(&self) -> tokio::sync::broadcast::Sender<PackageOperation> {
+        self.progress.clone()
+    }
+    
     pub fn get_operation_history(&self, limit: Option<usize>) -> Vec<PackageOperation> {
         let mut history = self.operation_history.clone();
         history.sort_by(|a, b| b.started_at.cmp(&a.started_at)); // Most recent first
@@ -739,6 +1812,9 @@ impl PackageManager {
                     first_submitted: None,
                     url: None,
                     aur_package: false,
+                    pinned: false,
+                    votes: None,
+                    popularity: None,
                 });
             } else if let Some(ref mut pkg) = current_package {
                 if line.starts_with("Version") {
@@ -751,11 +1827,11 @@ impl PackageManager {
                     pkg.repository = line.split(':').nth(1).unwrap_or("").trim().to_string();
                 } else if line.starts_with("Installed Size") {
                     if let Some(size_str) = line.split(':').nth(1) {
-                        pkg.installed_size = self.parse_size(size_str.trim());
+                        pkg.installed_size = Self::parse_size(size_str.trim());
                     }
                 } else if line.starts_with("Download Size") {
                     if let Some(size_str) = line.split(':').nth(1) {
-                        pkg.download_size = self.parse_size(size_str.trim());
+                        pkg.download_size = Self::parse_size(size_str.trim());
                     }
                 } else if line.starts_with("Depends On") {
                     let deps = line.split(':').nth(1).unwrap_or("").trim();
@@ -764,6 +1840,13 @@ impl PackageManager {
                             .map(|s| s.to_string())
                             .collect();
                     }
+                } else if line.starts_with("Provides") {
+                    let provides = line.split(':').nth(1).unwrap_or("").trim();
+                    if provides != "None" {
+                        pkg.provides = provides.split_whitespace()
+                            .map(|s| s.to_string())
+                            .collect();
+                    }
                 }
             }
         }
@@ -808,7 +1891,7 @@ impl PackageManager {
                             description,
                             architecture: "x86_64".to_string(),
                             repository,
-                            installed: self.installed_packages.contains_key(&repo_name[1]),
+                            installed: self.installed_packages.contains_key(repo_name[1]),
                             installed_size: 0,
                             download_size: 0,
                             dependencies: Vec::new(),
@@ -822,6 +1905,9 @@ impl PackageManager {
                             first_submitted: None,
                             url: None,
                             aur_package: false,
+                            pinned: false,
+                            votes: None,
+                            popularity: None,
                         });
                         
                         i += 1; // Skip description line
@@ -834,40 +1920,63 @@ impl PackageManager {
         packages
     }
     
-    fn parse_aur_search(&self, output: &str) -> Vec<PackageInfo> {
+    /// Parse the two-line AUR search format used by yay/paru:
+    ///
+    /// ```text
+    /// aur/name 1.2.3-1 (+123 4.56) (Installed)        yay
+    /// aur/name 1.2.3-1 [+123 ~4.56] [Installed]       paru
+    ///     Description on the following indented line
+    /// ```
+    fn parse_aur_search(output: &str) -> Vec<PackageInfo> {
         let mut packages = Vec::new();
+        let lines: Vec<&str> = output.lines().collect();
         
-        for line in output.lines() {
-            if line.starts_with("aur/") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 3 {
-                    let name = parts[0].strip_prefix("aur/").unwrap_or(parts[0]).to_string();
-                    let version = parts[1].to_string();
-                    let votes_downloads = parts[2]; // Format: (+votes, downloads%)
-                    
-                    packages.push(PackageInfo {
-                        name,
-                        version,
-                        description: parts[3..].join(" "),
-                        architecture: "any".to_string(),
-                        repository: "aur".to_string(),
-                        installed: false,
-                        installed_size: 0,
-                        download_size: 0,
-                        dependencies: Vec::new(),
-                        optional_dependencies: Vec::new(),
-                        conflicts: Vec::new(),
-                        provides: Vec::new(),
-                        groups: Vec::new(),
-                        licenses: Vec::new(),
-                        maintainer: None,
-                        last_modified: None,
-                        first_submitted: None,
-                        url: None,
-                        aur_package: true,
-                    });
-                }
+        for (index, line) in lines.iter().enumerate() {
+            if !line.starts_with("aur/") {
+                continue;
             }
+            
+            let mut parts = line.split_whitespace();
+            let name = match parts.next().and_then(|p| p.strip_prefix("aur/")) {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => continue,
+            };
+            let version = parts.next().unwrap_or("").to_string();
+            let rest: Vec<&str> = parts.collect();
+            
+            let (votes, popularity) = parse_aur_votes(&rest);
+            let installed = rest.iter()
+                .any(|p| p.to_lowercase().contains("installed"));
+            
+            let description = lines.get(index + 1)
+                .filter(|next| next.starts_with(char::is_whitespace))
+                .map(|next| next.trim().to_string())
+                .unwrap_or_default();
+            
+            packages.push(PackageInfo {
+                name,
+                version,
+                description,
+                architecture: "any".to_string(),
+                repository: "aur".to_string(),
+                installed,
+                installed_size: 0,
+                download_size: 0,
+                dependencies: Vec::new(),
+                optional_dependencies: Vec::new(),
+                conflicts: Vec::new(),
+                provides: Vec::new(),
+                groups: Vec::new(),
+                licenses: Vec::new(),
+                maintainer: None,
+                last_modified: None,
+                first_submitted: None,
+                url: None,
+                aur_package: true,
+                pinned: false,
+                votes,
+                popularity,
+            });
         }
         
         packages
@@ -894,6 +2003,9 @@ impl PackageManager {
             first_submitted: None,
             url: None,
             aur_package: true,
+            pinned: false,
+            votes: None,
+            popularity: None,
         };
         
         for line in output.lines() {
@@ -924,20 +2036,611 @@ impl PackageManager {
         }
     }
     
-    fn parse_size(&self, size_str: &str) -> u64 {
+    /// `.SRCINFO` is `key = value` lines; repeated keys (depends etc.) accumulate.
+    /// Only the pkgbase section is read, which holds the shared version and dependencies.
+    fn parse_srcinfo(&self, srcinfo: &str) -> HashMap<String, Vec<String>> {
+        let mut fields: HashMap<String, Vec<String>> = HashMap::new();
+        
+        for line in srcinfo.lines() {
+            let line = line.trim();
+            if line.starts_with("pkgname") && !fields.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(" = ") {
+                fields.entry(key.to_string()).or_default().push(value.to_string());
+            }
+        }
+        
+        fields
+    }
+    
+    /// Parse pacman sizes like "12.34 MiB", "12,34 MiB" (decimal comma locales)
+    /// or "1,234.50 KiB". IEC units are powers of 1024, SI units powers of 1000.
+    fn parse_size(size_str: &str) -> u64 {
         let parts: Vec<&str> = size_str.split_whitespace().collect();
-        if parts.len() >= 2 {
-            if let Ok(value) = parts[0].replace(",", "").parse::<f64>() {
-                let multiplier = match parts[1] {
-                    "KiB" => 1024,
-                    "MiB" => 1024 * 1024,
-                    "GiB" => 1024 * 1024 * 1024,
-                    "TiB" => 1024u64 * 1024 * 1024 * 1024,
-                    _ => 1,
-                };
-                return (value * multiplier as f64) as u64;
+        if parts.len() < 2 {
+            warn!("⚠️ Unrecognized package size '{}'", size_str);
+            return 0;
+        }
+        
+        let number = parts[0];
+        let normalized = match (number.rfind(','), number.rfind('.')) {
+            // Both present: whichever comes last is the decimal separator
+            (Some(comma), Some(dot)) if comma > dot => number.replace('.', "").replace(',', "."),
+            (Some(_), Some(_)) => number.replace(',', ""),
+            // Comma only: pacman prints two decimals, so ",dd" is a decimal comma
+            (Some(comma), None) if number.len() - comma - 1 != 3 => number.replace(',', "."),
+            (Some(_), None) => number.replace(',', ""),
+            (None, _) => number.to_string(),
+        };
+        
+        let value = match normalized.parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => {
+                warn!("⚠️ Unrecognized package size '{}'", size_str);
+                return 0;
             }
+        };
+        
+        let multiplier: u64 = match parts[1] {
+            "B" => 1,
+            "KiB" => 1024,
+            "MiB" => 1024 * 1024,
+            "GiB" => 1024 * 1024 * 1024,
+            "TiB" => 1024u64 * 1024 * 1024 * 1024,
+            "KB" | "kB" => 1000,
+            "MB" => 1000 * 1000,
+            "GB" => 1000 * 1000 * 1000,
+            "TB" => 1000u64 * 1000 * 1000 * 1000,
+            unit => {
+                warn!("⚠️ Unrecognized size unit '{}' in '{}'", unit, size_str);
+                return 0;
+            }
+        };
+        
+        (value * multiplier as f64).round() as u64
+    }
+}
+
+/// pacman.conf with each (key, value) set in `[options]`; `None` values are bare flags
+/// like `Color`. Commented-out lines for a key are uncommented in place, missing keys
+/// go after the section's last setting. None if there is no `[options]` section.
+fn set_pacman_options(content: &str, settings: &[(&str, Option<String>)]) -> Option<String> {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let start = lines.iter().position(|line| line.trim() == "[options]")?;
+    let end = lines.iter().enumerate().skip(start + 1)
+        .find(|(_, line)| line.trim_start().starts_with('['))
+        .map(|(i, _)| i)
+        .unwrap_or(lines.len());
+    
+    let key_of = |line: &str| -> String {
+        line.trim().trim_start_matches('#').split('=').next().unwrap_or("").trim().to_string()
+    };
+    
+    let mut missing = Vec::new();
+    for (key, value) in settings {
+        let setting = match value {
+            Some(value) => format!("{} = {}", key, value),
+            None => key.to_string(),
+        };
+        // A live setting wins over commented-out examples of it
+        let existing = (start + 1..end).find(|&i| key_of(&lines[i]) == *key && !lines[i].trim().starts_with('#'))
+            .or_else(|| (start + 1..end).find(|&i| key_of(&lines[i]) == *key));
+        match existing {
+            Some(i) => lines[i] = setting,
+            None => missing.push(setting),
+        }
+    }
+    
+    if !missing.is_empty() {
+        let last_setting = (start..end).rev()
+            .find(|&i| !lines[i].trim().is_empty())
+            .unwrap_or(start);
+        for (offset, setting) in missing.into_iter().enumerate() {
+            lines.insert(last_setting + 1 + offset, setting);
         }
-        0
+    }
+    
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+/// Dependency graph over `installed`. A dependency on a virtual name ("sh",
+/// "java-runtime") links to every installed package providing it, so none of them
+/// can be removed without the dependent showing up.
+fn dependency_graph(installed: &HashMap<String, PackageInfo>) -> DependencyGraph {
+    // Strip version constraints ("glibc>=2.38", "sh=5") down to the bare name
+    let bare_name = |spec: &str| -> String {
+        spec.split(|c| c == '<' || c == '>' || c == '=').next().unwrap_or(spec).to_string()
+    };
+    
+    let mut providers: HashMap<String, Vec<String>> = HashMap::new();
+    for package in installed.values() {
+        for provided in &package.provides {
+            providers.entry(bare_name(provided)).or_default().push(package.name.clone());
+        }
+    }
+    for names in providers.values_mut() {
+        names.sort();
+    }
+    
+    let mut graph = DependencyGraph::default();
+    for package in installed.values() {
+        let mut resolved = Vec::new();
+        for dependency in &package.dependencies {
+            let name = bare_name(dependency);
+            let targets = if installed.contains_key(&name) {
+                vec![name]
+            } else {
+                providers.get(&name).cloned().unwrap_or_default()
+            };
+            for target in targets {
+                if !resolved.contains(&target) {
+                    graph.dependents.entry(target.clone()).or_default().push(package.name.clone());
+                    resolved.push(target);
+                }
+            }
+        }
+        graph.dependencies.insert(package.name.clone(), resolved);
+    }
+    
+    for dependents in graph.dependents.values_mut() {
+        dependents.sort();
+    }
+    
+    graph
+}
+
+/// Active, fully synced mirrors from the archlinux.org status JSON, best score first
+fn parse_mirror_status(status: &serde_json::Value, country: Option<&str>) -> Vec<MirrorInfo> {
+    let cutoff = Utc::now() - chrono::Duration::hours(MIRROR_MAX_SYNC_AGE_HOURS);
+    let mut mirrors: Vec<(f64, MirrorInfo)> = status["urls"].as_array().cloned().unwrap_or_default()
+        .iter()
+        .filter(|m| m["active"].as_bool().unwrap_or(false))
+        .filter(|m| matches!(m["protocol"].as_str(), Some("https") | Some("http")))
+        .filter(|m| m["completion_pct"].as_f64().map(|pct| pct >= 1.0).unwrap_or(false))
+        .filter(|m| {
+            country.map(|wanted| {
+                [&m["country"], &m["country_code"]].iter()
+                    .filter_map(|v| v.as_str())
+                    .any(|c| c.eq_ignore_ascii_case(wanted))
+            }).unwrap_or(true)
+        })
+        .filter_map(|m| {
+            let last_sync = m["last_sync"].as_str()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|d| d.with_timezone(&Utc))?;
+            if last_sync < cutoff {
+                return None;
+            }
+            // Lower score is better; it folds in sync delay and reliability
+            let score = m["score"].as_f64().unwrap_or(f64::MAX);
+            Some((score, MirrorInfo {
+                url: m["url"].as_str()?.to_string(),
+                country: m["country"].as_str().unwrap_or("").to_string(),
+                last_sync: Some(last_sync),
+                latency_ms: 0,
+                speed_kib_per_sec: 0.0,
+            }))
+        })
+        .collect();
+    
+    mirrors.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    // Benchmarking every mirror would take minutes; the score already weeds out stragglers
+    mirrors.into_iter().take(MIRROR_BENCHMARK_CONCURRENCY * 4).map(|(_, mirror)| mirror).collect()
+}
+
+/// A pacman mirrorlist serving `ranked` in order, with each mirror's benchmark as a comment
+fn format_mirrorlist(ranked: &[MirrorInfo], mirrorlist: &Path, ranked_at: DateTime<Utc>) -> String {
+    let mut content = String::new();
+    // Writing to a String can't fail
+    let _ = writeln!(content, "# Managed by AI SysAdmin Supreme, ranked {}", ranked_at.format("%Y-%m-%d %H:%M UTC"));
+    let _ = writeln!(content, "# Previous mirrorlist: {}.bak", mirrorlist.display());
+    let _ = writeln!(content);
+    for mirror in ranked {
+        let _ = writeln!(content, "# {} - {:.0} KiB/s, {} ms", mirror.country, mirror.speed_kib_per_sec, mirror.latency_ms);
+        let _ = writeln!(content, "Server = {}$repo/os/$arch", mirror.url);
+    }
+    content
+}
+
+/// (latency in ms, KiB/s) for downloading core.db from `url`, None on error or timeout
+async fn benchmark_mirror(client: &reqwest::Client, url: &str) -> Option<(u64, f64)> {
+    let started = Instant::now();
+    let download = async {
+        let response = client.get(format!("{}core/os/x86_64/core.db", url))
+            .send().await.ok()?
+            .error_for_status().ok()?;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let bytes = response.bytes().await.ok()?;
+        Some((latency_ms, bytes.len()))
+    };
+    
+    let (latency_ms, size) = tokio::time::timeout(MIRROR_BENCHMARK_TIMEOUT, download).await.ok()??;
+    let seconds = started.elapsed().as_secs_f64().max(0.001);
+    Some((latency_ms, size as f64 / 1024.0 / seconds))
+}
+
+/// Transaction progress from pacman's plain output. With piped output pacman draws
+/// no progress bars, so it runs with --noprogressbar and progress comes from the
+/// `Packages (N)` summary and the one line it prints per package in each stage.
+/// Downloads cover the first 40%, the checking stages the next 10% and the
+/// `installing foo...` lines the rest.
+#[derive(Debug, Default)]
+struct PacmanProgress {
+    /// Packages in the transaction; the requested count until pacman lists them all
+    total: usize,
+    downloaded: usize,
+    installed: usize,
+}
+
+impl PacmanProgress {
+    fn new(expected: usize) -> Self {
+        Self { total: expected, ..Default::default() }
+    }
+    
+    /// Overall fraction (0.0-1.0) after `line`, or None if it doesn't move progress
+    fn update(&mut self, line: &str) -> Option<f64> {
+        if let Some(count) = parse_package_count(line) {
+            self.total = count;
+            return Some(0.0);
+        }
+        
+        let verb = line.split_whitespace().next().unwrap_or("");
+        let stage_line = line.ends_with("...");
+        if stage_line && ["installing", "upgrading", "reinstalling", "downgrading", "removing"].contains(&verb) {
+            self.installed += 1;
+            return Some(0.5 + 0.5 * self.fraction(self.installed));
+        }
+        // pacman 6 prints " foo-1.0-1-x86_64 downloading..." per file
+        if stage_line && line.trim_end_matches("...").ends_with("downloading") {
+            self.downloaded += 1;
+            return Some(0.4 * self.fraction(self.downloaded));
+        }
+        if line.starts_with("checking ") || line.starts_with(":: Processing package changes") {
+            return Some(0.45);
+        }
+        None
+    }
+    
+    fn fraction(&self, done: usize) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (done as f64 / self.total as f64).min(1.0)
+    }
+}
+
+/// N from the `Packages (N) foo-1.0 bar-2.0` transaction summary
+fn parse_package_count(line: &str) -> Option<usize> {
+    let rest = line.strip_prefix("Packages (")?;
+    rest.split(')').next()?.trim().parse().ok()
+}
+
+/// Votes and popularity from the `(+N x.xx)` (yay) or `[+N ~x.xx]` (paru)
+/// fields of an AUR search line
+fn parse_aur_votes(fields: &[&str]) -> (Option<u32>, Option<f64>) {
+    let start = fields.iter().position(|field| {
+        field.strip_prefix(|c| c == '(' || c == '[')
+            .map_or(false, |rest| rest.starts_with('+'))
+    });
+    let start = match start {
+        Some(start) => start,
+        None => return (None, None),
+    };
+    
+    let votes = fields[start][2..].parse::<u32>().ok();
+    let popularity = fields.get(start + 1)
+        .map(|field| field.trim_start_matches('~').trim_end_matches(|c| c == ')' || c == ']' || c == '%'))
+        .and_then(|value| value.parse::<f64>().ok());
+    (votes, popularity)
+}
+
+/// Package names as makepkg allows them: lowercase alphanumerics and `@._+-`,
+/// not starting with a dot or hyphen
+fn is_valid_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.starts_with('-')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "@._+-".contains(c))
+}
+
+/// Compare pacman versions (`[epoch:]pkgver-pkgrel`) the way vercmp does for
+/// common cases: epoch first, then numeric/alphabetic segments, then pkgrel.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split_epoch(version: &str) -> (u64, &str) {
+        match version.split_once(':') {
+            Some((epoch, rest)) => (epoch.parse().unwrap_or(0), rest),
+            None => (0, version),
+        }
+    }
+    
+    fn compare_segments(a: &str, b: &str) -> Ordering {
+        let segments = |s: &str| -> Vec<String> {
+            let mut result = Vec::new();
+            let mut current = String::new();
+            for c in s.chars() {
+                if !c.is_ascii_alphanumeric() {
+                    if !current.is_empty() {
+                        result.push(std::mem::take(&mut current));
+                    }
+                } else if !current.is_empty() && current.chars().last().unwrap().is_ascii_digit() != c.is_ascii_digit() {
+                    result.push(std::mem::replace(&mut current, c.to_string()));
+                } else {
+                    current.push(c);
+                }
+            }
+            if !current.is_empty() {
+                result.push(current);
+            }
+            result
+        };
+        
+        let (left, right) = (segments(a), segments(b));
+        for (x, y) in left.iter().zip(right.iter()) {
+            let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                // A numeric segment is newer than an alphabetic one (1.0 > 1.0rc)
+                (Ok(_), Err(_)) => Ordering::Greater,
+                (Err(_), Ok(_)) => Ordering::Less,
+                (Err(_), Err(_)) => x.cmp(y),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        
+        // Past the common segments, an extra number is newer (1.0.1 > 1.0) and an
+        // extra letter segment is a pre-release, so older (1.0rc < 1.0)
+        let common = left.len().min(right.len());
+        let is_number = |segment: &String| segment.parse::<u64>().is_ok();
+        match (left.get(common), right.get(common)) {
+            (Some(extra), None) => if is_number(extra) { Ordering::Greater } else { Ordering::Less },
+            (None, Some(extra)) => if is_number(extra) { Ordering::Less } else { Ordering::Greater },
+            _ => Ordering::Equal,
+        }
+    }
+    
+    let (epoch_a, rest_a) = split_epoch(a);
+    let (epoch_b, rest_b) = split_epoch(b);
+    let (ver_a, rel_a) = rest_a.rsplit_once('-').unwrap_or((rest_a, "0"));
+    let (ver_b, rel_b) = rest_b.rsplit_once('-').unwrap_or((rest_b, "0"));
+    
+    epoch_a.cmp(&epoch_b)
+        .then_with(|| compare_segments(ver_a, ver_b))
+        .then_with(|| compare_segments(rel_a, rel_b))
+}
+
+/// One `warning: <pkg>: <path> (<reason>)` line from `pacman -Qkk`
+fn parse_qkk_line(line: &str) -> Option<FileIntegrityIssue> {
+    let rest = line.strip_prefix("warning: ")?;
+    let (package, rest) = rest.split_once(": ")?;
+    let open = rest.rfind(" (")?;
+    let path = &rest[..open];
+    let reason = rest[open + 2..].trim_end_matches(')');
+    
+    let mismatch = match reason {
+        r if r.contains("checksum mismatch") => IntegrityMismatch::Checksum,
+        "Size mismatch" => IntegrityMismatch::Size,
+        "Permissions mismatch" => IntegrityMismatch::Permissions,
+        "UID mismatch" | "GID mismatch" => IntegrityMismatch::Ownership,
+        "Modification time mismatch" => IntegrityMismatch::ModificationTime,
+        "File type mismatch" => IntegrityMismatch::FileType,
+        "Symlink path mismatch" => IntegrityMismatch::SymlinkTarget,
+        "No such file or directory" => IntegrityMismatch::Missing,
+        // Unreadable files are not mismatches
+        "Permission denied" => return None,
+        other => IntegrityMismatch::Other(other.to_string()),
+    };
+    
+    Some(FileIntegrityIssue {
+        package: package.to_string(),
+        path: PathBuf::from(path),
+        mismatch,
+        file_class: classify_package_file(path),
+    })
+}
+
+fn classify_package_file(path: &str) -> FileClass {
+    if path.starts_with("/etc/") || path.ends_with(".conf") {
+        return FileClass::Config;
+    }
+    
+    let binary_dirs = ["/usr/bin/", "/usr/sbin/", "/bin/", "/sbin/", "/usr/lib/systemd/", "/boot/"];
+    let is_library = path.starts_with("/usr/lib/")
+        && (path.ends_with(".so") || path.contains(".so.") || path.ends_with(".ko") || path.ends_with(".ko.zst"));
+    if is_library || binary_dirs.iter().any(|dir| path.starts_with(dir)) {
+        return FileClass::Binary;
+    }
+    
+    FileClass::Other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn compare_versions_matches_vercmp() {
+        let cases = [
+            ("1.0", "1.0", Ordering::Equal),
+            ("1.0.1", "1.0", Ordering::Greater),
+            ("1.0rc", "1.0", Ordering::Less),
+            ("1.0alpha", "1.0beta", Ordering::Less),
+            ("1.0a", "1.0", Ordering::Less),
+            ("1.0rc1", "1.0", Ordering::Less),
+            ("1.10", "1.9", Ordering::Greater),
+            ("1:1.0", "2.0", Ordering::Greater),
+            ("1.0-2", "1.0-10", Ordering::Less),
+            ("1.0-1", "1.0-1.1", Ordering::Less),
+            ("2.0-1", "1.9-5", Ordering::Greater),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(compare_versions(a, b), expected, "{} vs {}", a, b);
+            assert_eq!(compare_versions(b, a), expected.reverse(), "{} vs {}", b, a);
+        }
+    }
+    
+    #[test]
+    fn package_names_are_checked_before_reaching_paths_and_urls() {
+        for name in ["yay", "google-chrome", "python-pip", "lib32-mesa", "gtk+3", "foo@bar", "dropbox.cli"] {
+            assert!(is_valid_package_name(name), "{}", name);
+        }
+        for name in ["", "..", ".hidden", "-rf", "../../etc", "foo/bar", "Yay", "foo bar", "foo.git?x=1", "name\n"] {
+            assert!(!is_valid_package_name(name), "{}", name);
+        }
+    }
+    
+    fn installed(name: &str, dependencies: &[&str], provides: &[&str]) -> PackageInfo {
+        PackageInfo {
+            name: name.to_string(),
+            version: "1.0-1".to_string(),
+            description: String::new(),
+            architecture: "x86_64".to_string(),
+            repository: "extra".to_string(),
+            installed: true,
+            installed_size: 0,
+            download_size: 0,
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            optional_dependencies: Vec::new(),
+            conflicts: Vec::new(),
+            provides: provides.iter().map(|p| p.to_string()).collect(),
+            groups: Vec::new(),
+            licenses: Vec::new(),
+            maintainer: None,
+            last_modified: None,
+            first_submitted: None,
+            url: None,
+            aur_package: false,
+            pinned: false,
+            votes: None,
+            popularity: None,
+        }
+    }
+    
+    #[test]
+    fn virtual_dependencies_link_to_every_provider() {
+        let packages: HashMap<String, PackageInfo> = [
+            installed("jdk17-openjdk", &[], &["java-runtime=17"]),
+            installed("jdk21-openjdk", &[], &["java-runtime=21"]),
+            installed("bash", &["glibc>=2.38"], &["sh"]),
+            installed("glibc", &[], &[]),
+            installed("minecraft-launcher", &["java-runtime>=17", "sh"], &[]),
+        ].into_iter().map(|p| (p.name.clone(), p)).collect();
+        
+        let graph = dependency_graph(&packages);
+        assert_eq!(graph.dependencies["minecraft-launcher"], ["jdk17-openjdk", "jdk21-openjdk", "bash"]);
+        assert_eq!(graph.dependents_of("jdk17-openjdk"), ["minecraft-launcher"]);
+        assert_eq!(graph.dependents_of("jdk21-openjdk"), ["minecraft-launcher"]);
+        assert_eq!(graph.dependents_of("glibc"), ["bash"]);
+        assert_eq!(graph.dependency_closure("minecraft-launcher"), ["bash", "glibc", "jdk17-openjdk", "jdk21-openjdk"]);
+    }
+    
+    #[test]
+    fn mirrorlist_has_one_line_per_entry() {
+        let ranked = [
+            MirrorInfo {
+                url: "https://fast.example/archlinux/".to_string(),
+                country: "Germany".to_string(),
+                last_sync: None,
+                latency_ms: 12,
+                speed_kib_per_sec: 40960.4,
+            },
+            MirrorInfo {
+                url: "https://slow.example/arch/".to_string(),
+                country: "France".to_string(),
+                last_sync: None,
+                latency_ms: 80,
+                speed_kib_per_sec: 1024.0,
+            },
+        ];
+        let ranked_at = DateTime::parse_from_rfc3339("2024-05-01T08:30:00Z").unwrap().with_timezone(&Utc);
+        
+        let content = format_mirrorlist(&ranked, Path::new(PACMAN_MIRRORLIST), ranked_at);
+        assert_eq!(content, "\
+# Managed by AI SysAdmin Supreme, ranked 2024-05-01 08:30 UTC
+# Previous mirrorlist: /etc/pacman.d/mirrorlist.bak
+
+# Germany - 40960 KiB/s, 12 ms
+Server = https://fast.example/archlinux/$repo/os/$arch
+# France - 1024 KiB/s, 80 ms
+Server = https://slow.example/arch/$repo/os/$arch
+");
+    }
+    
+    #[test]
+    fn parse_size_handles_every_unit() {
+        let cases = [
+            ("512.00 B", 512),
+            ("1.00 KiB", 1024),
+            ("12.34 MiB", 12_939_428),
+            ("2.00 GiB", 2 * 1024 * 1024 * 1024),
+            ("1.00 TiB", 1024u64.pow(4)),
+            ("1.50 KB", 1500),
+            ("1.50 kB", 1500),
+            ("3.00 MB", 3_000_000),
+            ("4.00 GB", 4_000_000_000),
+            ("1.00 TB", 1_000_000_000_000),
+            // Decimal comma locales and thousands separators
+            ("12,34 MiB", 12_939_428),
+            ("1.234,50 KiB", 1_264_128),
+            ("1,234.50 KiB", 1_264_128),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(PackageManager::parse_size(input), expected, "{}", input);
+        }
+    }
+    
+    #[test]
+    fn parse_size_rejects_unknown_input() {
+        assert_eq!(PackageManager::parse_size("12.34 furlongs"), 0);
+        assert_eq!(PackageManager::parse_size("12.34"), 0);
+        assert_eq!(PackageManager::parse_size("lots MiB"), 0);
+    }
+    
+    #[test]
+    fn parse_aur_search_reads_yay_output() {
+        // yay -Ss --aur paru (yay 12.3)
+        let output = "\
+aur/paru 2.0.3-1 (+1770 9.25) (Installed)
+    Feature packed AUR helper
+aur/paru-bin 2.0.3-1 (+190 1.03) (Out-of-date: 2024-05-01)
+    Feature packed AUR helper
+";
+        let packages = PackageManager::parse_aur_search(output);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "paru");
+        assert_eq!(packages[0].version, "2.0.3-1");
+        assert_eq!(packages[0].votes, Some(1770));
+        assert_eq!(packages[0].popularity, Some(9.25));
+        assert!(packages[0].installed);
+        assert_eq!(packages[0].description, "Feature packed AUR helper");
+        assert_eq!(packages[1].votes, Some(190));
+        assert_eq!(packages[1].popularity, Some(1.03));
+        assert!(!packages[1].installed);
+    }
+    
+    #[test]
+    fn parse_aur_search_reads_paru_output() {
+        // paru -Ss --aur yay (paru 2.0)
+        let output = "\
+aur/yay 12.3.5-1 [+2241 ~13.60] [Installed]
+    Yet another yogurt. Pacman wrapper and AUR helper written in go.
+aur/yay-git 12.3.5.r0.g1bd3b9c-1 [+127 ~0.24] [Orphaned]
+    Yet another yogurt. Pacman wrapper and AUR helper written in go. (development version)
+";
+        let packages = PackageManager::parse_aur_search(output);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "yay");
+        assert_eq!(packages[0].votes, Some(2241));
+        assert_eq!(packages[0].popularity, Some(13.60));
+        assert!(packages[0].installed);
+        assert_eq!(packages[1].name, "yay-git");
+        assert_eq!(packages[1].votes, Some(127));
+        assert_eq!(packages[1].popularity, Some(0.24));
+        assert!(!packages[1].installed);
     }
 }
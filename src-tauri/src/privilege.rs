@@ -0,0 +1,609 @@
+// Privilege - Root-only sysfs writes and commands, elevated through pkexec when the GUI runs as a user
+// Everything a caller wants done goes into one PrivilegedBatch, so the user sees at most one polkit prompt
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug, warn};
+
+use crate::error::{SysError, SysResult};
+
+/// Makes the binary act as the elevated helper instead of starting the GUI
+pub const HELPER_FLAG: &str = "--privileged-helper";
+pub const POLKIT_ACTION_ID: &str = "com.lou.ai-sysadmin-supreme.apply-settings";
+pub const POLKIT_POLICY_PATH: &str = "/usr/share/polkit-1/actions/com.lou.ai-sysadmin-supreme.policy";
+
+/// Sysctl settings the app manages, reloaded with `sysctl --system`
+pub const SYSCTL_DROP_IN: &str = "/etc/sysctl.d/99-ai-sysadmin.conf";
+
+/// Rewritten by mirror ranking, with the previous list kept alongside
+pub const PACMAN_MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+pub const PACMAN_MIRRORLIST_BACKUP: &str = "/etc/pacman.d/mirrorlist.bak";
+
+/// The helper runs as root for whoever passed the polkit check, so it only touches
+/// the files the app manages. `*` matches within one path component, `**` any
+/// number of components.
+const WRITABLE_PATHS: &[&str] = &[
+    "/sys/devices/system/cpu/cpu*/cpufreq/scaling_governor",
+    "/sys/devices/system/cpu/cpu*/cpufreq/scaling_min_freq",
+    "/sys/devices/system/cpu/cpu*/cpufreq/scaling_max_freq",
+    "/sys/devices/system/cpu/cpu*/cpufreq/energy_performance_preference",
+    "/sys/devices/system/cpu/cpu*/cpuidle/state*/disable",
+    "/sys/class/hwmon/hwmon*/pwm*",
+    "/sys/class/hwmon/hwmon*/power1_cap",
+    "/sys/class/drm/card*/device/power_dpm_force_performance_level",
+    "/sys/kernel/mm/hugepages/hugepages-*/nr_hugepages",
+    "/sys/fs/cgroup/cgroup.subtree_control",
+    "/sys/fs/cgroup/ai-sysadmin-limit-*/cpu.max",
+    "/sys/fs/cgroup/ai-sysadmin-limit-*/memory.max",
+    // Moving a process into a limit and back to the cgroup it came from
+    "/sys/fs/cgroup/**/cgroup.procs",
+    SYSCTL_DROP_IN,
+    PACMAN_MIRRORLIST,
+    PACMAN_MIRRORLIST_BACKUP,
+];
+const REMOVABLE_PATHS: &[&str] = &[SYSCTL_DROP_IN];
+/// The cgroups process limits are placed in
+const CGROUP_DIRS: &[&str] = &["/sys/fs/cgroup/ai-sysadmin-limit-*"];
+
+/// The only keys the sysctl drop-in may set
+const SYSCTL_KEYS: &[&str] = &[
+    "vm.nr_hugepages",
+    "vm.swappiness",
+    "vm.dirty_ratio",
+    "vm.dirty_background_ratio",
+    "vm.vfs_cache_pressure",
+    "kernel.numa_balancing",
+    "kernel.sched_autogroup_enabled",
+    "kernel.sched_migration_cost_ns",
+    "kernel.sched_child_runs_first",
+    "fs.inotify.max_user_watches",
+    "fs.file-max",
+];
+
+/// pkexec exit codes for a dismissed prompt and a refused authorization
+const PKEXEC_DISMISSED: i32 = 126;
+const PKEXEC_NOT_AUTHORIZED: i32 = 127;
+
+/// How often a time-limited elevation checks whether pkexec has finished
+const ELEVATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PrivilegedOp {
+    Write { path: PathBuf, value: String },
+    /// Succeeds when the file is already gone
+    Remove { path: PathBuf },
+    /// Succeeds when the directory already exists
+    CreateDir { path: PathBuf },
+    /// Empty directories only, e.g. a cgroup; succeeds when it is already gone
+    RemoveDir { path: PathBuf },
+    Run { program: String, args: Vec<String> },
+}
+
+impl PrivilegedOp {
+    fn describe(&self) -> String {
+        match self {
+            PrivilegedOp::Write { path, .. }
+            | PrivilegedOp::Remove { path }
+            | PrivilegedOp::CreateDir { path }
+            | PrivilegedOp::RemoveDir { path } => path.display().to_string(),
+            PrivilegedOp::Run { program, args } => format!("{} {}", program, args.join(" ")),
+        }
+    }
+    
+    /// What an elevated helper is allowed to do on our behalf
+    fn check_allowed(&self) -> SysResult<()> {
+        let allowed = match self {
+            PrivilegedOp::Write { path, value } => {
+                path_allowed(path, WRITABLE_PATHS) && (path != Path::new(SYSCTL_DROP_IN) || sysctl_drop_in_allowed(value))
+            }
+            PrivilegedOp::Remove { path } => path_allowed(path, REMOVABLE_PATHS),
+            PrivilegedOp::CreateDir { path } | PrivilegedOp::RemoveDir { path } => path_allowed(path, CGROUP_DIRS),
+            PrivilegedOp::Run { program, args } => command_allowed(program, args),
+        };
+        if !allowed {
+            return Err(SysError::InvalidParameter(format!("{} is not something the helper may do", self.describe())));
+        }
+        Ok(())
+    }
+    
+    fn perform(&self) -> SysResult<()> {
+        match self {
+            PrivilegedOp::Write { path, value } => fs::write(path, value).map_err(|e| SysError::io(path, e)),
+            PrivilegedOp::Remove { path } => match fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(SysError::io(path, e)),
+                _ => Ok(()),
+            },
+            PrivilegedOp::CreateDir { path } => match fs::create_dir(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => Err(SysError::io(path, e)),
+                _ => Ok(()),
+            },
+            PrivilegedOp::RemoveDir { path } => match fs::remove_dir(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(SysError::io(path, e)),
+                _ => Ok(()),
+            },
+            PrivilegedOp::Run { program, args } => {
+                let output = Command::new(program).args(args).output().map_err(|e| match e.kind() {
+                    std::io::ErrorKind::NotFound => SysError::HardwareUnavailable(format!("{} is not installed", program)),
+                    _ => SysError::from(e),
+                })?;
+                if !output.status.success() {
+                    return Err(SysError::command_failed(&self.describe(), &output));
+                }
+                Ok(())
+            }
+        }
+    }
+    
+    /// Rebuild a SysError from the helper's plain-text reply
+    fn helper_error(&self, message: String) -> SysError {
+        match self {
+            PrivilegedOp::Run { .. } => SysError::CommandFailed { cmd: self.describe(), code: None, stderr: message },
+            _ => SysError::Other(message),
+        }
+    }
+}
+
+/// Privileged operations applied together, in order
+#[derive(Debug, Clone, Default)]
+pub struct PrivilegedBatch {
+    ops: Vec<PrivilegedOp>,
+}
+
+impl PrivilegedBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn write(&mut self, path: impl Into<PathBuf>, value: impl Into<String>) -> &mut Self {
+        self.ops.push(PrivilegedOp::Write { path: path.into(), value: value.into() });
+        self
+    }
+    
+    pub fn remove(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.ops.push(PrivilegedOp::Remove { path: path.into() });
+        self
+    }
+    
+    pub fn create_dir(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.ops.push(PrivilegedOp::CreateDir { path: path.into() });
+        self
+    }
+    
+    pub fn remove_dir(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.ops.push(PrivilegedOp::RemoveDir { path: path.into() });
+        self
+    }
+    
+    pub fn run(&mut self, program: &str, args: &[&str]) -> &mut Self {
+        self.ops.push(PrivilegedOp::Run {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        });
+        self
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+    
+    /// Apply every operation in order and return one result per operation.
+    ///
+    /// As root everything runs in-process. Otherwise writes are tried directly
+    /// (udev rules often make sysfs files user-writable) until one is refused with
+    /// EACCES or a command comes up; that operation and everything after it then go
+    /// through a single pkexec helper run. Blocks while the polkit prompt is open.
+    pub fn apply(self) -> Vec<SysResult<()>> {
+        self.apply_with_timeout(None)
+    }
+    
+    /// `apply`, closing the polkit prompt after `timeout`; the elevated
+    /// operations then fail with PermissionDenied
+    pub fn apply_within(self, timeout: Duration) -> Vec<SysResult<()>> {
+        self.apply_with_timeout(Some(timeout))
+    }
+    
+    fn apply_with_timeout(self, timeout: Option<Duration>) -> Vec<SysResult<()>> {
+        if is_root() {
+            return self.ops.iter().map(PrivilegedOp::perform).collect();
+        }
+        
+        let mut results = Vec::with_capacity(self.ops.len());
+        let mut ops = self.ops.into_iter();
+        let mut deferred = Vec::new();
+        for op in ops.by_ref() {
+            let result = match &op {
+                PrivilegedOp::Run { .. } => Err(SysError::PermissionDenied(op.describe())),
+                _ => op.perform(),
+            };
+            if let Err(SysError::PermissionDenied(_)) = result {
+                deferred.push(op);
+                break;
+            }
+            results.push(result);
+        }
+        // The rest of the batch follows the refused operation, so nothing overtakes it
+        deferred.extend(ops);
+        if deferred.is_empty() {
+            return results;
+        }
+        
+        debug!("🔐 Elevating {} operations through pkexec", deferred.len());
+        match run_elevated(&deferred, timeout) {
+            Ok(replies) => {
+                results.extend(deferred.iter().zip(replies).map(|(op, reply)| match reply {
+                    None => Ok(()),
+                    Some(message) => Err(op.helper_error(message)),
+                }));
+            }
+            Err(e) => {
+                warn!("⚠️ Could not elevate {} operations: {}", deferred.len(), e);
+                results.extend(deferred.iter().map(|op| Err(SysError::PermissionDenied(op.describe()))));
+            }
+        }
+        results
+    }
+    
+    /// `apply`, failing with the first error
+    pub fn apply_all(self) -> SysResult<()> {
+        self.apply().into_iter().collect()
+    }
+}
+
+/// Whether `path` is absolute, plain (no `.`, `..` or empty components) and
+/// matches one of `patterns`
+fn path_allowed(path: &Path, patterns: &[&str]) -> bool {
+    let components: Vec<&str> = match path.to_str().and_then(|path| path.strip_prefix('/')) {
+        Some(relative) => relative.split('/').collect(),
+        None => return false,
+    };
+    if components.iter().any(|c| c.is_empty() || *c == "." || *c == "..") {
+        return false;
+    }
+    patterns.iter().any(|pattern| {
+        let pattern: Vec<&str> = pattern.trim_start_matches('/').split('/').collect();
+        components_match(&components, &pattern)
+    })
+}
+
+fn components_match(path: &[&str], pattern: &[&str]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, _) => path.is_empty(),
+        (Some((&"**", rest)), _) => components_match(path, rest) || (!path.is_empty() && components_match(&path[1..], pattern)),
+        (Some((glob, rest)), Some((component, remaining))) => component_matches(component, glob) && components_match(remaining, rest),
+        (Some(_), None) => false,
+    }
+}
+
+/// One path component against a glob with at most one `*`
+fn component_matches(component: &str, glob: &str) -> bool {
+    match glob.split_once('*') {
+        None => component == glob,
+        Some((prefix, suffix)) => component.len() > prefix.len() + suffix.len()
+            && component.starts_with(prefix)
+            && component.ends_with(suffix),
+    }
+}
+
+/// Comments and `[-]key = number` lines for the keys in SYSCTL_KEYS
+fn sysctl_drop_in_allowed(content: &str) -> bool {
+    content.lines().map(str::trim).all(|line| {
+        if line.is_empty() || line.starts_with('#') {
+            return true;
+        }
+        match line.trim_start_matches('-').split_once('=') {
+            Some((key, value)) => SYSCTL_KEYS.contains(&key.trim()) && is_number(value.trim()),
+            None => false,
+        }
+    })
+}
+
+/// The exact argument shapes the app passes to each program it runs elevated
+fn command_allowed(program: &str, args: &[String]) -> bool {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match (program, args.as_slice()) {
+        ("sysctl", ["--system"]) => true,
+        ("kill", ["-s", signal, pid]) => {
+            !signal.is_empty() && signal.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) && is_target_pid(pid)
+        }
+        ("renice", ["-n", nice, "-p", pid]) => {
+            nice.parse::<i32>().map_or(false, |nice| (-20..=19).contains(&nice)) && is_target_pid(pid)
+        }
+        ("nvidia-smi", ["-i", index, "-pl", watts]) => {
+            is_number(index) && watts.parse::<f64>().map_or(false, |watts| watts.is_finite() && watts > 0.0)
+        }
+        ("nvidia-smi", ["-rgc", "-rac"]) => true,
+        ("journalctl", [vacuum]) => {
+            let size = vacuum.strip_prefix("--vacuum-size=").and_then(|size| size.strip_suffix('M'));
+            let age = vacuum.strip_prefix("--vacuum-time=").and_then(|age| age.strip_suffix('d'));
+            size.or(age).map_or(false, is_number)
+        }
+        _ => false,
+    }
+}
+
+fn is_number(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// A single process other than init; 0 and negative pids name process groups
+fn is_target_pid(pid: &str) -> bool {
+    is_number(pid) && pid.parse::<i32>().map_or(false, |pid| pid > 1)
+}
+
+/// Effective UID 0, from /proc so it doesn't need libc
+pub fn is_root() -> bool {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status.lines()
+                .find(|line| line.starts_with("Uid:"))
+                .and_then(|line| line.split_whitespace().nth(2).map(|euid| euid == "0"))
+        })
+        .unwrap_or(false)
+}
+
+pub fn pkexec_available() -> bool {
+    Command::new("which").arg("pkexec").output().map(|output| output.status.success()).unwrap_or(false)
+}
+
+pub fn polkit_policy_installed() -> bool {
+    Path::new(POLKIT_POLICY_PATH).exists()
+}
+
+/// Run `ops` in `pkexec <this binary> --privileged-helper`; one reply per op, None on
+/// success. pkexec is killed if it hasn't finished within `timeout`.
+fn run_elevated(ops: &[PrivilegedOp], timeout: Option<Duration>) -> SysResult<Vec<Option<String>>> {
+    let exe = std::env::current_exe()?;
+    let mut child = Command::new("pkexec")
+        .arg(&exe)
+        .arg(HELPER_FLAG)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => SysError::PermissionDenied("system settings (pkexec is not installed; run as root instead)".to_string()),
+            _ => SysError::from(e),
+        })?;
+    
+    let request = serde_json::to_vec(ops).map_err(|e| SysError::Other(e.to_string()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&request)?;
+    }
+    if let Some(timeout) = timeout {
+        // The reply is a few bytes of JSON, so the pipes can't fill while we poll
+        let deadline = Instant::now() + timeout;
+        while child.try_wait()?.is_none() {
+            if Instant::now() >= deadline {
+                // Closes the prompt; once authorized the helper runs as root and can't be killed, but it is quick
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(SysError::PermissionDenied("system settings (authentication timed out)".to_string()));
+            }
+            thread::sleep(ELEVATION_POLL_INTERVAL);
+        }
+    }
+    let output = child.wait_with_output()?;
+    
+    match output.status.code() {
+        Some(PKEXEC_DISMISSED) => return Err(SysError::PermissionDenied("system settings (authentication was dismissed)".to_string())),
+        Some(PKEXEC_NOT_AUTHORIZED) => return Err(SysError::PermissionDenied("system settings (not authorized)".to_string())),
+        _ if !output.status.success() => return Err(SysError::command_failed("pkexec", &output)),
+        _ => {}
+    }
+    
+    let replies: Vec<Option<String>> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| SysError::Other(format!("Unreadable privileged helper reply: {}", e)))?;
+    if replies.len() != ops.len() {
+        return Err(SysError::Other(format!("Privileged helper answered {} of {} operations", replies.len(), ops.len())));
+    }
+    Ok(replies)
+}
+
+/// Entry point for `--privileged-helper`: read a JSON batch from stdin, apply it,
+/// print one reply per operation. Returns the process exit code.
+pub fn run_helper() -> i32 {
+    if !is_root() {
+        eprintln!("{} must be run through pkexec", HELPER_FLAG);
+        return 1;
+    }
+    
+    let mut request = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut request) {
+        eprintln!("Failed to read request: {}", e);
+        return 1;
+    }
+    let ops: Vec<PrivilegedOp> = match serde_json::from_str(&request) {
+        Ok(ops) => ops,
+        Err(e) => {
+            eprintln!("Invalid request: {}", e);
+            return 1;
+        }
+    };
+    
+    let replies: Vec<Option<String>> = ops.iter()
+        .map(|op| op.check_allowed().and_then(|_| op.perform()).err().map(|e| e.to_string()))
+        .collect();
+    match serde_json::to_string(&replies) {
+        Ok(reply) => {
+            println!("{}", reply);
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to encode reply: {}", e);
+            1
+        }
+    }
+}
+
+/// Policy with a single action whose exec.path is `binary_path`. Active sessions
+/// authenticate once and stay authorized for a few minutes (auth_admin_keep).
+pub fn generate_polkit_policy(binary_path: &Path) -> String {
+    let binary_path = binary_path.to_string_lossy()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    
+    let mut policy = String::new();
+    policy.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    policy.push_str("<!DOCTYPE policyconfig PUBLIC \"-//freedesktop//DTD PolicyKit Policy Configuration 1.0//EN\"\n");
+    policy.push_str(" \"http://www.freedesktop.org/standards/PolicyKit/1/policyconfig.dtd\">\n");
+    policy.push_str("<policyconfig>\n");
+    policy.push_str("  <vendor>Lou's Garuda AI SysAdmin Control Center</vendor>\n");
+    policy.push_str("  <vendor_url>https://github.com/wlfogle/ai-sysadmin-supreme</vendor_url>\n");
+    policy.push_str(&format!("  <action id=\"{}\">\n", POLKIT_ACTION_ID));
+    policy.push_str("    <description>Apply hardware and kernel settings</description>\n");
+    policy.push_str("    <message>Authentication is required to change CPU, fan, memory and kernel settings</message>\n");
+    policy.push_str("    <icon_name>preferences-system</icon_name>\n");
+    policy.push_str("    <defaults>\n");
+    policy.push_str("      <allow_any>auth_admin</allow_any>\n");
+    policy.push_str("      <allow_inactive>auth_admin</allow_inactive>\n");
+    policy.push_str("      <allow_active>auth_admin_keep</allow_active>\n");
+    policy.push_str("    </defaults>\n");
+    policy.push_str(&format!("    <annotate key=\"org.freedesktop.policykit.exec.path\">{}</annotate>\n", binary_path));
+    policy.push_str("    <annotate key=\"org.freedesktop.policykit.exec.allow_gui\">true</annotate>\n");
+    policy.push_str("  </action>\n");
+    policy.push_str("</policyconfig>\n");
+    policy
+}
+
+/// Install the policy for the running binary. As a user this goes through pkexec
+/// once with the generic "run as root" prompt, which the policy then replaces.
+pub fn install_polkit_policy() -> SysResult<String> {
+    if !is_root() {
+        return run_self_elevated("--install-polkit-policy");
+    }
+    
+    let exe = std::env::current_exe()?;
+    let path = Path::new(POLKIT_POLICY_PATH);
+    fs::write(path, generate_polkit_policy(&exe)).map_err(|e| SysError::io(path, e))?;
+    info!("🔐 Installed polkit policy {} for {}", POLKIT_POLICY_PATH, exe.display());
+    Ok(format!("Installed polkit policy {}", POLKIT_POLICY_PATH))
+}
+
+pub fn uninstall_polkit_policy() -> SysResult<String> {
+    if !polkit_policy_installed() {
+        return Ok("Polkit policy is not installed".to_string());
+    }
+    if !is_root() {
+        return run_self_elevated("--uninstall-polkit-policy");
+    }
+    
+    let path = Path::new(POLKIT_POLICY_PATH);
+    fs::remove_file(path).map_err(|e| SysError::io(path, e))?;
+    info!("🗑️ Removed polkit policy {}", POLKIT_POLICY_PATH);
+    Ok(format!("Removed polkit policy {}", POLKIT_POLICY_PATH))
+}
+
+fn run_self_elevated(flag: &str) -> SysResult<String> {
+    let exe = std::env::current_exe()?;
+    let output = Command::new("pkexec").arg(&exe).arg(flag).output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => SysError::PermissionDenied(format!("{} (pkexec is not installed; run '{} {}' as root)", POLKIT_POLICY_PATH, exe.display(), flag)),
+        _ => SysError::from(e),
+    })?;
+    match output.status.code() {
+        Some(PKEXEC_DISMISSED) | Some(PKEXEC_NOT_AUTHORIZED) => Err(SysError::PermissionDenied(POLKIT_POLICY_PATH.to_string())),
+        _ if !output.status.success() => Err(SysError::command_failed(&format!("pkexec {}", flag), &output)),
+        _ => Ok(format!("{} done", flag.trim_start_matches("--").replace('-', " "))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn write(path: &str, value: &str) -> PrivilegedOp {
+        PrivilegedOp::Write { path: PathBuf::from(path), value: value.to_string() }
+    }
+    
+    fn run(program: &str, args: &[&str]) -> PrivilegedOp {
+        PrivilegedOp::Run { program: program.to_string(), args: args.iter().map(|a| a.to_string()).collect() }
+    }
+    
+    #[test]
+    fn check_allowed_takes_only_the_files_the_app_manages() {
+        let allowed = [
+            write("/sys/devices/system/cpu/cpu3/cpufreq/scaling_governor", "powersave"),
+            write("/sys/devices/system/cpu/cpu0/cpuidle/state2/disable", "1"),
+            write("/sys/class/hwmon/hwmon4/pwm1", "255"),
+            write("/sys/class/hwmon/hwmon4/pwm1_enable", "1"),
+            write("/sys/class/drm/card1/device/power_dpm_force_performance_level", "low"),
+            write("/sys/kernel/mm/hugepages/hugepages-2048kB/nr_hugepages", "512"),
+            write("/sys/fs/cgroup/ai-sysadmin-limit-4242/cpu.max", "50000 100000"),
+            write("/sys/fs/cgroup/user.slice/user-1000.slice/session-2.scope/cgroup.procs", "4242"),
+            write("/sys/fs/cgroup/cgroup.procs", "4242"),
+            write(SYSCTL_DROP_IN, "# Managed\n-vm.swappiness = 1\n-kernel.sched_migration_cost_ns = 5000000\n"),
+            write(PACMAN_MIRRORLIST, "Server = https://mirror.example/$repo/os/$arch\n"),
+            write(PACMAN_MIRRORLIST_BACKUP, "Server = https://mirror.example/$repo/os/$arch\n"),
+            PrivilegedOp::Remove { path: PathBuf::from(SYSCTL_DROP_IN) },
+            PrivilegedOp::CreateDir { path: PathBuf::from("/sys/fs/cgroup/ai-sysadmin-limit-4242") },
+            PrivilegedOp::RemoveDir { path: PathBuf::from("/sys/fs/cgroup/ai-sysadmin-limit-4242") },
+        ];
+        for op in &allowed {
+            assert!(op.check_allowed().is_ok(), "{:?}", op);
+        }
+        
+        let refused = [
+            write("/etc/passwd", "root::0:0::/root:/bin/sh"),
+            write("/sys/power/state", "mem"),
+            write("/proc/sys/kernel/core_pattern", "|/tmp/x"),
+            write("/sys/class/hwmon/hwmon4/../../../power/state", "mem"),
+            write("/sys/devices/system/cpu/cpu0/cpufreq/./scaling_governor", "powersave"),
+            write("/sys/devices/system/cpu//cpu0/cpufreq/scaling_governor", "powersave"),
+            write("sys/class/hwmon/hwmon4/pwm1", "255"),
+            write("/sys/fs/cgroup/ai-sysadmin-limit-4242/cgroup.subtree_control", "+cpu"),
+            write("/etc/sysctl.d/10-other.conf", "vm.swappiness = 1"),
+            write("/etc/pacman.d/mirrorlist.pacnew", "Server = https://mirror.example/$repo/os/$arch\n"),
+            write("/etc/pacman.conf", "[options]\n"),
+            write(SYSCTL_DROP_IN, "kernel.core_pattern = |/tmp/x\n"),
+            write(SYSCTL_DROP_IN, "vm.swappiness = 1; reboot\n"),
+            PrivilegedOp::Remove { path: PathBuf::from("/etc/sysctl.d/10-other.conf") },
+            PrivilegedOp::CreateDir { path: PathBuf::from("/sys/fs/cgroup/user.slice/escape") },
+            PrivilegedOp::RemoveDir { path: PathBuf::from("/sys/fs/cgroup/user.slice") },
+        ];
+        for op in &refused {
+            assert!(op.check_allowed().is_err(), "{:?}", op);
+        }
+    }
+    
+    #[test]
+    fn check_allowed_takes_only_the_arguments_the_app_passes() {
+        let allowed = [
+            run("sysctl", &["--system"]),
+            run("kill", &["-s", "TERM", "4242"]),
+            run("renice", &["-n", "-5", "-p", "4242"]),
+            run("nvidia-smi", &["-i", "0", "-pl", "125.00"]),
+            run("nvidia-smi", &["-rgc", "-rac"]),
+            run("journalctl", &["--vacuum-size=500M"]),
+            run("journalctl", &["--vacuum-time=14d"]),
+        ];
+        for op in &allowed {
+            assert!(op.check_allowed().is_ok(), "{:?}", op);
+        }
+        
+        let refused = [
+            run("sh", &["-c", "id"]),
+            run("sysctl", &["-w", "kernel.core_pattern=|/tmp/x"]),
+            run("sysctl", &["-p", "/tmp/evil.conf"]),
+            run("kill", &["-s", "KILL", "-1"]),
+            run("kill", &["-s", "KILL", "1"]),
+            run("kill", &["-s", "TERM", "4242", "4243"]),
+            run("renice", &["-n", "-40", "-p", "4242"]),
+            run("renice", &["-n", "5", "-g", "0"]),
+            run("nvidia-smi", &["-i", "0", "-pl", "NaN"]),
+            run("nvidia-smi", &["--persistence-mode=0"]),
+            run("journalctl", &["--rotate"]),
+            run("journalctl", &["--vacuum-size=500M", "--flush"]),
+        ];
+        for op in &refused {
+            assert!(op.check_allowed().is_err(), "{:?}", op);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+// RAPL - CPU package power from the powercap energy counters
+// The counter only counts up, so power is the energy used between two reads
+// over the time between them. Intel and, on recent kernels, AMD both expose it.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+pub const POWERCAP_DIR: &str = "/sys/class/powercap";
+/// Package 0; on multi-socket machines the other packages are intel-rapl:1 and up
+const PACKAGE_ZONE: &str = "intel-rapl:0";
+
+#[derive(Debug, Clone, Copy)]
+pub struct EnergySample {
+    pub energy_uj: u64,
+    pub at: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct RaplZone {
+    dir: PathBuf,
+    /// energy_uj wraps back to 0 after this
+    max_energy_uj: u64,
+}
+
+impl RaplZone {
+    /// The CPU package zone, when the kernel has RAPL for this CPU
+    pub fn package() -> Option<Self> {
+        Self::at(&Path::new(POWERCAP_DIR).join(PACKAGE_ZONE))
+    }
+    
+    pub fn at(dir: &Path) -> Option<Self> {
+        let max_energy_uj = fs::read_to_string(dir.join("max_energy_range_uj")).ok()?.trim().parse().ok()?;
+        Some(Self { dir: dir.to_path_buf(), max_energy_uj })
+    }
+    
+    /// Fails with PermissionDenied for non-root users on kernels since 5.10,
+    /// which made energy_uj root-only
+    pub fn sample(&self) -> io::Result<EnergySample> {
+        let raw = fs::read_to_string(self.dir.join("energy_uj"))?;
+        let energy_uj = raw.trim().parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("energy_uj {:?}: {}", raw.trim(), e)))?;
+        Ok(EnergySample { energy_uj, at: Instant::now() })
+    }
+    
+    /// Average watts from `earlier` to `later`. A counter that went down has
+    /// wrapped once; more than one wrap between samples can't be told apart.
+    pub fn watts_between(&self, earlier: &EnergySample, later: &EnergySample) -> Option<f64> {
+        let secs = later.at.checked_duration_since(earlier.at)?.as_secs_f64();
+        if secs <= 0.0 {
+            return None;
+        }
+        let used_uj = if later.energy_uj >= earlier.energy_uj {
+            later.energy_uj - earlier.energy_uj
+        } else {
+            self.max_energy_uj.saturating_sub(earlier.energy_uj) + later.energy_uj
+        };
+        Some(used_uj as f64 / 1_000_000.0 / secs)
+    }
+    
+    /// Sample twice `interval` apart, blocking the thread in between
+    pub fn measure(&self, interval: Duration) -> io::Result<f64> {
+        let earlier = self.sample()?;
+        std::thread::sleep(interval);
+        let later = self.sample()?;
+        self.watts_between(&earlier, &later)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no time passed between RAPL samples"))
+    }
+}
+
+/// Package power averaged since the previous read, so a periodic collector
+/// gets a value every time without waiting. The first read only primes it.
+#[derive(Debug, Default)]
+pub struct PowerMeter {
+    zone: Option<RaplZone>,
+    last: Option<EnergySample>,
+}
+
+impl PowerMeter {
+    pub fn new() -> Self {
+        Self { zone: RaplZone::package(), last: None }
+    }
+    
+    pub fn read_watts(&mut self) -> Option<f64> {
+        let zone = self.zone.as_ref()?;
+        let sample = match zone.sample() {
+            Ok(sample) => sample,
+            Err(e) => {
+                debug!("CPU package power unavailable: {}", e);
+                return None;
+            }
+        };
+        let watts = self.last.and_then(|last| zone.watts_between(&last, &sample));
+        self.last = Some(sample);
+        watts
+    }
+}
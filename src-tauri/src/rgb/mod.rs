@@ -0,0 +1,2 @@
+// RGB - Multi-device RGB control beyond the built-in Clevo keyboard
+pub mod openrgb;
@@ -0,0 +1,377 @@
+// OpenRGB SDK client - Binary network protocol for the OpenRGB server (port 6742)
+use anyhow::{Result, Context, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Default address of a local OpenRGB SDK server
+pub const OPENRGB_DEFAULT_ADDR: &str = "127.0.0.1:6742";
+
+/// Every packet starts with this magic, then device index, packet id and payload size
+const OPENRGB_MAGIC: &[u8; 4] = b"ORGB";
+const HEADER_SIZE: usize = 16;
+/// Largest payload accepted from the server. Controller data for a full keyboard
+/// matrix is tens of KiB; anything near this is a broken or hostile server.
+const MAX_PAYLOAD_SIZE: usize = 1024 * 1024;
+
+/// Packet ids from the OpenRGB SDK documentation
+const NET_PACKET_ID_REQUEST_CONTROLLER_COUNT: u32 = 0;
+const NET_PACKET_ID_REQUEST_CONTROLLER_DATA: u32 = 1;
+const NET_PACKET_ID_SET_CLIENT_NAME: u32 = 50;
+const NET_PACKET_ID_RGBCONTROLLER_UPDATELEDS: u32 = 1050;
+const NET_PACKET_ID_RGBCONTROLLER_SETCUSTOMMODE: u32 = 1100;
+
+/// How long to wait for the server before treating it as absent
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One RGB controller (RAM stick, motherboard, peripheral) known to OpenRGB
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RgbDevice {
+    /// Controller index on the server, used for all per-device requests
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub location: String,
+    pub led_names: Vec<String>,
+}
+
+impl RgbDevice {
+    pub fn led_count(&self) -> usize {
+        self.led_names.len()
+    }
+}
+
+/// Client for the OpenRGB SDK server, speaking protocol version 0 so it
+/// works with every server release
+pub struct OpenRgbClient {
+    /// Open connection to the server
+    stream: TcpStream,
+    /// Devices switched to custom mode on this connection, so per-frame updates
+    /// don't re-send the mode change
+    custom_mode: HashSet<u32>,
+}
+
+impl OpenRgbClient {
+    /// Connects to an OpenRGB server and registers this client by name
+    pub async fn connect(addr: &str) -> Result<Self> {
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(addr))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to OpenRGB server at {}", addr))?
+            .context(format!("Cannot connect to OpenRGB server at {}", addr))?;
+        stream.set_nodelay(true)?;
+        
+        let mut client = Self { stream, custom_mode: HashSet::new() };
+        client.send_packet(0, NET_PACKET_ID_SET_CLIENT_NAME, b"AI SysAdmin Supreme\0").await?;
+        
+        Ok(client)
+    }
+    
+    /// Number of controllers the server exposes
+    pub async fn controller_count(&mut self) -> Result<u32> {
+        self.send_packet(0, NET_PACKET_ID_REQUEST_CONTROLLER_COUNT, &[]).await?;
+        let payload = self.read_reply(NET_PACKET_ID_REQUEST_CONTROLLER_COUNT).await?;
+        PacketReader::new(&payload).u32()
+    }
+    
+    /// Lists every controller with its LEDs
+    pub async fn list_rgb_devices(&mut self) -> Result<Vec<RgbDevice>> {
+        let count = self.controller_count().await?;
+        let mut devices = Vec::with_capacity(count as usize);
+        
+        for id in 0..count {
+            self.send_packet(id, NET_PACKET_ID_REQUEST_CONTROLLER_DATA, &[]).await?;
+            let payload = self.read_reply(NET_PACKET_ID_REQUEST_CONTROLLER_DATA).await?;
+            devices.push(parse_controller_data(id, &payload)
+                .context(format!("Malformed data for OpenRGB controller {}", id))?);
+        }
+        
+        Ok(devices)
+    }
+    
+    /// Sets a device's LEDs, one color per LED in server order. The device is
+    /// switched to its direct/custom mode the first time so the colors stick.
+    pub async fn set_device_color(&mut self, device_id: u32, leds: &[[u8; 3]]) -> Result<()> {
+        let payload = update_leds_payload(leds)?;
+        
+        if !self.custom_mode.contains(&device_id) {
+            self.send_packet(device_id, NET_PACKET_ID_RGBCONTROLLER_SETCUSTOMMODE, &[]).await?;
+            self.custom_mode.insert(device_id);
+        }
+        
+        self.send_packet(device_id, NET_PACKET_ID_RGBCONTROLLER_UPDATELEDS, &payload).await
+    }
+    
+    /// Switch devices to custom mode again on their next update, e.g. when an
+    /// effect starts after another app may have changed the mode
+    pub fn forget_custom_modes(&mut self) {
+        self.custom_mode.clear();
+    }
+    
+    /// Writes one packet: header followed by payload
+    async fn send_packet(&mut self, device_id: u32, packet_id: u32, payload: &[u8]) -> Result<()> {
+        let packet = encode_packet(device_id, packet_id, payload);
+        
+        tokio::time::timeout(IO_TIMEOUT, self.stream.write_all(&packet))
+            .await
+            .map_err(|_| anyhow!("Timed out writing to OpenRGB server"))??;
+        Ok(())
+    }
+    
+    /// Reads packets until one with `packet_id` arrives, returning its payload.
+    /// The server can interleave device-list-updated notifications.
+    async fn read_reply(&mut self, packet_id: u32) -> Result<Vec<u8>> {
+        loop {
+            let mut header = [0u8; HEADER_SIZE];
+            tokio::time::timeout(IO_TIMEOUT, self.stream.read_exact(&mut header))
+                .await
+                .map_err(|_| anyhow!("Timed out waiting for OpenRGB server"))??;
+            
+            let (received_id, size) = parse_header(&header)?;
+            let mut payload = vec![0u8; size];
+            tokio::time::timeout(IO_TIMEOUT, self.stream.read_exact(&mut payload))
+                .await
+                .map_err(|_| anyhow!("Timed out reading OpenRGB payload"))??;
+            
+            if received_id == packet_id {
+                return Ok(payload);
+            }
+        }
+    }
+}
+
+/// Header and payload of one packet
+fn encode_packet(device_id: u32, packet_id: u32, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(HEADER_SIZE + payload.len());
+    packet.extend_from_slice(OPENRGB_MAGIC);
+    packet.extend_from_slice(&device_id.to_le_bytes());
+    packet.extend_from_slice(&packet_id.to_le_bytes());
+    packet.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/// (packet id, payload size) from a received header. The size comes from the
+/// server, so it is capped before anything is allocated for it.
+fn parse_header(header: &[u8; HEADER_SIZE]) -> Result<(u32, usize)> {
+    if &header[0..4] != OPENRGB_MAGIC {
+        return Err(anyhow!("Invalid OpenRGB packet header"));
+    }
+    let packet_id = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+    let size = u32::from_le_bytes([header[12], header[13], header[14], header[15]]) as usize;
+    if size > MAX_PAYLOAD_SIZE {
+        return Err(anyhow!("OpenRGB packet {} claims {} bytes, more than the {} allowed", packet_id, size, MAX_PAYLOAD_SIZE));
+    }
+    Ok((packet_id, size))
+}
+
+/// UPDATELEDS payload: data_size (u32, includes itself) + led count (u16) + 4 bytes per color
+fn update_leds_payload(leds: &[[u8; 3]]) -> Result<Vec<u8>> {
+    if leds.len() > u16::MAX as usize {
+        return Err(anyhow!("Too many LEDs for one update: {}", leds.len()));
+    }
+    
+    let data_size = 4 + 2 + leds.len() * 4;
+    let mut payload = Vec::with_capacity(data_size);
+    payload.extend_from_slice(&(data_size as u32).to_le_bytes());
+    payload.extend_from_slice(&(leds.len() as u16).to_le_bytes());
+    for [r, g, b] in leds {
+        payload.extend_from_slice(&[*r, *g, *b, 0]);
+    }
+    Ok(payload)
+}
+
+/// Parses a protocol-0 controller data blob, keeping name, description, location and LEDs
+fn parse_controller_data(id: u32, payload: &[u8]) -> Result<RgbDevice> {
+    let mut reader = PacketReader::new(payload);
+    
+    let _data_size = reader.u32()?;
+    let _device_type = reader.u32()?;
+    let name = reader.string()?;
+    let description = reader.string()?;
+    let _version = reader.string()?;
+    let _serial = reader.string()?;
+    let location = reader.string()?;
+    
+    // Modes
+    let mode_count = reader.u16()?;
+    let _active_mode = reader.u32()?;
+    for _ in 0..mode_count {
+        reader.string()?; // name
+        // value, flags, speed min/max, colors min/max, speed, direction, color mode
+        reader.skip(9 * 4)?;
+        let color_count = reader.u16()?;
+        reader.skip(color_count as usize * 4)?;
+    }
+    
+    // Zones
+    let zone_count = reader.u16()?;
+    for _ in 0..zone_count {
+        reader.string()?; // name
+        reader.skip(4 * 4)?; // type, leds min/max/count
+        let matrix_size = reader.u16()?;
+        reader.skip(matrix_size as usize)?;
+    }
+    
+    // LEDs
+    let led_count = reader.u16()?;
+    let mut led_names = Vec::with_capacity(led_count as usize);
+    for _ in 0..led_count {
+        led_names.push(reader.string()?);
+        reader.u32()?; // value
+    }
+    
+    Ok(RgbDevice {
+        id,
+        name,
+        description,
+        location,
+        led_names,
+    })
+}
+
+/// Little-endian cursor over a packet payload
+struct PacketReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PacketReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+    
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow!("OpenRGB packet truncated"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+    
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len).map(|_| ())
+    }
+    
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+    
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+    
+    /// Length-prefixed string; the length includes the trailing NUL
+    fn string(&mut self) -> Result<String> {
+        let len = self.u16()? as usize;
+        let bytes = self.take(len)?;
+        let text = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        Ok(String::from_utf8_lossy(text).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Length-prefixed, NUL-terminated string as the server sends it
+    fn string(text: &str) -> Vec<u8> {
+        let mut bytes = ((text.len() + 1) as u16).to_le_bytes().to_vec();
+        bytes.extend_from_slice(text.as_bytes());
+        bytes.push(0);
+        bytes
+    }
+
+    /// Controller data for a two-LED RAM stick with one mode and one zone
+    fn controller_fixture() -> Vec<u8> {
+        [
+            &100u32.to_le_bytes()[..],          // data size
+            &1u32.to_le_bytes(),                // device type: DRAM
+            &string("Corsair Vengeance"),
+            &string("Corsair DRAM"),
+            &[1, 0, 0],                         // version: empty string, NUL only
+            &[0, 0],                            // serial: zero length
+            &string("I2C: /dev/i2c-3, address 0x58"),
+            &1u16.to_le_bytes(),                // mode count
+            &0u32.to_le_bytes(),                // active mode
+            &string("Direct"),
+            &[0; 36],                           // value, flags, speeds, colors, direction, color mode
+            &1u16.to_le_bytes(),                // mode colors
+            &[0xff, 0, 0, 0],
+            &1u16.to_le_bytes(),                // zone count
+            &string("DRAM"),
+            &[0; 16],                           // type, leds min/max/count
+            &2u16.to_le_bytes(),                // matrix size
+            &[0, 1],
+            &2u16.to_le_bytes(),                // led count
+            &string("LED 1"),
+            &0u32.to_le_bytes(),
+            &string("LED 2"),
+            &1u32.to_le_bytes(),
+            &0u16.to_le_bytes(),                // color count, unread
+        ].concat()
+    }
+
+    #[test]
+    fn controller_data_yields_names_and_leds() {
+        let device = parse_controller_data(3, &controller_fixture()).unwrap();
+        assert_eq!(device.id, 3);
+        assert_eq!(device.name, "Corsair Vengeance");
+        assert_eq!(device.description, "Corsair DRAM");
+        assert_eq!(device.location, "I2C: /dev/i2c-3, address 0x58");
+        assert_eq!(device.led_names, ["LED 1", "LED 2"]);
+    }
+
+    #[test]
+    fn truncated_controller_data_is_an_error() {
+        let fixture = controller_fixture();
+        // Cut inside the last LED name
+        assert!(parse_controller_data(0, &fixture[..fixture.len() - 10]).is_err());
+        assert!(parse_controller_data(0, &[]).is_err());
+        // A string length running past the end
+        let mut lying = fixture[..8].to_vec();
+        lying.extend_from_slice(&[0xff, 0xff, b'x']);
+        assert!(parse_controller_data(0, &lying).is_err());
+    }
+
+    #[test]
+    fn packets_are_framed_with_a_little_endian_header() {
+        let packet = encode_packet(2, NET_PACKET_ID_RGBCONTROLLER_UPDATELEDS, &update_leds_payload(&[[1, 2, 3], [4, 5, 6]]).unwrap());
+        assert_eq!(packet, [
+            b'O', b'R', b'G', b'B',
+            2, 0, 0, 0,                         // device
+            0x1a, 0x04, 0, 0,                   // packet id 1050
+            14, 0, 0, 0,                        // payload size
+            14, 0, 0, 0,                        // data size
+            2, 0,                               // led count
+            1, 2, 3, 0,
+            4, 5, 6, 0,
+        ]);
+
+        let header: [u8; HEADER_SIZE] = packet[..HEADER_SIZE].try_into().unwrap();
+        assert_eq!(parse_header(&header).unwrap(), (NET_PACKET_ID_RGBCONTROLLER_UPDATELEDS, 14));
+    }
+
+    #[test]
+    fn headers_are_checked_before_allocating() {
+        let mut header: [u8; HEADER_SIZE] = encode_packet(0, NET_PACKET_ID_REQUEST_CONTROLLER_DATA, &[])[..].try_into().unwrap();
+        header[12..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_header(&header).is_err());
+
+        header[12..].copy_from_slice(&(MAX_PAYLOAD_SIZE as u32).to_le_bytes());
+        assert_eq!(parse_header(&header).unwrap(), (NET_PACKET_ID_REQUEST_CONTROLLER_DATA, MAX_PAYLOAD_SIZE));
+
+        header[..4].copy_from_slice(b"HTTP");
+        assert!(parse_header(&header).is_err());
+    }
+
+    #[test]
+    fn led_updates_are_limited_to_a_u16_count() {
+        assert!(update_leds_payload(&vec![[0, 0, 0]; u16::MAX as usize + 1]).is_err());
+    }
+}
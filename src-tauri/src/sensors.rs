@@ -0,0 +1,240 @@
+// Sensor Map - Which hwmon chip is the CPU, the GPU, a drive or the board
+// Every temperature, fan and PWM channel is tagged with its chip and a readable
+// label, so the UI and the AI don't have to guess what "temp1" means
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+pub const HWMON_DIR: &str = "/sys/class/hwmon";
+
+/// CPU temperature labels in order of preference: the package sensor on Intel,
+/// the real die temperature on AMD, then Tctl, which some Ryzens offset by up to 20°C
+const CPU_PACKAGE_LABELS: &[&str] = &["Package id", "Tdie", "Tctl"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChipKind {
+    Cpu,
+    Gpu,
+    Storage,
+    /// Platform controller hub, e.g. pch_cannonlake
+    Chipset,
+    /// Super I/O and vendor embedded controllers: board temperatures and case fans
+    Motherboard,
+    /// acpitz, the firmware's own thermal zone
+    Acpi,
+    Other,
+}
+
+impl ChipKind {
+    /// From the hwmon `name` file
+    pub fn from_driver(driver: &str) -> Self {
+        match driver {
+            "coretemp" | "k10temp" | "zenpower" | "cpu_thermal" => ChipKind::Cpu,
+            "amdgpu" | "radeon" | "nouveau" | "i915" | "xe" => ChipKind::Gpu,
+            "nvme" | "drivetemp" => ChipKind::Storage,
+            "acpitz" => ChipKind::Acpi,
+            name if name.starts_with("pch_") => ChipKind::Chipset,
+            name if ["nct", "it87", "it86", "w83", "f71", "asus", "dell_smm", "thinkpad", "applesmc"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix)) => ChipKind::Motherboard,
+            _ => ChipKind::Other,
+        }
+    }
+    
+    fn description(&self) -> &'static str {
+        match self {
+            ChipKind::Cpu => "CPU",
+            ChipKind::Gpu => "GPU",
+            ChipKind::Storage => "Drive",
+            ChipKind::Chipset => "Chipset",
+            ChipKind::Motherboard => "Motherboard",
+            ChipKind::Acpi => "ACPI",
+            ChipKind::Other => "Sensor",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorKind {
+    /// °C
+    Temperature,
+    /// RPM
+    Fan,
+    /// Raw duty cycle, 0-255
+    Pwm,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chip {
+    /// hwmon directory name, e.g. hwmon3
+    pub hwmon: String,
+    /// Driver from the `name` file, e.g. nct6798
+    pub driver: String,
+    pub kind: ChipKind,
+    /// What the chip is attached to, e.g. nvme0 or a PCI address; tells two drives apart
+    pub device: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sensor {
+    /// hwmon directory of the chip this channel belongs to
+    pub hwmon: String,
+    pub chip_kind: ChipKind,
+    pub kind: SensorKind,
+    /// sysfs channel, e.g. temp1, fan2 or pwm2
+    pub channel: String,
+    /// e.g. "CPU Package id 0", "Drive nvme0 Composite" or "Motherboard fan2"
+    pub label: String,
+    /// The driver's own label from tempN_label or fanN_label, e.g. "Package id 0"
+    pub hwmon_label: Option<String>,
+    /// The *_input file, or the pwm file itself
+    pub path: PathBuf,
+    pub value: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SensorMap {
+    pub chips: Vec<Chip>,
+    pub sensors: Vec<Sensor>,
+}
+
+impl SensorMap {
+    pub fn scan() -> Self {
+        Self::scan_dir(Path::new(HWMON_DIR))
+    }
+    
+    pub fn scan_dir(hwmon_dir: &Path) -> Self {
+        let mut dirs: Vec<PathBuf> = fs::read_dir(hwmon_dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        dirs.sort();
+        
+        let mut map = SensorMap::default();
+        for dir in dirs {
+            let driver = match read_trimmed(&dir.join("name")) {
+                Some(driver) => driver,
+                None => continue,
+            };
+            let chip = Chip {
+                hwmon: dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+                kind: ChipKind::from_driver(&driver),
+                device: fs::canonicalize(dir.join("device")).ok()
+                    .and_then(|device| device.file_name().map(|name| name.to_string_lossy().to_string())),
+                driver,
+            };
+            map.sensors.extend(chip_sensors(&dir, &chip));
+            map.chips.push(chip);
+        }
+        
+        map
+    }
+    
+    pub fn chip(&self, hwmon: &str) -> Option<&Chip> {
+        self.chips.iter().find(|chip| chip.hwmon == hwmon)
+    }
+    
+    pub fn sensors_of(&self, kind: SensorKind) -> impl Iterator<Item = &Sensor> {
+        self.sensors.iter().filter(move |sensor| sensor.kind == kind)
+    }
+    
+    /// CPU package temperature in °C: the Package/Tdie/Tctl reading from the CPU
+    /// chip, or its hottest sensor when none of those is labelled
+    pub fn cpu_temperature(&self) -> Option<f64> {
+        let cpu_temps: Vec<(&Sensor, f64)> = self.sensors_of(SensorKind::Temperature)
+            .filter(|sensor| sensor.chip_kind == ChipKind::Cpu)
+            .filter_map(|sensor| sensor.value.map(|value| (sensor, value)))
+            .collect();
+        
+        for preferred in CPU_PACKAGE_LABELS {
+            if let Some((_, value)) = cpu_temps.iter()
+                .find(|(sensor, _)| sensor.hwmon_label.as_deref().map_or(false, |label| label.starts_with(preferred)))
+            {
+                return Some(*value);
+            }
+        }
+        cpu_temps.into_iter().map(|(_, value)| value).fold(None, max_temperature)
+    }
+    
+    /// Hottest temperature reported by a GPU hwmon chip; NVIDIA's proprietary
+    /// driver has no hwmon and has to be asked through nvidia-smi instead
+    pub fn gpu_temperature(&self) -> Option<f64> {
+        self.sensors_of(SensorKind::Temperature)
+            .filter(|sensor| sensor.chip_kind == ChipKind::Gpu)
+            .filter_map(|sensor| sensor.value)
+            .fold(None, max_temperature)
+    }
+}
+
+/// temp*, fan* and pwm* channels of one hwmon directory, in channel order
+fn chip_sensors(dir: &Path, chip: &Chip) -> Vec<Sensor> {
+    let files: Vec<String> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.file_name().to_string_lossy().to_string()).collect())
+        .unwrap_or_default();
+    
+    let mut sensors = Vec::new();
+    for file in files {
+        let (kind, channel) = if let Some(channel) = file.strip_suffix("_input").filter(|c| c.starts_with("temp")) {
+            (SensorKind::Temperature, channel.to_string())
+        } else if let Some(channel) = file.strip_suffix("_input").filter(|c| c.starts_with("fan")) {
+            (SensorKind::Fan, channel.to_string())
+        } else if file.len() > 3 && file.starts_with("pwm") && file[3..].chars().all(|c| c.is_ascii_digit()) {
+            (SensorKind::Pwm, file.clone())
+        } else {
+            continue;
+        };
+        
+        let path = dir.join(&file);
+        let value = read_trimmed(&path).and_then(|raw| raw.parse::<f64>().ok()).map(|raw| match kind {
+            SensorKind::Temperature => raw / 1000.0,
+            SensorKind::Fan | SensorKind::Pwm => raw,
+        });
+        // pwmN has no label of its own; it drives fanN
+        let hwmon_label = match kind {
+            SensorKind::Pwm => read_trimmed(&dir.join(format!("fan{}_label", &channel[3..]))),
+            _ => read_trimmed(&dir.join(format!("{}_label", channel))),
+        }
+        .filter(|label| !label.is_empty());
+        
+        let mut label = chip.kind.description().to_string();
+        if let (ChipKind::Storage | ChipKind::Gpu, Some(device)) = (chip.kind, &chip.device) {
+            label.push(' ');
+            label.push_str(device);
+        }
+        label.push(' ');
+        label.push_str(hwmon_label.as_deref().unwrap_or(&channel));
+        if kind == SensorKind::Pwm {
+            label.push_str(" PWM");
+        }
+        
+        sensors.push(Sensor {
+            hwmon: chip.hwmon.clone(),
+            chip_kind: chip.kind,
+            kind,
+            channel,
+            label,
+            hwmon_label,
+            path,
+            value,
+        });
+    }
+    
+    sensors.sort_by_key(|sensor| (sensor.kind as u8, channel_number(&sensor.channel)));
+    sensors
+}
+
+/// 10 in "temp10", so temp10 sorts after temp9
+fn channel_number(channel: &str) -> u32 {
+    channel.trim_start_matches(|c: char| c.is_ascii_alphabetic()).parse().unwrap_or(0)
+}
+
+fn max_temperature(max: Option<f64>, temp: f64) -> Option<f64> {
+    Some(max.map_or(temp, |max| max.max(temp)))
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
@@ -0,0 +1,171 @@
+// Service - systemd unit generation and installation for headless daemon mode
+// Runs the monitor + AI loop on boot without the Tauri window
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::dbus_service;
+
+pub const SERVICE_NAME: &str = "ai-sysadmin.service";
+pub const SERVICE_UNIT_PATH: &str = "/etc/systemd/system/ai-sysadmin.service";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    pub binary_path: PathBuf,
+    pub working_directory: PathBuf,
+    pub description: String,
+    /// Run as this user instead of root; hardware control needs root
+    pub user: Option<String>,
+    pub restart_sec: u32,
+    pub memory_max: String,
+    pub cpu_quota: String,
+    pub nice: i32,
+    pub log_filter: String,
+}
+
+impl DaemonConfig {
+    /// Defaults pointing at the currently running binary
+    pub fn for_current_exe() -> Result<Self> {
+        let binary_path = std::env::current_exe().context("Cannot determine the current executable")?;
+        let working_directory = binary_path.parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("/"));
+        
+        Ok(Self {
+            binary_path,
+            working_directory,
+            description: "Lou's Garuda AI SysAdmin monitoring daemon".to_string(),
+            user: None,
+            restart_sec: 10,
+            memory_max: "512M".to_string(),
+            cpu_quota: "25%".to_string(),
+            nice: 5,
+            log_filter: "lous_garuda_ai_sysadmin=info".to_string(),
+        })
+    }
+}
+
+pub struct ServiceManager {
+    pub unit_path: PathBuf,
+}
+
+impl ServiceManager {
+    pub fn new() -> Self {
+        Self {
+            unit_path: PathBuf::from(SERVICE_UNIT_PATH),
+        }
+    }
+    
+    pub fn generate_systemd_unit(&self, config: &DaemonConfig) -> String {
+        let mut unit = String::new();
+        
+        unit.push_str("[Unit]\n");
+        unit.push_str(&format!("Description={}\n", config.description));
+        unit.push_str("After=network-online.target\n");
+        unit.push_str("Wants=network-online.target\n");
+        unit.push_str("StartLimitIntervalSec=300\n");
+        unit.push_str("StartLimitBurst=5\n");
+        unit.push('\n');
+        
+        unit.push_str("[Service]\n");
+        unit.push_str("Type=simple\n");
+        unit.push_str(&format!("ExecStart={} --daemon\n", quote_exec_arg(&config.binary_path.to_string_lossy())));
+        unit.push_str(&format!("WorkingDirectory={}\n", config.working_directory.display()));
+        if let Some(user) = &config.user {
+            unit.push_str(&format!("User={}\n", user));
+        }
+        unit.push_str(&format!("Environment=RUST_LOG={}\n", config.log_filter));
+        unit.push_str("Restart=on-failure\n");
+        unit.push_str(&format!("RestartSec={}\n", config.restart_sec));
+        unit.push_str(&format!("MemoryMax={}\n", config.memory_max));
+        unit.push_str(&format!("CPUQuota={}\n", config.cpu_quota));
+        unit.push_str(&format!("Nice={}\n", config.nice));
+        unit.push_str("LimitNOFILE=4096\n");
+        unit.push_str("TasksMax=256\n");
+        unit.push('\n');
+        
+        unit.push_str("[Install]\n");
+        unit.push_str("WantedBy=multi-user.target\n");
+        
+        unit
+    }
+    
+    /// Write the unit, reload systemd and enable it for boot. Needs root.
+    pub fn install_service(&self, config: &DaemonConfig) -> Result<()> {
+        let unit = self.generate_systemd_unit(config);
+        fs::write(&self.unit_path, unit)
+            .context(format!("Failed to write {} (are you root?)", self.unit_path.display()))?;
+        info!("📝 Wrote systemd unit to {}", self.unit_path.display());
+        
+        // The daemon registers on the system bus, which refuses the name without a policy
+        let owner = config.user.as_deref().unwrap_or("root");
+        fs::write(dbus_service::DBUS_POLICY_PATH, dbus_service::system_bus_policy(owner))
+            .context(format!("Failed to write {}", dbus_service::DBUS_POLICY_PATH))?;
+        info!("📝 Wrote D-Bus policy to {}", dbus_service::DBUS_POLICY_PATH);
+        reload_dbus();
+        
+        run_systemctl(&["daemon-reload"])?;
+        run_systemctl(&["enable", SERVICE_NAME])?;
+        
+        info!("✅ Installed {}; start it with: systemctl start {}", SERVICE_NAME, SERVICE_NAME);
+        Ok(())
+    }
+    
+    /// Stop and disable the service, remove the unit and reload systemd
+    pub fn uninstall_service(&self) -> Result<()> {
+        if self.unit_path.exists() {
+            // Disabling a unit that is already stopped or disabled is fine to fail
+            let _ = run_systemctl(&["disable", "--now", SERVICE_NAME]);
+            fs::remove_file(&self.unit_path)
+                .context(format!("Failed to remove {} (are you root?)", self.unit_path.display()))?;
+            info!("🗑️ Removed {}", self.unit_path.display());
+        }
+        if Path::new(dbus_service::DBUS_POLICY_PATH).exists() {
+            fs::remove_file(dbus_service::DBUS_POLICY_PATH)
+                .context(format!("Failed to remove {}", dbus_service::DBUS_POLICY_PATH))?;
+            reload_dbus();
+        }
+        
+        run_systemctl(&["daemon-reload"])?;
+        Ok(())
+    }
+}
+
+fn run_systemctl(args: &[&str]) -> Result<()> {
+    let output = Command::new("systemctl")
+        .args(args)
+        .output()
+        .context("Failed to run systemctl")?;
+    
+    if !output.status.success() {
+        return Err(anyhow!(
+            "systemctl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    
+    Ok(())
+}
+
+/// Make the bus pick up a changed policy. Not fatal: the policy also applies
+/// after the next reboot.
+fn reload_dbus() {
+    if let Err(e) = run_systemctl(&["reload", "dbus.service"]) {
+        warn!("⚠️ Failed to reload D-Bus, the policy applies after a reboot: {}", e);
+    }
+}
+
+/// systemd splits ExecStart on whitespace; quote paths that contain it
+fn quote_exec_arg(arg: &str) -> String {
+    if arg.contains(char::is_whitespace) || arg.contains('"') {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
@@ -0,0 +1,265 @@
+// Snapshots - Btrfs system snapshots through snapper, as set up on Garuda
+// Falls back to plain `btrfs subvolume snapshot` when snapper isn't configured for /
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command as TokioCommand;
+use tracing::{info, warn, debug};
+
+const SNAPPER_CONFIG: &str = "root";
+const SNAPPER_CONFIG_DIR: &str = "/etc/snapper/configs";
+/// Where the plain btrfs backend keeps its read-only snapshots; must be on the root filesystem
+const BTRFS_SNAPSHOT_DIR: &str = "/.ai-snapshots";
+/// snap-pac already takes pre/post snapshots around every pacman transaction
+const SNAP_PAC_HOOK: &str = "/usr/share/libalpm/hooks/05-snap-pac-pre.hook";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotBackend {
+    Snapper,
+    Btrfs,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub id: u32,
+    pub description: String,
+    pub date: Option<DateTime<Utc>>,
+    /// snapper's single/pre/post; always "single" for the btrfs backend
+    pub kind: String,
+    pub backend: SnapshotBackend,
+}
+
+pub struct SnapshotManager {
+    pub backend: SnapshotBackend,
+    /// `sudo`/`pkexec` when snapshots need elevation, `None` when already root
+    pub privilege_helper: Option<String>,
+}
+
+impl SnapshotManager {
+    /// None unless / is Btrfs and snapper or btrfs-progs is installed
+    pub async fn detect(privilege_helper: Option<String>) -> Option<Self> {
+        if !is_btrfs_root() {
+            debug!("📸 Root filesystem is not Btrfs, snapshots unavailable");
+            return None;
+        }
+        
+        let backend = if command_exists("snapper").await && Path::new(SNAPPER_CONFIG_DIR).join(SNAPPER_CONFIG).exists() {
+            SnapshotBackend::Snapper
+        } else if command_exists("btrfs").await {
+            SnapshotBackend::Btrfs
+        } else {
+            warn!("⚠️ Root is Btrfs but neither snapper nor btrfs-progs is installed");
+            return None;
+        };
+        
+        debug!("📸 Using {:?} for system snapshots", backend);
+        Some(Self { backend, privilege_helper })
+    }
+    
+    /// Whether pacman transactions are already snapshotted by snap-pac
+    pub fn snap_pac_installed() -> bool {
+        Path::new(SNAP_PAC_HOOK).exists()
+    }
+    
+    pub async fn create_snapshot(&self, description: &str) -> Result<SnapshotInfo> {
+        info!("📸 Creating snapshot: {}", description);
+        
+        match self.backend {
+            SnapshotBackend::Snapper => {
+                let output = self.run("snapper", &["-c", SNAPPER_CONFIG, "create", "--print-number", "--cleanup-algorithm", "number", "--description", description]).await?;
+                let id = output.trim().parse::<u32>()
+                    .map_err(|_| anyhow!("Unexpected snapper output: {}", output.trim()))?;
+                
+                Ok(SnapshotInfo {
+                    id,
+                    description: description.to_string(),
+                    date: Some(Utc::now()),
+                    kind: "single".to_string(),
+                    backend: self.backend,
+                })
+            }
+            SnapshotBackend::Btrfs => {
+                self.run("mkdir", &["-p", BTRFS_SNAPSHOT_DIR]).await?;
+                let id = self.btrfs_snapshot_ids().into_iter().max().unwrap_or(0) + 1;
+                let path = btrfs_snapshot_path(id);
+                self.run("btrfs", &["subvolume", "snapshot", "-r", "/", &path.to_string_lossy()]).await?;
+                
+                let snapshot = SnapshotInfo {
+                    id,
+                    description: description.to_string(),
+                    date: Some(Utc::now()),
+                    kind: "single".to_string(),
+                    backend: self.backend,
+                };
+                // The snapshot itself is read-only, so its metadata lives next to it
+                let metadata = serde_json::to_string_pretty(&snapshot)?;
+                let mut tee = self.command("tee");
+                tee.arg(btrfs_metadata_path(id)).stdin(Stdio::piped()).stdout(Stdio::null());
+                let mut child = tee.spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(metadata.as_bytes()).await?;
+                }
+                if !child.wait().await?.success() {
+                    warn!("⚠️ Snapshot {} created but its description could not be saved", id);
+                }
+                
+                Ok(snapshot)
+            }
+        }
+    }
+    
+    pub async fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>> {
+        match self.backend {
+            SnapshotBackend::Snapper => {
+                let output = self.run("snapper", &["--jsonout", "-c", SNAPPER_CONFIG, "list"]).await?;
+                let json: Value = serde_json::from_str(&output)
+                    .map_err(|e| anyhow!("Unreadable snapper output: {}", e))?;
+                
+                let snapshots = json[SNAPPER_CONFIG].as_array().cloned().unwrap_or_default()
+                    .iter()
+                    // Snapshot 0 is the live system, not a snapshot
+                    .filter(|entry| entry["number"].as_u64().map(|n| n > 0).unwrap_or(false))
+                    .map(|entry| SnapshotInfo {
+                        id: entry["number"].as_u64().unwrap_or(0) as u32,
+                        description: entry["description"].as_str().unwrap_or("").to_string(),
+                        date: entry["date"].as_str().and_then(parse_snapper_date),
+                        kind: entry["type"].as_str().unwrap_or("single").to_string(),
+                        backend: self.backend,
+                    })
+                    .collect();
+                Ok(snapshots)
+            }
+            SnapshotBackend::Btrfs => {
+                let mut snapshots: Vec<SnapshotInfo> = self.btrfs_snapshot_ids().into_iter()
+                    .map(|id| {
+                        fs::read_to_string(btrfs_metadata_path(id)).ok()
+                            .and_then(|content| serde_json::from_str(&content).ok())
+                            .unwrap_or_else(|| SnapshotInfo {
+                                id,
+                                description: String::new(),
+                                date: None,
+                                kind: "single".to_string(),
+                                backend: self.backend,
+                            })
+                    })
+                    .collect();
+                snapshots.sort_by_key(|snapshot| snapshot.id);
+                Ok(snapshots)
+            }
+        }
+    }
+    
+    pub async fn delete_snapshot(&self, id: u32) -> Result<()> {
+        info!("🗑️ Deleting snapshot {}", id);
+        
+        match self.backend {
+            SnapshotBackend::Snapper => {
+                self.run("snapper", &["-c", SNAPPER_CONFIG, "delete", &id.to_string()]).await?;
+            }
+            SnapshotBackend::Btrfs => {
+                let path = btrfs_snapshot_path(id);
+                if !path.exists() {
+                    return Err(anyhow!("Snapshot {} not found", id));
+                }
+                self.run("btrfs", &["subvolume", "delete", &path.to_string_lossy()]).await?;
+                self.run("rm", &["-f", &btrfs_metadata_path(id).to_string_lossy()]).await?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Make snapshot `id` the root filesystem on next boot. Takes effect only after a reboot.
+    pub async fn rollback(&self, id: u32) -> Result<()> {
+        info!("⏪ Rolling back to snapshot {}", id);
+        
+        match self.backend {
+            SnapshotBackend::Snapper => {
+                self.run("snapper", &["-c", SNAPPER_CONFIG, "rollback", &id.to_string()]).await?;
+                info!("✅ Rollback to snapshot {} staged, reboot to apply", id);
+                Ok(())
+            }
+            // Swapping the default subvolume by hand depends on the distro's subvolume
+            // layout and fstab; getting it wrong leaves the system unbootable
+            SnapshotBackend::Btrfs => Err(anyhow!(
+                "Rollback needs snapper; boot snapshot {} from the bootloader or restore it manually from {}",
+                id, btrfs_snapshot_path(id).display()
+            )),
+        }
+    }
+    
+    /// A command elevated through `privilege_helper` when needed
+    fn command(&self, program: &str) -> TokioCommand {
+        match &self.privilege_helper {
+            Some(helper) => {
+                let mut command = TokioCommand::new(helper);
+                command.arg(program);
+                command
+            }
+            None => TokioCommand::new(program),
+        }
+    }
+    
+    async fn run(&self, program: &str, args: &[&str]) -> Result<String> {
+        let output = self.command(program).args(args).output().await?;
+        if !output.status.success() {
+            return Err(anyhow!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+    
+    fn btrfs_snapshot_ids(&self) -> Vec<u32> {
+        fs::read_dir(BTRFS_SNAPSHOT_DIR)
+            .map(|entries| {
+                entries.flatten()
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().to_string_lossy().parse::<u32>().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Whether / is mounted from a Btrfs filesystem, per /proc/mounts
+pub fn is_btrfs_root() -> bool {
+    fs::read_to_string("/proc/mounts")
+        .map(|mounts| {
+            // Later entries shadow earlier ones mounted on the same point
+            mounts.lines()
+                .filter_map(|line| {
+                    let mut fields = line.split_whitespace();
+                    let mount_point = fields.nth(1)?;
+                    let fs_type = fields.next()?;
+                    (mount_point == "/").then_some(fs_type == "btrfs")
+                })
+                .last()
+                .unwrap_or(false)
+        })
+        .unwrap_or(false)
+}
+
+async fn command_exists(program: &str) -> bool {
+    TokioCommand::new("which").arg(program).output().await
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn btrfs_snapshot_path(id: u32) -> PathBuf {
+    Path::new(BTRFS_SNAPSHOT_DIR).join(id.to_string())
+}
+
+fn btrfs_metadata_path(id: u32) -> PathBuf {
+    Path::new(BTRFS_SNAPSHOT_DIR).join(format!("{}.json", id))
+}
+
+/// snapper prints local time as "2024-05-01 12:34:56"
+fn parse_snapper_date(date: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").ok()?;
+    chrono::Local.from_local_datetime(&naive).single().map(|local| local.with_timezone(&Utc))
+}
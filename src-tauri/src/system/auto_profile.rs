@@ -0,0 +1,354 @@
+// Auto Profile - One adaptive profile instead of switching Gaming/Dev/LLM by hand
+// A control thread classifies the workload and reads temperatures every few seconds,
+// then sets the governor, fan curve and NVIDIA power limit to match
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use sysinfo::System;
+use tracing::{info, debug, warn, error};
+use crate::ai::WorkloadType;
+use crate::ai::workload_classifier::WorkloadClassifier;
+use crate::config::{self, AutoProfileConfig};
+use crate::error::{SysError, SysResult};
+use crate::monitoring_system::ProcessInfo;
+use crate::privilege::PrivilegedBatch;
+use crate::watchdog;
+use crate::{FanCurve, HardwareController};
+use super::SystemController;
+
+/// How hard the machine is allowed to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AutoLevel {
+    Quiet,
+    Balanced,
+    Performance,
+}
+
+impl AutoLevel {
+    fn for_workload(workload: &WorkloadType) -> Self {
+        match workload {
+            WorkloadType::Gaming => AutoLevel::Performance,
+            WorkloadType::Idle => AutoLevel::Quiet,
+            WorkloadType::Development | WorkloadType::Media | WorkloadType::SystemMaintenance => AutoLevel::Balanced,
+        }
+    }
+    
+    fn step_down(self) -> Self {
+        match self {
+            AutoLevel::Performance => AutoLevel::Balanced,
+            AutoLevel::Balanced | AutoLevel::Quiet => AutoLevel::Quiet,
+        }
+    }
+    
+    fn governor(self) -> Option<String> {
+        match self {
+            AutoLevel::Performance => Some("performance".to_string()),
+            AutoLevel::Balanced => SystemController::default_governor(),
+            AutoLevel::Quiet => Some("powersave".to_string()),
+        }
+    }
+    
+    fn fan_curve(self) -> FanCurve {
+        let points = match self {
+            AutoLevel::Quiet => vec![(40.0, 20), (60.0, 35), (75.0, 60), (85.0, 100)],
+            AutoLevel::Balanced => vec![(40.0, 30), (60.0, 45), (75.0, 75), (85.0, 100)],
+            AutoLevel::Performance => vec![(40.0, 40), (55.0, 60), (70.0, 85), (80.0, 100)],
+        };
+        FanCurve { points }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutoProfileStatus {
+    pub running: bool,
+    /// Workload the settings follow, once it has held for `switch_after_reads` polls
+    pub workload: Option<WorkloadType>,
+    pub level: Option<AutoLevel>,
+    /// Stepped down a level because the CPU or GPU ran hot
+    pub thermal_limited: bool,
+    /// Held at Balanced or below
+    pub on_battery: bool,
+    pub deferring_to_watchdog: bool,
+    pub cpu_temperature: Option<f64>,
+    pub gpu_temperature: Option<f64>,
+    pub last_change: Option<u64>,
+    /// What the last poll did, for display
+    pub activity: String,
+}
+
+/// Power limits in W for one NVIDIA GPU
+struct NvidiaPowerRange {
+    index: String,
+    min: f64,
+    default: f64,
+    max: f64,
+}
+
+impl NvidiaPowerRange {
+    fn limit_for(&self, level: AutoLevel) -> f64 {
+        match level {
+            AutoLevel::Quiet => self.min,
+            AutoLevel::Balanced => self.default,
+            AutoLevel::Performance => self.max,
+        }
+    }
+}
+
+struct AutoProfileTask {
+    stop: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+    status: Arc<Mutex<AutoProfileStatus>>,
+}
+
+static AUTO_PROFILE: Mutex<Option<AutoProfileTask>> = Mutex::new(None);
+
+/// Start the control thread. Returns false when it was already running.
+pub fn start() -> SysResult<bool> {
+    let mut task = AUTO_PROFILE.lock().unwrap();
+    if task.is_some() {
+        return Ok(false);
+    }
+    
+    let stop = Arc::new(AtomicBool::new(false));
+    let status = Arc::new(Mutex::new(AutoProfileStatus {
+        running: true,
+        activity: "Sampling the workload".to_string(),
+        ..Default::default()
+    }));
+    let stop_flag = stop.clone();
+    let shared_status = status.clone();
+    
+    let handle = thread::Builder::new()
+        .name("auto-profile".to_string())
+        .spawn(move || {
+            info!("🤖 Auto profile started");
+            let mut controller = AutoController::new();
+            loop {
+                // CPU usage needs two process samples, so the first poll waits an interval
+                thread::park_timeout(config::get().auto_profile.interval());
+                if stop_flag.load(Ordering::SeqCst) {
+                    break;
+                }
+                let settings = config::get();
+                controller.poll(&settings.auto_profile, settings.thresholds.cpu_temperature, settings.thresholds.cpu_temperature_clear);
+                *shared_status.lock().unwrap() = controller.status.clone();
+            }
+            controller.release();
+            shared_status.lock().unwrap().running = false;
+            info!("🤖 Auto profile stopped");
+        })
+        .map_err(|e| SysError::Other(format!("Failed to start the auto profile thread: {}", e)))?;
+    
+    *task = Some(AutoProfileTask { stop, handle, status });
+    Ok(true)
+}
+
+/// Stop the control thread and hand the settings back to the defaults. Returns
+/// false when it wasn't running.
+pub fn stop() -> bool {
+    let task = AUTO_PROFILE.lock().unwrap().take();
+    match task {
+        Some(task) => {
+            task.stop.store(true, Ordering::SeqCst);
+            task.handle.thread().unpark();
+            if task.handle.join().is_err() {
+                error!("Auto profile thread panicked");
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// None when the auto profile isn't running
+pub fn status() -> Option<AutoProfileStatus> {
+    AUTO_PROFILE.lock().unwrap().as_ref().map(|task| task.status.lock().unwrap().clone())
+}
+
+struct AutoController {
+    system: System,
+    classifier: WorkloadClassifier,
+    /// Workload seen on the last poll and for how many polls in a row
+    candidate: Option<(WorkloadType, u32)>,
+    thermal_limited: bool,
+    applied: Option<AutoLevel>,
+    nvidia_gpus: Vec<NvidiaPowerRange>,
+    status: AutoProfileStatus,
+}
+
+impl AutoController {
+    fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_memory();
+        system.refresh_processes();
+        
+        Self {
+            system,
+            classifier: WorkloadClassifier::default(),
+            candidate: None,
+            thermal_limited: false,
+            applied: None,
+            nvidia_gpus: nvidia_power_ranges(),
+            status: AutoProfileStatus { running: true, ..Default::default() },
+        }
+    }
+    
+    fn poll(&mut self, settings: &AutoProfileConfig, cpu_limit: f64, cpu_clear: f64) {
+        let cpu = watchdog::read_cpu_temperature();
+        let gpu = watchdog::read_gpu_temperature();
+        self.status.cpu_temperature = cpu;
+        self.status.gpu_temperature = gpu;
+        
+        // Its limits win; settings are left alone until it lets go
+        self.status.deferring_to_watchdog = watchdog::is_throttling();
+        if self.status.deferring_to_watchdog {
+            self.status.activity = "Safety watchdog is throttling, holding off".to_string();
+            return;
+        }
+        
+        self.system.refresh_processes();
+        let observed = self.classifier.classify(&self.processes());
+        let seen = match &self.candidate {
+            Some((workload, seen)) if *workload == observed => seen + 1,
+            _ => 1,
+        };
+        self.candidate = Some((observed.clone(), seen));
+        if self.status.workload.is_none() || seen >= settings.switch_after_reads.max(1) {
+            self.status.workload = Some(observed);
+        }
+        
+        // Separate trip and clear temperatures, so it doesn't flap around one value
+        let above = |temp: Option<f64>, limit: f64| temp.map(|t| t >= limit).unwrap_or(false);
+        if above(cpu, cpu_limit) || above(gpu, settings.gpu_temperature_limit) {
+            self.thermal_limited = true;
+        } else if !above(cpu, cpu_clear) && !above(gpu, settings.gpu_temperature_clear) {
+            self.thermal_limited = false;
+        }
+        self.status.thermal_limited = self.thermal_limited;
+        self.status.on_battery = SystemController::on_ac_power() == Some(false);
+        
+        let workload = self.status.workload.clone().unwrap_or(WorkloadType::Idle);
+        let mut level = AutoLevel::for_workload(&workload);
+        if self.status.on_battery {
+            level = level.min(AutoLevel::Balanced);
+        }
+        if self.thermal_limited {
+            level = level.step_down();
+        }
+        
+        if self.applied != Some(level) {
+            self.apply(level);
+        }
+        self.status.level = Some(level);
+        self.status.activity = format!(
+            "{:?} for {:?}{}{}",
+            level, workload,
+            if self.thermal_limited { ", stepped down while running hot" } else { "" },
+            if self.status.on_battery { ", on battery" } else { "" }
+        );
+    }
+    
+    /// Governor and GPU power limits in one privileged batch, then the fan curve.
+    /// Recorded as applied even if parts fail, so a refused prompt isn't repeated every poll.
+    fn apply(&mut self, level: AutoLevel) {
+        info!("🤖 Auto profile: {:?} -> {:?}", self.applied, level);
+        let mut batch = PrivilegedBatch::new();
+        
+        let available = SystemController::available_governors().unwrap_or_default();
+        if let Some(governor) = level.governor().filter(|governor| available.contains(governor)) {
+            for (_, path) in HardwareController::cpu_governor_paths() {
+                batch.write(path, governor.clone());
+            }
+        }
+        for gpu in &self.nvidia_gpus {
+            batch.run("nvidia-smi", &["-i", &gpu.index, "-pl", &format!("{:.0}", gpu.limit_for(level))]);
+        }
+        
+        if !batch.is_empty() {
+            let results = batch.apply();
+            let total = results.len();
+            let errors: Vec<SysError> = results.into_iter().filter_map(Result::err).collect();
+            if let Some(e) = errors.first() {
+                warn!("Auto profile: {} of {} settings failed for {:?}: {}", errors.len(), total, level, e);
+            }
+        }
+        
+        if let Err(e) = HardwareController::apply_fan_curve(&level.fan_curve()) {
+            debug!("Auto profile left the fans alone: {}", e);
+        }
+        
+        self.applied = Some(level);
+        self.status.last_change = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs());
+    }
+    
+    /// Default governor, default GPU power limits and automatic fans
+    fn release(&mut self) {
+        if self.applied.take().is_none() {
+            return;
+        }
+        
+        let mut batch = PrivilegedBatch::new();
+        if let Some(governor) = SystemController::default_governor() {
+            for (_, path) in HardwareController::cpu_governor_paths() {
+                batch.write(path, governor.clone());
+            }
+        }
+        for gpu in &self.nvidia_gpus {
+            batch.run("nvidia-smi", &["-i", &gpu.index, "-pl", &format!("{:.0}", gpu.default)]);
+        }
+        if !batch.is_empty() {
+            if let Err(e) = batch.apply_all() {
+                warn!("Auto profile could not restore the defaults: {}", e);
+            }
+        }
+        
+        if let Err(e) = HardwareController::stop_fan_curve() {
+            warn!("Failed to return fans to automatic control: {}", e);
+        }
+    }
+    
+    fn processes(&self) -> Vec<ProcessInfo> {
+        let total_memory = self.system.total_memory().max(1) as f32;
+        self.system.processes().iter()
+            .map(|(pid, process)| ProcessInfo {
+                pid: pid.as_u32(),
+                name: process.name().to_string(),
+                cpu_usage: process.cpu_usage(),
+                memory_usage: process.memory(),
+                memory_percent: process.memory() as f32 / total_memory * 100.0,
+                status: format!("{:?}", process.status()),
+                command: process.cmd().join(" "),
+            })
+            .collect()
+    }
+}
+
+/// Every NVIDIA GPU that reports min, default and max power limits
+fn nvidia_power_ranges() -> Vec<NvidiaPowerRange> {
+    let output = match Command::new("nvidia-smi")
+        .args(["--query-gpu=index,power.min_limit,power.default_limit,power.max_limit", "--format=csv,noheader,nounits"])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+    
+    String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            match fields.as_slice() {
+                [index, min, default, max] => Some(NvidiaPowerRange {
+                    index: index.to_string(),
+                    min: min.parse().ok()?,
+                    default: default.parse().ok()?,
+                    max: max.parse().ok()?,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,312 @@
+// Benchmark - Repeatable micro-benchmarks to check whether an optimization profile helps
+// Every score is "higher is better" so before/after deltas read the same way for all tests
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug, warn};
+use crate::config;
+use crate::hardware;
+use crate::rapl::PowerMeter;
+
+/// Fixed amount of integer work per CPU run; about 0.5s on one modern core
+const CPU_ITERATIONS: u64 = 200_000_000;
+/// Runs per test; the best one counts, which filters out scheduler noise
+const RUNS_PER_TEST: usize = 3;
+/// Large enough to spill every cache level
+const MEMORY_BUFFER_BYTES: usize = 256 * 1024 * 1024;
+const MEMORY_PASSES: usize = 4;
+/// Same prompt and length every time so token rates are comparable
+const OLLAMA_PROMPT: &str = "Explain in three sentences how a CPU cache works.";
+const OLLAMA_TOKENS: u32 = 128;
+const OLLAMA_TIMEOUT: Duration = Duration::from_secs(300);
+/// CPU package and GPU power are sampled this often while tests run
+const POWER_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+pub const SINGLE_THREAD: &str = "single_thread";
+pub const MULTI_THREAD: &str = "multi_thread";
+pub const MEMORY_BANDWIDTH: &str = "memory_bandwidth_gbps";
+pub const OLLAMA_TOKENS_PER_SEC: &str = "ollama_tokens_per_sec";
+pub const OLLAMA_TOKENS_PER_WATT: &str = "ollama_tokens_per_watt";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkSuite {
+    pub single_thread: bool,
+    pub multi_thread: bool,
+    pub memory_bandwidth: bool,
+    /// Model for an `ollama` token/sec run; skipped when None
+    pub ollama_model: Option<String>,
+}
+
+impl BenchmarkSuite {
+    /// CPU and memory only, a few seconds
+    pub fn quick() -> Self {
+        Self {
+            single_thread: true,
+            multi_thread: true,
+            memory_bandwidth: true,
+            ollama_model: None,
+        }
+    }
+    
+    /// Adds LLM inference speed on `model`
+    pub fn with_ollama(model: &str) -> Self {
+        Self {
+            ollama_model: Some(model.to_string()),
+            ..Self::quick()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    /// Test name -> score. CPU scores are million iterations per second.
+    pub scores: BTreeMap<String, f64>,
+    /// Per-watt figures, e.g. OLLAMA_TOKENS_PER_WATT. Kept apart from `scores` so
+    /// power isn't counted again in average_delta; power_delta_watts covers it.
+    #[serde(default)]
+    pub efficiency: BTreeMap<String, f64>,
+    /// Average CPU package plus GPU draw over the whole suite; None when neither can be read
+    #[serde(default)]
+    pub average_power_watts: Option<f64>,
+    pub duration_secs: f64,
+    pub run_at: DateTime<Utc>,
+}
+
+/// Scores before and after applying a profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkComparison {
+    pub profile: String,
+    pub before: BenchmarkResult,
+    pub after: BenchmarkResult,
+    /// Test name -> change in percent; positive means the profile helped
+    pub deltas: BTreeMap<String, f64>,
+    /// The same for the per-watt figures; not part of average_delta
+    #[serde(default)]
+    pub efficiency_deltas: BTreeMap<String, f64>,
+    /// Change in average CPU plus GPU draw; positive means the profile costs power
+    #[serde(default)]
+    pub power_delta_watts: Option<f64>,
+}
+
+impl BenchmarkComparison {
+    pub fn new(profile: &str, before: BenchmarkResult, after: BenchmarkResult) -> Self {
+        let deltas = percent_changes(&before.scores, &after.scores);
+        let efficiency_deltas = percent_changes(&before.efficiency, &after.efficiency);
+        let power_delta_watts = match (before.average_power_watts, after.average_power_watts) {
+            (Some(before_watts), Some(after_watts)) => Some(after_watts - before_watts),
+            _ => None,
+        };
+        Self { profile: profile.to_string(), before, after, deltas, efficiency_deltas, power_delta_watts }
+    }
+    
+    /// "gaming profile improved single-thread score by 12.0%, multi-thread score by 3.1%"
+    pub fn summary(&self) -> String {
+        if self.deltas.is_empty() {
+            return format!("No comparable benchmark results for the {} profile", self.profile);
+        }
+        
+        let changes: Vec<String> = self.deltas.iter().chain(&self.efficiency_deltas)
+            .map(|(test, delta)| {
+                let label = test_label(test);
+                if delta.abs() < 1.0 {
+                    format!("{} unchanged", label)
+                } else if *delta > 0.0 {
+                    format!("improved {} by {:.1}%", label, delta)
+                } else {
+                    format!("reduced {} by {:.1}%", label, -delta)
+                }
+            })
+            .collect();
+        let power = match self.power_delta_watts {
+            Some(delta) if delta.abs() >= 1.0 => format!(", drawing {:.0} W {}", delta.abs(), if delta > 0.0 { "more" } else { "less" }),
+            _ => String::new(),
+        };
+        format!("{} profile {}{}", self.profile, changes.join(", "), power)
+    }
+    
+    /// Mean change across tests, the single number used to learn whether a profile pays off.
+    /// Per-watt figures are left out; power is judged separately from power_delta_watts.
+    pub fn average_delta(&self) -> f64 {
+        if self.deltas.is_empty() {
+            return 0.0;
+        }
+        self.deltas.values().sum::<f64>() / self.deltas.len() as f64
+    }
+}
+
+pub async fn run_suite(suite: &BenchmarkSuite) -> Result<BenchmarkResult, Box<dyn std::error::Error>> {
+    info!("⏱️ Running benchmark suite");
+    let started = Instant::now();
+    let mut scores = BTreeMap::new();
+    let mut efficiency = BTreeMap::new();
+    
+    // The CPU and memory tests block, keep them off the async workers
+    let cpu_suite = suite.clone();
+    let (cpu_scores, cpu_power) = measure_power(tokio::task::spawn_blocking(move || {
+        let mut scores = BTreeMap::new();
+        if cpu_suite.single_thread {
+            scores.insert(SINGLE_THREAD.to_string(), best_of(cpu_single_thread));
+        }
+        if cpu_suite.multi_thread {
+            scores.insert(MULTI_THREAD.to_string(), best_of(cpu_multi_thread));
+        }
+        if cpu_suite.memory_bandwidth {
+            scores.insert(MEMORY_BANDWIDTH.to_string(), best_of(memory_bandwidth));
+        }
+        scores
+    })).await;
+    scores.extend(cpu_scores?);
+    let mut power_samples = cpu_power;
+    
+    if let Some(model) = &suite.ollama_model {
+        let (rate, ollama_power) = measure_power(ollama_tokens_per_sec(model)).await;
+        match rate {
+            Ok(rate) => {
+                scores.insert(OLLAMA_TOKENS_PER_SEC.to_string(), rate);
+                if let Some(watts) = average(&ollama_power).filter(|watts| *watts > 0.0) {
+                    efficiency.insert(OLLAMA_TOKENS_PER_WATT.to_string(), rate / watts);
+                }
+            }
+            Err(e) => warn!("Skipping Ollama benchmark: {}", e),
+        }
+        power_samples.extend(ollama_power);
+    }
+    
+    let result = BenchmarkResult {
+        scores,
+        efficiency,
+        average_power_watts: average(&power_samples),
+        duration_secs: started.elapsed().as_secs_f64(),
+        run_at: Utc::now(),
+    };
+    debug!("⏱️ Benchmark scores: {:?}", result.scores);
+    Ok(result)
+}
+
+/// Run `work`, sampling CPU package plus GPU power every `POWER_SAMPLE_INTERVAL`
+/// meanwhile. The RAPL reading covers the whole interval; the GPU one is instantaneous.
+async fn measure_power<T>(work: impl Future<Output = T>) -> (T, Vec<f64>) {
+    let mut meter = PowerMeter::new();
+    // Primes the meter so the first sample covers one interval
+    meter.read_watts();
+    let mut ticker = tokio::time::interval(POWER_SAMPLE_INTERVAL);
+    ticker.tick().await;
+    
+    let mut samples = Vec::new();
+    tokio::pin!(work);
+    let output = loop {
+        tokio::select! {
+            output = &mut work => break output,
+            _ = ticker.tick() => {
+                let cpu = meter.read_watts();
+                let gpu = hardware::read_gpu_power().await;
+                if cpu.is_some() || gpu.is_some() {
+                    samples.push(cpu.unwrap_or(0.0) + gpu.unwrap_or(0.0));
+                }
+            }
+        }
+    };
+    (output, samples)
+}
+
+fn average(samples: &[f64]) -> Option<f64> {
+    (!samples.is_empty()).then(|| samples.iter().sum::<f64>() / samples.len() as f64)
+}
+
+fn best_of(test: fn() -> f64) -> f64 {
+    (0..RUNS_PER_TEST).map(|_| test()).fold(0.0, f64::max)
+}
+
+/// xorshift plus a multiply: integer ALU work the compiler can't fold away
+fn cpu_work(iterations: u64, seed: u64) -> u64 {
+    let mut x = seed | 1;
+    let mut acc = 0u64;
+    for i in 0..iterations {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        acc = acc.wrapping_add(x.wrapping_mul(i | 1));
+    }
+    acc
+}
+
+/// Million iterations per second on one thread
+fn cpu_single_thread() -> f64 {
+    let started = Instant::now();
+    black_box(cpu_work(black_box(CPU_ITERATIONS), 0x9E37_79B9));
+    CPU_ITERATIONS as f64 / started.elapsed().as_secs_f64() / 1e6
+}
+
+/// Million iterations per second summed over every logical CPU
+fn cpu_multi_thread() -> f64 {
+    let threads = config::get().hardware.cpu_threads().max(1);
+    let started = Instant::now();
+    std::thread::scope(|scope| {
+        for t in 0..threads {
+            scope.spawn(move || black_box(cpu_work(black_box(CPU_ITERATIONS), 0x9E37_79B9 + t as u64)));
+        }
+    });
+    (CPU_ITERATIONS * threads as u64) as f64 / started.elapsed().as_secs_f64() / 1e6
+}
+
+/// Copy bandwidth in GB/s (bytes read plus bytes written)
+fn memory_bandwidth() -> f64 {
+    let source = vec![1u8; MEMORY_BUFFER_BYTES];
+    let mut destination = vec![0u8; MEMORY_BUFFER_BYTES];
+    
+    let started = Instant::now();
+    for _ in 0..MEMORY_PASSES {
+        destination.copy_from_slice(black_box(&source));
+        black_box(&mut destination);
+    }
+    let bytes = (MEMORY_BUFFER_BYTES * MEMORY_PASSES * 2) as f64;
+    bytes / started.elapsed().as_secs_f64() / 1e9
+}
+
+/// Generation speed from Ollama's own eval counters, so load time doesn't count
+async fn ollama_tokens_per_sec(model: &str) -> Result<f64, Box<dyn std::error::Error>> {
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(format!("{}/api/generate", config::get().ollama.base_url()))
+        .timeout(OLLAMA_TIMEOUT)
+        .json(&serde_json::json!({
+            "model": model,
+            "prompt": OLLAMA_PROMPT,
+            "stream": false,
+            "options": { "num_predict": OLLAMA_TOKENS, "temperature": 0, "seed": 42 },
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    
+    let eval_count = response["eval_count"].as_f64().ok_or("Ollama response missing eval_count")?;
+    let eval_duration_ns = response["eval_duration"].as_f64().filter(|d| *d > 0.0)
+        .ok_or("Ollama response missing eval_duration")?;
+    Ok(eval_count / (eval_duration_ns / 1e9))
+}
+
+/// Test name -> change in percent for tests in both runs
+fn percent_changes(before: &BTreeMap<String, f64>, after: &BTreeMap<String, f64>) -> BTreeMap<String, f64> {
+    before.iter()
+        .filter_map(|(test, before_score)| {
+            let after_score = after.get(test)?;
+            (*before_score > 0.0).then(|| (test.clone(), (after_score - before_score) / before_score * 100.0))
+        })
+        .collect()
+}
+
+fn test_label(test: &str) -> &str {
+    match test {
+        SINGLE_THREAD => "single-thread score",
+        MULTI_THREAD => "multi-thread score",
+        MEMORY_BANDWIDTH => "memory bandwidth",
+        OLLAMA_TOKENS_PER_SEC => "LLM tokens/sec",
+        OLLAMA_TOKENS_PER_WATT => "LLM tokens per watt",
+        other => other,
+    }
+}
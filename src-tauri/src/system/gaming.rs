@@ -0,0 +1,260 @@
+// Gaming Process Scheduling - P-core pinning for games, E-cores for background work
+// Affinity and niceness are per thread on Linux, so every thread in /proc/<pid>/task is changed
+
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+use std::sync::Mutex;
+use nix::sched::{sched_getaffinity, sched_setaffinity, CpuSet};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug, warn};
+use super::SystemController;
+
+/// Niceness given to a pinned game; negative needs root
+const GAME_NICE: i32 = -5;
+
+/// Daemons that do heavy indexing/maintenance in the background
+const DEFAULT_BACKGROUND_DAEMONS: &[&str] = &[
+    "baloo_file",
+    "baloo_file_extr",
+    "tracker-miner-f",
+    "tracker-extract",
+    "packagekitd",
+    "pamac-daemon",
+    "snapperd",
+    "updatedb",
+    "mandb",
+    "syncthing",
+    "dropbox",
+];
+
+/// Affinity and niceness of one thread before it was changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadScheduling {
+    pub tid: u32,
+    pub cpus: Vec<usize>,
+    pub nice: i32,
+}
+
+/// Everything changed when a game was pinned, so it can be undone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinnedGame {
+    pub pid: u32,
+    pub name: String,
+    pub game_threads: Vec<ThreadScheduling>,
+    pub background_threads: Vec<ThreadScheduling>,
+}
+
+pub struct GamingOptimizer {
+    /// Process names (as in /proc/<pid>/comm, 15 chars max) moved to E-cores
+    pub background_daemons: Vec<String>,
+    pinned: Mutex<HashMap<u32, PinnedGame>>,
+}
+
+impl GamingOptimizer {
+    pub fn new() -> Self {
+        Self {
+            background_daemons: DEFAULT_BACKGROUND_DAEMONS.iter().map(|d| d.to_string()).collect(),
+            pinned: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Pin `pid` to the P-cores at nice -5 and move background daemons to the E-cores
+    pub fn pin_game_process(&self, pid: u32) -> Result<PinnedGame, Box<dyn std::error::Error>> {
+        let (p_cores, e_cores) = SystemController::detect_core_types()?;
+        if p_cores.is_empty() {
+            return Err("No P-cores detected".into());
+        }
+        
+        let name = process_name(pid).ok_or(format!("Process {} not found", pid))?;
+        info!("🎮 Pinning {} ({}) to P-cores {:?}", name, pid, p_cores);
+        
+        // Re-pinning the same game must keep the original state for restore
+        self.restore_process_scheduling(pid).ok();
+        
+        let mut game_threads = Vec::new();
+        for tid in process_threads(pid) {
+            match apply_scheduling(tid, &p_cores, Some(GAME_NICE)) {
+                Ok(saved) => game_threads.push(saved),
+                Err(e) => warn!("⚠️ Failed to pin thread {} of {}: {}", tid, name, e),
+            }
+        }
+        if game_threads.is_empty() {
+            return Err(format!("Could not change scheduling of any thread in {}", pid).into());
+        }
+        
+        let mut background_threads = Vec::new();
+        if e_cores.is_empty() {
+            warn!("No E-cores detected; background daemons left in place");
+        } else {
+            for daemon_pid in self.find_background_daemons() {
+                for tid in process_threads(daemon_pid) {
+                    match apply_scheduling(tid, &e_cores, None) {
+                        Ok(saved) => background_threads.push(saved),
+                        Err(e) => debug!("Could not move background thread {}: {}", tid, e),
+                    }
+                }
+            }
+            info!("🧹 Moved {} background threads to E-cores {:?}", background_threads.len(), e_cores);
+        }
+        
+        let pinned = PinnedGame {
+            pid,
+            name,
+            game_threads,
+            background_threads,
+        };
+        self.pinned.lock().map_err(|e| e.to_string())?.insert(pid, pinned.clone());
+        
+        Ok(pinned)
+    }
+    
+    /// Pin the process using the most GPU, for when the caller has no pid
+    pub fn pin_foreground_game(&self) -> Result<PinnedGame, Box<dyn std::error::Error>> {
+        let pid = detect_foreground_game().ok_or("No process is using the GPU")?;
+        self.pin_game_process(pid)
+    }
+    
+    /// Put the game and the daemons moved for it back to their previous affinity and niceness
+    pub fn restore_process_scheduling(&self, pid: u32) -> Result<(), Box<dyn std::error::Error>> {
+        let pinned = self.pinned.lock().map_err(|e| e.to_string())?.remove(&pid)
+            .ok_or(format!("Process {} was not pinned", pid))?;
+        
+        // Threads that exited since pinning simply fail; nothing to restore there
+        for saved in pinned.game_threads.iter().chain(pinned.background_threads.iter()) {
+            if let Err(e) = apply_scheduling(saved.tid, &saved.cpus, Some(saved.nice)) {
+                debug!("Could not restore thread {}: {}", saved.tid, e);
+            }
+        }
+        
+        info!("↩️ Restored scheduling for {} ({})", pinned.name, pid);
+        Ok(())
+    }
+    
+    pub fn pinned_games(&self) -> Vec<PinnedGame> {
+        self.pinned.lock().map(|pinned| pinned.values().cloned().collect()).unwrap_or_default()
+    }
+    
+    fn find_background_daemons(&self) -> Vec<u32> {
+        list_pids().into_iter()
+            .filter(|pid| {
+                process_name(*pid)
+                    .map(|name| self.background_daemons.iter().any(|d| *d == name))
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+}
+
+/// Set affinity (and optionally niceness) of one thread, returning its previous state
+fn apply_scheduling(tid: u32, cpus: &[usize], nice: Option<i32>) -> Result<ThreadScheduling, Box<dyn std::error::Error>> {
+    let thread = Pid::from_raw(tid as i32);
+    
+    let current = sched_getaffinity(thread)?;
+    let previous_cpus: Vec<usize> = (0..CpuSet::count())
+        .filter(|cpu| current.is_set(*cpu).unwrap_or(false))
+        .collect();
+    let previous_nice = thread_nice(tid).unwrap_or(0);
+    
+    let mut cpu_set = CpuSet::new();
+    for cpu in cpus {
+        cpu_set.set(*cpu)?;
+    }
+    sched_setaffinity(thread, &cpu_set)?;
+    
+    if let Some(nice) = nice {
+        if nice != previous_nice {
+            let output = Command::new("renice")
+                .args(&["-n", &nice.to_string(), "-p", &tid.to_string()])
+                .output()?;
+            if !output.status.success() {
+                return Err(format!("renice failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+            }
+        }
+    }
+    
+    Ok(ThreadScheduling {
+        tid,
+        cpus: previous_cpus,
+        nice: previous_nice,
+    })
+}
+
+/// Process with the highest GPU utilisation: nvidia-smi pmon first, then the
+/// DRM fdinfo render-engine time that amdgpu/i915/xe expose
+pub fn detect_foreground_game() -> Option<u32> {
+    if let Ok(output) = Command::new("nvidia-smi").args(&["pmon", "-c", "1", "-s", "u"]).output() {
+        if output.status.success() {
+            // # gpu  pid  type  sm  mem  enc  dec  command
+            let best = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+                .filter_map(|line| {
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    let pid = fields.get(1)?.parse::<u32>().ok()?;
+                    let sm = fields.get(3)?.parse::<u32>().unwrap_or(0);
+                    Some((pid, sm))
+                })
+                .max_by_key(|(_, sm)| *sm);
+            if let Some((pid, _)) = best {
+                return Some(pid);
+            }
+        }
+    }
+    
+    list_pids().into_iter()
+        .filter_map(|pid| drm_render_time(pid).map(|ns| (pid, ns)))
+        .filter(|(_, ns)| *ns > 0)
+        .max_by_key(|(_, ns)| *ns)
+        .map(|(pid, _)| pid)
+}
+
+/// Total drm-engine-{gfx,render} time (ns) across the process's DRM file descriptors
+fn drm_render_time(pid: u32) -> Option<u64> {
+    let entries = fs::read_dir(format!("/proc/{}/fdinfo", pid)).ok()?;
+    let mut total = 0u64;
+    for entry in entries.flatten() {
+        let content = match fs::read_to_string(entry.path()) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        for line in content.lines() {
+            if let Some(value) = line.strip_prefix("drm-engine-gfx:").or_else(|| line.strip_prefix("drm-engine-render:")) {
+                total += value.trim().trim_end_matches("ns").trim().parse::<u64>().unwrap_or(0);
+            }
+        }
+    }
+    if total > 0 { Some(total) } else { None }
+}
+
+fn list_pids() -> Vec<u32> {
+    fs::read_dir("/proc")
+        .map(|entries| {
+            entries.flatten()
+                .filter_map(|entry| entry.file_name().to_string_lossy().parse::<u32>().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn process_threads(pid: u32) -> Vec<u32> {
+    fs::read_dir(format!("/proc/{}/task", pid))
+        .map(|entries| {
+            entries.flatten()
+                .filter_map(|entry| entry.file_name().to_string_lossy().parse::<u32>().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn process_name(pid: u32) -> Option<String> {
+    fs::read_to_string(format!("/proc/{}/comm", pid)).ok().map(|name| name.trim().to_string())
+}
+
+/// Field 19 of /proc/<tid>/stat, read after the ")" since comm may contain spaces
+fn thread_nice(tid: u32) -> Option<i32> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", tid)).ok()?;
+    let after_comm = &stat[stat.rfind(')')? + 1..];
+    after_comm.split_whitespace().nth(16)?.parse().ok()
+}
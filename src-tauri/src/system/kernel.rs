@@ -0,0 +1,337 @@
+// Kernel Build Module - Structured, cancellable custom kernel compilation
+// Replaces the old compile-custom-kernel.sh heredoc from i9-13900hx-optimizations
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command as AsyncCommand;
+use tokio::sync::oneshot;
+use tracing::{info, debug, warn};
+
+const KERNEL_GIT_URL: &str = "https://git.kernel.org/pub/scm/linux/kernel/git/stable/linux.git";
+const LOG_TAIL_LINES: usize = 200;
+
+// Kernel options for the i9-13900HX build. Applied with scripts/config so
+// dependencies are resolved by olddefconfig instead of being silently dropped.
+const KERNEL_CONFIG_OPTIONS: &[(&str, bool)] = &[
+    // CPU optimizations
+    ("GENERIC_CPU", false),
+    ("MALDERLAKE", true), // Intel 13th gen
+    ("X86_INTEL_PSTATE", true),
+    ("CPU_FREQ_DEFAULT_GOV_PERFORMANCE", true),
+    // Gaming optimizations
+    ("PREEMPT_VOLUNTARY", false),
+    ("PREEMPT", true),
+    ("PREEMPT_COUNT", true),
+    ("PREEMPT_RCU", true),
+    ("HIGH_RES_TIMERS", true),
+    ("NO_HZ_FULL", true),
+    // Virtualization support
+    ("KVM", true),
+    ("KVM_INTEL", true),
+    ("VFIO", true),
+    ("VFIO_PCI", true),
+    ("VFIO_MDEV", true),
+    // AI/ML optimizations
+    ("TRANSPARENT_HUGEPAGE", true),
+    ("TRANSPARENT_HUGEPAGE_ALWAYS", true),
+    ("NUMA_BALANCING", true),
+    ("NUMA_BALANCING_DEFAULT_ENABLED", true),
+    // Storage optimizations
+    ("BLK_DEV_NVME", true),
+    ("NVME_CORE", true),
+    ("BLK_MQ_VIRTIO", true),
+    ("MQ_IOSCHED_DEADLINE", true),
+    // Container support
+    ("NAMESPACES", true),
+    ("CGROUPS", true),
+    // Security features
+    ("SECURITY_APPARMOR", true),
+    ("DEFAULT_SECURITY_APPARMOR", true),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum KernelBuildStage {
+    Pending,
+    Fetching,
+    Configuring,
+    Compiling,
+    InstallingModules,
+    InstallingKernel,
+    UpdatingBootloader,
+    Completed,
+    Cancelled,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KernelBuildStatus {
+    pub stage: KernelBuildStage,
+    pub percent: u8,
+    pub log_tail: VecDeque<String>,
+}
+
+impl Default for KernelBuildStatus {
+    fn default() -> Self {
+        Self {
+            stage: KernelBuildStage::Pending,
+            percent: 0,
+            log_tail: VecDeque::new(),
+        }
+    }
+}
+
+pub enum StageResult {
+    Finished,
+    Cancelled,
+}
+
+pub struct KernelBuild {
+    pub build_dir: PathBuf,
+    status: Arc<Mutex<KernelBuildStatus>>,
+    cancel: oneshot::Receiver<()>,
+    cancel_open: bool,
+}
+
+impl KernelBuild {
+    pub fn new(build_dir: PathBuf, status: Arc<Mutex<KernelBuildStatus>>, cancel: oneshot::Receiver<()>) -> Self {
+        Self {
+            build_dir,
+            status,
+            cancel,
+            cancel_open: true,
+        }
+    }
+    
+    fn source_dir(&self) -> PathBuf {
+        self.build_dir.join("linux-stable")
+    }
+    
+    /// Run every stage in order. Returns the kernel tag that was built, or
+    /// `None` if the build was cancelled.
+    pub async fn run(&mut self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        match self.run_stages().await {
+            Ok(Some(tag)) => {
+                self.set_stage(KernelBuildStage::Completed, 100);
+                Ok(Some(tag))
+            }
+            Ok(None) => {
+                let percent = self.status.lock().unwrap().percent;
+                self.set_stage(KernelBuildStage::Cancelled, percent);
+                Ok(None)
+            }
+            Err(e) => {
+                let percent = self.status.lock().unwrap().percent;
+                self.set_stage(KernelBuildStage::Failed(e.to_string()), percent);
+                Err(e)
+            }
+        }
+    }
+    
+    async fn run_stages(&mut self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.build_dir)?;
+        let source_dir = self.source_dir();
+        
+        // Stage 1: fetch sources and check out the latest stable tag
+        self.set_stage(KernelBuildStage::Fetching, 0);
+        if !source_dir.exists() {
+            let mut clone = AsyncCommand::new("git");
+            clone.arg("clone").arg(KERNEL_GIT_URL).arg(&source_dir);
+            if let StageResult::Cancelled = self.run_command(clone).await? {
+                return Ok(None);
+            }
+        } else {
+            let mut fetch = AsyncCommand::new("git");
+            fetch.args(["fetch", "--tags"]).current_dir(&source_dir);
+            if let StageResult::Cancelled = self.run_command(fetch).await? {
+                return Ok(None);
+            }
+        }
+        
+        let tag = Self::latest_stable_tag(&source_dir).await?;
+        info!("📥 Building kernel {}", tag);
+        
+        let mut checkout = AsyncCommand::new("git");
+        checkout.args(["checkout", &tag]).current_dir(&source_dir);
+        if let StageResult::Cancelled = self.run_command(checkout).await? {
+            return Ok(None);
+        }
+        
+        // Stage 2: base config from the running kernel, then apply our options
+        if self.cancel_requested() {
+            return Ok(None);
+        }
+        self.set_stage(KernelBuildStage::Configuring, 10);
+        if !Self::copy_running_config(&source_dir)? {
+            let mut defconfig = AsyncCommand::new("make");
+            defconfig.arg("defconfig").current_dir(&source_dir);
+            if let StageResult::Cancelled = self.run_command(defconfig).await? {
+                return Ok(None);
+            }
+        }
+        
+        let mut config = AsyncCommand::new(source_dir.join("scripts/config"));
+        config.arg("--file").arg(source_dir.join(".config")).current_dir(&source_dir);
+        for (option, enabled) in KERNEL_CONFIG_OPTIONS {
+            config.arg(if *enabled { "--enable" } else { "--disable" }).arg(option);
+        }
+        if let StageResult::Cancelled = self.run_command(config).await? {
+            return Ok(None);
+        }
+        
+        let mut olddefconfig = AsyncCommand::new("make");
+        olddefconfig.arg("olddefconfig").current_dir(&source_dir);
+        if let StageResult::Cancelled = self.run_command(olddefconfig).await? {
+            return Ok(None);
+        }
+        
+        // Stages 3-6: compile, install, update bootloader
+        let jobs = num_cpus::get() + 4;
+        let steps: [(KernelBuildStage, u8, &str, Vec<String>); 4] = [
+            (KernelBuildStage::Compiling, 15, "make", vec![format!("-j{}", jobs)]),
+            (KernelBuildStage::InstallingModules, 85, "sudo", vec!["make".into(), "modules_install".into()]),
+            (KernelBuildStage::InstallingKernel, 92, "sudo", vec!["make".into(), "install".into()]),
+            (KernelBuildStage::UpdatingBootloader, 97, "sudo", vec!["grub-mkconfig".into(), "-o".into(), "/boot/grub/grub.cfg".into()]),
+        ];
+        
+        for (stage, percent, program, args) in steps {
+            if self.cancel_requested() {
+                return Ok(None);
+            }
+            self.set_stage(stage, percent);
+            
+            let mut command = AsyncCommand::new(program);
+            command.args(&args).current_dir(&source_dir);
+            if let StageResult::Cancelled = self.run_command(command).await? {
+                return Ok(None);
+            }
+        }
+        
+        Ok(Some(tag))
+    }
+    
+    fn set_stage(&self, stage: KernelBuildStage, percent: u8) {
+        debug!("🔨 Kernel build stage {:?} ({}%)", stage, percent);
+        let mut status = self.status.lock().unwrap();
+        status.stage = stage;
+        status.percent = percent;
+    }
+    
+    fn cancel_requested(&mut self) -> bool {
+        if !self.cancel_open {
+            return false;
+        }
+        match self.cancel.try_recv() {
+            Ok(()) => {
+                self.cancel_open = false;
+                true
+            }
+            Err(oneshot::error::TryRecvError::Empty) => false,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                // Nobody can cancel any more
+                self.cancel_open = false;
+                false
+            }
+        }
+    }
+    
+    /// Spawn a command, stream its output into the log tail and wait for it,
+    /// killing it if a cancel signal arrives first.
+    async fn run_command(&mut self, mut command: AsyncCommand) -> Result<StageResult, Box<dyn std::error::Error>> {
+        if self.cancel_requested() {
+            return Ok(StageResult::Cancelled);
+        }
+        
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        
+        let stdout_task = child.stdout.take().map(|out| tokio::spawn(Self::stream_lines(out, self.status.clone())));
+        let stderr_task = child.stderr.take().map(|err| tokio::spawn(Self::stream_lines(err, self.status.clone())));
+        
+        let exit_status = tokio::select! {
+            exit = child.wait() => exit?,
+            signal = &mut self.cancel, if self.cancel_open => {
+                self.cancel_open = false;
+                if signal.is_ok() {
+                    warn!("⏹️ Kernel build cancelled, stopping current stage");
+                    let _ = child.kill().await;
+                    return Ok(StageResult::Cancelled);
+                }
+                // Sender dropped without cancelling; keep waiting for the stage
+                child.wait().await?
+            }
+        };
+        
+        for task in [stdout_task, stderr_task].into_iter().flatten() {
+            let _ = task.await;
+        }
+        
+        if exit_status.success() {
+            Ok(StageResult::Finished)
+        } else {
+            let stage = self.status.lock().unwrap().stage.clone();
+            Err(format!("Kernel build failed during {:?} ({})", stage, exit_status).into())
+        }
+    }
+    
+    async fn stream_lines<R: AsyncRead + Unpin>(reader: R, status: Arc<Mutex<KernelBuildStatus>>) {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let mut status = status.lock().unwrap();
+            status.log_tail.push_back(line);
+            while status.log_tail.len() > LOG_TAIL_LINES {
+                status.log_tail.pop_front();
+            }
+        }
+    }
+    
+    async fn latest_stable_tag(source_dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+        let output = AsyncCommand::new("git")
+            .arg("tag")
+            .current_dir(source_dir)
+            .output()
+            .await?;
+        
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|tag| {
+                let version: Vec<u32> = tag.strip_prefix('v')?
+                    .split('.')
+                    .map(|part| part.parse().ok())
+                    .collect::<Option<Vec<u32>>>()?;
+                (version.len() == 3).then(|| (version, tag.to_string()))
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, tag)| tag)
+            .ok_or_else(|| "No stable kernel tags found".into())
+    }
+    
+    /// Seed .config from the running kernel. Returns false if no config was found.
+    fn copy_running_config(source_dir: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+        let target = source_dir.join(".config");
+        
+        if let Ok(compressed) = fs::File::open("/proc/config.gz") {
+            let mut config = String::new();
+            flate2::read::GzDecoder::new(compressed).read_to_string(&mut config)?;
+            fs::write(&target, config)?;
+            return Ok(true);
+        }
+        
+        let release = fs::read_to_string("/proc/sys/kernel/osrelease").unwrap_or_default();
+        let boot_config = PathBuf::from(format!("/boot/config-{}", release.trim()));
+        if boot_config.exists() {
+            fs::copy(&boot_config, &target)?;
+            return Ok(true);
+        }
+        
+        Ok(false)
+    }
+}
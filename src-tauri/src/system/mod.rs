@@ -1,21 +1,34 @@
 // System Controller - Integrating i9-13900HX optimizations
 // Based on https://github.com/wlfogle/i9-13900hx-optimizations
 
-use std::collections::HashMap;
-use std::process::{Command, Stdio};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::process::Command;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
 use tracing::{info, debug, warn, error};
 use tokio::process::Command as AsyncCommand;
+use tokio::sync::oneshot;
 use crate::ai::SystemState;
+use crate::config;
+use crate::error::{SysError, SysResult};
+use crate::hardware::CpuTopology;
+use crate::privilege::{PrivilegedBatch, SYSCTL_DROP_IN};
 
+pub mod auto_profile;
+pub mod benchmark;
 pub mod kernel;
 pub mod ollama;
 pub mod gaming;
 pub mod virtualization;
 pub mod security;
 
+use benchmark::{BenchmarkComparison, BenchmarkResult, BenchmarkSuite};
+use kernel::{KernelBuild, KernelBuildStatus};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemController {
     pub current_governor: String,
@@ -26,6 +39,21 @@ pub struct SystemController {
     pub security_hardening: bool,
     pub huge_pages_enabled: bool,
     pub performance_profile: PerformanceProfile,
+    pub undervolt_confirmed: bool,
+    /// What this app has changed since startup; `revert_all` only undoes these
+    pub applied_changes: BTreeSet<AppliedChange>,
+}
+
+/// A kind of system setting the app changes and knows how to put back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AppliedChange {
+    CpuGovernor,
+    CpuEnergyPreference,
+    CpuIdleStates,
+    CpuFrequencyLimit,
+    GpuClocks,
+    ManualFans,
+    SysctlDropIn,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +74,79 @@ pub struct OllamaConfig {
     pub monitoring_enabled: bool,
 }
 
+/// Hugepage sizes the kernel can expose under /sys/kernel/mm/hugepages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HugePageSize {
+    Size2M,
+    /// Only when the CPU supports pdpe1gb
+    Size1G,
+}
+
+impl HugePageSize {
+    pub const ALL: [HugePageSize; 2] = [HugePageSize::Size2M, HugePageSize::Size1G];
+    
+    pub fn size_kb(self) -> u64 {
+        match self {
+            HugePageSize::Size2M => 2048,
+            HugePageSize::Size1G => 1_048_576,
+        }
+    }
+    
+    fn sysfs_dir(self) -> PathBuf {
+        PathBuf::from(format!("/sys/kernel/mm/hugepages/hugepages-{}kB", self.size_kb()))
+    }
+}
+
+/// Hugepage pool counts for one page size, as the kernel reports them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HugePageStatus {
+    pub size: HugePageSize,
+    pub total: u64,
+    pub free: u64,
+    /// Promised to a mapping but not faulted in yet
+    pub reserved: u64,
+}
+
+impl HugePageStatus {
+    pub fn total_bytes(&self) -> u64 {
+        self.total * self.size.size_kb() * 1024
+    }
+}
+
+/// scaling_min_freq / scaling_max_freq as written to one CPU, after clamping
+/// to its cpuinfo range. Hybrid CPUs clamp differently on P- and E-cores.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuFrequencyLimit {
+    pub cpu: usize,
+    pub min_khz: u32,
+    pub max_khz: u32,
+    /// The request fell outside what this core supports
+    pub clamped: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndervoltOffsets {
+    pub core_offset_mv: i32,
+    pub cache_offset_mv: i32,
+}
+
+// MSR 0x150 (OC mailbox) voltage planes, same layout intel-undervolt uses
+const MSR_OC_MAILBOX: u64 = 0x150;
+const VOLTAGE_PLANE_CORE: u64 = 0;
+const VOLTAGE_PLANE_CACHE: u64 = 2;
+const MAX_UNDERVOLT_MV: i32 = -150;
+const MSR_DEVICE: &str = "/dev/cpu/0/msr";
+
+/// scaling_max_freq during emergency cooling
+const EMERGENCY_MAX_FREQ_KHZ: u32 = 3_000_000;
+
+/// Hugepages are unusable for anything but hugepage mappings; below this share
+/// of RAM left as normal memory the desktop starts swapping or OOM-killing
+const MIN_NORMAL_MEMORY_PERCENT: u64 = 25;
+
+/// Pause after switching profiles so clocks and governors settle before measuring
+const BENCHMARK_SETTLE_TIME: std::time::Duration = std::time::Duration::from_secs(5);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PerformanceProfile {
     Gaming,
@@ -53,10 +154,12 @@ pub enum PerformanceProfile {
     LLMInference,
     Virtualization,
     Balanced,
+    /// Settings follow the detected workload and temperatures, see `set_auto_profile`
+    Auto,
 }
 
 impl SystemController {
-    pub async fn new_garuda() -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new_garuda() -> SysResult<Self> {
         info!("🚀 Initializing System Controller with Garuda Linux optimizations...");
         
         let mut controller = Self {
@@ -80,6 +183,8 @@ impl SystemController {
             security_hardening: false,
             huge_pages_enabled: false,
             performance_profile: PerformanceProfile::Balanced,
+            undervolt_confirmed: false,
+            applied_changes: BTreeSet::new(),
         };
         
         // Detect current system state
@@ -91,7 +196,7 @@ impl SystemController {
         Ok(controller)
     }
     
-    async fn detect_current_configuration(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn detect_current_configuration(&mut self) -> SysResult<()> {
         debug!("🔍 Detecting current system configuration...");
         
         // Check current CPU governor
@@ -110,25 +215,9 @@ impl SystemController {
         }
         
         // Check huge pages
-        if let Ok(hugepages) = fs::read_to_string("/proc/meminfo") {
-            if hugepages.contains("HugePages_Total:") && !hugepages.contains("HugePages_Total:        0") {
-                self.huge_pages_enabled = true;
-                
-                // Extract huge pages size for Ollama
-                for line in hugepages.lines() {
-                    if line.starts_with("HugePages_Total:") {
-                        if let Some(total_str) = line.split_whitespace().nth(1) {
-                            if let Ok(total) = total_str.parse::<u32>() {
-                                // Each huge page is typically 2MB
-                                self.ollama_config.huge_pages_gb = (total * 2) / 1024;
-                                if self.ollama_config.huge_pages_gb > 0 {
-                                    self.ollama_config.optimized = true;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+        self.refresh_hugepage_config();
+        if self.ollama_config.huge_pages_gb > 0 {
+            self.ollama_config.optimized = true;
         }
         
         // Check if Ollama is installed and configured
@@ -146,7 +235,7 @@ impl SystemController {
         Ok(())
     }
     
-    async fn initialize_i9_optimizations(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    async fn initialize_i9_optimizations(&mut self) -> SysResult<()> {
         info!("🔧 Initializing i9-13900HX specific optimizations...");
         
         // Set up performance tweaks specific to i9-13900HX
@@ -165,164 +254,68 @@ impl SystemController {
             "5400000".to_string() // 5.4 GHz maximum
         );
         
-        // i9-13900HX has 24 cores (8 P-cores + 16 E-cores)
+        // Detected unless overridden in the config (24 cores / 32 threads on the i9-13900HX)
+        let hardware = config::get().hardware;
+        let topology = CpuTopology::detect();
         self.kernel_optimizations.performance_tweaks.insert(
             "cores_total".to_string(), 
-            "24".to_string()
+            hardware.cpu_cores.unwrap_or(topology.physical_cores).to_string()
         );
         
         self.kernel_optimizations.performance_tweaks.insert(
             "threads_total".to_string(), 
-            "32".to_string() // 8P-cores with HT + 16 E-cores
+            hardware.cpu_threads.unwrap_or(topology.threads).to_string()
+        );
+        
+        self.kernel_optimizations.performance_tweaks.insert(
+            "cores_performance".to_string(), 
+            topology.performance_core_count().to_string()
+        );
+        
+        self.kernel_optimizations.performance_tweaks.insert(
+            "cores_efficiency".to_string(), 
+            topology.efficiency_core_count().to_string()
         );
         
         Ok(())
     }
     
-    pub async fn compile_custom_kernel(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    /// Build and install a custom kernel tuned for the i9-13900HX.
+    ///
+    /// Progress (stage, percent, log tail) is published through `status` so the
+    /// caller can poll it; sending on `cancel` stops the build at the current stage.
+    pub async fn compile_custom_kernel(
+        &mut self,
+        status: Arc<Mutex<KernelBuildStatus>>,
+        cancel: oneshot::Receiver<()>,
+    ) -> SysResult<String> {
         info!("🔨 Starting custom kernel compilation for i9-13900HX...");
         
-        // Based on compile-custom-kernel.sh from i9-13900hx-optimizations
-        let compilation_script = r#"#!/bin/bash
-# Custom Kernel Compilation for i9-13900HX
-# Integrated from https://github.com/wlfogle/i9-13900hx-optimizations
-
-set -e
-
-echo "🚀 Starting custom kernel compilation for i9-13900HX..."
-echo "💻 Detected: 24 cores (8 P-cores + 16 E-cores), 32 threads"
-echo "⚡ Expected compilation time: 12-25 minutes"
-
-# Create build directory
-BUILD_DIR="$HOME/kernel-build"
-mkdir -p "$BUILD_DIR"
-cd "$BUILD_DIR"
-
-# Download latest stable kernel
-echo "📥 Downloading latest stable kernel..."
-if [ ! -d "linux-stable" ]; then
-    git clone https://git.kernel.org/pub/scm/linux/kernel/git/stable/linux.git linux-stable
-fi
-
-cd linux-stable
-git fetch
-git checkout $(git tag | grep -E '^v[0-9]+\.[0-9]+\.[0-9]+$' | sort -V | tail -1)
-
-# Copy current config as base
-if [ -f "/proc/config.gz" ]; then
-    zcat /proc/config.gz > .config
-elif [ -f "/boot/config-$(uname -r)" ]; then
-    cp "/boot/config-$(uname -r)" .config
-else
-    make defconfig
-fi
-
-# Enable i9-13900HX specific optimizations
-echo "🔧 Applying i9-13900HX optimizations..."
-
-# CPU optimizations
-echo "CONFIG_GENERIC_CPU=n" >> .config
-echo "CONFIG_MALDERLAKE=y" >> .config  # Intel 13th gen
-echo "CONFIG_X86_INTEL_PSTATE=y" >> .config
-echo "CONFIG_CPU_FREQ_DEFAULT_GOV_PERFORMANCE=y" >> .config
-
-# Gaming optimizations
-echo "CONFIG_PREEMPT_VOLUNTARY=n" >> .config
-echo "CONFIG_PREEMPT=y" >> .config
-echo "CONFIG_PREEMPT_COUNT=y" >> .config
-echo "CONFIG_PREEMPT_RCU=y" >> .config
-echo "CONFIG_HIGH_RES_TIMERS=y" >> .config
-echo "CONFIG_NO_HZ_FULL=y" >> .config
-
-# Virtualization support
-echo "CONFIG_KVM=y" >> .config
-echo "CONFIG_KVM_INTEL=y" >> .config
-echo "CONFIG_VFIO=y" >> .config
-echo "CONFIG_VFIO_PCI=y" >> .config
-echo "CONFIG_VFIO_MDEV=y" >> .config
-
-# AI/ML optimizations
-echo "CONFIG_TRANSPARENT_HUGEPAGE=y" >> .config
-echo "CONFIG_TRANSPARENT_HUGEPAGE_ALWAYS=y" >> .config
-echo "CONFIG_NUMA_BALANCING=y" >> .config
-echo "CONFIG_NUMA_BALANCING_DEFAULT_ENABLED=y" >> .config
-
-# Storage optimizations
-echo "CONFIG_BLK_DEV_NVME=y" >> .config
-echo "CONFIG_NVME_CORE=y" >> .config
-echo "CONFIG_BLK_MQ_VIRTIO=y" >> .config
-echo "CONFIG_IOSCHED_DEADLINE=y" >> .config
-echo "CONFIG_MQ_IOSCHED_DEADLINE=y" >> .config
-
-# Container support
-echo "CONFIG_NAMESPACES=y" >> .config
-echo "CONFIG_CGROUPS=y" >> .config
-echo "CONFIG_DOCKER=y" >> .config
-
-# Security features
-echo "CONFIG_SECURITY_APPARMOR=y" >> .config
-echo "CONFIG_DEFAULT_SECURITY_APPARMOR=y" >> .config
-
-# Resolve config dependencies
-make olddefconfig
-
-# Compile with optimal job count for i9-13900HX
-JOBS=$(($(nproc) + 4))  # 24 + 4 = 28 jobs
-echo "🔥 Compiling with $JOBS parallel jobs..."
-
-time make -j$JOBS
-
-echo "📦 Installing modules..."
-sudo make modules_install
-
-echo "🏗️ Installing kernel..."
-sudo make install
-
-echo "🔄 Updating GRUB..."
-sudo grub-mkconfig -o /boot/grub/grub.cfg
-
-echo "✅ Custom kernel compilation completed!"
-echo "⚠️  Please reboot to use the new kernel."
-"#;
-        
-        // Write and execute the compilation script
-        let script_path = "/tmp/compile_i9_kernel.sh";
-        fs::write(script_path, compilation_script)?;
-        
-        // Make executable
-        AsyncCommand::new("chmod")
-            .arg("+x")
-            .arg(script_path)
-            .output()
-            .await?;
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        let mut build = KernelBuild::new(PathBuf::from(home).join("kernel-build"), status, cancel);
         
-        // Execute compilation
-        let output = AsyncCommand::new("bash")
-            .arg(script_path)
-            .output()
-            .await?;
-        
-        if output.status.success() {
-            self.kernel_optimizations.custom_kernel_installed = true;
-            self.kernel_optimizations.optimizations_enabled = vec![
-                "i9-13900HX CPU optimization".to_string(),
-                "Gaming performance".to_string(),
-                "Virtualization support".to_string(),
-                "AI/ML acceleration".to_string(),
-                "NVMe storage optimization".to_string(),
-                "Container support".to_string(),
-                "Security hardening".to_string(),
-            ];
-            
-            Ok("✅ Custom kernel compiled successfully! Reboot required.".to_string())
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            Err(format!("Kernel compilation failed: {}", error).into())
+        match build.run().await? {
+            Some(tag) => {
+                self.kernel_optimizations.custom_kernel_installed = true;
+                self.kernel_optimizations.optimizations_enabled = vec![
+                    "i9-13900HX CPU optimization".to_string(),
+                    "Gaming performance".to_string(),
+                    "Virtualization support".to_string(),
+                    "AI/ML acceleration".to_string(),
+                    "NVMe storage optimization".to_string(),
+                    "Container support".to_string(),
+                    "Security hardening".to_string(),
+                ];
+                
+                Ok(format!("✅ Custom kernel {} compiled successfully! Reboot required.", tag))
+            }
+            None => Ok("⏹️ Kernel compilation cancelled".to_string()),
         }
     }
     
-    pub async fn optimize_for_ollama(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn optimize_for_ollama(&mut self) -> SysResult<String> {
         info!("🧠 Optimizing system for Ollama LLM inference...");
+        auto_profile::stop();
         
         // Based on optimize-ollama-system.sh from i9-13900hx-optimizations
         let optimization_script = r#"#!/bin/bash
@@ -332,19 +325,7 @@ echo "⚠️  Please reboot to use the new kernel."
 set -e
 
 echo "🧠 Optimizing system for LLM inference on i9-13900HX..."
-echo "💾 System RAM: 64GB - Configuring for 70B+ models"
-
-# Memory optimization for large models
-TOTAL_MEMORY_GB=64
-HUGEPAGE_SIZE_GB=$((TOTAL_MEMORY_GB * 40 / 100))  # 40% of RAM for huge pages
-
-echo "📊 Configuring ${HUGEPAGE_SIZE_GB}GB of huge pages..."
-
-# Configure huge pages
-echo $((HUGEPAGE_SIZE_GB * 1024 / 2)) | sudo tee /sys/kernel/mm/hugepages/hugepages-2048kB/nr_hugepages
-
-# Make persistent
-echo "vm.nr_hugepages=$((HUGEPAGE_SIZE_GB * 1024 / 2))" | sudo tee -a /etc/sysctl.conf
+echo "💾 System RAM: @TOTAL_MEMORY_GB@GB - Configuring for large models"
 
 # CPU optimization for inference
 echo "⚡ Setting performance governor for all cores..."
@@ -354,30 +335,6 @@ for cpu in /sys/devices/system/cpu/cpu*/cpufreq/scaling_governor; do
     fi
 done
 
-# Disable CPU idle states for consistent performance
-echo "🔥 Disabling CPU idle states for consistent latency..."
-for cpu in /sys/devices/system/cpu/cpu*/cpuidle/state*/disable; do
-    if [ -f "$cpu" ]; then
-        echo 1 | sudo tee "$cpu" 2>/dev/null || true
-    fi
-done
-
-# Optimize kernel parameters for LLM workloads
-echo "🔧 Optimizing kernel parameters..."
-cat << EOF | sudo tee -a /etc/sysctl.conf
-# LLM Inference Optimizations
-vm.swappiness=1
-vm.dirty_ratio=15
-vm.dirty_background_ratio=5
-vm.vfs_cache_pressure=50
-kernel.numa_balancing=1
-kernel.sched_autogroup_enabled=0
-kernel.sched_migration_cost_ns=5000000
-EOF
-
-# Apply sysctl changes
-sudo sysctl -p
-
 # Install and configure Ollama if not present
 if ! command -v ollama &> /dev/null; then
     echo "📦 Installing Ollama..."
@@ -460,7 +417,7 @@ cat << 'EOF' > "$HOME/manage-llm-models.sh"
 
 case "$1" in
     "install-recommended")
-        echo "📥 Installing recommended models for 64GB system..."
+        echo "📥 Installing recommended models for @TOTAL_MEMORY_GB@GB system..."
         ollama pull llama3.1:8b
         ollama pull codellama:13b
         ollama pull mistral:7b
@@ -470,7 +427,7 @@ case "$1" in
         ollama pull llama3.1:70b
         ;;
     "list-sizes")
-        echo "📊 Model size recommendations for 64GB RAM:"
+        echo "📊 Model size recommendations for @TOTAL_MEMORY_GB@GB RAM:"
         echo "Safe (multiple models): 7B, 8B, 13B"
         echo "Large (single model): 30B, 70B"
         echo "Maximum: 70B (requires ~40GB RAM)"
@@ -494,9 +451,29 @@ echo "📊 Run ~/monitor-llm-performance.sh to monitor performance"
 echo "📦 Run ~/manage-llm-models.sh install-recommended to install models"
 "#;
         
+        // LLM inference kernel parameters; 40% of RAM as 2MB huge pages
+        let total_memory_gb = config::get().hardware.total_memory_gb();
+        let huge_pages_gb = total_memory_gb * 40 / 100;
+        let hugepages = huge_pages_gb * 1024 / 2;
+        self.apply_sysctl(&HashMap::from([
+            ("vm.nr_hugepages".to_string(), hugepages.to_string()),
+            ("vm.swappiness".to_string(), "1".to_string()),
+            ("vm.dirty_ratio".to_string(), "15".to_string()),
+            ("vm.dirty_background_ratio".to_string(), "5".to_string()),
+            ("vm.vfs_cache_pressure".to_string(), "50".to_string()),
+            ("kernel.numa_balancing".to_string(), "1".to_string()),
+            ("kernel.sched_autogroup_enabled".to_string(), "0".to_string()),
+            ("kernel.sched_migration_cost_ns".to_string(), "5000000".to_string()),
+        ])).await?;
+        
+        // The sysctl above persists the pool; this allocates it now
+        if let Err(e) = self.set_hugepages(HugePageSize::Size2M, hugepages as u64) {
+            warn!("Failed to allocate huge pages: {}", e);
+        }
+        
         // Execute optimization script
         let script_path = "/tmp/optimize_ollama.sh";
-        fs::write(script_path, optimization_script)?;
+        fs::write(script_path, optimization_script.replace("@TOTAL_MEMORY_GB@", &total_memory_gb.to_string()))?;
         
         AsyncCommand::new("chmod")
             .arg("+x")
@@ -509,54 +486,374 @@ echo "📦 Run ~/manage-llm-models.sh install-recommended to install models"
             .output()
             .await?;
         
+        // The script sets governors even if a later step fails
+        self.applied_changes.insert(AppliedChange::CpuGovernor);
+        
+        // Idle states off for consistent inference latency
+        if let Err(e) = self.disable_all_idle_states() {
+            warn!("Failed to disable CPU idle states: {}", e);
+        }
+        
         if output.status.success() {
             self.ollama_config.optimized = true;
-            self.ollama_config.huge_pages_gb = 25; // 40% of 64GB
             self.ollama_config.performance_governor_set = true;
             self.ollama_config.monitoring_enabled = true;
             
             Ok("✅ System optimized for Ollama LLM inference!".to_string())
         } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            Err(format!("Ollama optimization failed: {}", error).into())
+            Err(SysError::command_failed("Ollama optimization script", &output))
         }
     }
     
-    pub async fn set_cpu_governor(&mut self, governor: &str) -> Result<String, Box<dyn std::error::Error>> {
+    /// Refuses `performance` on battery unless `force` is set; it drains the
+    /// battery quickly and runs a laptop hot with no charger to fall back on.
+    pub async fn set_cpu_governor(&mut self, governor: &str, force: bool) -> SysResult<String> {
         info!("⚡ Setting CPU governor to: {}", governor);
         
-        // Validate governor
-        let valid_governors = ["performance", "powersave", "ondemand", "conservative", "schedutil"];
-        if !valid_governors.contains(&governor) {
-            return Err(format!("Invalid governor: {}. Valid options: {:?}", governor, valid_governors).into());
+        if governor == "performance" && !force && Self::on_ac_power() == Some(false) {
+            return Err(SysError::NotSupported("the performance governor on battery power; connect the charger or force it".to_string()));
+        }
+        
+        // Validate governor against what this hardware's cpufreq driver offers
+        let available = Self::available_governors()?;
+        if !available.iter().any(|g| g == governor) {
+            return Err(SysError::InvalidParameter(format!("governor {}. Valid options: {}", governor, available.join(", "))));
         }
         
-        // Set governor for all CPUs (i9-13900HX has 24 cores)
-        for cpu_id in 0..24 {
-            let governor_path = format!("/sys/devices/system/cpu/cpu{}/cpufreq/scaling_governor", cpu_id);
-            if let Err(e) = fs::write(&governor_path, governor) {
+        // Set governor only on the cores that actually exist
+        let paths = Self::cpu_governor_paths();
+        let mut batch = PrivilegedBatch::new();
+        for (_, governor_path) in &paths {
+            batch.write(governor_path, governor);
+        }
+        let mut write_error = None;
+        for ((cpu_id, _), result) in paths.iter().zip(batch.apply()) {
+            if let Err(e) = result {
                 warn!("Failed to set governor for CPU {}: {}", cpu_id, e);
+                write_error.get_or_insert(e);
             }
         }
+        self.applied_changes.insert(AppliedChange::CpuGovernor);
         
         // Verify the change
-        if let Ok(current) = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor") {
-            self.current_governor = current.trim().to_string();
-            if self.current_governor == governor {
-                Ok(format!("✅ CPU governor set to: {}", governor))
-            } else {
-                Err("Failed to verify governor change".into())
+        let governor_path = Path::new("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor");
+        let current = fs::read_to_string(governor_path).map_err(|e| SysError::io(governor_path, e))?;
+        self.current_governor = current.trim().to_string();
+        if self.current_governor == governor {
+            Ok(format!("✅ CPU governor set to: {}", governor))
+        } else {
+            Err(write_error.unwrap_or_else(|| SysError::Other(format!("Governor is still {} after setting {}", self.current_governor, governor))))
+        }
+    }
+    
+    /// Set the energy performance preference (EPP) on every CPU, e.g. performance,
+    /// balance_performance, balance_power or power. Only intel_pstate and
+    /// amd-pstate in active mode expose it; other drivers get NotSupported.
+    pub fn set_epp(&mut self, epp: &str) -> SysResult<String> {
+        info!("⚡ Setting energy performance preference to: {}", epp);
+        
+        let available = Self::available_epp()?;
+        if !available.iter().any(|p| p == epp) {
+            return Err(SysError::InvalidParameter(format!("energy performance preference {}. Valid options: {}", epp, available.join(", "))));
+        }
+        
+        let paths: Vec<(usize, PathBuf)> = Self::cpu_governor_paths().into_iter()
+            .map(|(cpu_id, governor_path)| (cpu_id, governor_path.with_file_name("energy_performance_preference")))
+            .filter(|(_, path)| path.exists())
+            .collect();
+        let mut batch = PrivilegedBatch::new();
+        for (_, epp_path) in &paths {
+            batch.write(epp_path, epp);
+        }
+        let mut write_error = None;
+        for ((cpu_id, _), result) in paths.iter().zip(batch.apply()) {
+            if let Err(e) = result {
+                warn!("Failed to set energy performance preference for CPU {}: {}", cpu_id, e);
+                write_error.get_or_insert(e);
             }
+        }
+        self.applied_changes.insert(AppliedChange::CpuEnergyPreference);
+        
+        // intel_pstate refuses anything but "performance" under the performance governor.
+        // "default" reads back as whatever the firmware default is.
+        let current = Self::get_epp()?;
+        if current == epp || (epp == "default" && write_error.is_none()) {
+            Ok(format!("✅ Energy performance preference set to: {}", epp))
         } else {
-            Err("Failed to read current governor".into())
+            Err(write_error.unwrap_or_else(|| SysError::Other(format!(
+                "Energy performance preference is still {} after setting {} (governor {})", current, epp, self.current_governor
+            ))))
+        }
+    }
+    
+    /// The energy performance preference of cpu0
+    pub fn get_epp() -> SysResult<String> {
+        let path = Path::new("/sys/devices/system/cpu/cpu0/cpufreq/energy_performance_preference");
+        if !path.exists() {
+            return Err(Self::epp_not_supported());
+        }
+        fs::read_to_string(path)
+            .map(|epp| epp.trim().to_string())
+            .map_err(|e| SysError::io(path, e))
+    }
+    
+    fn available_epp() -> SysResult<Vec<String>> {
+        let path = Path::new("/sys/devices/system/cpu/cpu0/cpufreq/energy_performance_available_preferences");
+        if !path.exists() {
+            return Err(Self::epp_not_supported());
+        }
+        let content = fs::read_to_string(path).map_err(|e| SysError::io(path, e))?;
+        Ok(content.split_whitespace().map(|p| p.to_string()).collect())
+    }
+    
+    fn epp_not_supported() -> SysError {
+        let driver = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_driver")
+            .map(|driver| driver.trim().to_string())
+            .unwrap_or_else(|_| "no".to_string());
+        SysError::NotSupported(format!(
+            "energy performance preference with the {} cpufreq driver; it needs intel_pstate or amd-pstate in active mode", driver
+        ))
+    }
+    
+    /// Set EPP as part of a profile. Drivers without EPP are skipped quietly;
+    /// a failed write is logged but doesn't fail the profile.
+    fn apply_profile_epp(&mut self, epp: &str) {
+        match self.set_epp(epp) {
+            Ok(_) => {}
+            Err(SysError::NotSupported(reason)) => debug!("Skipping {}", reason),
+            Err(e) => warn!("Failed to set energy performance preference {}: {}", epp, e),
+        }
+    }
+    
+    /// Acknowledge the risks of undervolting. Must be called before
+    /// `set_cpu_undervolt` will touch the voltage planes.
+    pub fn confirm_undervolt_risk(&mut self) {
+        warn!("⚠️ CPU undervolting enabled by user confirmation");
+        self.undervolt_confirmed = true;
+    }
+    
+    /// Apply a voltage offset to the core and cache planes through MSR 0x150.
+    ///
+    /// Offsets are in millivolts and must be between -150 and 0. If the system
+    /// becomes unstable the offset stays applied until the next reboot, which
+    /// resets the voltage planes to stock.
+    pub async fn set_cpu_undervolt(&mut self, core_offset_mv: i32, cache_offset_mv: i32) -> SysResult<String> {
+        if !self.undervolt_confirmed {
+            return Err(SysError::NotSupported("undervolting without explicit confirmation (call confirm_undervolt_risk first)".to_string()));
+        }
+        
+        for (plane, offset) in [("core", core_offset_mv), ("cache", cache_offset_mv)] {
+            if offset < MAX_UNDERVOLT_MV || offset > 0 {
+                return Err(SysError::InvalidParameter(format!("{} offset of {}mV: must be between {}mV and 0mV", plane, offset, MAX_UNDERVOLT_MV)));
+            }
+        }
+        
+        Self::ensure_msr_available()?;
+        
+        info!("⚡ Applying undervolt: core {}mV, cache {}mV", core_offset_mv, cache_offset_mv);
+        
+        Self::write_voltage_offset(VOLTAGE_PLANE_CORE, core_offset_mv)?;
+        Self::write_voltage_offset(VOLTAGE_PLANE_CACHE, cache_offset_mv)?;
+        
+        let applied = self.get_current_undervolt().await?;
+        Ok(format!("✅ Undervolt applied: core {}mV, cache {}mV (reboot clears it)", 
+            applied.core_offset_mv, applied.cache_offset_mv))
+    }
+    
+    pub async fn get_current_undervolt(&self) -> SysResult<UndervoltOffsets> {
+        Self::ensure_msr_available()?;
+        
+        Ok(UndervoltOffsets {
+            core_offset_mv: Self::read_voltage_offset(VOLTAGE_PLANE_CORE)?,
+            cache_offset_mv: Self::read_voltage_offset(VOLTAGE_PLANE_CACHE)?,
+        })
+    }
+    
+    fn ensure_msr_available() -> SysResult<()> {
+        if !Path::new("/sys/module/msr").exists() {
+            return Err(SysError::HardwareUnavailable("msr kernel module is not loaded (run 'modprobe msr')".to_string()));
+        }
+        if !Path::new("/dev/cpu/0/msr").exists() {
+            return Err(SysError::HardwareUnavailable("/dev/cpu/0/msr".to_string()));
+        }
+        Ok(())
+    }
+    
+    fn write_voltage_offset(plane: u64, offset_mv: i32) -> SysResult<()> {
+        // Offset is an 11-bit signed value in 1/1024 V units, stored in bits 21..31
+        let units = (offset_mv as f64 * 1.024).round() as i32;
+        let offset_bits = ((units << 21) as u32 & 0xFFE0_0000) as u64;
+        let value = 0x8000_0011_0000_0000u64 | (plane << 40) | offset_bits;
+        
+        let msr = OpenOptions::new().write(true).open(MSR_DEVICE).map_err(|e| SysError::io(Path::new(MSR_DEVICE), e))?;
+        msr.write_all_at(&value.to_le_bytes(), MSR_OC_MAILBOX)?;
+        Ok(())
+    }
+    
+    fn read_voltage_offset(plane: u64) -> SysResult<i32> {
+        // Ask the mailbox for the plane's current offset, then read the reply
+        let request = 0x8000_0010_0000_0000u64 | (plane << 40);
+        let msr = OpenOptions::new().read(true).write(true).open(MSR_DEVICE).map_err(|e| SysError::io(Path::new(MSR_DEVICE), e))?;
+        msr.write_all_at(&request.to_le_bytes(), MSR_OC_MAILBOX)?;
+        
+        let mut buf = [0u8; 8];
+        msr.read_exact_at(&mut buf, MSR_OC_MAILBOX)?;
+        let reply = u64::from_le_bytes(buf);
+        
+        let units = (reply as u32 as i32) >> 21;
+        Ok((units as f64 / 1.024).round() as i32)
+    }
+    
+    /// Set the governor on specific CPUs only. Returns the CPUs that accepted it.
+    pub async fn set_governor_for_cores(&mut self, cores: &[usize], governor: &str) -> SysResult<Vec<usize>> {
+        let available = Self::available_governors()?;
+        if !available.iter().any(|g| g == governor) {
+            return Err(SysError::InvalidParameter(format!("governor {}. Valid options: {}", governor, available.join(", "))));
+        }
+        
+        let paths: Vec<(usize, PathBuf)> = Self::cpu_governor_paths().into_iter()
+            .filter(|(cpu_id, _)| cores.contains(cpu_id))
+            .collect();
+        let mut batch = PrivilegedBatch::new();
+        for (_, governor_path) in &paths {
+            batch.write(governor_path, governor);
+        }
+        
+        let mut applied = Vec::new();
+        let mut write_error = None;
+        for ((cpu_id, _), result) in paths.iter().zip(batch.apply()) {
+            match result {
+                Ok(_) => applied.push(*cpu_id),
+                Err(e) => {
+                    warn!("Failed to set governor for CPU {}: {}", cpu_id, e);
+                    write_error.get_or_insert(e);
+                }
+            }
+        }
+        
+        if applied.is_empty() {
+            return Err(write_error.unwrap_or_else(|| SysError::InvalidParameter(format!("none of CPUs {:?} exist", cores))));
+        }
+        self.applied_changes.insert(AppliedChange::CpuGovernor);
+        
+        debug!("⚡ Governor {} applied to CPUs {:?}", governor, applied);
+        Ok(applied)
+    }
+    
+    /// Split logical CPUs into (P-cores, E-cores) using the detected topology.
+    /// On CPUs without E-cores the second list is empty.
+    pub fn detect_core_types() -> SysResult<(Vec<usize>, Vec<usize>)> {
+        let topology = CpuTopology::detect();
+        if topology.cpus.is_empty() {
+            return Err(SysError::HardwareUnavailable("no CPUs found in /sys/devices/system/cpu".to_string()));
+        }
+        
+        Ok((topology.p_cpus(), topology.e_cpus()))
+    }
+    
+    /// Apply one governor to P-cores and another to E-cores.
+    /// Returns governor -> CPUs it was applied to.
+    pub async fn set_hybrid_governors(&mut self, p_gov: &str, e_gov: &str) -> SysResult<HashMap<String, Vec<usize>>> {
+        let (p_cores, e_cores) = Self::detect_core_types()?;
+        if e_cores.is_empty() {
+            warn!("No E-cores detected; applying {} to all cores", p_gov);
+        }
+        
+        info!("⚡ Hybrid governors: P-cores {:?} -> {}, E-cores {:?} -> {}", p_cores, p_gov, e_cores, e_gov);
+        
+        let mut assignments: HashMap<String, Vec<usize>> = HashMap::new();
+        
+        let applied = self.set_governor_for_cores(&p_cores, p_gov).await?;
+        assignments.entry(p_gov.to_string()).or_default().extend(applied);
+        
+        if !e_cores.is_empty() {
+            let applied = self.set_governor_for_cores(&e_cores, e_gov).await?;
+            assignments.entry(e_gov.to_string()).or_default().extend(applied);
+        }
+        
+        self.current_governor = if p_gov == e_gov || e_cores.is_empty() {
+            p_gov.to_string()
+        } else {
+            format!("{}/{}", p_gov, e_gov)
+        };
+        
+        Ok(assignments)
+    }
+    
+    /// `performance` on AC; on battery `schedutil`, or `powersave` where the
+    /// cpufreq driver has no schedutil (intel_pstate)
+    pub async fn set_governor_for_power_source(&mut self) -> SysResult<String> {
+        let on_ac = Self::on_ac_power().unwrap_or(true);
+        let governor = if on_ac {
+            "performance"
+        } else if Self::available_governors()?.iter().any(|g| g == "schedutil") {
+            "schedutil"
+        } else {
+            "powersave"
+        };
+        
+        info!("🔌 On {} power, switching CPU governor to {}", if on_ac { "AC" } else { "battery" }, governor);
+        self.set_cpu_governor(governor, false).await
+    }
+    
+    /// Whether a mains adapter (AC*, ADP*) is online. None when there is no
+    /// adapter to ask, as on desktops, which are always on mains.
+    pub fn on_ac_power() -> Option<bool> {
+        let entries = fs::read_dir("/sys/class/power_supply").ok()?;
+        let mut on_ac = None;
+        
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_mains = name.starts_with("AC") || name.starts_with("ADP")
+                || fs::read_to_string(entry.path().join("type")).map(|t| t.trim() == "Mains").unwrap_or(false);
+            if !is_mains {
+                continue;
+            }
+            
+            if let Ok(online) = fs::read_to_string(entry.path().join("online")) {
+                if online.trim() == "1" {
+                    return Some(true);
+                }
+                on_ac = Some(false);
+            }
+        }
+        
+        on_ac
+    }
+    
+    fn available_governors() -> SysResult<Vec<String>> {
+        let path = Path::new("/sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors");
+        let content = fs::read_to_string(path).map_err(|e| SysError::io(path, e))?;
+        Ok(content.split_whitespace().map(|g| g.to_string()).collect())
+    }
+    
+    /// scaling_governor paths for every CPU that exposes cpufreq, sorted by CPU id
+    fn cpu_governor_paths() -> Vec<(usize, PathBuf)> {
+        let mut paths = Vec::new();
+        
+        if let Ok(entries) = fs::read_dir("/sys/devices/system/cpu") {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(Ok(cpu_id)) = name.strip_prefix("cpu").map(|n| n.parse::<usize>()) {
+                    let path = entry.path().join("cpufreq/scaling_governor");
+                    if path.exists() {
+                        paths.push((cpu_id, path));
+                    }
+                }
+            }
         }
+        
+        paths.sort_by_key(|(cpu_id, _)| *cpu_id);
+        paths
     }
     
-    pub async fn optimize_for_gaming(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn optimize_for_gaming(&mut self) -> SysResult<String> {
         info!("🎮 Optimizing system for gaming performance...");
+        auto_profile::stop();
         
         // Set performance governor
-        self.set_cpu_governor("performance").await?;
+        self.set_cpu_governor("performance", false).await?;
+        self.apply_profile_epp("performance");
         
         // Gaming-specific optimizations
         let gaming_script = r#"#!/bin/bash
@@ -564,13 +861,6 @@ echo "📦 Run ~/manage-llm-models.sh install-recommended to install models"
 
 echo "🎮 Applying gaming optimizations..."
 
-# Disable CPU idle states for lowest latency
-for cpu in /sys/devices/system/cpu/cpu*/cpuidle/state*/disable; do
-    if [ -f "$cpu" ]; then
-        echo 1 | sudo tee "$cpu" 2>/dev/null || true
-    fi
-done
-
 # Set I/O scheduler to performance for NVMe drives
 for nvme in /sys/block/nvme*/queue/scheduler; do
     if [ -f "$nvme" ]; then
@@ -578,15 +868,16 @@ for nvme in /sys/block/nvme*/queue/scheduler; do
     fi
 done
 
-# Optimize kernel parameters for gaming
-sysctl -w kernel.sched_migration_cost_ns=5000000
-sysctl -w kernel.sched_autogroup_enabled=0
-sysctl -w vm.dirty_ratio=15
-sysctl -w vm.dirty_background_ratio=5
-
 echo "✅ Gaming optimizations applied!"
 "#;
         
+        self.apply_sysctl(&HashMap::from([
+            ("kernel.sched_migration_cost_ns".to_string(), "5000000".to_string()),
+            ("kernel.sched_autogroup_enabled".to_string(), "0".to_string()),
+            ("vm.dirty_ratio".to_string(), "15".to_string()),
+            ("vm.dirty_background_ratio".to_string(), "5".to_string()),
+        ])).await?;
+        
         let script_path = "/tmp/gaming_optimization.sh";
         fs::write(script_path, gaming_script)?;
         
@@ -596,38 +887,42 @@ echo "✅ Gaming optimizations applied!"
             .output()
             .await?;
         
+        // Idle states off for lowest latency; they stay off until another profile
+        if let Err(e) = self.disable_all_idle_states() {
+            warn!("Failed to disable CPU idle states: {}", e);
+        }
+        
         self.gaming_mode = true;
         self.performance_profile = PerformanceProfile::Gaming;
         
         Ok("✅ System optimized for gaming performance!".to_string())
     }
     
-    pub async fn optimize_for_development(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn optimize_for_development(&mut self) -> SysResult<String> {
         info!("💻 Optimizing system for development workload...");
+        auto_profile::stop();
         
         // Balanced performance for development
-        self.set_cpu_governor("ondemand").await?;
+        self.set_cpu_governor("ondemand", false).await?;
         
         let dev_script = r#"#!/bin/bash
 # Development optimizations
 
 echo "💻 Applying development optimizations..."
 
-# Enable better file watching for development tools
-echo fs.inotify.max_user_watches=524288 | sudo tee -a /etc/sysctl.conf
-echo fs.file-max=2097152 | sudo tee -a /etc/sysctl.conf
-
-# Optimize for compilation workloads
-sysctl -w kernel.sched_child_runs_first=1
-
 # I/O optimizations for code compilation
 echo mq-deadline | sudo tee /sys/block/nvme*/queue/scheduler
 
-sysctl -p
-
 echo "✅ Development optimizations applied!"
 "#;
         
+        // Better file watching for development tools, tuned for compilation
+        self.apply_sysctl(&HashMap::from([
+            ("fs.inotify.max_user_watches".to_string(), "524288".to_string()),
+            ("fs.file-max".to_string(), "2097152".to_string()),
+            ("kernel.sched_child_runs_first".to_string(), "1".to_string()),
+        ])).await?;
+        
         let script_path = "/tmp/dev_optimization.sh";
         fs::write(script_path, dev_script)?;
         
@@ -637,13 +932,261 @@ echo "✅ Development optimizations applied!"
             .output()
             .await?;
         
+        // Undo the gaming/inference latency setting, it wastes power here
+        if let Err(e) = self.enable_all_idle_states() {
+            warn!("Failed to re-enable CPU idle states: {}", e);
+        }
+        
         self.gaming_mode = false;
         self.performance_profile = PerformanceProfile::Development;
         
         Ok("✅ System optimized for development workload!".to_string())
     }
     
-    async fn get_ollama_models(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    pub async fn run_benchmark(&self, suite: BenchmarkSuite) -> SysResult<BenchmarkResult> {
+        Ok(benchmark::run_suite(&suite).await?)
+    }
+    
+    /// Benchmark, switch to `profile` (gaming, ollama, development or balanced), and
+    /// benchmark again. The profile stays applied afterwards.
+    pub async fn benchmark_profile(&mut self, profile: &str, suite: BenchmarkSuite) -> SysResult<BenchmarkComparison> {
+        info!("⏱️ Benchmarking the {} profile", profile);
+        let before = self.run_benchmark(suite.clone()).await?;
+        
+        match profile {
+            "gaming" => self.optimize_for_gaming().await?,
+            "ollama" | "llm" => self.optimize_for_ollama().await?,
+            "development" => self.optimize_for_development().await?,
+            "balanced" => self.optimize_for_balanced().await?,
+            other => return Err(SysError::InvalidParameter(format!("profile '{}'", other))),
+        };
+        tokio::time::sleep(BENCHMARK_SETTLE_TIME).await;
+        
+        let after = self.run_benchmark(suite).await?;
+        let comparison = BenchmarkComparison::new(profile, before, after);
+        info!("⏱️ {}", comparison.summary());
+        Ok(comparison)
+    }
+    
+    /// Hand the governor, fan curve and NVIDIA power limit to a control loop
+    /// that follows the detected workload and temperatures. It holds off while
+    /// the safety watchdog is throttling, and runs until `stop_auto_profile` or
+    /// until another profile is chosen.
+    pub fn set_auto_profile(&mut self) -> SysResult<String> {
+        if !auto_profile::start()? {
+            return Ok("Auto profile is already active".to_string());
+        }
+        self.record_change(AppliedChange::CpuGovernor);
+        self.record_change(AppliedChange::ManualFans);
+        self.gaming_mode = false;
+        self.performance_profile = PerformanceProfile::Auto;
+        Ok("✅ Auto profile active: settings now follow the workload and temperatures".to_string())
+    }
+    
+    /// Stop the auto profile's loop; it puts back the default governor, GPU
+    /// power limits and automatic fans on the way out
+    pub fn stop_auto_profile(&mut self) -> SysResult<String> {
+        if !auto_profile::stop() {
+            return Ok("Auto profile is not active".to_string());
+        }
+        if matches!(self.performance_profile, PerformanceProfile::Auto) {
+            self.performance_profile = PerformanceProfile::Balanced;
+        }
+        Ok("Auto profile stopped".to_string())
+    }
+    
+    /// What the auto profile is doing right now; None when it isn't active
+    pub fn auto_profile_status(&self) -> Option<auto_profile::AutoProfileStatus> {
+        auto_profile::status()
+    }
+    
+    /// Back to the default governor with idle states enabled. Unlike `revert_all`
+    /// this leaves sysctl, GPU and fan settings alone.
+    pub async fn optimize_for_balanced(&mut self) -> SysResult<String> {
+        info!("⚖️ Switching to balanced profile...");
+        // A fixed profile replaces the adaptive one
+        auto_profile::stop();
+        
+        if let Some(governor) = Self::default_governor() {
+            self.set_cpu_governor(&governor, false).await?;
+        }
+        self.apply_profile_epp("balance_performance");
+        self.enable_all_idle_states()?;
+        
+        self.gaming_mode = false;
+        self.performance_profile = PerformanceProfile::Balanced;
+        
+        Ok("✅ Balanced profile active".to_string())
+    }
+    
+    /// Turn off every CPU idle state (C-states) for the lowest wake-up latency,
+    /// at the cost of much higher idle power
+    pub fn disable_all_idle_states(&mut self) -> SysResult<usize> {
+        let written = Self::write_all_idle_states(true)?;
+        self.applied_changes.insert(AppliedChange::CpuIdleStates);
+        info!("🔥 Disabled {} CPU idle states", written);
+        Ok(written)
+    }
+    
+    pub fn enable_all_idle_states(&mut self) -> SysResult<usize> {
+        let written = Self::write_all_idle_states(false)?;
+        self.applied_changes.remove(&AppliedChange::CpuIdleStates);
+        info!("❄️ Re-enabled {} CPU idle states", written);
+        Ok(written)
+    }
+    
+    /// (disabled, total) idle states across all CPUs, read from sysfs
+    pub fn idle_state_status() -> (usize, usize) {
+        let mut disabled = 0;
+        let mut total = 0;
+        
+        for cpu_id in CpuTopology::detect().cpu_ids() {
+            let cpuidle = PathBuf::from(format!("/sys/devices/system/cpu/cpu{}/cpuidle", cpu_id));
+            for state in fs::read_dir(&cpuidle).into_iter().flatten().flatten() {
+                if !state.file_name().to_string_lossy().starts_with("state") {
+                    continue;
+                }
+                if let Ok(value) = fs::read_to_string(state.path().join("disable")) {
+                    total += 1;
+                    if value.trim() == "1" {
+                        disabled += 1;
+                    }
+                }
+            }
+        }
+        
+        (disabled, total)
+    }
+    
+    /// Resize the hugepage pool for `size` to `count` pages and return what the
+    /// kernel actually allocated, which is less when memory is too fragmented.
+    pub fn set_hugepages(&mut self, size: HugePageSize, count: u64) -> SysResult<HugePageStatus> {
+        let dir = size.sysfs_dir();
+        if !dir.exists() {
+            return Err(SysError::NotSupported(format!("{} KB hugepages on this kernel", size.size_kb())));
+        }
+        info!("📊 Setting {} KB hugepages to {}", size.size_kb(), count);
+        
+        // What the other page sizes already hold stays unavailable too
+        let other_bytes: u64 = HugePageSize::ALL.iter()
+            .filter(|other| **other != size)
+            .filter_map(|other| Self::get_hugepage_status(*other))
+            .map(|status| status.total_bytes())
+            .sum();
+        let requested_bytes = count * size.size_kb() * 1024;
+        if let Some(total_bytes) = Self::mem_total_bytes() {
+            let normal_bytes = total_bytes.saturating_sub(requested_bytes + other_bytes);
+            if normal_bytes * 100 < total_bytes * MIN_NORMAL_MEMORY_PERCENT {
+                warn!(
+                    "⚠️ {} hugepages would leave only {:.1} GB of {:.1} GB RAM as normal memory",
+                    count, normal_bytes as f64 / 1e9, total_bytes as f64 / 1e9
+                );
+            }
+        }
+        
+        let mut batch = PrivilegedBatch::new();
+        batch.write(dir.join("nr_hugepages"), count.to_string());
+        batch.apply_all()?;
+        
+        let status = Self::get_hugepage_status(size)
+            .ok_or_else(|| SysError::Other("Failed to read back hugepage status".to_string()))?;
+        if status.total < count {
+            warn!("⚠️ Only {} of {} hugepages could be allocated; memory is fragmented", status.total, count);
+        }
+        self.refresh_hugepage_config();
+        Ok(status)
+    }
+    
+    /// None when the kernel doesn't support `size`
+    pub fn get_hugepage_status(size: HugePageSize) -> Option<HugePageStatus> {
+        let dir = size.sysfs_dir();
+        let read = |file: &str| -> Option<u64> {
+            fs::read_to_string(dir.join(file)).ok()?.trim().parse().ok()
+        };
+        
+        Some(HugePageStatus {
+            size,
+            total: read("nr_hugepages")?,
+            free: read("free_hugepages").unwrap_or(0),
+            reserved: read("resv_hugepages").unwrap_or(0),
+        })
+    }
+    
+    /// Hugepage state from sysfs, for every size, into `huge_pages_enabled` and
+    /// `ollama_config.huge_pages_gb`
+    fn refresh_hugepage_config(&mut self) {
+        let total_bytes: u64 = HugePageSize::ALL.iter()
+            .filter_map(|size| Self::get_hugepage_status(*size))
+            .map(|status| status.total_bytes())
+            .sum();
+        self.huge_pages_enabled = total_bytes > 0;
+        self.ollama_config.huge_pages_gb = (total_bytes / (1024 * 1024 * 1024)) as u32;
+    }
+    
+    fn mem_total_bytes() -> Option<u64> {
+        fs::read_to_string("/proc/meminfo").ok()?
+            .lines()
+            .find(|line| line.starts_with("MemTotal:"))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+    }
+    
+    /// Merge `settings` into the managed drop-in and reload sysctl.
+    ///
+    /// The drop-in is rewritten in full every time, so applying the same
+    /// optimization twice never duplicates lines. Keys that fail to apply on the
+    /// running kernel are ignored (written with a leading `-`).
+    pub async fn apply_sysctl(&mut self, settings: &HashMap<String, String>) -> SysResult<String> {
+        let mut managed = Self::read_managed_sysctl();
+        for (key, value) in settings {
+            managed.insert(key.clone(), value.clone());
+        }
+        
+        let mut content = String::from("# Managed by AI SysAdmin Supreme - do not edit, changes are overwritten\n");
+        for (key, value) in &managed {
+            content.push_str(&format!("-{} = {}\n", key, value));
+        }
+        
+        // Drop-in and reload in one batch, so at most one authentication prompt
+        let mut batch = PrivilegedBatch::new();
+        batch.write(SYSCTL_DROP_IN, content).run("sysctl", &["--system"]);
+        let mut results = batch.apply().into_iter();
+        results.next().unwrap_or(Ok(()))?;
+        self.applied_changes.insert(AppliedChange::SysctlDropIn);
+        results.next().unwrap_or(Ok(()))?;
+        
+        info!("🔧 Applied {} sysctl settings via {}", settings.len(), SYSCTL_DROP_IN);
+        Ok(format!("✅ Applied {} sysctl settings", settings.len()))
+    }
+    
+    /// Remove the managed drop-in and reload. Values already applied to the
+    /// running kernel stay until they are overridden or the system reboots.
+    pub async fn reset_sysctl(&mut self) -> SysResult<String> {
+        let mut batch = PrivilegedBatch::new();
+        batch.remove(SYSCTL_DROP_IN).run("sysctl", &["--system"]);
+        let mut results = batch.apply().into_iter();
+        results.next().unwrap_or(Ok(()))?;
+        self.applied_changes.remove(&AppliedChange::SysctlDropIn);
+        results.next().unwrap_or(Ok(()))?;
+        
+        info!("🔧 Removed managed sysctl drop-in");
+        Ok("✅ Managed sysctl settings removed".to_string())
+    }
+    
+    fn read_managed_sysctl() -> BTreeMap<String, String> {
+        fs::read_to_string(SYSCTL_DROP_IN)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                Some((key.trim().trim_start_matches('-').to_string(), value.trim().to_string()))
+            })
+            .collect()
+    }
+    
+    async fn get_ollama_models(&self) -> SysResult<Vec<String>> {
         use crate::system::ollama::OllamaManager;
         
         // Use the new dynamic Ollama manager
@@ -658,11 +1201,21 @@ echo "✅ Development optimizations applied!"
         Ok(models)
     }
     
-    pub async fn get_system_status(&self) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    /// Audit SSH, firewall, AppArmor and $PATH; hardened means no high-severity findings
+    pub fn run_security_audit(&mut self, auditor: &mut security::SecurityAuditor) -> security::SecurityReport {
+        let report = auditor.audit();
+        self.security_hardening = report.high_severity().is_empty();
+        report
+    }
+    
+    pub async fn get_system_status(&self) -> SysResult<HashMap<String, String>> {
         let mut status = HashMap::new();
         
         // CPU governor
         status.insert("cpu_governor".to_string(), self.current_governor.clone());
+        if let Ok(epp) = Self::get_epp() {
+            status.insert("cpu_energy_preference".to_string(), epp);
+        }
         
         // Kernel version
         status.insert("kernel_version".to_string(), self.kernel_optimizations.kernel_version.clone());
@@ -681,52 +1234,287 @@ echo "✅ Development optimizations applied!"
         
         // Huge pages
         status.insert("huge_pages_enabled".to_string(), self.huge_pages_enabled.to_string());
+        for size in HugePageSize::ALL {
+            if let Some(pages) = Self::get_hugepage_status(size) {
+                status.insert(
+                    format!("huge_pages_{}kb", size.size_kb()),
+                    format!("{} total, {} free, {} reserved", pages.total, pages.free, pages.reserved),
+                );
+            }
+        }
         
         // Virtualization
         status.insert("virtualization_enabled".to_string(), self.virtualization_enabled.to_string());
         
+        // CPU idle states
+        let (disabled, total) = Self::idle_state_status();
+        status.insert("idle_states_disabled".to_string(), disabled.to_string());
+        status.insert("idle_states_total".to_string(), total.to_string());
+        
         Ok(status)
     }
     
-    pub async fn emergency_cooling(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn emergency_cooling(&mut self) -> SysResult<String> {
         warn!("🚨 Emergency cooling activated!");
+        // It would put its own settings back on the next change
+        auto_profile::stop();
         
         // Set powersave governor to reduce heat
-        self.set_cpu_governor("powersave").await?;
+        self.set_cpu_governor("powersave", false).await?;
         
-        // Enable all CPU idle states
-        let cooling_script = r#"#!/bin/bash
-# Emergency cooling for i9-13900HX
-
-echo "🚨 Activating emergency cooling..."
-
-# Enable deep CPU idle states
-for cpu in /sys/devices/system/cpu/cpu*/cpuidle/state*/disable; do
-    if [ -f "$cpu" ]; then
-        echo 0 | sudo tee "$cpu" 2>/dev/null || true
-    fi
-done
-
-# Reduce CPU max frequency temporarily
-for cpu in /sys/devices/system/cpu/cpu*/cpufreq/scaling_max_freq; do
-    if [ -f "$cpu" ]; then
-        echo 3000000 | sudo tee "$cpu" # Limit to 3GHz
-    fi
-done
-
-echo "✅ Emergency cooling measures applied!"
-"#;
+        // Deep idle states back on
+        if let Err(e) = self.enable_all_idle_states() {
+            warn!("Failed to re-enable CPU idle states: {}", e);
+        }
         
-        let script_path = "/tmp/emergency_cooling.sh";
-        fs::write(script_path, cooling_script)?;
+        // Reduce CPU max frequency until the limits are reset
+        let limits = self.set_frequency_limits(None, Some(EMERGENCY_MAX_FREQ_KHZ))?;
+        let max_khz = limits.iter().map(|limit| limit.max_khz).max().unwrap_or(EMERGENCY_MAX_FREQ_KHZ);
         
-        AsyncCommand::new("sudo")
-            .arg("bash")
-            .arg(script_path)
-            .output()
-            .await?;
+        Ok(format!("🚨 Emergency cooling activated - CPU frequency limited to {:.1}GHz", max_khz as f64 / 1_000_000.0))
+    }
+    
+    /// Write scaling_min_freq and/or scaling_max_freq on every CPU, clamped to
+    /// each core's cpuinfo_min_freq..cpuinfo_max_freq. A limit left as None
+    /// keeps its current value unless the other one has to push it out of the way.
+    pub fn set_frequency_limits(&mut self, min_khz: Option<u32>, max_khz: Option<u32>) -> SysResult<Vec<CpuFrequencyLimit>> {
+        if let (Some(min), Some(max)) = (min_khz, max_khz) {
+            if min > max {
+                return Err(SysError::InvalidParameter(format!("frequency limits: minimum {} kHz is above maximum {} kHz", min, max)));
+            }
+        }
+        info!("⚡ Setting CPU frequency limits: min {:?} kHz, max {:?} kHz", min_khz, max_khz);
+        
+        let limits = Self::write_frequency_limits(min_khz, max_khz)?;
+        self.applied_changes.insert(AppliedChange::CpuFrequencyLimit);
+        
+        let clamped: Vec<usize> = limits.iter().filter(|limit| limit.clamped).map(|limit| limit.cpu).collect();
+        if !clamped.is_empty() {
+            warn!("⚠️ Frequency limits clamped to the hardware range on CPUs {:?}", clamped);
+        }
+        Ok(limits)
+    }
+    
+    /// Put scaling_min_freq and scaling_max_freq back to the hardware extremes on every CPU
+    pub fn reset_frequency_limits(&mut self) -> SysResult<Vec<CpuFrequencyLimit>> {
+        let limits = Self::write_frequency_limits(Some(0), Some(u32::MAX))?;
+        self.applied_changes.remove(&AppliedChange::CpuFrequencyLimit);
+        info!("⚡ CPU frequency limits reset to the hardware range");
+        Ok(limits)
+    }
+    
+    /// For changes made outside the controller (GPU clocks, manual fan control)
+    /// that `revert_all` should still undo
+    pub fn record_change(&mut self, change: AppliedChange) {
+        self.applied_changes.insert(change);
+    }
+    
+    /// Undo everything in `applied_changes`: default governor and EPP, idle states back
+    /// on, frequency limits lifted, GPU clocks reset, fans on automatic, and the
+    /// managed sysctl drop-in removed. Changes that fail to revert stay recorded.
+    pub async fn revert_all(&mut self) -> SysResult<String> {
+        auto_profile::stop();
+        let changes = std::mem::take(&mut self.applied_changes);
+        if changes.is_empty() {
+            return Ok("Nothing to revert".to_string());
+        }
+        warn!("🛑 Reverting {} applied changes", changes.len());
         
-        Ok("🚨 Emergency cooling activated - CPU frequency limited to 3GHz".to_string())
+        let mut failed = BTreeSet::new();
+        let mut errors = Vec::new();
+        let mut permission_denied = false;
+        for change in &changes {
+            let result = match change {
+                AppliedChange::CpuGovernor => match Self::default_governor() {
+                    // Restoring, so the battery guard doesn't apply
+                    Some(governor) => self.set_cpu_governor(&governor, true).await.map(|_| ()),
+                    None => Err(SysError::HardwareUnavailable("cpufreq governors".to_string())),
+                },
+                // "default" puts back what the firmware chose, where the kernel offers it
+                AppliedChange::CpuEnergyPreference => match Self::available_epp() {
+                    Ok(available) => {
+                        let epp = if available.iter().any(|p| p == "default") { "default" } else { "balance_performance" };
+                        self.set_epp(epp).map(|_| ())
+                    }
+                    Err(e) => Err(e),
+                },
+                AppliedChange::CpuIdleStates => self.enable_all_idle_states().map(|_| ()),
+                AppliedChange::CpuFrequencyLimit => self.reset_frequency_limits().map(|_| ()),
+                AppliedChange::GpuClocks => Self::reset_gpu_clocks(),
+                AppliedChange::ManualFans => Self::restore_automatic_fans(),
+                AppliedChange::SysctlDropIn => self.reset_sysctl().await.map(|_| ()),
+            };
+            
+            if let Err(e) = result {
+                warn!("Failed to revert {:?}: {}", change, e);
+                permission_denied |= matches!(e, SysError::PermissionDenied(_));
+                errors.push(format!("{:?}: {}", change, e));
+                failed.insert(*change);
+            }
+        }
+        self.applied_changes = failed;
+        
+        self.gaming_mode = false;
+        self.performance_profile = PerformanceProfile::Balanced;
+        
+        if errors.is_empty() {
+            info!("✅ Reverted {} changes", changes.len());
+            Ok(format!("✅ Reverted {} changes", changes.len()))
+        } else {
+            let summary = format!("Reverted {} of {} changes; failed: {}", changes.len() - errors.len(), changes.len(), errors.join("; "));
+            // Elevating and retrying fixes the lot, so let the caller know to ask
+            if permission_denied {
+                Err(SysError::PermissionDenied(summary))
+            } else {
+                Err(SysError::Other(summary))
+            }
+        }
+    }
+    
+    /// The distribution default for the active cpufreq driver: powersave for the
+    /// pstate drivers (it still scales), otherwise schedutil or ondemand
+    fn default_governor() -> Option<String> {
+        let available = Self::available_governors().ok()?;
+        let driver = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_driver").unwrap_or_default();
+        let preferred: &[&str] = if matches!(driver.trim(), "intel_pstate" | "amd-pstate-epp") {
+            &["powersave"]
+        } else {
+            &["schedutil", "ondemand", "powersave"]
+        };
+        
+        preferred.iter()
+            .find(|governor| available.iter().any(|g| g == *governor))
+            .map(|governor| governor.to_string())
+            .or_else(|| available.first().cloned())
+    }
+    
+    /// Write `disable` to every cpuidle state of every CPU. Returns how many were written.
+    fn write_all_idle_states(disable: bool) -> SysResult<usize> {
+        let value = if disable { "1" } else { "0" };
+        let mut batch = PrivilegedBatch::new();
+        for cpu_id in CpuTopology::detect().cpu_ids() {
+            let cpuidle = PathBuf::from(format!("/sys/devices/system/cpu/cpu{}/cpuidle", cpu_id));
+            for state in fs::read_dir(&cpuidle).into_iter().flatten().flatten() {
+                if state.file_name().to_string_lossy().starts_with("state") {
+                    batch.write(state.path().join("disable"), value);
+                }
+            }
+        }
+        
+        let mut written = 0;
+        let mut failures = 0;
+        let mut first_error = None;
+        for result in batch.apply() {
+            match result {
+                Ok(_) => written += 1,
+                Err(e) => {
+                    failures += 1;
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        
+        if written == 0 && failures > 0 {
+            warn!("Failed to write any of {} CPU idle states", failures);
+            return Err(first_error.unwrap_or_else(|| SysError::Other("Failed to write CPU idle states".to_string())));
+        }
+        Ok(written)
+    }
+    
+    /// Clamp and write the limits for every CPU with cpufreq. Returns the CPUs
+    /// where both writes went through.
+    fn write_frequency_limits(min_khz: Option<u32>, max_khz: Option<u32>) -> SysResult<Vec<CpuFrequencyLimit>> {
+        let read_khz = |path: PathBuf| fs::read_to_string(path).ok().and_then(|khz| khz.trim().parse::<u32>().ok());
+        
+        let mut limits = Vec::new();
+        let mut batch = PrivilegedBatch::new();
+        for (cpu_id, governor_path) in Self::cpu_governor_paths() {
+            let cpufreq = governor_path.parent().map(Path::to_path_buf).unwrap_or_default();
+            let (hw_min, hw_max, current_min, current_max) = match (
+                read_khz(cpufreq.join("cpuinfo_min_freq")),
+                read_khz(cpufreq.join("cpuinfo_max_freq")),
+                read_khz(cpufreq.join("scaling_min_freq")),
+                read_khz(cpufreq.join("scaling_max_freq")),
+            ) {
+                (Some(hw_min), Some(hw_max), Some(current_min), Some(current_max)) => (hw_min, hw_max, current_min, current_max),
+                _ => continue,
+            };
+            
+            let max = max_khz.unwrap_or(current_max).clamp(hw_min, hw_max);
+            let min = min_khz.unwrap_or(current_min).clamp(hw_min, max);
+            let clamped = max_khz.map_or(false, |requested| requested != max) || min_khz.map_or(false, |requested| requested != min);
+            
+            // The kernel rejects a max below the current min and a min above the current max
+            let (min_path, max_path) = (cpufreq.join("scaling_min_freq"), cpufreq.join("scaling_max_freq"));
+            if max >= current_min {
+                batch.write(max_path, max.to_string());
+                batch.write(min_path, min.to_string());
+            } else {
+                batch.write(min_path, min.to_string());
+                batch.write(max_path, max.to_string());
+            }
+            limits.push(CpuFrequencyLimit { cpu: cpu_id, min_khz: min, max_khz: max, clamped });
+        }
+        
+        // Two writes per CPU, in the order they were queued
+        let mut results = batch.apply().into_iter();
+        let mut first_error = None;
+        let mut applied = Vec::new();
+        for limit in limits {
+            match [results.next(), results.next()].into_iter().flatten().find_map(Result::err) {
+                Some(e) => {
+                    warn!("Failed to set frequency limits for CPU {}: {}", limit.cpu, e);
+                    first_error.get_or_insert(e);
+                }
+                None => applied.push(limit),
+            }
+        }
+        
+        if applied.is_empty() {
+            return Err(first_error.unwrap_or_else(|| SysError::HardwareUnavailable("cpufreq frequency limits".to_string())));
+        }
+        Ok(applied)
+    }
+    
+    fn reset_gpu_clocks() -> SysResult<()> {
+        let mut batch = PrivilegedBatch::new();
+        batch.run("nvidia-smi", &["-rgc", "-rac"]);
+        batch.apply_all()
+    }
+    
+    /// pwm*_enable = 2 hands every fan back to the firmware
+    fn restore_automatic_fans() -> SysResult<()> {
+        let hwmon_dir = Path::new("/sys/class/hwmon");
+        let mut paths = Vec::new();
+        for hwmon in fs::read_dir(hwmon_dir).map_err(|e| SysError::io(hwmon_dir, e))?.flatten() {
+            for entry in fs::read_dir(hwmon.path()).into_iter().flatten().flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with("pwm") && name.ends_with("_enable") {
+                    paths.push(entry.path());
+                }
+            }
+        }
+        let mut batch = PrivilegedBatch::new();
+        for path in &paths {
+            batch.write(path, "2");
+        }
+        
+        let mut restored = 0;
+        let mut first_error = None;
+        for (path, result) in paths.iter().zip(batch.apply()) {
+            match result {
+                Ok(_) => restored += 1,
+                Err(e) => {
+                    warn!("Failed to restore automatic control for {}: {}", path.display(), e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        
+        if restored == 0 {
+            return Err(first_error.unwrap_or_else(|| SysError::HardwareUnavailable("PWM fan controls".to_string())));
+        }
+        Ok(())
     }
 }
 
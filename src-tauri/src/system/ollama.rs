@@ -4,9 +4,19 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use tracing::{info, debug, warn, error};
 use tokio::process::Command as AsyncCommand;
+use tokio::sync::mpsc;
+use crate::config;
+
+/// Fixed probe so successive measurements are comparable
+const PROBE_PROMPT: &str = "Write one sentence about the weather.";
+const PROBE_TOKENS: u32 = 64;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(120);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OllamaManager {
@@ -44,11 +54,61 @@ pub struct OllamaConfig {
     pub huge_pages_enabled: bool,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum OllamaError {
+    #[error("Ollama daemon is not reachable at {0} - is the service running?")]
+    DaemonUnavailable(String),
+    #[error("Ollama API request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Ollama API error: {0}")]
+    Api(String),
+    #[error("Invalid response from Ollama: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+}
+
+/// One NDJSON status line from `POST /api/pull`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullProgress {
+    pub status: String,
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+}
+
+/// Inference speed measured from one streamed generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InferenceStats {
+    pub model: String,
+    /// Generation speed from Ollama's eval_count / eval_duration
+    pub tokens_per_sec: f64,
+    /// Prompt processing speed, when the prompt wasn't already cached
+    pub prompt_tokens_per_sec: Option<f64>,
+    /// From sending the request to the first generated token, including any model load
+    pub time_to_first_token_ms: f64,
+    /// Time spent loading the model into memory; near zero when it was already resident
+    pub load_duration_ms: f64,
+    pub eval_count: u64,
+    pub measured_at: DateTime<Utc>,
+}
+
+impl PullProgress {
+    pub fn percent(&self) -> Option<f64> {
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) if total > 0 => Some(completed as f64 / total as f64 * 100.0),
+            _ => None,
+        }
+    }
+}
+
 impl Default for OllamaConfig {
     fn default() -> Self {
+        let settings = config::get().ollama;
         Self {
-            host: "0.0.0.0".to_string(),
-            port: 11434,
+            host: settings.host,
+            port: settings.port,
             num_parallel: 4,
             max_loaded_models: 3,
             flash_attention: true,
@@ -480,6 +540,139 @@ echo "✅ Ollama configured for {} discovered models"
         }
     }
     
+    /// Base URL of the Ollama HTTP API. A wildcard bind address is reached via loopback.
+    pub fn api_base_url(&self) -> String {
+        let host = match self.configuration.host.as_str() {
+            "0.0.0.0" | "" => "127.0.0.1",
+            host => host,
+        };
+        format!("http://{}:{}", host, self.configuration.port)
+    }
+    
+    fn map_request_error(&self, e: reqwest::Error) -> OllamaError {
+        if e.is_connect() || e.is_timeout() {
+            OllamaError::DaemonUnavailable(self.api_base_url())
+        } else {
+            OllamaError::Http(e)
+        }
+    }
+    
+    /// Pull a model through the Ollama API, forwarding each progress line to
+    /// `progress` so the UI can render a download bar.
+    pub async fn pull_model(&mut self, name: &str, progress: mpsc::Sender<PullProgress>) -> Result<(), OllamaError> {
+        info!("📥 Pulling Ollama model: {}", name);
+        
+        let mut response = reqwest::Client::new()
+            .post(format!("{}/api/pull", self.api_base_url()))
+            .json(&serde_json::json!({ "name": name, "stream": true }))
+            .send()
+            .await
+            .map_err(|e| self.map_request_error(e))?;
+        
+        if !response.status().is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(OllamaError::Api(body));
+        }
+        
+        // The body is NDJSON; chunks don't necessarily end on line boundaries
+        let mut buffer = String::new();
+        while let Some(chunk) = response.chunk().await? {
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+            while let Some(newline) = buffer.find('\n') {
+                let line: String = buffer.drain(..=newline).collect();
+                self.handle_pull_line(line.trim(), &progress).await?;
+            }
+        }
+        self.handle_pull_line(buffer.trim(), &progress).await?;
+        
+        self.service_running = true;
+        self.refresh_models().await;
+        
+        info!("✅ Pulled Ollama model: {}", name);
+        Ok(())
+    }
+    
+    /// Periodically measure inference speed on `model` until the stream is dropped.
+    /// Ollama has no metrics endpoint, so each item comes from a short streamed
+    /// `/api/generate` probe; failed probes are logged and skipped.
+    pub fn monitor_inference(&self, model: &str, interval: Duration) -> impl Stream<Item = InferenceStats> + Send + 'static {
+        let base_url = self.api_base_url();
+        let model = model.to_string();
+        
+        stream::unfold((base_url, model, true), move |(base_url, model, first)| async move {
+            if !first {
+                tokio::time::sleep(interval).await;
+            }
+            let stats = match probe_inference(&base_url, &model).await {
+                Ok(stats) => {
+                    debug!("🧠 {} generating at {:.1} tokens/s", model, stats.tokens_per_sec);
+                    Some(stats)
+                }
+                Err(e) => {
+                    warn!("Ollama inference probe failed: {}", e);
+                    None
+                }
+            };
+            Some((stats, (base_url, model, false)))
+        })
+        .filter_map(|stats| async move { stats })
+    }
+    
+    /// One inference measurement on `model`
+    pub async fn measure_inference(&self, model: &str) -> Result<InferenceStats, OllamaError> {
+        probe_inference(&self.api_base_url(), model).await
+    }
+    
+    async fn handle_pull_line(&self, line: &str, progress: &mpsc::Sender<PullProgress>) -> Result<(), OllamaError> {
+        if line.is_empty() {
+            return Ok(());
+        }
+        
+        let value: serde_json::Value = serde_json::from_str(line)?;
+        if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+            return Err(OllamaError::Api(error.to_string()));
+        }
+        
+        let update: PullProgress = serde_json::from_value(value)?;
+        // A closed receiver just means nobody is watching any more
+        let _ = progress.send(update).await;
+        Ok(())
+    }
+    
+    pub async fn delete_model(&mut self, name: &str) -> Result<(), OllamaError> {
+        info!("🗑️ Deleting Ollama model: {}", name);
+        
+        let response = reqwest::Client::new()
+            .delete(format!("{}/api/delete", self.api_base_url()))
+            .json(&serde_json::json!({ "name": name }))
+            .send()
+            .await
+            .map_err(|e| self.map_request_error(e))?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(OllamaError::Api(format!("{} ({})", body.trim(), status)));
+        }
+        
+        self.service_running = true;
+        self.refresh_models().await;
+        
+        Ok(())
+    }
+    
+    async fn refresh_models(&mut self) {
+        self.models_discovered.clear();
+        if let Err(e) = self.scan_existing_models().await {
+            warn!("Failed to refresh Ollama models: {}", e);
+        }
+        if self.models_path.is_none() && self.ollama_installed {
+            if let Err(e) = self.scan_via_ollama_command().await {
+                warn!("Failed to list Ollama models: {}", e);
+            }
+        }
+    }
+    
     pub fn get_discovered_models(&self) -> &Vec<ModelInfo> {
         &self.models_discovered
     }
@@ -507,3 +700,79 @@ echo "✅ Ollama configured for {} discovered models"
         status
     }
 }
+
+/// Stream a short generation and time it. Token counts and durations come from the
+/// final `done` object; time-to-first-token is measured here since Ollama doesn't report it.
+async fn probe_inference(base_url: &str, model: &str) -> Result<InferenceStats, OllamaError> {
+    let started = Instant::now();
+    let mut response = reqwest::Client::new()
+        .post(format!("{}/api/generate", base_url))
+        .timeout(PROBE_TIMEOUT)
+        .json(&serde_json::json!({
+            "model": model,
+            "prompt": PROBE_PROMPT,
+            "stream": true,
+            "options": { "num_predict": PROBE_TOKENS, "temperature": 0, "seed": 42 },
+        }))
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_connect() || e.is_timeout() {
+                OllamaError::DaemonUnavailable(base_url.to_string())
+            } else {
+                OllamaError::Http(e)
+            }
+        })?;
+    
+    if !response.status().is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(OllamaError::Api(body));
+    }
+    
+    let mut first_token: Option<Duration> = None;
+    let mut summary: Option<serde_json::Value> = None;
+    let mut buffer = String::new();
+    while let Some(chunk) = response.chunk().await? {
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(newline) = buffer.find('\n') {
+            let line: String = buffer.drain(..=newline).collect();
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            
+            let value: serde_json::Value = serde_json::from_str(line)?;
+            if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+                return Err(OllamaError::Api(error.to_string()));
+            }
+            if first_token.is_none() && value["response"].as_str().map(|r| !r.is_empty()).unwrap_or(false) {
+                first_token = Some(started.elapsed());
+            }
+            if value["done"].as_bool().unwrap_or(false) {
+                summary = Some(value);
+            }
+        }
+    }
+    
+    let summary = summary.ok_or_else(|| OllamaError::Api("Generation ended without a final summary".to_string()))?;
+    let eval_count = summary["eval_count"].as_u64().unwrap_or(0);
+    let eval_duration_ns = summary["eval_duration"].as_f64().unwrap_or(0.0);
+    if eval_count == 0 || eval_duration_ns <= 0.0 {
+        return Err(OllamaError::Api("Response carried no eval timings".to_string()));
+    }
+    
+    let prompt_tokens_per_sec = match (summary["prompt_eval_count"].as_f64(), summary["prompt_eval_duration"].as_f64()) {
+        (Some(count), Some(duration_ns)) if count > 0.0 && duration_ns > 0.0 => Some(count / (duration_ns / 1e9)),
+        _ => None,
+    };
+    
+    Ok(InferenceStats {
+        model: model.to_string(),
+        tokens_per_sec: eval_count as f64 / (eval_duration_ns / 1e9),
+        prompt_tokens_per_sec,
+        time_to_first_token_ms: first_token.unwrap_or_else(|| started.elapsed()).as_secs_f64() * 1000.0,
+        load_duration_ms: summary["load_duration"].as_f64().unwrap_or(0.0) / 1e6,
+        eval_count,
+        measured_at: Utc::now(),
+    })
+}
@@ -0,0 +1,455 @@
+// Security Audit - SSH, firewall, AppArmor and $PATH permission checks
+// Each finding carries remediation text; the ones we can fix safely are applied by id
+
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug, warn};
+use crate::config;
+use crate::monitoring_system::read_connections;
+
+const SSHD_CONFIG: &str = "/etc/ssh/sshd_config";
+const SSHD_CONFIG_DIR: &str = "/etc/ssh/sshd_config.d";
+/// sshd keeps the first value it reads, so the drop-in sorts first
+const SSHD_HARDENING_DROP_IN: &str = "/etc/ssh/sshd_config.d/00-ai-sysadmin-hardening.conf";
+
+pub const FINDING_SSH_ROOT_LOGIN: &str = "ssh_root_login";
+pub const FINDING_SSH_PASSWORD_AUTH: &str = "ssh_password_auth";
+pub const FINDING_NO_FIREWALL: &str = "no_firewall";
+pub const FINDING_APPARMOR_DISABLED: &str = "apparmor_disabled";
+pub const FINDING_APPARMOR_COMPLAIN: &str = "apparmor_complain_profiles";
+pub const FINDING_WORLD_WRITABLE_PATH: &str = "world_writable_path";
+pub const FINDING_UNEXPECTED_CONNECTIONS: &str = "unexpected_connections";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub id: String,
+    pub title: String,
+    pub severity: Severity,
+    pub description: String,
+    pub remediation: String,
+    /// apply_hardening can fix this one
+    pub auto_fixable: bool,
+    /// Files or directories the finding is about, used when fixing
+    pub affected_paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityReport {
+    pub generated_at: DateTime<Utc>,
+    pub findings: Vec<Finding>,
+}
+
+impl SecurityReport {
+    pub fn high_severity(&self) -> Vec<&Finding> {
+        self.findings.iter().filter(|f| f.severity >= Severity::High).collect()
+    }
+}
+
+pub struct SecurityAuditor {
+    pub last_report: Option<SecurityReport>,
+}
+
+impl SecurityAuditor {
+    pub fn new() -> Self {
+        Self { last_report: None }
+    }
+    
+    pub fn audit(&mut self) -> SecurityReport {
+        info!("🛡️ Running security audit...");
+        
+        let mut findings = Vec::new();
+        findings.extend(check_ssh());
+        findings.extend(check_firewall());
+        findings.extend(check_apparmor());
+        findings.extend(check_world_writable_path());
+        findings.extend(check_unexpected_connections());
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+        
+        info!("🛡️ Security audit found {} issues", findings.len());
+        
+        let report = SecurityReport {
+            generated_at: Utc::now(),
+            findings,
+        };
+        self.last_report = Some(report.clone());
+        report
+    }
+    
+    /// The last report if it is newer than `max_age_secs`, otherwise a fresh audit
+    pub fn cached_report(&mut self, max_age_secs: i64) -> SecurityReport {
+        match &self.last_report {
+            Some(report) if (Utc::now() - report.generated_at).num_seconds() < max_age_secs => report.clone(),
+            _ => self.audit(),
+        }
+    }
+    
+    /// Fix the selected findings from the last audit. Returns one message per fix.
+    pub fn apply_hardening(&self, finding_ids: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let report = self.last_report.as_ref().ok_or("Run a security audit before applying hardening")?;
+        
+        let mut applied = Vec::new();
+        let mut ssh_settings = Vec::new();
+        
+        for id in finding_ids {
+            let finding = report.findings.iter().find(|f| &f.id == id)
+                .ok_or(format!("Unknown finding: {}", id))?;
+            if !finding.auto_fixable {
+                return Err(format!("'{}' must be fixed manually: {}", finding.title, finding.remediation).into());
+            }
+            
+            match finding.id.as_str() {
+                FINDING_SSH_ROOT_LOGIN => ssh_settings.push(("PermitRootLogin", "no")),
+                FINDING_SSH_PASSWORD_AUTH => ssh_settings.push(("PasswordAuthentication", "no")),
+                FINDING_NO_FIREWALL => applied.push(enable_firewall()?),
+                FINDING_WORLD_WRITABLE_PATH => {
+                    for path in &finding.affected_paths {
+                        let mut permissions = fs::metadata(path)?.permissions();
+                        permissions.set_mode(permissions.mode() & !0o002);
+                        fs::set_permissions(path, permissions)
+                            .map_err(|e| format!("Failed to chmod o-w {}: {}", path.display(), e))?;
+                    }
+                    applied.push(format!("Removed world-write from {} paths", finding.affected_paths.len()));
+                },
+                _ => return Err(format!("No automatic fix for {}", finding.id).into()),
+            }
+        }
+        
+        if !ssh_settings.is_empty() {
+            applied.push(write_sshd_hardening(&ssh_settings)?);
+        }
+        
+        Ok(applied)
+    }
+}
+
+/// Effective sshd settings: `sshd -T` when we may run it, else the config files
+fn sshd_setting(key: &str) -> Option<String> {
+    let key = key.to_lowercase();
+    
+    if let Ok(output) = Command::new("sshd").arg("-T").output() {
+        if output.status.success() {
+            return String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .find_map(|line| {
+                    let (k, v) = line.split_once(' ')?;
+                    (k == key).then(|| v.trim().to_string())
+                });
+        }
+    }
+    
+    // Drop-ins are included at the top of the Arch default config, so they win
+    let mut files: Vec<PathBuf> = fs::read_dir(SSHD_CONFIG_DIR)
+        .map(|entries| entries.flatten().map(|e| e.path())
+            .filter(|p| p.extension().map(|ext| ext == "conf").unwrap_or(false))
+            .collect())
+        .unwrap_or_default();
+    files.sort();
+    files.push(PathBuf::from(SSHD_CONFIG));
+    
+    for file in files {
+        let content = fs::read_to_string(&file).unwrap_or_default();
+        for line in content.lines() {
+            let line = line.trim();
+            // Settings after a Match block only apply to matching connections
+            if line.to_lowercase().starts_with("match ") {
+                break;
+            }
+            let mut parts = line.split_whitespace();
+            if parts.next().map(|k| k.to_lowercase()) == Some(key.clone()) {
+                return parts.next().map(|v| v.to_lowercase());
+            }
+        }
+    }
+    
+    None
+}
+
+fn check_ssh() -> Vec<Finding> {
+    let mut findings = Vec::new();
+    if !Path::new(SSHD_CONFIG).exists() {
+        debug!("sshd not installed, skipping SSH checks");
+        return findings;
+    }
+    
+    if sshd_setting("PermitRootLogin").as_deref() == Some("yes") {
+        findings.push(Finding {
+            id: FINDING_SSH_ROOT_LOGIN.to_string(),
+            title: "SSH allows root login".to_string(),
+            severity: Severity::High,
+            description: "PermitRootLogin is 'yes', so root can log in over SSH with a password.".to_string(),
+            remediation: "Set 'PermitRootLogin no' and use sudo from a normal account.".to_string(),
+            auto_fixable: true,
+            affected_paths: vec![PathBuf::from(SSHD_CONFIG)],
+        });
+    }
+    
+    // OpenSSH defaults to password authentication when unset
+    if sshd_setting("PasswordAuthentication").map(|v| v == "yes").unwrap_or(true) {
+        findings.push(Finding {
+            id: FINDING_SSH_PASSWORD_AUTH.to_string(),
+            title: "SSH password authentication enabled".to_string(),
+            severity: Severity::Medium,
+            description: "Passwords can be brute-forced; keys cannot.".to_string(),
+            remediation: "Install your public key in ~/.ssh/authorized_keys, then set 'PasswordAuthentication no'.".to_string(),
+            auto_fixable: true,
+            affected_paths: vec![PathBuf::from(SSHD_CONFIG)],
+        });
+    }
+    
+    findings
+}
+
+fn write_sshd_hardening(settings: &[(&str, &str)]) -> Result<String, Box<dyn std::error::Error>> {
+    // Keep settings from earlier hardening runs
+    let mut merged: Vec<(String, String)> = fs::read_to_string(SSHD_HARDENING_DROP_IN)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once(' '))
+        .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+        .collect();
+    for (key, value) in settings {
+        merged.retain(|(existing, _)| !existing.eq_ignore_ascii_case(key));
+        merged.push((key.to_string(), value.to_string()));
+    }
+    
+    let mut content = String::from("# Managed by AI SysAdmin security hardening\n");
+    for (key, value) in &merged {
+        content.push_str(&format!("{} {}\n", key, value));
+    }
+    
+    fs::create_dir_all(SSHD_CONFIG_DIR)?;
+    fs::write(SSHD_HARDENING_DROP_IN, content)
+        .map_err(|e| format!("Failed to write {} (are you root?): {}", SSHD_HARDENING_DROP_IN, e))?;
+    
+    // Never reload a config sshd itself rejects
+    let check = Command::new("sshd").arg("-t").output()?;
+    if !check.status.success() {
+        fs::remove_file(SSHD_HARDENING_DROP_IN).ok();
+        return Err(format!("sshd rejected the hardened config: {}", String::from_utf8_lossy(&check.stderr).trim()).into());
+    }
+    
+    if !systemctl_is_active("sshd") {
+        return Ok(format!("SSH hardened ({}); takes effect when sshd starts", SSHD_HARDENING_DROP_IN));
+    }
+    Command::new("systemctl").args(&["reload", "sshd"]).output()?;
+    Ok(format!("SSH hardened and reloaded ({})", SSHD_HARDENING_DROP_IN))
+}
+
+fn systemctl_is_active(unit: &str) -> bool {
+    Command::new("systemctl")
+        .args(&["is-active", "--quiet", unit])
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+fn command_exists(name: &str) -> bool {
+    Command::new("which").arg(name).output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn check_firewall() -> Vec<Finding> {
+    let ufw_active = Command::new("ufw").arg("status").output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("Status: active"))
+        .unwrap_or(false);
+    let firewalld_active = systemctl_is_active("firewalld");
+    // A bare nftables service with an empty ruleset filters nothing, so look for an input hook
+    let nftables_active = Command::new("nft").args(&["list", "ruleset"]).output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("hook input"))
+        .unwrap_or(false);
+    
+    if ufw_active || firewalld_active || nftables_active {
+        return Vec::new();
+    }
+    
+    let auto_fixable = command_exists("ufw") || command_exists("firewall-cmd");
+    vec![Finding {
+        id: FINDING_NO_FIREWALL.to_string(),
+        title: "No active firewall".to_string(),
+        severity: Severity::High,
+        description: "Neither ufw, firewalld nor an nftables input chain is filtering incoming traffic.".to_string(),
+        remediation: if auto_fixable {
+            "Enable the installed firewall with a default-deny incoming policy.".to_string()
+        } else {
+            "Install ufw (pacman -S ufw) and run 'ufw enable'.".to_string()
+        },
+        auto_fixable,
+        affected_paths: Vec::new(),
+    }]
+}
+
+fn enable_firewall() -> Result<String, Box<dyn std::error::Error>> {
+    if command_exists("ufw") {
+        // Keep an SSH session that is administering this box alive
+        let mut steps: Vec<Vec<&str>> = vec![
+            vec!["default", "deny", "incoming"],
+            vec!["default", "allow", "outgoing"],
+        ];
+        if systemctl_is_active("sshd") {
+            steps.push(vec!["allow", "ssh"]);
+        }
+        steps.push(vec!["--force", "enable"]);
+        
+        for args in steps {
+            let output = Command::new("ufw").args(&args).output()?;
+            if !output.status.success() {
+                return Err(format!("ufw {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()).into());
+            }
+        }
+        Command::new("systemctl").args(&["enable", "ufw"]).output()?;
+        return Ok("ufw enabled with default-deny incoming".to_string());
+    }
+    
+    let output = Command::new("systemctl").args(&["enable", "--now", "firewalld"]).output()?;
+    if !output.status.success() {
+        return Err(format!("Failed to enable firewalld: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok("firewalld enabled".to_string())
+}
+
+fn check_apparmor() -> Vec<Finding> {
+    let enabled = fs::read_to_string("/sys/module/apparmor/parameters/enabled")
+        .map(|v| v.trim() == "Y")
+        .unwrap_or(false);
+    
+    if !enabled {
+        return vec![Finding {
+            id: FINDING_APPARMOR_DISABLED.to_string(),
+            title: "AppArmor is not enabled".to_string(),
+            severity: Severity::Medium,
+            description: "No mandatory access control confines services if they are compromised.".to_string(),
+            remediation: "Install apparmor, add 'lsm=landlock,lockdown,yama,integrity,apparmor,bpf' to the kernel \
+                command line, enable apparmor.service and reboot.".to_string(),
+            auto_fixable: false,
+            affected_paths: Vec::new(),
+        }];
+    }
+    
+    let output = match Command::new("aa-status").output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+        Err(e) => {
+            warn!("⚠️ AppArmor is enabled but aa-status failed: {}", e);
+            return Vec::new();
+        }
+    };
+    
+    // "N profiles are in enforce mode." / "N profiles are in complain mode."
+    let count = |mode: &str| -> u32 {
+        output.lines()
+            .find(|line| line.contains("profiles are in") && line.contains(mode))
+            .and_then(|line| line.split_whitespace().next())
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0)
+    };
+    let enforcing = count("enforce mode");
+    let complaining = count("complain mode");
+    
+    if enforcing == 0 || complaining > 0 {
+        return vec![Finding {
+            id: FINDING_APPARMOR_COMPLAIN.to_string(),
+            title: "AppArmor profiles not enforcing".to_string(),
+            severity: if enforcing == 0 { Severity::Medium } else { Severity::Low },
+            description: format!("{} profiles enforcing, {} only logging (complain mode).", enforcing, complaining),
+            remediation: "Review the complain-mode profiles with aa-logprof, then switch them with aa-enforce.".to_string(),
+            auto_fixable: false,
+            affected_paths: Vec::new(),
+        }];
+    }
+    
+    Vec::new()
+}
+
+/// World-writable directories in $PATH, and world-writable files inside them
+fn check_world_writable_path() -> Vec<Finding> {
+    let path_var = std::env::var("PATH").unwrap_or_default();
+    let mut seen = HashSet::new();
+    let mut writable = Vec::new();
+    
+    for dir in path_var.split(':').filter(|d| !d.is_empty()) {
+        let dir = PathBuf::from(dir);
+        if !seen.insert(dir.clone()) {
+            continue;
+        }
+        
+        if is_world_writable(&dir) {
+            writable.push(dir.clone());
+        }
+        
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if is_world_writable(&path) {
+                    writable.push(path);
+                }
+            }
+        }
+    }
+    
+    if writable.is_empty() {
+        return Vec::new();
+    }
+    
+    vec![Finding {
+        id: FINDING_WORLD_WRITABLE_PATH.to_string(),
+        title: "World-writable files in $PATH".to_string(),
+        severity: Severity::High,
+        description: format!(
+            "Any user can replace commands you run: {}",
+            writable.iter().take(5).map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+        ),
+        remediation: "Remove the world-write bit (chmod o-w) from these paths.".to_string(),
+        auto_fixable: true,
+        affected_paths: writable,
+    }]
+}
+
+/// Outbound connections to public hosts on ports outside monitoring.expected_remote_ports
+fn check_unexpected_connections() -> Vec<Finding> {
+    let expected_ports = config::get().monitoring.expected_remote_ports;
+    let unexpected: Vec<String> = read_connections(Path::new("/proc"), &expected_ports)
+        .into_iter()
+        .filter(|c| c.unexpected_port)
+        .map(|c| format!("{} → {}", c.process_name.unwrap_or_else(|| format!("uid {}", c.uid)), c.remote_addr))
+        .collect();
+    
+    if unexpected.is_empty() {
+        return Vec::new();
+    }
+    
+    vec![Finding {
+        id: FINDING_UNEXPECTED_CONNECTIONS.to_string(),
+        title: "Connections to unusual remote ports".to_string(),
+        severity: Severity::Medium,
+        description: format!(
+            "{} outbound connections use ports outside the expected list: {}",
+            unexpected.len(),
+            unexpected.iter().take(5).cloned().collect::<Vec<_>>().join(", ")
+        ),
+        remediation: "Check that these processes should be talking to these hosts, or add the ports to monitoring.expected_remote_ports.".to_string(),
+        auto_fixable: false,
+        affected_paths: Vec::new(),
+    }]
+}
+
+/// Symlinks are always 0777; only the target's mode matters
+fn is_world_writable(path: &Path) -> bool {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.file_type().is_symlink() => metadata.permissions().mode() & 0o002 != 0,
+        _ => false,
+    }
+}
@@ -0,0 +1,459 @@
+// Virtualization - VFIO GPU passthrough helpers and libvirt VM control for the Windows gaming VM
+// VFIO works directly on sysfs (binding needs root); libvirt is driven through virsh
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use tracing::{info, debug, warn, error};
+
+const IOMMU_GROUPS_DIR: &str = "/sys/kernel/iommu_groups";
+const PCI_DEVICES_DIR: &str = "/sys/bus/pci/devices";
+const VFIO_PCI_DRIVER_DIR: &str = "/sys/bus/pci/drivers/vfio-pci";
+
+pub const DEFAULT_LIBVIRT_URI: &str = "qemu:///system";
+
+/// Default hugepage size on x86_64 when the domain XML doesn't give one
+const DEFAULT_HUGEPAGE_KB: u64 = 2048;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VirtError {
+    #[error("libvirtd is not reachable at {0} - is the service running?")]
+    LibvirtUnavailable(String),
+    #[error("VM not found: {0}")]
+    VmNotFound(String),
+    #[error("virsh {0}")]
+    CommandFailed(String),
+    #[error("Failed to run virsh: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PciDevice {
+    pub address: String,
+    pub vendor_id: String,
+    pub device_id: String,
+    /// PCI class code, e.g. 0x030000 for a VGA controller
+    pub class: u32,
+    pub driver: Option<String>,
+}
+
+impl PciDevice {
+    /// VGA, XGA or 3D controller
+    pub fn is_display(&self) -> bool {
+        self.class >> 16 == 0x03
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IommuGroup {
+    pub id: u32,
+    pub devices: Vec<PciDevice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IommuStatus {
+    /// intel_iommu=on (or amd_iommu=on) is on the kernel command line
+    pub cmdline_enabled: bool,
+    /// The kernel actually created IOMMU groups
+    pub groups_present: bool,
+    pub message: String,
+}
+
+/// Hugepages backing a VM's memory; these stay reserved on the host while it exists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VmHugepages {
+    pub page_size_kb: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VmInfo {
+    pub name: String,
+    /// virsh state string: running, paused, shut off, ...
+    pub state: String,
+    pub vcpus: u32,
+    pub memory_mb: u64,
+    pub hugepages: Option<VmHugepages>,
+}
+
+impl VmInfo {
+    pub fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VmResourceUsage {
+    pub name: String,
+    /// Total guest CPU time since start
+    pub cpu_time_secs: f64,
+    pub vcpus: u32,
+    /// Memory currently given to the guest (balloon size)
+    pub memory_current_mb: u64,
+    /// Host memory the QEMU process actually holds
+    pub memory_rss_mb: u64,
+    pub hugepages: Option<VmHugepages>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualizationManager {
+    pub iommu_status: IommuStatus,
+    pub libvirt: LibvirtClient,
+}
+
+impl VirtualizationManager {
+    pub fn new() -> Self {
+        let iommu_status = Self::check_iommu();
+        if !iommu_status.cmdline_enabled || !iommu_status.groups_present {
+            warn!("⚠️ {}", iommu_status.message);
+        }
+        
+        Self {
+            iommu_status,
+            libvirt: LibvirtClient::new(),
+        }
+    }
+    
+    /// Report whether IOMMU is enabled on the kernel command line and active
+    pub fn check_iommu() -> IommuStatus {
+        let cmdline = fs::read_to_string("/proc/cmdline").unwrap_or_default();
+        let cmdline_enabled = cmdline.split_whitespace()
+            .any(|arg| arg == "intel_iommu=on" || arg == "amd_iommu=on");
+        let groups_present = fs::read_dir(IOMMU_GROUPS_DIR)
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false);
+        
+        let message = match (cmdline_enabled, groups_present) {
+            (true, true) => "IOMMU enabled".to_string(),
+            (false, true) => "IOMMU groups exist but intel_iommu=on is not on the kernel command line; \
+                add it to GRUB_CMDLINE_LINUX_DEFAULT so passthrough survives kernel defaults changing".to_string(),
+            (_, false) => "IOMMU is not active: add intel_iommu=on iommu=pt to the kernel command line and reboot".to_string(),
+        };
+        
+        IommuStatus {
+            cmdline_enabled,
+            groups_present,
+            message,
+        }
+    }
+    
+    /// Every IOMMU group with the PCI devices in it, sorted by group id
+    pub fn list_iommu_groups() -> Vec<IommuGroup> {
+        let mut groups = Vec::new();
+        
+        let entries = match fs::read_dir(IOMMU_GROUPS_DIR) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("No IOMMU groups: {}", e);
+                return groups;
+            }
+        };
+        
+        for entry in entries.flatten() {
+            let id = match entry.file_name().to_string_lossy().parse::<u32>() {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+            
+            let mut devices: Vec<PciDevice> = fs::read_dir(entry.path().join("devices"))
+                .map(|devices| {
+                    devices.flatten()
+                        .filter_map(|device| read_pci_device(&device.file_name().to_string_lossy()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            devices.sort_by(|a, b| a.address.cmp(&b.address));
+            
+            groups.push(IommuGroup { id, devices });
+        }
+        
+        groups.sort_by_key(|group| group.id);
+        groups
+    }
+    
+    /// Detach `pci_addr` from its driver and hand it to vfio-pci
+    pub fn bind_to_vfio(&self, pci_addr: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let device = read_pci_device(pci_addr).ok_or(format!("PCI device {} not found", pci_addr))?;
+        
+        let status = Self::check_iommu();
+        if !status.groups_present {
+            return Err(status.message.into());
+        }
+        if !status.cmdline_enabled {
+            warn!("⚠️ {}", status.message);
+        }
+        
+        if device.driver.as_deref() == Some("vfio-pci") {
+            return Ok(format!("{} is already bound to vfio-pci", pci_addr));
+        }
+        
+        if device.is_display() && !self.has_other_display_adapter(pci_addr) {
+            error!("🚨 {} is the ONLY display adapter; binding it to vfio-pci will leave the host without a display", pci_addr);
+        }
+        
+        // Passthrough needs the whole group; anything else left on a host driver will block the VM
+        if let Some(group) = Self::list_iommu_groups().into_iter()
+            .find(|group| group.devices.iter().any(|d| d.address == pci_addr))
+        {
+            for other in group.devices.iter().filter(|d| d.address != pci_addr) {
+                if other.driver.as_deref() != Some("vfio-pci") && !is_pci_bridge(other) {
+                    warn!("⚠️ {} shares IOMMU group {} with {} (driver {:?}); bind it too",
+                          other.address, group.id, pci_addr, other.driver);
+                }
+            }
+        }
+        
+        ensure_vfio_module()?;
+        
+        let device_dir = Path::new(PCI_DEVICES_DIR).join(pci_addr);
+        
+        // driver_override makes sure vfio-pci, not the old driver, claims it next
+        fs::write(device_dir.join("driver_override"), "vfio-pci")
+            .map_err(|e| format!("Failed to set driver_override for {}: {}", pci_addr, e))?;
+        
+        if let Some(driver) = &device.driver {
+            info!("🔌 Unbinding {} from {}", pci_addr, driver);
+            fs::write(device_dir.join("driver/unbind"), pci_addr)
+                .map_err(|e| format!("Failed to unbind {} from {}: {}", pci_addr, driver, e))?;
+        }
+        
+        fs::write(PathBuf::from(VFIO_PCI_DRIVER_DIR).join("bind"), pci_addr)
+            .map_err(|e| format!("Failed to bind {} to vfio-pci: {}", pci_addr, e))?;
+        
+        info!("✅ {} bound to vfio-pci", pci_addr);
+        Ok(format!(
+            "{} moved from {} to vfio-pci",
+            pci_addr,
+            device.driver.as_deref().unwrap_or("no driver")
+        ))
+    }
+    
+    /// Release `pci_addr` from vfio-pci and let the kernel reprobe its normal driver
+    pub fn unbind_from_vfio(&self, pci_addr: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let device = read_pci_device(pci_addr).ok_or(format!("PCI device {} not found", pci_addr))?;
+        let device_dir = Path::new(PCI_DEVICES_DIR).join(pci_addr);
+        
+        if device.driver.as_deref() == Some("vfio-pci") {
+            fs::write(PathBuf::from(VFIO_PCI_DRIVER_DIR).join("unbind"), pci_addr)
+                .map_err(|e| format!("Failed to unbind {} from vfio-pci: {}", pci_addr, e))?;
+        }
+        
+        // An empty override lets the normal driver match again
+        fs::write(device_dir.join("driver_override"), "\n")
+            .map_err(|e| format!("Failed to clear driver_override for {}: {}", pci_addr, e))?;
+        fs::write("/sys/bus/pci/drivers_probe", pci_addr)
+            .map_err(|e| format!("Failed to reprobe {}: {}", pci_addr, e))?;
+        
+        let driver = read_pci_device(pci_addr).and_then(|d| d.driver);
+        info!("✅ {} released from vfio-pci, now on {:?}", pci_addr, driver);
+        Ok(format!("{} returned to {}", pci_addr, driver.as_deref().unwrap_or("no driver")))
+    }
+    
+    fn has_other_display_adapter(&self, pci_addr: &str) -> bool {
+        fs::read_dir(PCI_DEVICES_DIR)
+            .map(|entries| {
+                entries.flatten()
+                    .filter_map(|entry| read_pci_device(&entry.file_name().to_string_lossy()))
+                    .any(|device| device.address != pci_addr && device.is_display()
+                        && device.driver.as_deref() != Some("vfio-pci"))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Thin wrapper over `virsh -c <uri>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibvirtClient {
+    pub uri: String,
+}
+
+impl LibvirtClient {
+    pub fn new() -> Self {
+        Self {
+            uri: DEFAULT_LIBVIRT_URI.to_string(),
+        }
+    }
+    
+    pub fn list_vms(&self) -> Result<Vec<VmInfo>, VirtError> {
+        let names = self.virsh(&["list", "--all", "--name"], None)?;
+        
+        let mut vms = Vec::new();
+        for name in names.lines().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            match self.vm_info(name) {
+                Ok(vm) => vms.push(vm),
+                // Domains can disappear between list and dominfo
+                Err(VirtError::VmNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        
+        Ok(vms)
+    }
+    
+    pub fn vm_info(&self, name: &str) -> Result<VmInfo, VirtError> {
+        let info = parse_key_values(&self.virsh(&["dominfo", name], Some(name))?, ':');
+        
+        // "Max memory:     16777216 KiB"
+        let memory_kb = info.get("Max memory")
+            .and_then(|m| m.split_whitespace().next())
+            .and_then(|m| m.parse::<u64>().ok())
+            .unwrap_or(0);
+        
+        Ok(VmInfo {
+            name: name.to_string(),
+            state: info.get("State").cloned().unwrap_or_else(|| "unknown".to_string()),
+            vcpus: info.get("CPU(s)").and_then(|c| c.parse().ok()).unwrap_or(0),
+            memory_mb: memory_kb / 1024,
+            hugepages: self.vm_hugepages(name, memory_kb)?,
+        })
+    }
+    
+    pub fn start_vm(&self, name: &str) -> Result<String, VirtError> {
+        self.virsh(&["start", name], Some(name))?;
+        info!("▶️ Started VM {}", name);
+        Ok(format!("VM {} started", name))
+    }
+    
+    /// ACPI shutdown request; the guest decides how long it takes
+    pub fn shutdown_vm(&self, name: &str) -> Result<String, VirtError> {
+        self.virsh(&["shutdown", name], Some(name))?;
+        info!("⏹️ Requested shutdown of VM {}", name);
+        Ok(format!("Shutdown requested for VM {}", name))
+    }
+    
+    /// Pausing stops the guest using CPU; its memory stays allocated
+    pub fn suspend_vm(&self, name: &str) -> Result<String, VirtError> {
+        self.virsh(&["suspend", name], Some(name))?;
+        info!("⏸️ Paused VM {}", name);
+        Ok(format!("VM {} paused", name))
+    }
+    
+    pub fn resume_vm(&self, name: &str) -> Result<String, VirtError> {
+        self.virsh(&["resume", name], Some(name))?;
+        info!("▶️ Resumed VM {}", name);
+        Ok(format!("VM {} resumed", name))
+    }
+    
+    pub fn vm_resource_usage(&self, name: &str) -> Result<VmResourceUsage, VirtError> {
+        let output = self.virsh(&["domstats", name, "--cpu-total", "--balloon", "--vcpu"], Some(name))?;
+        let stats = parse_key_values(&output, '=');
+        let stat = |key: &str| stats.get(key).and_then(|v| v.parse::<u64>().ok()).unwrap_or(0);
+        
+        let vcpus = stat("vcpu.current") as u32;
+        let memory_current_kb = stat("balloon.current");
+        
+        Ok(VmResourceUsage {
+            name: name.to_string(),
+            cpu_time_secs: stat("cpu.time") as f64 / 1_000_000_000.0,
+            vcpus,
+            memory_current_mb: memory_current_kb / 1024,
+            memory_rss_mb: stat("balloon.rss") / 1024,
+            hugepages: self.vm_hugepages(name, memory_current_kb)?,
+        })
+    }
+    
+    /// Hugepages from the domain's <memoryBacking>, sized to cover `memory_kb`
+    fn vm_hugepages(&self, name: &str, memory_kb: u64) -> Result<Option<VmHugepages>, VirtError> {
+        let xml = self.virsh(&["dumpxml", name], Some(name))?;
+        if !xml.contains("<hugepages") {
+            return Ok(None);
+        }
+        
+        // <page size='1' unit='G'/> inside <hugepages>; absent means the default size
+        let page_size_kb = xml.find("<page ")
+            .map(|start| &xml[start..xml[start..].find('>').map(|end| start + end).unwrap_or(xml.len())])
+            .and_then(|tag| {
+                let size = xml_attr(tag, "size")?.parse::<u64>().ok()?;
+                let multiplier = match xml_attr(tag, "unit").unwrap_or("KiB") {
+                    "G" | "GiB" => 1024 * 1024,
+                    "M" | "MiB" => 1024,
+                    _ => 1,
+                };
+                Some(size * multiplier)
+            })
+            .unwrap_or(DEFAULT_HUGEPAGE_KB);
+        
+        Ok(Some(VmHugepages {
+            page_size_kb,
+            count: (memory_kb + page_size_kb - 1) / page_size_kb,
+        }))
+    }
+    
+    fn virsh(&self, args: &[&str], vm_name: Option<&str>) -> Result<String, VirtError> {
+        let output = Command::new("virsh")
+            .arg("-c")
+            .arg(&self.uri)
+            .args(args)
+            .output()?;
+        
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+        }
+        
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let lowered = stderr.to_lowercase();
+        if lowered.contains("failed to connect") {
+            return Err(VirtError::LibvirtUnavailable(self.uri.clone()));
+        }
+        if let Some(name) = vm_name {
+            if lowered.contains("domain not found") || lowered.contains("failed to get domain") {
+                return Err(VirtError::VmNotFound(name.to_string()));
+            }
+        }
+        
+        Err(VirtError::CommandFailed(format!("{} failed: {}", args.join(" "), stderr)))
+    }
+}
+
+/// `key<sep>value` lines, trimmed; lines without the separator are skipped
+fn parse_key_values(output: &str, separator: char) -> HashMap<String, String> {
+    output.lines()
+        .filter_map(|line| line.split_once(separator))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn xml_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!("{}='", name)).map(|i| i + name.len() + 2)
+        .or_else(|| tag.find(&format!("{}=\"", name)).map(|i| i + name.len() + 2))?;
+    let end = tag[start..].find(|c| c == '\'' || c == '"')?;
+    Some(&tag[start..start + end])
+}
+
+fn read_pci_device(address: &str) -> Option<PciDevice> {
+    let dir = Path::new(PCI_DEVICES_DIR).join(address);
+    let read = |name: &str| fs::read_to_string(dir.join(name)).ok().map(|s| s.trim().to_string());
+    
+    let class = read("class")
+        .and_then(|c| u32::from_str_radix(c.trim_start_matches("0x"), 16).ok())?;
+    let driver = fs::read_link(dir.join("driver")).ok()
+        .and_then(|link| link.file_name().map(|name| name.to_string_lossy().to_string()));
+    
+    Some(PciDevice {
+        address: address.to_string(),
+        vendor_id: read("vendor").unwrap_or_default(),
+        device_id: read("device").unwrap_or_default(),
+        class,
+        driver,
+    })
+}
+
+/// PCIe root ports and bridges stay on the host even when their group is passed through
+fn is_pci_bridge(device: &PciDevice) -> bool {
+    device.class >> 16 == 0x06
+}
+
+fn ensure_vfio_module() -> Result<(), Box<dyn std::error::Error>> {
+    if Path::new(VFIO_PCI_DRIVER_DIR).exists() {
+        return Ok(());
+    }
+    
+    let output = Command::new("modprobe").arg("vfio-pci").output()?;
+    if !output.status.success() {
+        return Err(format!("Failed to load vfio-pci: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,117 @@
+// Thermal Throttle - CPU throttle event counters from sysfs
+// The counters only count up, so throttling is happening when they moved
+// between two polls; their absolute value says nothing about now.
+
+use std::fs;
+use std::path::Path;
+
+pub const CPU_DIR: &str = "/sys/devices/system/cpu";
+
+/// Total thermal throttle events under a sysfs cpu root (e.g. `/sys/devices/system/cpu`).
+/// The package counter is shared by every CPU, so it is counted once; core
+/// counters are summed. Returns `None` when no counters are exposed.
+pub fn read_throttle_count(cpu_root: &Path) -> Option<u64> {
+    let mut package_count: Option<u64> = None;
+    let mut core_count: Option<u64> = None;
+    
+    let entries = fs::read_dir(cpu_root).ok()?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let is_cpu = name.strip_prefix("cpu")
+            .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false);
+        if !is_cpu {
+            continue;
+        }
+        
+        let throttle_dir = entry.path().join("thermal_throttle");
+        let read_counter = |file: &str| {
+            fs::read_to_string(throttle_dir.join(file))
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+        };
+        
+        if let Some(count) = read_counter("package_throttle_count") {
+            package_count = Some(package_count.unwrap_or(0).max(count));
+        }
+        if let Some(count) = read_counter("core_throttle_count") {
+            core_count = Some(core_count.unwrap_or(0) + count);
+        }
+    }
+    
+    match (package_count, core_count) {
+        (None, None) => None,
+        (package, core) => Some(package.unwrap_or(0) + core.unwrap_or(0)),
+    }
+}
+
+/// Throttle events since the previous read, so a periodic collector sees whether
+/// the CPU throttled in between. The first read only primes it.
+#[derive(Debug, Default)]
+pub struct ThrottleMeter {
+    last: Option<u64>,
+}
+
+impl ThrottleMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// None on the first read and when the kernel exposes no counters
+    pub fn read_events(&mut self) -> Option<u64> {
+        let count = read_throttle_count(Path::new(CPU_DIR))?;
+        let events = self.last.map(|last| throttle_delta(Some(last), count));
+        self.last = Some(count);
+        events
+    }
+}
+
+/// Events since the `previous` total. The first reading only sets the baseline,
+/// and a counter that went backwards (CPU hotplug) counts as no new events.
+pub fn throttle_delta(previous: Option<u64>, count: u64) -> u64 {
+    match previous {
+        Some(previous) => count.saturating_sub(previous),
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn throttle_count_tracks_mock_sysfs() {
+        let root = std::env::temp_dir().join(format!("thermal_throttle_{}", std::process::id()));
+        let write_counters = |package: u64, cores: [u64; 2]| {
+            for (cpu, core) in cores.iter().enumerate() {
+                let dir = root.join(format!("cpu{}", cpu)).join("thermal_throttle");
+                fs::create_dir_all(&dir).unwrap();
+                fs::write(dir.join("package_throttle_count"), format!("{}\n", package)).unwrap();
+                fs::write(dir.join("core_throttle_count"), format!("{}\n", core)).unwrap();
+            }
+        };
+        fs::create_dir_all(root.join("cpufreq")).unwrap();
+        
+        // Package counted once, cores summed: 10 + 3 + 4
+        write_counters(10, [3, 4]);
+        let first = read_throttle_count(&root);
+        let baseline = throttle_delta(None, first.unwrap());
+        
+        let idle = throttle_delta(first, read_throttle_count(&root).unwrap());
+        
+        write_counters(12, [5, 4]);
+        let second = read_throttle_count(&root);
+        let throttled = throttle_delta(first, second.unwrap());
+        
+        let missing = read_throttle_count(&root.join("absent"));
+        fs::remove_dir_all(&root).unwrap();
+        
+        assert_eq!(first, Some(17));
+        assert_eq!(baseline, 0);
+        assert_eq!(idle, 0);
+        assert_eq!(second, Some(21));
+        assert_eq!(throttled, 4);
+        assert_eq!(throttle_delta(second, 3), 0);
+        assert_eq!(missing, None);
+    }
+}
@@ -0,0 +1,498 @@
+// Safety Watchdog - Last line of defence when fan control fails and temperatures run away
+// Runs on its own thread and reads sysfs and nvidia-smi directly, so it keeps protecting
+// the hardware when the monitor, the AI loop or the async runtime are stuck
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use tracing::{debug, error, info, warn};
+
+use crate::config::{self, SafetyConfig};
+use crate::privilege::PrivilegedBatch;
+use crate::HardwareController;
+
+/// hwmon drivers that report the CPU package or die temperature
+const CPU_HWMON_DRIVERS: &[&str] = &["coretemp", "k10temp", "zenpower", "cpu_thermal"];
+
+/// Hardware limits assumed when the sensors don't report one: AMD's 95°C TjMax,
+/// the lower of the common CPU limits, and a typical GPU slowdown temperature.
+/// NVIDIA GPUs never report theirs through sysfs.
+const FALLBACK_CPU_LIMIT: f64 = 95.0;
+const FALLBACK_GPU_LIMIT: f64 = 93.0;
+
+const PCI_DEVICES_DIR: &str = "/sys/bus/pci/devices";
+const NVIDIA_PCI_VENDOR: &str = "0x10de";
+
+/// How long a throttle or restore waits on the polkit prompt before giving up on it
+const ELEVATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Set while the watchdog's limits are in place, so other controllers hold off
+static THROTTLING: AtomicBool = AtomicBool::new(false);
+
+/// Whether the watchdog is currently holding the CPU and GPU down
+pub fn is_throttling() -> bool {
+    THROTTLING.load(Ordering::SeqCst)
+}
+
+pub struct SafetyWatchdog {
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+/// What the watchdog changed while throttling, so recovery can put it back
+struct Throttle {
+    governors: Vec<(PathBuf, String)>,
+    max_freqs: Vec<(PathBuf, String)>,
+    /// (nvidia-smi index, previous limit in W)
+    nvidia_power_limits: Vec<(String, String)>,
+    /// (amdgpu power1_cap path, previous cap in µW)
+    amdgpu_power_caps: Vec<(PathBuf, String)>,
+}
+
+/// The temperatures the watchdog acts at, after filling unset ones from the hardware
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Limits {
+    cpu_critical: f64,
+    cpu_emergency: f64,
+    gpu_critical: f64,
+    gpu_emergency: f64,
+}
+
+impl Limits {
+    fn resolve(settings: &SafetyConfig, cpu_limit: Option<f64>, gpu_limit: Option<f64>) -> Self {
+        let cpu_limit = cpu_limit.unwrap_or(FALLBACK_CPU_LIMIT);
+        let gpu_limit = gpu_limit.unwrap_or(FALLBACK_GPU_LIMIT);
+        Self {
+            cpu_critical: settings.cpu_critical.unwrap_or(cpu_limit - settings.critical_margin),
+            cpu_emergency: settings.cpu_emergency.unwrap_or(cpu_limit - settings.emergency_margin),
+            gpu_critical: settings.gpu_critical.unwrap_or(gpu_limit - settings.critical_margin),
+            gpu_emergency: settings.gpu_emergency.unwrap_or(gpu_limit - settings.emergency_margin),
+        }
+    }
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    critical_reads: u32,
+    emergency_reads: u32,
+    recovered_reads: u32,
+    throttle: Option<Throttle>,
+    emergency_triggered: bool,
+    sensors_missing_logged: bool,
+    /// (CPU, GPU) limits the sensors report, read on the first poll
+    hardware_limits: Option<(Option<f64>, Option<f64>)>,
+    /// Throttle or restore still being applied, possibly waiting on a polkit prompt
+    elevation: Option<thread::JoinHandle<()>>,
+}
+
+impl SafetyWatchdog {
+    /// Start polling on a dedicated thread. Limits are re-read from the config
+    /// every poll, so changes apply without a restart.
+    pub fn start() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        
+        let handle = thread::Builder::new()
+            .name("safety-watchdog".to_string())
+            .spawn(move || {
+                info!("🛡️ Safety watchdog started");
+                let mut state = WatchdogState::default();
+                while !stop_flag.load(Ordering::SeqCst) {
+                    let settings = config::get().safety;
+                    if settings.enabled {
+                        state.poll(&settings);
+                    }
+                    thread::park_timeout(settings.poll_interval());
+                }
+                // Still hot machines stay throttled; the limits are only lifted on recovery
+                if state.throttle.is_some() {
+                    warn!("🛡️ Safety watchdog stopped while throttling; limits stay in place");
+                }
+            });
+        
+        let handle = match handle {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                error!("🚨 Failed to start the safety watchdog, temperatures are NOT protected: {}", e);
+                None
+            }
+        };
+        
+        Self { stop, handle }
+    }
+    
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+    
+    fn shutdown(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.stop.store(true, Ordering::SeqCst);
+            handle.thread().unpark();
+            if handle.join().is_err() {
+                error!("Safety watchdog thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for SafetyWatchdog {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl WatchdogState {
+    fn poll(&mut self, settings: &SafetyConfig) {
+        let (cpu_limit, gpu_limit) = *self.hardware_limits.get_or_insert_with(|| {
+            let limits = (read_cpu_limit(), read_gpu_limit());
+            info!("🛡️ Hardware temperature limits: CPU {:?}°C, GPU {:?}°C", limits.0, limits.1);
+            limits
+        });
+        let limits = Limits::resolve(settings, cpu_limit, gpu_limit);
+        
+        let cpu = read_cpu_temperature();
+        let gpu = read_gpu_temperature();
+        if cpu.is_none() && gpu.is_none() {
+            if !self.sensors_missing_logged {
+                warn!("🛡️ Safety watchdog found no CPU or GPU temperature sensor");
+                self.sensors_missing_logged = true;
+            }
+            return;
+        }
+        
+        let above = |temp: Option<f64>, limit: f64| temp.map(|t| t >= limit).unwrap_or(false);
+        let emergency = above(cpu, limits.cpu_emergency) || above(gpu, limits.gpu_emergency);
+        let critical = emergency || above(cpu, limits.cpu_critical) || above(gpu, limits.gpu_critical);
+        let recovered = !above(cpu, limits.cpu_critical - settings.recovery_margin)
+            && !above(gpu, limits.gpu_critical - settings.recovery_margin);
+        
+        self.critical_reads = if critical { self.critical_reads + 1 } else { 0 };
+        self.emergency_reads = if emergency { self.emergency_reads + 1 } else { 0 };
+        self.recovered_reads = if recovered { self.recovered_reads + 1 } else { 0 };
+        debug!("🛡️ Watchdog: CPU {:?}°C, GPU {:?}°C", cpu, gpu);
+        
+        let required = settings.consecutive_reads.max(1);
+        if self.critical_reads >= required && self.throttle.is_none() && !self.elevation_pending() {
+            error!(
+                "🚨 SAFETY WATCHDOG: critical temperature for {} reads (CPU {:?}°C, GPU {:?}°C), throttling CPU and GPU",
+                self.critical_reads, cpu, gpu
+            );
+            let (throttle, batch, descriptions) = throttle(settings.frequency_cap_percent);
+            // Kept even if parts failed, so a refused prompt isn't repeated every poll
+            self.throttle = Some(throttle);
+            self.elevation = apply_in_background(batch, descriptions, "throttle");
+            THROTTLING.store(true, Ordering::SeqCst);
+        }
+        
+        if self.emergency_reads >= required && !self.emergency_triggered {
+            self.emergency_triggered = true;
+            error!(
+                "🚨 SAFETY WATCHDOG: emergency temperature for {} reads (CPU {:?}°C, GPU {:?}°C)",
+                self.emergency_reads, cpu, gpu
+            );
+            emergency_action(&settings.emergency_action);
+        }
+        
+        // Restoring waits for the throttle to finish, so it can't be overtaken by it
+        if self.recovered_reads >= required {
+            self.emergency_triggered = false;
+            if self.throttle.is_some() && !self.elevation_pending() {
+                warn!("🛡️ SAFETY WATCHDOG: temperatures back to normal (CPU {:?}°C, GPU {:?}°C), lifting limits", cpu, gpu);
+                if let Some(throttle) = self.throttle.take() {
+                    let (batch, descriptions) = restore(throttle);
+                    self.elevation = apply_in_background(batch, descriptions, "restore");
+                }
+                THROTTLING.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+    
+    fn elevation_pending(&mut self) -> bool {
+        match &self.elevation {
+            Some(handle) if !handle.is_finished() => true,
+            _ => {
+                self.elevation = None;
+                false
+            }
+        }
+    }
+}
+
+/// Powersave governor, capped scaling_max_freq and minimum GPU power limits,
+/// all in one privileged batch, with the settings it replaces
+fn throttle(frequency_cap_percent: u8) -> (Throttle, PrivilegedBatch, Vec<String>) {
+    let mut throttle = Throttle {
+        governors: Vec::new(),
+        max_freqs: Vec::new(),
+        nvidia_power_limits: Vec::new(),
+        amdgpu_power_caps: Vec::new(),
+    };
+    let mut batch = PrivilegedBatch::new();
+    let mut descriptions = Vec::new();
+    
+    let powersave_available = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors")
+        .map(|available| available.split_whitespace().any(|g| g == "powersave"))
+        .unwrap_or(false);
+    if powersave_available {
+        for (cpu_id, path) in HardwareController::cpu_governor_paths() {
+            if let Some(previous) = read_trimmed(&path) {
+                batch.write(&path, "powersave");
+                descriptions.push(format!("CPU{} governor -> powersave", cpu_id));
+                throttle.governors.push((path, previous));
+            }
+        }
+    }
+    
+    let cap_percent = u64::from(frequency_cap_percent.clamp(10, 100));
+    for cpufreq in HardwareController::cpu_governor_paths().iter().filter_map(|(_, path)| path.parent()) {
+        let hardware_max = read_u64(&cpufreq.join("cpuinfo_max_freq"));
+        let hardware_min = read_u64(&cpufreq.join("cpuinfo_min_freq")).unwrap_or(0);
+        let max_freq_path = cpufreq.join("scaling_max_freq");
+        if let (Some(hardware_max), Some(previous)) = (hardware_max, read_trimmed(&max_freq_path)) {
+            let cap = (hardware_max * cap_percent / 100).max(hardware_min);
+            batch.write(&max_freq_path, cap.to_string());
+            descriptions.push(format!("{} -> {} kHz", max_freq_path.display(), cap));
+            throttle.max_freqs.push((max_freq_path, previous));
+        }
+    }
+    
+    for (index, limit, min_limit) in nvidia_power_limits() {
+        batch.run("nvidia-smi", &["-i", &index, "-pl", &min_limit]);
+        descriptions.push(format!("GPU{} power limit -> {} W", index, min_limit));
+        throttle.nvidia_power_limits.push((index, limit));
+    }
+    
+    for hwmon in hwmon_dirs_named(&["amdgpu"]) {
+        let cap_path = hwmon.join("power1_cap");
+        if let (Some(previous), Some(min_cap)) = (read_trimmed(&cap_path), read_trimmed(&hwmon.join("power1_cap_min"))) {
+            batch.write(&cap_path, min_cap.clone());
+            descriptions.push(format!("{} -> {} µW", cap_path.display(), min_cap));
+            throttle.amdgpu_power_caps.push((cap_path, previous));
+        }
+    }
+    
+    (throttle, batch, descriptions)
+}
+
+/// The batch that puts back everything `throttle` changed
+fn restore(throttle: Throttle) -> (PrivilegedBatch, Vec<String>) {
+    let mut batch = PrivilegedBatch::new();
+    let mut descriptions = Vec::new();
+    
+    for (path, previous) in throttle.max_freqs.into_iter().chain(throttle.governors).chain(throttle.amdgpu_power_caps) {
+        descriptions.push(format!("{} -> {}", path.display(), previous));
+        batch.write(path, previous);
+    }
+    for (index, limit) in throttle.nvidia_power_limits {
+        batch.run("nvidia-smi", &["-i", &index, "-pl", &limit]);
+        descriptions.push(format!("GPU{} power limit -> {} W", index, limit));
+    }
+    
+    (batch, descriptions)
+}
+
+/// Apply `batch` on its own thread, so a polkit prompt left open doesn't stop the
+/// watchdog from polling and reaching the emergency action
+fn apply_in_background(batch: PrivilegedBatch, descriptions: Vec<String>, stage: &'static str) -> Option<thread::JoinHandle<()>> {
+    if batch.is_empty() {
+        error!("🚨 SAFETY WATCHDOG: nothing to {} on this machine", stage);
+        return None;
+    }
+    
+    let spawned = thread::Builder::new()
+        .name(format!("watchdog-{}", stage))
+        .spawn(move || apply_logged(batch, &descriptions, stage));
+    match spawned {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            error!("🚨 SAFETY WATCHDOG: could not start {}: {}", stage, e);
+            None
+        }
+    }
+}
+
+fn apply_logged(batch: PrivilegedBatch, descriptions: &[String], stage: &str) {
+    for (description, result) in descriptions.iter().zip(batch.apply_within(ELEVATION_TIMEOUT)) {
+        match result {
+            Ok(_) => warn!("🛡️ SAFETY WATCHDOG {}: {}", stage, description),
+            Err(e) => error!("🚨 SAFETY WATCHDOG {} failed: {}: {}", stage, description, e),
+        }
+    }
+}
+
+/// Suspend or power off through logind, which allows it for the active session
+fn emergency_action(action: &str) {
+    let verb = match action {
+        "suspend" => "suspend",
+        "shutdown" => "poweroff",
+        "none" => {
+            error!("🚨 SAFETY WATCHDOG: emergency action disabled, relying on firmware thermal protection");
+            return;
+        }
+        other => {
+            error!("🚨 SAFETY WATCHDOG: unknown emergency_action '{}', suspending instead", other);
+            "suspend"
+        }
+    };
+    
+    error!("🚨 SAFETY WATCHDOG: running systemctl {} to protect the hardware", verb);
+    match Command::new("systemctl").arg(verb).output() {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => error!(
+            "🚨 SAFETY WATCHDOG: systemctl {} failed: {}",
+            verb, String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => error!("🚨 SAFETY WATCHDOG: could not run systemctl {}: {}", verb, e),
+    }
+}
+
+/// Hottest CPU sensor from the CPU hwmon drivers, else the x86_pkg_temp thermal zone
+pub(crate) fn read_cpu_temperature() -> Option<f64> {
+    let hwmon_max = hwmon_dirs_named(CPU_HWMON_DRIVERS).iter()
+        .filter_map(|dir| max_temp_input(dir))
+        .fold(None, max_temperature);
+    if hwmon_max.is_some() {
+        return hwmon_max;
+    }
+    
+    fs::read_dir("/sys/class/thermal").ok()?
+        .flatten()
+        .filter(|entry| read_trimmed(&entry.path().join("type")).as_deref() == Some("x86_pkg_temp"))
+        .filter_map(|entry| read_u64(&entry.path().join("temp")))
+        .map(|millis| millis as f64 / 1000.0)
+        .fold(None, max_temperature)
+}
+
+/// Lowest limit among the CPU sensors, so the hottest-running one is protected
+fn read_cpu_limit() -> Option<f64> {
+    hwmon_dirs_named(CPU_HWMON_DRIVERS).iter()
+        .filter_map(|dir| temp_limit(dir))
+        .fold(None, |min: Option<f64>, limit| Some(min.map_or(limit, |min| min.min(limit))))
+}
+
+fn read_gpu_limit() -> Option<f64> {
+    hwmon_dirs_named(&["amdgpu"]).iter()
+        .filter_map(|dir| temp_limit(dir))
+        .fold(None, |min: Option<f64>, limit| Some(min.map_or(limit, |min| min.min(limit))))
+}
+
+/// Lowest tempN_crit in a hwmon directory, or tempN_max where a sensor has no crit, in °C
+fn temp_limit(dir: &Path) -> Option<f64> {
+    let mut limits = Vec::new();
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let sensor = match name.strip_suffix("_input") {
+            Some(sensor) if sensor.starts_with("temp") => sensor.to_string(),
+            _ => continue,
+        };
+        let limit = read_u64(&dir.join(format!("{}_crit", sensor)))
+            .or_else(|| read_u64(&dir.join(format!("{}_max", sensor))))
+            // Some drivers report 0 or absurd values for sensors without a limit
+            .filter(|millis| (50_000..=150_000).contains(millis));
+        if let Some(millis) = limit {
+            limits.push(millis as f64 / 1000.0);
+        }
+    }
+    limits.into_iter().reduce(f64::min)
+}
+
+/// Whether any NVIDIA GPU is powered up. nvidia-smi wakes a runtime-suspended dGPU
+/// and keeps it awake if polled every few seconds; a suspended GPU is cool anyway.
+fn nvidia_gpu_awake() -> bool {
+    let devices = match fs::read_dir(PCI_DEVICES_DIR) {
+        Ok(devices) => devices,
+        Err(_) => return false,
+    };
+    devices.flatten()
+        .map(|entry| entry.path())
+        // Display controllers (class 0x03xxxx) from NVIDIA
+        .filter(|device| read_trimmed(&device.join("vendor")).as_deref() == Some(NVIDIA_PCI_VENDOR)
+            && read_trimmed(&device.join("class")).map(|class| class.starts_with("0x03")).unwrap_or(false))
+        .any(|device| read_trimmed(&device.join("power/runtime_status")).as_deref() != Some("suspended"))
+}
+
+/// Hottest GPU, from nvidia-smi and the amdgpu hwmon
+pub(crate) fn read_gpu_temperature() -> Option<f64> {
+    let nvidia = if nvidia_gpu_awake() { read_nvidia_temperatures() } else { Vec::new() };
+    let amdgpu = hwmon_dirs_named(&["amdgpu"]).iter().filter_map(|dir| max_temp_input(dir)).collect::<Vec<_>>();
+    
+    nvidia.into_iter().chain(amdgpu).fold(None, max_temperature)
+}
+
+fn read_nvidia_temperatures() -> Vec<f64> {
+    Command::new("nvidia-smi")
+        .args(["--query-gpu=temperature.gpu", "--format=csv,noheader,nounits"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout).lines()
+                .filter_map(|line| line.trim().parse::<f64>().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// (index, current power limit, minimum power limit) in W for each NVIDIA GPU
+/// that supports power management
+pub(crate) fn nvidia_power_limits() -> Vec<(String, String, String)> {
+    let output = match Command::new("nvidia-smi")
+        .args(["--query-gpu=index,power.limit,power.min_limit", "--format=csv,noheader,nounits"])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+    
+    String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            match fields.as_slice() {
+                [index, limit, min_limit] if limit.parse::<f64>().is_ok() && min_limit.parse::<f64>().is_ok() => {
+                    Some((index.to_string(), limit.to_string(), min_limit.to_string()))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn hwmon_dirs_named(drivers: &[&str]) -> Vec<PathBuf> {
+    fs::read_dir("/sys/class/hwmon")
+        .map(|entries| entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|dir| read_trimmed(&dir.join("name")).map(|name| drivers.contains(&name.as_str())).unwrap_or(false))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Highest tempN_input in a hwmon directory, in °C
+fn max_temp_input(dir: &Path) -> Option<f64> {
+    fs::read_dir(dir).ok()?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("temp") && name.ends_with("_input")
+        })
+        .filter_map(|entry| read_u64(&entry.path()))
+        .map(|millis| millis as f64 / 1000.0)
+        .fold(None, max_temperature)
+}
+
+fn max_temperature(max: Option<f64>, temp: f64) -> Option<f64> {
+    Some(max.map_or(temp, |max| max.max(temp)))
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_trimmed(path).and_then(|value| value.parse().ok())
+}